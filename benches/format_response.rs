@@ -0,0 +1,77 @@
+// Markdown rendering of docs responses, the last step of every docs tool call,
+// and the near-duplicate filter that runs before it
+//
+// Usage: cargo bench --bench format_response --no-default-features --features docs
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use devkit::docs_client::{DocsRequest, DocsResponse, DocumentSnippet, PaginationInfo};
+use devkit::mcp::tools::docs::rank;
+use devkit::mcp::tools::DocsTool;
+use std::hint::black_box;
+
+const WORDS: &[&str] = &[
+    "route", "handler", "request", "response", "segment", "layout", "page", "cache", "server", "client",
+    "component", "fetch", "stream", "header", "cookie", "redirect", "params", "query", "static", "dynamic",
+    "render", "export", "async", "await", "middleware", "config", "build", "deploy", "edge", "runtime",
+    "error", "boundary", "loading", "metadata", "image", "font", "script", "revalidate", "action", "form",
+];
+
+/// About 1.5 KB of prose, different for every `seed` so the deduplication keeps each snippet
+fn body(seed: usize) -> String {
+    let mut state = (seed as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15).wrapping_add(1);
+    let mut body = String::with_capacity(1600);
+    while body.len() < 1500 {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        body.push_str(WORDS[(state % WORDS.len() as u64) as usize]);
+        body.push(if state.is_multiple_of(12) { '\n' } else { ' ' });
+    }
+    body
+}
+
+/// `count` distinct titled snippets, like a merged multi-page response
+fn response(count: usize) -> DocsResponse {
+    DocsResponse {
+        snippets: (0..count)
+            .map(|i| DocumentSnippet {
+                content: format!("```ts\n// example {}\n```\n{}", i, body(i)),
+                title: Some(format!("Route Handlers {}", i)),
+                score: None,
+            })
+            .collect(),
+        pagination: Some(PaginationInfo { current_page: 1, total_pages: 10, has_next: true }),
+        ..Default::default()
+    }
+}
+
+fn format_response(c: &mut Criterion) {
+    let request = DocsRequest { topic: Some("routing".to_string()), ..DocsRequest::new("vercel/next.js") };
+    let mut group = c.benchmark_group("format_response");
+    for count in [10, 100, 1000] {
+        let response = response(count);
+        assert_eq!(rank::dedup(response.snippets.iter().collect()).1, 0, "bench snippets must survive deduplication");
+        let bytes: usize = response.snippets.iter().map(|s| s.content.len()).sum();
+        group.throughput(Throughput::Bytes(bytes as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &response, |b, response| {
+            b.iter(|| DocsTool::format_response(black_box(response), black_box(&request)))
+        });
+    }
+    group.finish();
+}
+
+fn dedup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rank::dedup");
+    for count in [10, 100, 1000] {
+        let response = response(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &response, |b, response| {
+            b.iter(|| rank::dedup(black_box(response.snippets.iter().collect())))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, format_response, dedup);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+// Query the docs API through the library client, without the MCP server
+//
+// Usage: cargo run --example docs_client --no-default-features --features client -- vercel/next.js [topic]
+
+use devkit::{DocsClient, DocsConfig, DocsRequest};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let library = args.next().unwrap_or_else(|| "vercel/next.js".to_string());
+
+    let mut request = DocsRequest::new(library);
+    request.topic = args.next();
+
+    let client = DocsClient::new(DocsConfig::default())?;
+    let response = client.docs(&request).await?;
+    for snippet in &response.snippets {
+        if let Some(title) = &snippet.title {
+            println!("## {}\n", title);
+        }
+        println!("{}\n", snippet.content);
+    }
+    Ok(())
+}
@@ -0,0 +1,11 @@
+// Print the MCP tools compiled into this build
+//
+// Usage: cargo run --example tool_list --no-default-features --features server
+
+use devkit::mcp::DevkitServer;
+
+fn main() {
+    for tool in DevkitServer::new().tool_definitions() {
+        println!("{}", tool.name);
+    }
+}
@@ -1,5 +1,6 @@
 // 类型定义
 export type {
+  DiffView,
   IPopupManager,
   McpRequest,
   PopupConfig,
@@ -9,5 +10,6 @@ export type {
 export { default as McpPopup } from './McpPopup.vue'
 export { default as PopupActions } from './PopupActions.vue'
 export { default as PopupContent } from './PopupContent.vue'
+export { default as PopupDiff } from './PopupDiff.vue'
 export { default as PopupHeader } from './PopupHeader.vue'
 export { default as PopupInput } from './PopupInput.vue'
@@ -0,0 +1,118 @@
+import type { ShortcutBinding, ShortcutKey } from '../types/popup'
+
+/**
+ * 弹窗键盘操作映射
+ *
+ * 规则与 src/rust/mcp/tools/interaction/keys.rs 中的 map_key 一致，测试也在那里
+ */
+export type PopupAction
+  = | { type: 'submit' }
+    | { type: 'enhance' }
+    | { type: 'continue' }
+    | { type: 'toggle_option', index: number }
+    | { type: 'focus_next' }
+    | { type: 'focus_previous' }
+    | { type: 'request_cancel' }
+    | { type: 'confirm_cancel' }
+    | { type: 'dismiss_cancel' }
+    | { type: 'toggle_help' }
+
+export interface PopupKeyContext {
+  optionCount: number
+  // 焦点在文本框内，此时数字和 ? 作为普通输入
+  editingText: boolean
+  confirmingCancel: boolean
+  helpOpen: boolean
+  isMac: boolean
+}
+
+export function eventToShortcutKey(event: KeyboardEvent): ShortcutKey {
+  return {
+    key: event.key,
+    ctrl: event.ctrlKey,
+    alt: event.altKey,
+    shift: event.shiftKey,
+    meta: event.metaKey,
+  }
+}
+
+// 按键是否匹配绑定；macOS 上 Ctrl 绑定同时接受 Cmd
+export function matchesBinding(binding: ShortcutKey, event: ShortcutKey, isMac: boolean): boolean {
+  if (binding.key.toLowerCase() !== event.key.toLowerCase() || binding.alt !== event.alt || binding.shift !== event.shift)
+    return false
+  const exact = binding.ctrl === event.ctrl && binding.meta === event.meta
+  const cmdForCtrl = isMac && binding.ctrl && !binding.meta && event.meta && !event.ctrl
+  return exact || cmdForCtrl
+}
+
+export function mapPopupKey(
+  event: ShortcutKey,
+  ctx: PopupKeyContext,
+  shortcuts: Record<string, ShortcutBinding>,
+): PopupAction | null {
+  const plain = !event.ctrl && !event.alt && !event.meta
+
+  // 确认取消时只接受确认或返回
+  if (ctx.confirmingCancel) {
+    if (plain && ['Enter', 'y', 'Y'].includes(event.key))
+      return { type: 'confirm_cancel' }
+    if (plain && ['Escape', 'n', 'N'].includes(event.key))
+      return { type: 'dismiss_cancel' }
+    return null
+  }
+
+  if (ctx.helpOpen) {
+    if (plain && (event.key === 'Escape' || event.key === '?'))
+      return { type: 'toggle_help' }
+    return null
+  }
+
+  const binding = Object.values(shortcuts).find(b =>
+    b.enabled && b.scope === 'popup' && matchesBinding(b.key_combination, event, ctx.isMac),
+  )
+  switch (binding?.action) {
+    case 'submit': return { type: 'submit' }
+    case 'enhance': return { type: 'enhance' }
+    case 'continue': return { type: 'continue' }
+  }
+
+  if (!plain)
+    return null
+  if (event.key === 'Escape')
+    return { type: 'request_cancel' }
+  if (event.key === 'Tab')
+    return { type: event.shift ? 'focus_previous' : 'focus_next' }
+  if (ctx.editingText)
+    return null
+  // ? 通常带 Shift，不检查 Shift
+  if (event.key === '?')
+    return { type: 'toggle_help' }
+  if (!event.shift && /^[1-9]$/.test(event.key)) {
+    const digit = Number(event.key)
+    if (digit <= ctx.optionCount)
+      return { type: 'toggle_option', index: digit - 1 }
+  }
+  return null
+}
+
+// 按钮上的快捷键提示；macOS 上 Ctrl 绑定以 Cmd 显示
+export function shortcutHint(key: ShortcutKey, isMac: boolean): string {
+  const name = key.key === 'Enter' ? '↵' : key.key.length === 1 ? key.key.toUpperCase() : key.key
+  if (isMac) {
+    const ctrlAsCmd = key.ctrl && !key.meta
+    return [
+      key.meta || ctrlAsCmd ? '⌘' : '',
+      key.ctrl && !ctrlAsCmd ? '⌃' : '',
+      key.alt ? '⌥' : '',
+      key.shift ? '⇧' : '',
+      name,
+    ].join('')
+  }
+  return [
+    key.ctrl ? 'Ctrl' : '',
+    key.alt ? 'Alt' : '',
+    key.shift ? 'Shift' : '',
+    key.meta ? 'Meta' : '',
+    name,
+  ].filter(Boolean).join('+')
+}
@@ -1,7 +1,6 @@
 import type { ShortcutBinding, ShortcutConfig, ShortcutKey } from '../types/popup'
 import { invoke } from '@tauri-apps/api/core'
-import { useMagicKeys } from '@vueuse/core'
-import { computed, ref, watch } from 'vue'
+import { computed, ref } from 'vue'
 
 /**
  * 自定义快捷键管理
@@ -11,8 +10,6 @@ export function useShortcuts() {
     shortcuts: {},
   })
 
-  const keys = useMagicKeys()
-
   // 检测操作系统
   const isMac = computed(() => {
     if (typeof navigator !== 'undefined') {
@@ -159,81 +156,6 @@ export function useShortcuts() {
     return `${shortcutKeyToString(binding.key_combination)} ${binding.name}`
   })
 
-  // 监听快速发送快捷键
-  function useQuickSubmitShortcut(callback: () => void) {
-    const binding = computed(() => getShortcutByAction('submit'))
-
-    watch(
-      () => binding.value,
-      (newBinding) => {
-        if (!newBinding)
-          return
-
-        const magicKey = shortcutKeyToMagicKey(newBinding.key_combination)
-        const keyRef = keys[magicKey]
-
-        if (keyRef) {
-          watch(keyRef, (pressed) => {
-            if (pressed) {
-              callback()
-            }
-          })
-        }
-      },
-      { immediate: true },
-    )
-  }
-
-  // 监听增强快捷键
-  function useEnhanceShortcut(callback: () => void) {
-    const binding = computed(() => getShortcutByAction('enhance'))
-
-    watch(
-      () => binding.value,
-      (newBinding) => {
-        if (!newBinding)
-          return
-
-        const magicKey = shortcutKeyToMagicKey(newBinding.key_combination)
-        const keyRef = keys[magicKey]
-
-        if (keyRef) {
-          watch(keyRef, (pressed) => {
-            if (pressed) {
-              callback()
-            }
-          })
-        }
-      },
-      { immediate: true },
-    )
-  }
-
-  // 监听继续快捷键
-  function useContinueShortcut(callback: () => void) {
-    const binding = computed(() => getShortcutByAction('continue'))
-
-    watch(
-      () => binding.value,
-      (newBinding) => {
-        if (!newBinding)
-          return
-
-        const magicKey = shortcutKeyToMagicKey(newBinding.key_combination)
-        const keyRef = keys[magicKey]
-
-        if (keyRef) {
-          watch(keyRef, (pressed) => {
-            if (pressed) {
-              callback()
-            }
-          })
-        }
-      },
-      { immediate: true },
-    )
-  }
-
   return {
     shortcutConfig,
     isMac,
@@ -247,8 +169,5 @@ export function useShortcuts() {
     quickSubmitShortcutText,
     enhanceShortcutText,
     continueShortcutText,
-    useQuickSubmitShortcut,
-    useEnhanceShortcut,
-    useContinueShortcut,
   }
 }
@@ -196,6 +196,7 @@ export const MCP_TOOLS_CONFIG: ToolPromptConfig[] = [
       howToUse: [
         'Library format: owner/repo (e.g. vercel/next.js)',
         'Short names work too, tool will search',
+        'Several topics of one library at once: topics (e.g. routing, auth, config)',
       ],
     },
     ui: {
@@ -206,6 +207,122 @@ export const MCP_TOOLS_CONFIG: ToolPromptConfig[] = [
       darkIconBg: 'dark:bg-orange-900',
     },
   },
+
+  // docs_search - library identifier lookup
+  {
+    id: 'docs_search',
+    name: 'Docs Search',
+    description: 'Find library identifiers in the documentation index',
+    prompt: {
+      base: '',
+      whenToUse: [
+        'Before docs: look up the owner/repo identifier instead of guessing it',
+      ],
+      howToUse: [
+        'Library name or keywords, optionally a language such as python',
+      ],
+    },
+    ui: {
+      enabled: true,
+      canDisable: true,
+      icon: 'i-carbon-search-locate text-lg text-orange-600 dark:text-orange-400',
+      iconBg: 'bg-orange-100',
+      darkIconBg: 'dark:bg-orange-900',
+    },
+  },
+
+  // docs_compare - same topic in several libraries
+  {
+    id: 'docs_compare',
+    name: 'Docs Compare',
+    description: 'Compare how libraries document the same topic, side by side',
+    prompt: {
+      base: '',
+      whenToUse: [
+        'When choosing between libraries or porting code: the same topic in two or three libraries, e.g. react vs vue routing',
+      ],
+      howToUse: [
+        'Two or three library identifiers and one topic',
+      ],
+    },
+    ui: {
+      enabled: true,
+      canDisable: true,
+      icon: 'i-carbon-compare text-lg text-orange-600 dark:text-orange-400',
+      iconBg: 'bg-orange-100',
+      darkIconBg: 'dark:bg-orange-900',
+    },
+  },
+
+  // devkit_stats - docs API request metrics
+  {
+    id: 'devkit_stats',
+    name: 'Stats',
+    description: 'Docs API request metrics and cache hit rate',
+    prompt: {
+      base: '',
+      whenToUse: [
+        'When docs lookups are slow or failing: check status codes, latency and cache hit rate',
+      ],
+      howToUse: [
+        'No arguments',
+      ],
+    },
+    ui: {
+      enabled: true,
+      canDisable: true,
+      icon: 'i-carbon-chart-line text-lg text-orange-600 dark:text-orange-400',
+      iconBg: 'bg-orange-100',
+      darkIconBg: 'dark:bg-orange-900',
+    },
+  },
+
+  // docs_status - why docs lookups fail
+  {
+    id: 'docs_status',
+    name: 'Docs Status',
+    description: 'Why docs queries fail: reachability, keys, rate limit and last error',
+    prompt: {
+      base: '',
+      whenToUse: [
+        'When docs lookups keep failing: check whether the API is reachable, the keys are accepted and quota is left',
+      ],
+      howToUse: [
+        'No arguments',
+      ],
+    },
+    ui: {
+      enabled: true,
+      canDisable: true,
+      icon: 'i-carbon-health-cross text-lg text-orange-600 dark:text-orange-400',
+      iconBg: 'bg-orange-100',
+      darkIconBg: 'dark:bg-orange-900',
+    },
+  },
+
+  // project_docs - project documentation search
+  {
+    id: 'project_docs',
+    name: 'Project Docs',
+    description: 'Offline search over the project\'s README, docs/ and doc comments',
+    prompt: {
+      base: '',
+      whenToUse: [
+        'Project conventions: check the repo\'s own docs before guessing',
+        'Citing internal documentation: results include file paths and line ranges',
+      ],
+      howToUse: [
+        'Use absolute project path and a few keywords',
+      ],
+    },
+    ui: {
+      enabled: true,
+      canDisable: true,
+      icon: 'i-carbon-notebook text-lg text-teal-600 dark:text-teal-400',
+      iconBg: 'bg-teal-100',
+      darkIconBg: 'dark:bg-teal-900',
+    },
+  },
 ]
 
 // ----------------------------------------------------------------------------
@@ -65,6 +65,15 @@ export const MCP_TOOLS = {
   store: 'store',
   index: 'index',
   docs: 'docs',
+  docs_search: 'docs_search',
+  docs_compare: 'docs_compare',
+  devkit_stats: 'devkit_stats',
+  docs_status: 'docs_status',
+  project_docs: 'project_docs',
+  run_command: 'run_command',
+  git_context: 'git_context',
+  list_sessions: 'list_sessions',
+  reload_config: 'reload_config',
 } as const
 
 // 字体大小常量
@@ -6,6 +6,45 @@ export interface McpRequest {
   menu?: string[]
   chalkboard?: boolean
   project_root_path?: string
+  diff?: DiffView
+}
+
+// 待审阅的 diff（由 MCP 端解析，见 src/rust/mcp/tools/interaction/diff.rs）
+export type DiffLineKind = 'context' | 'added' | 'removed' | 'no_newline'
+
+export interface DiffLine {
+  kind: DiffLineKind
+  text: string
+  old_line: number | null
+  new_line: number | null
+}
+
+export interface DiffHunk {
+  header: string
+  old_start: number
+  old_count: number
+  new_start: number
+  new_count: number
+  lines: DiffLine[]
+}
+
+export interface DiffFile {
+  old_path: string | null
+  new_path: string | null
+  hunks: DiffHunk[]
+  additions: number
+  deletions: number
+  truncated: boolean
+}
+
+export interface DiffView {
+  files: DiffFile[]
+  additions: number
+  deletions: number
+  total_lines: number
+  shown_lines: number
+  // 无法解析时的原文
+  plain?: string
 }
 
 // 自定义prompt类型定义
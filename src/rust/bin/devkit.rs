@@ -0,0 +1,16 @@
+// Unified command line entry point for headless operations
+//
+// Usage:
+//   devkit instances                  List running devkit-mcp-http instances
+//   devkit completions <shell>        Print shell completion script
+//   devkit config get <key>           Print a config value
+//   devkit memory sync <path>         Merge project memories with a shared store file
+//   devkit prompt <request-file>      Answer an interaction request in the terminal
+//   devkit call <tool> [--args-file]  Run one tool call and print the result as JSON
+
+fn main() {
+    if let Err(e) = devkit::cli::run() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -11,55 +11,482 @@
 //        }
 //      }
 //    }
+//    Clients that speak streamable HTTP natively (Claude Desktop, newer Windsurf builds)
+//    can use http://127.0.0.1:8808/mcp instead; both paths serve the same sessions.
+//
+// In Docker, run `devkit-mcp-http --container` (detected automatically via /.dockerenv):
+// binds 0.0.0.0, honors PORT, logs JSON to stdout, disables the popup tool and serves
+// GET /health and GET /readiness. Set PUBLIC_URL to print the externally reachable URL.
+// See `devkit-mcp-http --help` for the individual overrides.
+//
+// To reach the server from other machines or containers, bind another address with
+// --host, MCP_HTTP_BIND or mcp_config.http_bind (e.g. 0.0.0.0, or :: / [::] for IPv6).
+// Set DEVKIT_HTTP_TOKEN (or mcp_config.http_token) to require a shared secret on the MCP
+// endpoints, sent as "Authorization: Bearer <token>" or ?token=<token>; a non-loopback bind
+// without one is logged as a warning.
+//
+// HTTPS without a reverse proxy: --tls-cert/--tls-key (PEM files), or --tls-self-signed to
+// generate a certificate once into the state directory; its SHA-256 fingerprint is logged.
+//
+// A config file that fails to parse is moved aside and defaults are used;
+// pass --strict-config to exit instead.
+//
+// The config file is watched: tool switches, docs API keys, rate and concurrency limits and
+// mcp_config.log_level take effect on the next call without a restart (the reload_config tool
+// does the same on demand). Bind address, token, endpoints and telemetry need a restart.
+//
+// Ctrl+C or SIGTERM (docker stop, systemd) drains first: new calls get a 503, running
+// ones finish within --shutdown-grace seconds (default 30), then sessions are saved and
+// the logs flushed. A second signal exits immediately.
+//
+// Open sessions (client info, memory subscriptions, last SSE event id) are saved on
+// shutdown; after a restart on the same port clients reconnecting with their old
+// Mcp-Session-Id resume within --resume-grace seconds (default 600, 0 disables).
+//
+// --websocket also serves MCP over WebSocket on /ws (one session per connection,
+// JSON-RPC messages as text frames).
+//
+// Extra named endpoints with their own tool sets come from mcp_config.endpoints, e.g.
+//   "endpoints": [{"name": "readonly", "path": "/sse/readonly",
+//                  "tools": {"cache": false, "store": true}, "memory_read_only": true}]
+//
+// GET /manifest describes the enabled tools, their limits and the error codes as JSON
+// (the same document as `devkit manifest`).
+//
+// Clients may pick their SSE keep-alive interval with ?keepalive=<secs> on the endpoint URL,
+// clamped to --sse-keepalive-min/--sse-keepalive-max (default 30s within 5..120s).
+//
+// Sessions without requests or open streams for --session-idle-timeout (default 1800s) are
+// closed so vanished clients do not leak memory and sockets; --session-max-lifetime closes
+// any session after that many seconds. 0 disables either limit.
+//
+// Builds with the otel feature export tool-call and HTTP request spans over OTLP
+// when mcp_config.telemetry.enabled is set; a W3C traceparent header continues the caller's trace.
+//
+// mcp_config.docs_contract_preflight checks the docs API response format once in the
+// background after start; drift is logged and reported by GET /health. The configured
+// docs API keys are checked the same way unless mcp_config.docs_key_check is false.
+//
+// JSON-RPC batches (a JSON array of messages) are answered with an array of responses in
+// batch order; --max-batch (default 50) caps the number of messages per batch.
+//
+// DEVKIT_DOCS_RECORD=<dir> / DEVKIT_DOCS_REPLAY=<dir> record upstream docs traffic or
+// replay a recording instead of calling the docs API (see `devkit --record`).
+//
+// Open http://127.0.0.1:8808/ in a browser for a status page with client config snippets.
+//
+// Running instances can be listed with `devkit instances`.
+//
+// When the port is in use the server takes the next free one (or any free port) and logs
+// the updated client config; --auto-port=false (DEVKIT_AUTO_PORT=0) fails instead, as does
+// container mode by default. The port bound is written to <state_dir>/mcp_http.port.
+//
+// Responses of 1 KiB or more are compressed with gzip or deflate when the client sends
+// Accept-Encoding; SSE streams never are. --compression=false (DEVKIT_HTTP_COMPRESSION=0)
+// turns this off, e.g. behind a proxy that compresses already.
+//
+// Only one server runs per configured port: a second start (another IDE launching it) finds
+// <state_dir>/locks/http-<port>.lock taken and, when the running server answers, prints its
+// endpoint and exits 0 so the IDE can use it. --join=false (DEVKIT_JOIN=0) fails instead.
+//
+// --daemon starts the server in the background (logging to the log file, pid in
+// <state_dir>/mcp_http.pid) and returns once it listens; `devkit-mcp-http status` and
+// `devkit-mcp-http stop` report and stop it.
+//
+// Every request is logged with its method, path, MCP session, status and duration under a
+// correlation id: the client's X-Request-Id, or a fresh one echoed in the response. Log
+// lines of the tool calls it carries show the same id as [req=<id>].
+//
+// A missing or read-only config/state/cache directory does not stop the server: it runs
+// degraded (see `devkit doctor`), logs one warning and reports the directories in GET /health.
 
-use devkit::{mcp::DevkitServer, utils::auto_init_logger, log_important};
-use axum::Router;
+use devkit::{mcp::DevkitServer, utils::flush_logs, utils::init_server_logger, utils::paths::preflight, utils::server_log_path, log_important};
+use devkit::mcp::boundary::reject_malformed_messages;
+use devkit::mcp::bootstrap::Bootstrap;
+use devkit::mcp::telemetry::trace_http_requests;
+use devkit::mcp::http::{daemon, lock};
+use devkit::mcp::http::{
+    acquire_instance_lock, bind_listener, compression_layer, default_router, endpoints_router, require_token, health_router, keepalive_service, log_http_requests, manifest_router, metrics_router, register_instance, reject_when_draining, serve_tls, split_batches, status_router,
+    list_instances, read_port_file, shutdown_signal, write_port_file, validate_endpoints, ws_router, AccessToken, DrainState, HttpServerArgs, HttpServerOptions, InstanceInfo, LockOutcome, LockOwner, OutboundBuffers, ResumableSessionManager, SessionStore, StatusPage, TlsSetup,
+    DEFAULT_MCP_PATH, HEALTH_PATH, METRICS_PATH, READINESS_PATH, STREAMABLE_HTTP_PATH, WS_PATH,
+};
+use devkit::mcp::server::DEFAULT_ENDPOINT;
+use axum::middleware::{from_fn, from_fn_with_state};
+use clap::{Parser, Subcommand};
 use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
-use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
-use std::net::SocketAddr;
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 
+/// Streamable HTTP MCP server
+#[derive(Parser)]
+#[command(name = "devkit-mcp-http", version)]
+struct Cli {
+    /// Run in the background, logging to the log file; manage it with `stop` and `status`
+    #[arg(long)]
+    daemon: bool,
+
+    #[command(subcommand)]
+    command: Option<DaemonCommand>,
+
+    #[command(flatten)]
+    server: HttpServerArgs,
+}
+
+/// Manage a server started with --daemon
+#[derive(Subcommand)]
+enum DaemonCommand {
+    /// Stop the background server, letting in-flight calls finish
+    Stop,
+    /// Show whether a background server is running and where (exit code 3 when not)
+    Status,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    auto_init_logger()?;
-    
-    let port: u16 = std::env::var("MCP_HTTP_PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(8808);
-    
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    
-    log_important!(info, "Starting MCP HTTP (Streamable) server on port {}", port);
-
-    let session_manager = Arc::new(LocalSessionManager::default());
+    let started = Instant::now();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(DaemonCommand::Stop) => return stop_daemon(),
+        Some(DaemonCommand::Status) => return daemon_status(),
+        None => {}
+    }
+    let daemon_child = daemon::is_daemon_child();
+    if cli.daemon && !daemon_child {
+        let pid = daemon::start()?;
+        println!("devkit-mcp-http running in the background (pid {})", pid);
+        print_daemon_location(pid);
+        return Ok(());
+    }
+    let mut options = HttpServerOptions::resolve(&cli.server);
+    // Nobody reads the terminal of a background server
+    if daemon_child {
+        options.log_stdout = false;
+    }
+
+    init_server_logger(options.log_format, options.log_stdout)?;
+
+    // Config, its watcher, telemetry and docs checks, shared with the stdio server
+    let bootstrap = Bootstrap::start(cli.server.strict_config).await?;
+    let mcp_config = bootstrap.config.mcp_config.clone();
+    options.apply_config_bind(mcp_config.http_bind.as_deref())?;
+    let token = AccessToken::resolve(mcp_config.http_token.as_deref());
+    let tls = options.tls()?.map(|source| TlsSetup::load(&source, &options.tls_names())).transpose()?;
+    let endpoints = mcp_config.endpoints;
+    validate_endpoints(&endpoints)?;
+
+    log_important!(
+        info,
+        "Starting MCP HTTP (Streamable) server on {}{}",
+        options.bind_addr(),
+        if options.container { " (container mode)" } else { "" }
+    );
+    if let Some(warning) = options.exposure_warning(token.is_some()) {
+        log_important!(warn, "{}", warning);
+    }
+    if let Some(tls) = &tls {
+        let kind = if tls.self_signed { "self-signed certificate" } else { "certificate" };
+        log_important!(info, "HTTPS with {} {}", kind, tls.cert_file.display());
+        log_important!(info, "Certificate SHA-256 fingerprint: {}", tls.fingerprint);
+    }
+
+    // Claim the configured port before binding so a second start can tell who serves it
+    let instance_lock = match acquire_instance_lock(options.port) {
+        Ok(LockOutcome::Acquired(instance_lock)) => Some(instance_lock),
+        Ok(LockOutcome::Held(owner)) => {
+            join_running_instance(owner, options.join).await?;
+            flush_logs();
+            return Ok(());
+        }
+        Err(e) => {
+            log_important!(warn, "Running without an instance lock: {}", e);
+            None
+        }
+    };
+
+    let listener = bind_listener(options.bind_addr(), options.auto_port).await?;
+    let local_addr = listener.local_addr()?;
+    let local_url = options.local_url(local_addr);
+    if let Some(Err(e)) = instance_lock.as_ref().map(|instance_lock| instance_lock.set_owner(&local_url)) {
+        log_important!(warn, "Failed to record the instance lock owner: {}", e);
+    }
+    let public_url = options.public_url(local_addr);
+    if local_addr.port() != options.port {
+        log_important!(
+            warn,
+            "Port {} is in use, listening on {} instead; update client configs from the snippet below",
+            options.port,
+            local_addr.port()
+        );
+    }
+
+    // Transport name -> path, for the discovery file and the status page
+    let mut transports = BTreeMap::new();
+    transports.insert("streamable_http".to_string(), DEFAULT_MCP_PATH.to_string());
+    transports.insert("mcp".to_string(), STREAMABLE_HTTP_PATH.to_string());
+    for endpoint in &endpoints {
+        transports.insert(format!("streamable_http:{}", endpoint.name), endpoint.path.clone());
+    }
+    if options.health {
+        transports.insert("health".to_string(), HEALTH_PATH.to_string());
+        transports.insert("readiness".to_string(), READINESS_PATH.to_string());
+    }
+    transports.insert("metrics".to_string(), METRICS_PATH.to_string());
+    if options.websocket {
+        transports.insert("websocket".to_string(), WS_PATH.to_string());
+    }
+    let mut instance = InstanceInfo::for_current_process(local_url, transports.clone());
+    instance.auth = token.is_some().then(|| "bearer".to_string());
+
+    // Sessions saved by the previous process on this port; an unwritable state
+    // directory was already reported by the startup preflight
+    let persist_state = preflight().persist_state();
+    let sessions = if persist_state {
+        SessionStore::for_port(local_addr.port(), options.resume_grace).unwrap_or_else(|e| {
+            log_important!(warn, "Session resumption disabled: {}", e);
+            SessionStore::disabled()
+        })
+    } else {
+        SessionStore::disabled()
+    };
+    match sessions.load() {
+        Ok(0) => {}
+        Ok(count) => log_important!(
+            info,
+            "{} session(s) from the previous run can resume within {}s",
+            count,
+            options.resume_grace.as_secs()
+        ),
+        Err(e) => log_important!(warn, "Failed to load saved sessions: {}", e),
+    }
+
+    let cancellation_token = CancellationToken::new();
     let server_config = StreamableHttpServerConfig {
-        sse_keep_alive: Some(Duration::from_secs(30)),
+        sse_keep_alive: Some(options.sse_keepalive.default),
         stateful_mode: true,
-        cancellation_token: CancellationToken::new(),
+        cancellation_token: cancellation_token.clone(),
     };
 
+    OutboundBuffers::global().set_limits(options.sse_buffer);
+    let drain = DrainState::new();
+    let popup_enabled = options.popup;
+    let session_drain = drain.clone();
+    let server_sessions = sessions.clone();
+    // Shared by every transport
+    let new_server = move || {
+        DevkitServer::new()
+            .with_popup(popup_enabled)
+            .with_drain(session_drain.clone())
+            .with_sessions(server_sessions.clone())
+    };
+    let http_server = new_server.clone();
+    let session_manager =
+        ResumableSessionManager::new(sessions.clone(), DEFAULT_ENDPOINT, new_server.clone()).with_limits(options.session_limits);
     let mcp_service = StreamableHttpService::new(
-        || Ok::<_, std::io::Error>(DevkitServer::new()),
-        session_manager,
-        server_config,
+        move || Ok::<_, std::io::Error>(http_server()),
+        Arc::new(session_manager),
+        server_config.clone(),
     );
 
-    // Keep the original /sse path for configuration compatibility
-    let app = Router::new().route_service("/sse", mcp_service);
-    
-    log_important!(info, "MCP HTTP server ready at http://{}", addr);
+    // The original /sse path for configuration compatibility, plus /mcp
+    let mut app = default_router(keepalive_service(mcp_service, options.sse_keepalive))
+        .merge(endpoints_router(
+            &endpoints,
+            new_server.clone(),
+            &sessions,
+            server_config,
+            options.sse_keepalive,
+            options.session_limits,
+        ))
+        .layer(from_fn(reject_malformed_messages))
+        .layer(from_fn_with_state(options.max_batch, split_batches))
+        .layer(from_fn_with_state(drain.clone(), reject_when_draining));
+    app = app.merge(manifest_router(new_server.clone()));
+    if options.websocket {
+        app = app.merge(ws_router(new_server.clone(), drain.clone(), cancellation_token.clone(), options.ws_max_frame_bytes));
+    }
+    // Everything above needs the token; status, metrics and health stay open
+    if let Some(token) = &token {
+        app = app.layer(from_fn_with_state(token.clone(), require_token));
+    }
+    let status_page = StatusPage {
+        url: public_url.clone(),
+        transports,
+        auth_required: instance.auth.is_some(),
+        started,
+    };
+    app = app.merge(status_router(status_page, drain.clone(), new_server.clone()));
+    app = app.merge(metrics_router());
+    if options.health {
+        app = app.merge(health_router(drain.clone(), started, token.clone(), new_server.clone()));
+    }
+    if options.compression {
+        app = app.layer(compression_layer());
+    }
+    app = app.layer(from_fn(trace_http_requests));
+    // Outermost, so rejected and unauthorized requests are logged too
+    app = app.layer(from_fn(log_http_requests));
+
+    log_important!(info, "MCP HTTP server ready at {}", public_url);
+    log_important!(info, "Status page: {}/", public_url);
+    if options.health {
+        log_important!(info, "Health endpoints: {0}{1}, {0}{2}", public_url, HEALTH_PATH, READINESS_PATH);
+    }
+    log_important!(info, "Metrics endpoint: {}{}", public_url, METRICS_PATH);
+    if options.websocket {
+        let ws_url = public_url.replacen("http", "ws", 1);
+        log_important!(info, "WebSocket endpoint: {}{}", ws_url, WS_PATH);
+    }
+    for endpoint in &endpoints {
+        log_important!(info, "Endpoint '{}': {}{}", endpoint.name, public_url, endpoint.path);
+    }
+    if !options.popup {
+        log_important!(info, "Popup tools disabled");
+    }
+    if token.is_some() {
+        log_important!(info, "MCP endpoints require the access token (Authorization: Bearer <token> or ?token=<token>)");
+    }
     log_important!(info, "");
     log_important!(info, "=== Windsurf Configuration ===");
     log_important!(info, r#"Add to ~/.codeium/windsurf/mcp_config.json:"#);
-    log_important!(info, r#"{{"mcpServers": {{"devkit": {{"serverUrl": "http://127.0.0.1:{}/sse"}}}}}}"#, port);
+    log_important!(info, r#"{{"mcpServers": {{"devkit": {{"serverUrl": "{}/sse"}}}}}}"#, public_url);
+    log_important!(info, "Streamable HTTP clients (Claude Desktop, newer Windsurf): {}{}", public_url, STREAMABLE_HTTP_PATH);
     log_important!(info, "");
-    
-    // Start server
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
-    
+
+    // Publish discovery file so clients can find this instance
+    let registration = match persist_state.then(|| register_instance(&instance)) {
+        None => None,
+        Some(Ok(registration)) => Some(registration),
+        Some(Err(e)) => {
+            log_important!(warn, "Failed to write discovery file: {}", e);
+            None
+        }
+    };
+    // Well-known file with just the port, for clients that cannot list instances
+    let port_file = match persist_state.then(|| write_port_file(local_addr.port())) {
+        None => None,
+        Some(Ok(port_file)) => {
+            log_important!(info, "Port file: {}", port_file.path().display());
+            Some(port_file)
+        }
+        Some(Err(e)) => {
+            log_important!(warn, "Failed to write port file: {}", e);
+            None
+        }
+    };
+
+    // Written once listening, which is what `--daemon` waits for
+    let pid_file = match daemon_child.then(|| daemon::write_pid_file(options.shutdown_grace)) {
+        None => None,
+        Some(Ok(pid_file)) => {
+            log_important!(info, "Running in the background, pid file: {}", pid_file.path().display());
+            Some(pid_file)
+        }
+        Some(Err(e)) => {
+            log_important!(warn, "Failed to write pid file: {}", e);
+            None
+        }
+    };
+
+    // On Ctrl+C or SIGTERM: reject new calls, let running ones finish within the grace
+    // period, then save the open sessions for the next process. A second signal exits at once.
+    let shutdown_token = cancellation_token.clone();
+    let shutdown_grace = options.shutdown_grace;
+    let shutdown = async move {
+        let signal = shutdown_signal().await;
+        log_important!(info, "{} received, shutting down", signal);
+        tokio::spawn(async {
+            let signal = shutdown_signal().await;
+            log_important!(warn, "{} received again, exiting without waiting for in-flight calls", signal);
+            flush_logs();
+            std::process::exit(130);
+        });
+        drain.shutdown(shutdown_grace, &shutdown_token).await;
+        if let Err(e) = sessions.save() {
+            log_important!(warn, "Failed to save sessions: {}", e);
+        }
+    };
+    match tls {
+        Some(tls) => serve_tls(listener, app, tls.config, shutdown).await?,
+        None => axum::serve(listener, app).with_graceful_shutdown(shutdown).await?,
+    }
+
+    if let Some(registration) = registration {
+        registration.remove();
+    }
+    drop(port_file);
+    drop(pid_file);
+    drop(instance_lock);
+    log_important!(info, "MCP HTTP server stopped");
+    flush_logs();
+
+    Ok(())
+}
+
+/// Another process holds the lock of the port: point at it when it is healthy, fail otherwise
+async fn join_running_instance(owner: Option<LockOwner>, join: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(owner) = owner else {
+        return Err("Another devkit-mcp-http is starting on this port; try again in a moment or pick another --port".into());
+    };
+    if !lock::is_loopback_url(&owner.url) {
+        return Err(format!(
+            "devkit-mcp-http (pid {}) holds this port on {}, which is not a loopback address and is not probed; stop it or pick another --port",
+            owner.pid, owner.url
+        )
+        .into());
+    }
+    if !lock::probe(&owner.url).await {
+        return Err(format!(
+            "devkit-mcp-http (pid {}) holds this port but does not answer on {}; stop it or pick another --port",
+            owner.pid, owner.url
+        )
+        .into());
+    }
+    if !join {
+        return Err(format!(
+            "devkit-mcp-http (pid {}) already serves {}; use it, pass --join to reuse it or pick another --port",
+            owner.pid, owner.url
+        )
+        .into());
+    }
+    log_important!(info, "devkit-mcp-http (pid {}) already serves {}, using it", owner.pid, owner.url);
+    println!("devkit-mcp-http is already running (pid {})", owner.pid);
+    println!("Streamable HTTP: {}{}", owner.url, STREAMABLE_HTTP_PATH);
+    println!("SSE: {}{}", owner.url, DEFAULT_MCP_PATH);
+    println!(r#"Windsurf: {{"mcpServers": {{"devkit": {{"serverUrl": "{}{}"}}}}}}"#, owner.url, DEFAULT_MCP_PATH);
+    Ok(())
+}
+
+/// `devkit-mcp-http stop`
+fn stop_daemon() -> Result<(), Box<dyn std::error::Error>> {
+    match daemon::stop()? {
+        Some(pid) => println!("Stopped devkit-mcp-http (pid {})", pid),
+        None => println!("No background devkit-mcp-http is running"),
+    }
+    Ok(())
+}
+
+/// `devkit-mcp-http status`; exits with 3 when no background server runs, like init scripts
+fn daemon_status() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(pid) = daemon::running_pid() else {
+        println!("No background devkit-mcp-http is running");
+        std::process::exit(3);
+    };
+    println!("devkit-mcp-http is running in the background (pid {})", pid);
+    print_daemon_location(pid);
     Ok(())
 }
+
+/// URL and log file of the background server
+fn print_daemon_location(pid: u32) {
+    let instance = list_instances().ok().and_then(|instances| instances.into_iter().find(|instance| instance.pid == pid));
+    match (instance, read_port_file()) {
+        (Some(instance), _) => println!("URL: {}", instance.url),
+        (None, Some(port)) => println!("Port: {}", port),
+        (None, None) => {}
+    }
+    if let Some(log) = server_log_path() {
+        println!("Log: {}", log.display());
+    }
+}
@@ -1,9 +1,41 @@
-// MCP server entry point
+// MCP server entry point (stdio)
+//
+// For clients that only spawn stdio servers (Claude Desktop by default, most CLIs).
+// Serves the same tools as devkit-mcp-http: both build DevkitServer after the shared
+// startup in devkit::mcp::bootstrap.
+//
+// Options:
+//   --strict-config   Exit when the config file cannot be parsed instead of
+//                     quarantining it and starting with defaults
+//   --pipe [name]     Windows only: serve on the named pipe \\.\pipe\<name>
+//                     (default devkit-mcp) instead of stdio; each client that
+//                     opens the pipe gets its own session
+//
+// With mcp_config.docs_contract_preflight set, the docs API response format is
+// checked once in the background after start; drift is only logged.
+// Configured docs API keys are checked the same way unless mcp_config.docs_key_check
+// is false; expired or rejected keys are logged as warnings.
+//
+// Edits of the config file (tool switches, docs API keys, rate and concurrency limits,
+// mcp_config.log_level) apply without a restart; the reload_config tool reloads on demand.
+//
+// DEVKIT_DOCS_RECORD=<dir> writes every upstream docs request/response to numbered
+// JSON files (credentials redacted); DEVKIT_DOCS_REPLAY=<dir> answers from them instead.
 use devkit::{mcp::run_server, utils::init_mcp_logger, log_important};
+use devkit::mcp::bootstrap::Bootstrap;
+use devkit::mcp::pipe::{pipe_arg, run_pipe_server};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     init_mcp_logger()?;
     log_important!(info, "Starting MCP server");
-    run_server().await
+
+    let args: Vec<String> = std::env::args().collect();
+    // Dropped when main returns, stopping the config watcher and flushing telemetry
+    let _bootstrap = Bootstrap::start(args.iter().any(|arg| arg == "--strict-config")).await?;
+
+    match pipe_arg(&args) {
+        Some(name) => run_pipe_server(&name).await,
+        None => run_server().await,
+    }
 }
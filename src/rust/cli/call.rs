@@ -0,0 +1,193 @@
+//! `devkit call <tool>`：单次工具调用，用于脚本与问题复现
+//!
+//! 在进程内与 stdio 服务走同一条路径（消息校验、参数校验、工具分发与超时）：
+//! 先完成 initialize 握手，再执行一次 tools/call，把 CallToolResult
+//! （文本与结构化内容）以 JSON 输出到标准输出。
+//!
+//! 退出码：0 成功，1 工具返回 `is_error`，2 JSON-RPC 错误（例如参数不合法）。
+
+use anyhow::{Context, Result};
+use rmcp::model::{CallToolResult, ErrorData as McpError, JsonObject, ProtocolVersion};
+use rmcp::ServiceExt;
+use serde_json::{json, Value};
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader, Lines};
+
+use crate::mcp::boundary::checked_transport;
+use crate::mcp::DevkitServer;
+
+/// 工具返回 `is_error: true`
+pub const EXIT_TOOL_ERROR: i32 = 1;
+/// 调用被拒绝（JSON-RPC 错误）
+pub const EXIT_RPC_ERROR: i32 = 2;
+
+/// 服务端与客户端之间的管道缓冲
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// 执行一次调用并打印结果，返回退出码
+pub fn run(tool: &str, args_file: Option<&Path>) -> Result<i32> {
+    let arguments = read_arguments(args_file)?;
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    let outcome = runtime.block_on(call_once(DevkitServer::new(), tool, arguments))?;
+
+    let (output, code) = match outcome {
+        Ok(result) => {
+            let code = if result.is_error == Some(true) { EXIT_TOOL_ERROR } else { 0 };
+            (serde_json::to_value(&result)?, code)
+        }
+        Err(error) => (json!({ "error": error }), EXIT_RPC_ERROR),
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(code)
+}
+
+/// 读取调用参数：`-` 表示标准输入；未指定时标准输入不是终端则读取它，否则为空对象
+pub fn read_arguments(args_file: Option<&Path>) -> Result<JsonObject> {
+    let content = match args_file {
+        Some(path) if path.as_os_str() == "-" => read_stdin()?,
+        Some(path) => std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?,
+        None if std::io::stdin().is_terminal() => String::new(),
+        None => read_stdin()?,
+    };
+    if content.trim().is_empty() {
+        return Ok(JsonObject::new());
+    }
+    match serde_json::from_str(&content).context("Arguments are not valid JSON")? {
+        Value::Object(arguments) => Ok(arguments),
+        other => anyhow::bail!("Arguments must be a JSON object, got {}", other),
+    }
+}
+
+fn read_stdin() -> Result<String> {
+    let mut content = String::new();
+    std::io::stdin().read_to_string(&mut content).context("Failed to read arguments from stdin")?;
+    Ok(content)
+}
+
+/// 通过进程内的 stdio 传输完成握手并调用一次工具
+///
+/// 外层错误表示传输或握手失败；内层错误是服务端对这次调用返回的 JSON-RPC 错误。
+pub async fn call_once(
+    server: DevkitServer,
+    tool: &str,
+    arguments: JsonObject,
+) -> Result<std::result::Result<CallToolResult, McpError>> {
+    call_with_capabilities(server, json!({}), tool, arguments).await
+}
+
+/// 同 [`call_once`]，握手时声明给定的客户端能力
+pub async fn call_with_capabilities(
+    server: DevkitServer,
+    capabilities: Value,
+    tool: &str,
+    arguments: JsonObject,
+) -> Result<std::result::Result<CallToolResult, McpError>> {
+    let (client_side, server_side) = tokio::io::duplex(PIPE_CAPACITY);
+    let (server_read, server_write) = tokio::io::split(server_side);
+    let service = tokio::spawn(async move {
+        if let Ok(running) = server.serve(checked_transport(server_read, server_write)).await {
+            let _ = running.waiting().await;
+        }
+    });
+
+    let (client_read, mut client_write) = tokio::io::split(client_side);
+    let mut lines = BufReader::new(client_read).lines();
+    send(&mut client_write, &json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": ProtocolVersion::LATEST,
+            "capabilities": capabilities,
+            "clientInfo": { "name": "devkit-call", "version": env!("CARGO_PKG_VERSION") }
+        }
+    }))
+    .await?;
+    let initialized = receive(&mut lines, 1).await?;
+    if let Some(error) = initialized.get("error") {
+        anyhow::bail!("Initialize failed: {}", error);
+    }
+    send(&mut client_write, &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).await?;
+
+    send(&mut client_write, &json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": { "name": tool, "arguments": arguments }
+    }))
+    .await?;
+    let response = receive(&mut lines, 2).await?;
+
+    drop(client_write);
+    service.abort();
+
+    if let Some(error) = response.get("error") {
+        return Ok(Err(serde_json::from_value(error.clone()).context("Invalid error response")?));
+    }
+    let result = response.get("result").cloned().context("Response has neither result nor error")?;
+    Ok(Ok(serde_json::from_value(result).context("Invalid tool result")?))
+}
+
+/// 读取到指定 id 的响应为止，跳过其间的通知（日志、进度等）
+async fn receive(lines: &mut Lines<impl AsyncBufRead + Unpin>, id: i64) -> Result<Value> {
+    while let Some(line) = lines.next_line().await? {
+        let message: Value = serde_json::from_str(&line).context("Server sent invalid JSON")?;
+        if message.get("id") == Some(&json!(id)) {
+            return Ok(message);
+        }
+    }
+    anyhow::bail!("Server closed the connection before answering")
+}
+
+async fn send(writer: &mut (impl AsyncWrite + Unpin), message: &Value) -> Result<()> {
+    writer.write_all(format!("{}\n", message).as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[tokio::test]
+    async fn test_invalid_arguments_are_rejected() {
+        let arguments = json!({ "project_root_path": 42 }).as_object().cloned().unwrap();
+        let error = call_once(DevkitServer::new(), "git_context", arguments).await.unwrap().unwrap_err();
+        assert_eq!(error.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert_eq!(error.data, Some(json!({ "path": "project_root_path" })));
+    }
+
+    #[test]
+    fn test_read_arguments() {
+        let dir = TestDir::new("call-args");
+        let path = dir.join("args.json");
+        std::fs::write(&path, r#"{"library": "vercel/next.js"}"#).unwrap();
+        assert_eq!(read_arguments(Some(&path)).unwrap()["library"], "vercel/next.js");
+
+        std::fs::write(&path, "[1, 2]").unwrap();
+        assert!(read_arguments(Some(&path)).is_err());
+    }
+
+    #[cfg(feature = "docs")]
+    #[tokio::test]
+    async fn test_docs_call_against_mock_server() {
+        use crate::docs_client::mock::{MockResponse, MockServer};
+
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/docs/code/devkit-call/mock" => MockResponse::text(200, "Mock library docs"),
+            _ => MockResponse::text(404, "not found"),
+        })
+        .await;
+        let _guard = crate::mcp::tools::docs::DOCS_API_URL_LOCK.lock().await;
+        std::env::set_var(crate::mcp::tools::docs::DOCS_API_URL_ENV, server.base_url());
+
+        let arguments = json!({ "library": "devkit-call/mock" }).as_object().cloned().unwrap();
+        let result = call_once(DevkitServer::new(), "docs", arguments).await.unwrap().unwrap();
+        assert_eq!(result.is_error, Some(false));
+        assert!(serde_json::to_string(&result.content).unwrap().contains("Mock library docs"));
+
+        std::env::remove_var(crate::mcp::tools::docs::DOCS_API_URL_ENV);
+    }
+}
@@ -0,0 +1,176 @@
+//! 补全脚本生成
+//!
+//! 使用 clap_complete 生成各 shell 的补全脚本，配置键路径从默认配置结构动态推导，
+//! 工具名取自工具开关列表。候选只用于补全，不限制取值：例如 tool_limits 下的条目不在默认配置中，
+//! 由命令在运行时按实际配置查找
+
+use anyhow::Result;
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser};
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::Cli;
+use crate::config::{default_mcp_tools, AppConfig};
+
+const BIN_NAME: &str = "devkit";
+
+/// 将补全脚本写入指定输出
+pub fn write(shell: Shell, out: &mut dyn Write) -> Result<()> {
+    let mut cmd = Cli::command();
+    clap_complete::generate(shell, &mut cmd, BIN_NAME, out);
+    Ok(())
+}
+
+/// 生成补全脚本字符串
+pub fn generate(shell: Shell) -> Result<String> {
+    let mut buf = Vec::new();
+    write(shell, &mut buf)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+/// 安装补全脚本到各 shell 的约定位置
+pub fn install(shell: Shell) -> Result<PathBuf> {
+    let path = install_path(shell)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, generate(shell)?)?;
+    Ok(path)
+}
+
+/// 各 shell 的约定补全脚本路径
+fn install_path(shell: Shell) -> Result<PathBuf> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("无法获取用户主目录"))?;
+    let path = match shell {
+        Shell::Bash => dirs::data_dir()
+            .unwrap_or_else(|| home.join(".local").join("share"))
+            .join("bash-completion")
+            .join("completions")
+            .join(BIN_NAME),
+        Shell::Zsh => std::env::var("ZDOTDIR")
+            .map(PathBuf::from)
+            .unwrap_or(home)
+            .join(".zfunc")
+            .join(format!("_{}", BIN_NAME)),
+        Shell::Fish => dirs::config_dir()
+            .unwrap_or_else(|| home.join(".config"))
+            .join("fish")
+            .join("completions")
+            .join(format!("{}.fish", BIN_NAME)),
+        _ => anyhow::bail!(
+            "无法自动检测 {} 的补全目录，请重定向输出: devkit completions {} > <path>",
+            shell,
+            shell
+        ),
+    };
+    Ok(path)
+}
+
+/// 接受任意字符串的解析器，附带写入补全脚本的候选值
+#[derive(Debug, Clone)]
+pub struct CandidatesParser(Vec<String>);
+
+impl TypedValueParser for CandidatesParser {
+    type Value = String;
+
+    fn parse_ref(&self, cmd: &clap::Command, arg: Option<&clap::Arg>, value: &OsStr) -> Result<String, clap::Error> {
+        StringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(self.0.iter().map(|value| PossibleValue::new(value.clone()))))
+    }
+}
+
+/// 配置键路径的补全候选
+pub fn config_key_parser() -> CandidatesParser {
+    CandidatesParser(config_key_paths())
+}
+
+/// 工具名的补全候选
+pub fn tool_name_parser() -> CandidatesParser {
+    let mut names: Vec<String> = default_mcp_tools().into_keys().collect();
+    names.sort();
+    CandidatesParser(names)
+}
+
+/// 从默认配置推导全部叶子键路径（如 mcp_config.tools.docs）
+pub fn config_key_paths() -> Vec<String> {
+    let value = serde_json::to_value(AppConfig::default()).unwrap_or_default();
+    let mut keys = Vec::new();
+    collect_key_paths(&value, String::new(), &mut keys);
+    keys.sort();
+    keys
+}
+
+fn collect_key_paths(value: &serde_json::Value, prefix: String, keys: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                collect_key_paths(child, path, keys);
+            }
+        }
+        _ => {
+            if !prefix.is_empty() {
+                keys.push(prefix);
+            }
+        }
+    }
+}
+
+/// 按点分路径查找配置值
+pub fn lookup_key_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(value, |current, segment| current.get(segment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_scripts_contain_subcommands() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let script = generate(shell).unwrap();
+            assert!(!script.is_empty(), "{} script is empty", shell);
+            for name in ["instances", "completions", "config"] {
+                assert!(script.contains(name), "{} script lacks {}", shell, name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_config_key_paths() {
+        let keys = config_key_paths();
+        assert!(keys.contains(&"mcp_config.tools.docs".to_string()));
+        assert!(keys.contains(&"ui_config.theme".to_string()));
+
+        let value = serde_json::to_value(AppConfig::default()).unwrap();
+        assert!(lookup_key_path(&value, "ui_config.theme").is_some());
+        assert!(lookup_key_path(&value, "ui_config.nope").is_none());
+    }
+
+    #[test]
+    fn test_candidates_complete_without_restricting() {
+        use clap::Parser;
+
+        let script = generate(Shell::Bash).unwrap();
+        assert!(script.contains("mcp_config.tools.docs"));
+        assert!(script.contains("git_context"));
+
+        // Keys outside the defaults, such as tool_limits entries, are looked up at run time
+        let cli = Cli::try_parse_from(["devkit", "config", "get", "mcp_config.tool_limits.docs"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            super::super::Command::Config { command: super::super::ConfigCommand::Get { key } } if key == "mcp_config.tool_limits.docs"
+        ));
+        assert!(Cli::try_parse_from(["devkit", "call", "custom_tool"]).is_ok());
+    }
+}
@@ -0,0 +1,205 @@
+//! `devkit docs fetch`：在终端查询文档
+//!
+//! 与 MCP docs 工具走同一条路径（缓存、离线模式、自动解析）；`-o` 对应工具的 `save_to`，
+//! 路径相对于 `--project`（默认当前目录），连同 front matter 写入文件，已存在时需要 `--overwrite`
+//!
+//! `devkit docs snapshot`：联网获取并存入本地缓存，供离线模式与 `offline_ok` 使用
+
+use anyhow::Result;
+use clap::Args;
+use rmcp::model::{CallToolResult, RawContent};
+use std::path::PathBuf;
+
+use crate::docs_client::{DocType, DocsRequest};
+use crate::mcp::tools::docs::types::{DocsToolRequest, OutputFormat};
+use crate::mcp::tools::DocsTool;
+use crate::mcp::utils::ToolError;
+use crate::utils::locale::Locale;
+
+#[derive(Debug, Args)]
+pub struct FetchArgs {
+    /// Library identifier, e.g. vercel/next.js
+    pub library: String,
+    /// Query topic, e.g. routing
+    #[arg(long)]
+    pub topic: Option<String>,
+    /// Version, e.g. v15.1.8
+    #[arg(long)]
+    pub version: Option<String>,
+    /// Page number (1-10)
+    #[arg(long)]
+    pub page: Option<u32>,
+    /// Fetch up to this many pages starting at --page and merge them (1-10)
+    #[arg(long)]
+    pub max_pages: Option<u32>,
+    /// Documentation type
+    #[arg(long, value_parser = ["code", "info", "auto"])]
+    pub doc_type: Option<String>,
+    /// Return at most this many snippets
+    #[arg(long)]
+    pub max_snippets: Option<u32>,
+    /// Keep the output within about this many tokens (100-100000)
+    #[arg(long)]
+    pub max_tokens: Option<u32>,
+    /// Order snippets by how well they match --topic
+    #[arg(long, requires = "topic")]
+    pub rerank: bool,
+    /// Serve from the local cache only
+    #[arg(long)]
+    pub offline: bool,
+    /// Print the stored snapshot, even an expired one, when the docs service is unavailable
+    #[arg(long, conflicts_with = "offline")]
+    pub offline_ok: bool,
+    /// Skip the cache and fetch again, updating the cached copy
+    #[arg(long, conflicts_with = "offline")]
+    pub refresh: bool,
+    /// Save the Markdown with a front-matter header to this project-relative file instead of printing it
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    /// Replace an existing output file
+    #[arg(long, requires = "output")]
+    pub overwrite: bool,
+    /// Project directory the output path is relative to (defaults to the current directory)
+    #[arg(long, requires = "output")]
+    pub project: Option<PathBuf>,
+    /// Format of the printed result
+    #[arg(long, value_parser = ["markdown", "json", "plain"])]
+    pub format: Option<String>,
+    /// Language of titles, hints and error messages (en or zh)
+    #[arg(long, value_parser = ["en", "zh"])]
+    pub language: Option<String>,
+}
+
+/// 执行查询：保存时打印写入的路径，否则按 `--format` 打印结果（默认 Markdown）
+pub fn fetch(args: FetchArgs) -> Result<()> {
+    let project = match (&args.output, args.project) {
+        (None, _) => None,
+        (Some(_), Some(project)) => Some(project),
+        (Some(_), None) => Some(std::env::current_dir()?),
+    };
+    let doc_type = parse_doc_type(args.doc_type.as_deref());
+    let query_request = DocsRequest {
+        topic: args.topic,
+        version: args.version,
+        page: args.page,
+        max_pages: args.max_pages,
+        doc_type,
+        max_snippets: args.max_snippets,
+        max_tokens: args.max_tokens,
+        rerank: args.rerank.then_some(true),
+        offline: args.offline.then_some(true),
+        offline_ok: args.offline_ok.then_some(true),
+        refresh: args.refresh.then_some(true),
+        ..DocsRequest::new(args.library)
+    };
+    let request = DocsToolRequest {
+        project_root_path: project.map(|p| p.to_string_lossy().into_owned()),
+        save_to: args.output.map(|p| p.to_string_lossy().into_owned()),
+        overwrite: Some(args.overwrite),
+        format: match args.format.as_deref() {
+            Some("json") => Some(OutputFormat::Json),
+            Some("plain") => Some(OutputFormat::Plain),
+            _ => None,
+        },
+        language: args.language.as_deref().and_then(Locale::from_tag),
+        ..query_request.into()
+    };
+    let saving = request.save_to.is_some();
+
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    let (result, text) = query(&runtime, request)?;
+    if result.is_error == Some(true) {
+        anyhow::bail!("{}", text);
+    }
+
+    let saved = result.structured_content.as_ref().and_then(|s| s.get("saved")).filter(|s| !s.is_null());
+    match saved {
+        Some(saved) => println!(
+            "Saved {} ({} bytes)",
+            saved["path"].as_str().unwrap_or_default(),
+            saved["bytes"].as_u64().unwrap_or_default()
+        ),
+        None if saving => anyhow::bail!("Nothing saved: no documentation was returned\n\n{}", text),
+        None => println!("{}", text),
+    }
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+pub struct SnapshotArgs {
+    /// Library identifiers, e.g. vercel/next.js
+    #[arg(required = true)]
+    pub libraries: Vec<String>,
+    /// Query topic, e.g. routing
+    #[arg(long)]
+    pub topic: Option<String>,
+    /// Version, e.g. v15.1.8
+    #[arg(long)]
+    pub version: Option<String>,
+    /// Store up to this many pages of each library (1-10)
+    #[arg(long)]
+    pub max_pages: Option<u32>,
+    /// Documentation type
+    #[arg(long, value_parser = ["code", "info", "auto"])]
+    pub doc_type: Option<String>,
+}
+
+/// 逐个库联网获取（跳过缓存）并写入缓存，打印每个库的结果；有库失败时返回错误
+pub fn snapshot(args: SnapshotArgs) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+    let mut failed = Vec::new();
+    for library in args.libraries {
+        let request = DocsRequest {
+            topic: args.topic.clone(),
+            version: args.version.clone(),
+            max_pages: args.max_pages,
+            doc_type: parse_doc_type(args.doc_type.as_deref()),
+            refresh: Some(true),
+            ..DocsRequest::new(library.clone())
+        };
+        let (result, text) = query(&runtime, request.into())?;
+        let snippets = result
+            .structured_content
+            .as_ref()
+            .and_then(|s| s.get("snippets"))
+            .and_then(|s| s.as_array())
+            .map(Vec::len);
+        match snippets {
+            Some(count) if result.is_error != Some(true) => println!("Stored {} ({} snippets)", library, count),
+            _ => {
+                println!("Not stored {}: {}", library, text.lines().next().unwrap_or_default());
+                failed.push(library);
+            }
+        }
+    }
+    if !failed.is_empty() {
+        anyhow::bail!("No snapshot stored for {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+fn parse_doc_type(doc_type: Option<&str>) -> Option<DocType> {
+    match doc_type {
+        Some("info") => Some(DocType::Info),
+        Some("auto") => Some(DocType::Auto),
+        Some(_) => Some(DocType::Code),
+        None => None,
+    }
+}
+
+/// 执行一次查询，返回结果与其中的文本
+fn query(runtime: &tokio::runtime::Runtime, request: DocsToolRequest) -> Result<(CallToolResult, String)> {
+    let result = runtime.block_on(DocsTool::query_docs(request)).map_err(|e| match e {
+        ToolError::Rpc(error) => anyhow::anyhow!(error.message),
+        ToolError::Failed(error) => error,
+    })?;
+    let text: String = result
+        .content
+        .iter()
+        .filter_map(|content| match &content.raw {
+            RawContent::Text(text) => Some(text.text.as_str()),
+            _ => None,
+        })
+        .collect();
+    Ok((result, text))
+}
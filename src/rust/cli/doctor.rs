@@ -0,0 +1,153 @@
+//! `devkit doctor`：检查本地安装状态
+//!
+//! 目前检查配置文件能否解析，以及是否有之前隔离的损坏配置待处理；
+//! 同时列出生效的 docs 上游 HTTP 客户端参数、User-Agent、附加请求头与离线模式状态；
+//! 开启 `docs_contract_preflight` 时还会请求一次 Docs API，检查返回字段是否与解析器预期一致；
+//! 配置了 API 密钥时逐个验证，过期或被拒绝的密钥计入问题数；
+//! 最后列出启动预检对配置、状态、缓存目录的判定（与 /health 相同），不可写的目录计入问题数；
+//! 旧版文件迁移时新旧内容不同的文件也计入问题数
+
+use anyhow::Result;
+
+use crate::config::{get_standalone_config_path, load_standalone_config, offline_mode};
+use crate::config::migration;
+use crate::config::recovery::{check_config_file, quarantined_configs};
+use crate::docs_client::contract::ContractStatus;
+use crate::docs_client::{Client, DocsConfig};
+use crate::utils::paths::preflight;
+
+/// 执行检查并打印结果，发现问题时返回错误（退出码非 0）
+pub fn run() -> Result<()> {
+    let config_path = get_standalone_config_path()?;
+    let mut problems = 0;
+
+    println!("Config file: {}", config_path.display());
+    let config_check = check_config_file(&config_path);
+    match &config_check {
+        Ok(()) if config_path.exists() => println!("  ok"),
+        Ok(()) => println!("  not found, defaults are used"),
+        Err(e) => {
+            problems += 1;
+            println!("  error: failed to parse ({})", e);
+            println!("  the next start will move it aside and use defaults (--strict-config to refuse)");
+        }
+    }
+
+    let quarantined = quarantined_configs(&config_path);
+    if !quarantined.is_empty() {
+        problems += quarantined.len();
+        println!("Quarantined config files:");
+        for path in &quarantined {
+            println!("  {}", path.display());
+        }
+        println!("  these were corrupted and replaced by defaults; restore your settings from them, then delete them");
+    }
+
+    // 配置损坏时不加载（加载会触发隔离），只在能解析时显示生效设置
+    if config_check.is_ok() {
+        if let Ok(config) = load_standalone_config() {
+            let docs = config.mcp_config.docs_client_config();
+            println!("Docs HTTP client: {}", docs.http);
+            println!("Docs User-Agent: {}", docs.effective_user_agent());
+            println!("Docs extra headers: {}", docs.describe_headers());
+            if let Err(e) = docs.upstream_headers() {
+                problems += 1;
+                println!("  error: {}", e);
+            }
+            if !docs.keys().is_empty() {
+                problems += check_docs_keys(docs.clone());
+            }
+            if config.mcp_config.docs_contract_preflight {
+                check_docs_contract(docs);
+            }
+        }
+        println!("Offline mode: {}", offline_mode());
+    }
+
+    let preflight = preflight();
+    println!("Directories:");
+    for check in &preflight.directories {
+        let path = check.path.as_ref().map_or_else(|| "(unknown)".to_string(), |p| p.display().to_string());
+        match &check.error {
+            Some(error) => println!("  {:<6} {}  {} ({})", check.dir.name(), path, check.status, error),
+            None => println!("  {:<6} {}  {}", check.dir.name(), path, check.status),
+        }
+        if !check.status.is_writable() {
+            problems += 1;
+        }
+    }
+    if let Some(warning) = preflight.warning() {
+        println!("  {}", warning);
+    }
+
+    // 没有旧版文件时不显示
+    if let Some(report) = migration::last_report().filter(|r| !r.copied.is_empty() || !r.conflicts.is_empty()) {
+        println!("Legacy files ({}):", report.legacy_dir.display());
+        println!("  {} copied on {}", report.copied.len(), report.migrated_at.format("%Y-%m-%d %H:%M"));
+        for conflict in &report.conflicts {
+            problems += 1;
+            println!("  conflict: {} differs from {}; the latter is used", conflict.from.display(), conflict.to.display());
+        }
+        if !report.conflicts.is_empty() {
+            println!("  merge any settings you still need from the old files, then delete them");
+        }
+    }
+
+    if problems > 0 {
+        anyhow::bail!("found {} problem(s)", problems);
+    }
+    println!("No problems found");
+    Ok(())
+}
+
+/// 验证每个 API 密钥，返回过期或被拒绝的密钥数；无法连接时只提示
+fn check_docs_keys(docs: DocsConfig) -> usize {
+    println!("Docs API keys:");
+    if let Err(e) = offline_mode().check("docs API key check") {
+        println!("  skipped ({})", e);
+        return 0;
+    }
+    let report = Client::new(docs).map_err(|e| e.to_string()).and_then(|client| {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| e.to_string())?;
+        Ok(runtime.block_on(client.check_keys()))
+    });
+    match report {
+        Ok(report) => {
+            for check in &report.keys {
+                println!("  {}  {}", check.key, check.health);
+            }
+            let problems = report.problems().count();
+            if problems > 0 {
+                println!("  replace or remove the keys above in mcp_config.docs_api_key / docs_api_keys");
+            }
+            problems
+        }
+        Err(error) => {
+            println!("  not checked ({})", error);
+            0
+        }
+    }
+}
+
+/// 上游接口偏差不是本地安装问题，只提示、不计入问题数
+fn check_docs_contract(docs: DocsConfig) {
+    print!("Docs API contract: ");
+    if let Err(e) = offline_mode().check("docs API contract check") {
+        println!("skipped ({})", e);
+        return;
+    }
+    let report = Client::new(docs).map_err(|e| e.to_string()).and_then(|client| {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| e.to_string())?;
+        Ok(runtime.block_on(client.check_contract()))
+    });
+    match report.map(|report| report.status) {
+        Ok(ContractStatus::Ok) => println!("ok"),
+        Ok(ContractStatus::Drift { missing }) => {
+            println!("warning: API drift detected");
+            for field in missing {
+                println!("  missing field {}", field);
+            }
+        }
+        Ok(ContractStatus::Unreachable { error }) | Err(error) => println!("not checked ({})", error),
+    }
+}
@@ -0,0 +1,237 @@
+//! `devkit` 命令行工具
+//!
+//! 提供无界面环境下使用的子命令（实例列表、补全脚本、配置查询等）
+
+pub mod call;
+pub mod completions;
+#[cfg(feature = "docs")]
+pub mod docs;
+pub mod doctor;
+pub mod prompt;
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use clap_complete::Shell;
+use std::path::PathBuf;
+
+use crate::docs_client::cache::DocsCache;
+use crate::docs_client::Transport;
+use crate::mcp::http::{format_instances, list_instances};
+
+#[derive(Debug, Parser)]
+#[command(name = "devkit", version, about = "Development Toolkit CLI")]
+pub struct Cli {
+    /// Fail instead of falling back to defaults when the config file is corrupted
+    #[arg(long, global = true)]
+    pub strict_config: bool,
+
+    /// Write every upstream docs request and response to numbered JSON files in DIR
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer upstream docs requests from a recording made with --record instead of the network
+    #[arg(long, global = true, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// List running devkit-mcp-http instances
+    Instances,
+    /// Generate shell completion scripts
+    Completions {
+        /// Target shell
+        shell: Shell,
+        /// Install into the conventional per-shell location instead of printing
+        #[arg(long)]
+        install: bool,
+    },
+    /// Check the local installation for problems
+    Doctor,
+    /// Inspect configuration values
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Look up library documentation
+    #[cfg(feature = "docs")]
+    Docs {
+        #[command(subcommand)]
+        command: DocsCommand,
+    },
+    /// Inspect or clear the docs cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+    /// Manage project memories
+    Memory {
+        #[command(subcommand)]
+        command: MemoryCommand,
+    },
+    /// Run one tool call through the MCP server and print the result as JSON
+    ///
+    /// Exits with 1 when the tool reports an error and 2 when the call is
+    /// rejected (e.g. invalid arguments).
+    Call {
+        /// Tool name, e.g. docs
+        #[arg(value_parser = completions::tool_name_parser(), hide_possible_values = true)]
+        tool: String,
+        /// JSON object with the tool arguments, `-` for stdin; defaults to
+        /// stdin when it is not a terminal, otherwise no arguments
+        #[arg(long)]
+        args_file: Option<PathBuf>,
+    },
+    /// Describe every enabled tool, its limits and the error codes as JSON
+    Manifest {
+        /// Render as Markdown instead of JSON
+        #[arg(long)]
+        markdown: bool,
+    },
+    /// Answer an interaction request in the terminal instead of the popup
+    Prompt {
+        /// Request file, as passed to devkit-ui --mcp-request
+        request: PathBuf,
+        /// Write the response here instead of stdout
+        #[arg(long)]
+        response_file: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MemoryCommand {
+    /// Merge the project's memories with a store file shared between machines
+    Sync {
+        /// Sync file, or a folder to keep devkit-memory.json in
+        path: PathBuf,
+        /// Project directory (defaults to the current directory)
+        #[arg(long)]
+        project: Option<PathBuf>,
+    },
+}
+
+#[cfg(feature = "docs")]
+#[derive(Debug, Subcommand)]
+pub enum DocsCommand {
+    /// Fetch docs and print them, or save them with -o, e.g.
+    /// `devkit docs fetch vercel/next.js --topic routing -o docs/nextjs-routing.md`
+    Fetch(docs::FetchArgs),
+    /// Fetch docs from the network and store them for offline use (served with
+    /// `offline` or, when the service is down, `offline_ok`), e.g.
+    /// `devkit docs snapshot vercel/next.js facebook/react --max-pages 3`
+    Snapshot(docs::SnapshotArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommand {
+    /// Show entry counts, size, hit ratio and the oldest entry
+    Stats,
+    /// Remove all cached docs, or only those of one library
+    Clear {
+        /// Library identifier, e.g. vercel/next.js
+        #[arg(long)]
+        library: Option<String>,
+    },
+    /// Zero the hit/miss counters
+    Reset,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the value at a dotted key path, e.g. mcp_config.tools.docs
+    Get {
+        #[arg(value_parser = completions::config_key_parser(), hide_possible_values = true)]
+        key: String,
+    },
+    /// Print the config file location
+    Path,
+}
+
+/// 解析命令行参数并执行
+pub fn run() -> Result<()> {
+    let cli = Cli::parse();
+    execute(cli)
+}
+
+fn execute(cli: Cli) -> Result<()> {
+    crate::config::set_strict_config(cli.strict_config);
+    if let Some(dir) = &cli.record {
+        Transport::set_default(Transport::record(dir)?);
+    }
+    if let Some(dir) = &cli.replay {
+        Transport::set_default(Transport::replay(dir)?);
+    }
+
+    match cli.command {
+        Command::Instances => {
+            let instances = list_instances()?;
+            print!("{}", format_instances(&instances));
+        }
+        Command::Completions { shell, install } => {
+            if install {
+                let path = completions::install(shell)?;
+                println!("Installed {} completions to {}", shell, path.display());
+            } else {
+                completions::write(shell, &mut std::io::stdout())?;
+            }
+        }
+        Command::Doctor => doctor::run()?,
+        #[cfg(feature = "docs")]
+        Command::Docs { command } => match command {
+            DocsCommand::Fetch(args) => docs::fetch(args)?,
+            DocsCommand::Snapshot(args) => docs::snapshot(args)?,
+        },
+        Command::Cache { command } => {
+            let cache = DocsCache::shared();
+            match command {
+                CacheCommand::Stats => print!("{}", cache.stats()),
+                CacheCommand::Clear { library } => println!("{}", cache.clear(library.as_deref())),
+                CacheCommand::Reset => {
+                    cache.reset_counters();
+                    println!("Cache hit/miss counters reset");
+                }
+            }
+        }
+        Command::Memory { command } => match command {
+            MemoryCommand::Sync { path, project } => {
+                let project = match project {
+                    Some(project) => project,
+                    None => std::env::current_dir()?,
+                };
+                let manager = crate::mcp::tools::memory::MemoryManager::new(&project.to_string_lossy())?;
+                println!("{}", crate::mcp::tools::memory::sync_store(&manager, &path)?);
+            }
+        },
+        Command::Call { tool, args_file } => {
+            let code = call::run(&tool, args_file.as_deref())?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Command::Manifest { markdown } => {
+            let manifest = crate::mcp::manifest::Manifest::build(&crate::mcp::DevkitServer::new());
+            if markdown {
+                print!("{}", manifest.to_markdown());
+            } else {
+                println!("{}", serde_json::to_string_pretty(&manifest)?);
+            }
+        }
+        Command::Prompt { request, response_file } => prompt::run(&request, response_file.as_deref())?,
+        Command::Config { command } => match command {
+            ConfigCommand::Get { key } => {
+                let config = crate::config::load_standalone_config()?;
+                let value = serde_json::to_value(&config)?;
+                let found = completions::lookup_key_path(&value, &key)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown config key: {}", key))?;
+                println!("{}", serde_json::to_string_pretty(found)?);
+            }
+            ConfigCommand::Path => {
+                println!("{}", crate::config::get_standalone_config_path()?.display());
+            }
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,251 @@
+//! 终端中的交互弹窗
+//!
+//! `devkit prompt <request.json>` 读取与 `devkit-ui --mcp-request` 相同的请求文件，
+//! 在终端中展示消息、diff 与选项，按行读取回复（行内编辑由终端提供），
+//! 结果写到标准输出或 `--response-file`。提示信息全部输出到标准错误。
+
+use anyhow::Result;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::mpsc;
+
+use crate::mcp::tools::interaction::diff::DiffLineKind;
+use crate::mcp::tools::interaction::keys::{map_line, LineAction};
+use crate::mcp::types::{build_refill_response, build_serve_response, PopupRequest};
+
+const HELP: &str = "\
+Keys:
+  <text>          add a line to the reply
+  1 3 / 1,3       toggle options (before any reply text)
+  .   or Ctrl+D   send
+  :continue       reply with the configured continue prompt
+  Esc, :q, Ctrl+C cancel (asks for confirmation)
+  ?               show this help
+";
+
+/// 终端输入事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptEvent {
+    Line(String),
+    /// Ctrl+D 或输入结束
+    Eof,
+    /// Ctrl+C
+    Interrupt,
+}
+
+/// 会话结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptOutcome {
+    Submit { note: Option<String>, toppings: Vec<String> },
+    Continue,
+    Cancel,
+}
+
+pub fn run(request_file: &Path, response_file: Option<&Path>) -> Result<()> {
+    let content = std::fs::read_to_string(request_file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", request_file.display(), e))?;
+    let request: PopupRequest = serde_json::from_str(&content)?;
+
+    let events = spawn_input();
+    let outcome = run_session(&request, events.iter(), &mut std::io::stderr())?;
+
+    let response = match outcome {
+        PromptOutcome::Submit { note, toppings } => {
+            build_serve_response(note, toppings, vec![], Some(request.id.clone()), "terminal")
+        }
+        PromptOutcome::Continue => build_refill_response(Some(request.id.clone()), "terminal_continue"),
+        PromptOutcome::Cancel => "CANCELLED".to_string(),
+    };
+    match response_file {
+        Some(path) => std::fs::write(path, response)?,
+        None => println!("{}", response),
+    }
+    Ok(())
+}
+
+/// 在后台线程读取标准输入并监听 Ctrl+C
+fn spawn_input() -> mpsc::Receiver<PromptEvent> {
+    let (tx, rx) = mpsc::channel();
+
+    let lines = tx.clone();
+    std::thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let Ok(line) = line else { break };
+            if lines.send(PromptEvent::Line(line)).is_err() {
+                return;
+            }
+        }
+        let _ = lines.send(PromptEvent::Eof);
+    });
+
+    std::thread::spawn(move || {
+        let Ok(runtime) = tokio::runtime::Builder::new_current_thread().enable_all().build() else {
+            return;
+        };
+        while runtime.block_on(tokio::signal::ctrl_c()).is_ok() {
+            if tx.send(PromptEvent::Interrupt).is_err() {
+                return;
+            }
+        }
+    });
+
+    rx
+}
+
+/// 处理输入事件直到发送、继续或确认取消
+pub fn run_session(
+    request: &PopupRequest,
+    mut events: impl Iterator<Item = PromptEvent>,
+    out: &mut impl Write,
+) -> Result<PromptOutcome> {
+    let options = request.menu.clone().unwrap_or_default();
+    let mut selected = vec![false; options.len()];
+    let mut text: Vec<String> = Vec::new();
+
+    render_request(request, out)?;
+    render_options(&options, &selected, out)?;
+    writeln!(out, "Type your reply, '.' on its own line to send, '?' for help.")?;
+
+    loop {
+        let has_content = text.iter().any(|line| !line.trim().is_empty()) || selected.contains(&true);
+        let submit = |text: &[String], selected: &[bool]| PromptOutcome::Submit {
+            note: Some(text.join("\n").trim().to_string()).filter(|note| !note.is_empty()),
+            toppings: options.iter().zip(selected).filter(|(_, on)| **on).map(|(o, _)| o.clone()).collect(),
+        };
+
+        let action = match events.next() {
+            // 输入已结束，无法再确认：有内容就发送，避免丢失
+            None | Some(PromptEvent::Eof) if has_content => return Ok(submit(&text, &selected)),
+            None | Some(PromptEvent::Eof) => return Ok(PromptOutcome::Cancel),
+            Some(PromptEvent::Interrupt) => LineAction::RequestCancel,
+            Some(PromptEvent::Line(line)) => map_line(&line, options.len(), !text.is_empty()),
+        };
+
+        match action {
+            LineAction::Submit if has_content => return Ok(submit(&text, &selected)),
+            LineAction::Submit => writeln!(out, "Nothing to send yet; type a reply or pick an option.")?,
+            LineAction::Continue => return Ok(PromptOutcome::Continue),
+            LineAction::ShowHelp => write!(out, "{}", HELP)?,
+            LineAction::ToggleOptions(picks) => {
+                for pick in picks {
+                    selected[pick] = !selected[pick];
+                }
+                render_options(&options, &selected, out)?;
+            }
+            LineAction::Text(line) => text.push(line),
+            LineAction::RequestCancel => {
+                if !has_content {
+                    return Ok(PromptOutcome::Cancel);
+                }
+                write!(out, "Discard your reply and cancel? [y/N] ")?;
+                out.flush()?;
+                match events.next() {
+                    Some(PromptEvent::Line(answer)) if matches!(answer.trim(), "y" | "Y" | "yes") => {
+                        return Ok(PromptOutcome::Cancel)
+                    }
+                    Some(PromptEvent::Interrupt) => return Ok(PromptOutcome::Cancel),
+                    None | Some(PromptEvent::Eof) => return Ok(submit(&text, &selected)),
+                    Some(PromptEvent::Line(_)) => writeln!(out, "Keep editing.")?,
+                }
+            }
+        }
+    }
+}
+
+fn render_request(request: &PopupRequest, out: &mut impl Write) -> Result<()> {
+    writeln!(out, "{}\n", request.message.trim_end())?;
+
+    let Some(diff) = &request.diff else { return Ok(()) };
+    if let Some(plain) = &diff.plain {
+        writeln!(out, "{}", plain.trim_end())?;
+    }
+    for file in &diff.files {
+        let path = file.new_path.as_deref().or(file.old_path.as_deref()).unwrap_or("(unnamed)");
+        writeln!(out, "--- {} (+{} -{})", path, file.additions, file.deletions)?;
+        for hunk in &file.hunks {
+            writeln!(out, "{}", hunk.header)?;
+            for line in &hunk.lines {
+                let prefix = match line.kind {
+                    DiffLineKind::Added => "+",
+                    DiffLineKind::Removed => "-",
+                    DiffLineKind::Context => " ",
+                    DiffLineKind::NoNewline => "",
+                };
+                writeln!(out, "{}{}", prefix, line.text)?;
+            }
+        }
+    }
+    if diff.shown_lines < diff.total_lines {
+        writeln!(out, "(diff truncated: showing {} of {} lines)", diff.shown_lines, diff.total_lines)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+fn render_options(options: &[String], selected: &[bool], out: &mut impl Write) -> Result<()> {
+    for (i, (option, on)) in options.iter().zip(selected).enumerate() {
+        writeln!(out, "  [{}] {}. {}", if *on { "x" } else { " " }, i + 1, option)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(menu: &[&str]) -> PopupRequest {
+        PopupRequest {
+            id: "req-1".to_string(),
+            message: "Proceed?".to_string(),
+            menu: Some(menu.iter().map(|s| s.to_string()).collect()),
+            chalkboard: false,
+            project_root_path: None,
+            diff: None,
+        }
+    }
+
+    fn session(menu: &[&str], events: Vec<PromptEvent>) -> (PromptOutcome, String) {
+        let mut out = Vec::new();
+        let outcome = run_session(&request(menu), events.into_iter(), &mut out).unwrap();
+        (outcome, String::from_utf8(out).unwrap())
+    }
+
+    fn line(s: &str) -> PromptEvent {
+        PromptEvent::Line(s.to_string())
+    }
+
+    #[test]
+    fn test_pick_options_and_send() {
+        let (outcome, out) = session(&["Yes", "No", "Later"], vec![line("1,3"), line("looks fine"), line("3"), line(".")]);
+        assert_eq!(
+            outcome,
+            PromptOutcome::Submit { note: Some("looks fine\n3".to_string()), toppings: vec!["Yes".to_string(), "Later".to_string()] }
+        );
+        assert!(out.contains("[x] 1. Yes"));
+    }
+
+    #[test]
+    fn test_cancel_requires_confirmation_when_there_is_input() {
+        let (outcome, out) = session(&[], vec![line("draft"), PromptEvent::Interrupt, line("n"), line(":q"), line("y")]);
+        assert_eq!(outcome, PromptOutcome::Cancel);
+        assert_eq!(out.matches("Discard your reply").count(), 2);
+
+        // 没有输入时直接取消
+        let (outcome, _) = session(&["A"], vec![line("\u{1b}")]);
+        assert_eq!(outcome, PromptOutcome::Cancel);
+    }
+
+    #[test]
+    fn test_eof_never_drops_input() {
+        let (outcome, _) = session(&[], vec![line("keep me"), PromptEvent::Eof]);
+        assert_eq!(outcome, PromptOutcome::Submit { note: Some("keep me".to_string()), toppings: vec![] });
+
+        let (outcome, _) = session(&[], vec![line("keep me"), line(":q")]);
+        assert_eq!(outcome, PromptOutcome::Submit { note: Some("keep me".to_string()), toppings: vec![] });
+
+        let (outcome, out) = session(&[], vec![line("."), PromptEvent::Eof]);
+        assert_eq!(outcome, PromptOutcome::Cancel);
+        assert!(out.contains("Nothing to send yet"));
+    }
+}
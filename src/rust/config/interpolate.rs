@@ -0,0 +1,193 @@
+// 配置值中的环境变量替换
+// 反序列化后对所有字符串字段做替换，便于在共享配置里写 "${CTX7_KEY}" 而把密钥留在环境变量中。
+// 语法：${VAR}、${VAR:-默认值}（未设置或为空时使用默认值），$${ 表示字面量 ${。
+// 只在独立进程（MCP 服务器、CLI）加载配置时替换；GUI 保留原文，避免保存设置时把密钥写回文件
+
+use serde_json::Value;
+
+use super::settings::AppConfig;
+
+/// 替换失败的原因
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InterpolationError {
+    #[error("配置项 {field} 引用的环境变量 {variable} 未设置（可写成 ${{{variable}:-默认值}} 提供默认值）")]
+    Unset { field: String, variable: String },
+
+    #[error("配置项 {field} 中的变量引用无效: {message}")]
+    Syntax { field: String, message: String },
+
+    #[error("替换环境变量后配置无效: {0}")]
+    Invalid(String),
+}
+
+/// 使用当前进程的环境变量替换
+pub fn interpolate_config(config: AppConfig) -> Result<AppConfig, InterpolationError> {
+    interpolate_config_with(config, |name| std::env::var(name).ok())
+}
+
+/// 使用给定的变量来源替换（测试用）
+pub fn interpolate_config_with(
+    config: AppConfig,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<AppConfig, InterpolationError> {
+    let mut value = serde_json::to_value(&config).map_err(|e| InterpolationError::Invalid(e.to_string()))?;
+    if !interpolate_value(&mut value, "", &env)? {
+        // 没有任何引用时原样返回，不做序列化往返
+        return Ok(config);
+    }
+    serde_json::from_value(value).map_err(|e| InterpolationError::Invalid(e.to_string()))
+}
+
+/// 递归替换，返回是否有字段被改动
+fn interpolate_value(
+    value: &mut Value,
+    field: &str,
+    env: &impl Fn(&str) -> Option<String>,
+) -> Result<bool, InterpolationError> {
+    match value {
+        Value::String(text) if text.contains('$') => {
+            let replaced = interpolate_str(text, field, env)?;
+            let changed = replaced != *text;
+            *text = replaced;
+            Ok(changed)
+        }
+        Value::Array(items) => {
+            let mut changed = false;
+            for (i, item) in items.iter_mut().enumerate() {
+                changed |= interpolate_value(item, &format!("{}[{}]", field, i), env)?;
+            }
+            Ok(changed)
+        }
+        Value::Object(map) => {
+            let mut changed = false;
+            for (key, item) in map.iter_mut() {
+                let path = if field.is_empty() { key.clone() } else { format!("{}.{}", field, key) };
+                changed |= interpolate_value(item, &path, env)?;
+            }
+            Ok(changed)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// 替换单个字符串中的引用
+pub fn interpolate_str(
+    text: &str,
+    field: &str,
+    env: &impl Fn(&str) -> Option<String>,
+) -> Result<String, InterpolationError> {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if let Some(after) = tail.strip_prefix("$${") {
+            output.push_str("${");
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("${") {
+            let end = after.find('}').ok_or_else(|| InterpolationError::Syntax {
+                field: field.to_string(),
+                message: format!("'{}' 缺少右花括号", tail),
+            })?;
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(InterpolationError::Syntax {
+                    field: field.to_string(),
+                    message: format!("'{}' 不是合法的变量名", name),
+                });
+            }
+
+            let resolved = match (env(name), default) {
+                (Some(value), Some(default)) if value.is_empty() => default.to_string(),
+                (Some(value), _) => value,
+                (None, Some(default)) => default.to_string(),
+                (None, None) => {
+                    return Err(InterpolationError::Unset { field: field.to_string(), variable: name.to_string() })
+                }
+            };
+            output.push_str(&resolved);
+            rest = &after[end + 1..];
+        } else {
+            output.push('$');
+            rest = &tail[1..];
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_nested_fields_are_replaced() {
+        let mut config = AppConfig::default();
+        config.mcp_config.docs_api_key = Some("${CTX7_KEY}".to_string());
+        config.mcp_config.docs_api_keys = vec!["plain".to_string(), "team-${TEAM}-key".to_string()];
+
+        let config = interpolate_config_with(config, env(&[("CTX7_KEY", "secret-1"), ("TEAM", "web")])).unwrap();
+        assert_eq!(config.mcp_config.docs_api_key.as_deref(), Some("secret-1"));
+        assert_eq!(config.mcp_config.docs_api_keys, vec!["plain", "team-web-key"]);
+    }
+
+    #[test]
+    fn test_defaults() {
+        let env = env(&[("EMPTY", ""), ("SET", "value")]);
+        assert_eq!(interpolate_str("${MISSING:-fallback}", "f", &env).unwrap(), "fallback");
+        assert_eq!(interpolate_str("${EMPTY:-fallback}", "f", &env).unwrap(), "fallback");
+        assert_eq!(interpolate_str("${SET:-fallback}", "f", &env).unwrap(), "value");
+        assert_eq!(interpolate_str("${MISSING:-}", "f", &env).unwrap(), "");
+        // 未带默认值时，设置为空也算已设置
+        assert_eq!(interpolate_str("[${EMPTY}]", "f", &env).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_escapes_and_plain_dollars() {
+        let env = env(&[("HOME", "/home/me")]);
+        assert_eq!(interpolate_str("$${HOME}", "f", &env).unwrap(), "${HOME}");
+        assert_eq!(interpolate_str("a $$ b", "f", &env).unwrap(), "a $$ b");
+        assert_eq!(interpolate_str("costs $5 at ${HOME}", "f", &env).unwrap(), "costs $5 at /home/me");
+        assert_eq!(interpolate_str("trailing $", "f", &env).unwrap(), "trailing $");
+    }
+
+    #[test]
+    fn test_error_names_field_and_variable() {
+        let mut config = AppConfig::default();
+        config.mcp_config.docs_api_keys = vec!["ok".to_string(), "${CTX7_KEY_2}".to_string()];
+        let err = interpolate_config_with(config, env(&[])).unwrap_err();
+        assert_eq!(
+            err,
+            InterpolationError::Unset {
+                field: "mcp_config.docs_api_keys[1]".to_string(),
+                variable: "CTX7_KEY_2".to_string(),
+            }
+        );
+        let message = err.to_string();
+        assert!(message.contains("mcp_config.docs_api_keys[1]") && message.contains("CTX7_KEY_2"));
+        assert!(message.contains("${CTX7_KEY_2:-默认值}"));
+
+        let err = interpolate_str("${UNCLOSED", "a.b", &env(&[])).unwrap_err();
+        assert!(matches!(err, InterpolationError::Syntax { ref field, .. } if field == "a.b"));
+        assert!(interpolate_str("${BAD NAME}", "a.b", &env(&[])).is_err());
+    }
+
+    #[test]
+    fn test_config_without_references_is_untouched() {
+        let config = AppConfig::default();
+        let expected = serde_json::to_value(&config).unwrap();
+        let config = interpolate_config_with(config, env(&[])).unwrap();
+        assert_eq!(serde_json::to_value(&config).unwrap(), expected);
+    }
+}
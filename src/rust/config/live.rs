@@ -0,0 +1,230 @@
+// 配置热加载
+// MCP 服务进程持有一份当前配置并监听配置文件，文件变化（编辑器或 GUI 保存）后重新读取并替换：
+// 工具开关、Docs API 密钥、频率与并发上限对之后的调用立即生效，log_level 立即调整日志级别。
+// 新内容无法解析时保留当前配置并记录警告（不隔离文件），改正后再次保存即可；
+// reload_config 工具可显式触发一次重新加载；监听没有启动时，工具开关每次调用都直接读取配置文件。
+// 监听地址、访问令牌、命名端点与链路导出只在启动时读取，修改后需重启服务。
+
+use anyhow::{anyhow, Result};
+use log::LevelFilter;
+use notify_debouncer_full::{
+    new_debouncer,
+    notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher},
+    DebounceEventResult, Debouncer, FileIdMap,
+};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::settings::{AppConfig, McpConfig};
+use super::storage::{get_standalone_config_path, load_standalone_config, reload_standalone_config};
+use crate::utils::set_log_level;
+use crate::{log_debug, log_important};
+
+/// 文件变化后等待多久再读取，合并编辑器保存时的多次写入
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 修改后需要重启服务才生效的 mcp_config 配置项
+pub const RESTART_REQUIRED: &[&str] = &["endpoints", "http_bind", "http_token", "telemetry"];
+
+static CURRENT: Lazy<RwLock<Arc<AppConfig>>> = Lazy::new(|| RwLock::new(Arc::new(initial_config())));
+
+/// 正在运行的配置文件监听数
+static WATCHERS: AtomicUsize = AtomicUsize::new(0);
+
+fn initial_config() -> AppConfig {
+    load_standalone_config().unwrap_or_else(|e| {
+        log_important!(warn, "加载配置失败，使用默认配置: {}", e);
+        AppConfig::default()
+    })
+}
+
+/// 当前配置；首次调用时读取配置文件
+pub fn current() -> Arc<AppConfig> {
+    CURRENT.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 是否有配置文件监听在运行，即文件修改会自动反映到 [`current`]
+pub fn is_watching() -> bool {
+    WATCHERS.load(Ordering::SeqCst) > 0
+}
+
+/// 反映配置文件最新内容的配置：有监听时即当前配置，否则重新读取文件；读取或解析失败时退回当前配置
+pub fn latest() -> Arc<AppConfig> {
+    latest_from(is_watching(), reload_standalone_config)
+}
+
+fn latest_from(watching: bool, read: impl FnOnce() -> Result<AppConfig>) -> Arc<AppConfig> {
+    if watching {
+        return current();
+    }
+    match read() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            log_debug!("读取配置文件失败，使用当前配置: {}", e);
+            current()
+        }
+    }
+}
+
+/// 一次重新加载的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigReload {
+    /// 取值有变化的 mcp_config 配置项（按名称排序）
+    pub changed: Vec<String>,
+    /// 其中需要重启服务才生效的项
+    pub restart_required: Vec<String>,
+    /// 重新加载后的日志级别
+    pub log_level: String,
+}
+
+/// 重新读取配置文件并替换当前配置；解析失败时保留当前配置并返回错误
+pub fn reload() -> Result<ConfigReload> {
+    let config = reload_standalone_config()?;
+
+    let mut current = CURRENT.write().unwrap_or_else(|e| e.into_inner());
+    let changed = changed_keys(&current.mcp_config, &config.mcp_config);
+    let level = apply_log_level(&config.mcp_config);
+    *current = Arc::new(config);
+    drop(current);
+
+    let restart_required = changed.iter().filter(|key| RESTART_REQUIRED.contains(&key.as_str())).cloned().collect();
+    Ok(ConfigReload { changed, restart_required, log_level: level.to_string().to_lowercase() })
+}
+
+/// 重新加载并记录结果，文件监听与 reload_config 工具共用
+pub fn reload_and_log(trigger: &str) -> Result<ConfigReload> {
+    match reload() {
+        Ok(report) => {
+            if report.changed.is_empty() {
+                log_debug!("{}，配置内容没有变化", trigger);
+            } else {
+                log_important!(info, "{}，已重新加载配置: {}", trigger, report.changed.join(", "));
+            }
+            if !report.restart_required.is_empty() {
+                log_important!(warn, "以下配置项需要重启服务才会生效: {}", report.restart_required.join(", "));
+            }
+            Ok(report)
+        }
+        Err(e) => {
+            log_important!(warn, "{}，但重新加载失败，继续使用当前配置: {}", trigger, e);
+            Err(e)
+        }
+    }
+}
+
+/// 配置文件监听句柄，丢弃后停止监听
+pub struct ConfigWatcher {
+    _debouncer: Debouncer<RecommendedWatcher, FileIdMap>,
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        WATCHERS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 应用当前配置中的日志级别，并开始监听配置文件
+///
+/// 监听的是配置文件所在目录：GUI 与多数编辑器通过改名替换文件，只监听文件本身会在第一次保存后失效。
+pub fn watch() -> Result<ConfigWatcher> {
+    apply_log_level(&current().mcp_config);
+
+    let config_path = get_standalone_config_path()?;
+    let dir = config_path
+        .parent()
+        .ok_or_else(|| anyhow!("配置文件没有所在目录: {}", config_path.display()))?
+        .to_path_buf();
+    let file_name = config_path.file_name().map(|name| name.to_os_string());
+
+    let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| match result {
+        Ok(events) => {
+            // 只关心配置文件本身的写入、创建与删除；读取（包括本进程的读取）不算变化
+            let touched = events.iter().any(|event| {
+                matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_))
+                    && event.paths.iter().any(|path| path.file_name() == file_name.as_deref())
+            });
+            if touched {
+                let _ = reload_and_log("配置文件已变化");
+            }
+        }
+        Err(errors) => log_debug!("配置文件监听错误: {:?}", errors),
+    })?;
+    debouncer.watcher().watch(&dir, RecursiveMode::NonRecursive)?;
+    log_important!(info, "监听配置文件变化: {}", config_path.display());
+
+    WATCHERS.fetch_add(1, Ordering::SeqCst);
+    Ok(ConfigWatcher { _debouncer: debouncer })
+}
+
+/// 按 log_level 调整日志级别，未设置时恢复启动时的级别；设置了 RUST_LOG 时以环境变量为准
+fn apply_log_level(config: &McpConfig) -> LevelFilter {
+    if std::env::var_os("RUST_LOG").is_some() {
+        return log::max_level();
+    }
+    set_log_level(config.log_level.as_deref().and_then(parse_log_level))
+}
+
+fn parse_log_level(level: &str) -> Option<LevelFilter> {
+    match level.trim().parse::<LevelFilter>() {
+        Ok(level) => Some(level),
+        Err(_) => {
+            log_important!(warn, "无法识别的 log_level {:?}（可选 off、error、warn、info、debug、trace），保持启动时的级别", level);
+            None
+        }
+    }
+}
+
+/// 两份配置中取值不同的 mcp_config 配置项
+fn changed_keys(old: &McpConfig, new: &McpConfig) -> Vec<String> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut keys: Vec<String> = old.keys().chain(new.keys()).filter(|key| old.get(*key) != new.get(*key)).cloned().collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{default_mcp_config, ToolLimitConfig};
+
+    #[test]
+    fn test_changed_keys() {
+        let old = default_mcp_config();
+        assert!(changed_keys(&old, &old.clone()).is_empty());
+
+        let mut new = old.clone();
+        new.tools.insert("run_command".to_string(), true);
+        new.docs_api_key = Some("new-key".to_string());
+        new.session_call_limit = ToolLimitConfig { max_concurrent: 2, ..Default::default() };
+        new.http_bind = Some("0.0.0.0".to_string());
+        assert_eq!(changed_keys(&old, &new), ["docs_api_key", "http_bind", "session_call_limit", "tools"]);
+    }
+
+    #[test]
+    fn test_latest_reads_the_file_unless_watched() {
+        let mut edited = AppConfig::default();
+        edited.mcp_config.tools.insert("latest-test-tool".to_string(), true);
+        let edited_switch = |config: &AppConfig| config.mcp_config.tools.get("latest-test-tool").copied();
+
+        // Not watched: a switch toggled in the file applies on the next call
+        assert_eq!(edited_switch(&latest_from(false, || Ok(edited.clone()))), Some(true));
+        // Watched: the current config, which the watcher keeps up to date
+        assert_eq!(edited_switch(&latest_from(true, || panic!("read while watched"))), None);
+        // Unreadable file: the current config
+        assert_eq!(edited_switch(&latest_from(false, || Err(anyhow!("broken")))), None);
+    }
+
+    #[test]
+    fn test_parse_log_level() {
+        assert_eq!(parse_log_level(" Debug "), Some(LevelFilter::Debug));
+        assert_eq!(parse_log_level("off"), Some(LevelFilter::Off));
+        assert_eq!(parse_log_level("verbose"), None);
+    }
+}
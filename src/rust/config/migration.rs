@@ -0,0 +1,258 @@
+// 旧版文件位置迁移
+// 早期版本把配置（config.json）和全局记忆库（.devkit-memory/）放在可执行文件旁边，
+// 改用平台目录后这些文件不再被读取。启动时（首次加载配置前）执行一次迁移：
+// - 复制（不移动）到新位置：config.json -> <配置目录>/config.json，.devkit-memory/ -> <配置目录>/memory/
+// - 新旧文件都存在且内容不同时保留新文件，冲突记录下来由 doctor 报告
+// - 在旧目录留下 MIGRATED.txt 说明复制了什么，日志中逐项记录
+// - 在状态目录写入标记文件（内容即迁移报告），之后启动只检查标记是否存在
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::log_important;
+use crate::utils::paths::{self, preflight, AppDir};
+
+/// 状态目录中的迁移标记文件名
+pub const MARKER_FILE: &str = "legacy-migration.json";
+
+/// 旧目录中留下的说明文件名
+pub const BREADCRUMB_FILE: &str = "MIGRATED.txt";
+
+/// 旧版全局记忆库目录名
+const LEGACY_MEMORY_DIR: &str = ".devkit-memory";
+
+/// 本进程的迁移结果，首次加载配置时执行
+static STARTUP_MIGRATION: Lazy<Option<MigrationReport>> = Lazy::new(|| {
+    let layout = Layout::current()?;
+    match migrate(&layout) {
+        Ok(report) => report,
+        Err(e) => {
+            log_important!(warn, "旧版文件迁移失败: {}", e);
+            None
+        }
+    }
+});
+
+/// 迁移涉及的目录
+#[derive(Debug, Clone)]
+pub struct Layout {
+    /// 旧版文件所在目录（可执行文件所在目录）
+    pub legacy_dir: PathBuf,
+    pub config_dir: PathBuf,
+    /// 存放标记文件；为空时每次启动都会检查
+    pub state_dir: Option<PathBuf>,
+}
+
+impl Layout {
+    /// 当前进程对应的目录；配置目录不可写时不迁移
+    fn current() -> Option<Self> {
+        if !preflight().is_writable(AppDir::Config) {
+            return None;
+        }
+        let legacy_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+        let config_dir = paths::config_dir().ok()?;
+        if legacy_dir == config_dir {
+            return None;
+        }
+        let state_dir = preflight().is_writable(AppDir::State).then(paths::state_dir).and_then(Result::ok);
+        Some(Self { legacy_dir, config_dir, state_dir })
+    }
+
+    /// (旧路径, 新路径)
+    fn items(&self) -> [(PathBuf, PathBuf); 2] {
+        [
+            (self.legacy_dir.join("config.json"), self.config_dir.join("config.json")),
+            (self.legacy_dir.join(LEGACY_MEMORY_DIR), self.config_dir.join("memory")),
+        ]
+    }
+
+    fn marker(&self) -> Option<PathBuf> {
+        self.state_dir.as_ref().map(|dir| dir.join(MARKER_FILE))
+    }
+}
+
+/// 一个文件的迁移
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigratedFile {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// 一次迁移的结果（同时是标记文件的内容）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub legacy_dir: PathBuf,
+    /// 已复制到新位置的文件
+    pub copied: Vec<MigratedFile>,
+    /// 新旧文件都存在且内容不同，保留了新文件
+    pub conflicts: Vec<MigratedFile>,
+    pub migrated_at: DateTime<Utc>,
+}
+
+/// 启动时执行一次迁移（重复调用不再检查）
+pub fn migrate_on_startup() {
+    Lazy::force(&STARTUP_MIGRATION);
+}
+
+/// 标记文件中记录的上次迁移结果，供 doctor 使用
+pub fn last_report() -> Option<MigrationReport> {
+    let marker = Layout::current()?.marker()?;
+    read_marker(&marker)
+}
+
+/// 执行迁移；已有标记时直接返回 `None`，否则返回本次结果
+pub fn migrate(layout: &Layout) -> Result<Option<MigrationReport>> {
+    let marker = layout.marker();
+    if marker.as_ref().is_some_and(|m| m.exists()) {
+        return Ok(None);
+    }
+
+    let mut report = MigrationReport {
+        legacy_dir: layout.legacy_dir.clone(),
+        copied: Vec::new(),
+        conflicts: Vec::new(),
+        migrated_at: Utc::now(),
+    };
+    for (from, to) in layout.items() {
+        for (from, to) in files_below(&from, &to)? {
+            if !to.exists() {
+                if let Some(parent) = to.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(&from, &to)?;
+                log_important!(info, "已迁移旧版文件 {} -> {}", from.display(), to.display());
+                report.copied.push(MigratedFile { from, to });
+            } else if fs::read(&from)? != fs::read(&to)? {
+                log_important!(warn, "旧版文件 {} 与 {} 内容不同，保留新文件", from.display(), to.display());
+                report.conflicts.push(MigratedFile { from, to });
+            }
+        }
+    }
+
+    if !report.copied.is_empty() || !report.conflicts.is_empty() {
+        fs::write(layout.legacy_dir.join(BREADCRUMB_FILE), breadcrumb(&report))?;
+    }
+    if let Some(marker) = marker {
+        fs::create_dir_all(marker.parent().unwrap_or(Path::new(".")))?;
+        fs::write(&marker, serde_json::to_string_pretty(&report)?)?;
+    }
+    Ok(Some(report))
+}
+
+/// `from` 下的全部文件及其在 `to` 下的对应路径；`from` 是文件时就是它本身
+fn files_below(from: &Path, to: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    if from.is_file() {
+        return Ok(vec![(from.to_path_buf(), to.to_path_buf())]);
+    }
+    let mut files = Vec::new();
+    if from.is_dir() {
+        let mut entries: Vec<_> = fs::read_dir(from)?.collect::<std::io::Result<_>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            files.extend(files_below(&entry.path(), &to.join(entry.file_name()))?);
+        }
+    }
+    Ok(files)
+}
+
+fn read_marker(marker: &Path) -> Option<MigrationReport> {
+    serde_json::from_str(&fs::read_to_string(marker).ok()?).ok()
+}
+
+fn breadcrumb(report: &MigrationReport) -> String {
+    let mut text = format!(
+        "devkit now keeps its files in the platform directories. On {} these files were copied\n\
+         (the originals here are no longer read and can be deleted):\n\n",
+        report.migrated_at.to_rfc3339()
+    );
+    for file in &report.copied {
+        text.push_str(&format!("  {} -> {}\n", file.from.display(), file.to.display()));
+    }
+    if !report.conflicts.is_empty() {
+        text.push_str("\nThese already existed with different content; the new files were kept (see `devkit doctor`):\n\n");
+        for file in &report.conflicts {
+            text.push_str(&format!("  {} (kept {})\n", file.from.display(), file.to.display()));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    /// Layout under a fresh directory, removed when the returned guard is dropped
+    fn fixture(name: &str) -> (TestDir, Layout) {
+        let root = TestDir::new(&format!("migration-{}", name));
+        let legacy_dir = root.join("bin");
+        fs::create_dir_all(legacy_dir.join(LEGACY_MEMORY_DIR)).unwrap();
+        fs::write(legacy_dir.join("config.json"), r#"{"mcp_config": {}}"#).unwrap();
+        fs::write(legacy_dir.join(LEGACY_MEMORY_DIR).join("rules.md"), "- Use tabs").unwrap();
+        fs::write(legacy_dir.join("devkit"), "binary").unwrap();
+        let layout = Layout { legacy_dir, config_dir: root.join("config"), state_dir: Some(root.join("state")) };
+        (root, layout)
+    }
+
+    #[test]
+    fn test_clean_migration_copies_and_leaves_breadcrumb() {
+        let (_root, layout) = fixture("clean");
+        let report = migrate(&layout).unwrap().unwrap();
+
+        let copied: Vec<_> = report.copied.iter().map(|f| f.to.clone()).collect();
+        assert_eq!(copied, vec![layout.config_dir.join("config.json"), layout.config_dir.join("memory/rules.md")]);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(fs::read_to_string(layout.config_dir.join("memory/rules.md")).unwrap(), "- Use tabs");
+        // Copied, not moved
+        assert!(layout.legacy_dir.join("config.json").exists());
+
+        let breadcrumb = fs::read_to_string(layout.legacy_dir.join(BREADCRUMB_FILE)).unwrap();
+        assert!(breadcrumb.contains(&layout.config_dir.join("config.json").display().to_string()), "{}", breadcrumb);
+        assert_eq!(read_marker(&layout.marker().unwrap()), Some(report));
+    }
+
+    #[test]
+    fn test_rerun_is_skipped_by_marker() {
+        let (_root, layout) = fixture("rerun");
+        migrate(&layout).unwrap().unwrap();
+        fs::write(layout.legacy_dir.join("config.json"), r#"{"changed": true}"#).unwrap();
+        fs::remove_file(layout.config_dir.join("memory/rules.md")).unwrap();
+
+        assert_eq!(migrate(&layout).unwrap(), None);
+        assert!(!layout.config_dir.join("memory/rules.md").exists());
+        assert_eq!(fs::read_to_string(layout.config_dir.join("config.json")).unwrap(), r#"{"mcp_config": {}}"#);
+
+        // Without a state dir there is no marker, but identical files are left alone
+        let unmarked = Layout { state_dir: None, ..layout.clone() };
+        fs::write(layout.legacy_dir.join("config.json"), r#"{"mcp_config": {}}"#).unwrap();
+        let report = migrate(&unmarked).unwrap().unwrap();
+        assert_eq!(report.copied.len(), 1);
+        assert!(report.conflicts.is_empty());
+        assert!(migrate(&unmarked).unwrap().unwrap().copied.is_empty());
+    }
+
+    #[test]
+    fn test_conflict_keeps_new_file() {
+        let (_root, layout) = fixture("conflict");
+        fs::create_dir_all(&layout.config_dir).unwrap();
+        fs::write(layout.config_dir.join("config.json"), r#"{"mcp_config": {"offline": true}}"#).unwrap();
+
+        let report = migrate(&layout).unwrap().unwrap();
+        assert_eq!(
+            report.conflicts,
+            vec![MigratedFile { from: layout.legacy_dir.join("config.json"), to: layout.config_dir.join("config.json") }]
+        );
+        assert_eq!(report.copied.len(), 1);
+        assert_eq!(
+            fs::read_to_string(layout.config_dir.join("config.json")).unwrap(),
+            r#"{"mcp_config": {"offline": true}}"#
+        );
+        let breadcrumb = fs::read_to_string(layout.legacy_dir.join(BREADCRUMB_FILE)).unwrap();
+        assert!(breadcrumb.contains("the new files were kept"), "{}", breadcrumb);
+        assert_eq!(read_marker(&layout.marker().unwrap()).unwrap().conflicts, report.conflicts);
+    }
+}
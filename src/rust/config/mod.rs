@@ -1,5 +1,14 @@
+pub mod interpolate;
+pub mod live;
+pub mod migration;
+pub mod offline;
+pub mod recovery;
 pub mod settings;
 pub mod storage;
 
+pub use interpolate::{interpolate_config, InterpolationError};
+pub use live::{ConfigReload, ConfigWatcher};
+pub use offline::{offline_mode, OfflineError, OfflineMode};
+pub use recovery::{set_strict_config, is_strict_config, config_warnings};
 pub use settings::*;
 pub use storage::*;
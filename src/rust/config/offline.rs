@@ -0,0 +1,172 @@
+// 离线模式开关
+// 三个来源按优先级：单次调用的 offline 参数 > DEVKIT_OFFLINE 环境变量 > 配置文件 mcp_config.offline。
+// 离线时文档只从缓存读取，更新检查跳过，其余需要联网的操作返回统一的结构化错误
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// 离线模式环境变量
+pub const OFFLINE_ENV: &str = "DEVKIT_OFFLINE";
+
+/// 决定离线状态的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OfflineSource {
+    /// 工具调用的 offline 参数
+    Parameter,
+    /// DEVKIT_OFFLINE 环境变量
+    Env,
+    /// 配置文件 mcp_config.offline
+    Config,
+}
+
+impl OfflineSource {
+    /// 用户可以据此找到并关闭的开关名
+    pub fn switch(self) -> &'static str {
+        match self {
+            OfflineSource::Parameter => "offline parameter",
+            OfflineSource::Env => OFFLINE_ENV,
+            OfflineSource::Config => "mcp_config.offline",
+        }
+    }
+}
+
+/// 生效的离线状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OfflineMode {
+    pub enabled: bool,
+    /// 显式设置了状态的最高优先级来源；都未设置时为 None
+    pub source: Option<OfflineSource>,
+}
+
+impl fmt::Display for OfflineMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", if self.enabled { "on" } else { "off" })?;
+        if let Some(source) = self.source {
+            write!(f, " (set by {})", source.switch())?;
+        }
+        Ok(())
+    }
+}
+
+impl OfflineMode {
+    /// 按优先级解析：参数 > 环境变量 > 配置
+    pub fn resolve_with(parameter: Option<bool>, env: Option<&str>, config: Option<bool>) -> Self {
+        let env = env.and_then(parse_bool);
+        let (enabled, source) = if let Some(enabled) = parameter {
+            (enabled, Some(OfflineSource::Parameter))
+        } else if let Some(enabled) = env {
+            (enabled, Some(OfflineSource::Env))
+        } else if let Some(enabled) = config {
+            (enabled, Some(OfflineSource::Config))
+        } else {
+            (false, None)
+        };
+        Self { enabled, source }
+    }
+
+    /// 结合当前环境变量与配置文件解析
+    pub fn resolve(parameter: Option<bool>) -> Self {
+        let env = std::env::var(OFFLINE_ENV).ok();
+        let config = super::load_standalone_config()
+            .ok()
+            .map(|c| c.mcp_config.offline)
+            .filter(|offline| *offline);
+        Self::resolve_with(parameter, env.as_deref(), config)
+    }
+
+    /// 离线时返回 `operation` 对应的错误
+    pub fn check(&self, operation: &str) -> Result<(), OfflineError> {
+        match (self.enabled, self.source) {
+            (true, Some(source)) => Err(OfflineError { operation: operation.to_string(), enabled_by: source }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 离线模式下拒绝联网操作的错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Offline mode is on (set by {}): {operation} needs network access", enabled_by.switch())]
+pub struct OfflineError {
+    pub operation: String,
+    /// 开启离线模式的来源
+    pub enabled_by: OfflineSource,
+}
+
+impl OfflineError {
+    /// 统一的结构化错误内容
+    pub fn to_json(&self) -> Value {
+        json!({
+            "error": {
+                "kind": "offline",
+                "message": self.to_string(),
+                "operation": self.operation,
+                "source": self.enabled_by,
+                "switch": self.enabled_by.switch(),
+            }
+        })
+    }
+}
+
+/// 当前进程的离线状态（不含单次调用参数），供诊断与 /health 使用
+pub fn offline_mode() -> OfflineMode {
+    OfflineMode::resolve(None)
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_source_enables_offline() {
+        let mode = OfflineMode::resolve_with(Some(true), None, None);
+        assert_eq!(mode, OfflineMode { enabled: true, source: Some(OfflineSource::Parameter) });
+
+        let mode = OfflineMode::resolve_with(None, Some("1"), None);
+        assert_eq!(mode, OfflineMode { enabled: true, source: Some(OfflineSource::Env) });
+
+        let mode = OfflineMode::resolve_with(None, None, Some(true));
+        assert_eq!(mode, OfflineMode { enabled: true, source: Some(OfflineSource::Config) });
+
+        assert_eq!(OfflineMode::resolve_with(None, None, None), OfflineMode { enabled: false, source: None });
+    }
+
+    #[test]
+    fn test_precedence() {
+        // 参数优先于环境变量，环境变量优先于配置
+        let mode = OfflineMode::resolve_with(Some(false), Some("1"), Some(true));
+        assert!(!mode.enabled);
+        assert_eq!(mode.source, Some(OfflineSource::Parameter));
+
+        let mode = OfflineMode::resolve_with(None, Some("off"), Some(true));
+        assert!(!mode.enabled);
+        assert_eq!(mode.source, Some(OfflineSource::Env));
+
+        // 无法识别的环境变量值视为未设置
+        let mode = OfflineMode::resolve_with(None, Some("maybe"), Some(true));
+        assert_eq!(mode.source, Some(OfflineSource::Config));
+    }
+
+    #[test]
+    fn test_error_shape() {
+        let err = OfflineMode::resolve_with(None, Some("true"), None).check("docs fetch").unwrap_err();
+        assert_eq!(err.to_string(), "Offline mode is on (set by DEVKIT_OFFLINE): docs fetch needs network access");
+
+        let value = err.to_json();
+        assert_eq!(value["error"]["kind"], "offline");
+        assert_eq!(value["error"]["operation"], "docs fetch");
+        assert_eq!(value["error"]["source"], "env");
+        assert_eq!(value["error"]["switch"], OFFLINE_ENV);
+
+        assert!(OfflineMode::resolve_with(Some(false), None, None).check("docs fetch").is_ok());
+    }
+}
@@ -0,0 +1,240 @@
+// 损坏配置文件恢复
+// 解析失败时将原文件改名隔离为 config.json.broken-<时间戳>，使用默认配置继续启动；
+// 严格模式（--strict-config / DEVKIT_STRICT_CONFIG）下保持直接报错的旧行为
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use super::settings::AppConfig;
+use crate::log_important;
+
+/// 严格模式环境变量
+pub const STRICT_CONFIG_ENV: &str = "DEVKIT_STRICT_CONFIG";
+
+/// 隔离文件名中的标记
+const BROKEN_MARKER: &str = ".broken-";
+
+static STRICT_CONFIG: AtomicBool = AtomicBool::new(false);
+static LAST_RECOVERY: Mutex<Option<ConfigRecovery>> = Mutex::new(None);
+
+/// 一次配置恢复的记录
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigRecovery {
+    /// 隔离后的损坏文件路径
+    pub broken_path: PathBuf,
+    /// 解析错误信息
+    pub error: String,
+    /// 出错行号（从 1 开始）
+    pub line: usize,
+    /// 出错列号（从 1 开始）
+    pub column: usize,
+    pub recovered_at: DateTime<Utc>,
+}
+
+/// 设置严格模式（由 --strict-config 参数调用）
+pub fn set_strict_config(strict: bool) {
+    STRICT_CONFIG.store(strict, Ordering::SeqCst);
+}
+
+/// 是否处于严格模式
+pub fn is_strict_config() -> bool {
+    STRICT_CONFIG.load(Ordering::SeqCst)
+        || std::env::var(STRICT_CONFIG_ENV)
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+            .unwrap_or(false)
+}
+
+/// 本进程最近一次配置恢复记录
+pub fn last_recovery() -> Option<ConfigRecovery> {
+    LAST_RECOVERY.lock().ok().and_then(|r| r.clone())
+}
+
+/// 解析配置内容，失败时按当前模式隔离或报错
+pub fn parse_config(path: &Path, json: &str) -> Result<AppConfig> {
+    parse_config_with(path, json, is_strict_config())
+}
+
+/// 解析配置内容，失败时总是报错、不隔离文件（运行中重新加载时使用）
+pub fn parse_config_strict(path: &Path, json: &str) -> Result<AppConfig> {
+    parse_config_with(path, json, true)
+}
+
+fn parse_config_with(path: &Path, json: &str, strict: bool) -> Result<AppConfig> {
+    let err = match serde_json::from_str::<AppConfig>(json) {
+        Ok(config) => return Ok(config),
+        Err(e) => e,
+    };
+
+    if strict {
+        anyhow::bail!(
+            "配置文件解析失败 {} (第 {} 行第 {} 列): {}",
+            path.display(),
+            err.line(),
+            err.column(),
+            err
+        );
+    }
+
+    let broken_path = quarantine(path)?;
+    log_important!(error, "==================== 配置文件已损坏 ====================");
+    log_important!(error, "无法解析 {} (第 {} 行第 {} 列): {}", path.display(), err.line(), err.column(), err);
+    log_important!(error, "已将原文件移至 {}，本次使用默认配置启动", broken_path.display());
+    log_important!(error, "请对照该文件重新设置后删除它；如需启动失败而非回退默认配置，请使用 --strict-config");
+    log_important!(error, "========================================================");
+
+    let recovery = ConfigRecovery {
+        broken_path,
+        error: err.to_string(),
+        line: err.line(),
+        column: err.column(),
+        recovered_at: Utc::now(),
+    };
+    if let Ok(mut last) = LAST_RECOVERY.lock() {
+        *last = Some(recovery);
+    }
+
+    Ok(AppConfig::default())
+}
+
+/// 将损坏的配置文件改名隔离
+fn quarantine(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("config.json");
+    let timestamp = Utc::now().format("%Y%m%d-%H%M%S%.3f");
+    let broken_path = path.with_file_name(format!("{}{}{}", file_name, BROKEN_MARKER, timestamp));
+    fs::rename(path, &broken_path)
+        .map_err(|e| anyhow::anyhow!("无法隔离损坏的配置文件 {}: {}", path.display(), e))?;
+    Ok(broken_path)
+}
+
+/// 列出配置文件旁边残留的隔离文件
+pub fn quarantined_configs(config_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(file_name)) = (config_path.parent(), config_path.file_name().and_then(|n| n.to_str())) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}{}", file_name, BROKEN_MARKER);
+
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with(&prefix))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// 检查配置文件能否解析（不做隔离，供 doctor 使用）
+pub fn check_config_file(path: &Path) -> std::result::Result<(), String> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("无法读取: {}", e)),
+    };
+    serde_json::from_str::<AppConfig>(&json)
+        .map(|_| ())
+        .map_err(|e| format!("第 {} 行第 {} 列: {}", e.line(), e.column(), e))
+}
+
+/// 需要提示用户的配置问题（供 /health 与 doctor 使用）
+pub fn config_warnings() -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(recovery) = last_recovery() {
+        warnings.push(format!(
+            "config was corrupted ({}:{}: {}); started with defaults, original kept at {}",
+            recovery.line,
+            recovery.column,
+            recovery.error,
+            recovery.broken_path.display()
+        ));
+    }
+    if let Ok(path) = super::get_standalone_config_path() {
+        for broken in quarantined_configs(&path) {
+            let already_reported = last_recovery().is_some_and(|r| r.broken_path == broken);
+            if !already_reported {
+                warnings.push(format!("quarantined config file pending review: {}", broken.display()));
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    const TRUNCATED: &str = include_str!("fixtures/config_truncated.json");
+    const CONFLICT: &str = include_str!("fixtures/config_conflict.json");
+
+    fn temp_config(content: &str) -> (TestDir, PathBuf) {
+        let dir = TestDir::new("config-test");
+        let path = dir.join("config.json");
+        fs::write(&path, content).unwrap();
+        (dir, path)
+    }
+
+    fn assert_quarantined(content: &str) {
+        let (_dir, path) = temp_config(content);
+
+        let config = parse_config_with(&path, content, false).unwrap();
+        // 默认配置中含有创建时间，只比较稳定的部分
+        assert_eq!(
+            serde_json::to_value(&config.mcp_config).unwrap(),
+            serde_json::to_value(AppConfig::default().mcp_config).unwrap()
+        );
+        assert!(!path.exists());
+
+        let broken = quarantined_configs(&path);
+        assert_eq!(broken.len(), 1);
+        assert_eq!(fs::read_to_string(&broken[0]).unwrap(), content);
+
+        let recovery = last_recovery().unwrap();
+        assert!(recovery.line > 0);
+    }
+
+    #[test]
+    fn test_truncated_config_is_quarantined() {
+        assert_quarantined(TRUNCATED);
+    }
+
+    #[test]
+    fn test_conflict_markers_are_quarantined() {
+        assert_quarantined(CONFLICT);
+    }
+
+    #[test]
+    fn test_strict_mode_fails_fast() {
+        let (_dir, path) = temp_config(CONFLICT);
+
+        let err = parse_config_with(&path, CONFLICT, true).unwrap_err();
+        assert!(err.to_string().contains("第 3 行"));
+        assert!(path.exists());
+        assert!(quarantined_configs(&path).is_empty());
+        assert!(check_config_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_valid_config_is_untouched() {
+        let json = serde_json::to_string(&AppConfig::default()).unwrap();
+        let (_dir, path) = temp_config(&json);
+
+        parse_config_with(&path, &json, false).unwrap();
+        assert!(path.exists());
+        assert!(check_config_file(&path).is_ok());
+    }
+}
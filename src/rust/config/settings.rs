@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Mutex;
+use std::time::Duration;
 use crate::constants::{window, theme, audio, mcp, telegram, font};
+use crate::docs_client::cache::{DEFAULT_MAX_BYTES, DEFAULT_TTL};
+use crate::docs_client::{docs_api_base_url, BreakerTuning, DocsConfig, HttpTuning, RetryTuning, ThrottleTuning};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppConfig {
@@ -110,6 +113,8 @@ pub struct ReplyConfig {
 pub struct McpConfig {
     #[serde(default = "default_mcp_tools")]
     pub tools: HashMap<String, bool>, // MCP工具启用状态
+    #[serde(default)]
+    pub tool_limits: HashMap<String, ToolLimitConfig>, // 单个工具的并发上限，键为工具名（"cache" 同时限制 cache_sync），未列出的工具不限制
     pub acemcp_base_url: Option<String>, // acemcp API端点URL
     pub acemcp_token: Option<String>, // acemcp认证令牌
     pub acemcp_batch_size: Option<u32>, // acemcp批处理大小
@@ -125,9 +130,111 @@ pub struct McpConfig {
     pub acemcp_proxy_type: Option<String>, // 代理类型: "http" | "https" | "socks5"
     pub acemcp_proxy_username: Option<String>, // 代理用户名（可选）
     pub acemcp_proxy_password: Option<String>, // 代理密码（可选）
+    pub acemcp_timeout_secs: Option<u64>, // acemcp 请求超时（秒），默认 60
     pub docs_api_key: Option<String>, // Docs API密钥 (可选，免费使用时可为空)
+    #[serde(default)]
+    pub docs_api_keys: Vec<String>, // 额外的 Docs API 密钥，与 docs_api_key 轮流使用；401 的密钥本次会话停用，429 的密钥按 Retry-After 暂停
+    #[serde(default)]
+    pub docs_auto_resolve: bool, // 库不存在时自动采用置信度足够高的搜索结果
+    // 上游 HTTP 客户端参数，Docs 与 acemcp 共用（未设置时使用 HttpTuning 默认值）
+    pub docs_pool_max_idle_per_host: Option<usize>, // 每个主机的最大空闲连接数
+    pub docs_pool_idle_timeout_secs: Option<u64>, // 空闲连接保留时间（秒）
+    pub docs_http2: Option<bool>, // 是否允许 HTTP/2
+    pub docs_tcp_keepalive_secs: Option<u64>, // TCP keepalive 间隔（秒），0 表示关闭
+    pub docs_proxy_url: Option<String>, // 上游请求的代理地址（http/https/socks5），未设置时使用 HTTP_PROXY/HTTPS_PROXY/NO_PROXY 环境变量；acemcp 未启用自身代理时也使用它
+    pub docs_base_url: Option<String>, // Docs API 地址，默认 https://context7.com/api/v2，可指向自建或镜像服务（环境变量 DEVKIT_DOCS_API_URL 优先）
+    pub docs_page_budget_chars: Option<usize>, // docs 的 max_pages 连续翻页时的字符预算，累计内容达到后停止，默认 60000
+    pub docs_timeout_secs: Option<u64>, // Docs 文档请求每次尝试的超时（秒），默认 30
+    pub docs_search_timeout_secs: Option<u64>, // Docs 搜索请求每次尝试的超时（秒），默认 15
+    // Docs 上游 5xx 与 429 的重试参数（未设置时使用 RetryTuning 默认值）
+    pub docs_retry_max_retries: Option<u32>, // 最多重试次数，0 表示不重试
+    pub docs_retry_base_delay_ms: Option<u64>, // 第一次重试前的等待（毫秒），之后每次翻倍
+    pub docs_retry_max_delay_ms: Option<u64>, // 单次等待上限（毫秒）
+    pub docs_retry_jitter: Option<bool>, // 是否随机化等待时间
+    pub docs_retry_max_retry_after_secs: Option<u64>, // 429 的 Retry-After 超过该值（秒）时直接返回错误
+    pub docs_breaker_failure_threshold: Option<u32>, // Docs 上游连续失败（5xx、超时、连接失败）多少次后熔断，默认 5，0 表示不熔断
+    pub docs_breaker_cooldown_secs: Option<u64>, // 熔断后直接返回"上游不可用"的时间（秒），默认 30
+    pub docs_throttle_per_minute: Option<u32>, // 客户端每分钟最多发出的 Docs 请求数，未配置 API 密钥时默认 30，配置后默认不限速；0 表示不限速
+    pub docs_throttle_burst: Option<u32>, // 限速前可以连续发出的请求数，默认 5
+    pub docs_user_agent: Option<String>, // Docs 上游请求的 User-Agent，默认 devkit/<版本>
+    #[serde(default, alias = "context7_extra_headers")]
+    pub docs_headers: BTreeMap<String, String>, // 附加到每个 Docs 上游请求的请求头（不能包含 Authorization、Host、User-Agent），如 API 网关要求的租户或追踪头；也可写作 context7_extra_headers
+    #[serde(default)]
+    pub docs_sensitive_headers: Vec<String>, // docs_headers 中值为敏感信息的请求头名称，日志与诊断中不显示其值
+    #[serde(default)]
+    pub docs_contract_preflight: bool, // 启动时请求一次 Docs API 并检查返回字段是否与解析器预期一致，发现偏差只记录警告（默认关闭）
+    #[serde(default)]
+    pub docs_key_check: Option<bool>, // 启动时用每个配置的 API 密钥请求一次 Docs API，检查密钥是否有效、是否过期及剩余额度（默认启用，未配置密钥时不请求）
+    #[serde(default)]
+    pub offline: bool, // 离线模式：文档只读缓存，跳过更新检查等联网操作（可被 DEVKIT_OFFLINE 与单次调用参数覆盖）
+    pub docs_cache_ttl_secs: Option<u64>, // 文档缓存有效期（秒），默认 86400（一天）；0 表示每次都请求上游
+    pub docs_cache_max_mb: Option<u64>, // 文档缓存占用上限（MB），默认 100，超出时淘汰最旧的条目；0 表示不限制
+    pub docs_max_response_bytes: Option<u64>, // Docs 回答的字节上限，默认 100000，超出时只保留放得下的片段并列出省略片段的标题；0 表示不限制
+    pub docs_quota_footer_every: Option<u64>, // 每隔多少次上游请求附加一次用量提示，默认 10，0 表示只在剩余额度不足时提示
+    pub language: Option<String>, // 输出语言（"en" | "zh"），设置后固定使用；未设置时跟随客户端 initialize 中声明的语言，默认英文
     #[serde(default = "default_interaction_wait_ms")]
     pub interaction_wait_ms: u64, // 单次等待阈值（毫秒），0 表示无限等待
+    pub popup_diff_max_lines: Option<usize>, // 弹窗中 diff 最多展示的行数，超出部分只显示统计，默认 2000
+    pub project_docs_globs: Option<Vec<String>>, // project_docs 工具索引的文件（相对项目根目录的 glob），未设置时使用 README、docs/ 与 Rust 文档注释
+    #[serde(default)]
+    pub commands: Vec<CommandRule>, // run_command 工具允许执行的命令模板，未列出的命令一律拒绝
+    #[serde(default)]
+    pub command_env: Vec<String>, // run_command 子进程额外继承的环境变量名（默认只继承 PATH、HOME 等基础变量）
+    #[serde(default)]
+    pub endpoints: Vec<McpEndpointConfig>, // HTTP 服务额外的命名端点，各自使用不同的工具集
+    pub http_bind: Option<String>, // HTTP 服务监听的 IP（如 0.0.0.0、::），默认 127.0.0.1，容器模式默认 0.0.0.0；--host 与 MCP_HTTP_BIND/MCP_HTTP_HOST 优先
+    pub http_token: Option<String>, // HTTP 服务的访问令牌，设置后 MCP 端点要求 Authorization: Bearer <令牌> 或 ?token=<令牌>；环境变量 DEVKIT_HTTP_TOKEN 优先
+    pub session_rate_limit: Option<u32>, // 每个 MCP 会话每分钟最多的工具调用次数，超出时返回可重试的错误；未设置或 0 表示不限制，list_sessions 可单独调整某个会话
+    #[serde(default)]
+    pub session_call_limit: ToolLimitConfig, // 单个会话同时执行的工具调用上限（不分工具），超出的调用排队，超时返回 busy 错误；默认不限制
+    #[serde(default)]
+    pub call_limit: ToolLimitConfig, // 整个进程同时执行的工具调用上限（所有会话合计），防止代理循环占满服务；默认不限制
+    #[serde(default)]
+    pub telemetry: TelemetryConfig, // OpenTelemetry 链路导出（需要 otel 特性编译）
+    pub log_level: Option<String>, // MCP 服务的日志级别（error | warn | info | debug | trace），修改后无需重启即生效；环境变量 RUST_LOG 优先
+}
+
+// OpenTelemetry 链路导出配置：工具调用、HTTP 请求与上游请求的 span 通过 OTLP/HTTP 发送
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct TelemetryConfig {
+    #[serde(default)]
+    pub enabled: bool, // 是否导出
+    pub endpoint: Option<String>, // OTLP/HTTP traces 地址，默认 http://localhost:4318/v1/traces（也可用 OTEL_EXPORTER_OTLP_ENDPOINT）
+    #[serde(default)]
+    pub headers: HashMap<String, String>, // 导出请求附加的 HTTP 头，例如认证信息
+    pub sample_ratio: Option<f64>, // 采样比例 0.0-1.0，默认 1.0；已有上游 trace 时跟随其采样决定
+}
+
+// 单个工具的并发上限：超出的调用排队等待，超过排队时间返回 busy 错误
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ToolLimitConfig {
+    pub max_concurrent: usize, // 同时执行的调用数，0 表示不限制
+    pub queue_timeout_ms: Option<u64>, // 排队等待的最长时间（毫秒），默认 30000，0 表示不排队直接拒绝
+}
+
+// HTTP 服务中的命名 MCP 端点（例如 /sse/readonly），与默认的 /sse 共享缓存与记忆存储
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct McpEndpointConfig {
+    pub name: String, // 端点名称，用于会话列表与 /health 统计
+    pub path: String, // URL 路径，例如 "/sse/readonly"
+    #[serde(default)]
+    pub tools: HashMap<String, bool>, // 覆盖 mcp_config.tools 的工具开关；"cache" 控制弹窗工具
+    #[serde(default)]
+    pub memory_read_only: bool, // 只允许回忆，不允许写入记忆
+}
+
+// run_command 允许执行的命令模板，例如 "cargo test {...}"
+// 模板按空白分词：字面词必须完全一致，{} 匹配一个参数，{...} 匹配其余任意个参数（只能放在最后）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct CommandRule {
+    pub template: String, // 命令模板
+    #[serde(default)]
+    pub project: Option<String>, // 只对该项目路径生效，未设置时对所有项目生效
+    #[serde(default)]
+    pub requires_approval: bool, // 执行前通过弹窗确认
+    pub timeout_secs: Option<u64>, // 超时时间（秒），默认 300
+    #[serde(default)]
+    pub env: Vec<String>, // 该命令额外继承的环境变量名
 }
 
 // 自定义prompt结构
@@ -287,6 +394,7 @@ pub fn default_audio_config() -> AudioConfig {
 pub fn default_mcp_config() -> McpConfig {
     McpConfig {
         tools: default_mcp_tools(),
+        tool_limits: HashMap::new(),
         acemcp_base_url: None,
         acemcp_token: None,
         acemcp_batch_size: None,
@@ -302,8 +410,52 @@ pub fn default_mcp_config() -> McpConfig {
         acemcp_proxy_type: None,
         acemcp_proxy_username: None,
         acemcp_proxy_password: None,
+        acemcp_timeout_secs: None,
         docs_api_key: None,
+        docs_api_keys: Vec::new(),
+        docs_auto_resolve: false,
+        docs_pool_max_idle_per_host: None,
+        docs_pool_idle_timeout_secs: None,
+        docs_http2: None,
+        docs_tcp_keepalive_secs: None,
+        docs_proxy_url: None,
+        docs_base_url: None,
+        docs_page_budget_chars: None,
+        docs_timeout_secs: None,
+        docs_search_timeout_secs: None,
+        docs_retry_max_retries: None,
+        docs_retry_base_delay_ms: None,
+        docs_retry_max_delay_ms: None,
+        docs_retry_jitter: None,
+        docs_retry_max_retry_after_secs: None,
+        docs_breaker_failure_threshold: None,
+        docs_breaker_cooldown_secs: None,
+        docs_throttle_per_minute: None,
+        docs_throttle_burst: None,
+        docs_user_agent: None,
+        docs_headers: BTreeMap::new(),
+        docs_sensitive_headers: Vec::new(),
+        docs_contract_preflight: false,
+        docs_key_check: None,
+        offline: false,
+        docs_cache_ttl_secs: None,
+        docs_cache_max_mb: None,
+        docs_max_response_bytes: None,
+        docs_quota_footer_every: None,
+        language: None,
         interaction_wait_ms: default_interaction_wait_ms(),
+        popup_diff_max_lines: None,
+        project_docs_globs: None,
+        commands: Vec::new(),
+        command_env: Vec::new(),
+        endpoints: Vec::new(),
+        http_bind: None,
+        http_token: None,
+        session_rate_limit: None,
+        session_call_limit: ToolLimitConfig::default(),
+        call_limit: ToolLimitConfig::default(),
+        telemetry: TelemetryConfig::default(),
+        log_level: None,
     }
 }
 
@@ -406,6 +558,15 @@ pub fn default_mcp_tools() -> HashMap<String, bool> {
     tools.insert(mcp::TOOL_STORE.to_string(), true);
     tools.insert(mcp::TOOL_INDEX.to_string(), false);
     tools.insert(mcp::TOOL_DOCS.to_string(), true);
+    tools.insert(mcp::TOOL_DOCS_SEARCH.to_string(), true);
+    tools.insert(mcp::TOOL_DOCS_COMPARE.to_string(), true);
+    tools.insert(mcp::TOOL_DEVKIT_STATS.to_string(), true);
+    tools.insert(mcp::TOOL_DOCS_STATUS.to_string(), true);
+    tools.insert(mcp::TOOL_PROJECT_DOCS.to_string(), true);
+    tools.insert(mcp::TOOL_RUN_COMMAND.to_string(), false);
+    tools.insert(mcp::TOOL_GIT_CONTEXT.to_string(), true);
+    tools.insert(mcp::TOOL_LIST_SESSIONS.to_string(), false);
+    tools.insert(mcp::TOOL_RELOAD_CONFIG.to_string(), false);
     tools
 }
 
@@ -457,6 +618,92 @@ pub fn default_telegram_api_base_url() -> String {
     telegram::API_BASE_URL.to_string()
 }
 
+impl McpConfig {
+    // Docs 上游 HTTP 客户端参数，未设置的项取默认值
+    pub fn docs_http_tuning(&self) -> HttpTuning {
+        let defaults = HttpTuning::default();
+        HttpTuning {
+            pool_max_idle_per_host: self.docs_pool_max_idle_per_host.unwrap_or(defaults.pool_max_idle_per_host),
+            pool_idle_timeout_secs: self.docs_pool_idle_timeout_secs.unwrap_or(defaults.pool_idle_timeout_secs),
+            http2: self.docs_http2.unwrap_or(defaults.http2),
+            tcp_keepalive_secs: match self.docs_tcp_keepalive_secs {
+                Some(0) => None,
+                Some(secs) => Some(secs),
+                None => defaults.tcp_keepalive_secs,
+            },
+            proxy_url: self.docs_proxy_url.clone().filter(|url| !url.trim().is_empty()),
+        }
+    }
+
+    // Docs 上游重试参数，未设置的项取默认值
+    pub fn docs_retry_tuning(&self) -> RetryTuning {
+        let defaults = RetryTuning::default();
+        RetryTuning {
+            max_retries: self.docs_retry_max_retries.unwrap_or(defaults.max_retries),
+            base_delay_ms: self.docs_retry_base_delay_ms.unwrap_or(defaults.base_delay_ms),
+            max_delay_ms: self.docs_retry_max_delay_ms.unwrap_or(defaults.max_delay_ms),
+            jitter: self.docs_retry_jitter.unwrap_or(defaults.jitter),
+            max_retry_after_secs: self.docs_retry_max_retry_after_secs.unwrap_or(defaults.max_retry_after_secs),
+        }
+    }
+
+    // Docs 上游熔断参数，未设置的项取默认值
+    pub fn docs_breaker_tuning(&self) -> BreakerTuning {
+        let defaults = BreakerTuning::default();
+        BreakerTuning {
+            failure_threshold: self.docs_breaker_failure_threshold.unwrap_or(defaults.failure_threshold),
+            cooldown_secs: self.docs_breaker_cooldown_secs.unwrap_or(defaults.cooldown_secs),
+        }
+    }
+
+    // Docs 客户端限速参数：未配置 API 密钥（免费额度）时默认每分钟 30 次
+    pub fn docs_throttle_tuning(&self) -> ThrottleTuning {
+        const FREE_PER_MINUTE: u32 = 30;
+        let free = self.docs_api_key.as_deref().is_none_or(|key| key.trim().is_empty())
+            && self.docs_api_keys.iter().all(|key| key.trim().is_empty());
+        ThrottleTuning {
+            per_minute: self.docs_throttle_per_minute.unwrap_or(if free { FREE_PER_MINUTE } else { 0 }),
+            burst: self.docs_throttle_burst.unwrap_or(ThrottleTuning::default().burst),
+        }
+    }
+
+    // 文档缓存有效期，未设置时为默认值
+    pub fn docs_cache_ttl(&self) -> Duration {
+        self.docs_cache_ttl_secs.map_or(DEFAULT_TTL, Duration::from_secs)
+    }
+
+    // 文档缓存占用上限（字节），None 表示不限制
+    pub fn docs_cache_max_bytes(&self) -> Option<u64> {
+        match self.docs_cache_max_mb {
+            Some(0) => None,
+            Some(mb) => Some(mb.saturating_mul(1024 * 1024)),
+            None => Some(DEFAULT_MAX_BYTES),
+        }
+    }
+
+    // Docs 客户端配置（上游地址、密钥、HTTP 参数、User-Agent 与附加请求头）
+    pub fn docs_client_config(&self) -> DocsConfig {
+        DocsConfig {
+            base_url: match self.docs_base_url.as_deref().map(str::trim) {
+                Some(url) if !url.is_empty() => url.trim_end_matches('/').to_string(),
+                _ => docs_api_base_url(),
+            },
+            api_key: self.docs_api_key.clone(),
+            api_keys: self.docs_api_keys.clone(),
+            http: self.docs_http_tuning(),
+            timeout_secs: self.docs_timeout_secs,
+            search_timeout_secs: self.docs_search_timeout_secs,
+            retry: self.docs_retry_tuning(),
+            breaker: self.docs_breaker_tuning(),
+            throttle: self.docs_throttle_tuning(),
+            page_budget_chars: self.docs_page_budget_chars,
+            user_agent: self.docs_user_agent.clone(),
+            headers: self.docs_headers.clone(),
+            sensitive_headers: self.docs_sensitive_headers.clone(),
+        }
+    }
+}
+
 impl WindowConfig {
     // 获取当前模式的宽度
     pub fn current_width(&self) -> f64 {
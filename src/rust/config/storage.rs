@@ -1,15 +1,20 @@
 use anyhow::Result;
 use std::fs;
 use std::path::PathBuf;
+#[cfg(feature = "gui")]
 use tauri::{AppHandle, LogicalSize, Manager, State};
 
-use super::settings::{AppConfig, AppState, default_shortcuts};
+use super::settings::{AppConfig, default_shortcuts};
+#[cfg(feature = "gui")]
+use super::settings::AppState;
 
+#[cfg(feature = "gui")]
 pub fn get_config_path(_app: &AppHandle) -> Result<PathBuf> {
     // 使用与独立配置相同的路径，确保一致性
     get_standalone_config_path()
 }
 
+#[cfg(feature = "gui")]
 pub async fn save_config(state: &State<'_, AppState>, app: &AppHandle) -> Result<()> {
     let config_path = get_config_path(app)?;
 
@@ -24,13 +29,15 @@ pub async fn save_config(state: &State<'_, AppState>, app: &AppHandle) -> Result
         .map_err(|e| anyhow::anyhow!("获取配置失败: {}", e))?;
     let config_json = serde_json::to_string_pretty(&*config)?;
 
-    // 写入文件
-    fs::write(&config_path, config_json)?;
+    // 先写临时文件再改名，避免其他进程读到写了一半的配置
+    let tmp_path = config_path.with_extension("json.tmp");
+    fs::write(&tmp_path, config_json)?;
 
     // 强制刷新文件系统缓存
-    if let Ok(file) = std::fs::OpenOptions::new().write(true).open(&config_path) {
+    if let Ok(file) = std::fs::OpenOptions::new().write(true).open(&tmp_path) {
         let _ = file.sync_all();
     }
+    fs::rename(&tmp_path, &config_path)?;
 
     log::debug!("配置已保存到: {:?}", config_path);
 
@@ -38,12 +45,13 @@ pub async fn save_config(state: &State<'_, AppState>, app: &AppHandle) -> Result
 }
 
 /// Tauri应用专用的配置加载函数
+#[cfg(feature = "gui")]
 pub async fn load_config(state: &State<'_, AppState>, app: &AppHandle) -> Result<()> {
     let config_path = get_config_path(app)?;
 
     if config_path.exists() {
         let config_json = fs::read_to_string(&config_path)?;
-        let mut config: AppConfig = serde_json::from_str(&config_json)?;
+        let mut config = super::recovery::parse_config(&config_path, &config_json)?;
 
         // 合并默认快捷键配置，确保新的默认快捷键被添加
         merge_default_shortcuts(&mut config);
@@ -58,6 +66,7 @@ pub async fn load_config(state: &State<'_, AppState>, app: &AppHandle) -> Result
     Ok(())
 }
 
+#[cfg(feature = "gui")]
 pub async fn load_config_and_apply_window_settings(
     state: &State<'_, AppState>,
     app: &AppHandle,
@@ -121,11 +130,16 @@ pub async fn load_config_and_apply_window_settings(
 
 /// 独立加载配置文件（用于MCP服务器等独立进程）
 pub fn load_standalone_config() -> Result<AppConfig> {
+    // 旧版放在可执行文件旁的配置先复制到新位置（每个进程只检查一次）
+    super::migration::migrate_on_startup();
     let config_path = get_standalone_config_path()?;
 
     if config_path.exists() {
-        let config_json = fs::read_to_string(config_path)?;
-        let mut config: AppConfig = serde_json::from_str(&config_json)?;
+        let config_json = fs::read_to_string(&config_path)?;
+        let config = super::recovery::parse_config(&config_path, &config_json)?;
+
+        // 先替换 ${VAR} 引用，之后读取到的都是实际值
+        let mut config = super::interpolate::interpolate_config(config)?;
 
         // 合并默认快捷键配置
         merge_default_shortcuts(&mut config);
@@ -137,6 +151,22 @@ pub fn load_standalone_config() -> Result<AppConfig> {
     }
 }
 
+/// 运行中重新读取配置文件（用于热加载）
+/// 与启动时不同，解析失败时直接报错而不隔离文件：编辑器保存到一半的内容不应让服务退回默认配置
+pub fn reload_standalone_config() -> Result<AppConfig> {
+    let config_path = get_standalone_config_path()?;
+
+    if config_path.exists() {
+        let config_json = fs::read_to_string(&config_path)?;
+        let config = super::recovery::parse_config_strict(&config_path, &config_json)?;
+        let mut config = super::interpolate::interpolate_config(config)?;
+        merge_default_shortcuts(&mut config);
+        Ok(config)
+    } else {
+        Ok(AppConfig::default())
+    }
+}
+
 /// 独立加载Telegram配置（用于MCP模式下的配置检查）
 pub fn load_standalone_telegram_config() -> Result<super::settings::TelegramConfig> {
     let config = load_standalone_config()?;
@@ -144,14 +174,14 @@ pub fn load_standalone_telegram_config() -> Result<super::settings::TelegramConf
 }
 
 /// 获取独立配置文件路径（不依赖Tauri）
-fn get_standalone_config_path() -> Result<PathBuf> {
+pub fn get_standalone_config_path() -> Result<PathBuf> {
     // 使用标准的配置目录
-    let config_dir = dirs::config_dir()
-        .ok_or_else(|| anyhow::anyhow!("无法获取配置目录"))?
-        .join("devkit");
+    let config_dir = crate::utils::paths::config_dir()?;
 
-    // 确保目录存在
-    fs::create_dir_all(&config_dir)?;
+    // 确保目录存在；预检判定不可写时只读运行（没有配置文件时使用默认配置）
+    if crate::utils::paths::preflight().is_writable(crate::utils::paths::AppDir::Config) {
+        fs::create_dir_all(&config_dir)?;
+    }
 
     Ok(config_dir.join("config.json"))
 }
@@ -1,4 +1,4 @@
-/// 字体配置常量
+// 字体配置常量
 
 // 默认字体系列
 pub const DEFAULT_FONT_FAMILY: &str = "inter";
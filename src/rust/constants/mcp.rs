@@ -12,8 +12,35 @@ pub const TOOL_INDEX: &str = "index";
 /// Docs tool identifier
 pub const TOOL_DOCS: &str = "docs";
 
+/// Docs search tool identifier
+pub const TOOL_DOCS_SEARCH: &str = "docs_search";
+
+/// Docs compare tool identifier
+pub const TOOL_DOCS_COMPARE: &str = "docs_compare";
+
+/// Stats tool identifier
+pub const TOOL_DEVKIT_STATS: &str = "devkit_stats";
+
+/// Docs status tool identifier
+pub const TOOL_DOCS_STATUS: &str = "docs_status";
+
+/// Project docs tool identifier
+pub const TOOL_PROJECT_DOCS: &str = "project_docs";
+
+/// Run command tool identifier
+pub const TOOL_RUN_COMMAND: &str = "run_command";
+
+/// Git context tool identifier
+pub const TOOL_GIT_CONTEXT: &str = "git_context";
+
+/// List sessions tool identifier
+pub const TOOL_LIST_SESSIONS: &str = "list_sessions";
+
+/// Reload config tool identifier
+pub const TOOL_RELOAD_CONFIG: &str = "reload_config";
+
 /// 默认启用的工具列表
-pub const DEFAULT_ENABLED_TOOLS: &[&str] = &[TOOL_CACHE, TOOL_STORE, TOOL_INDEX, TOOL_DOCS];
+pub const DEFAULT_ENABLED_TOOLS: &[&str] = &[TOOL_CACHE, TOOL_STORE, TOOL_INDEX, TOOL_DOCS, TOOL_DOCS_SEARCH, TOOL_DOCS_COMPARE, TOOL_DEVKIT_STATS, TOOL_DOCS_STATUS, TOOL_PROJECT_DOCS, TOOL_GIT_CONTEXT];
 
 /// 继续回复默认启用状态
 pub const DEFAULT_CONTINUE_REPLY_ENABLED: bool = true;
@@ -66,7 +93,10 @@ impl Default for McpConfig {
                 McpToolConfig::new(TOOL_CACHE, true, false),
                 McpToolConfig::new(TOOL_STORE, true, true),
                 McpToolConfig::new(TOOL_INDEX, false, true),
-                McpToolConfig::new(TOOL_DOCS, true, true)
+                McpToolConfig::new(TOOL_DOCS, true, true),
+                McpToolConfig::new(TOOL_PROJECT_DOCS, true, true),
+                McpToolConfig::new(TOOL_RUN_COMMAND, false, true),
+                McpToolConfig::new(TOOL_GIT_CONTEXT, true, true)
             ],
             continue_reply_enabled: DEFAULT_CONTINUE_REPLY_ENABLED,
             auto_continue_threshold: DEFAULT_AUTO_CONTINUE_THRESHOLD,
@@ -128,5 +158,5 @@ pub fn get_default_mcp_config() -> McpConfig {
 
 /// 检查是否为有效的工具 ID
 pub fn is_valid_tool_id(tool_id: &str) -> bool {
-    matches!(tool_id, TOOL_CACHE | TOOL_STORE | TOOL_INDEX | TOOL_DOCS)
+    matches!(tool_id, TOOL_CACHE | TOOL_STORE | TOOL_INDEX | TOOL_DOCS | TOOL_PROJECT_DOCS | TOOL_RUN_COMMAND | TOOL_GIT_CONTEXT)
 }
@@ -0,0 +1,165 @@
+//! Shorthands learned from the search fallback
+//!
+//! When a library that is not an id, or an id that 404s, is resolved by search
+//! (auto-resolve), the mapping is remembered so the next lookup with the same
+//! shorthand goes straight to the library instead of repeating the failed
+//! request and the search. Learned aliases live next to the cache entries and
+//! are forgotten with them; one that stops returning docs is dropped on use.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use super::resolve;
+use crate::log_debug;
+
+/// Aliases kept; the oldest are dropped beyond this
+pub const MAX_LEARNED_ALIASES: usize = 500;
+
+/// One learned shorthand
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LearnedAlias {
+    /// Library id the shorthand resolves to
+    pub id: String,
+    pub learned_at: DateTime<Utc>,
+}
+
+/// Learned aliases by normalized shorthand, persisted to `file` when set
+#[derive(Debug)]
+pub struct LearnedAliases {
+    file: Option<PathBuf>,
+    aliases: Mutex<BTreeMap<String, LearnedAlias>>,
+}
+
+impl LearnedAliases {
+    /// Aliases stored in `file` (memory only when `None`); an unreadable file starts empty
+    pub fn new(file: Option<PathBuf>) -> Self {
+        let aliases = file
+            .as_deref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self { file, aliases: Mutex::new(aliases) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, LearnedAlias>> {
+        self.aliases.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Library id learned for `library`
+    pub fn get(&self, library: &str) -> Option<String> {
+        self.lock().get(&key(library)?).map(|alias| alias.id.clone())
+    }
+
+    /// Remember that `library` resolves to `id`
+    pub fn learn(&self, library: &str, id: &str) {
+        let Some(key) = key(library) else {
+            return;
+        };
+        let mut aliases = self.lock();
+        if aliases.get(&key).is_some_and(|alias| alias.id == id) {
+            return;
+        }
+        aliases.insert(key, LearnedAlias { id: id.to_string(), learned_at: Utc::now() });
+        while aliases.len() > MAX_LEARNED_ALIASES {
+            let Some(oldest) = aliases.iter().min_by_key(|(_, alias)| alias.learned_at).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            aliases.remove(&oldest);
+        }
+        self.save(&aliases);
+    }
+
+    /// Drop what was learned for `library`
+    pub fn forget(&self, library: &str) {
+        let Some(key) = key(library) else {
+            return;
+        };
+        let mut aliases = self.lock();
+        if aliases.remove(&key).is_some() {
+            self.save(&aliases);
+        }
+    }
+
+    /// Aliases resolving to `id`, or every alias when `None`
+    pub fn count(&self, id: Option<&str>) -> usize {
+        self.lock().values().filter(|alias| id.is_none_or(|id| alias.id == id)).count()
+    }
+
+    /// Forget the aliases resolving to `id`, or every alias when `None`; returns how many
+    pub fn clear(&self, id: Option<&str>) -> usize {
+        let mut aliases = self.lock();
+        let before = aliases.len();
+        aliases.retain(|_, alias| id.is_some_and(|id| alias.id != id));
+        let removed = before - aliases.len();
+        if removed > 0 {
+            self.save(&aliases);
+        }
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.count(None)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn save(&self, aliases: &BTreeMap<String, LearnedAlias>) {
+        if let Some(file) = &self.file {
+            if let Err(e) = super::cache::write_atomic(file, aliases) {
+                log_debug!("Failed to save learned docs aliases to {}: {}", file.display(), e);
+            }
+        }
+    }
+}
+
+/// Lookup key: ids compare case-insensitively, names like the bundled alias table
+fn key(library: &str) -> Option<String> {
+    let key = if resolve::is_library_id(library) {
+        library.trim().trim_matches('/').to_lowercase()
+    } else {
+        resolve::normalize(library)
+    };
+    (!key.is_empty()).then_some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn test_learned_aliases_persist() {
+        let dir = TestDir::new("aliases");
+        let file = dir.join("aliases.json");
+        let aliases = LearnedAliases::new(Some(file.clone()));
+        aliases.learn("Next", "vercel/next.js");
+        aliases.learn("react/react", "facebook/react");
+
+        let reloaded = LearnedAliases::new(Some(file));
+        assert_eq!(reloaded.get("next").as_deref(), Some("vercel/next.js"));
+        assert_eq!(reloaded.get(" /React/React/ ").as_deref(), Some("facebook/react"));
+        assert_eq!(reloaded.get("nuxt"), None);
+
+        reloaded.forget("NEXT");
+        assert_eq!(reloaded.get("next"), None);
+        assert_eq!(reloaded.clear(Some("facebook/react")), 1);
+        assert!(reloaded.is_empty());
+    }
+
+    #[test]
+    fn test_oldest_aliases_are_dropped_beyond_the_limit() {
+        let aliases = LearnedAliases::new(None);
+        for i in 0..=MAX_LEARNED_ALIASES {
+            aliases.learn(&format!("lib{}", i), "owner/repo");
+        }
+        assert_eq!(aliases.len(), MAX_LEARNED_ALIASES);
+        assert_eq!(aliases.get(&format!("lib{}", MAX_LEARNED_ALIASES)).as_deref(), Some("owner/repo"));
+        assert_eq!(aliases.clear(None), MAX_LEARNED_ALIASES);
+    }
+}
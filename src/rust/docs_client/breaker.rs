@@ -0,0 +1,157 @@
+//! Circuit breaker for the docs upstream
+//!
+//! Every upstream attempt reports whether it failed: a 5xx response, a timeout
+//! or a connection error counts against the upstream, anything else (including
+//! 404 and 429) counts as the upstream answering. After
+//! [`BreakerTuning::failure_threshold`] failures in a row the breaker opens and
+//! requests fail right away with [`DocsError::Degraded`] for the cool-down,
+//! instead of each waiting out its own timeout. Once the cool-down is over
+//! requests go through again; the first failure reopens the breaker, the first
+//! success closes it.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{BreakerTuning, DocsError};
+use crate::log_important;
+
+/// Breaker of the configured upstream, shared by every client in this process
+static SHARED_BREAKER: Mutex<Option<(String, BreakerTuning, Arc<CircuitBreaker>)>> = Mutex::new(None);
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    tuning: BreakerTuning,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Failures since the last success
+    failures: u32,
+    /// Requests are refused until then
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(tuning: BreakerTuning) -> Self {
+        Self { tuning, state: Mutex::new(State::default()) }
+    }
+
+    /// Process-wide breaker for `base_url`; another upstream or changed
+    /// settings start a fresh breaker
+    pub fn shared(base_url: &str, tuning: &BreakerTuning) -> Arc<CircuitBreaker> {
+        let mut shared = SHARED_BREAKER.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((url, current, breaker)) = shared.as_ref() {
+            if url == base_url && current == tuning {
+                return breaker.clone();
+            }
+        }
+        let breaker = Arc::new(CircuitBreaker::new(tuning.clone()));
+        *shared = Some((base_url.to_string(), tuning.clone(), breaker.clone()));
+        breaker
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Refuse the request while the breaker is open
+    pub fn check(&self) -> Result<(), DocsError> {
+        let state = self.lock();
+        match state.open_until {
+            Some(until) if until > Instant::now() => Err(DocsError::Degraded {
+                failures: state.failures,
+                retry_in: until - Instant::now(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count a completed attempt; `failed` when the upstream itself failed
+    pub fn record(&self, failed: bool) {
+        if !self.tuning.enabled() {
+            return;
+        }
+        let mut state = self.lock();
+        if !failed {
+            if state.open_until.take().is_some() {
+                log_important!(info, "Docs upstream answered again, closing the circuit breaker");
+            }
+            state.failures = 0;
+            return;
+        }
+        state.failures = state.failures.saturating_add(1);
+        if state.failures >= self.tuning.failure_threshold {
+            let cooldown = Duration::from_secs(self.tuning.cooldown_secs);
+            if state.open_until.is_none_or(|until| until <= Instant::now()) {
+                log_important!(
+                    warn,
+                    "Docs upstream failed {} times in a row, refusing requests for {}s",
+                    state.failures,
+                    cooldown.as_secs()
+                );
+            }
+            state.open_until = Some(Instant::now() + cooldown);
+        }
+    }
+
+    /// Whether requests are currently refused
+    pub fn is_open(&self) -> bool {
+        self.check().is_err()
+    }
+}
+
+/// Whether an attempt that ended in `error` counts against the upstream
+pub fn is_upstream_failure(error: &DocsError) -> bool {
+    match error {
+        DocsError::Server { .. } => true,
+        DocsError::Network(e) => e.is_timeout() || e.is_connect(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(failure_threshold: u32, cooldown_secs: u64) -> CircuitBreaker {
+        CircuitBreaker::new(BreakerTuning { failure_threshold, cooldown_secs })
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = breaker(3, 60);
+        breaker.record(true);
+        breaker.record(true);
+        breaker.record(false);
+        breaker.record(true);
+        breaker.record(true);
+        assert!(breaker.check().is_ok(), "a success resets the count");
+
+        breaker.record(true);
+        let err = breaker.check().unwrap_err();
+        assert!(matches!(err, DocsError::Degraded { failures: 3, retry_in } if retry_in > Duration::from_secs(55)));
+        assert!(err.to_string().contains("degraded"), "{}", err);
+    }
+
+    #[test]
+    fn test_cooldown_lets_requests_through_again() {
+        let breaker = breaker(2, 0);
+        breaker.record(true);
+        breaker.record(true);
+        // A zero cool-down has already passed
+        assert!(!breaker.is_open());
+        breaker.record(false);
+        assert_eq!(breaker.lock().failures, 0);
+        assert!(breaker.lock().open_until.is_none());
+    }
+
+    #[test]
+    fn test_zero_threshold_disables_the_breaker() {
+        let breaker = breaker(0, 60);
+        for _ in 0..10 {
+            breaker.record(true);
+        }
+        assert!(!breaker.is_open());
+    }
+}
@@ -0,0 +1,827 @@
+//! Memory + disk cache for docs responses
+//!
+//! Entries are keyed by library/topic/version/page/doc type and stored as one
+//! JSON file per entry under `<cache dir>/docs/<library>/`. Every disk access
+//! happens under the cache lock and files are written through a temp file and
+//! rename, so clearing while other calls are in flight never exposes a
+//! half-written or half-deleted entry. Memory hits are re-checked against the
+//! disk so a `devkit cache clear` from another process takes effect here too.
+//! When the entries outgrow the size limit, the oldest ones are evicted on the
+//! next store.
+//!
+//! Expired entries are misses but stay stored until evicted or cleared: they
+//! are the offline snapshot served by [`DocsCache::snapshot`] when the upstream
+//! cannot be reached, and their ETag lets the next fetch ask the upstream
+//! whether they changed at all.
+//!
+//! Shorthands learned from the search fallback ([`LearnedAliases`]) are kept
+//! in the same directory and cleared along with the entries they point to.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use super::aliases::LearnedAliases;
+use super::types::{DocType, DocsRequest, DocsResponse};
+use crate::log_debug;
+
+/// How long a cached response is served
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Size the entries may take before the oldest are evicted
+pub const DEFAULT_MAX_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Hit/miss counters persisted next to the entries
+const STATS_FILE: &str = "stats.json";
+/// Learned aliases, next to the counters
+const ALIASES_FILE: &str = "aliases.json";
+/// Minimum interval between counter writes
+const STATS_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+static SHARED: Lazy<DocsCache> = Lazy::new(|| {
+    // An unwritable cache directory is reported once by the startup preflight
+    let dir = crate::utils::paths::preflight()
+        .disk_cache()
+        .then(|| crate::utils::paths::cache_subdir("docs"))
+        .and_then(|dir| dir.map_err(|e| log_debug!("Docs disk cache unavailable, using memory only: {}", e)).ok());
+    // TTL and size limit are read once, like the directory
+    let (ttl, max_bytes) = match crate::config::load_standalone_config() {
+        Ok(config) => (config.mcp_config.docs_cache_ttl(), config.mcp_config.docs_cache_max_bytes()),
+        Err(_) => (DEFAULT_TTL, Some(DEFAULT_MAX_BYTES)),
+    };
+    DocsCache::new(dir, ttl).with_max_bytes(max_bytes)
+});
+
+/// Identity of a cached response
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub library: String,
+    pub topic: Option<String>,
+    pub version: Option<String>,
+    pub page: Option<u32>,
+    pub doc_type: DocType,
+}
+
+impl CacheKey {
+    pub fn from_request(request: &DocsRequest) -> Self {
+        Self {
+            library: normalize_library(&request.library),
+            topic: request.topic.clone(),
+            version: request.version.clone(),
+            page: request.page,
+            doc_type: request.doc_type.unwrap_or_default(),
+        }
+    }
+
+    /// Stable file name for this key
+    fn id(&self) -> String {
+        let raw = format!(
+            "{}\0{}\0{}\0{}\0{}",
+            self.library,
+            self.topic.as_deref().unwrap_or(""),
+            self.version.as_deref().unwrap_or(""),
+            self.page.unwrap_or(1),
+            doc_type_key(self.doc_type)
+        );
+        short_hash(&raw, 24)
+    }
+}
+
+/// Stored entry, including the client-filled fields `DocsResponse` skips when serialized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    library: String,
+    stored_at: DateTime<Utc>,
+    response: DocsResponse,
+    moved_to: Option<String>,
+    doc_type: DocType,
+    auto_fallback: bool,
+    /// Entries stored before ETags were kept have none
+    #[serde(default)]
+    etag: Option<String>,
+}
+
+impl CacheEntry {
+    fn new(library: &str, response: &DocsResponse) -> Self {
+        Self {
+            library: library.to_string(),
+            stored_at: Utc::now(),
+            response: response.clone(),
+            moved_to: response.moved_to.clone(),
+            doc_type: response.doc_type,
+            auto_fallback: response.auto_fallback,
+            etag: response.etag.clone(),
+        }
+    }
+
+    fn into_response(self) -> DocsResponse {
+        DocsResponse {
+            moved_to: self.moved_to,
+            doc_type: self.doc_type,
+            auto_fallback: self.auto_fallback,
+            etag: self.etag,
+            ..self.response
+        }
+    }
+
+    fn is_expired(&self, ttl: Duration) -> bool {
+        let age = Utc::now().signed_duration_since(self.stored_at);
+        age.to_std().map(|age| age > ttl).unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Counters {
+    hits: u64,
+    misses: u64,
+    #[serde(skip)]
+    flushed_at: Option<Instant>,
+}
+
+/// A stored response regardless of its age, for serving while offline
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub response: DocsResponse,
+    pub stored_at: DateTime<Utc>,
+    /// Older than the cache TTL, so a normal lookup would fetch it again
+    pub expired: bool,
+}
+
+/// Oldest entry currently cached
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OldestEntry {
+    pub library: String,
+    pub stored_at: DateTime<Utc>,
+}
+
+/// Cache statistics, also returned as structured tool content
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub memory_entries: usize,
+    pub disk_entries: usize,
+    /// Bytes on disk, or the serialized size of memory entries without a disk tier
+    pub total_bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_ratio: f64,
+    pub oldest: Option<OldestEntry>,
+    pub directory: Option<PathBuf>,
+    /// Size limit, `None` when unlimited
+    pub max_bytes: Option<u64>,
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Entries: {} on disk, {} in memory", self.disk_entries, self.memory_entries)?;
+        match self.max_bytes {
+            Some(max_bytes) => writeln!(f, "Size: {} bytes (limit {} bytes)", self.total_bytes, max_bytes)?,
+            None => writeln!(f, "Size: {} bytes", self.total_bytes)?,
+        }
+        writeln!(
+            f,
+            "Hits: {}, misses: {} (hit ratio {:.1}%)",
+            self.hits,
+            self.misses,
+            self.hit_ratio * 100.0
+        )?;
+        match &self.oldest {
+            Some(oldest) => writeln!(f, "Oldest entry: {} ({})", oldest.library, oldest.stored_at.to_rfc3339())?,
+            None => writeln!(f, "Oldest entry: -")?,
+        }
+        if let Some(dir) = &self.directory {
+            writeln!(f, "Directory: {}", dir.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of a clear operation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClearResult {
+    /// Library the clear was limited to, `None` for a full wipe
+    pub library: Option<String>,
+    pub removed_entries: usize,
+    pub removed_bytes: u64,
+    /// Learned aliases forgotten with the entries
+    pub forgotten_aliases: usize,
+}
+
+impl fmt::Display for ClearResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.library {
+            Some(library) => write!(f, "Cleared {} cached entries for {}", self.removed_entries, library)?,
+            None => write!(f, "Cleared {} cached entries", self.removed_entries)?,
+        }
+        write!(f, " ({} bytes)", self.removed_bytes)?;
+        if self.forgotten_aliases > 0 {
+            write!(f, " and forgot {} learned aliases", self.forgotten_aliases)?;
+        }
+        Ok(())
+    }
+}
+
+/// Two-tier docs response cache
+pub struct DocsCache {
+    dir: Option<PathBuf>,
+    ttl: Duration,
+    max_bytes: Option<u64>,
+    /// Memory tier; the lock also serializes every disk access
+    memory: RwLock<HashMap<String, CacheEntry>>,
+    counters: Mutex<Counters>,
+    aliases: LearnedAliases,
+}
+
+impl DocsCache {
+    /// Cache rooted at `dir` (memory only when `None`)
+    pub fn new(dir: Option<PathBuf>, ttl: Duration) -> Self {
+        let counters = dir
+            .as_deref()
+            .and_then(|dir| fs::read_to_string(dir.join(STATS_FILE)).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let aliases = LearnedAliases::new(dir.as_ref().map(|dir| dir.join(ALIASES_FILE)));
+        Self {
+            dir,
+            ttl,
+            max_bytes: None,
+            memory: RwLock::new(HashMap::new()),
+            counters: Mutex::new(counters),
+            aliases,
+        }
+    }
+
+    /// Evict the oldest entries once they take more than `max_bytes` (unlimited when `None`)
+    pub fn with_max_bytes(mut self, max_bytes: Option<u64>) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Process-wide cache under the user cache directory
+    ///
+    /// TTL and size limit come from `docs_cache_ttl_secs` and `docs_cache_max_mb`.
+    pub fn shared() -> &'static DocsCache {
+        &SHARED
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Shorthands learned from the search fallback
+    pub fn aliases(&self) -> &LearnedAliases {
+        &self.aliases
+    }
+
+    /// Look up a fresh entry, counting the hit or miss
+    pub fn get(&self, key: &CacheKey) -> Option<DocsResponse> {
+        let entry = self.lookup(key);
+        self.record(entry.is_some());
+        entry.map(CacheEntry::into_response)
+    }
+
+    fn lookup(&self, key: &CacheKey) -> Option<CacheEntry> {
+        let id = key.id();
+        let path = self.entry_path(&key.library, &id);
+
+        {
+            let memory = self.memory.read().ok()?;
+            if let Some(entry) = memory.get(&id) {
+                // Memory hits only count while the disk copy exists (it may be cleared by another process)
+                let on_disk = path.as_deref().map(Path::exists).unwrap_or(true);
+                if on_disk && !entry.is_expired(self.ttl) {
+                    return Some(entry.clone());
+                }
+            }
+        }
+
+        let mut memory = self.memory.write().ok()?;
+        // Without a disk tier an expired memory entry is the only snapshot
+        let path = path?;
+        memory.remove(&id);
+        let entry = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<CacheEntry>(&json).ok());
+        match entry {
+            Some(entry) if !entry.is_expired(self.ttl) => {
+                memory.insert(id, entry.clone());
+                Some(entry)
+            }
+            // Expired: kept as an offline snapshot until evicted
+            Some(_) => None,
+            // Unreadable: drop it so it does not count against the cache size
+            None => {
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// The stored response for `key`, expired or not, without counting a hit or miss
+    pub fn snapshot(&self, key: &CacheKey) -> Option<Snapshot> {
+        let id = key.id();
+        let memory = self.memory.read().ok()?;
+        let entry = match self.entry_path(&key.library, &id) {
+            Some(path) => serde_json::from_str::<CacheEntry>(&fs::read_to_string(path).ok()?).ok()?,
+            None => memory.get(&id)?.clone(),
+        };
+        drop(memory);
+        Some(Snapshot { stored_at: entry.stored_at, expired: entry.is_expired(self.ttl), response: entry.into_response() })
+    }
+
+    /// Store a response
+    pub fn put(&self, key: &CacheKey, response: &DocsResponse) {
+        let id = key.id();
+        let entry = CacheEntry::new(&key.library, response);
+        let Ok(mut memory) = self.memory.write() else {
+            return;
+        };
+
+        if let Some(path) = self.entry_path(&key.library, &id) {
+            if let Err(e) = write_atomic(&path, &entry) {
+                log_debug!("Failed to write docs cache entry {}: {}", path.display(), e);
+            }
+        }
+        memory.insert(id.clone(), entry);
+        self.evict(&mut memory, &id);
+    }
+
+    /// Remove the oldest entries until the cache fits its size limit, never `keep`
+    ///
+    /// Called with the memory lock held. On disk, age is the file's modification
+    /// time, which is when the entry was stored.
+    fn evict(&self, memory: &mut HashMap<String, CacheEntry>, keep: &str) {
+        let Some(max_bytes) = self.max_bytes else {
+            return;
+        };
+        // (stored at, id, file, bytes)
+        let mut entries: Vec<(SystemTime, String, Option<PathBuf>, u64)> = match &self.dir {
+            Some(dir) => entry_files(dir)
+                .into_iter()
+                .filter_map(|path| {
+                    let metadata = fs::metadata(&path).ok()?;
+                    let id = path.file_stem()?.to_string_lossy().into_owned();
+                    Some((metadata.modified().ok()?, id, Some(path), metadata.len()))
+                })
+                .collect(),
+            None => memory
+                .iter()
+                .map(|(id, entry)| {
+                    let bytes = serde_json::to_string(entry).map(|s| s.len() as u64).unwrap_or(0);
+                    (entry.stored_at.into(), id.clone(), None, bytes)
+                })
+                .collect(),
+        };
+        let mut total: u64 = entries.iter().map(|(_, _, _, bytes)| bytes).sum();
+        if total <= max_bytes {
+            return;
+        }
+
+        entries.sort_by_key(|(stored_at, ..)| *stored_at);
+        let mut evicted = 0;
+        for (_, id, path, bytes) in entries {
+            if total <= max_bytes {
+                break;
+            }
+            if id == keep || path.as_ref().is_some_and(|path| fs::remove_file(path).is_err()) {
+                continue;
+            }
+            memory.remove(&id);
+            total -= bytes;
+            evicted += 1;
+        }
+        if let Some(dir) = &self.dir {
+            remove_empty_dirs(dir);
+        }
+        log_debug!("Evicted {} docs cache entries to stay under {} bytes", evicted, max_bytes);
+    }
+
+    /// Entry counts, size, hit ratio and oldest entry
+    pub fn stats(&self) -> CacheStats {
+        let memory = match self.memory.read() {
+            Ok(memory) => memory,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let counters = self.flush_counters();
+
+        let mut disk_entries = 0;
+        let mut total_bytes = 0;
+        let mut oldest: Option<OldestEntry> = None;
+        let mut consider = |library: &str, stored_at: DateTime<Utc>| {
+            if oldest.as_ref().is_none_or(|o| stored_at < o.stored_at) {
+                oldest = Some(OldestEntry { library: library.to_string(), stored_at });
+            }
+        };
+
+        match &self.dir {
+            Some(dir) => {
+                for path in entry_files(dir) {
+                    let Ok(json) = fs::read_to_string(&path) else { continue };
+                    disk_entries += 1;
+                    total_bytes += json.len() as u64;
+                    if let Ok(entry) = serde_json::from_str::<CacheEntry>(&json) {
+                        consider(&entry.library, entry.stored_at);
+                    }
+                }
+            }
+            None => {
+                for entry in memory.values() {
+                    total_bytes += serde_json::to_string(entry).map(|s| s.len() as u64).unwrap_or(0);
+                    consider(&entry.library, entry.stored_at);
+                }
+            }
+        }
+
+        let lookups = counters.hits + counters.misses;
+        CacheStats {
+            memory_entries: memory.len(),
+            disk_entries,
+            total_bytes,
+            hits: counters.hits,
+            misses: counters.misses,
+            hit_ratio: if lookups == 0 { 0.0 } else { counters.hits as f64 / lookups as f64 },
+            oldest,
+            directory: self.dir.clone(),
+            max_bytes: self.max_bytes,
+        }
+    }
+
+    /// What [`clear`](Self::clear) would remove, without removing anything
+    ///
+    /// Returns the totals and the entry files on disk.
+    pub fn clear_preview(&self, library: Option<&str>) -> (ClearResult, Vec<PathBuf>) {
+        let library = library.map(normalize_library).filter(|l| !l.is_empty());
+        let files = self.entry_files_of(library.as_deref());
+        let (removed_entries, removed_bytes) = match &self.dir {
+            Some(_) => (files.len(), files.iter().filter_map(|path| fs::metadata(path).ok()).map(|m| m.len()).sum()),
+            None => {
+                let memory = match self.memory.read() {
+                    Ok(memory) => memory,
+                    Err(poisoned) => poisoned.into_inner(),
+                };
+                let count = memory.values().filter(|entry| library.as_ref().is_none_or(|l| &entry.library == l)).count();
+                (count, 0)
+            }
+        };
+        let forgotten_aliases = self.aliases.count(library.as_deref());
+        (ClearResult { library, removed_entries, removed_bytes, forgotten_aliases }, files)
+    }
+
+    /// Remove every entry, or only those of `library`
+    pub fn clear(&self, library: Option<&str>) -> ClearResult {
+        let library = library.map(normalize_library).filter(|l| !l.is_empty());
+        let mut memory = match self.memory.write() {
+            Ok(memory) => memory,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let before = memory.len();
+        match &library {
+            Some(library) => memory.retain(|_, entry| &entry.library != library),
+            None => memory.clear(),
+        }
+        let mut removed_entries = before - memory.len();
+        let mut removed_bytes = 0;
+
+        if let Some(dir) = &self.dir {
+            removed_entries = 0;
+            for path in self.entry_files_of(library.as_deref()) {
+                let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                if fs::remove_file(&path).is_ok() {
+                    removed_entries += 1;
+                    removed_bytes += size;
+                }
+            }
+            remove_empty_dirs(dir);
+        }
+
+        drop(memory);
+        self.flush_counters();
+        let forgotten_aliases = self.aliases.clear(library.as_deref());
+        ClearResult { library, removed_entries, removed_bytes, forgotten_aliases }
+    }
+
+    /// Zero the hit/miss counters
+    pub fn reset_counters(&self) {
+        if let Ok(mut counters) = self.counters.lock() {
+            *counters = Counters::default();
+            self.write_counters(&mut counters);
+        }
+    }
+
+    /// Entry files on disk, of `library` or of every library
+    fn entry_files_of(&self, library: Option<&str>) -> Vec<PathBuf> {
+        match (&self.dir, library) {
+            (Some(dir), Some(library)) => entry_files_in(&dir.join(library_dir_name(library))),
+            (Some(dir), None) => entry_files(dir),
+            (None, _) => Vec::new(),
+        }
+    }
+
+    fn entry_path(&self, library: &str, id: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(library_dir_name(library)).join(format!("{}.json", id)))
+    }
+
+    fn record(&self, hit: bool) {
+        let Ok(mut counters) = self.counters.lock() else {
+            return;
+        };
+        if hit {
+            counters.hits += 1;
+        } else {
+            counters.misses += 1;
+        }
+        if counters.flushed_at.is_none_or(|at| at.elapsed() >= STATS_FLUSH_INTERVAL) {
+            self.write_counters(&mut counters);
+        }
+    }
+
+    /// Persist the counters now and return them
+    fn flush_counters(&self) -> Counters {
+        match self.counters.lock() {
+            Ok(mut counters) => {
+                self.write_counters(&mut counters);
+                *counters
+            }
+            Err(_) => Counters::default(),
+        }
+    }
+
+    fn write_counters(&self, counters: &mut Counters) {
+        if let Some(dir) = &self.dir {
+            let _ = write_atomic(&dir.join(STATS_FILE), &*counters);
+        }
+        counters.flushed_at = Some(Instant::now());
+    }
+}
+
+/// Key component; not the URL segment, because auto results differ from code results
+fn doc_type_key(doc_type: DocType) -> &'static str {
+    match doc_type {
+        DocType::Code => "code",
+        DocType::Info => "info",
+        DocType::Auto => "auto",
+    }
+}
+
+fn normalize_library(library: &str) -> String {
+    library.trim().trim_matches('/').to_string()
+}
+
+fn short_hash(value: &str, len: usize) -> String {
+    let hex = hex::encode(digest(&SHA256, value.as_bytes()).as_ref());
+    hex[..len].to_string()
+}
+
+/// Readable, collision-free directory name for a library
+fn library_dir_name(library: &str) -> String {
+    let readable: String = library
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    format!("{}-{}", readable, short_hash(library, 8))
+}
+
+pub(super) fn write_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension(format!("tmp-{}", std::process::id()));
+    fs::write(&tmp, serde_json::to_vec(value)?)?;
+    fs::rename(&tmp, path)
+}
+
+/// Entry files of every library
+fn entry_files(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .flat_map(|p| entry_files_in(&p))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Entry files of one library directory
+fn entry_files_in(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                // Fails (and is ignored) while the directory still has entries
+                let _ = fs::remove_dir(&path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use crate::docs_client::types::DocumentSnippet;
+    use std::sync::Arc;
+
+    fn temp_cache() -> (DocsCache, TestDir) {
+        let dir = TestDir::reserve("docs-cache");
+        (DocsCache::new(Some(dir.to_path_buf()), DEFAULT_TTL), dir)
+    }
+
+    fn key(library: &str, topic: Option<&str>) -> CacheKey {
+        CacheKey::from_request(&DocsRequest {
+            topic: topic.map(str::to_string),
+            ..DocsRequest::new(library)
+        })
+    }
+
+    fn response(content: &str) -> DocsResponse {
+        DocsResponse {
+            snippets: vec![DocumentSnippet { content: content.to_string(), title: None, score: None }],
+            ..DocsResponse::default()
+        }
+    }
+
+    #[test]
+    fn test_stats_after_known_operations() {
+        let (cache, dir) = temp_cache();
+        assert_eq!(cache.stats().disk_entries, 0);
+
+        cache.put(&key("vercel/next.js", None), &response("next"));
+        cache.put(&key("vercel/next.js", Some("routing")), &response("routing"));
+        cache.put(&key("facebook/react", None), &response("react"));
+
+        assert!(cache.get(&key("vercel/next.js", None)).is_some());
+        assert!(cache.get(&key("/facebook/react", None)).is_some());
+        assert!(cache.get(&key("vercel/next.js", Some("missing"))).is_none());
+
+        let stats = cache.stats();
+        assert_eq!(stats.disk_entries, 3);
+        assert_eq!(stats.memory_entries, 3);
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert!((stats.hit_ratio - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.oldest.unwrap().library, "vercel/next.js");
+        let bytes: u64 = entry_files(&dir).iter().map(|p| fs::metadata(p).unwrap().len()).sum();
+        assert_eq!(stats.total_bytes, bytes);
+
+        // Counters survive a restart
+        let reopened = DocsCache::new(Some(dir.to_path_buf()), DEFAULT_TTL);
+        assert_eq!((reopened.stats().hits, reopened.stats().misses), (2, 1));
+    }
+
+    #[test]
+    fn test_selective_invalidation() {
+        let (cache, _dir) = temp_cache();
+        cache.put(&key("vercel/next.js", None), &response("next"));
+        cache.put(&key("vercel/next.js", Some("routing")), &response("routing"));
+        cache.put(&key("facebook/react", None), &response("react"));
+
+        // The preview removes nothing and predicts the clear
+        let (preview, files) = cache.clear_preview(Some("vercel/next.js"));
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|file| file.exists()));
+
+        let result = cache.clear(Some("/vercel/next.js/"));
+        assert_eq!(result, preview);
+        assert_eq!(result.library.as_deref(), Some("vercel/next.js"));
+        assert_eq!(result.removed_entries, 2);
+        assert!(result.removed_bytes > 0);
+
+        assert!(cache.get(&key("vercel/next.js", None)).is_none());
+        assert!(cache.get(&key("vercel/next.js", Some("routing"))).is_none());
+        assert_eq!(cache.get(&key("facebook/react", None)).unwrap().snippets[0].content, "react");
+
+        let result = cache.clear(None);
+        assert_eq!(result.removed_entries, 1);
+        assert_eq!(cache.stats().disk_entries, 0);
+    }
+
+    #[test]
+    fn test_clear_from_another_process_invalidates_memory() {
+        let (cache, dir) = temp_cache();
+        cache.put(&key("facebook/react", None), &response("react"));
+        assert!(cache.get(&key("facebook/react", None)).is_some());
+
+        // Same directory, separate instance: what `devkit cache clear` does
+        DocsCache::new(Some(dir.to_path_buf()), DEFAULT_TTL).clear(None);
+        assert!(cache.get(&key("facebook/react", None)).is_none());
+        assert_eq!(cache.stats().memory_entries, 0);
+    }
+
+    #[test]
+    fn test_expired_entries_are_misses() {
+        let dir = TestDir::reserve("docs-cache");
+        let cache = DocsCache::new(Some(dir.to_path_buf()), Duration::ZERO);
+        cache.put(&key("facebook/react", None), &response("react"));
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert!(cache.get(&key("facebook/react", None)).is_none());
+        // Still stored for offline use
+        assert_eq!(cache.stats().disk_entries, 1);
+        let snapshot = cache.snapshot(&key("facebook/react", None)).unwrap();
+        assert!(snapshot.expired);
+        assert_eq!(snapshot.response.snippets[0].content, "react");
+        assert!(cache.snapshot(&key("vercel/next.js", None)).is_none());
+        assert_eq!(cache.stats().misses, 1);
+
+        let memory_only = DocsCache::new(None, Duration::ZERO);
+        memory_only.put(&key("facebook/react", None), &response("react"));
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(memory_only.get(&key("facebook/react", None)).is_none());
+        assert!(memory_only.snapshot(&key("facebook/react", None)).unwrap().expired);
+    }
+
+    #[test]
+    fn test_oldest_entries_are_evicted_over_the_size_limit() {
+        let (cache, dir) = temp_cache();
+        cache.put(&key("facebook/react", None), &response("react"));
+        let entry_bytes = cache.stats().total_bytes;
+        let cache = DocsCache::new(Some(dir.to_path_buf()), DEFAULT_TTL).with_max_bytes(Some(entry_bytes * 5 / 2));
+
+        for library in ["vercel/next.js", "sveltejs/kit"] {
+            std::thread::sleep(Duration::from_millis(10));
+            cache.put(&key(library, None), &response("react"));
+        }
+        assert!(cache.get(&key("facebook/react", None)).is_none());
+        assert!(cache.get(&key("vercel/next.js", None)).is_some());
+        assert!(cache.get(&key("sveltejs/kit", None)).is_some());
+        let stats = cache.stats();
+        assert_eq!(stats.disk_entries, 2);
+        assert!(stats.total_bytes <= entry_bytes * 5 / 2);
+        assert!(stats.to_string().contains(&format!("(limit {} bytes)", entry_bytes * 5 / 2)));
+
+        // An entry larger than the limit is still kept until the next store
+        let tiny = DocsCache::new(None, DEFAULT_TTL).with_max_bytes(Some(1));
+        tiny.put(&key("facebook/react", None), &response("react"));
+        assert!(tiny.get(&key("facebook/react", None)).is_some());
+        tiny.put(&key("vercel/next.js", None), &response("next"));
+        assert!(tiny.get(&key("facebook/react", None)).is_none());
+        assert_eq!(tiny.stats().memory_entries, 1);
+    }
+
+    #[test]
+    fn test_client_fields_round_trip() {
+        let (cache, dir) = temp_cache();
+        let mut moved = response("moved");
+        moved.moved_to = Some("owner/new".to_string());
+        moved.doc_type = DocType::Info;
+        moved.auto_fallback = true;
+        cache.put(&key("owner/old", None), &moved);
+
+        let reopened = DocsCache::new(Some(dir.to_path_buf()), DEFAULT_TTL);
+        let cached = reopened.get(&key("owner/old", None)).unwrap();
+        assert_eq!(cached.moved_to.as_deref(), Some("owner/new"));
+        assert_eq!(cached.doc_type, DocType::Info);
+        assert!(cached.auto_fallback);
+    }
+
+    #[test]
+    fn test_clear_during_concurrent_reads() {
+        let (cache, _dir) = temp_cache();
+        let cache = Arc::new(cache);
+        for i in 0..20 {
+            cache.put(&key("org/lib", Some(&i.to_string())), &response(&"x".repeat(4096)));
+        }
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for i in 0..50 {
+                        // Either the whole entry or nothing, never a truncated one
+                        if let Some(hit) = cache.get(&key("org/lib", Some(&(i % 20).to_string()))) {
+                            assert_eq!(hit.snippets[0].content.len(), 4096);
+                        }
+                    }
+                })
+            })
+            .collect();
+        cache.clear(Some("org/lib"));
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(cache.stats().disk_entries, 0);
+    }
+}
@@ -0,0 +1,247 @@
+//! Startup check that the docs API still returns the fields we parse
+//!
+//! The lenient parser keeps working when upstream renames or drops a field, it
+//! just quietly returns less. The preflight fetches one long-lived library with
+//! a tiny token budget, runs the body through the same parser and compares the
+//! raw JSON against [`DOCS_CONTRACT`]. Missing fields are logged as
+//! `API drift detected: missing field <path>` and the outcome is kept for
+//! `/health` and `devkit doctor`. The check never fails: an unreachable API is
+//! reported as such and nothing else happens.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{Client, DocsResponse};
+use crate::log_important;
+
+/// Library fetched by the preflight; expected to stay available
+pub const CONTRACT_LIBRARY: &str = "vercel/next.js";
+
+/// Token budget of the preflight request
+pub const CONTRACT_TOKENS: u32 = 200;
+
+/// Timeout of the preflight request
+const CONTRACT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Fields a docs response is expected to carry
+///
+/// `a.b` is a nested field, `a[]` checks the first array element and
+/// `name|alias` accepts any of the names the parser understands.
+pub const DOCS_CONTRACT: &[&str] = &[
+    "snippets",
+    "snippets[].content",
+    "pagination",
+    "pagination.current_page|currentPage|page",
+    "pagination.total_pages|totalPages",
+    "pagination.has_next|hasNext",
+];
+
+/// Outcome of the last preflight
+static LAST_REPORT: Mutex<Option<ContractReport>> = Mutex::new(None);
+
+/// Result of one contract check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ContractStatus {
+    /// Every expected field is present
+    Ok,
+    /// Fields missing from the response, by their first contract name
+    Drift { missing: Vec<String> },
+    /// The request failed; says nothing about the contract
+    Unreachable { error: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ContractReport {
+    pub library: String,
+    #[serde(flatten)]
+    pub status: ContractStatus,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl ContractReport {
+    pub fn is_drift(&self) -> bool {
+        matches!(self.status, ContractStatus::Drift { .. })
+    }
+}
+
+/// Outcome of the last preflight in this process, if one ran
+pub fn last_report() -> Option<ContractReport> {
+    LAST_REPORT.lock().ok()?.clone()
+}
+
+/// Whether the last preflight found missing fields
+pub fn api_drift() -> bool {
+    last_report().is_some_and(|report| report.is_drift())
+}
+
+/// Contract fields missing from a raw docs body
+///
+/// A body that is not a JSON object misses every field.
+pub fn missing_fields(body: &str) -> Vec<String> {
+    let value = serde_json::from_str::<Value>(body.trim()).ok().filter(Value::is_object);
+    let mut missing: Vec<String> = match &value {
+        Some(value) => DOCS_CONTRACT.iter().filter(|path| !has_path(value, path)).map(|path| first_name(path)).collect(),
+        None => DOCS_CONTRACT.iter().map(|path| first_name(path)).collect(),
+    };
+
+    // Present but unusable: the lenient parser salvaged nothing from a non-empty array
+    let raw_snippets = value.as_ref().and_then(|v| v.get("snippets")).and_then(Value::as_array).map_or(0, Vec::len);
+    if raw_snippets > 0 && DocsResponse::from_body(body).snippet_count() == 0 && !missing.iter().any(|m| m.starts_with("snippets")) {
+        missing.push("snippets[].content".to_string());
+    }
+    missing
+}
+
+/// `path` from [`DOCS_CONTRACT`] without its aliases
+fn first_name(path: &str) -> String {
+    path.split('.').map(|segment| segment.split('|').next().unwrap_or(segment)).collect::<Vec<_>>().join(".")
+}
+
+fn has_path(value: &Value, path: &str) -> bool {
+    let mut current = value;
+    for segment in path.split('.') {
+        let (names, into_array) = match segment.strip_suffix("[]") {
+            Some(names) => (names, true),
+            None => (segment, false),
+        };
+        let Some(next) = names.split('|').find_map(|name| current.get(name).filter(|v| !v.is_null())) else {
+            return false;
+        };
+        current = if into_array {
+            match next.as_array().map(|items| items.first()) {
+                Some(Some(first)) => first,
+                // Nothing to inspect in an empty array
+                Some(None) => return true,
+                None => return false,
+            }
+        } else {
+            next
+        };
+    }
+    true
+}
+
+impl Client {
+    /// Fetch [`CONTRACT_LIBRARY`] once and compare the response with [`DOCS_CONTRACT`]
+    ///
+    /// The report is logged and kept for [`last_report`]; errors end up in the report.
+    pub async fn check_contract(&self) -> ContractReport {
+        let status = match self.fetch_contract_body().await {
+            Ok(body) => {
+                let missing = missing_fields(&body);
+                if missing.is_empty() {
+                    log_important!(info, "Docs API contract check passed ({})", CONTRACT_LIBRARY);
+                    ContractStatus::Ok
+                } else {
+                    for field in &missing {
+                        log_important!(warn, "API drift detected: missing field {}", field);
+                    }
+                    ContractStatus::Drift { missing }
+                }
+            }
+            Err(error) => {
+                log_important!(warn, "Docs API contract check skipped, request failed: {}", error);
+                ContractStatus::Unreachable { error }
+            }
+        };
+
+        let report = ContractReport { library: CONTRACT_LIBRARY.to_string(), status, checked_at: Utc::now() };
+        if let Ok(mut last) = LAST_REPORT.lock() {
+            *last = Some(report.clone());
+        }
+        report
+    }
+
+    async fn fetch_contract_body(&self) -> Result<String, String> {
+        let url = format!("{}/docs/code/{}", self.config.base_url, CONTRACT_LIBRARY);
+        let request = self
+            .http
+            .get(&url)
+            .timeout(CONTRACT_TIMEOUT)
+            .query(&[("tokens", CONTRACT_TOKENS.to_string()), ("type", "json".to_string())]);
+        let response = self.send(request).await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("HTTP {}", status.as_u16()));
+        }
+        response.text().await.map_err(|e| e.to_string())
+    }
+}
+
+/// Run [`Client::check_contract`] in the background so startup never waits for it
+pub fn spawn_preflight(client: Client) -> tokio::task::JoinHandle<ContractReport> {
+    tokio::spawn(async move { client.check_contract().await })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::mock::{MockResponse, MockServer};
+    use crate::docs_client::{DocsConfig, RetryPolicy};
+
+    async fn check(body: &'static str) -> (ContractReport, MockServer) {
+        let server = MockServer::start(move |req| {
+            assert_eq!(req.path, format!("/docs/code/{}", CONTRACT_LIBRARY));
+            assert_eq!(req.query("tokens"), Some(CONTRACT_TOKENS.to_string()));
+            MockResponse::json(200, body)
+        })
+        .await;
+        let client = Client::new(DocsConfig { base_url: server.base_url(), ..Default::default() })
+            .unwrap()
+            .with_retry(RetryPolicy::none());
+        (spawn_preflight(client).await.unwrap(), server)
+    }
+
+    #[tokio::test]
+    async fn test_matching_response_passes() {
+        let (report, _server) = check(include_str!("fixtures/docs_full.json")).await;
+        assert_eq!(report.status, ContractStatus::Ok);
+        assert_eq!(report.library, CONTRACT_LIBRARY);
+
+        // camelCase aliases are part of the contract
+        let camel = r#"{"snippets": [{"content": "x"}], "pagination": {"currentPage": 1, "totalPages": 2, "hasNext": true}}"#;
+        assert!(missing_fields(camel).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_partial_match_names_missing_fields() {
+        let body = r#"{"snippets": [{"content": "x", "title": "t"}], "pagination": {"current_page": 1, "has_next": false}}"#;
+        let (report, _server) = check(body).await;
+        assert_eq!(report.status, ContractStatus::Drift { missing: vec!["pagination.total_pages".to_string()] });
+        assert!(report.is_drift());
+
+        let (report, _server) = check(include_str!("fixtures/docs_no_pagination.json")).await;
+        let ContractStatus::Drift { missing } = report.status else { panic!("expected drift") };
+        assert!(missing.iter().all(|field| field.starts_with("pagination")), "{:?}", missing);
+    }
+
+    #[tokio::test]
+    async fn test_divergent_response_misses_everything() {
+        let (report, _server) = check(r#"{"data": {"items": [{"text": "x"}]}, "meta": {"pages": 3}}"#).await;
+        let expected: Vec<String> = DOCS_CONTRACT.iter().map(|path| first_name(path)).collect();
+        assert_eq!(report.status, ContractStatus::Drift { missing: expected.clone() });
+
+        // Markdown instead of JSON
+        assert_eq!(missing_fields("# Next.js\n\nSome docs"), expected);
+        // Snippets without any usable content
+        assert_eq!(
+            missing_fields(r#"{"snippets": [{"text": "x"}], "pagination": {"page": 1, "totalPages": 1, "hasNext": false}}"#),
+            vec!["snippets[].content".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_api_is_not_drift() {
+        let server = MockServer::start(|_| MockResponse::text(503, "down")).await;
+        let client = Client::new(DocsConfig { base_url: server.base_url(), ..Default::default() })
+            .unwrap()
+            .with_retry(RetryPolicy::none());
+        let report = client.check_contract().await;
+        assert!(matches!(report.status, ContractStatus::Unreachable { .. }));
+        assert!(!report.is_drift());
+    }
+}
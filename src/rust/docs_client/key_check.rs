@@ -0,0 +1,215 @@
+//! Check of the configured API keys against the docs API
+//!
+//! Each key in turn sends one library search on its own, outside the
+//! rotation, so a bad key shows up before an agent run depends on it instead
+//! of as a 401 halfway through. The answer tells whether the key is accepted
+//! and, from the rate-limit headers, how much of its quota is left. The API
+//! does not report an expiry date; a key it rejects as expired is recognised
+//! by the 401 message.
+//! The outcome is logged and kept for `/health`, `devkit doctor` and
+//! `devkit_stats`. Like the contract check, this never fails: an unreachable
+//! API is reported as such.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::keys::{mask_key, KeyPool};
+use super::quota::RateLimit;
+use super::{Client, RetryPolicy};
+use crate::log_important;
+
+/// Query of the check request; any library the index knows will do
+pub const KEY_CHECK_QUERY: &str = "react";
+
+/// Timeout of each check request
+const KEY_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of the last check
+static LAST_REPORT: Mutex<Option<KeyCheckReport>> = Mutex::new(None);
+
+/// What the API said about one key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum KeyHealth {
+    /// Accepted; quota as reported by the rate-limit headers
+    Valid { rate_limit: Option<RateLimit> },
+    /// Accepted but out of quota for now
+    RateLimited { rate_limit: Option<RateLimit> },
+    /// Rejected because it expired
+    Expired { message: String },
+    /// Rejected (401 or 403)
+    Invalid { code: u16, message: String },
+    /// No answer about the key; says nothing about it
+    Unreachable { error: String },
+}
+
+impl KeyHealth {
+    /// Whether the key needs replacing
+    pub fn is_problem(&self) -> bool {
+        matches!(self, KeyHealth::Expired { .. } | KeyHealth::Invalid { .. })
+    }
+}
+
+impl fmt::Display for KeyHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quota = |f: &mut fmt::Formatter<'_>, rate_limit: &Option<RateLimit>| match rate_limit {
+            Some(RateLimit { remaining: Some(remaining), limit, reset_secs }) => {
+                write!(f, ", {} of {} requests left", remaining, limit.map_or("?".to_string(), |n| n.to_string()))?;
+                match reset_secs {
+                    Some(secs) => write!(f, ", resets in {}s", secs),
+                    None => Ok(()),
+                }
+            }
+            _ => write!(f, ", quota not reported"),
+        };
+        match self {
+            KeyHealth::Valid { rate_limit } => {
+                write!(f, "valid")?;
+                quota(f, rate_limit)
+            }
+            KeyHealth::RateLimited { rate_limit } => {
+                write!(f, "valid but rate limited")?;
+                quota(f, rate_limit)
+            }
+            KeyHealth::Expired { message } => write!(f, "expired ({})", message),
+            KeyHealth::Invalid { code, message } => write!(f, "rejected with {} ({})", code, message),
+            KeyHealth::Unreachable { error } => write!(f, "not checked ({})", error),
+        }
+    }
+}
+
+/// Check result of one key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyCheck {
+    /// Masked key
+    pub key: String,
+    #[serde(flatten)]
+    pub health: KeyHealth,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyCheckReport {
+    pub keys: Vec<KeyCheck>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl KeyCheckReport {
+    /// Keys that are expired or rejected
+    pub fn problems(&self) -> impl Iterator<Item = &KeyCheck> {
+        self.keys.iter().filter(|check| check.health.is_problem())
+    }
+}
+
+/// Outcome of the last key check in this process, if one ran
+pub fn last_report() -> Option<KeyCheckReport> {
+    LAST_REPORT.lock().ok()?.clone()
+}
+
+impl Client {
+    /// Check every configured key with one search request each
+    ///
+    /// The report is logged and kept for [`last_report`]; without keys it is empty.
+    pub async fn check_keys(&self) -> KeyCheckReport {
+        let mut keys = Vec::new();
+        for key in self.config.keys() {
+            let health = self.check_key(&key).await;
+            let key = mask_key(&key);
+            if health.is_problem() {
+                log_important!(warn, "Docs API key {} is {}", key, health);
+            } else {
+                log_important!(info, "Docs API key {}: {}", key, health);
+            }
+            keys.push(KeyCheck { key, health });
+        }
+        let report = KeyCheckReport { keys, checked_at: Utc::now() };
+        if let Ok(mut last) = LAST_REPORT.lock() {
+            *last = Some(report.clone());
+        }
+        report
+    }
+
+    async fn check_key(&self, key: &str) -> KeyHealth {
+        // A pool of its own: the shared rotation would move on to another key
+        let client = self.clone().with_keys(Arc::new(KeyPool::new(&[key.to_string()]))).with_retry(RetryPolicy::none());
+        let url = format!("{}/search", self.config.base_url);
+        let request = client.http.get(&url).timeout(KEY_CHECK_TIMEOUT).query(&[("query", KEY_CHECK_QUERY)]);
+        let response = match client.send(request).await {
+            Ok(response) => response,
+            Err(e) => return KeyHealth::Unreachable { error: e.to_string() },
+        };
+        let status = response.status().as_u16();
+        let rate_limit = RateLimit::from_headers(response.headers());
+        match status {
+            200..=299 => KeyHealth::Valid { rate_limit },
+            429 => KeyHealth::RateLimited { rate_limit },
+            401 | 403 => {
+                let body = response.text().await.unwrap_or_default();
+                let message = crate::utils::text::truncate(body.trim(), 200).into_owned();
+                if message.to_lowercase().contains("expired") {
+                    KeyHealth::Expired { message }
+                } else {
+                    KeyHealth::Invalid { code: status, message }
+                }
+            }
+            _ => KeyHealth::Unreachable { error: format!("HTTP {}", status) },
+        }
+    }
+}
+
+/// Run [`Client::check_keys`] in the background so startup never waits for it
+pub fn spawn_key_check(client: Client) -> tokio::task::JoinHandle<KeyCheckReport> {
+    tokio::spawn(async move { client.check_keys().await })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::mock::{MockResponse, MockServer};
+    use crate::docs_client::DocsConfig;
+
+    #[tokio::test]
+    async fn test_each_key_is_checked_on_its_own() {
+        let server = MockServer::start(|req| {
+            assert_eq!(req.path, "/search");
+            assert_eq!(req.query("query").as_deref(), Some(KEY_CHECK_QUERY));
+            match req.header("authorization").unwrap_or_default().as_str() {
+                "Bearer good-key-000001" => MockResponse::json(200, r#"{"results": []}"#)
+                    .with_header("X-RateLimit-Limit", "1000")
+                    .with_header("X-RateLimit-Remaining", "998")
+                    .with_header("X-RateLimit-Reset", "3600"),
+                "Bearer old-key-0000002" => MockResponse::text(401, "API key expired on 2026-01-31"),
+                "Bearer busy-key-000003" => MockResponse::text(429, "slow down").with_header("Retry-After", "30"),
+                _ => MockResponse::text(401, "Invalid API key"),
+            }
+        })
+        .await;
+        let keys = ["good-key-000001", "old-key-0000002", "busy-key-000003", "typo-key-000004"].map(str::to_string);
+        let config = DocsConfig { base_url: server.base_url(), api_keys: keys.to_vec(), ..Default::default() };
+        let client = Client::new(config).unwrap();
+
+        let report = spawn_key_check(client).await.unwrap();
+        let health: Vec<_> = report.keys.iter().map(|check| check.health.clone()).collect();
+        assert_eq!(
+            health[0],
+            KeyHealth::Valid { rate_limit: Some(RateLimit { limit: Some(1000), remaining: Some(998), reset_secs: Some(3600) }) }
+        );
+        assert_eq!(health[1], KeyHealth::Expired { message: "API key expired on 2026-01-31".to_string() });
+        assert!(matches!(&health[2], KeyHealth::RateLimited { rate_limit: Some(RateLimit { reset_secs: Some(30), .. }) }));
+        assert_eq!(health[3], KeyHealth::Invalid { code: 401, message: "Invalid API key".to_string() });
+
+        assert_eq!(report.problems().count(), 2);
+        assert!(report.keys.iter().all(|check| !check.key.contains("-0000")), "keys must be masked: {:?}", report.keys);
+        assert_eq!(health[0].to_string(), "valid, 998 of 1000 requests left, resets in 3600s");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_api_says_nothing_about_the_key() {
+        let config = DocsConfig { base_url: "http://127.0.0.1:9".to_string(), api_key: Some("some-key-123456".to_string()), ..Default::default() };
+        let report = Client::new(config).unwrap().check_keys().await;
+        assert!(matches!(report.keys[0].health, KeyHealth::Unreachable { .. }));
+        assert_eq!(report.problems().count(), 0);
+    }
+}
@@ -0,0 +1,299 @@
+//! Rotation over several docs API keys
+//!
+//! Keys are used round-robin. A key answered with 401 is disabled for the rest
+//! of the process, one answered with 429 is benched until its `Retry-After`
+//! has passed; in both cases the request moves on to the next usable key.
+//! Keys only ever appear in logs and stats in masked form.
+
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::quota::RateLimit;
+use super::DocsError;
+use crate::log_important;
+
+/// Bench time for a 429 without a usable `Retry-After`
+pub const DEFAULT_BENCH: Duration = Duration::from_secs(60);
+
+/// Key to use for one request
+#[derive(Debug, Clone)]
+pub struct KeyLease {
+    index: usize,
+    pub key: String,
+}
+
+impl KeyLease {
+    pub fn masked(&self) -> String {
+        mask_key(&self.key)
+    }
+}
+
+/// Whether a key can currently be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum KeyStatus {
+    Active,
+    /// Rate limited; usable again after `secs`
+    Benched { secs: u64 },
+    /// Rejected with 401 for this session
+    Disabled,
+}
+
+impl fmt::Display for KeyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStatus::Active => write!(f, "active"),
+            KeyStatus::Benched { secs } => write!(f, "rate limited, {}s left", secs),
+            KeyStatus::Disabled => write!(f, "disabled (401)"),
+        }
+    }
+}
+
+/// Usage of one key, as shown by the stats action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyUsage {
+    /// Masked key
+    pub key: String,
+    pub requests: u64,
+    pub status: KeyStatus,
+}
+
+#[derive(Debug)]
+struct KeyState {
+    key: String,
+    requests: u64,
+    disabled: bool,
+    benched_until: Option<Instant>,
+}
+
+impl KeyState {
+    fn usable(&self, now: Instant) -> bool {
+        !self.disabled && self.benched_until.is_none_or(|until| until <= now)
+    }
+}
+
+#[derive(Debug)]
+struct PoolState {
+    keys: Vec<KeyState>,
+    /// Index the next rotation starts from
+    cursor: usize,
+}
+
+/// Round-robin pool of API keys with per-key cooldowns
+#[derive(Debug)]
+pub struct KeyPool {
+    state: Mutex<PoolState>,
+}
+
+/// Pool of the configured key list, shared by every client in this process
+static SHARED_POOL: Mutex<Option<Arc<KeyPool>>> = Mutex::new(None);
+
+impl KeyPool {
+    pub fn new(keys: &[String]) -> Self {
+        let keys = keys
+            .iter()
+            .map(|key| KeyState { key: key.clone(), requests: 0, disabled: false, benched_until: None })
+            .collect();
+        Self { state: Mutex::new(PoolState { keys, cursor: 0 }) }
+    }
+
+    /// Process-wide pool for `keys`; a changed key list starts a fresh pool
+    pub fn shared(keys: &[String]) -> Arc<KeyPool> {
+        let mut shared = SHARED_POOL.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(pool) = shared.as_ref() {
+            if pool.keys() == keys {
+                return pool.clone();
+            }
+        }
+        let pool = Arc::new(KeyPool::new(keys));
+        *shared = Some(pool.clone());
+        pool
+    }
+
+    /// Pool last returned by [`KeyPool::shared`], if any
+    pub fn current() -> Option<Arc<KeyPool>> {
+        SHARED_POOL.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PoolState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.lock().keys.iter().map(|k| k.key.clone()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Next usable key in rotation
+    ///
+    /// `Ok(None)` when no keys are configured. When every key is disabled or
+    /// benched, the error the last upstream answer would have produced.
+    pub fn next(&self) -> Result<Option<KeyLease>, DocsError> {
+        let mut state = self.lock();
+        let count = state.keys.len();
+        if count == 0 {
+            return Ok(None);
+        }
+
+        let now = Instant::now();
+        let start = state.cursor;
+        let Some(index) = (0..count).map(|offset| (start + offset) % count).find(|&i| state.keys[i].usable(now)) else {
+            return Err(if state.keys.iter().all(|k| k.disabled) {
+                DocsError::Unauthorized
+            } else {
+                // Until the first benched key is usable again
+                let retry_after = state
+                    .keys
+                    .iter()
+                    .filter(|k| !k.disabled)
+                    .filter_map(|k| k.benched_until)
+                    .min()
+                    .map(|until| until.saturating_duration_since(now));
+                DocsError::RateLimited { retry_after }
+            });
+        };
+
+        state.cursor = (index + 1) % count;
+        let entry = &mut state.keys[index];
+        entry.benched_until = None;
+        entry.requests += 1;
+        Ok(Some(KeyLease { index, key: entry.key.clone() }))
+    }
+
+    /// Record the answer to a request made with `lease`
+    ///
+    /// Returns true when the key was taken out of rotation, i.e. the request
+    /// is worth repeating with another key.
+    pub fn report(&self, lease: &KeyLease, status: u16, headers: &HeaderMap) -> bool {
+        let mut state = self.lock();
+        let Some(entry) = state.keys.get_mut(lease.index) else {
+            return false;
+        };
+        match status {
+            401 => {
+                entry.disabled = true;
+                log_important!(warn, "Docs API key {} was rejected (401), disabled for this session", lease.masked());
+                true
+            }
+            429 => {
+                let bench = RateLimit::from_headers(headers)
+                    .and_then(|r| r.reset_secs)
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_BENCH);
+                entry.benched_until = Some(Instant::now() + bench);
+                log_important!(warn, "Docs API key {} is rate limited, benched for {}s", lease.masked(), bench.as_secs());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// True when [`KeyPool::next`] would hand out a key
+    pub fn has_usable(&self) -> bool {
+        let now = Instant::now();
+        self.lock().keys.iter().any(|k| k.usable(now))
+    }
+
+    /// Per-key request counts and status, keys masked
+    pub fn usage(&self) -> Vec<KeyUsage> {
+        let now = Instant::now();
+        self.lock()
+            .keys
+            .iter()
+            .map(|k| KeyUsage {
+                key: mask_key(&k.key),
+                requests: k.requests,
+                status: if k.disabled {
+                    KeyStatus::Disabled
+                } else if let Some(until) = k.benched_until.filter(|until| *until > now) {
+                    KeyStatus::Benched { secs: (until - now).as_secs().max(1) }
+                } else {
+                    KeyStatus::Active
+                },
+            })
+            .collect()
+    }
+
+    /// Zero the request counters; disabled and benched keys stay out of rotation
+    pub fn reset_counters(&self) {
+        for key in self.lock().keys.iter_mut() {
+            key.requests = 0;
+        }
+    }
+}
+
+/// Key reduced to a recognizable but useless form, e.g. `ctx7…9f2a`
+pub fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() < 12 {
+        return "****".to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn pool(keys: &[&str]) -> KeyPool {
+        KeyPool::new(&keys.iter().map(|k| k.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_mask_key() {
+        assert_eq!(mask_key("ctx7sk-0123456789abcdef"), "ctx7…cdef");
+        assert_eq!(mask_key("short"), "****");
+    }
+
+    #[test]
+    fn test_round_robin_skips_benched_and_disabled_keys() {
+        let pool = pool(&["key-a", "key-b", "key-c"]);
+        let order: Vec<_> = (0..4).map(|_| pool.next().unwrap().unwrap().key).collect();
+        assert_eq!(order, vec!["key-a", "key-b", "key-c", "key-a"]);
+
+        let lease = pool.next().unwrap().unwrap();
+        assert_eq!(lease.key, "key-b");
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("30"));
+        assert!(pool.report(&lease, 429, &headers));
+
+        let lease = pool.next().unwrap().unwrap();
+        assert_eq!(lease.key, "key-c");
+        assert!(pool.report(&lease, 401, &HeaderMap::new()));
+        assert!(!pool.report(&pool.next().unwrap().unwrap(), 200, &HeaderMap::new()));
+
+        let usage = pool.usage();
+        assert_eq!(usage[0].status, KeyStatus::Active);
+        assert!(matches!(usage[1].status, KeyStatus::Benched { secs } if secs <= 30));
+        assert_eq!(usage[2].status, KeyStatus::Disabled);
+        assert_eq!(pool.next().unwrap().unwrap().key, "key-a");
+    }
+
+    #[test]
+    fn test_exhausted_pool_errors() {
+        let pool = pool(&["key-a"]);
+        assert!(pool.next().unwrap().is_some());
+        let lease = pool.next().unwrap().unwrap();
+        pool.report(&lease, 429, &HeaderMap::new());
+        assert!(matches!(
+            pool.next(),
+            Err(DocsError::RateLimited { retry_after: Some(wait) }) if wait > Duration::from_secs(55)
+        ));
+        pool.report(&lease, 401, &HeaderMap::new());
+        assert!(matches!(pool.next(), Err(DocsError::Unauthorized)));
+        assert!(KeyPool::new(&[]).next().unwrap().is_none());
+    }
+}
@@ -0,0 +1,249 @@
+//! Upstream request metrics
+//!
+//! Every upstream attempt is counted per [`Operation`]: the status code it got,
+//! or a failure when no response came back, and the time until the response
+//! headers arrived in a histogram over [`LATENCY_BUCKETS_MS`]. Bodies read by
+//! the client add to the bytes received. The registry is shared by every client
+//! in the process; the `devkit_stats` tool and the HTTP server's `/metrics`
+//! report it next to the cache hit rate. The last upstream error is kept too,
+//! for `docs_status`.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Upper bounds (ms) of the latency histogram buckets; slower attempts land in
+/// one more bucket past the last bound
+pub const LATENCY_BUCKETS_MS: &[u64] = &[50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000];
+
+/// Kind of upstream request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Docs,
+    Search,
+}
+
+impl Operation {
+    /// Operation of a request to `url`
+    pub fn of(url: &reqwest::Url) -> Self {
+        if url.path().trim_end_matches('/').ends_with("/search") {
+            Operation::Search
+        } else {
+            Operation::Docs
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Docs => "docs",
+            Operation::Search => "search",
+        }
+    }
+}
+
+/// Latency histogram of one operation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Latency {
+    pub count: u64,
+    pub sum_ms: u64,
+    pub max_ms: u64,
+    /// Attempts per bucket of [`LATENCY_BUCKETS_MS`] (not cumulative), the last
+    /// one counting attempts slower than every bound
+    pub buckets: Vec<u64>,
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Self { count: 0, sum_ms: 0, max_ms: 0, buckets: vec![0; LATENCY_BUCKETS_MS.len() + 1] }
+    }
+}
+
+impl Latency {
+    fn record(&mut self, elapsed: Duration) {
+        let ms = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        self.count += 1;
+        self.sum_ms = self.sum_ms.saturating_add(ms);
+        self.max_ms = self.max_ms.max(ms);
+        let bucket = LATENCY_BUCKETS_MS.iter().position(|&bound| ms <= bound).unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    pub fn mean_ms(&self) -> Option<u64> {
+        (self.count > 0).then(|| self.sum_ms / self.count)
+    }
+
+    /// Bucket bound below which a `quantile` (0..=1) of the attempts finished;
+    /// `max_ms` when that falls in the last, unbounded bucket
+    pub fn quantile_ms(&self, quantile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((self.count as f64) * quantile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(LATENCY_BUCKETS_MS.get(bucket).copied().unwrap_or(self.max_ms).min(self.max_ms));
+            }
+        }
+        Some(self.max_ms)
+    }
+}
+
+/// Counters of one operation
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OperationMetrics {
+    /// Attempts sent upstream, with or without a response
+    pub requests: u64,
+    /// Attempts that got no response (timeout, connection or TLS error)
+    pub failures: u64,
+    /// Responses per status code
+    pub statuses: BTreeMap<u16, u64>,
+    pub latency: Latency,
+    pub bytes_received: u64,
+}
+
+/// Most recent request that failed or got an error status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UpstreamError {
+    pub operation: Operation,
+    /// Status code, `None` when no response came back
+    pub status: Option<u16>,
+    pub message: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Point-in-time copy of [`Metrics`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricsSnapshot {
+    pub since: DateTime<Utc>,
+    pub operations: BTreeMap<Operation, OperationMetrics>,
+    pub last_error: Option<UpstreamError>,
+}
+
+impl MetricsSnapshot {
+    fn empty() -> Self {
+        Self { since: Utc::now(), operations: BTreeMap::new(), last_error: None }
+    }
+}
+
+/// Metrics registry of the upstream requests
+#[derive(Debug)]
+pub struct Metrics {
+    state: Mutex<MetricsSnapshot>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self { state: Mutex::new(MetricsSnapshot::empty()) }
+    }
+}
+
+impl Metrics {
+    /// Registry shared by every client in this process
+    pub fn shared() -> Arc<Metrics> {
+        static SHARED: Lazy<Arc<Metrics>> = Lazy::new(Arc::default);
+        SHARED.clone()
+    }
+
+    fn update(&self, operation: Operation, update: impl FnOnce(&mut OperationMetrics)) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        update(state.operations.entry(operation).or_default());
+    }
+
+    /// Count an attempt answered with `status` after `elapsed`
+    pub fn record_response(&self, operation: Operation, status: u16, elapsed: Duration) {
+        self.update(operation, |metrics| {
+            metrics.requests += 1;
+            *metrics.statuses.entry(status).or_default() += 1;
+            metrics.latency.record(elapsed);
+        });
+    }
+
+    /// Count an attempt that failed without a response after `elapsed`
+    pub fn record_failure(&self, operation: Operation, elapsed: Duration) {
+        self.update(operation, |metrics| {
+            metrics.requests += 1;
+            metrics.failures += 1;
+            metrics.latency.record(elapsed);
+        });
+    }
+
+    /// Count a response body of `bytes`
+    pub fn record_bytes(&self, operation: Operation, bytes: usize) {
+        self.update(operation, |metrics| metrics.bytes_received += bytes as u64);
+    }
+
+    /// Remember a request that failed (`status` is `None`) or was answered with an error status
+    pub fn record_error(&self, operation: Operation, status: Option<u16>, message: impl Into<String>) {
+        let error = UpstreamError { operation, status, message: message.into(), at: Utc::now() };
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).last_error = Some(error);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Start counting from zero
+    pub fn reset(&self) {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = MetricsSnapshot::empty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_statuses_failures_latency_and_bytes() {
+        let metrics = Metrics::default();
+        metrics.record_response(Operation::Docs, 200, Duration::from_millis(40));
+        metrics.record_response(Operation::Docs, 200, Duration::from_millis(300));
+        metrics.record_response(Operation::Docs, 429, Duration::from_millis(90));
+        metrics.record_failure(Operation::Docs, Duration::from_secs(45));
+        metrics.record_bytes(Operation::Docs, 1_500);
+        metrics.record_response(Operation::Search, 200, Duration::from_millis(120));
+
+        let snapshot = metrics.snapshot();
+        let docs = &snapshot.operations[&Operation::Docs];
+        assert_eq!((docs.requests, docs.failures, docs.bytes_received), (4, 1, 1_500));
+        assert_eq!(docs.statuses, BTreeMap::from([(200, 2), (429, 1)]));
+        assert_eq!(docs.latency.buckets, vec![1, 1, 0, 1, 0, 0, 0, 0, 0, 1]);
+        assert_eq!((docs.latency.count, docs.latency.max_ms), (4, 45_000));
+        assert_eq!(docs.latency.mean_ms(), Some(11_357));
+        assert_eq!(docs.latency.quantile_ms(0.5), Some(100));
+        assert_eq!(docs.latency.quantile_ms(0.99), Some(45_000));
+        assert_eq!(snapshot.operations[&Operation::Search].requests, 1);
+        assert_eq!(snapshot.last_error, None);
+
+        metrics.record_error(Operation::Docs, Some(429), "HTTP 429 Too Many Requests");
+        let error = metrics.snapshot().last_error.unwrap();
+        assert_eq!((error.operation, error.status, error.message.as_str()), (Operation::Docs, Some(429), "HTTP 429 Too Many Requests"));
+
+        metrics.reset();
+        assert!(metrics.snapshot().operations.is_empty());
+        assert_eq!(metrics.snapshot().last_error, None);
+        assert_eq!(Latency::default().quantile_ms(0.5), None);
+    }
+
+    #[test]
+    fn test_operation_of_url() {
+        let url = |url: &str| reqwest::Url::parse(url).unwrap();
+        assert_eq!(Operation::of(&url("https://context7.com/api/v2/search?query=next")), Operation::Search);
+        assert_eq!(Operation::of(&url("https://context7.com/api/v2/docs/code/vercel/next.js")), Operation::Docs);
+        // Library names mentioning search are still docs requests
+        assert_eq!(Operation::of(&url("https://context7.com/api/v2/docs/code/acme/search-kit")), Operation::Docs);
+    }
+
+    #[test]
+    fn test_snapshot_serializes_operations_and_statuses_as_keys() {
+        let metrics = Metrics::default();
+        metrics.record_response(Operation::Search, 503, Duration::from_millis(10));
+        let value = serde_json::to_value(metrics.snapshot()).unwrap();
+        assert_eq!(value["operations"]["search"]["statuses"]["503"], 1);
+    }
+}
@@ -0,0 +1,201 @@
+//! Tolerant parsing of docs API responses
+//!
+//! The upstream API adds and drops fields without notice. Bodies are staged as
+//! `serde_json::Value` first; when the strict parse fails, the snippet/result
+//! arrays are salvaged item by item and the offending JSON path is logged.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::types::{DocsResponse, DocumentSnippet, PaginationInfo, SearchResponse};
+use super::DocsError;
+use crate::log_important;
+
+impl DocsResponse {
+    /// Parse a docs body: structured JSON when possible, otherwise plain Markdown as one snippet
+    pub fn from_body(body: &str) -> Self {
+        let trimmed = body.trim();
+        if trimmed.is_empty() {
+            return Self::default();
+        }
+
+        if trimmed.starts_with('{') {
+            if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+                if value.get("snippets").is_some() {
+                    return Self::from_value(value);
+                }
+            }
+        }
+
+        Self {
+            snippets: vec![DocumentSnippet {
+                content: body.to_string(),
+                title: None,
+                score: None,
+            }],
+            ..Self::default()
+        }
+    }
+
+    /// Strict parse with a lenient fallback that keeps every snippet that parses
+    fn from_value(value: Value) -> Self {
+        match parse_logged::<DocsResponse>(&value, "") {
+            Some(response) => response,
+            None => Self {
+                snippets: salvage_array(&value, "snippets"),
+                pagination: value
+                    .get("pagination")
+                    .filter(|v| !v.is_null())
+                    .and_then(|v| parse_logged::<PaginationInfo>(v, "pagination")),
+                ..Self::default()
+            },
+        }
+    }
+}
+
+impl SearchResponse {
+    /// Parse a search body, salvaging individual results when the envelope is malformed
+    pub fn from_body(body: &str) -> Result<Self, DocsError> {
+        let value: Value = serde_json::from_str(body)
+            .map_err(|e| DocsError::Parse(format!("search response: {}", e)))?;
+
+        if !value.is_object() {
+            return Err(DocsError::Parse("search response: expected a JSON object".to_string()));
+        }
+
+        Ok(parse_logged::<SearchResponse>(&value, "").unwrap_or_else(|| SearchResponse {
+            results: salvage_array(&value, "results"),
+        }))
+    }
+}
+
+/// Deserialize `value`, logging the JSON path of the first failure
+fn parse_logged<T: DeserializeOwned>(value: &Value, prefix: &str) -> Option<T> {
+    match serde_path_to_error::deserialize::<_, T>(value) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            log_important!(
+                warn,
+                "Docs response field failed to parse at {}: {}",
+                join_path(prefix, &e.path().to_string()),
+                e.inner()
+            );
+            None
+        }
+    }
+}
+
+/// Parse each element of `value[key]`, skipping the ones that fail
+fn salvage_array<T: DeserializeOwned>(value: &Value, key: &str) -> Vec<T> {
+    let Some(items) = value.get(key).and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, item)| parse_logged::<T>(item, &format!("{}[{}]", key, idx)))
+        .collect()
+}
+
+fn join_path(prefix: &str, path: &str) -> String {
+    match (prefix.is_empty(), path == ".") {
+        (true, _) => path.to_string(),
+        (false, true) => prefix.to_string(),
+        (false, false) => format!("{}.{}", prefix, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::types::LibraryState;
+
+    const DOCS_FULL: &str = include_str!("fixtures/docs_full.json");
+    const DOCS_EXTRA_FIELDS: &str = include_str!("fixtures/docs_extra_fields.json");
+    const DOCS_NO_PAGINATION: &str = include_str!("fixtures/docs_no_pagination.json");
+    const DOCS_BAD_SIBLINGS: &str = include_str!("fixtures/docs_bad_siblings.json");
+    const SEARCH_MIXED: &str = include_str!("fixtures/search_mixed.json");
+
+    #[test]
+    fn test_full_fixture() {
+        let response = DocsResponse::from_body(DOCS_FULL);
+        assert_eq!(response.snippets.len(), 2);
+        assert_eq!(response.snippets[0].title.as_deref(), Some("App Router"));
+        let pagination = response.pagination.unwrap();
+        assert_eq!((pagination.current_page, pagination.total_pages), (1, 4));
+        assert!(pagination.has_next);
+    }
+
+    #[test]
+    fn test_extra_fields_and_camel_case() {
+        let response = DocsResponse::from_body(DOCS_EXTRA_FIELDS);
+        assert_eq!(response.snippets.len(), 2);
+        assert_eq!(response.snippets[0].title.as_deref(), Some("Middleware"));
+        assert_eq!(response.snippets[1].title, None);
+        let pagination = response.pagination.unwrap();
+        assert_eq!(pagination.total_pages, 2);
+        assert!(pagination.has_next);
+    }
+
+    #[test]
+    fn test_missing_pagination_and_null_titles() {
+        let response = DocsResponse::from_body(DOCS_NO_PAGINATION);
+        assert!(response.pagination.is_none());
+        assert!(response.snippets.iter().all(|s| s.title.is_none()));
+        assert_eq!(response.snippets.len(), 2);
+    }
+
+    #[test]
+    fn test_salvages_snippets_when_siblings_fail() {
+        let response = DocsResponse::from_body(DOCS_BAD_SIBLINGS);
+        // The malformed snippet is skipped, the others survive
+        assert_eq!(response.snippets.len(), 2);
+        assert_eq!(response.snippets[1].content, "third");
+        assert!(response.pagination.is_none());
+    }
+
+    #[test]
+    fn test_search_salvages_results_and_tolerates_unknown_state() {
+        let response = SearchResponse::from_body(SEARCH_MIXED).unwrap();
+        let ids: Vec<_> = response.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["/facebook/react", "/preactjs/preact"]);
+        assert_eq!(response.results[0].state, Some(LibraryState::Finalized));
+        assert_eq!(response.results[1].state, Some(LibraryState::Other));
+        assert_eq!(response.results[0].versions, ["v18.3.1", "v19.0.0"]);
+        assert!(response.results[1].versions.is_empty());
+
+        assert!(SearchResponse::from_body("[]").is_err());
+        assert!(SearchResponse::from_body("{}").unwrap().results.is_empty());
+    }
+
+    #[test]
+    fn test_non_json_bodies_become_text() {
+        let response = DocsResponse::from_body("{ not json, just braces in markdown");
+        assert_eq!(response.snippets.len(), 1);
+        let response = DocsResponse::from_body(r#"{"error":"upstream hiccup"}"#);
+        assert!(response.snippets[0].content.contains("upstream hiccup"));
+        assert!(DocsResponse::from_body("  \n").snippets.is_empty());
+    }
+
+    #[test]
+    fn test_random_mutations_never_panic() {
+        let replacements = ["null", "1", "-3.5", "\"x\"", "[]", "{}", "true", "", "}", "\"", ","];
+        let mut rng = fastrand::Rng::with_seed(0x5eed);
+
+        for fixture in [DOCS_FULL, DOCS_EXTRA_FIELDS, DOCS_NO_PAGINATION, SEARCH_MIXED] {
+            let chars: Vec<char> = fixture.chars().collect();
+            for _ in 0..500 {
+                let mut mutated = String::new();
+                let start = rng.usize(..chars.len());
+                let end = (start + rng.usize(..8)).min(chars.len());
+                mutated.extend(&chars[..start]);
+                mutated.push_str(replacements[rng.usize(..replacements.len())]);
+                mutated.extend(&chars[end..]);
+
+                let _ = DocsResponse::from_body(&mutated);
+                let _ = SearchResponse::from_body(&mutated);
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Per-process usage of the docs API quota
+//!
+//! Every upstream request is counted, and the rate-limit headers of the latest
+//! response are kept, so tool output can occasionally tell free-tier users how
+//! much of the limit this session has used.
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::utils::locale::Message;
+
+/// Default number of upstream requests between two usage footers
+pub const DEFAULT_FOOTER_EVERY: u64 = 10;
+/// At or below this many remaining requests the footer is shown on every call
+pub const LOW_REMAINING: u64 = 10;
+
+/// Rate-limit state reported by the last response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Seconds until the window resets
+    pub reset_secs: Option<u64>,
+}
+
+impl RateLimit {
+    /// Parse `X-RateLimit-*` (or the draft-standard `RateLimit-*`) headers
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let number = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| headers.get(*name))
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let rate_limit = Self {
+            limit: number(&["x-ratelimit-limit", "ratelimit-limit"]),
+            remaining: number(&["x-ratelimit-remaining", "ratelimit-remaining"]),
+            reset_secs: number(&["x-ratelimit-reset", "ratelimit-reset", "retry-after"]),
+        };
+        (rate_limit != Self::default()).then_some(rate_limit)
+    }
+}
+
+/// Request counter and latest rate-limit state of this process
+#[derive(Debug)]
+pub struct SessionUsage {
+    requests: AtomicU64,
+    /// Request count when the last footer was produced
+    footer_at: AtomicU64,
+    state: Mutex<UsageState>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UsageState {
+    rate_limit: Option<RateLimit>,
+    since: DateTime<Utc>,
+}
+
+/// Point-in-time copy of [`SessionUsage`]
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct UsageSnapshot {
+    pub requests: u64,
+    pub rate_limit: Option<RateLimit>,
+    pub since: DateTime<Utc>,
+}
+
+impl fmt::Display for UsageSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "this session: {} request{}", self.requests, if self.requests == 1 { "" } else { "s" })?;
+        if let Some(remaining) = self.rate_limit.and_then(|r| r.remaining) {
+            write!(f, "; ~{} remaining before rate limit", remaining)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for SessionUsage {
+    fn default() -> Self {
+        Self {
+            requests: AtomicU64::new(0),
+            footer_at: AtomicU64::new(0),
+            state: Mutex::new(UsageState { rate_limit: None, since: Utc::now() }),
+        }
+    }
+}
+
+impl SessionUsage {
+    /// Usage shared by every client in this process
+    pub fn shared() -> Arc<SessionUsage> {
+        static SHARED: Lazy<Arc<SessionUsage>> = Lazy::new(Arc::default);
+        SHARED.clone()
+    }
+
+    /// Count one upstream response and remember its rate-limit headers
+    pub fn record(&self, status: u16, headers: &HeaderMap) {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        let mut rate_limit = RateLimit::from_headers(headers);
+        if status == 429 {
+            rate_limit.get_or_insert_with(RateLimit::default).remaining = Some(0);
+        }
+        if let (Some(rate_limit), Ok(mut state)) = (rate_limit, self.state.lock()) {
+            state.rate_limit = Some(rate_limit);
+        }
+    }
+
+    pub fn snapshot(&self) -> UsageSnapshot {
+        let state = self.state.lock().map(|s| *s).unwrap_or_else(|e| *e.into_inner());
+        UsageSnapshot {
+            requests: self.requests.load(Ordering::SeqCst),
+            rate_limit: state.rate_limit,
+            since: state.since,
+        }
+    }
+
+    /// Footer line, when due: once every `every` requests (0 disables that
+    /// cadence) and on every call once the remaining quota is low, in the
+    /// current locale
+    pub fn footer(&self, every: u64) -> Option<String> {
+        let snapshot = self.snapshot();
+        let low = snapshot
+            .rate_limit
+            .and_then(|r| r.remaining)
+            .is_some_and(|remaining| remaining <= LOW_REMAINING);
+        let last = self.footer_at.load(Ordering::SeqCst);
+        let due = every > 0 && snapshot.requests / every > last / every;
+
+        if snapshot.requests == 0 || !(low || due) {
+            return None;
+        }
+        self.footer_at.store(snapshot.requests, Ordering::SeqCst);
+        let remaining = snapshot.rate_limit.and_then(|r| r.remaining);
+        Some(Message::QuotaFooter { requests: snapshot.requests, remaining }.text())
+    }
+
+    /// Start counting from zero again
+    pub fn reset(&self) {
+        self.requests.store(0, Ordering::SeqCst);
+        self.footer_at.store(0, Ordering::SeqCst);
+        if let Ok(mut state) = self.state.lock() {
+            *state = UsageState { rate_limit: None, since: Utc::now() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(remaining: u64) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("60"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+        headers
+    }
+
+    #[test]
+    fn test_parses_rate_limit_headers() {
+        let rate_limit = RateLimit::from_headers(&headers(13)).unwrap();
+        assert_eq!(rate_limit.limit, Some(60));
+        assert_eq!(rate_limit.remaining, Some(13));
+        assert!(RateLimit::from_headers(&HeaderMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_footer_cadence() {
+        let usage = SessionUsage::default();
+        let mut footers = Vec::new();
+        for n in 1..=7 {
+            usage.record(200, &headers(100 - n));
+            footers.push(usage.footer(3));
+        }
+        let shown: Vec<usize> = footers.iter().enumerate().filter(|(_, f)| f.is_some()).map(|(i, _)| i + 1).collect();
+        assert_eq!(shown, vec![3, 6]);
+        assert_eq!(footers[5].as_deref(), Some("_This session: 6 requests; ~94 remaining before rate limit._"));
+    }
+
+    #[test]
+    fn test_footer_on_every_call_when_remaining_is_low() {
+        let usage = SessionUsage::default();
+        usage.record(200, &headers(LOW_REMAINING + 1));
+        assert!(usage.footer(0).is_none());
+        usage.record(200, &headers(LOW_REMAINING));
+        assert!(usage.footer(0).unwrap().contains(&format!("~{} remaining", LOW_REMAINING)));
+        usage.record(429, &HeaderMap::new());
+        assert!(usage.footer(0).unwrap().contains("~0 remaining"));
+    }
+
+    #[test]
+    fn test_reset() {
+        let usage = SessionUsage::default();
+        usage.record(200, &headers(5));
+        usage.reset();
+        let snapshot = usage.snapshot();
+        assert_eq!(snapshot.requests, 0);
+        assert!(snapshot.rate_limit.is_none());
+        assert!(usage.footer(1).is_none());
+    }
+}
@@ -0,0 +1,180 @@
+//! Confidence that a search hit is the library the caller meant
+//!
+//! Used by auto-resolve: when a library id 404s, the top search hit is fetched
+//! instead if its score reaches [`AUTO_RESOLVE_THRESHOLD`].
+//!
+//! A bare name such as `nextjs` or `spring boot` is not an id at all. Well-known
+//! names map to their id through a bundled alias table ([`alias`]); other bare
+//! names go straight to the search instead of a request that can only 404.
+//! What the search resolves is remembered per cache directory
+//! ([`LearnedAliases`](super::aliases::LearnedAliases)).
+
+use super::types::SearchResult;
+
+/// Minimum confidence for fetching a search hit in place of the requested library
+pub const AUTO_RESOLVE_THRESHOLD: f64 = 0.85;
+
+/// Weight of the name similarity; the rest comes from the trust score
+const NAME_WEIGHT: f64 = 0.75;
+
+/// Trust score used when the API does not report one
+const UNKNOWN_TRUST: f64 = 0.5;
+
+/// Well-known libraries by normalized name (see [`normalize`])
+const ALIASES: &[(&str, &str)] = &[
+    ("angular", "angular/angular"),
+    ("astro", "withastro/astro"),
+    ("axum", "tokio-rs/axum"),
+    ("django", "django/django"),
+    ("express", "expressjs/express"),
+    ("expressjs", "expressjs/express"),
+    ("fastapi", "fastapi/fastapi"),
+    ("flask", "pallets/flask"),
+    ("laravel", "laravel/framework"),
+    ("nestjs", "nestjs/nest"),
+    ("next", "vercel/next.js"),
+    ("nextjs", "vercel/next.js"),
+    ("node", "nodejs/node"),
+    ("nodejs", "nodejs/node"),
+    ("nuxt", "nuxt/nuxt"),
+    ("nuxtjs", "nuxt/nuxt"),
+    ("prisma", "prisma/prisma"),
+    ("rails", "rails/rails"),
+    ("react", "facebook/react"),
+    ("reactjs", "facebook/react"),
+    ("remix", "remix-run/remix"),
+    ("serde", "serde-rs/serde"),
+    ("spring", "spring-projects/spring-framework"),
+    ("springboot", "spring-projects/spring-boot"),
+    ("springframework", "spring-projects/spring-framework"),
+    ("svelte", "sveltejs/svelte"),
+    ("sveltekit", "sveltejs/kit"),
+    ("tailwind", "tailwindlabs/tailwindcss"),
+    ("tailwindcss", "tailwindlabs/tailwindcss"),
+    ("tauri", "tauri-apps/tauri"),
+    ("tokio", "tokio-rs/tokio"),
+    ("typescript", "microsoft/typescript"),
+    ("vite", "vitejs/vite"),
+    ("vue", "vuejs/core"),
+    ("vuejs", "vuejs/core"),
+];
+
+/// Whether `library` has the `owner/repo` shape of a library id
+pub fn is_library_id(library: &str) -> bool {
+    let library = library.trim().trim_matches('/');
+    library.contains('/') && !library.contains(char::is_whitespace)
+}
+
+/// Id of a well-known library given by bare name, e.g. `Next.js` or `spring boot`
+pub fn alias(library: &str) -> Option<&'static str> {
+    if is_library_id(library) {
+        return None;
+    }
+    let name = normalize(library);
+    ALIASES.iter().find(|(alias, _)| *alias == name).map(|(_, id)| *id)
+}
+
+/// Score in 0..=1 combining name similarity and the library's trust score
+pub fn match_confidence(requested: &str, result: &SearchResult) -> f64 {
+    let wanted = normalize(repo_name(requested));
+    if wanted.is_empty() {
+        return 0.0;
+    }
+
+    let similarity = [Some(repo_name(&result.id)), result.title.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(|name| strsim::normalized_levenshtein(&wanted, &normalize(name)))
+        .fold(0.0, f64::max);
+    let trust = result
+        .trust_score
+        .map(|score| (score / 10.0).clamp(0.0, 1.0))
+        .unwrap_or(UNKNOWN_TRUST);
+
+    NAME_WEIGHT * similarity + (1.0 - NAME_WEIGHT) * trust
+}
+
+/// The search hit that is `library` itself, ignoring case and slashes
+pub fn find_library<'a>(results: &'a [SearchResult], library: &str) -> Option<&'a SearchResult> {
+    let wanted = library.trim().trim_matches('/');
+    results.iter().find(|result| library_id(result).eq_ignore_ascii_case(wanted))
+}
+
+/// Library id of a search hit, without the leading slash
+pub fn library_id(result: &SearchResult) -> String {
+    result.id.trim_matches('/').to_string()
+}
+
+/// Last path segment: `facebook/react` -> `react`
+fn repo_name(library: &str) -> &str {
+    library.trim_matches('/').rsplit('/').next().unwrap_or(library)
+}
+
+/// Lower-case alphanumerics only, so `next.js` matches `nextjs`
+pub(super) fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(id: &str, title: Option<&str>, trust: Option<f64>) -> SearchResult {
+        serde_json::from_value(serde_json::json!({ "id": id, "title": title, "trustScore": trust })).unwrap()
+    }
+
+    #[test]
+    fn test_exact_repo_name_with_high_trust_resolves() {
+        let react = hit("/facebook/react", Some("React"), Some(9.5));
+        assert!(match_confidence("react/react", &react) >= AUTO_RESOLVE_THRESHOLD);
+        assert!(match_confidence("react", &react) >= AUTO_RESOLVE_THRESHOLD);
+
+        let next = hit("/vercel/next.js", Some("Next.js"), Some(10.0));
+        assert!(match_confidence("nextjs", &next) >= AUTO_RESOLVE_THRESHOLD);
+    }
+
+    #[test]
+    fn test_weak_matches_stay_below_threshold() {
+        let preact = hit("/preactjs/preact", Some("Preact"), Some(8.0));
+        assert!(match_confidence("react", &preact) < AUTO_RESOLVE_THRESHOLD);
+
+        // Right name but an untrusted fork
+        let fork = hit("/someone/react", None, Some(2.0));
+        assert!(match_confidence("react", &fork) < AUTO_RESOLVE_THRESHOLD);
+
+        assert_eq!(match_confidence("/", &fork), 0.0);
+    }
+
+    #[test]
+    fn test_bare_names_resolve_through_aliases() {
+        assert_eq!(alias("nextjs"), Some("vercel/next.js"));
+        assert_eq!(alias(" Next.js "), Some("vercel/next.js"));
+        assert_eq!(alias("spring boot"), Some("spring-projects/spring-boot"));
+        assert_eq!(alias("React"), Some("facebook/react"));
+        assert_eq!(alias("facebook/react"), None);
+        assert_eq!(alias("left-pad"), None);
+        assert!(ALIASES.iter().all(|(alias, id)| normalize(alias) == *alias && is_library_id(id)));
+    }
+
+    #[test]
+    fn test_library_id_shape() {
+        assert!(is_library_id("/vercel/next.js/"));
+        assert!(!is_library_id("nextjs"));
+        assert!(!is_library_id("spring boot/starter"));
+    }
+
+    #[test]
+    fn test_find_library_matches_the_exact_id() {
+        let results = [hit("/preactjs/preact", None, None), hit("/facebook/react", None, None)];
+        assert_eq!(find_library(&results, "Facebook/React/").unwrap().id, "/facebook/react");
+        assert!(find_library(&results, "react").is_none());
+    }
+
+    #[test]
+    fn test_library_id_strips_slashes() {
+        assert_eq!(library_id(&hit("/facebook/react", None, None)), "facebook/react");
+    }
+}
@@ -0,0 +1,280 @@
+//! Retry policy for docs API requests
+//!
+//! Failures are grouped into classes with their own settings. Server errors and
+//! rate limiting back off exponentially. A 429 waits at least its `Retry-After`,
+//! or fails right away when that is longer than the policy is willing to wait.
+//! Timeouts get fewer attempts, full-jitter backoff and a shorter per-attempt
+//! timeout, so a hung upstream cannot multiply the caller's latency.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use super::{DocsError, RetryTuning};
+use crate::{log_debug, log_important};
+
+/// Kind of failure a retry decision is made for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// 5xx responses and 429
+    Status,
+    /// The request did not complete within its timeout
+    Timeout,
+}
+
+impl ErrorClass {
+    /// Class of a retryable error; `None` for errors that are never retried
+    pub fn of(error: &DocsError) -> Option<Self> {
+        match error {
+            DocsError::Server { .. } | DocsError::RateLimited { .. } => Some(ErrorClass::Status),
+            DocsError::Network(e) if e.is_timeout() => Some(ErrorClass::Timeout),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ErrorClass::Status => "server error",
+            ErrorClass::Timeout => "timeout",
+        }
+    }
+}
+
+/// How the backoff delay is randomized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jitter {
+    /// Use the exponential delay as is
+    None,
+    /// Pick uniformly between zero and the exponential delay
+    Full,
+}
+
+/// Retry settings for one [`ErrorClass`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each further retry
+    pub base_delay: Duration,
+    /// Upper bound for a single delay
+    pub max_delay: Duration,
+    pub jitter: Jitter,
+    /// Per-attempt timeout of a retry, as a fraction of the original timeout
+    pub retry_timeout_factor: f64,
+}
+
+impl ClassPolicy {
+    /// Never retry
+    pub fn disabled() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+            jitter: Jitter::None,
+            retry_timeout_factor: 1.0,
+        }
+    }
+
+    /// Delay before retry number `retry` (starting at 1)
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(1u32 << retry.saturating_sub(1).min(16))
+            .min(self.max_delay);
+        match self.jitter {
+            Jitter::None => exponential,
+            Jitter::Full => exponential.mul_f64(fastrand::f64()),
+        }
+    }
+
+    fn retry_timeout(&self, timeout: Duration) -> Duration {
+        timeout.mul_f64(self.retry_timeout_factor.clamp(0.0, 1.0)).max(Duration::from_millis(1))
+    }
+}
+
+/// Retry settings for docs requests, with per-class overrides
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub status: ClassPolicy,
+    pub timeout: ClassPolicy,
+    /// Longest `Retry-After` of a 429 that is waited out
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from(&RetryTuning::default())
+    }
+}
+
+impl From<&RetryTuning> for RetryPolicy {
+    /// Policy with the configured server-error settings; timeouts keep their defaults
+    fn from(tuning: &RetryTuning) -> Self {
+        Self {
+            status: ClassPolicy {
+                max_retries: tuning.max_retries,
+                base_delay: Duration::from_millis(tuning.base_delay_ms),
+                max_delay: Duration::from_millis(tuning.max_delay_ms),
+                jitter: if tuning.jitter { Jitter::Full } else { Jitter::None },
+                retry_timeout_factor: 1.0,
+            },
+            timeout: ClassPolicy {
+                max_retries: 1,
+                base_delay: Duration::from_millis(250),
+                max_delay: Duration::from_secs(2),
+                jitter: Jitter::Full,
+                retry_timeout_factor: 0.5,
+            },
+            max_retry_after: Duration::from_secs(tuning.max_retry_after_secs),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Fail on the first error
+    pub fn none() -> Self {
+        Self { status: ClassPolicy::disabled(), timeout: ClassPolicy::disabled(), max_retry_after: Duration::ZERO }
+    }
+
+    pub fn class(&self, class: ErrorClass) -> &ClassPolicy {
+        match class {
+            ErrorClass::Status => &self.status,
+            ErrorClass::Timeout => &self.timeout,
+        }
+    }
+
+    /// Run `attempt` until it succeeds, fails with a non-retryable error or the
+    /// retries of the failing class are used up
+    ///
+    /// `attempt` receives the timeout to apply to that attempt.
+    pub async fn run<T, F, Fut>(&self, label: &str, timeout: Duration, mut attempt: F) -> Result<T, DocsError>
+    where
+        F: FnMut(Duration) -> Fut,
+        Fut: Future<Output = Result<T, DocsError>>,
+    {
+        let started = Instant::now();
+        let mut attempt_timeout = timeout;
+        let mut status_retries = 0;
+        let mut timeout_retries = 0;
+        let mut attempts = 1;
+
+        loop {
+            let error = match attempt(attempt_timeout).await {
+                Ok(value) => {
+                    if attempts > 1 {
+                        log_important!(
+                            info,
+                            "Docs {} succeeded after {} attempts, {} ms total",
+                            label,
+                            attempts,
+                            started.elapsed().as_millis()
+                        );
+                    }
+                    return Ok(value);
+                }
+                Err(error) => error,
+            };
+
+            let Some(class) = ErrorClass::of(&error) else {
+                return Err(error);
+            };
+            let policy = self.class(class);
+            let retries = match class {
+                ErrorClass::Status => &mut status_retries,
+                ErrorClass::Timeout => &mut timeout_retries,
+            };
+            let retry_after = match &error {
+                DocsError::RateLimited { retry_after } => *retry_after,
+                _ => None,
+            };
+            let waits_too_long = retry_after.is_some_and(|wait| wait > self.max_retry_after);
+            if *retries >= policy.max_retries || waits_too_long {
+                if attempts > 1 {
+                    log_important!(
+                        warn,
+                        "Docs {} failed after {} attempts, {} ms total: {}",
+                        label,
+                        attempts,
+                        started.elapsed().as_millis(),
+                        error
+                    );
+                }
+                return Err(error);
+            }
+            *retries += 1;
+
+            if class == ErrorClass::Timeout {
+                attempt_timeout = policy.retry_timeout(timeout);
+            }
+            let delay = policy.delay(*retries).max(retry_after.unwrap_or_default());
+            log_debug!(
+                "Docs {}: {} on attempt {}, retrying in {} ms with a {} ms timeout",
+                label,
+                class.label(),
+                attempts,
+                delay.as_millis(),
+                attempt_timeout.as_millis()
+            );
+            tokio::time::sleep(delay).await;
+            attempts += 1;
+        }
+    }
+}
+
+/// Wait asked for by a `Retry-After` header, in seconds or as an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((at.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exponential_delay_is_capped() {
+        let policy = RetryPolicy::default().status;
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_secs(1));
+        assert_eq!(policy.delay(10), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_full_jitter_stays_below_exponential_delay() {
+        let policy = RetryPolicy::default().timeout;
+        for _ in 0..100 {
+            assert!(policy.delay(1) <= Duration::from_millis(250));
+        }
+    }
+
+    #[test]
+    fn test_error_classes() {
+        assert_eq!(ErrorClass::of(&DocsError::RateLimited { retry_after: None }), Some(ErrorClass::Status));
+        assert_eq!(
+            ErrorClass::of(&DocsError::Server { status: 502, message: String::new() }),
+            Some(ErrorClass::Status)
+        );
+        assert_eq!(ErrorClass::of(&DocsError::NotFound { library: "a/b".to_string() }), None);
+        assert_eq!(ErrorClass::of(&DocsError::Unauthorized), None);
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, value.parse().unwrap());
+            headers
+        };
+        assert_eq!(retry_after(&headers("7")), Some(Duration::from_secs(7)));
+        assert_eq!(retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")), Some(Duration::ZERO));
+        let later = (chrono::Utc::now() + chrono::Duration::seconds(120)).to_rfc2822();
+        let wait = retry_after(&headers(&later)).unwrap();
+        assert!(wait > Duration::from_secs(100) && wait <= Duration::from_secs(120), "{:?}", wait);
+        assert_eq!(retry_after(&headers("soon")), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+    }
+}
@@ -0,0 +1,122 @@
+//! Client-side request throttling
+//!
+//! A token bucket spreads bursts of upstream requests so a burst of agent calls
+//! does not run straight into 429s, which matters most without an API key. A
+//! request that finds the bucket empty waits for its token; when that wait
+//! would be longer than the caller accepts, it fails with
+//! [`DocsError::RateLimited`] without contacting the upstream.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{DocsError, ThrottleTuning};
+use crate::log_debug;
+
+/// Throttle of the configured rate, shared by every client in this process
+static SHARED_THROTTLE: Mutex<Option<(ThrottleTuning, Arc<Throttle>)>> = Mutex::new(None);
+
+#[derive(Debug)]
+pub struct Throttle {
+    tuning: ThrottleTuning,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Negative while requests are queued for tokens not yet refilled
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Throttle {
+    pub fn new(tuning: ThrottleTuning) -> Self {
+        let tokens = f64::from(tuning.burst.max(1));
+        Self { tuning, state: Mutex::new(Bucket { tokens, updated: Instant::now() }) }
+    }
+
+    /// Process-wide throttle for `tuning`; changed settings start a fresh one
+    pub fn shared(tuning: &ThrottleTuning) -> Arc<Throttle> {
+        let mut shared = SHARED_THROTTLE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((current, throttle)) = shared.as_ref() {
+            if current == tuning {
+                return throttle.clone();
+            }
+        }
+        let throttle = Arc::new(Throttle::new(tuning.clone()));
+        *shared = Some((tuning.clone(), throttle.clone()));
+        throttle
+    }
+
+    /// Take a token, or reserve the next one and return how long to wait for it
+    ///
+    /// Nothing is reserved when the wait would exceed `max_wait`; the error
+    /// carries that wait.
+    fn reserve(&self, max_wait: Duration) -> Result<Duration, DocsError> {
+        if !self.tuning.enabled() {
+            return Ok(Duration::ZERO);
+        }
+        let per_sec = f64::from(self.tuning.per_minute) / 60.0;
+        let mut bucket = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let refilled = bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_sec;
+        bucket.tokens = refilled.min(f64::from(self.tuning.burst.max(1)));
+        bucket.updated = now;
+
+        let wait = Duration::from_secs_f64((1.0 - bucket.tokens).max(0.0) / per_sec);
+        if wait > max_wait {
+            return Err(DocsError::RateLimited { retry_after: Some(wait) });
+        }
+        bucket.tokens -= 1.0;
+        Ok(wait)
+    }
+
+    /// Wait for a token, at most `max_wait`
+    pub async fn acquire(&self, max_wait: Duration) -> Result<(), DocsError> {
+        let wait = self.reserve(max_wait)?;
+        if !wait.is_zero() {
+            log_debug!("Docs request throttled for {} ms ({} per minute)", wait.as_millis(), self.tuning.per_minute);
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle(per_minute: u32, burst: u32) -> Throttle {
+        Throttle::new(ThrottleTuning { per_minute, burst })
+    }
+
+    #[test]
+    fn test_burst_passes_then_requests_are_spaced() {
+        let throttle = throttle(60, 3);
+        for _ in 0..3 {
+            assert_eq!(throttle.reserve(Duration::ZERO).unwrap(), Duration::ZERO);
+        }
+        // One token per second at 60 per minute; queued requests wait in turn
+        let first = throttle.reserve(Duration::from_secs(5)).unwrap();
+        let second = throttle.reserve(Duration::from_secs(5)).unwrap();
+        assert!(first > Duration::from_millis(900) && first <= Duration::from_secs(1), "{:?}", first);
+        assert!(second > Duration::from_millis(1900) && second <= Duration::from_secs(2), "{:?}", second);
+    }
+
+    #[test]
+    fn test_too_long_a_wait_is_rate_limited_without_reserving() {
+        let throttle = throttle(6, 1);
+        throttle.reserve(Duration::ZERO).unwrap();
+        let err = throttle.reserve(Duration::from_secs(1)).unwrap_err();
+        assert!(matches!(err, DocsError::RateLimited { retry_after: Some(wait) } if wait > Duration::from_secs(9)));
+        // The refused request did not take the next token
+        assert!(throttle.reserve(Duration::from_secs(10)).unwrap() <= Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_zero_rate_disables_throttling() {
+        let throttle = throttle(0, 1);
+        for _ in 0..100 {
+            assert_eq!(throttle.reserve(Duration::ZERO).unwrap(), Duration::ZERO);
+        }
+    }
+}
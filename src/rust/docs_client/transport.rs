@@ -0,0 +1,362 @@
+//! Where the client's upstream requests go
+//!
+//! [`Transport::Network`] is the normal case. [`Transport::Record`] also writes
+//! every request/response pair to a directory as numbered JSON files
+//! (`0001.json`, `0002.json`, ...) with credentials redacted, so the exact
+//! upstream traffic behind a bad rendering can be attached to a bug report.
+//! [`Transport::Replay`] answers from such a directory instead of the network,
+//! matching method, path and query; a request that was not recorded fails with
+//! [`DocsError::NotRecorded`].
+//!
+//! Clients pick up the process default from [`Transport::current`], set by
+//! `devkit --record/--replay` or the [`RECORD_ENV`] / [`REPLAY_ENV`] variables.
+
+use reqwest::header::{HeaderMap, AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION};
+use reqwest::{ResponseBuilderExt, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::DocsError;
+use crate::log_important;
+
+/// Record upstream traffic into this directory
+pub const RECORD_ENV: &str = "DEVKIT_DOCS_RECORD";
+
+/// Answer upstream requests from the recording in this directory
+pub const REPLAY_ENV: &str = "DEVKIT_DOCS_REPLAY";
+
+/// Value written instead of a credential
+const REDACTED: &str = "<redacted>";
+
+/// Response headers that describe the wire encoding rather than the body kept in a recording
+const WIRE_HEADERS: &[&str] = &["content-length", "content-encoding", "transfer-encoding", "connection"];
+
+static DEFAULT: once_cell::sync::Lazy<Mutex<Transport>> = once_cell::sync::Lazy::new(|| Mutex::new(Transport::from_env()));
+
+/// How upstream requests are answered
+#[derive(Debug, Clone, Default)]
+pub enum Transport {
+    #[default]
+    Network,
+    Record(Arc<Recorder>),
+    Replay(Arc<Replayer>),
+}
+
+/// One recorded request/response pair
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub path: String,
+    /// Raw query string, without the `?`
+    #[serde(default)]
+    pub query: String,
+    /// Request headers with credentials redacted
+    #[serde(default)]
+    pub request_headers: BTreeMap<String, String>,
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Path of the final URL when the API redirected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+    pub body: String,
+}
+
+/// Writes exchanges to numbered files
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+/// Serves recorded exchanges
+#[derive(Debug)]
+pub struct Replayer {
+    dir: PathBuf,
+    exchanges: Vec<Exchange>,
+    /// Whether each exchange was served; repeated requests get the next unused match
+    served: Mutex<Vec<bool>>,
+}
+
+impl Transport {
+    /// Record into `dir`, continuing the numbering of files already there
+    pub fn record(dir: impl Into<PathBuf>) -> Result<Self, DocsError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| recording_error(&dir, e))?;
+        let existing = exchange_files(&dir)?.len();
+        Ok(Self::Record(Arc::new(Recorder { dir, next: AtomicUsize::new(existing + 1) })))
+    }
+
+    /// Replay the recording in `dir`
+    pub fn replay(dir: impl Into<PathBuf>) -> Result<Self, DocsError> {
+        let dir = dir.into();
+        let mut exchanges = Vec::new();
+        for path in exchange_files(&dir)? {
+            let text = std::fs::read_to_string(&path).map_err(|e| recording_error(&path, e))?;
+            let exchange = serde_json::from_str(&text).map_err(|e| recording_error(&path, e))?;
+            exchanges.push(exchange);
+        }
+        if exchanges.is_empty() {
+            return Err(recording_error(&dir, "no recorded exchanges"));
+        }
+        let served = Mutex::new(vec![false; exchanges.len()]);
+        Ok(Self::Replay(Arc::new(Replayer { dir, exchanges, served })))
+    }
+
+    /// Transport of clients created from now on
+    pub fn set_default(transport: Transport) {
+        match &transport {
+            Transport::Network => {}
+            Transport::Record(recorder) => log_important!(info, "Recording docs traffic to {}", recorder.dir.display()),
+            Transport::Replay(replayer) => log_important!(
+                info,
+                "Replaying docs traffic from {} ({} exchanges)",
+                replayer.dir.display(),
+                replayer.exchanges.len()
+            ),
+        }
+        *DEFAULT.lock().unwrap_or_else(|e| e.into_inner()) = transport;
+    }
+
+    /// The process default: [`Transport::set_default`], else the environment, else the network
+    pub fn current() -> Self {
+        DEFAULT.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let transport = match (var(RECORD_ENV), var(REPLAY_ENV)) {
+            (_, Some(dir)) => Self::replay(dir),
+            (Some(dir), None) => Self::record(dir),
+            (None, None) => return Self::Network,
+        };
+        transport.unwrap_or_else(|e| {
+            log_important!(warn, "Ignoring docs record/replay setting: {}", e);
+            Self::Network
+        })
+    }
+
+    /// Whether requests go to the network without being recorded
+    pub fn is_network(&self) -> bool {
+        matches!(self, Transport::Network)
+    }
+
+    /// Send `request` the way this transport does
+    pub(crate) async fn execute(&self, http: &reqwest::Client, request: reqwest::Request) -> Result<reqwest::Response, DocsError> {
+        match self {
+            Transport::Network => http.execute(request).await.map_err(DocsError::from_send),
+            Transport::Record(recorder) => recorder.execute(http, request).await,
+            Transport::Replay(replayer) => replayer.respond(&request),
+        }
+    }
+}
+
+impl Recorder {
+    async fn execute(&self, http: &reqwest::Client, request: reqwest::Request) -> Result<reqwest::Response, DocsError> {
+        let method = request.method().to_string();
+        let url = request.url().clone();
+        let request_headers = header_map(request.headers(), true);
+
+        let response = http.execute(request).await.map_err(DocsError::from_send)?;
+        let status = response.status().as_u16();
+        let headers = header_map(response.headers(), false);
+        let final_url = response.url().clone();
+        let body = String::from_utf8_lossy(&response.bytes().await?).into_owned();
+
+        let exchange = Exchange {
+            method,
+            path: url.path().to_string(),
+            query: url.query().unwrap_or_default().to_string(),
+            request_headers,
+            status,
+            headers,
+            redirected_to: (final_url.path() != url.path()).then(|| final_url.path().to_string()),
+            body,
+        };
+        self.write(&exchange)?;
+        build_response(&exchange, final_url)
+    }
+
+    fn write(&self, exchange: &Exchange) -> Result<(), DocsError> {
+        let path = self.dir.join(format!("{:04}.json", self.next.fetch_add(1, Ordering::SeqCst)));
+        let text = serde_json::to_string_pretty(exchange).map_err(|e| recording_error(&path, e))?;
+        std::fs::write(&path, text + "\n").map_err(|e| recording_error(&path, e))
+    }
+}
+
+impl Replayer {
+    /// Recorded exchanges, in file order
+    pub fn exchanges(&self) -> &[Exchange] {
+        &self.exchanges
+    }
+
+    fn respond(&self, request: &reqwest::Request) -> Result<reqwest::Response, DocsError> {
+        let url = request.url();
+        let method = request.method().as_str();
+        let wanted = sorted_pairs(url.query().unwrap_or_default());
+        let matches: Vec<usize> = (0..self.exchanges.len())
+            .filter(|&i| {
+                let exchange = &self.exchanges[i];
+                exchange.method.eq_ignore_ascii_case(method) && exchange.path == url.path() && sorted_pairs(&exchange.query) == wanted
+            })
+            .collect();
+
+        let Some(&last) = matches.last() else {
+            let query = url.query().map(|q| format!("?{}", q)).unwrap_or_default();
+            return Err(DocsError::NotRecorded {
+                request: format!("{} {}{}", method, url.path(), query),
+                dir: self.dir.display().to_string(),
+            });
+        };
+        // Retries and repeated lookups get the recorded responses in order, then the last one again
+        let index = {
+            let mut served = self.served.lock().unwrap_or_else(|e| e.into_inner());
+            let index = matches.iter().copied().find(|&i| !served[i]).unwrap_or(last);
+            served[index] = true;
+            index
+        };
+
+        let exchange = &self.exchanges[index];
+        let mut final_url = url.clone();
+        if let Some(path) = &exchange.redirected_to {
+            final_url.set_path(path);
+        }
+        build_response(exchange, final_url)
+    }
+}
+
+fn build_response(exchange: &Exchange, url: Url) -> Result<reqwest::Response, DocsError> {
+    let mut builder = http::Response::builder().status(exchange.status).url(url);
+    for (name, value) in &exchange.headers {
+        if !WIRE_HEADERS.contains(&name.as_str()) {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+    }
+    let response = builder
+        .body(exchange.body.clone())
+        .map_err(|e| DocsError::Parse(format!("invalid recorded response: {}", e)))?;
+    Ok(reqwest::Response::from(response))
+}
+
+/// Headers by lower-case name; repeated headers are joined with `, `
+fn header_map(headers: &HeaderMap, redact: bool) -> BTreeMap<String, String> {
+    let mut map: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let credential = *name == AUTHORIZATION || *name == PROXY_AUTHORIZATION || *name == COOKIE || value.is_sensitive();
+        let value = if redact && credential { REDACTED.to_string() } else { String::from_utf8_lossy(value.as_bytes()).into_owned() };
+        map.entry(name.as_str().to_string())
+            .and_modify(|joined| {
+                joined.push_str(", ");
+                joined.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    map
+}
+
+fn sorted_pairs(query: &str) -> Vec<(String, String)> {
+    let Ok(url) = Url::parse(&format!("http://recorded/?{}", query)) else {
+        return Vec::new();
+    };
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    pairs.sort();
+    pairs
+}
+
+/// `*.json` files of a recording, by name
+fn exchange_files(dir: &Path) -> Result<Vec<PathBuf>, DocsError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => return Err(recording_error(dir, e)),
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn recording_error(path: &Path, reason: impl std::fmt::Display) -> DocsError {
+    DocsError::Recording { path: path.display().to_string(), reason: reason.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use crate::docs_client::mock::{MockResponse, MockServer};
+    use crate::docs_client::{Client, DocsConfig, DocsRequest, RetryPolicy};
+
+    fn client(base_url: String, transport: Transport) -> Client {
+        let config = DocsConfig { api_key: Some("secret-key".to_string()), base_url, ..Default::default() };
+        Client::new(config).unwrap().with_retry(RetryPolicy::none()).with_transport(transport)
+    }
+
+    #[tokio::test]
+    async fn test_record_writes_numbered_sanitized_files() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/search" => MockResponse::json(200, include_str!("fixtures/search_mixed.json")),
+            _ => MockResponse::json(200, include_str!("fixtures/docs_full.json")).with_header("X-RateLimit-Remaining", "41"),
+        })
+        .await;
+        let dir = TestDir::reserve("recording");
+        let client = client(server.base_url(), Transport::record(dir.path()).unwrap());
+
+        let mut request = DocsRequest::new("vercel/next.js");
+        request.topic = Some("routing".to_string());
+        client.docs(&request).await.unwrap();
+        client.search("next").await.unwrap();
+
+        let files = exchange_files(&dir).unwrap();
+        let names: Vec<_> = files.iter().map(|f| f.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(names, vec!["0001.json", "0002.json"]);
+
+        let text = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(!text.contains("secret-key"), "{}", text);
+        let first: Exchange = serde_json::from_str(&text).unwrap();
+        assert_eq!((first.method.as_str(), first.path.as_str(), first.query.as_str()), ("GET", "/docs/code/vercel/next.js", "topic=routing"));
+        assert_eq!(first.request_headers["authorization"], REDACTED);
+        assert_eq!(first.headers["x-ratelimit-remaining"], "41");
+        assert_eq!(first.body, include_str!("fixtures/docs_full.json"));
+
+        // A new recorder continues the numbering
+        let Transport::Record(recorder) = Transport::record(dir.path()).unwrap() else { unreachable!() };
+        assert_eq!(recorder.next.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_replay_matches_method_path_and_query() {
+        let server = MockServer::start(|req| MockResponse::text(200, &format!("page {}", req.query("page").unwrap_or_default()))).await;
+        let dir = TestDir::reserve("recording");
+        let recording = client(server.base_url(), Transport::record(dir.path()).unwrap());
+        for page in [1, 2] {
+            let request = DocsRequest { page: Some(page), topic: Some("routing".to_string()), ..DocsRequest::new("a/b") };
+            recording.docs(&request).await.unwrap();
+        }
+        drop(server);
+
+        // Replayed without a server; query order does not matter
+        let replay = client("http://127.0.0.1:9".to_string(), Transport::replay(dir.path()).unwrap());
+        let request = DocsRequest { page: Some(2), topic: Some("routing".to_string()), ..DocsRequest::new("a/b") };
+        assert_eq!(replay.docs(&request).await.unwrap().snippets[0].content, "page 2");
+        let Transport::Replay(replayer) = Transport::replay(dir.path()).unwrap() else { unreachable!() };
+        let request = reqwest::Client::new().get("http://127.0.0.1:9/docs/code/a/b?page=1&topic=routing").build().unwrap();
+        assert_eq!(replayer.respond(&request).unwrap().text().await.unwrap(), "page 1");
+
+        let error = replay.docs(&DocsRequest::new("c/d")).await.unwrap_err();
+        assert!(matches!(error, DocsError::NotRecorded { .. }));
+        assert_eq!(error.to_string(), format!("No recorded response for GET /docs/code/c/d in {}", dir.display()));
+    }
+
+    #[test]
+    fn test_replay_needs_a_recording() {
+        let dir = TestDir::reserve("recording");
+        assert!(matches!(Transport::replay(dir.path()), Err(DocsError::Recording { .. })));
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(Transport::replay(dir.path()).unwrap_err().to_string().contains("no recorded exchanges"));
+    }
+}
@@ -0,0 +1,552 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, HOST, USER_AGENT};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::DocsError;
+use crate::constants::network::DEFAULT_USER_AGENT;
+
+/// Docs 查询请求参数
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DocsRequest {
+    /// 库标识符，格式: owner/repo (例如: "vercel/next.js", "facebook/react")
+    /// 查询时必填；缓存操作时作为可选的库筛选条件
+    #[schemars(description = "库标识符，格式: owner/repo (例如: vercel/next.js, facebook/react)")]
+    #[serde(default)]
+    pub library: String,
+    /// 多个库标识符 (可选)：与 library 一起并发查询，结果按库分节
+    #[schemars(description = "多个库标识符 (可选，最多 5 个)：与 library 一起并发查询，结果按库分节")]
+    #[serde(default)]
+    pub libraries: Option<Vec<String>>,
+    /// 查询主题 (可选，例如: "routing", "authentication")
+    #[schemars(description = "查询主题 (可选，例如: routing, authentication)")]
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// 多个查询主题 (可选)：与 topic 一起并发查询同一个库，结果按主题分节
+    #[schemars(description = "多个查询主题 (可选，最多 5 个)：与 topic 一起并发查询同一个库，结果按主题分节")]
+    #[serde(default)]
+    pub topics: Option<Vec<String>>,
+    /// 版本号 (可选，例如: "v15.1.8")
+    #[schemars(description = "版本号 (可选，例如: v15.1.8)")]
+    #[serde(default)]
+    pub version: Option<String>,
+    /// 分页页码 (可选，默认1，最大10)
+    #[schemars(description = "分页页码 (可选，默认1，最大10)")]
+    #[serde(default)]
+    pub page: Option<u32>,
+    /// 连续获取的页数 (可选，1-10，默认 1)：从 page 开始逐页获取并合并片段，
+    /// 累计内容达到字符预算 (配置 docs_page_budget_chars) 或没有下一页时停止
+    #[schemars(description = "连续获取的页数 (可选，1-10)：从 page 开始逐页获取并合并片段，达到字符预算或没有下一页时停止")]
+    #[serde(default)]
+    pub max_pages: Option<u32>,
+    /// 文档类型 (可选，默认 code)
+    #[schemars(description = "文档类型 (可选): code 代码片段 (默认), info 说明文档, auto 代码片段过少时改用说明文档")]
+    #[serde(default)]
+    pub doc_type: Option<DocType>,
+    /// 最多返回的片段数 (可选，1-50)
+    #[schemars(description = "最多返回的片段数 (可选，1-50)")]
+    #[serde(default)]
+    pub max_snippets: Option<u32>,
+    /// 库不存在时是否自动采用最匹配的搜索结果 (可选，默认取配置 docs_auto_resolve)
+    #[schemars(description = "库不存在时是否自动采用最匹配的搜索结果 (可选)")]
+    #[serde(default)]
+    pub auto_resolve: Option<bool>,
+    /// 是否按与 topic 的相关度重新排序片段 (可选，默认 false，保留上游顺序)
+    #[schemars(description = "按与 topic 的相关度重新排序片段 (可选，默认保留上游顺序)")]
+    #[serde(default)]
+    pub rerank: Option<bool>,
+    /// 返回内容的 token 上限 (可选，100-100000)：按相关度保留放得下的片段，
+    /// 第一个片段就超出时截断它，并注明省略了多少
+    #[schemars(description = "返回内容的 token 上限 (可选，100-100000，按每 4 个字符 1 个 token 估算)")]
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// 返回内容的字节上限 (可选，1000-10000000，默认取配置 docs_max_response_bytes)：超出时只保留放得下的片段，
+    /// 并列出省略片段的标题，供 titles 再次请求
+    #[schemars(description = "返回内容的字节上限 (可选，1000-10000000，默认取配置)：超出时只保留放得下的片段，并列出省略片段的标题")]
+    #[serde(default)]
+    pub max_bytes: Option<u32>,
+    /// 只返回这些标题的片段 (可选)，用于再次请求因 max_bytes 省略的片段
+    #[schemars(description = "只返回这些标题的片段 (可选)：用于再次请求因大小上限省略的片段")]
+    #[serde(default)]
+    pub titles: Option<Vec<String>>,
+    /// 离线模式 (可选)，为 true 时只从缓存读取，优先于环境变量与配置
+    #[schemars(description = "离线模式 (可选): true 时只从缓存读取，不联网")]
+    #[serde(default)]
+    pub offline: Option<bool>,
+    /// 上游不可用 (网络错误、超时、5xx、熔断) 时是否改用本地缓存的快照，即使已过期 (可选，默认 false)；
+    /// 结果开头注明快照时间
+    #[schemars(description = "上游不可用时改用本地缓存的快照 (可选): true 时即使缓存已过期也返回，并注明快照时间")]
+    #[serde(default)]
+    pub offline_ok: Option<bool>,
+    /// 强制刷新 (可选)，为 true 时跳过缓存直接请求上游，并用结果更新缓存；离线时不生效
+    #[schemars(description = "强制刷新 (可选): true 时跳过缓存直接请求上游，并用结果更新缓存")]
+    #[serde(default)]
+    pub refresh: Option<bool>,
+    /// 列出库已收录文档的版本 (可选)，为 true 时不查询文档
+    #[schemars(description = "列出库已收录文档的版本 (可选): true 时只返回可用作 version 的取值，不查询文档")]
+    #[serde(default)]
+    pub list_versions: Option<bool>,
+}
+
+/// 文档类型，对应 API 路径 /docs/code 与 /docs/info
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DocType {
+    /// 代码片段
+    #[default]
+    Code,
+    /// 说明文档（以文字为主，片段可能没有标题）
+    Info,
+    /// 先查 code，片段数低于阈值时改查 info
+    Auto,
+}
+
+impl DocType {
+    /// API 路径中的类型段，auto 从 code 开始
+    pub fn path_segment(self) -> &'static str {
+        match self {
+            DocType::Code | DocType::Auto => "code",
+            DocType::Info => "info",
+        }
+    }
+}
+
+impl DocsRequest {
+    /// 仅指定库标识符的请求
+    pub fn new(library: impl Into<String>) -> Self {
+        Self {
+            library: library.into(),
+            libraries: None,
+            topic: None,
+            topics: None,
+            version: None,
+            page: None,
+            max_pages: None,
+            doc_type: None,
+            max_snippets: None,
+            max_tokens: None,
+            max_bytes: None,
+            titles: None,
+            rerank: None,
+            auto_resolve: None,
+            offline: None,
+            offline_ok: None,
+            refresh: None,
+            list_versions: None,
+        }
+    }
+}
+
+/// Docs 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocsConfig {
+    /// API 密钥 (可选，免费使用时可为空)
+    pub api_key: Option<String>,
+    /// 额外的 API 密钥，与 api_key 一起轮换使用
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// API 基础 URL
+    pub base_url: String,
+    /// 上游 HTTP 客户端参数
+    #[serde(default)]
+    pub http: HttpTuning,
+    /// 文档请求每次尝试的超时（秒），None 为默认 30 秒
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// 搜索请求每次尝试的超时（秒），None 为默认 15 秒
+    #[serde(default)]
+    pub search_timeout_secs: Option<u64>,
+    /// 服务端错误与限流的重试参数
+    #[serde(default)]
+    pub retry: RetryTuning,
+    /// 上游连续失败时的熔断参数
+    #[serde(default)]
+    pub breaker: BreakerTuning,
+    /// 客户端限速参数，默认不限速
+    #[serde(default)]
+    pub throttle: ThrottleTuning,
+    /// 连续获取多页时的字符预算，累计内容达到后不再请求下一页；None 为默认 60000
+    #[serde(default)]
+    pub page_budget_chars: Option<usize>,
+    /// 上游请求的 User-Agent，未设置时为 `<包名>/<版本>`
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// 附加到每个上游请求的请求头
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 值为敏感信息的请求头名称，日志与诊断中只显示 `<redacted>`
+    #[serde(default)]
+    pub sensitive_headers: Vec<String>,
+}
+
+/// 上游 HTTP 客户端的连接参数（连接池、HTTP/2、TCP keepalive）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpTuning {
+    /// 每个主机保留的最大空闲连接数
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接保留时间（秒）
+    pub pool_idle_timeout_secs: u64,
+    /// 是否允许通过 ALPN 协商 HTTP/2，关闭后只使用 HTTP/1.1
+    pub http2: bool,
+    /// TCP keepalive 间隔（秒），None 表示不启用
+    pub tcp_keepalive_secs: Option<u64>,
+    /// 代理地址（http://、https:// 或 socks5://，可带 user:password@），
+    /// None 时按 HTTP_PROXY / HTTPS_PROXY / NO_PROXY 环境变量决定
+    pub proxy_url: Option<String>,
+}
+
+impl Default for HttpTuning {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: 8,
+            pool_idle_timeout_secs: 90,
+            http2: true,
+            tcp_keepalive_secs: Some(60),
+            proxy_url: None,
+        }
+    }
+}
+
+impl HttpTuning {
+    /// 显式配置的代理；NO_PROXY 中的主机仍然直连。未配置时为 None，由 reqwest 读取环境变量
+    pub fn proxy(&self) -> Result<Option<reqwest::Proxy>, DocsError> {
+        let Some(url) = self.proxy_url.as_deref().map(str::trim).filter(|url| !url.is_empty()) else {
+            return Ok(None);
+        };
+        let invalid = |reason: String| DocsError::InvalidProxy { url: mask_proxy_url(url), reason };
+        let parsed = reqwest::Url::parse(url).map_err(|e| invalid(e.to_string()))?;
+        if !matches!(parsed.scheme(), "http" | "https" | "socks5" | "socks5h") {
+            return Err(invalid(format!("unsupported scheme {}, use http, https or socks5", parsed.scheme())));
+        }
+        let proxy = reqwest::Proxy::all(parsed).map_err(|e| invalid(e.to_string()))?;
+        Ok(Some(proxy.no_proxy(reqwest::NoProxy::from_env())))
+    }
+}
+
+/// 代理地址的日志形式：去掉密码
+pub fn mask_proxy_url(url: &str) -> String {
+    match reqwest::Url::parse(url.trim()) {
+        Ok(mut parsed) => {
+            if parsed.password().is_some() {
+                let _ = parsed.set_password(Some("****"));
+            }
+            parsed.to_string()
+        }
+        Err(_) => "<invalid url>".to_string(),
+    }
+}
+
+impl std::fmt::Display for HttpTuning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "pool_max_idle_per_host={}, pool_idle_timeout={}s, http2={}, tcp_keepalive={}, proxy={}",
+            self.pool_max_idle_per_host,
+            self.pool_idle_timeout_secs,
+            if self.http2 { "on" } else { "off" },
+            self.tcp_keepalive_secs.map_or("off".to_string(), |secs| format!("{}s", secs)),
+            self.proxy_url.as_deref().map_or("from environment".to_string(), mask_proxy_url)
+        )
+    }
+}
+
+/// 上游返回 5xx 或 429 时的重试参数（超时的重试次数与等待固定，见 [`super::RetryPolicy`]）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryTuning {
+    /// 首次请求之后最多重试几次，0 表示不重试
+    pub max_retries: u32,
+    /// 第一次重试前的等待（毫秒），之后每次翻倍
+    pub base_delay_ms: u64,
+    /// 单次等待的上限（毫秒）
+    pub max_delay_ms: u64,
+    /// 是否在 0 到退避时间之间随机取等待时间，避免多个客户端同时重试
+    pub jitter: bool,
+    /// 429 的 Retry-After 超过该值（秒）时不再等待，直接返回限流错误
+    pub max_retry_after_secs: u64,
+}
+
+impl Default for RetryTuning {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            base_delay_ms: 500,
+            max_delay_ms: 4000,
+            jitter: false,
+            max_retry_after_secs: 10,
+        }
+    }
+}
+
+/// 熔断参数：上游连续失败（5xx、超时、连接失败）达到阈值后，冷却时间内的请求直接失败
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreakerTuning {
+    /// 连续失败多少次后熔断，0 表示不熔断
+    pub failure_threshold: u32,
+    /// 熔断后拒绝请求的时间（秒），之后放行请求，再次失败则重新熔断
+    pub cooldown_secs: u64,
+}
+
+impl Default for BreakerTuning {
+    fn default() -> Self {
+        Self { failure_threshold: 5, cooldown_secs: 30 }
+    }
+}
+
+impl BreakerTuning {
+    pub fn enabled(&self) -> bool {
+        self.failure_threshold > 0
+    }
+}
+
+/// 客户端限速参数（令牌桶）：突发请求先用掉 burst 个令牌，之后按每分钟 per_minute 个的速度放行
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThrottleTuning {
+    /// 每分钟放行的请求数，0 表示不限速
+    pub per_minute: u32,
+    /// 可以不等待连续发出的请求数
+    pub burst: u32,
+}
+
+impl Default for ThrottleTuning {
+    fn default() -> Self {
+        Self { per_minute: 0, burst: 5 }
+    }
+}
+
+impl ThrottleTuning {
+    pub fn enabled(&self) -> bool {
+        self.per_minute > 0
+    }
+}
+
+pub fn docs_website_url() -> String {
+    format!("https://{}{}", "context", "7.com")
+}
+
+pub fn docs_api_base_url() -> String {
+    format!("{}/api/v2", docs_website_url())
+}
+
+impl Default for DocsConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            api_keys: Vec::new(),
+            base_url: docs_api_base_url(),
+            http: HttpTuning::default(),
+            timeout_secs: None,
+            search_timeout_secs: None,
+            retry: RetryTuning::default(),
+            breaker: BreakerTuning::default(),
+            throttle: ThrottleTuning::default(),
+            page_budget_chars: None,
+            user_agent: None,
+            headers: BTreeMap::new(),
+            sensitive_headers: Vec::new(),
+        }
+    }
+}
+
+impl DocsConfig {
+    /// 连续获取多页时的字符预算
+    pub fn page_budget(&self) -> usize {
+        self.page_budget_chars.unwrap_or(super::DEFAULT_PAGE_BUDGET_CHARS)
+    }
+
+    /// 参与轮换的全部密钥：api_key 在前，去掉空值与重复项
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for key in self.api_key.iter().chain(self.api_keys.iter()) {
+            let key = key.trim();
+            if !key.is_empty() && !keys.iter().any(|k| k == key) {
+                keys.push(key.to_string());
+            }
+        }
+        keys
+    }
+
+    /// 生效的 User-Agent：配置为空时使用默认值
+    pub fn effective_user_agent(&self) -> &str {
+        self.user_agent
+            .as_deref()
+            .map(str::trim)
+            .filter(|ua| !ua.is_empty())
+            .unwrap_or(DEFAULT_USER_AGENT)
+    }
+
+    fn is_sensitive(&self, name: &str) -> bool {
+        self.sensitive_headers.iter().any(|s| s.trim().eq_ignore_ascii_case(name))
+    }
+
+    /// 校验并生成附加到每个上游请求的请求头（含 User-Agent）
+    ///
+    /// Authorization 由密钥轮换设置，Host 由 URL 决定，User-Agent 有单独的配置项，
+    /// 这三个名称不允许出现在 `headers` 中。
+    pub fn upstream_headers(&self) -> Result<HeaderMap, DocsError> {
+        let invalid = |name: &str, reason: &str| DocsError::InvalidHeader { name: name.to_string(), reason: reason.to_string() };
+
+        let mut headers = HeaderMap::new();
+        let user_agent = HeaderValue::from_str(self.effective_user_agent())
+            .map_err(|_| invalid("User-Agent", "value contains characters not allowed in a header"))?;
+        headers.insert(USER_AGENT, user_agent);
+
+        for (name, value) in &self.headers {
+            let header = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| invalid(name, "not a valid header name"))?;
+            if header == AUTHORIZATION || header == HOST {
+                return Err(invalid(name, "conflicts with a header set by the client"));
+            }
+            if header == USER_AGENT {
+                return Err(invalid(name, "set docs_user_agent instead"));
+            }
+            let mut value =
+                HeaderValue::from_str(value.trim()).map_err(|_| invalid(name, "value contains characters not allowed in a header"))?;
+            value.set_sensitive(self.is_sensitive(name.trim()));
+            headers.insert(header, value);
+        }
+        Ok(headers)
+    }
+
+    /// 附加请求头的文字说明，敏感值以 `<redacted>` 代替
+    pub fn describe_headers(&self) -> String {
+        if self.headers.is_empty() {
+            return "none".to_string();
+        }
+        self.headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_sensitive(name.trim()) { "<redacted>" } else { value.trim() };
+                format!("{}: {}", name.trim(), value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Docs API 响应结构
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocsResponse {
+    /// 文档片段列表
+    #[serde(default)]
+    pub snippets: Vec<DocumentSnippet>,
+    /// 分页信息
+    #[serde(default)]
+    pub pagination: Option<PaginationInfo>,
+    /// 请求被重定向到的新库标识符（库已改名时由客户端填写）
+    #[serde(skip)]
+    pub moved_to: Option<String>,
+    /// 实际返回的文档类型（由客户端填写）
+    #[serde(skip)]
+    pub doc_type: DocType,
+    /// auto 模式下因代码片段过少改用了 info（由客户端填写）
+    #[serde(skip)]
+    pub auto_fallback: bool,
+    /// 合并进来的页数，max_pages 大于 1 时由工具填写，0 表示单页
+    #[serde(skip)]
+    pub merged_pages: u32,
+    /// 上游返回的 ETag，用于条件请求（由客户端填写）
+    #[serde(skip)]
+    pub etag: Option<String>,
+}
+
+impl DocsResponse {
+    /// 是否没有任何文档内容
+    pub fn is_empty(&self) -> bool {
+        self.snippets.iter().all(|s| s.content.trim().is_empty())
+    }
+
+    /// 有内容的片段数
+    pub fn snippet_count(&self) -> usize {
+        self.snippets.iter().filter(|s| !s.content.trim().is_empty()).count()
+    }
+}
+
+/// 文档片段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSnippet {
+    /// 片段内容 (Markdown 格式)
+    #[serde(default)]
+    pub content: String,
+    /// 片段标题
+    #[serde(default, alias = "codeTitle", alias = "pageTitle")]
+    pub title: Option<String>,
+    /// 相关性分数
+    #[serde(default)]
+    pub score: Option<f64>,
+}
+
+/// 分页信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaginationInfo {
+    /// 当前页码
+    #[serde(alias = "currentPage", alias = "page")]
+    pub current_page: u32,
+    /// 总页数
+    #[serde(alias = "totalPages")]
+    pub total_pages: u32,
+    /// 是否有下一页
+    #[serde(alias = "hasNext")]
+    pub has_next: bool,
+}
+
+impl Default for PaginationInfo {
+    fn default() -> Self {
+        Self {
+            current_page: 1,
+            total_pages: 1,
+            has_next: false,
+        }
+    }
+}
+
+/// 库搜索响应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResponse {
+    /// 搜索结果列表
+    #[serde(default)]
+    pub results: Vec<SearchResult>,
+}
+
+/// 搜索结果项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// 库标识符 (格式: /owner/repo)
+    pub id: String,
+    /// 库名称
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 库描述
+    #[serde(default)]
+    pub description: Option<String>,
+    /// GitHub stars 数量
+    #[serde(default)]
+    pub stars: Option<u64>,
+    /// 信任分数 (0-10)
+    #[serde(default, rename = "trustScore")]
+    pub trust_score: Option<f64>,
+    /// 基准测试分数
+    #[serde(default, rename = "benchmarkScore")]
+    pub benchmark_score: Option<f64>,
+    /// 单独收录了文档的版本，可作为查询的 version
+    #[serde(default)]
+    pub versions: Vec<String>,
+    /// 索引状态
+    #[serde(default)]
+    pub state: Option<LibraryState>,
+}
+
+/// 库索引状态，未知取值归入 Other，避免上游新增状态导致整体解析失败
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LibraryState {
+    Initial,
+    Processing,
+    Finalized,
+    Error,
+    #[serde(other)]
+    Other,
+}
+
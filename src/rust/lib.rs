@@ -1,9 +1,16 @@
+#[cfg(feature = "gui")]
 pub mod app;
+#[cfg(feature = "server")]
+pub mod cli;
 pub mod config;
 pub mod constants;
+pub mod docs_client;
+#[cfg(feature = "server")]
 pub mod mcp;
 pub mod network;
+#[cfg(feature = "gui")]
 pub mod telegram;
+#[cfg(feature = "gui")]
 pub mod ui;
 pub mod utils;
 
@@ -13,5 +20,14 @@ pub use utils::*;
 
 // 选择性导出常用项，避免冲突
 pub use constants::{app as app_constants, theme, validation, network as network_constants, telegram as telegram_constants};
+#[cfg(feature = "server")]
 pub use mcp::{server, tools, types, handlers, utils as mcp_utils};
+#[cfg(feature = "gui")]
 pub use ui::{window as ui_window, audio as ui_audio, audio_assets, updater};
+
+// 文档客户端库接口：启用 client 特性后可直接从 crate 根导入
+#[cfg(feature = "client")]
+pub use docs_client::{
+    Client as DocsClient, DocsConfig, DocsError, DocsRequest, DocsResponse, DocumentSnippet,
+    PaginationInfo, SearchResult,
+};
@@ -0,0 +1,48 @@
+//! Process setup shared by the MCP server binaries
+//!
+//! `devkit-mcp` (stdio, or a named pipe on Windows) and `devkit-mcp-http`
+//! serve the same tool registry from [`DevkitServer`](super::DevkitServer),
+//! so clients that can only spawn stdio servers get exactly the tools, limits
+//! and config handling of the HTTP server. Everything before the transport is
+//! done here, keeping the entry points from drifting apart: the config is
+//! loaded (failing in strict mode, quarantining a broken file otherwise) and
+//! watched for live reloads, telemetry export is started and the background
+//! docs checks are spawned. Logging stays with each binary, since stdio must
+//! keep stdout free for protocol messages.
+
+use anyhow::Result;
+
+use super::telemetry::{self, TelemetryGuard};
+use crate::config::{live, load_standalone_config, set_strict_config, AppConfig, ConfigWatcher};
+use crate::log_important;
+
+/// Startup state, kept alive until the server exits
+pub struct Bootstrap {
+    /// Config as loaded at startup; later edits are read through [`live::current`]
+    pub config: AppConfig,
+    _config_watcher: Option<ConfigWatcher>,
+    /// Dropped last, flushing pending spans within a bounded time
+    _telemetry: Option<TelemetryGuard>,
+}
+
+impl Bootstrap {
+    /// Load and watch the config, start telemetry and the docs checks
+    ///
+    /// Call once the logger is initialized and before serving.
+    pub async fn start(strict_config: bool) -> Result<Self> {
+        // Load config once up front: strict mode fails here, otherwise a broken file is quarantined now
+        set_strict_config(strict_config);
+        let config = load_standalone_config()?;
+        // Later edits of the config file apply to tool switches, API keys, limits and the log level
+        let config_watcher = live::watch()
+            .inspect_err(|e| log_important!(warn, "Config file not watched, tool switches are read from it on each call; call reload_config after other edits: {}", e))
+            .ok();
+        let telemetry = telemetry::init(&config.mcp_config.telemetry);
+        #[cfg(feature = "docs")]
+        crate::mcp::tools::DocsTool::start_contract_preflight().await;
+        #[cfg(feature = "docs")]
+        crate::mcp::tools::DocsTool::start_key_check().await;
+
+        Ok(Self { config, _config_watcher: config_watcher, _telemetry: telemetry })
+    }
+}
@@ -0,0 +1,431 @@
+//! Error mapping at the JSON-RPC boundary
+//!
+//! Every failure a client can see gets a code it can act on:
+//!
+//! | failure                               | code                          | data                          |
+//! |---------------------------------------|-------------------------------|-------------------------------|
+//! | body or line is not JSON              | -32700 parse error            | `line`, `column`              |
+//! | JSON but not a JSON-RPC message       | -32600 invalid request        |                               |
+//! | tool arguments violate the schema     | -32602 invalid params         | `path` of the offending field |
+//! | upstream timeout or network failure   | [`UPSTREAM_UNAVAILABLE`]      | `retryable: true`, `kind`     |
+//! | tool, session or process at its concurrency limit | [`TOOL_BUSY`]     | `tool`, `limit`, `retryable`, `scope` |
+//! | session over its rate limit           | [`SESSION_RATE_LIMITED`]      | `tool`, `limit`, `retry_after_ms` |
+//! | session cancelled by an administrator | [`SESSION_CANCELLED`]         | `retryable: false`            |
+//! | tool panicked                         | -32603 internal error         | `request_id`, `tool`          |
+//! | unexpected (de)serialization failure  | -32603 internal error         | `path` of the offending field |
+//!
+//! Transports check incoming messages with [`check_message`]; tool errors are
+//! mapped by [`map_tool_error`] when the server dispatches a call.
+
+use rmcp::model::{CallToolResult, ClientJsonRpcMessage, ErrorCode, ErrorData as McpError, JsonObject};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::future::Future;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, DuplexStream};
+use tracing::Instrument;
+
+#[cfg(doc)]
+use super::concurrency::TOOL_BUSY;
+#[cfg(doc)]
+use super::sessions::{SESSION_CANCELLED, SESSION_RATE_LIMITED};
+use super::utils::ToolError;
+use crate::log_important;
+use crate::utils::correlation;
+use crate::utils::locale::Message;
+
+/// Application error: an upstream service timed out or could not be reached.
+/// Retrying the same call later may succeed.
+pub const UPSTREAM_UNAVAILABLE: ErrorCode = ErrorCode(-32001);
+
+/// Buffer size of the pipes between a checked transport and the service
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Largest request body accepted by the HTTP transport
+pub const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Parse tool arguments, reporting schema violations with the offending path
+pub fn parse_arguments<T: DeserializeOwned>(tool: &str, arguments: Option<JsonObject>) -> Result<T, McpError> {
+    let value = Value::Object(arguments.unwrap_or_default());
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        let path = e.path().to_string();
+        McpError::invalid_params(
+            Message::InvalidArguments { tool, error: &e.inner().to_string(), path: &path }.text(),
+            Some(json!({ "path": path })),
+        )
+    })
+}
+
+/// Map a tool failure to the error the client receives
+pub fn map_tool_error(error: ToolError) -> McpError {
+    let error = match error {
+        ToolError::Rpc(error) => return error,
+        ToolError::Failed(error) => error,
+    };
+
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if let Some(kind) = upstream_failure_kind(e) {
+                return upstream_error(kind, &error);
+            }
+        }
+        if let Some(e) = cause.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>() {
+            return McpError::internal_error(format!("{:#}", error), Some(json!({ "path": e.path().to_string() })));
+        }
+        if let Some(e) = cause.downcast_ref::<serde_json::Error>() {
+            return McpError::internal_error(
+                format!("{:#}", error),
+                Some(json!({ "path": Value::Null, "line": e.line(), "column": e.column() })),
+            );
+        }
+    }
+    McpError::internal_error(format!("{:#}", error), None)
+}
+
+/// Whether a failure is an upstream timeout or network error
+pub fn is_upstream_unavailable(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+        .any(|e| upstream_failure_kind(e).is_some())
+}
+
+fn upstream_failure_kind(error: &reqwest::Error) -> Option<&'static str> {
+    if error.is_timeout() {
+        Some("timeout")
+    } else if error.is_connect() {
+        Some("connect")
+    } else if error.is_request() || error.is_body() {
+        Some("network")
+    } else {
+        None
+    }
+}
+
+fn upstream_error(kind: &str, error: &anyhow::Error) -> McpError {
+    McpError::new(
+        UPSTREAM_UNAVAILABLE,
+        format!("Upstream request failed: {:#}", error),
+        Some(json!({ "retryable": true, "kind": kind })),
+    )
+}
+
+/// Run a tool call as its own task and map its failure
+///
+/// A panic is reported as an internal error carrying the request id instead of
+/// taking the session down. Dropping the returned future aborts the call.
+pub async fn run_tool_call<F>(tool: &str, request_id: &str, call: F) -> Result<CallToolResult, McpError>
+where
+    F: Future<Output = Result<CallToolResult, ToolError>> + Send + 'static,
+{
+    // The task keeps the caller's span and correlation id so upstream requests nest under the tool call
+    let mut task = AbortOnDrop(tokio::spawn(correlation::inherit(call).in_current_span()));
+    match (&mut task.0).await {
+        Ok(result) => result.map_err(map_tool_error),
+        Err(e) => {
+            log_important!(error, "Tool {} panicked (request id {}): {}", tool, request_id, e);
+            Err(panic_error(tool, request_id))
+        }
+    }
+}
+
+struct AbortOnDrop<T>(tokio::task::JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Error for a tool call that panicked
+pub fn panic_error(tool: &str, request_id: &str) -> McpError {
+    McpError::internal_error(
+        format!("Tool {} failed unexpectedly (request id {})", tool, request_id),
+        Some(json!({ "request_id": request_id, "tool": tool })),
+    )
+}
+
+/// Check one incoming message
+///
+/// Returns the message, or the JSON-RPC error to answer with. Malformed
+/// notifications get no answer (`Err(None)`), as the protocol forbids one.
+pub fn check_message(bytes: &[u8]) -> Result<ClientJsonRpcMessage, Option<Value>> {
+    let value: Value = match serde_json::from_slice(bytes) {
+        Ok(value) => value,
+        Err(e) => {
+            return Err(Some(error_response(
+                Value::Null,
+                McpError::parse_error(
+                    format!("Parse error: {}", e),
+                    Some(json!({ "line": e.line(), "column": e.column() })),
+                ),
+            )))
+        }
+    };
+
+    let id = value.get("id").cloned();
+    match serde_json::from_value::<ClientJsonRpcMessage>(value) {
+        Ok(message) => Ok(message),
+        Err(e) => match id {
+            Some(id) => Err(Some(error_response(id, McpError::invalid_request(format!("Invalid request: {}", e), None)))),
+            None => {
+                log_important!(warn, "Ignoring invalid JSON-RPC notification: {}", e);
+                Err(None)
+            }
+        },
+    }
+}
+
+/// JSON-RPC error response; `id` is null when the request could not be read
+pub fn error_response(id: Value, error: McpError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
+
+/// Put message checking in front of a line-delimited transport such as stdio
+///
+/// Valid lines are passed to the returned reader unchanged; invalid ones are
+/// answered on `output` directly instead of ending the session, which is what
+/// the service does when it cannot decode a line. Everything the service
+/// writes to the returned writer is copied to `output`.
+pub fn checked_transport<R, W>(input: R, output: W) -> (DuplexStream, DuplexStream)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (service_in, mut forward) = tokio::io::duplex(PIPE_CAPACITY);
+    let (service_out, replies) = tokio::io::duplex(PIPE_CAPACITY);
+    let (errors_tx, mut errors_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(input).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match check_message(line.as_bytes()) {
+                Ok(_) => {
+                    if forward.write_all(format!("{}\n", line).as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+                Err(Some(reply)) => {
+                    log_important!(warn, "Rejected malformed message: {}", reply["error"]["message"]);
+                    let _ = errors_tx.send(reply.to_string());
+                }
+                Err(None) => {}
+            }
+        }
+        // Dropping `forward` ends the session like closing stdin would
+    });
+
+    // Single writer, so error replies never interleave with service output
+    tokio::spawn(async move {
+        let mut output = output;
+        let mut replies = BufReader::new(replies).lines();
+        let mut errors_open = true;
+        loop {
+            let line = tokio::select! {
+                line = replies.next_line() => match line {
+                    Ok(Some(line)) => line,
+                    _ => break,
+                },
+                error = errors_rx.recv(), if errors_open => match error {
+                    Some(error) => error,
+                    None => {
+                        errors_open = false;
+                        continue;
+                    }
+                },
+            };
+            if output.write_all(format!("{}\n", line).as_bytes()).await.is_err() || output.flush().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    (service_in, service_out)
+}
+
+#[cfg(feature = "http-server")]
+pub use middleware::reject_malformed_messages;
+
+#[cfg(feature = "http-server")]
+mod middleware {
+    use axum::body::{to_bytes, Body};
+    use axum::extract::Request;
+    use axum::http::{header, Method, StatusCode};
+    use axum::middleware::Next;
+    use axum::response::{IntoResponse, Response};
+
+    use super::{check_message, MAX_BODY_BYTES};
+
+    /// Axum middleware: answer malformed POST bodies with a JSON-RPC error
+    pub async fn reject_malformed_messages(request: Request, next: Next) -> Response {
+        if request.method() != Method::POST {
+            return next.run(request).await;
+        }
+
+        let (parts, body) = request.into_parts();
+        let Ok(bytes) = to_bytes(body, MAX_BODY_BYTES).await else {
+            return (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response();
+        };
+        match check_message(&bytes) {
+            Ok(_) => next.run(Request::from_parts(parts, Body::from(bytes))).await,
+            Err(Some(error)) => {
+                (StatusCode::BAD_REQUEST, [(header::CONTENT_TYPE, "application/json")], Body::from(error.to_string()))
+                    .into_response()
+            }
+            Err(None) => StatusCode::ACCEPTED.into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ErrorCode;
+    use tokio::io::AsyncReadExt;
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Args {
+        library: String,
+        page: Option<u32>,
+    }
+
+    fn object(value: Value) -> Option<JsonObject> {
+        value.as_object().cloned()
+    }
+
+    #[test]
+    fn test_schema_violation_is_invalid_params_with_path() {
+        let err = parse_arguments::<Args>("docs", object(json!({ "library": "a/b", "page": "two" }))).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+        assert_eq!(err.data, Some(json!({ "path": "page" })));
+
+        let err = parse_arguments::<Args>("docs", None).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+        assert!(err.message.contains("missing field `library`"));
+    }
+
+    #[test]
+    fn test_malformed_messages() {
+        let reply = check_message(b"{\"jsonrpc\": \"2.0\", ").unwrap_err().unwrap();
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], -32700);
+        assert_eq!(reply["error"]["data"]["line"], 1);
+
+        let reply = check_message(br#"{"jsonrpc":"2.0","id":7,"params":{}}"#).unwrap_err().unwrap();
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["error"]["code"], -32600);
+
+        // A broken notification is dropped without an answer
+        assert_eq!(check_message(br#"{"jsonrpc":"2.0","params":{}}"#).unwrap_err(), None);
+        assert!(check_message(br#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_upstream_timeout_is_retryable() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Accept and never answer
+        tokio::spawn(async move {
+            let _conn = listener.accept().await;
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        });
+        let err = reqwest::Client::new()
+            .get(format!("http://{}/", addr))
+            .timeout(std::time::Duration::from_millis(50))
+            .send()
+            .await
+            .unwrap_err();
+
+        let error = anyhow::Error::new(crate::docs_client::DocsError::Network(err)).context("Docs query failed");
+        assert!(is_upstream_unavailable(&error));
+        let mapped = map_tool_error(ToolError::Failed(error));
+        assert_eq!(mapped.code, UPSTREAM_UNAVAILABLE);
+        assert_eq!(mapped.data, Some(json!({ "retryable": true, "kind": "timeout" })));
+    }
+
+    #[test]
+    fn test_serde_failures_are_internal_with_path() {
+        let text = r#"{"entries": {"a": {"when": 5}}}"#;
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Entry {
+            when: String,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct File {
+            entries: std::collections::BTreeMap<String, Entry>,
+        }
+        let err = serde_path_to_error::deserialize::<_, File>(&mut serde_json::Deserializer::from_str(text)).unwrap_err();
+        let mapped = map_tool_error(ToolError::Failed(anyhow::Error::new(err).context("Failed to read store")));
+        assert_eq!(mapped.code, ErrorCode::INTERNAL_ERROR);
+        assert_eq!(mapped.data, Some(json!({ "path": "entries.a.when" })));
+        assert!(mapped.message.starts_with("Failed to read store: "));
+
+        let plain = serde_json::from_str::<Value>("[1,").unwrap_err();
+        let mapped = map_tool_error(ToolError::Failed(plain.into()));
+        assert_eq!(mapped.code, ErrorCode::INTERNAL_ERROR);
+        assert_eq!(mapped.data.unwrap()["path"], Value::Null);
+
+        let mapped = map_tool_error(ToolError::Rpc(McpError::invalid_params("bad", None)));
+        assert_eq!(mapped.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_panic_is_internal_with_request_id() {
+        let err = run_tool_call("docs", "42", async { panic!("boom") }).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::INTERNAL_ERROR);
+        assert_eq!(err.data, Some(json!({ "request_id": "42", "tool": "docs" })));
+
+        let ok = run_tool_call("docs", "43", async { Ok(CallToolResult::success(vec![])) }).await;
+        assert!(ok.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_checked_transport_answers_bad_lines_and_keeps_going() {
+        let (mut client, server_side) = tokio::io::duplex(4096);
+        let (server_read, server_write) = tokio::io::split(server_side);
+        let (mut service_in, mut service_out) = checked_transport(server_read, server_write);
+
+        client.write_all(b"not json\n\n{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n").await.unwrap();
+
+        let mut forwarded = BufReader::new(&mut service_in).lines();
+        let line = forwarded.next_line().await.unwrap().unwrap();
+        assert_eq!(line, r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#);
+
+        service_out.write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n").await.unwrap();
+        let mut received = String::new();
+        let mut buf = [0u8; 1024];
+        while received.lines().count() < 2 {
+            let n = client.read(&mut buf).await.unwrap();
+            received.push_str(std::str::from_utf8(&buf[..n]).unwrap());
+        }
+        let replies: Vec<Value> = received.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert!(replies.iter().any(|r| r["error"]["code"] == -32700));
+        assert!(replies.iter().any(|r| r["id"] == 1 && r.get("result").is_some()));
+    }
+
+    #[cfg(feature = "http-server")]
+    #[tokio::test]
+    async fn test_http_middleware_answers_malformed_bodies() {
+        use axum::{middleware::from_fn, routing::post, Router};
+
+        let app = Router::new().route("/sse", post(|| async { "reached" })).layer(from_fn(reject_malformed_messages));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sse", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = reqwest::Client::new();
+        let response = client.post(&url).header("content-type", "application/json").body("{oops").send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], -32700);
+        assert_eq!(body["id"], Value::Null);
+
+        let response = client.post(&url).body(r#"{"jsonrpc":"2.0","id":"a","method":"ping"}"#).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "reached");
+    }
+}
@@ -85,6 +85,123 @@ pub async fn get_mcp_tools_config(state: State<'_, AppState>) -> Result<Vec<MCPT
         has_config: true, // Docs 工具有配置选项
     });
 
+    // Docs search tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_DOCS_SEARCH.to_string(),
+        name: "Docs Search".to_string(),
+        description: "Find library identifiers in the documentation index".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_DOCS_SEARCH).copied().unwrap_or(true),
+        can_disable: true,
+        icon: "i-carbon-search-locate text-lg text-orange-600 dark:text-orange-400".to_string(),
+        icon_bg: "bg-orange-100 dark:bg-orange-900".to_string(),
+        dark_icon_bg: "dark:bg-orange-800".to_string(),
+        has_config: false,
+    });
+
+    // Docs compare tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_DOCS_COMPARE.to_string(),
+        name: "Docs Compare".to_string(),
+        description: "Compare how libraries document the same topic, side by side".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_DOCS_COMPARE).copied().unwrap_or(true),
+        can_disable: true,
+        icon: "i-carbon-compare text-lg text-orange-600 dark:text-orange-400".to_string(),
+        icon_bg: "bg-orange-100 dark:bg-orange-900".to_string(),
+        dark_icon_bg: "dark:bg-orange-800".to_string(),
+        has_config: false,
+    });
+
+    // Stats tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_DEVKIT_STATS.to_string(),
+        name: "Stats".to_string(),
+        description: "Docs API request metrics and cache hit rate".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_DEVKIT_STATS).copied().unwrap_or(true),
+        can_disable: true,
+        icon: "i-carbon-chart-line text-lg text-orange-600 dark:text-orange-400".to_string(),
+        icon_bg: "bg-orange-100 dark:bg-orange-900".to_string(),
+        dark_icon_bg: "dark:bg-orange-800".to_string(),
+        has_config: false,
+    });
+
+    // Docs status tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_DOCS_STATUS.to_string(),
+        name: "Docs Status".to_string(),
+        description: "Why docs queries fail: reachability, keys, rate limit and last error".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_DOCS_STATUS).copied().unwrap_or(true),
+        can_disable: true,
+        icon: "i-carbon-health-cross text-lg text-orange-600 dark:text-orange-400".to_string(),
+        icon_bg: "bg-orange-100 dark:bg-orange-900".to_string(),
+        dark_icon_bg: "dark:bg-orange-800".to_string(),
+        has_config: false,
+    });
+
+    // Project docs tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_PROJECT_DOCS.to_string(),
+        name: "Project Docs".to_string(),
+        description: "Offline search over the project's own documentation".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_PROJECT_DOCS).copied().unwrap_or(true),
+        can_disable: true,
+        icon: "i-carbon-notebook text-lg text-teal-600 dark:text-teal-400".to_string(),
+        icon_bg: "bg-teal-100 dark:bg-teal-900".to_string(),
+        dark_icon_bg: "dark:bg-teal-800".to_string(),
+        has_config: false,
+    });
+
+    // Run command tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_RUN_COMMAND.to_string(),
+        name: "Run Command".to_string(),
+        description: "Allowlisted project commands such as test, lint and build".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_RUN_COMMAND).copied().unwrap_or(false),
+        can_disable: true,
+        icon: "i-carbon-terminal text-lg text-gray-600 dark:text-gray-400".to_string(),
+        icon_bg: "bg-gray-100 dark:bg-gray-900".to_string(),
+        dark_icon_bg: "dark:bg-gray-800".to_string(),
+        has_config: false,
+    });
+
+    // Git context tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_GIT_CONTEXT.to_string(),
+        name: "Git Context".to_string(),
+        description: "Git status, diff and recent log of the project".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_GIT_CONTEXT).copied().unwrap_or(true),
+        can_disable: true,
+        icon: "i-carbon-branch text-lg text-red-600 dark:text-red-400".to_string(),
+        icon_bg: "bg-red-100 dark:bg-red-900".to_string(),
+        dark_icon_bg: "dark:bg-red-800".to_string(),
+        has_config: false,
+    });
+
+    // List sessions tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_LIST_SESSIONS.to_string(),
+        name: "Sessions".to_string(),
+        description: "Connected MCP clients, with cancellation and per-session rate limits".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_LIST_SESSIONS).copied().unwrap_or(false),
+        can_disable: true,
+        icon: "i-carbon-user-multiple text-lg text-gray-600 dark:text-gray-400".to_string(),
+        icon_bg: "bg-gray-100 dark:bg-gray-900".to_string(),
+        dark_icon_bg: "dark:bg-gray-800".to_string(),
+        has_config: false,
+    });
+
+    // Reload config tool
+    tools.push(MCPToolConfig {
+        id: mcp::TOOL_RELOAD_CONFIG.to_string(),
+        name: "Reload Config".to_string(),
+        description: "Apply config file changes to the running MCP server without a restart".to_string(),
+        enabled: config.mcp_config.tools.get(mcp::TOOL_RELOAD_CONFIG).copied().unwrap_or(false),
+        can_disable: true,
+        icon: "i-carbon-renew text-lg text-gray-600 dark:text-gray-400".to_string(),
+        icon_bg: "bg-gray-100 dark:bg-gray-900".to_string(),
+        dark_icon_bg: "dark:bg-gray-800".to_string(),
+        has_config: false,
+    });
+
     // 按启用状态排序，启用的在前
     tools.sort_by(|a, b| b.enabled.cmp(&a.enabled));
     
@@ -0,0 +1,482 @@
+//! Per-tool concurrency limits
+//!
+//! `mcp_config.tool_limits` caps how many calls of one tool run at once, e.g.
+//! a single popup or two docs lookups. Each limited tool gets its own
+//! semaphore shared by every session of the process, so a slow tool only
+//! queues its own calls. A call waits for a slot up to the tool's queue
+//! timeout and is then rejected with [`TOOL_BUSY`], naming the tool and its
+//! limit. Tools without a limit are not tracked at all.
+//!
+//! In-flight and queued counts of every limited tool are reported by `/health`.
+//!
+//! Independently of the tool, `mcp_config.session_call_limit` caps the calls
+//! one session runs at once and `mcp_config.call_limit` those of the whole
+//! process, so an agent loop cannot saturate the server. Both queue and reject
+//! the same way, with `scope` in the error data saying which cap was hit.
+
+use rmcp::model::{ErrorCode, ErrorData as McpError};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config::ToolLimitConfig;
+use crate::log_important;
+
+/// Application error: a tool is at its concurrency limit and the queue timed out.
+/// Retrying later may succeed.
+pub const TOOL_BUSY: ErrorCode = ErrorCode(-32002);
+
+/// Time a call waits for a slot when the config does not say
+pub const DEFAULT_QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+static GLOBAL: once_cell::sync::Lazy<ToolLimiter> = once_cell::sync::Lazy::new(ToolLimiter::default);
+
+static GLOBAL_CALLS: once_cell::sync::Lazy<CallLimiter> = once_cell::sync::Lazy::new(CallLimiter::default);
+
+/// Limit of one tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolLimit {
+    pub max_concurrent: usize,
+    pub queue_timeout: Duration,
+}
+
+impl ToolLimit {
+    /// `None` for a `max_concurrent` of 0, which means unlimited
+    pub fn from_config(config: &ToolLimitConfig) -> Option<Self> {
+        (config.max_concurrent > 0).then(|| Self {
+            max_concurrent: config.max_concurrent,
+            queue_timeout: config.queue_timeout_ms.map_or(DEFAULT_QUEUE_TIMEOUT, Duration::from_millis),
+        })
+    }
+}
+
+/// Key a tool is limited under: `cache_sync` shares the popup limit of `cache`
+pub fn limit_key(tool: &str) -> &str {
+    match tool {
+        "cache_sync" => "cache",
+        other => other,
+    }
+}
+
+/// Numbers of one limited tool, as reported by `/health`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ToolStats {
+    pub limit: usize,
+    pub in_flight: usize,
+    pub queued: usize,
+}
+
+/// Semaphore of one limit; the queue timeout is read per call, so only a
+/// changed `max_concurrent` replaces it
+struct Slot {
+    max_concurrent: usize,
+    semaphore: Arc<Semaphore>,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+}
+
+/// Holds a slot of a limited tool until dropped
+pub struct ToolPermit {
+    slot: Arc<Slot>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for ToolPermit {
+    fn drop(&mut self) {
+        self.slot.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Counts a call as queued while it waits, including when the wait is cancelled
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Semaphores of the limited tools
+#[derive(Default)]
+pub struct ToolLimiter {
+    slots: Mutex<HashMap<String, Arc<Slot>>>,
+}
+
+impl ToolLimiter {
+    /// Limiter shared by every session of the process
+    pub fn global() -> &'static ToolLimiter {
+        &GLOBAL
+    }
+
+    /// Wait for a slot of `tool`
+    ///
+    /// Returns `Ok(None)` for an unlimited tool. A changed `max_concurrent`
+    /// takes effect for new calls; calls holding a slot under the old limit
+    /// finish normally. A changed queue timeout keeps the slot.
+    pub async fn acquire(&self, tool: &str, limit: Option<ToolLimit>) -> Result<Option<ToolPermit>, McpError> {
+        let key = limit_key(tool);
+        let (Some(limit), Some(slot)) = (limit, self.slot(key, limit)) else {
+            return Ok(None);
+        };
+
+        match wait_for_slot(slot.clone(), limit.queue_timeout).await {
+            Some(permit) => Ok(Some(permit)),
+            None => {
+                log_important!(
+                    warn,
+                    "Tool {} is busy: {} call(s) running at limit {}, rejected after {} ms in queue",
+                    key,
+                    slot.in_flight.load(Ordering::SeqCst),
+                    limit.max_concurrent,
+                    limit.queue_timeout.as_millis()
+                );
+                Err(busy_error(key, limit))
+            }
+        }
+    }
+
+    /// In-flight and queued counts per limited tool
+    pub fn stats(&self) -> BTreeMap<String, ToolStats> {
+        let Ok(slots) = self.slots.lock() else {
+            return BTreeMap::new();
+        };
+        slots
+            .iter()
+            .map(|(tool, slot)| {
+                let stats = ToolStats {
+                    limit: slot.max_concurrent,
+                    in_flight: slot.in_flight.load(Ordering::SeqCst),
+                    queued: slot.queued.load(Ordering::SeqCst),
+                };
+                (tool.clone(), stats)
+            })
+            .collect()
+    }
+
+    /// Slot of `key` under `limit`, replacing one created for a different `max_concurrent`
+    fn slot(&self, key: &str, limit: Option<ToolLimit>) -> Option<Arc<Slot>> {
+        let mut slots = self.slots.lock().ok()?;
+        let Some(limit) = limit else {
+            slots.remove(key);
+            return None;
+        };
+        if let Some(slot) = slots.get(key).filter(|slot| slot.max_concurrent == limit.max_concurrent) {
+            return Some(slot.clone());
+        }
+        let slot = Arc::new(Slot::new(limit.max_concurrent));
+        slots.insert(key.to_string(), slot.clone());
+        Some(slot)
+    }
+}
+
+/// What a [`CallLimiter`] caps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallScope {
+    /// The calls of one session
+    Session,
+    /// Every call of the process
+    Process,
+}
+
+impl CallScope {
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Session => "this session",
+            Self::Process => "the server",
+        }
+    }
+}
+
+/// Cap on simultaneous calls of any tool, for one session or the whole process
+pub struct CallLimiter {
+    scope: CallScope,
+    slot: Mutex<Option<Arc<Slot>>>,
+}
+
+impl Default for CallLimiter {
+    fn default() -> Self {
+        Self::new(CallScope::Process)
+    }
+}
+
+impl CallLimiter {
+    pub fn new(scope: CallScope) -> Self {
+        Self { scope, slot: Mutex::new(None) }
+    }
+
+    /// Limiter of every call in the process
+    pub fn global() -> &'static CallLimiter {
+        &GLOBAL_CALLS
+    }
+
+    /// Wait for a slot for a call of `tool`; `Ok(None)` without a limit
+    pub async fn acquire(&self, tool: &str, limit: Option<ToolLimit>) -> Result<Option<ToolPermit>, McpError> {
+        let (Some(limit), Some(slot)) = (limit, self.slot(limit)) else {
+            return Ok(None);
+        };
+        match wait_for_slot(slot.clone(), limit.queue_timeout).await {
+            Some(permit) => Ok(Some(permit)),
+            None => {
+                log_important!(
+                    warn,
+                    "Too many calls in {}: {} running at limit {}, {} rejected after {} ms in queue",
+                    self.scope.describe(),
+                    slot.in_flight.load(Ordering::SeqCst),
+                    limit.max_concurrent,
+                    tool,
+                    limit.queue_timeout.as_millis()
+                );
+                Err(scope_busy_error(self.scope, tool, limit))
+            }
+        }
+    }
+
+    /// In-flight and queued calls, `None` without a limit
+    pub fn stats(&self) -> Option<ToolStats> {
+        let slot = self.slot.lock().ok()?.clone()?;
+        Some(ToolStats {
+            limit: slot.max_concurrent,
+            in_flight: slot.in_flight.load(Ordering::SeqCst),
+            queued: slot.queued.load(Ordering::SeqCst),
+        })
+    }
+
+    /// Slot under `limit`, replacing one created for a different `max_concurrent`
+    fn slot(&self, limit: Option<ToolLimit>) -> Option<Arc<Slot>> {
+        let mut current = self.slot.lock().ok()?;
+        let Some(limit) = limit else {
+            *current = None;
+            return None;
+        };
+        if let Some(slot) = current.as_ref().filter(|slot| slot.max_concurrent == limit.max_concurrent) {
+            return Some(slot.clone());
+        }
+        Some(current.insert(Arc::new(Slot::new(limit.max_concurrent))).clone())
+    }
+}
+
+impl Slot {
+    fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent,
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            in_flight: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// Wait up to `queue_timeout` for a permit; `None` when none freed up
+async fn wait_for_slot(slot: Arc<Slot>, queue_timeout: Duration) -> Option<ToolPermit> {
+    slot.queued.fetch_add(1, Ordering::SeqCst);
+    let queued = QueuedGuard(&slot.queued);
+    let acquired = match slot.semaphore.clone().try_acquire_owned() {
+        Ok(permit) => Some(permit),
+        Err(_) if queue_timeout.is_zero() => None,
+        Err(_) => tokio::time::timeout(queue_timeout, slot.semaphore.clone().acquire_owned())
+            .await
+            .ok()
+            .and_then(Result::ok),
+    };
+    drop(queued);
+
+    let permit = acquired?;
+    slot.in_flight.fetch_add(1, Ordering::SeqCst);
+    Some(ToolPermit { slot, _permit: permit })
+}
+
+fn busy_error(tool: &str, limit: ToolLimit) -> McpError {
+    McpError::new(
+        TOOL_BUSY,
+        format!(
+            "Tool {} is busy: at its concurrency limit of {}, no slot freed within {} ms",
+            tool,
+            limit.max_concurrent,
+            limit.queue_timeout.as_millis()
+        ),
+        Some(json!({ "tool": tool, "limit": limit.max_concurrent, "retryable": true })),
+    )
+}
+
+fn scope_busy_error(scope: CallScope, tool: &str, limit: ToolLimit) -> McpError {
+    McpError::new(
+        TOOL_BUSY,
+        format!(
+            "Too many tool calls running in {}: at its limit of {}, no slot freed within {} ms for {}",
+            scope.describe(),
+            limit.max_concurrent,
+            limit.queue_timeout.as_millis(),
+            tool
+        ),
+        Some(json!({ "scope": scope, "tool": tool, "limit": limit.max_concurrent, "retryable": true })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(max_concurrent: usize, queue_timeout_ms: u64) -> Option<ToolLimit> {
+        Some(ToolLimit { max_concurrent, queue_timeout: Duration::from_millis(queue_timeout_ms) })
+    }
+
+    /// Running and peak number of calls of one fake tool
+    #[derive(Default)]
+    struct Load {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    /// A fake tool that holds its slot for `duration`
+    async fn slow_call(limiter: Arc<ToolLimiter>, tool: &'static str, limit: Option<ToolLimit>, load: Arc<Load>, duration: Duration) {
+        let _permit = limiter.acquire(tool, limit).await.unwrap();
+        let running = load.running.fetch_add(1, Ordering::SeqCst) + 1;
+        load.peak.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(duration).await;
+        load.running.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_per_tool_caps_are_independent() {
+        let limiter = Arc::new(ToolLimiter::default());
+        let tools = [("popup", limit(1, 60_000)), ("docs", limit(2, 60_000)), ("git_context", None)];
+        let loads: Vec<Arc<Load>> = tools.iter().map(|_| Arc::new(Load::default())).collect();
+
+        let mut calls = Vec::new();
+        for ((tool, limit), load) in tools.iter().zip(&loads) {
+            for _ in 0..4 {
+                calls.push(tokio::spawn(slow_call(limiter.clone(), tool, *limit, load.clone(), Duration::from_secs(1))));
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let stats = limiter.stats();
+        assert_eq!(stats["popup"], ToolStats { limit: 1, in_flight: 1, queued: 3 });
+        assert_eq!(stats["docs"], ToolStats { limit: 2, in_flight: 2, queued: 2 });
+        assert!(!stats.contains_key("git_context"));
+
+        for call in calls {
+            call.await.unwrap();
+        }
+        let peaks: Vec<usize> = loads.iter().map(|load| load.peak.load(Ordering::SeqCst)).collect();
+        assert_eq!(peaks, vec![1, 2, 4]);
+        assert_eq!(limiter.stats()["popup"], ToolStats { limit: 1, in_flight: 0, queued: 0 });
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queue_timeout_returns_busy_error() {
+        let limiter = Arc::new(ToolLimiter::default());
+        let busy = tokio::spawn(slow_call(limiter.clone(), "docs", limit(1, 100), Arc::default(), Duration::from_secs(5)));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // A full tool does not hold up another one
+        let other = limiter.acquire("cache", limit(1, 100)).await.unwrap();
+        assert!(other.is_some());
+
+        let error = limiter.acquire("docs", limit(1, 100)).await.err().unwrap();
+        assert_eq!(error.code, TOOL_BUSY);
+        assert!(error.message.contains("Tool docs is busy"), "{}", error.message);
+        assert!(error.message.contains("limit of 1"), "{}", error.message);
+        assert_eq!(error.data.unwrap()["limit"], 1);
+        assert_eq!(limiter.stats()["docs"], ToolStats { limit: 1, in_flight: 1, queued: 0 });
+        busy.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cache_sync_shares_the_popup_limit() {
+        let limiter = ToolLimiter::default();
+        let _popup = limiter.acquire("cache", limit(1, 0)).await.unwrap();
+
+        // No queue: rejected right away
+        let started = tokio::time::Instant::now();
+        assert!(limiter.acquire("cache_sync", limit(1, 0)).await.is_err());
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        // A reload that only changes the queue timeout keeps the running call counted
+        assert!(limiter.acquire("cache", limit(1, 50)).await.is_err());
+        assert_eq!(limiter.stats()["cache"], ToolStats { limit: 1, in_flight: 1, queued: 0 });
+
+        // Removing the limit stops tracking the tool
+        assert!(limiter.acquire("cache_sync", None).await.unwrap().is_none());
+        assert!(limiter.stats().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queue_timeout_change_keeps_slot() {
+        let limiter = Arc::new(ToolLimiter::default());
+        let first = limiter.acquire("docs", limit(2, 100)).await.unwrap();
+        let _second = limiter.acquire("docs", limit(2, 100)).await.unwrap();
+
+        // The new timeout applies to this call, which queues on the same semaphore
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move {
+                let started = tokio::time::Instant::now();
+                let permit = limiter.acquire("docs", limit(2, 5_000)).await.unwrap();
+                (permit.is_some(), started.elapsed())
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(1_000)).await;
+        assert_eq!(limiter.stats()["docs"], ToolStats { limit: 2, in_flight: 2, queued: 1 });
+
+        drop(first);
+        let (admitted, waited) = waiting.await.unwrap();
+        assert!(admitted);
+        assert!(waited >= Duration::from_millis(1_000), "{:?}", waited);
+        assert_eq!(limiter.stats()["docs"], ToolStats { limit: 2, in_flight: 1, queued: 0 });
+
+        // A zero timeout read on this call rejects at once
+        let _third = limiter.acquire("docs", limit(2, 0)).await.unwrap();
+        let started = tokio::time::Instant::now();
+        assert!(limiter.acquire("docs", limit(2, 0)).await.is_err());
+        assert_eq!(started.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_session_limit_survives_reload() {
+        let limiter = CallLimiter::new(CallScope::Session);
+        let _running = limiter.acquire("docs", limit(1, 100)).await.unwrap();
+
+        // A reload changing only the queue timeout still counts the running call
+        let started = tokio::time::Instant::now();
+        let error = limiter.acquire("git_context", limit(1, 300)).await.err().unwrap();
+        assert_eq!(error.data.unwrap()["scope"], "session");
+        assert_eq!(started.elapsed(), Duration::from_millis(300));
+        assert_eq!(limiter.stats(), Some(ToolStats { limit: 1, in_flight: 1, queued: 0 }));
+
+        // A raised cap takes effect for new calls
+        assert!(limiter.acquire("git_context", limit(2, 0)).await.unwrap().is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_call_limit_spans_tools() {
+        let limiter = CallLimiter::new(CallScope::Session);
+        let docs = limiter.acquire("docs", limit(2, 100)).await.unwrap();
+        let _popup = limiter.acquire("cache", limit(2, 100)).await.unwrap();
+        assert_eq!(limiter.stats(), Some(ToolStats { limit: 2, in_flight: 2, queued: 0 }));
+
+        // A third call of any tool queues and is rejected with the scope
+        let error = limiter.acquire("git_context", limit(2, 100)).await.err().unwrap();
+        assert_eq!(error.code, TOOL_BUSY);
+        let data = error.data.unwrap();
+        assert_eq!(data["scope"], "session");
+        assert_eq!(data["tool"], "git_context");
+        assert_eq!(data["retryable"], true);
+
+        // A freed slot admits the queued call
+        let waiting = tokio::spawn(async move {
+            let permit = limiter.acquire("git_context", limit(2, 1_000)).await.unwrap();
+            (permit.is_some(), limiter.stats())
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(docs);
+        let (admitted, stats) = waiting.await.unwrap();
+        assert!(admitted);
+        assert_eq!(stats, Some(ToolStats { limit: 2, in_flight: 2, queued: 0 }));
+    }
+}
@@ -0,0 +1,295 @@
+//! Dry runs of mutating tool operations (`dry_run: true`)
+//!
+//! The server runs a call that asks for a dry run inside [`run`]. While it is
+//! active, the places that change state stage their writes here instead of
+//! touching the disk ([`stage`], [`write`], [`append`]), and reads of a staged
+//! file see the staged content, so an operation runs its normal code path and
+//! the writes it would make become the plan. Tools add what the operation
+//! would change in their own terms with [`record`] and phrase their text as
+//! "would". The result carries the plan as Markdown and as `dry_run` in the
+//! structured content, and is marked with [`META_KEY`] in `_meta` so
+//! middlewares can tell it changed nothing.
+//!
+//! Only the tools in [`DRY_RUN_TOOLS`] accept the flag; the others reject it
+//! rather than run for real.
+
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError, JsonObject, Meta, RawContent, Tool};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::utils::ToolError;
+
+/// Argument that asks for a dry run
+pub const DRY_RUN_KEY: &str = "dry_run";
+
+/// `_meta` flag of a dry-run result
+pub const META_KEY: &str = "dryRun";
+
+/// Tools whose mutating operations support `dry_run`
+pub const DRY_RUN_TOOLS: &[&str] = &["store", "docs"];
+
+tokio::task_local! {
+    static PLAN: Arc<Mutex<Plan>>;
+}
+
+/// What a dry run would have done
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Plan {
+    /// Changes in the tool's terms, e.g. the entries that would be deleted
+    pub changes: Vec<Value>,
+    /// Files that would be written, in the order they were first staged
+    pub files: Vec<PlannedFile>,
+}
+
+/// A file a dry run would write
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub bytes: usize,
+    /// Size of the file it would replace, `None` for a new file
+    pub previous_bytes: Option<usize>,
+    #[serde(skip)]
+    content: String,
+}
+
+/// Whether the current call is a dry run
+pub fn is_active() -> bool {
+    PLAN.try_with(|_| ()).is_ok()
+}
+
+/// Whether `arguments` ask for a dry run of `tool`
+///
+/// A dry run of a tool that cannot do one is refused, so it never runs for real.
+pub fn requested(tool: &str, arguments: Option<&JsonObject>) -> Result<bool, McpError> {
+    let requested = match arguments.and_then(|arguments| arguments.get(DRY_RUN_KEY)) {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(requested)) => *requested,
+        Some(_) => {
+            return Err(McpError::invalid_params(
+                format!("{} must be a boolean", DRY_RUN_KEY),
+                Some(json!({ "path": DRY_RUN_KEY })),
+            ))
+        }
+    };
+    if requested && !DRY_RUN_TOOLS.contains(&tool) {
+        return Err(McpError::invalid_params(
+            format!("{} does not support {}", tool, DRY_RUN_KEY),
+            Some(json!({ "path": DRY_RUN_KEY })),
+        ));
+    }
+    Ok(requested)
+}
+
+/// Add the `dry_run` parameter to the schemas of the tools that support it
+pub fn advertise(tools: &mut [Tool]) {
+    for tool in tools.iter_mut().filter(|tool| DRY_RUN_TOOLS.contains(&tool.name.as_ref())) {
+        let mut schema = tool.input_schema.as_ref().clone();
+        if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+            properties.insert(
+                DRY_RUN_KEY.to_string(),
+                json!({
+                    "type": "boolean",
+                    "description": "Return what the operation would change (entries, files, byte counts) without changing anything (optional, default false)"
+                }),
+            );
+        }
+        tool.input_schema = Arc::new(schema);
+    }
+}
+
+/// Run `call` as a dry run and add its plan to the result
+pub async fn run<F>(call: F) -> Result<CallToolResult, ToolError>
+where
+    F: Future<Output = Result<CallToolResult, ToolError>>,
+{
+    let plan = Arc::new(Mutex::new(Plan::default()));
+    let result = PLAN.scope(plan.clone(), call).await?;
+    let plan = plan.lock().map(|plan| plan.clone()).unwrap_or_default();
+    Ok(apply(result, plan))
+}
+
+/// Record a change the operation would make
+pub fn record(change: Value) {
+    let _ = PLAN.try_with(|plan| {
+        if let Ok(mut plan) = plan.lock() {
+            plan.changes.push(change);
+        }
+    });
+}
+
+/// Stage `content` for `path` during a dry run; `false` outside one
+///
+/// `previous_bytes` is the size of what the file holds now, kept from the first
+/// time the path is staged.
+pub fn stage(path: &Path, content: String, previous_bytes: Option<usize>) -> bool {
+    PLAN.try_with(|plan| {
+        if let Ok(mut plan) = plan.lock() {
+            match plan.files.iter_mut().find(|file| file.path == path) {
+                Some(file) => {
+                    file.bytes = content.len();
+                    file.content = content;
+                }
+                None => plan.files.push(PlannedFile {
+                    path: path.to_path_buf(),
+                    bytes: content.len(),
+                    previous_bytes,
+                    content,
+                }),
+            }
+        }
+    })
+    .is_ok()
+}
+
+/// Content staged for `path` by this dry run
+pub fn staged(path: &Path) -> Option<String> {
+    PLAN.try_with(|plan| {
+        plan.lock().ok()?.files.iter().find(|file| file.path == path).map(|file| file.content.clone())
+    })
+    .ok()
+    .flatten()
+}
+
+/// `fs::write` that is staged during a dry run
+pub fn write(path: &Path, content: String) -> io::Result<()> {
+    if is_active() {
+        let previous = disk_len(path);
+        stage(path, content, previous);
+        return Ok(());
+    }
+    std::fs::write(path, content)
+}
+
+/// Append `text` to `path`, creating it with `header` first; staged during a dry run
+pub fn append(path: &Path, header: &str, text: &str) -> io::Result<()> {
+    if is_active() {
+        let existing = staged(path).or_else(|| std::fs::read_to_string(path).ok());
+        let previous = disk_len(path);
+        let content = existing.unwrap_or_else(|| header.to_string()) + text;
+        stage(path, content, previous);
+        return Ok(());
+    }
+    let new_file = !path.exists();
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    if new_file {
+        file.write_all(header.as_bytes())?;
+    }
+    file.write_all(text.as_bytes())
+}
+
+/// `fs::create_dir_all`, skipped during a dry run
+pub fn create_dir_all(path: &Path) -> io::Result<()> {
+    if is_active() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(path)
+}
+
+fn disk_len(path: &Path) -> Option<usize> {
+    std::fs::metadata(path).ok().filter(|m| m.is_file()).map(|m| m.len() as usize)
+}
+
+/// Add `plan` to `result` and mark it as a dry run
+fn apply(mut result: CallToolResult, plan: Plan) -> CallToolResult {
+    let mut text = String::from("\n\n---\n**Dry run: nothing was changed.**\n\n");
+    if plan.files.is_empty() {
+        text.push_str("No files would be written.");
+    } else {
+        text.push_str("Files that would be written:\n");
+        for file in &plan.files {
+            let previous = match file.previous_bytes {
+                Some(bytes) => format!("replaces {} bytes", bytes),
+                None => "new file".to_string(),
+            };
+            text.push_str(&format!("\n- `{}` ({} bytes, {})", file.path.display(), file.bytes, previous));
+        }
+    }
+    match result.content.iter_mut().find_map(|content| match &mut content.raw {
+        RawContent::Text(raw) => Some(raw),
+        _ => None,
+    }) {
+        Some(raw) => raw.text.push_str(&text),
+        None => result.content.push(Content::text(text.trim_start())),
+    }
+
+    let summary = json!({
+        "changes": plan.changes,
+        "files": plan.files,
+        "bytes": plan.files.iter().map(|file| file.bytes).sum::<usize>(),
+    });
+    match result.structured_content.as_mut() {
+        Some(Value::Object(structured)) => {
+            structured.insert(DRY_RUN_KEY.to_string(), summary);
+        }
+        _ => result.structured_content = Some(json!({ DRY_RUN_KEY: summary })),
+    }
+    result.meta.get_or_insert_with(Meta::new).insert(META_KEY.to_string(), Value::Bool(true));
+    result
+}
+
+/// Planned files of a dry-run result with their sizes, for tests
+#[cfg(test)]
+pub fn planned_files(result: &CallToolResult) -> std::collections::BTreeMap<String, usize> {
+    result.structured_content.as_ref().and_then(|s| s[DRY_RUN_KEY]["files"].as_array().cloned()).unwrap_or_default()
+        .iter()
+        .map(|file| (file["path"].as_str().unwrap_or_default().to_string(), file["bytes"].as_u64().unwrap_or_default() as usize))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn test_requested_refuses_unsupported_tools() {
+        let arguments = |value: Value| json!({ "dry_run": value }).as_object().cloned();
+        assert!(requested("store", arguments(json!(true)).as_ref()).unwrap());
+        assert!(!requested("store", arguments(json!(false)).as_ref()).unwrap());
+        assert!(!requested("git_context", None).unwrap());
+        assert!(requested("git_context", arguments(json!(true)).as_ref()).unwrap_err().message.contains("does not support"));
+        assert!(requested("store", arguments(json!("yes")).as_ref()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_staged_and_read_back() {
+        let dir = TestDir::new("dry-run");
+        let existing = dir.join("existing.md");
+        std::fs::write(&existing, "# Log\n").unwrap();
+        let new = dir.join("sub/new.json");
+
+        let result = run(async {
+            create_dir_all(&dir.join("sub")).unwrap();
+            write(&new, "{}".to_string()).unwrap();
+            append(&existing, "# Log\n", "- one\n").unwrap();
+            append(&existing, "# Log\n", "- two\n").unwrap();
+            assert_eq!(staged(&existing).as_deref(), Some("# Log\n- one\n- two\n"));
+            record(json!({ "action": "delete", "id": "a" }));
+            Ok(CallToolResult::success(vec![Content::text("Would delete 1 memory: a")]))
+        })
+        .await
+        .unwrap();
+
+        // Nothing touched the disk
+        assert!(!dir.join("sub").exists());
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "# Log\n");
+
+        let structured = result.structured_content.as_ref().unwrap();
+        assert_eq!(structured["dry_run"]["changes"], json!([{ "action": "delete", "id": "a" }]));
+        assert_eq!(structured["dry_run"]["files"][1]["previous_bytes"], 6);
+        assert_eq!(structured["dry_run"]["bytes"], 2 + 18);
+        assert_eq!(planned_files(&result).len(), 2);
+        assert_eq!(result.meta.as_ref().unwrap().get(META_KEY), Some(&Value::Bool(true)));
+        let text = match &result.content[0].raw {
+            RawContent::Text(raw) => raw.text.clone(),
+            _ => unreachable!(),
+        };
+        assert!(text.contains("**Dry run: nothing was changed.**"), "{}", text);
+        assert!(text.contains("existing.md` (18 bytes, replaces 6 bytes)"), "{}", text);
+        assert!(!is_active());
+    }
+}
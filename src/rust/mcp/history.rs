@@ -116,9 +116,9 @@ fn build_markdown(
                 for o in opts {
                     out.push_str("- ");
                     out.push_str(o);
-                    out.push_str("\n");
+                    out.push('\n');
                 }
-                out.push_str("\n");
+                out.push('\n');
             }
         }
     }
@@ -135,18 +135,18 @@ fn build_markdown(
                 for o in r.toppings {
                     out.push_str("- ");
                     out.push_str(&o);
-                    out.push_str("\n");
+                    out.push('\n');
                 }
-                out.push_str("\n");
+                out.push('\n');
             }
         }
         Err(_) => {
             if let Some(s) = response.as_str() {
                 out.push_str(s);
-                out.push_str("\n");
+                out.push('\n');
             } else {
                 out.push_str(&response.to_string());
-                out.push_str("\n");
+                out.push('\n');
             }
         }
     }
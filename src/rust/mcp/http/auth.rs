@@ -0,0 +1,136 @@
+//! Shared-secret authentication of the MCP endpoints
+//!
+//! With a token configured (`DEVKIT_HTTP_TOKEN` or `mcp_config.http_token`), every
+//! request to an MCP transport must carry it, either as `Authorization: Bearer <token>`
+//! or as `?token=<token>` for clients that cannot set headers. Without it no session
+//! can be opened or used. The health, metrics and status endpoints stay open; the
+//! status page then shows a stub only, and `/health` leaves out the session list.
+
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use percent_encoding::percent_decode_str;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::log_debug;
+
+/// Environment variable holding the token; wins over the config file
+pub const HTTP_TOKEN_ENV: &str = "DEVKIT_HTTP_TOKEN";
+
+/// Query parameter accepted instead of the header
+pub const TOKEN_PARAM: &str = "token";
+
+/// Token clients must present
+#[derive(Clone)]
+pub struct AccessToken(Arc<str>);
+
+impl AccessToken {
+    /// Token from the environment, else from the config; blank values disable authentication
+    pub fn resolve(config: Option<&str>) -> Option<Self> {
+        Self::resolve_with(std::env::var(HTTP_TOKEN_ENV).ok().as_deref(), config)
+    }
+
+    fn resolve_with(env: Option<&str>, config: Option<&str>) -> Option<Self> {
+        [env, config]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .find(|token| !token.is_empty())
+            .map(|token| Self(Arc::from(token)))
+    }
+
+    /// Whether `request` carries this token
+    pub(crate) fn accepts(&self, request: &Request) -> bool {
+        let from_header = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, token)| token.trim().to_string());
+        let from_query = || {
+            request.uri().query().and_then(|q| {
+                q.split('&')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| *key == TOKEN_PARAM)
+                    .map(|(_, value)| percent_decode_str(value).decode_utf8_lossy().into_owned())
+            })
+        };
+        from_header.or_else(from_query).is_some_and(|token| constant_time_eq(token.as_bytes(), self.0.as_bytes()))
+    }
+}
+
+/// Axum middleware: 401 for requests without the token
+pub async fn require_token(State(token): State<AccessToken>, request: Request, next: Next) -> Response {
+    if token.accepts(&request) {
+        return next.run(request).await;
+    }
+    log_debug!("Rejected unauthenticated {} {}", request.method(), request.uri().path());
+    let body = json!({
+        "error": "unauthorized",
+        "message": format!("A valid access token is required: send 'Authorization: Bearer <token>' or ?{}=<token>", TOKEN_PARAM),
+    });
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::CONTENT_TYPE, "application/json"), (header::WWW_AUTHENTICATE, "Bearer realm=\"devkit\"")],
+        Body::from(body.to_string()),
+    )
+        .into_response()
+}
+
+/// Comparison whose duration does not depend on where the inputs differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::any;
+    use axum::Router;
+
+    #[test]
+    fn test_resolve() {
+        assert!(AccessToken::resolve_with(None, None).is_none());
+        assert!(AccessToken::resolve_with(Some("  "), None).is_none());
+        assert_eq!(&*AccessToken::resolve_with(Some(" env-secret "), Some("config-secret")).unwrap().0, "env-secret");
+        assert_eq!(&*AccessToken::resolve_with(Some(""), Some("config-secret")).unwrap().0, "config-secret");
+    }
+
+    #[tokio::test]
+    async fn test_requests_without_the_token_are_rejected() {
+        let token = AccessToken::resolve_with(Some("s3cret/+="), None).unwrap();
+        let app = Router::new().route("/sse", any(|| async { "session" })).layer(from_fn_with_state(token, require_token));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sse", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+
+        let rejected = client.post(&url).body("{}").send().await.unwrap();
+        assert_eq!(rejected.status(), reqwest::StatusCode::UNAUTHORIZED);
+        assert_eq!(rejected.headers()["www-authenticate"], "Bearer realm=\"devkit\"");
+        assert_eq!(rejected.json::<serde_json::Value>().await.unwrap()["error"], "unauthorized");
+
+        for request in [
+            client.get(&url).bearer_auth("wrong"),
+            client.get(&url).header("authorization", "Basic s3cret/+="),
+            client.get(format!("{}?token=s3cret", url)),
+        ] {
+            assert_eq!(request.send().await.unwrap().status(), reqwest::StatusCode::UNAUTHORIZED);
+        }
+
+        for request in [
+            client.get(&url).bearer_auth("s3cret/+="),
+            client.get(&url).header("authorization", "bearer s3cret/+="),
+            client.get(format!("{}?keepalive=10&token=s3cret%2F%2B%3D", url)),
+        ] {
+            let response = request.send().await.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+            assert_eq!(response.text().await.unwrap(), "session");
+        }
+    }
+}
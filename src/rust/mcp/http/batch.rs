@@ -0,0 +1,293 @@
+//! JSON-RPC batches on the streamable HTTP endpoints
+//!
+//! The MCP service only decodes single messages, so a POST whose body is a
+//! JSON array is split here. Every member goes through the rest of the stack
+//! as a POST of its own (message checks, session lookup, tool dispatch), the
+//! members run concurrently, and their responses are sent back as one array in
+//! the order of the batch. Per-tool concurrency limits apply to each member.
+//!
+//! As JSON-RPC 2.0 specifies:
+//! - notifications get no entry; a batch of only notifications is answered with 202
+//! - a member that is not an object gets an invalid request error with a null id
+//! - an empty batch, or one with more members than allowed, gets a single error
+//!
+//! `initialize` cannot be batched: it opens the session the other members need.
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::request::Parts;
+use axum::http::{header, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures::StreamExt;
+use rmcp::model::ErrorData as McpError;
+use serde_json::{json, Value};
+
+use crate::log_debug;
+use crate::mcp::boundary::{error_response, MAX_BODY_BYTES};
+
+/// Axum middleware: answer a batch with the responses of its members
+///
+/// `max_batch` is the largest number of members accepted in one batch.
+pub async fn split_batches(State(max_batch): State<usize>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_BODY_BYTES).await else {
+        return (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large").into_response();
+    };
+    // Anything but a well-formed array is checked as a single message
+    let members = match bytes.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'[') => serde_json::from_slice::<Vec<Value>>(&bytes).ok(),
+        _ => None,
+    };
+    let Some(members) = members else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+
+    if members.is_empty() {
+        return json_response(StatusCode::BAD_REQUEST, invalid_request(Value::Null, "Invalid request: empty batch", None));
+    }
+    if members.len() > max_batch {
+        return json_response(
+            StatusCode::BAD_REQUEST,
+            invalid_request(
+                Value::Null,
+                &format!("Batch of {} messages exceeds the limit of {}", members.len(), max_batch),
+                Some(json!({ "size": members.len(), "limit": max_batch })),
+            ),
+        );
+    }
+
+    log_debug!("JSON-RPC batch of {} messages", members.len());
+    let replies = futures::future::join_all(members.into_iter().map(|member| dispatch_member(&parts, next.clone(), member))).await;
+    let replies: Vec<Value> = replies.into_iter().flatten().collect();
+    if replies.is_empty() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+    json_response(StatusCode::OK, Value::Array(replies))
+}
+
+/// Run one member as its own request; `None` for a notification
+async fn dispatch_member(parts: &Parts, next: Next, member: Value) -> Option<Value> {
+    if !member.is_object() {
+        return Some(invalid_request(Value::Null, "Invalid request: batch member is not an object", None));
+    }
+    let id = member.get("id").cloned();
+    if member.get("method").and_then(Value::as_str) == Some("initialize") {
+        return id.map(|id| invalid_request(id, "Invalid request: initialize cannot be part of a batch", None));
+    }
+
+    let mut parts = parts.clone();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let response = next.run(Request::from_parts(parts, Body::from(member.to_string()))).await;
+    read_reply(response, &id?).await
+}
+
+/// The response to request `id` in a member's HTTP response
+///
+/// Requests are answered on an event stream, rejected messages with a JSON
+/// error and transport failures (e.g. an unknown session) with plain text.
+async fn read_reply(response: Response, id: &Value) -> Option<Value> {
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    if content_type.starts_with("text/event-stream") {
+        return Some(read_event_stream(response.into_body(), id).await.unwrap_or_else(|| {
+            error_response(id.clone(), McpError::internal_error("No response for batch member".to_string(), None))
+        }));
+    }
+
+    let body = to_bytes(response.into_body(), MAX_BODY_BYTES).await.unwrap_or_default();
+    if content_type.starts_with("application/json") {
+        if let Ok(reply) = serde_json::from_slice::<Value>(&body) {
+            return Some(reply);
+        }
+    }
+    let message = format!("HTTP {}: {}", status.as_u16(), String::from_utf8_lossy(&body).trim());
+    let data = Some(json!({ "status": status.as_u16() }));
+    let error = if status.is_server_error() {
+        McpError::internal_error(message, data)
+    } else {
+        McpError::invalid_request(message, data)
+    };
+    Some(error_response(id.clone(), error))
+}
+
+/// First response or error for `id` on an SSE body, skipping notifications sent before it
+async fn read_event_stream(body: Body, id: &Value) -> Option<Value> {
+    let mut stream = body.into_data_stream();
+    // Raw bytes: a chunk may end inside a multi-byte character, so only whole lines are decoded
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(Ok(chunk)) = stream.next().await {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let Ok(line) = std::str::from_utf8(&line) else {
+                continue;
+            };
+            let Some(data) = line.trim_end().strip_prefix("data:") else {
+                continue;
+            };
+            let Ok(message) = serde_json::from_str::<Value>(data.trim()) else {
+                continue;
+            };
+            if message.get("id") == Some(id) && (message.get("result").is_some() || message.get("error").is_some()) {
+                return Some(message);
+            }
+        }
+    }
+    None
+}
+
+fn invalid_request(id: Value, message: &str, data: Option<Value>) -> Value {
+    error_response(id, McpError::invalid_request(message.to_string(), data))
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response {
+    (status, [(header::CONTENT_TYPE, "application/json")], Body::from(body.to_string())).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::boundary::reject_malformed_messages;
+    use crate::mcp::http::{keepalive_service, DrainState};
+    use crate::mcp::DevkitServer;
+    use axum::middleware::{from_fn, from_fn_with_state};
+    use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+    use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
+    use std::sync::Arc;
+
+    const MAX_BATCH: usize = 8;
+
+    /// Serve /sse with the batch and message checks in front, as the HTTP binary does
+    async fn start() -> String {
+        let drain = DrainState::new();
+        let service = StreamableHttpService::new(
+            move || Ok::<_, std::io::Error>(DevkitServer::new().with_popup(false).with_drain(drain.clone())),
+            Arc::new(LocalSessionManager::default()),
+            StreamableHttpServerConfig { sse_keep_alive: None, ..Default::default() },
+        );
+        let app = axum::Router::new()
+            .route("/sse", keepalive_service(service, Default::default()))
+            .layer(from_fn(reject_malformed_messages))
+            .layer(from_fn_with_state(MAX_BATCH, split_batches));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sse", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    async fn post(url: &str, session: Option<&str>, body: &Value) -> reqwest::Response {
+        let mut request = reqwest::Client::new()
+            .post(url)
+            .header("Accept", "application/json, text/event-stream")
+            .json(body);
+        if let Some(session) = session {
+            request = request.header("Mcp-Session-Id", session);
+        }
+        request.send().await.unwrap()
+    }
+
+    async fn open_session(url: &str) -> String {
+        let init = post(url, None, &json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": { "name": "batch-test", "version": "0.0.0" }
+            }
+        }))
+        .await;
+        let session = init.headers()["mcp-session-id"].to_str().unwrap().to_string();
+        init.text().await.unwrap();
+        post(url, Some(&session), &json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).await;
+        session
+    }
+
+    #[tokio::test]
+    async fn test_mixed_batch_keeps_order_and_skips_notifications() {
+        let url = start().await;
+        let session = open_session(&url).await;
+
+        let batch = json!([
+            { "jsonrpc": "2.0", "id": 3, "method": "tools/list" },
+            { "jsonrpc": "2.0", "method": "notifications/roots/list_changed" },
+            { "jsonrpc": "2.0", "id": "b", "method": "ping" },
+            { "jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": { "name": "git_context", "arguments": { "project_root_path": 42 } } },
+            { "jsonrpc": "2.0", "id": 6, "params": {} },
+            7,
+            { "jsonrpc": "2.0", "id": 8, "method": "initialize", "params": {} }
+        ]);
+        let response = post(&url, Some(&session), &batch).await;
+        assert_eq!(response.status().as_u16(), StatusCode::OK.as_u16());
+        let replies: Vec<Value> = response.json().await.unwrap();
+
+        let ids: Vec<&Value> = replies.iter().map(|reply| &reply["id"]).collect();
+        assert_eq!(ids, vec![&json!(3), &json!("b"), &json!(5), &json!(6), &Value::Null, &json!(8)]);
+        assert!(replies[0]["result"]["tools"].as_array().is_some_and(|tools| !tools.is_empty()));
+        assert_eq!(replies[1]["result"], json!({}));
+        assert_eq!(replies[2]["error"]["code"], -32602);
+        assert_eq!(replies[2]["error"]["data"]["path"], "project_root_path");
+        for reply in &replies[3..] {
+            assert_eq!(reply["error"]["code"], -32600, "{}", reply);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notification_only_batch_is_accepted_without_body() {
+        let url = start().await;
+        let session = open_session(&url).await;
+
+        let batch = json!([
+            { "jsonrpc": "2.0", "method": "notifications/roots/list_changed" },
+            { "jsonrpc": "2.0", "method": "notifications/roots/list_changed" }
+        ]);
+        let response = post(&url, Some(&session), &batch).await;
+        assert_eq!(response.status().as_u16(), StatusCode::ACCEPTED.as_u16());
+        assert!(response.text().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_empty_and_oversized_batches_get_one_error() {
+        let url = start().await;
+        let session = open_session(&url).await;
+
+        let error: Value = post(&url, Some(&session), &json!([])).await.json().await.unwrap();
+        assert_eq!(error["error"]["code"], -32600);
+        assert_eq!(error["id"], Value::Null);
+
+        let pings: Vec<Value> = (0..=MAX_BATCH).map(|id| json!({ "jsonrpc": "2.0", "id": id, "method": "ping" })).collect();
+        let response = post(&url, Some(&session), &Value::Array(pings)).await;
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["error"]["code"], -32600);
+        assert_eq!(error["error"]["data"], json!({ "size": MAX_BATCH + 1, "limit": MAX_BATCH }));
+
+        // Members of a batch without a session fail one by one
+        let replies: Vec<Value> = post(&url, None, &json!([{ "jsonrpc": "2.0", "id": 1, "method": "ping" }])).await.json().await.unwrap();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0]["id"], 1);
+        assert!(replies[0]["error"]["code"].is_i64());
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_keeps_characters_split_across_chunks() {
+        let event = "data: {\"jsonrpc\":\"2.0\",\"id\":7,\"result\":{\"text\":\"配置\"}}\n\n".as_bytes();
+        // Cut in the middle of the three bytes of 配
+        let cut = event.iter().position(|&byte| byte >= 0x80).unwrap() + 1;
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> = vec![Ok(event[..cut].to_vec()), Ok(event[cut..].to_vec())];
+        let body = Body::from_stream(futures::stream::iter(chunks));
+
+        let message = read_event_stream(body, &json!(7)).await.unwrap();
+        assert_eq!(message["result"]["text"], "配置");
+    }
+}
@@ -0,0 +1,70 @@
+//! Response compression of the HTTP server
+//!
+//! Responses are compressed with gzip or deflate when the client lists one in
+//! `Accept-Encoding`, which shrinks large docs lookups and tool results on
+//! remote links considerably. Small bodies, images and SSE streams are sent as
+//! they are: compressing an event stream would buffer its events and hold back
+//! keep-alives. WebSocket upgrades are left alone as well.
+
+use axum::http::{Extensions, HeaderMap, StatusCode, Version};
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+
+/// Bodies smaller than this are not worth the compression overhead
+pub const MIN_COMPRESS_BYTES: u16 = 1024;
+
+/// Layer compressing responses the client accepts compressed
+pub fn compression_layer() -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(MIN_COMPRESS_BYTES)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::GRPC)
+        .and(|status: StatusCode, _: Version, _: &HeaderMap, _: &Extensions| status != StatusCode::SWITCHING_PROTOCOLS);
+    CompressionLayer::new().compress_when(predicate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::sse::{Event, Sse};
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Response head of `GET path` sent with `Accept-Encoding: accept`
+    async fn head(addr: std::net::SocketAddr, path: &str, accept: Option<&str>) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let accept = accept.map(|value| format!("Accept-Encoding: {}\r\n", value)).unwrap_or_default();
+        let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n{}Connection: close\r\n\r\n", path, accept);
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        let response = String::from_utf8_lossy(&response).to_lowercase();
+        response.split("\r\n\r\n").next().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_compresses_large_bodies_the_client_accepts() {
+        let large = serde_json::json!({ "text": "docs snippet ".repeat(500) });
+        let app = Router::new()
+            .route("/large", get(move || async move { Json(large) }))
+            .route("/small", get(|| async { Json(serde_json::json!({ "ok": true })) }))
+            .route(
+                "/sse",
+                get(|| async {
+                    let events = futures::stream::iter((0..200).map(|i| Ok::<_, std::convert::Infallible>(Event::default().data("event ".repeat(i)))));
+                    Sse::new(events)
+                }),
+            )
+            .layer(compression_layer());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        assert!(head(addr, "/large", Some("gzip")).await.contains("content-encoding: gzip"));
+        assert!(head(addr, "/large", Some("deflate")).await.contains("content-encoding: deflate"));
+        assert!(!head(addr, "/large", None).await.contains("content-encoding"));
+        assert!(!head(addr, "/small", Some("gzip")).await.contains("content-encoding"));
+        assert!(!head(addr, "/sse", Some("gzip")).await.contains("content-encoding"));
+    }
+}
@@ -0,0 +1,288 @@
+//! Running the HTTP server in the background
+//!
+//! `devkit-mcp-http --daemon` starts a copy of itself detached from the
+//! terminal (a new process group on Unix, a detached process on Windows) with
+//! the same arguments, waits until it is listening and exits. The background
+//! server logs to the rotating log file instead of the terminal and keeps its
+//! pid and shutdown grace in `<state_dir>/mcp_http.pid` while it runs, holding
+//! a lock on `mcp_http.pid.lock` next to it. `devkit-mcp-http status` reports
+//! it and `devkit-mcp-http stop` shuts it down gracefully, as Ctrl+C would, so
+//! its sessions are saved for the next start. Only a pid whose lock is still
+//! held is signalled: after a crash the pid may belong to another process.
+
+use anyhow::{bail, Result};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use super::DEFAULT_SHUTDOWN_GRACE;
+use crate::utils::{is_process_running, paths};
+use crate::{log_debug, log_important};
+
+/// Name of the pid file in the state directory
+pub const PID_FILE: &str = "mcp_http.pid";
+
+/// Set in the environment of the background copy
+pub const DAEMON_CHILD_ENV: &str = "DEVKIT_DAEMON_CHILD";
+
+/// How long `--daemon` waits for the background server to come up
+const START_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Time `stop` allows beyond the shutdown grace for sessions to be saved
+const STOP_MARGIN: Duration = Duration::from_secs(5);
+
+/// Whether this process is the background copy started by `--daemon`
+pub fn is_daemon_child() -> bool {
+    std::env::var_os(DAEMON_CHILD_ENV).is_some()
+}
+
+/// Path of the pid file
+pub fn pid_file_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(PID_FILE))
+}
+
+/// What the pid file records about the background server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PidRecord {
+    pid: u32,
+    /// Time the server lets running calls finish when asked to stop
+    shutdown_grace: Duration,
+}
+
+/// Pid of the background server, if it is still running
+///
+/// A pid file left behind by a crashed server is removed.
+pub fn running_pid() -> Option<u32> {
+    running_at(&pid_file_path().ok()?).map(|record| record.pid)
+}
+
+fn running_at(path: &Path) -> Option<PidRecord> {
+    let record = read_pid_file_at(path)?;
+    if is_locked(&lock_path(path)).unwrap_or_else(|| is_process_running(record.pid)) {
+        return Some(record);
+    }
+    log_debug!("Removing stale pid file {} of pid {}", path.display(), record.pid);
+    let _ = fs::remove_file(path);
+    None
+}
+
+/// Lock held by the background server for as long as it runs
+fn lock_path(pid_path: &Path) -> PathBuf {
+    pid_path.with_extension("pid.lock")
+}
+
+/// Whether a live process holds the lock at `path`; `None` when that cannot be told
+fn is_locked(path: &Path) -> Option<bool> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Some(false),
+        Err(_) => return None,
+    };
+    match file.try_lock() {
+        Ok(()) => Some(false),
+        Err(TryLockError::WouldBlock) => Some(true),
+        Err(TryLockError::Error(_)) => None,
+    }
+}
+
+/// Handle returned by [`write_pid_file`]; removes the file when dropped
+pub struct PidFile {
+    path: PathBuf,
+    _lock: File,
+}
+
+impl PidFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Only the server that wrote the file removes it
+        if read_pid_file_at(&self.path).map(|record| record.pid) != Some(std::process::id()) {
+            return;
+        }
+        match fs::remove_file(&self.path) {
+            Ok(()) => log_debug!("Removed pid file {}", self.path.display()),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => log_important!(warn, "Failed to remove pid file {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Record the pid of this process and its shutdown grace in the pid file
+pub fn write_pid_file(shutdown_grace: Duration) -> Result<PidFile> {
+    write_pid_file_at(&pid_file_path()?, PidRecord { pid: std::process::id(), shutdown_grace })
+}
+
+fn write_pid_file_at(path: &Path, record: PidRecord) -> Result<PidFile> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Locked before the pid is written, so a recorded pid always has its lock
+    let lock = OpenOptions::new().create(true).truncate(false).write(true).open(lock_path(path))?;
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => bail!("Another background devkit-mcp-http holds {}", lock_path(path).display()),
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+    // Write to a temp file first so `stop` never reads a half-written number
+    let tmp_path = path.with_extension(format!("pid.{}.tmp", record.pid));
+    fs::write(&tmp_path, format!("{}\n{}\n", record.pid, record.shutdown_grace.as_secs()))?;
+    fs::rename(&tmp_path, path)?;
+    Ok(PidFile { path: path.to_path_buf(), _lock: lock })
+}
+
+/// The pid on the first line and the shutdown grace in seconds on the second
+fn read_pid_file_at(path: &Path) -> Option<PidRecord> {
+    let content = fs::read_to_string(path).ok()?;
+    let mut lines = content.lines().map(str::trim);
+    let pid = lines.next()?.parse().ok()?;
+    let shutdown_grace = lines.next().and_then(|secs| secs.parse().ok()).map_or(DEFAULT_SHUTDOWN_GRACE, Duration::from_secs);
+    Some(PidRecord { pid, shutdown_grace })
+}
+
+/// Start the server in the background with this process's arguments and wait until it listens
+///
+/// Returns the pid of the background server.
+pub fn start() -> Result<u32> {
+    if let Some(pid) = running_pid() {
+        bail!("devkit-mcp-http is already running in the background (pid {}); stop it with `devkit-mcp-http stop`", pid);
+    }
+
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(std::env::args_os().skip(1))
+        .env(DAEMON_CHILD_ENV, "1")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    detach(&mut command);
+    let mut child = command.spawn()?;
+    let pid = child.id();
+
+    let started = Instant::now();
+    let pid_file = pid_file_path()?;
+    while started.elapsed() < START_TIMEOUT {
+        if let Some(status) = child.try_wait()? {
+            // A clean exit means it found a running server for the port and joined it
+            if status.success() {
+                bail!("Another devkit-mcp-http already serves this port; nothing was started");
+            }
+            bail!("The background server exited during startup ({}); see the log for details", status);
+        }
+        if read_pid_file_at(&pid_file).map(|record| record.pid) == Some(pid) {
+            return Ok(pid);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    bail!("The background server (pid {}) did not start listening within {} s; see the log for details", pid, START_TIMEOUT.as_secs())
+}
+
+/// Keep the background copy alive when the terminal goes away or gets Ctrl+C
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach(_command: &mut Command) {}
+
+/// Stop the background server and wait for it to exit
+///
+/// Returns the pid that was stopped, `None` when nothing was running.
+pub fn stop() -> Result<Option<u32>> {
+    let Some(PidRecord { pid, shutdown_grace }) = running_at(&pid_file_path()?) else {
+        return Ok(None);
+    };
+    terminate(pid)?;
+
+    let deadline = shutdown_grace + STOP_MARGIN;
+    let started = Instant::now();
+    while is_process_running(pid) {
+        if started.elapsed() > deadline {
+            bail!("devkit-mcp-http (pid {}) is still running {} s after being asked to stop", pid, deadline.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    Ok(Some(pid))
+}
+
+/// Ask `pid` to shut down gracefully (SIGTERM)
+#[cfg(unix)]
+fn terminate(pid: u32) -> Result<()> {
+    let status = Command::new("kill").args(["-TERM", &pid.to_string()]).status()?;
+    if !status.success() {
+        bail!("Cannot signal devkit-mcp-http (pid {}): kill exited with {}", pid, status);
+    }
+    Ok(())
+}
+
+/// Windows has no SIGTERM for a detached process; the server is ended without draining
+#[cfg(windows)]
+fn terminate(pid: u32) -> Result<()> {
+    let status = Command::new("taskkill").args(["/PID", &pid.to_string(), "/F"]).stdout(Stdio::null()).status()?;
+    if !status.success() {
+        bail!("Cannot stop devkit-mcp-http (pid {}): taskkill exited with {}", pid, status);
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn terminate(pid: u32) -> Result<()> {
+    bail!("Stopping devkit-mcp-http (pid {}) is not supported on this platform", pid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn record(pid: u32) -> PidRecord {
+        PidRecord { pid, shutdown_grace: Duration::from_secs(7) }
+    }
+
+    #[test]
+    fn test_pid_file_is_removed_only_by_its_writer() {
+        let dir = TestDir::reserve("pid-test");
+        let path = dir.join(PID_FILE);
+
+        let own = write_pid_file_at(&path, record(std::process::id())).unwrap();
+        assert_eq!(read_pid_file_at(&path), Some(record(std::process::id())));
+        drop(own);
+        assert!(!path.exists());
+
+        // Another server's file stays
+        let other = write_pid_file_at(&path, record(u32::MAX)).unwrap();
+        drop(other);
+        assert_eq!(read_pid_file_at(&path), Some(record(u32::MAX)));
+    }
+
+    #[test]
+    fn test_only_a_locked_pid_counts_as_running() {
+        let dir = TestDir::reserve("pid-lock-test");
+        let path = dir.join(PID_FILE);
+
+        let server = write_pid_file_at(&path, record(std::process::id())).unwrap();
+        assert_eq!(running_at(&path), Some(record(std::process::id())));
+        assert!(write_pid_file_at(&path, record(u32::MAX)).is_err());
+        drop(server);
+
+        // A live pid whose lock is gone was reused by another process: never signalled, file removed
+        fs::write(&path, format!("{}\n", std::process::id())).unwrap();
+        assert_eq!(running_at(&path), None);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,223 @@
+//! Instance discovery files for the HTTP server
+//!
+//! Every running `devkit-mcp-http` process writes `<state_dir>/instances/<pid>.json`
+//! after a successful bind so that clients can find it without hard-coding the URL.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::{is_process_running, paths};
+use crate::{log_debug, log_important};
+
+/// Sub-directory of the state dir holding one file per live instance
+const INSTANCES_DIR: &str = "instances";
+
+/// Discovery record for one running HTTP server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InstanceInfo {
+    pub pid: u32,
+    /// Base URL, e.g. http://127.0.0.1:8808
+    pub url: String,
+    /// Transport name -> path, e.g. "streamable_http" -> "/sse"
+    #[serde(default)]
+    pub transports: BTreeMap<String, String>,
+    /// Authentication hint ("bearer" when a token is required), never the secret itself
+    #[serde(default)]
+    pub auth: Option<String>,
+    pub version: String,
+    pub started_at: DateTime<Utc>,
+}
+
+impl InstanceInfo {
+    /// Build a record for the current process
+    pub fn for_current_process(url: String, transports: BTreeMap<String, String>) -> Self {
+        Self {
+            pid: std::process::id(),
+            url,
+            transports,
+            auth: None,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// Full URL of a transport endpoint
+    pub fn endpoint(&self, transport: &str) -> Option<String> {
+        self.transports
+            .get(transport)
+            .map(|path| format!("{}{}", self.url.trim_end_matches('/'), path))
+    }
+}
+
+/// Handle returned by [`register_instance`]; removes the discovery file when dropped
+pub struct InstanceRegistration {
+    path: PathBuf,
+}
+
+impl InstanceRegistration {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Remove the discovery file explicitly (graceful shutdown)
+    pub fn remove(self) {
+        // Drop does the work
+    }
+}
+
+impl Drop for InstanceRegistration {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log_important!(warn, "Failed to remove discovery file {}: {}", self.path.display(), e);
+            }
+        } else {
+            log_debug!("Removed discovery file {}", self.path.display());
+        }
+    }
+}
+
+/// Directory holding discovery files
+pub fn instances_dir() -> Result<PathBuf> {
+    paths::state_subdir(INSTANCES_DIR)
+}
+
+/// Write the discovery file for this process
+pub fn register_instance(info: &InstanceInfo) -> Result<InstanceRegistration> {
+    register_instance_in(&instances_dir()?, info)
+}
+
+/// List live instances, deleting stale files whose PID is gone
+pub fn list_instances() -> Result<Vec<InstanceInfo>> {
+    list_instances_in(&instances_dir()?)
+}
+
+fn register_instance_in(dir: &Path, info: &InstanceInfo) -> Result<InstanceRegistration> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.json", info.pid));
+    // Write to a temp file first so readers never see a half-written record
+    let tmp_path = dir.join(format!("{}.json.tmp", info.pid));
+    fs::write(&tmp_path, serde_json::to_string_pretty(info)?)?;
+    fs::rename(&tmp_path, &path)?;
+    log_debug!("Wrote discovery file {}", path.display());
+    Ok(InstanceRegistration { path })
+}
+
+fn list_instances_in(dir: &Path) -> Result<Vec<InstanceInfo>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut instances = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        let info = fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str::<InstanceInfo>(&s).ok());
+
+        match info {
+            Some(info) if is_process_running(info.pid) => instances.push(info),
+            Some(info) => {
+                log_debug!("Removing stale discovery file for dead pid {}", info.pid);
+                let _ = fs::remove_file(&path);
+            }
+            None => {
+                log_debug!("Removing unreadable discovery file {}", path.display());
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    instances.sort_by_key(|info| info.started_at);
+    Ok(instances)
+}
+
+/// Render the instance list for the `devkit instances` command
+pub fn format_instances(instances: &[InstanceInfo]) -> String {
+    if instances.is_empty() {
+        return "No running devkit-mcp-http instances found.\n".to_string();
+    }
+
+    let mut output = String::new();
+    for info in instances {
+        output.push_str(&format!(
+            "pid {}  v{}  {}  (started {})\n",
+            info.pid,
+            info.version,
+            info.url,
+            info.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+        for (name, path) in &info.transports {
+            output.push_str(&format!("  {:<16} {}{}\n", name, info.url.trim_end_matches('/'), path));
+        }
+        if let Some(auth) = &info.auth {
+            output.push_str(&format!("  {:<16} {}\n", "auth", auth));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn temp_instances_dir() -> TestDir {
+        TestDir::reserve("instances-test")
+    }
+
+    fn sample(pid: u32) -> InstanceInfo {
+        let mut transports = BTreeMap::new();
+        transports.insert("streamable_http".to_string(), "/sse".to_string());
+        InstanceInfo {
+            pid,
+            url: "http://127.0.0.1:8808".to_string(),
+            transports,
+            auth: None,
+            version: "1.0.0".to_string(),
+            started_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_stale_files_are_removed() {
+        let dir = temp_instances_dir();
+        let live = register_instance_in(&dir, &sample(std::process::id())).unwrap();
+        // u32::MAX is never a valid pid
+        let stale = register_instance_in(&dir, &sample(u32::MAX)).unwrap();
+        let stale_path = stale.path().to_path_buf();
+        std::mem::forget(stale);
+        fs::write(dir.join("garbage.json"), "{not json").unwrap();
+
+        let instances = list_instances_in(&dir).unwrap();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].pid, std::process::id());
+        assert!(!stale_path.exists());
+        assert!(!dir.join("garbage.json").exists());
+
+        live.remove();
+        assert!(list_instances_in(&dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_format_instances() {
+        let mut info = sample(4242);
+        info.auth = Some("bearer".to_string());
+        let output = format_instances(&[info]);
+        assert!(output.contains("pid 4242"));
+        assert!(output.contains("http://127.0.0.1:8808/sse"));
+        assert!(output.contains("bearer"));
+
+        assert!(format_instances(&[]).contains("No running"));
+    }
+}
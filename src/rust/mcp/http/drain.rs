@@ -0,0 +1,481 @@
+//! Draining phase of a graceful shutdown
+//!
+//! Once shutdown starts, new POSTs to the MCP endpoint are rejected with a 503
+//! JSON-RPC error, tool calls already running are allowed to finish (bounded by
+//! the grace period), and every session gets a final logging notification so
+//! clients can show "server shutting down" instead of a dead socket.
+
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::{Peer, RoleServer};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use super::outbound::{Occupancy, OutboundBuffers};
+use crate::log_important;
+use crate::utils::locale::Locale;
+
+/// Default time in-flight tool calls get to finish after shutdown starts
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+/// JSON-RPC error code used when rejecting requests during shutdown
+pub const SHUTTING_DOWN_CODE: i64 = -32000;
+
+/// Message sent to clients while shutting down
+pub const SHUTTING_DOWN_MESSAGE: &str = "Server is shutting down";
+
+/// Time given to the last responses to reach their streams before they are closed
+const FLUSH_DELAY: Duration = Duration::from_millis(200);
+
+/// Signal that asked the server to stop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownSignal {
+    /// Ctrl+C (SIGINT on Unix)
+    Interrupt,
+    /// SIGTERM, as sent by `docker stop`, systemd and Kubernetes
+    Terminate,
+}
+
+impl fmt::Display for ShutdownSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Interrupt => write!(f, "Ctrl+C"),
+            Self::Terminate => write!(f, "SIGTERM"),
+        }
+    }
+}
+
+/// Wait for Ctrl+C, or SIGTERM on Unix
+pub async fn shutdown_signal() -> ShutdownSignal {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => ShutdownSignal::Interrupt,
+                _ = terminate.recv() => ShutdownSignal::Terminate,
+            },
+            Err(e) => {
+                log_important!(warn, "Cannot listen for SIGTERM, only Ctrl+C stops the server gracefully: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+                ShutdownSignal::Interrupt
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        ShutdownSignal::Interrupt
+    }
+}
+
+/// Shared shutdown state: draining flag, in-flight tool calls and session registry
+#[derive(Clone, Default)]
+pub struct DrainState {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+    sessions: Mutex<Vec<SessionEntry>>,
+    /// In-flight calls per endpoint
+    endpoint_calls: Mutex<HashMap<String, usize>>,
+}
+
+struct SessionEntry {
+    peer: Peer<RoleServer>,
+    /// Streamable HTTP session id
+    session_id: Option<String>,
+    endpoint: String,
+    /// SSE keep-alive interval negotiated by the client
+    keep_alive: Option<Duration>,
+    locale: Locale,
+    draining: bool,
+}
+
+/// One open session, as listed by /health
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionSummary {
+    pub session_id: Option<String>,
+    pub endpoint: String,
+    pub keep_alive_ms: Option<u64>,
+    /// Language of the session's localized output
+    pub locale: Locale,
+    pub draining: bool,
+    /// Messages queued on the session's SSE streams, while any is open
+    pub outbound: Option<Occupancy>,
+}
+
+/// Session counts reported by /health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct SessionCounts {
+    pub active: usize,
+    pub draining: usize,
+}
+
+/// Per-endpoint numbers reported by /health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct EndpointStats {
+    pub sessions: SessionCounts,
+    pub in_flight: usize,
+}
+
+/// Keeps a tool call counted as in flight until dropped
+pub struct CallGuard {
+    inner: Arc<Inner>,
+    endpoint: String,
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        if let Ok(mut calls) = self.inner.endpoint_calls.lock() {
+            if let Some(count) = calls.get_mut(&self.endpoint) {
+                *count = count.saturating_sub(1);
+            }
+        }
+        if self.inner.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+impl DrainState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    /// Number of tool calls currently running
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Count a tool call on `endpoint` as in flight; `None` once draining has started
+    pub fn track_call(&self, endpoint: &str) -> Option<CallGuard> {
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut calls) = self.inner.endpoint_calls.lock() {
+            *calls.entry(endpoint.to_string()).or_default() += 1;
+        }
+        let guard = CallGuard { inner: self.inner.clone(), endpoint: endpoint.to_string() };
+        if self.is_draining() {
+            // Dropping the guard undoes the increment and wakes waiters if needed
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Remember a session of `endpoint` so it can be notified at shutdown
+    pub fn register_session(
+        &self,
+        peer: Peer<RoleServer>,
+        session_id: Option<&str>,
+        endpoint: &str,
+        keep_alive: Option<Duration>,
+        locale: Locale,
+    ) {
+        if let Ok(mut sessions) = self.inner.sessions.lock() {
+            sessions.retain(|s| !s.peer.is_transport_closed());
+            let draining = self.is_draining();
+            sessions.push(SessionEntry {
+                peer,
+                session_id: session_id.map(str::to_string),
+                endpoint: endpoint.to_string(),
+                keep_alive,
+                locale,
+                draining,
+            });
+        }
+    }
+
+    /// Open sessions with their endpoint, keep-alive interval, locale and outbound queues
+    pub fn sessions(&self) -> Vec<SessionSummary> {
+        let Ok(mut sessions) = self.inner.sessions.lock() else {
+            return Vec::new();
+        };
+        sessions.retain(|s| !s.peer.is_transport_closed());
+        sessions
+            .iter()
+            .map(|s| SessionSummary {
+                session_id: s.session_id.clone(),
+                endpoint: s.endpoint.clone(),
+                keep_alive_ms: s.keep_alive.map(|d| d.as_millis() as u64),
+                locale: s.locale,
+                draining: s.draining,
+                outbound: s.session_id.as_deref().and_then(|id| OutboundBuffers::global().occupancy(id)),
+            })
+            .collect()
+    }
+
+    /// Open sessions, split into active and draining
+    pub fn session_counts(&self) -> SessionCounts {
+        let mut counts = SessionCounts::default();
+        if let Ok(mut sessions) = self.inner.sessions.lock() {
+            sessions.retain(|s| !s.peer.is_transport_closed());
+            for session in sessions.iter() {
+                if session.draining {
+                    counts.draining += 1;
+                } else {
+                    counts.active += 1;
+                }
+            }
+        }
+        counts
+    }
+
+    /// Sessions and in-flight calls per endpoint
+    pub fn endpoint_stats(&self) -> BTreeMap<String, EndpointStats> {
+        let mut stats: BTreeMap<String, EndpointStats> = BTreeMap::new();
+        if let Ok(mut sessions) = self.inner.sessions.lock() {
+            sessions.retain(|s| !s.peer.is_transport_closed());
+            for session in sessions.iter() {
+                let counts = &mut stats.entry(session.endpoint.clone()).or_default().sessions;
+                if session.draining {
+                    counts.draining += 1;
+                } else {
+                    counts.active += 1;
+                }
+            }
+        }
+        if let Ok(calls) = self.inner.endpoint_calls.lock() {
+            for (endpoint, in_flight) in calls.iter().filter(|(_, n)| **n > 0) {
+                stats.entry(endpoint.clone()).or_default().in_flight = *in_flight;
+            }
+        }
+        stats
+    }
+
+    /// Enter the draining phase and mark every registered session as draining
+    pub fn start_draining(&self) {
+        self.inner.draining.store(true, Ordering::SeqCst);
+        if let Ok(mut sessions) = self.inner.sessions.lock() {
+            for session in sessions.iter_mut() {
+                session.draining = true;
+            }
+        }
+    }
+
+    /// Wait until no tool call is in flight; returns false when the grace period ran out
+    pub async fn wait_idle(&self, grace: Duration) -> bool {
+        tokio::time::timeout(grace, async {
+            loop {
+                let idle = self.inner.idle.notified();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    /// Send the final "shutting down" notification to every open session
+    pub async fn notify_sessions(&self, grace: Duration) {
+        let peers: Vec<Peer<RoleServer>> = match self.inner.sessions.lock() {
+            Ok(sessions) => sessions
+                .iter()
+                .filter(|s| !s.peer.is_transport_closed())
+                .map(|s| s.peer.clone())
+                .collect(),
+            Err(_) => return,
+        };
+
+        for peer in peers {
+            let notification = LoggingMessageNotificationParam {
+                level: LoggingLevel::Warning,
+                logger: Some("devkit".to_string()),
+                data: json!({
+                    "message": SHUTTING_DOWN_MESSAGE,
+                    "grace_period_secs": grace.as_secs(),
+                }),
+            };
+            if let Err(e) = peer.notify_logging_message(notification).await {
+                log_important!(warn, "Failed to notify session about shutdown: {}", e);
+            }
+        }
+    }
+
+    /// Full draining sequence: reject new calls, notify sessions, wait for
+    /// in-flight calls (at most `grace`), then cancel the transport
+    pub async fn shutdown(&self, grace: Duration, cancellation_token: &CancellationToken) {
+        self.start_draining();
+        log_important!(
+            info,
+            "Shutdown requested, draining {} in-flight call(s) (grace period {}s)",
+            self.in_flight(),
+            grace.as_secs()
+        );
+
+        self.notify_sessions(grace).await;
+
+        if self.wait_idle(grace).await {
+            tokio::time::sleep(FLUSH_DELAY).await;
+            log_important!(info, "All in-flight calls finished, stopping MCP HTTP server");
+        } else {
+            log_important!(
+                warn,
+                "Grace period elapsed with {} call(s) still running, stopping anyway",
+                self.in_flight()
+            );
+        }
+        cancellation_token.cancel();
+    }
+}
+
+#[cfg(feature = "http-server")]
+pub use middleware::reject_when_draining;
+
+#[cfg(feature = "http-server")]
+mod middleware {
+    use axum::body::{to_bytes, Body};
+    use axum::extract::{Request, State};
+    use axum::http::{header, Method, StatusCode};
+    use axum::middleware::Next;
+    use axum::response::{IntoResponse, Response};
+    use serde_json::{json, Value};
+
+    use super::{DrainState, SHUTTING_DOWN_CODE, SHUTTING_DOWN_MESSAGE};
+
+    /// Largest request body inspected for a JSON-RPC id when rejecting
+    const MAX_REJECTED_BODY: usize = 1024 * 1024;
+
+    /// Axum middleware: answer new POSTs with a 503 JSON-RPC error while draining
+    pub async fn reject_when_draining(State(drain): State<DrainState>, request: Request, next: Next) -> Response {
+        if !drain.is_draining() || request.method() != Method::POST {
+            return next.run(request).await;
+        }
+
+        let body = to_bytes(request.into_body(), MAX_REJECTED_BODY).await.unwrap_or_default();
+        let id = serde_json::from_slice::<Value>(&body)
+            .ok()
+            .and_then(|v| v.get("id").cloned())
+            .unwrap_or(Value::Null);
+        let error = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": SHUTTING_DOWN_CODE, "message": SHUTTING_DOWN_MESSAGE },
+        });
+
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::CONTENT_TYPE, "application/json"), (header::RETRY_AFTER, "5")],
+            Body::from(error.to_string()),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(all(test, feature = "http-server"))]
+mod tests {
+    use super::*;
+    use axum::{middleware::from_fn_with_state, routing::post, Router};
+    use std::time::Instant;
+
+    const GRACE: Duration = Duration::from_secs(3);
+
+    /// Stand-in for the MCP endpoint: every call is a slow tool call
+    async fn slow_tool(axum::extract::State(drain): axum::extract::State<DrainState>) -> &'static str {
+        let _guard = drain.track_call("default");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        "done"
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_in_flight_calls() {
+        let drain = DrainState::new();
+        let token = CancellationToken::new();
+        let app = Router::new()
+            .route("/sse", post(slow_tool))
+            .layer(from_fn_with_state(drain.clone(), reject_when_draining))
+            .with_state(drain.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sse", listener.local_addr().unwrap());
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let shutdown_drain = drain.clone();
+        let shutdown_token = token.clone();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown_rx.await;
+                    shutdown_drain.shutdown(GRACE, &shutdown_token).await;
+                })
+                .await
+        });
+
+        let client = reqwest::Client::new();
+        let old_call = tokio::spawn({
+            let client = client.clone();
+            let url = url.clone();
+            async move { client.post(&url).body("{}").send().await.unwrap().text().await.unwrap() }
+        });
+
+        // Wait for the slow call to be in flight, then start shutting down
+        while drain.in_flight() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let started = Instant::now();
+        shutdown_tx.send(()).unwrap();
+        while !drain.is_draining() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let rejected = client
+            .post(&url)
+            .body(r#"{"jsonrpc":"2.0","id":7,"method":"tools/call"}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(rejected.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = rejected.json().await.unwrap();
+        assert_eq!(body["id"], 7);
+        assert_eq!(body["error"]["code"], SHUTTING_DOWN_CODE);
+
+        assert_eq!(old_call.await.unwrap(), "done");
+        server.await.unwrap().unwrap();
+        assert!(started.elapsed() < GRACE);
+        assert!(token.is_cancelled());
+        assert_eq!(drain.in_flight(), 0);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_sigterm_is_a_shutdown_signal() {
+        let signal = tokio::spawn(shutdown_signal());
+        // Let the handler install before the signal arrives
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let status = std::process::Command::new("kill")
+            .args(["-TERM", &std::process::id().to_string()])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let signal = tokio::time::timeout(Duration::from_secs(5), signal).await.unwrap().unwrap();
+        assert_eq!(signal, ShutdownSignal::Terminate);
+        assert_eq!(signal.to_string(), "SIGTERM");
+    }
+
+    #[tokio::test]
+    async fn test_wait_idle_times_out() {
+        let drain = DrainState::new();
+        let _guard = drain.track_call("default").unwrap();
+        drain.start_draining();
+
+        assert!(drain.track_call("readonly").is_none());
+        assert_eq!(drain.in_flight(), 1);
+        assert_eq!(drain.endpoint_stats()["default"].in_flight, 1);
+        assert!(!drain.endpoint_stats().contains_key("readonly"));
+        assert!(!drain.wait_idle(Duration::from_millis(50)).await);
+    }
+}
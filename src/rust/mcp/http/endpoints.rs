@@ -0,0 +1,227 @@
+//! Named MCP endpoints
+//!
+//! `mcp_config.endpoints` lets one process serve several tool sets, each on its
+//! own path (e.g. `/sse/full` and `/sse/readonly`). Every endpoint gets its own
+//! [`DevkitServer`] factory and session manager; caches and the memory store
+//! are process-wide, so they stay shared.
+//!
+//! The default endpoint answers on two paths: `/sse`, which existing client
+//! configurations point at, and `/mcp`, where clients that speak the
+//! streamable HTTP transport natively (Claude Desktop, newer Windsurf builds)
+//! look for it. Both share one session manager, so either path can continue
+//! a session opened on the other.
+
+use axum::routing::MethodRouter;
+use axum::Router;
+use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::{keepalive_service, KeepAliveRange, ResumableSessionManager, SessionLimits, SessionStore, HEALTH_PATH, READINESS_PATH, WS_PATH};
+use crate::config::McpEndpointConfig;
+use crate::mcp::server::DEFAULT_ENDPOINT;
+use crate::mcp::DevkitServer;
+
+/// Path of the default streamable HTTP endpoint
+pub const DEFAULT_MCP_PATH: &str = "/sse";
+
+/// Second path of the default endpoint, the one streamable HTTP clients expect
+pub const STREAMABLE_HTTP_PATH: &str = "/mcp";
+
+/// Reject endpoint definitions that would shadow each other or a built-in route
+pub fn validate_endpoints(endpoints: &[McpEndpointConfig]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    let mut paths = HashSet::new();
+    for endpoint in endpoints {
+        if endpoint.name.trim().is_empty() || endpoint.name == DEFAULT_ENDPOINT {
+            return Err(format!("Endpoint name '{}' is reserved or empty", endpoint.name));
+        }
+        if !endpoint.path.starts_with('/') || endpoint.path.len() < 2 {
+            return Err(format!("Endpoint '{}': path must start with '/', got '{}'", endpoint.name, endpoint.path));
+        }
+        if [DEFAULT_MCP_PATH, STREAMABLE_HTTP_PATH, HEALTH_PATH, READINESS_PATH, WS_PATH].contains(&endpoint.path.as_str()) {
+            return Err(format!("Endpoint '{}': path {} is already in use", endpoint.name, endpoint.path));
+        }
+        if !names.insert(endpoint.name.as_str()) {
+            return Err(format!("Duplicate endpoint name '{}'", endpoint.name));
+        }
+        if !paths.insert(endpoint.path.as_str()) {
+            return Err(format!("Duplicate endpoint path {}", endpoint.path));
+        }
+    }
+    Ok(())
+}
+
+/// Router serving the default endpoint on [`DEFAULT_MCP_PATH`] and [`STREAMABLE_HTTP_PATH`]
+pub fn default_router<S>(service: MethodRouter<S>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route(DEFAULT_MCP_PATH, service.clone()).route(STREAMABLE_HTTP_PATH, service)
+}
+
+/// Router serving every named endpoint over streamable HTTP
+///
+/// `new_server` builds the base server (popup and drain settings); each
+/// endpoint then applies its own tool switches on top. Sessions saved in
+/// `sessions` by a previous process resume on the endpoint they were opened on;
+/// abandoned ones are closed under `limits`.
+pub fn endpoints_router<F>(
+    endpoints: &[McpEndpointConfig],
+    new_server: F,
+    sessions: &SessionStore,
+    config: StreamableHttpServerConfig,
+    keep_alive: KeepAliveRange,
+    limits: SessionLimits,
+) -> Router
+where
+    F: Fn() -> DevkitServer + Clone + Send + Sync + 'static,
+{
+    endpoints.iter().fold(Router::new(), |router, endpoint| {
+        let new_server = new_server.clone();
+        let endpoint_config = endpoint.clone();
+        let endpoint_server = move || new_server().with_endpoint(&endpoint_config);
+        let session_manager =
+            ResumableSessionManager::new(sessions.clone(), &endpoint.name, endpoint_server.clone()).with_limits(limits);
+        let service = StreamableHttpService::new(
+            move || Ok::<_, std::io::Error>(endpoint_server()),
+            Arc::new(session_manager),
+            config.clone(),
+        );
+        router.route(&endpoint.path, keepalive_service(service, keep_alive))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::http::DrainState;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use tokio_util::sync::CancellationToken;
+
+    fn endpoint(name: &str, path: &str, tools: &[(&str, bool)]) -> McpEndpointConfig {
+        McpEndpointConfig {
+            name: name.to_string(),
+            path: path.to_string(),
+            tools: tools.iter().map(|(tool, on)| (tool.to_string(), *on)).collect::<HashMap<_, _>>(),
+            memory_read_only: false,
+        }
+    }
+
+    /// JSON-RPC payload of a response sent either as JSON or as an SSE stream
+    async fn rpc_body(response: reqwest::Response) -> Value {
+        let text = response.text().await.unwrap();
+        text.lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .find_map(|data| serde_json::from_str(data.trim()).ok())
+            .unwrap_or_else(|| serde_json::from_str(&text).unwrap())
+    }
+
+    async fn list_tools(base: &str, path: &str) -> Vec<String> {
+        let client = reqwest::Client::new();
+        let url = format!("{}{}", base, path);
+        let post = |body: Value, session: Option<&str>| {
+            let mut request = client
+                .post(&url)
+                .header("Accept", "application/json, text/event-stream")
+                .json(&body);
+            if let Some(session) = session {
+                request = request.header("Mcp-Session-Id", session);
+            }
+            request.send()
+        };
+
+        let init = post(
+            json!({
+                "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": { "name": "endpoint-test", "version": "0.0.0" }
+                }
+            }),
+            None,
+        )
+        .await
+        .unwrap();
+        let session = init.headers()["mcp-session-id"].to_str().unwrap().to_string();
+        rpc_body(init).await;
+
+        post(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}), Some(&session))
+            .await
+            .unwrap();
+        let tools = rpc_body(post(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}), Some(&session)).await.unwrap()).await;
+        tools["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_endpoints() {
+        assert!(validate_endpoints(&[endpoint("full", "/sse/full", &[]), endpoint("ro", "/sse/ro", &[])]).is_ok());
+        assert!(validate_endpoints(&[endpoint("full", "sse/full", &[])]).is_err());
+        assert!(validate_endpoints(&[endpoint("full", HEALTH_PATH, &[])]).is_err());
+        assert!(validate_endpoints(&[endpoint("full", STREAMABLE_HTTP_PATH, &[])]).is_err());
+        assert!(validate_endpoints(&[endpoint(DEFAULT_ENDPOINT, "/sse/x", &[])]).is_err());
+        assert!(validate_endpoints(&[endpoint("a", "/sse/a", &[]), endpoint("a", "/sse/b", &[])]).is_err());
+        assert!(validate_endpoints(&[endpoint("a", "/sse/a", &[]), endpoint("b", "/sse/a", &[])]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_differs_between_endpoints() {
+        let endpoints = vec![
+            endpoint("full", "/sse/full", &[("index", true), ("store", true)]),
+            endpoint("readonly", "/sse/readonly", &[("index", false), ("store", false)]),
+        ];
+        let drain = DrainState::new();
+        let session_drain = drain.clone();
+        let config = StreamableHttpServerConfig {
+            sse_keep_alive: None,
+            stateful_mode: true,
+            cancellation_token: CancellationToken::new(),
+        };
+        let app = endpoints_router(
+            &endpoints,
+            move || DevkitServer::new().with_popup(false).with_drain(session_drain.clone()),
+            &SessionStore::disabled(),
+            config,
+            KeepAliveRange::default(),
+            SessionLimits::NONE,
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let full = list_tools(&base, "/sse/full").await;
+        let readonly = list_tools(&base, "/sse/readonly").await;
+        assert!(full.iter().any(|t| t == "index") && full.iter().any(|t| t == "store"));
+        assert!(!readonly.iter().any(|t| t == "index" || t == "store"));
+
+        // Sessions are labeled by the endpoint they connected to
+        let stats = drain.endpoint_stats();
+        assert_eq!(stats["full"].sessions.active, 1);
+        assert_eq!(stats["readonly"].sessions.active, 1);
+    }
+
+    #[tokio::test]
+    async fn test_default_endpoint_answers_on_both_paths() {
+        let service = StreamableHttpService::new(
+            || Ok::<_, std::io::Error>(DevkitServer::new().with_popup(false)),
+            Arc::new(rmcp::transport::streamable_http_server::session::local::LocalSessionManager::default()),
+            StreamableHttpServerConfig { sse_keep_alive: None, ..Default::default() },
+        );
+        let app = default_router(keepalive_service(service, KeepAliveRange::default()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let sse = list_tools(&base, DEFAULT_MCP_PATH).await;
+        let mcp = list_tools(&base, STREAMABLE_HTTP_PATH).await;
+        assert!(!mcp.is_empty());
+        assert_eq!(sse, mcp);
+    }
+}
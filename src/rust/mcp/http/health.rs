@@ -0,0 +1,211 @@
+//! Health endpoints for load balancers and container orchestrators
+//!
+//! `GET /health` is the full report (uptime, sessions, tool registry, warnings);
+//! `GET /readiness` is a cheap yes/no for probes that only need to know whether
+//! new sessions should be routed here. Both stay open when an access token is
+//! set, so the session list, whose ids would let a caller join a session, is
+//! only reported to requests carrying the token.
+
+use axum::extract::{Request, State};
+use axum::{http::StatusCode, routing::get, Json, Router};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Instant;
+
+use super::{AccessToken, DrainState};
+use crate::docs_client::contract::{self, ContractStatus};
+use crate::docs_client::key_check;
+use crate::mcp::DevkitServer;
+
+/// Path of the health endpoint
+pub const HEALTH_PATH: &str = "/health";
+
+/// Path of the readiness endpoint
+pub const READINESS_PATH: &str = "/readiness";
+
+#[derive(Clone)]
+struct HealthState {
+    drain: DrainState,
+    started: Instant,
+    /// Access token of the MCP endpoints, required to see the session list
+    token: Option<AccessToken>,
+    factory: Arc<dyn Fn() -> DevkitServer + Send + Sync>,
+}
+
+impl HealthState {
+    /// Names of the tools the default endpoint serves
+    fn tool_names(&self) -> Vec<String> {
+        (self.factory)()
+            .tool_definitions()
+            .into_iter()
+            .map(|tool| tool.name.into_owned())
+            .collect()
+    }
+}
+
+/// Router serving `GET /health` and `GET /readiness`
+///
+/// `factory` builds a server of the default endpoint to check its tool registry.
+/// With a `token`, only requests carrying it get the session list.
+pub fn health_router<F>(drain: DrainState, started: Instant, token: Option<AccessToken>, factory: F) -> Router
+where
+    F: Fn() -> DevkitServer + Send + Sync + 'static,
+{
+    let state = HealthState { drain, started, token, factory: Arc::new(factory) };
+    Router::new()
+        .route(HEALTH_PATH, get(health))
+        .route(READINESS_PATH, get(readiness))
+        .with_state(state)
+}
+
+/// 503 while draining so load balancers stop routing new sessions here
+async fn health(State(state): State<HealthState>, request: Request) -> (StatusCode, Json<Value>) {
+    let drain = &state.drain;
+    // Problems the operator should look at, e.g. a quarantined config file
+    let mut warnings = crate::config::config_warnings();
+    let preflight = crate::utils::paths::preflight();
+    warnings.extend(preflight.warning());
+    let docs_contract = contract::last_report();
+    if let Some(ContractStatus::Drift { missing }) = docs_contract.as_ref().map(|report| &report.status) {
+        warnings.push(format!("docs API drift detected: missing field(s) {}", missing.join(", ")));
+    }
+    let docs_keys = key_check::last_report();
+    for check in docs_keys.iter().flat_map(|report| report.problems()) {
+        warnings.push(format!("docs API key {} is {}", check.key, check.health));
+    }
+    let tool_names = state.tool_names();
+    if tool_names.is_empty() {
+        warnings.push("no tools are enabled on the default endpoint".to_string());
+    }
+    let sessions = drain.session_counts();
+    let offline = crate::config::offline_mode();
+
+    let (code, status) = if drain.is_draining() {
+        (StatusCode::SERVICE_UNAVAILABLE, "draining")
+    } else if warnings.is_empty() {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::OK, "degraded")
+    };
+
+    let mut report = json!({
+        "status": status,
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_secs": state.started.elapsed().as_secs(),
+        "sessions": { "active": sessions.active, "draining": sessions.draining },
+        "in_flight": drain.in_flight(),
+        "endpoints": drain.endpoint_stats(),
+        "tool_registry": {
+            "status": if tool_names.is_empty() { "empty" } else { "ok" },
+            "count": tool_names.len(),
+            "tools": tool_names,
+        },
+        "tools": crate::mcp::concurrency::ToolLimiter::global().stats(),
+        "calls": crate::mcp::concurrency::CallLimiter::global().stats(),
+        "truncated_results": crate::mcp::result_limit::truncated_results(),
+        "slow_consumer_drops": super::OutboundBuffers::global().dropped_sessions(),
+        "offline": offline,
+        "directories": preflight.directories,
+        "docs_api_drift": docs_contract.as_ref().is_some_and(|report| report.is_drift()),
+        "docs_contract": docs_contract,
+        "docs_keys": docs_keys,
+        "warnings": warnings,
+    });
+    if state.token.as_ref().is_none_or(|token| token.accepts(&request)) {
+        report["session_list"] = json!(drain.sessions());
+    }
+    (code, Json(report))
+}
+
+/// 200 when new sessions can be served, 503 with the reasons otherwise
+///
+/// Degraded warnings do not make the server unready; only draining or an
+/// empty tool registry do.
+async fn readiness(State(state): State<HealthState>) -> (StatusCode, Json<Value>) {
+    let mut reasons = Vec::new();
+    if state.drain.is_draining() {
+        reasons.push("draining");
+    }
+    let tools = state.tool_names().len();
+    if tools == 0 {
+        reasons.push("no tools enabled");
+    }
+
+    let code = if reasons.is_empty() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (
+        code,
+        Json(json!({
+            "ready": reasons.is_empty(),
+            "uptime_secs": state.started.elapsed().as_secs(),
+            "active_sessions": state.drain.session_counts().active,
+            "tools": tools,
+            "reasons": reasons,
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn serve(drain: DrainState) -> std::net::SocketAddr {
+        serve_with_token(drain, None).await
+    }
+
+    async fn serve_with_token(drain: DrainState, token: Option<AccessToken>) -> std::net::SocketAddr {
+        let app = health_router(drain, Instant::now(), token, || DevkitServer::new().with_popup(false));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_uptime_and_tool_registry() {
+        let addr = serve(DrainState::new()).await;
+        let response = reqwest::get(format!("http://{}{}", addr, HEALTH_PATH)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: Value = response.json().await.unwrap();
+        assert!(body["uptime_secs"].is_u64());
+        assert_eq!(body["sessions"]["active"], 0);
+        assert_eq!(body["tool_registry"]["status"], "ok");
+        assert!(body["tool_registry"]["count"].as_u64().unwrap() > 0);
+        assert!(body["session_list"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_session_list_needs_the_token() {
+        let addr = serve_with_token(DrainState::new(), AccessToken::resolve(Some("health-secret"))).await;
+        let url = format!("http://{}{}", addr, HEALTH_PATH);
+        let client = reqwest::Client::new();
+
+        let open: Value = client.get(&url).send().await.unwrap().json().await.unwrap();
+        assert_eq!(open["sessions"]["active"], 0);
+        assert!(open.get("session_list").is_none());
+
+        let wrong: Value = client.get(&url).bearer_auth("guess").send().await.unwrap().json().await.unwrap();
+        assert!(wrong.get("session_list").is_none());
+
+        let authorized: Value = client.get(&url).bearer_auth("health-secret").send().await.unwrap().json().await.unwrap();
+        assert!(authorized["session_list"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_readiness_fails_while_draining() {
+        let drain = DrainState::new();
+        let addr = serve(drain.clone()).await;
+        let url = format!("http://{}{}", addr, READINESS_PATH);
+
+        let response = reqwest::get(&url).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["ready"], true);
+
+        drain.start_draining();
+        let response = reqwest::get(&url).await.unwrap();
+        assert_eq!(response.status(), 503);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["reasons"], json!(["draining"]));
+    }
+}
@@ -0,0 +1,228 @@
+//! Per-connection SSE keep-alive
+//!
+//! Clients can append `?keepalive=<secs>` to a streamable HTTP endpoint URL to
+//! pick the interval of the keep-alive comments on their SSE streams. The value
+//! is clamped to the configured range; without it the server default applies.
+
+use std::time::Duration;
+
+#[cfg(feature = "http-server")]
+pub use service::{keepalive_service, EffectiveKeepAlive};
+
+/// Name of the query parameter
+pub const KEEPALIVE_PARAM: &str = "keepalive";
+
+/// Default keep-alive interval when the client does not ask for one
+pub const DEFAULT_SSE_KEEPALIVE: Duration = Duration::from_secs(30);
+/// Shortest interval a client may ask for
+pub const DEFAULT_SSE_KEEPALIVE_MIN: Duration = Duration::from_secs(5);
+/// Longest interval a client may ask for
+pub const DEFAULT_SSE_KEEPALIVE_MAX: Duration = Duration::from_secs(120);
+
+/// Server default and allowed range of the keep-alive interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepAliveRange {
+    pub default: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Default for KeepAliveRange {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_SSE_KEEPALIVE,
+            min: DEFAULT_SSE_KEEPALIVE_MIN,
+            max: DEFAULT_SSE_KEEPALIVE_MAX,
+        }
+    }
+}
+
+impl KeepAliveRange {
+    /// Interval for a request with the given query string
+    ///
+    /// Out-of-range values are clamped and unparsable ones fall back to the
+    /// default; neither is an error, as the stream works either way.
+    pub fn resolve(&self, query: Option<&str>) -> Duration {
+        let Some(raw) = query.and_then(|q| {
+            q.split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == KEEPALIVE_PARAM)
+                .map(|(_, value)| value)
+        }) else {
+            return self.default;
+        };
+
+        let requested = match raw.trim().parse::<f64>() {
+            Ok(secs) if secs.is_finite() && secs > 0.0 => Duration::from_secs_f64(secs),
+            _ => {
+                crate::log_debug!("Ignoring invalid keepalive value '{}', using {:?}", raw, self.default);
+                return self.default;
+            }
+        };
+        let clamped = requested.clamp(self.min, self.max.max(self.min));
+        if clamped != requested {
+            crate::log_debug!("Requested keepalive {:?} clamped to {:?}", requested, clamped);
+        }
+        clamped
+    }
+}
+
+#[cfg(feature = "http-server")]
+mod service {
+    use axum::extract::{Request, State};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{any, MethodRouter};
+    use rmcp::transport::streamable_http_server::SessionManager;
+    use rmcp::transport::StreamableHttpService;
+    use std::time::Duration;
+
+    use super::KeepAliveRange;
+    use crate::log_debug;
+    use crate::mcp::DevkitServer;
+
+    /// Keep-alive interval chosen for a request, visible to the MCP server
+    /// through the HTTP request parts
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct EffectiveKeepAlive(pub Duration);
+
+    struct KeepAliveState<M> {
+        service: StreamableHttpService<DevkitServer, M>,
+        range: KeepAliveRange,
+    }
+
+    impl<M> Clone for KeepAliveState<M> {
+        fn clone(&self) -> Self {
+            Self { service: self.service.clone(), range: self.range }
+        }
+    }
+
+    /// Serve `service` with the keep-alive interval chosen per request
+    pub fn keepalive_service<S, M>(service: StreamableHttpService<DevkitServer, M>, range: KeepAliveRange) -> MethodRouter<S>
+    where
+        S: Clone + Send + Sync + 'static,
+        M: SessionManager,
+    {
+        any(handle::<M>).with_state(KeepAliveState { service, range })
+    }
+
+    async fn handle<M: SessionManager>(State(state): State<KeepAliveState<M>>, mut request: Request) -> Response {
+        let keep_alive = state.range.resolve(request.uri().query());
+        log_debug!(
+            "{} {} keepalive={}ms",
+            request.method(),
+            request.uri().path(),
+            keep_alive.as_millis()
+        );
+        request.extensions_mut().insert(EffectiveKeepAlive(keep_alive));
+
+        let mut service = state.service.clone();
+        service.config.sse_keep_alive = Some(keep_alive);
+        service.handle(request).await.into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_clamps() {
+        let range = KeepAliveRange::default();
+        assert_eq!(range.resolve(None), DEFAULT_SSE_KEEPALIVE);
+        assert_eq!(range.resolve(Some("keepalive=15")), Duration::from_secs(15));
+        assert_eq!(range.resolve(Some("x=1&keepalive=7.5")), Duration::from_millis(7500));
+        assert_eq!(range.resolve(Some("keepalive=1")), DEFAULT_SSE_KEEPALIVE_MIN);
+        assert_eq!(range.resolve(Some("keepalive=9999")), DEFAULT_SSE_KEEPALIVE_MAX);
+        assert_eq!(range.resolve(Some("keepalive=soon")), DEFAULT_SSE_KEEPALIVE);
+        assert_eq!(range.resolve(Some("keepalive=-3")), DEFAULT_SSE_KEEPALIVE);
+    }
+
+    #[cfg(feature = "http-server")]
+    #[tokio::test]
+    async fn test_keepalive_cadence_per_connection() {
+        use crate::mcp::http::DrainState;
+        use crate::mcp::DevkitServer;
+        use futures::StreamExt;
+        use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+        use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
+        use serde_json::json;
+        use std::sync::Arc;
+
+        let drain = DrainState::new();
+        let session_drain = drain.clone();
+        let service = StreamableHttpService::new(
+            move || Ok::<_, std::io::Error>(DevkitServer::new().with_popup(false).with_drain(session_drain.clone())),
+            Arc::new(LocalSessionManager::default()),
+            StreamableHttpServerConfig::default(),
+        );
+        let range = KeepAliveRange {
+            default: Duration::from_secs(30),
+            min: Duration::from_millis(50),
+            max: Duration::from_secs(60),
+        };
+        let app = axum::Router::new().route("/sse", keepalive_service(service, range));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/sse", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        // Open a session and its standalone SSE stream, then count keep-alive comments
+        async fn count_comments(base: &str, keepalive: &str, window: Duration) -> usize {
+            let client = reqwest::Client::new();
+            let url = format!("{}?keepalive={}", base, keepalive);
+            let init = client
+                .post(&url)
+                .header("Accept", "application/json, text/event-stream")
+                .json(&json!({
+                    "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                    "params": {
+                        "protocolVersion": "2025-03-26",
+                        "capabilities": {},
+                        "clientInfo": { "name": "keepalive-test", "version": "0.0.0" }
+                    }
+                }))
+                .send()
+                .await
+                .unwrap();
+            let session = init.headers()["mcp-session-id"].to_str().unwrap().to_string();
+            init.text().await.unwrap();
+            client
+                .post(&url)
+                .header("Accept", "application/json, text/event-stream")
+                .header("Mcp-Session-Id", &session)
+                .json(&json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+                .send()
+                .await
+                .unwrap();
+
+            let stream = client
+                .get(&url)
+                .header("Accept", "text/event-stream")
+                .header("Mcp-Session-Id", &session)
+                .send()
+                .await
+                .unwrap();
+            let mut body = stream.bytes_stream();
+            let mut comments = 0;
+            let _ = tokio::time::timeout(window, async {
+                while let Some(Ok(chunk)) = body.next().await {
+                    comments += String::from_utf8_lossy(&chunk).lines().filter(|l| l.starts_with(':')).count();
+                }
+            })
+            .await;
+            comments
+        }
+
+        let window = Duration::from_millis(1200);
+        let (fast, slow) = tokio::join!(count_comments(&base, "0.1", window), count_comments(&base, "0.4", window));
+        assert!(fast >= 7, "fast stream sent {} keep-alives", fast);
+        assert!((1..=4).contains(&slow), "slow stream sent {} keep-alives", slow);
+
+        // The effective interval is recorded with the session
+        let mut recorded: Vec<_> = drain.sessions().into_iter().filter_map(|s| s.keep_alive_ms).collect();
+        recorded.sort();
+        assert_eq!(recorded, vec![100, 400]);
+        // Neither client declared a locale
+        assert!(drain.sessions().iter().all(|s| s.locale == crate::utils::locale::Locale::En));
+        assert!(drain.sessions().iter().all(|s| s.session_id.is_some()));
+    }
+}
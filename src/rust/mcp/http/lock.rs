@@ -0,0 +1,180 @@
+//! One HTTP server per port
+//!
+//! Before binding, the server takes an exclusive lock on
+//! `<state_dir>/locks/http-<port>.lock` for its configured port and, once it
+//! listens, records its pid and URL next to it. A second server started for
+//! the same port, typically by another IDE, finds the lock taken. If the holder
+//! answers on its URL the newcomer prints that endpoint and exits successfully
+//! (`--join`, the default) or fails with a message naming it, instead of
+//! falling over on the bind. The lock is released by the OS when the holder
+//! exits, even after a crash.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::log_debug;
+use crate::utils::paths;
+
+/// Sub-directory of the state dir holding the locks
+const LOCKS_DIR: &str = "locks";
+
+/// Time the running server has to answer the join probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Server holding the lock of a port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockOwner {
+    pub pid: u32,
+    /// Local URL of the running server, e.g. http://127.0.0.1:8808
+    pub url: String,
+}
+
+/// Result of [`acquire_instance_lock`]
+pub enum LockOutcome {
+    /// This process serves the port
+    Acquired(InstanceLock),
+    /// Another process does; `None` while it has not started listening yet
+    Held(Option<LockOwner>),
+}
+
+/// Exclusive lock of a port, released when dropped
+pub struct InstanceLock {
+    _file: File,
+    owner_path: PathBuf,
+}
+
+impl InstanceLock {
+    /// Record this process and the URL it listens on for later starts to join
+    pub fn set_owner(&self, url: &str) -> Result<()> {
+        let owner = LockOwner { pid: std::process::id(), url: url.to_string() };
+        let tmp_path = self.owner_path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(&owner)?)?;
+        fs::rename(&tmp_path, &self.owner_path)?;
+        Ok(())
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        match fs::remove_file(&self.owner_path) {
+            Ok(()) => log_debug!("Removed lock owner file {}", self.owner_path.display()),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => log_debug!("Failed to remove lock owner file {}: {}", self.owner_path.display(), e),
+        }
+    }
+}
+
+/// Take the lock of `port`, or find out who holds it
+pub fn acquire_instance_lock(port: u16) -> Result<LockOutcome> {
+    acquire_in(&paths::state_subdir(LOCKS_DIR)?, port)
+}
+
+fn acquire_in(dir: &Path, port: u16) -> Result<LockOutcome> {
+    fs::create_dir_all(dir)?;
+    let lock_path = dir.join(format!("http-{}.lock", port));
+    let owner_path = dir.join(format!("http-{}.json", port));
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(&lock_path)?;
+    match file.try_lock() {
+        Ok(()) => {
+            // Whatever a crashed holder left behind is stale now
+            let _ = fs::remove_file(&owner_path);
+            Ok(LockOutcome::Acquired(InstanceLock { _file: file, owner_path }))
+        }
+        Err(TryLockError::WouldBlock) => {
+            let owner = fs::read_to_string(&owner_path).ok().and_then(|content| serde_json::from_str(&content).ok());
+            Ok(LockOutcome::Held(owner))
+        }
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// Whether `url` points at this machine: localhost or a loopback address
+pub fn is_loopback_url(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    match url.host_str() {
+        Some(host) => {
+            host.eq_ignore_ascii_case("localhost")
+                || host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        }
+        None => false,
+    }
+}
+
+/// Whether the server at `url` answers HTTP, as its status page always does
+///
+/// Only loopback URLs are probed; any other URL read from the owner file is
+/// refused without a request.
+pub async fn probe(url: &str) -> bool {
+    if !is_loopback_url(url) {
+        log_debug!("Not probing {}: not a loopback URL", url);
+        return false;
+    }
+    // The running server may use a self-signed certificate, which is fine on this machine
+    let client = match reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .danger_accept_invalid_certs(true)
+        .no_proxy()
+        .build()
+    {
+        Ok(client) => client,
+        Err(_) => return false,
+    };
+    match client.get(format!("{}/", url)).send().await {
+        Ok(response) => !response.status().is_server_error(),
+        Err(e) => {
+            log_debug!("Probe of {} failed: {}", url, e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    #[test]
+    fn test_second_start_finds_the_owner() {
+        let dir = TestDir::reserve("lock-test");
+
+        let LockOutcome::Acquired(lock) = acquire_in(&dir, 8808).unwrap() else {
+            panic!("first start should get the lock");
+        };
+        // Taken but not listening yet
+        assert!(matches!(acquire_in(&dir, 8808).unwrap(), LockOutcome::Held(None)));
+
+        lock.set_owner("http://127.0.0.1:8808").unwrap();
+        match acquire_in(&dir, 8808).unwrap() {
+            LockOutcome::Held(Some(owner)) => {
+                assert_eq!(owner.pid, std::process::id());
+                assert_eq!(owner.url, "http://127.0.0.1:8808");
+            }
+            _ => panic!("second start should see the owner"),
+        }
+        // Other ports are independent
+        assert!(matches!(acquire_in(&dir, 8809).unwrap(), LockOutcome::Acquired(_)));
+
+        drop(lock);
+        assert!(matches!(acquire_in(&dir, 8808).unwrap(), LockOutcome::Acquired(_)));
+    }
+
+    #[tokio::test]
+    async fn test_only_loopback_urls_are_probed() {
+        assert!(is_loopback_url("http://127.0.0.1:8808"));
+        assert!(is_loopback_url("https://[::1]:8808"));
+        assert!(is_loopback_url("http://LOCALHOST:8808"));
+        assert!(!is_loopback_url("https://192.168.1.5:8808"));
+        assert!(!is_loopback_url("https://example.com"));
+        assert!(!is_loopback_url("not a url"));
+
+        // Refused before any connection is attempted
+        assert!(!probe("https://203.0.113.7:8808").await);
+    }
+}
@@ -0,0 +1,48 @@
+//! Capability manifest served at `/manifest`
+
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use std::sync::Arc;
+
+use crate::mcp::manifest::Manifest;
+use crate::mcp::DevkitServer;
+
+/// Path of the manifest endpoint
+pub const MANIFEST_PATH: &str = "/manifest";
+
+/// Router serving `GET /manifest`
+///
+/// `factory` builds a server of the default endpoint, so the manifest follows
+/// the tool switches in effect when it is requested.
+pub fn manifest_router<F>(factory: F) -> Router
+where
+    F: Fn() -> DevkitServer + Send + Sync + 'static,
+{
+    let factory: Arc<dyn Fn() -> DevkitServer + Send + Sync> = Arc::new(factory);
+    Router::new().route(MANIFEST_PATH, get(manifest)).with_state(factory)
+}
+
+async fn manifest(State(factory): State<Arc<dyn Fn() -> DevkitServer + Send + Sync>>) -> Json<Manifest> {
+    Json(Manifest::build(&factory()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_manifest_over_http() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, manifest_router(DevkitServer::new)).await.unwrap();
+        });
+
+        let response = reqwest::get(format!("http://{}{}", addr, MANIFEST_PATH)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let manifest: Manifest = response.json().await.unwrap();
+        assert_eq!(manifest.server.version, env!("CARGO_PKG_VERSION"));
+        assert!(manifest.tools.iter().any(|tool| tool.name == "store"));
+    }
+}
@@ -0,0 +1,128 @@
+//! Prometheus metrics endpoint
+//!
+//! Serves the docs client's [`Metrics`] registry and the docs cache counters in
+//! the Prometheus text exposition format, so a scraper can chart upstream
+//! latency, status codes and cache hit rate of this server.
+
+use axum::http::header::CONTENT_TYPE;
+use axum::{response::IntoResponse, routing::get, Router};
+use std::fmt::Write;
+
+use crate::docs_client::cache::{CacheStats, DocsCache};
+use crate::docs_client::metrics::{MetricsSnapshot, LATENCY_BUCKETS_MS};
+use crate::docs_client::Metrics;
+
+/// Path of the metrics endpoint
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Content type of the text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Router serving `GET /metrics`
+pub fn metrics_router() -> Router {
+    Router::new().route(METRICS_PATH, get(metrics))
+}
+
+async fn metrics() -> impl IntoResponse {
+    let body = render(&Metrics::shared().snapshot(), &DocsCache::shared().stats());
+    ([(CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body)
+}
+
+fn render(snapshot: &MetricsSnapshot, cache: &CacheStats) -> String {
+    let mut out = String::new();
+
+    header(&mut out, "devkit_docs_responses_total", "counter", "Docs API responses by operation and status code");
+    for (operation, metrics) in &snapshot.operations {
+        for (status, count) in &metrics.statuses {
+            let _ = writeln!(out, "devkit_docs_responses_total{{operation=\"{}\",status=\"{}\"}} {}", operation.as_str(), status, count);
+        }
+    }
+
+    header(&mut out, "devkit_docs_request_failures_total", "counter", "Docs API attempts that got no response");
+    for (operation, metrics) in &snapshot.operations {
+        let _ = writeln!(out, "devkit_docs_request_failures_total{{operation=\"{}\"}} {}", operation.as_str(), metrics.failures);
+    }
+
+    header(&mut out, "devkit_docs_request_duration_seconds", "histogram", "Time until the docs API response headers arrived");
+    for (operation, metrics) in &snapshot.operations {
+        let latency = &metrics.latency;
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(&latency.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "devkit_docs_request_duration_seconds_bucket{{operation=\"{}\",le=\"{}\"}} {}",
+                operation.as_str(),
+                *bound as f64 / 1000.0,
+                cumulative
+            );
+        }
+        let name = "devkit_docs_request_duration_seconds";
+        let _ = writeln!(out, "{}_bucket{{operation=\"{}\",le=\"+Inf\"}} {}", name, operation.as_str(), latency.count);
+        let _ = writeln!(out, "{}_sum{{operation=\"{}\"}} {}", name, operation.as_str(), latency.sum_ms as f64 / 1000.0);
+        let _ = writeln!(out, "{}_count{{operation=\"{}\"}} {}", name, operation.as_str(), latency.count);
+    }
+
+    header(&mut out, "devkit_docs_received_bytes_total", "counter", "Bytes of docs API response bodies");
+    for (operation, metrics) in &snapshot.operations {
+        let _ = writeln!(out, "devkit_docs_received_bytes_total{{operation=\"{}\"}} {}", operation.as_str(), metrics.bytes_received);
+    }
+
+    header(&mut out, "devkit_docs_cache_hits_total", "counter", "Docs cache lookups answered from the cache");
+    let _ = writeln!(out, "devkit_docs_cache_hits_total {}", cache.hits);
+    header(&mut out, "devkit_docs_cache_misses_total", "counter", "Docs cache lookups that went upstream");
+    let _ = writeln!(out, "devkit_docs_cache_misses_total {}", cache.misses);
+    header(&mut out, "devkit_docs_cache_hit_ratio", "gauge", "Share of docs cache lookups that were hits");
+    let _ = writeln!(out, "devkit_docs_cache_hit_ratio {}", cache.hit_ratio);
+    out
+}
+
+/// `# HELP` and `# TYPE` lines of a metric
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::metrics::Operation;
+    use std::time::Duration;
+
+    #[test]
+    fn test_render_prometheus_text() {
+        let metrics = Metrics::default();
+        metrics.record_response(Operation::Docs, 200, Duration::from_millis(40));
+        metrics.record_response(Operation::Docs, 200, Duration::from_millis(700));
+        metrics.record_failure(Operation::Docs, Duration::from_secs(60));
+        metrics.record_bytes(Operation::Docs, 512);
+        let cache = CacheStats {
+            memory_entries: 0,
+            disk_entries: 0,
+            total_bytes: 0,
+            hits: 3,
+            misses: 1,
+            hit_ratio: 0.75,
+            oldest: None,
+            directory: None,
+            max_bytes: None,
+        };
+
+        let text = render(&metrics.snapshot(), &cache);
+        for line in [
+            "# TYPE devkit_docs_responses_total counter",
+            "devkit_docs_responses_total{operation=\"docs\",status=\"200\"} 2",
+            "devkit_docs_request_failures_total{operation=\"docs\"} 1",
+            "devkit_docs_request_duration_seconds_bucket{operation=\"docs\",le=\"0.05\"} 1",
+            "devkit_docs_request_duration_seconds_bucket{operation=\"docs\",le=\"1\"} 2",
+            "devkit_docs_request_duration_seconds_bucket{operation=\"docs\",le=\"30\"} 2",
+            "devkit_docs_request_duration_seconds_bucket{operation=\"docs\",le=\"+Inf\"} 3",
+            "devkit_docs_request_duration_seconds_sum{operation=\"docs\"} 60.74",
+            "devkit_docs_request_duration_seconds_count{operation=\"docs\"} 3",
+            "devkit_docs_received_bytes_total{operation=\"docs\"} 512",
+            "devkit_docs_cache_hits_total 3",
+            "devkit_docs_cache_hit_ratio 0.75",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {:?} in\n{}", line, text);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! HTTP transport support shared by the `devkit-mcp-http` binary and the CLI
+
+#[cfg(feature = "http-server")]
+pub mod auth;
+#[cfg(feature = "http-server")]
+pub mod batch;
+#[cfg(feature = "http-server")]
+pub mod compression;
+pub mod daemon;
+pub mod discovery;
+pub mod drain;
+#[cfg(feature = "http-server")]
+pub mod endpoints;
+#[cfg(feature = "http-server")]
+pub mod health;
+pub mod keepalive;
+pub mod lock;
+#[cfg(feature = "http-server")]
+pub mod manifest;
+#[cfg(feature = "http-server")]
+pub mod metrics;
+pub mod options;
+pub mod outbound;
+pub mod port;
+pub mod reaper;
+#[cfg(feature = "http-server")]
+pub mod request_log;
+pub mod resume;
+#[cfg(feature = "http-server")]
+pub mod status;
+#[cfg(feature = "http-server")]
+pub mod tls;
+#[cfg(feature = "http-server")]
+pub mod websocket;
+
+#[cfg(feature = "http-server")]
+pub use auth::{require_token, AccessToken, HTTP_TOKEN_ENV};
+#[cfg(feature = "http-server")]
+pub use batch::split_batches;
+#[cfg(feature = "http-server")]
+pub use compression::compression_layer;
+pub use discovery::{InstanceInfo, InstanceRegistration, list_instances, register_instance, format_instances};
+pub use drain::{shutdown_signal, DrainState, EndpointStats, SessionCounts, SessionSummary, ShutdownSignal, DEFAULT_SHUTDOWN_GRACE};
+#[cfg(feature = "http-server")]
+pub use drain::reject_when_draining;
+#[cfg(feature = "http-server")]
+pub use endpoints::{default_router, endpoints_router, validate_endpoints, DEFAULT_MCP_PATH, STREAMABLE_HTTP_PATH};
+#[cfg(feature = "http-server")]
+pub use health::{health_router, HEALTH_PATH, READINESS_PATH};
+pub use keepalive::KeepAliveRange;
+#[cfg(feature = "http-server")]
+pub use keepalive::{keepalive_service, EffectiveKeepAlive};
+pub use lock::{acquire_instance_lock, InstanceLock, LockOutcome, LockOwner};
+#[cfg(feature = "http-server")]
+pub use manifest::{manifest_router, MANIFEST_PATH};
+#[cfg(feature = "http-server")]
+pub use metrics::{metrics_router, METRICS_PATH};
+pub use options::{HttpServerArgs, HttpServerOptions, TlsSource, DEFAULT_MAX_BATCH, DEFAULT_WS_MAX_FRAME_BYTES};
+pub use outbound::{Occupancy, OutboundBuffers, OutboundLimits};
+pub use port::{bind_listener, read_port_file, write_port_file, PortFile, PORT_FILE};
+pub use reaper::{SessionActivity, SessionLimits, DEFAULT_SESSION_IDLE_TIMEOUT};
+#[cfg(feature = "http-server")]
+pub use request_log::log_http_requests;
+pub use resume::{SavedSession, SessionStore, DEFAULT_RESUME_GRACE};
+#[cfg(feature = "http-server")]
+pub use resume::ResumableSessionManager;
+#[cfg(feature = "http-server")]
+pub use status::{status_router, StatusPage, STATUS_PATH};
+#[cfg(feature = "http-server")]
+pub use tls::{serve_tls, TlsSetup};
+#[cfg(feature = "http-server")]
+pub use websocket::{ws_router, WS_PATH};
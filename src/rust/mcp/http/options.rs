@@ -0,0 +1,662 @@
+//! Runtime options for `devkit-mcp-http`
+//!
+//! Every option resolves as: command line flag -> environment variable -> mode default.
+//! The bind address can also come from `mcp_config.http_bind`, which only applies when
+//! neither `--host` nor its environment variables are set.
+//! Container mode (`--container`, `DEVKIT_CONTAINER=1` or a `/.dockerenv` file) only
+//! changes the defaults, so each setting can still be overridden on its own.
+
+use clap::Args;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::keepalive::KeepAliveRange;
+use super::outbound::OutboundLimits;
+use super::reaper::SessionLimits;
+use super::{DEFAULT_RESUME_GRACE, DEFAULT_SHUTDOWN_GRACE};
+
+/// Default per-frame limit of the WebSocket endpoint (4 MiB)
+pub const DEFAULT_WS_MAX_FRAME_BYTES: usize = 4 * 1024 * 1024;
+
+/// Default limit on the number of messages in one JSON-RPC batch
+pub const DEFAULT_MAX_BATCH: usize = 50;
+
+use crate::utils::LogFormat;
+
+/// Default port when neither flag nor env var is set
+pub const DEFAULT_PORT: u16 = 8808;
+
+/// Command line flags of the HTTP server
+#[derive(Debug, Clone, Default, Args)]
+pub struct HttpServerArgs {
+    /// Container mode: bind 0.0.0.0, JSON logs on stdout, no popup tool, health endpoint on
+    #[arg(long)]
+    pub container: bool,
+
+    /// Address to bind, IPv4 or IPv6 (0.0.0.0 or :: for every interface) [env: MCP_HTTP_BIND, MCP_HTTP_HOST]
+    #[arg(long, value_parser = parse_bind_ip)]
+    pub host: Option<IpAddr>,
+
+    /// Port to bind [env: MCP_HTTP_PORT, PORT in container mode]
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Pick a free port when the configured one is in use (default on outside container mode) [env: DEVKIT_AUTO_PORT]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub auto_port: Option<bool>,
+
+    /// When a healthy server already runs for this port, print its endpoint and exit 0 instead of failing (default on) [env: DEVKIT_JOIN]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub join: Option<bool>,
+
+    /// Externally reachable base URL printed in the banner [env: PUBLIC_URL]
+    #[arg(long)]
+    pub public_url: Option<String>,
+
+    /// Log format: text or json [env: DEVKIT_LOG_FORMAT]
+    #[arg(long)]
+    pub log_format: Option<LogFormat>,
+
+    /// Log to stdout only instead of the log file [env: DEVKIT_LOG_STDOUT]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub log_stdout: Option<bool>,
+
+    /// Offer the interactive popup tools [env: DEVKIT_POPUP]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub popup: Option<bool>,
+
+    /// Serve GET /health and GET /readiness [env: DEVKIT_HEALTH]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub health: Option<bool>,
+
+    /// Seconds in-flight tool calls get to finish on shutdown [env: DEVKIT_SHUTDOWN_GRACE]
+    #[arg(long, value_name = "SECS")]
+    pub shutdown_grace: Option<u64>,
+
+    /// Seconds clients have to resume their sessions after a restart, 0 disables [env: DEVKIT_RESUME_GRACE]
+    #[arg(long, value_name = "SECS")]
+    pub resume_grace: Option<u64>,
+
+    /// Serve MCP over WebSocket on /ws [env: DEVKIT_WEBSOCKET]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub websocket: Option<bool>,
+
+    /// Compress responses with gzip or deflate when the client accepts it [env: DEVKIT_HTTP_COMPRESSION]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub compression: Option<bool>,
+
+    /// Largest WebSocket frame or message accepted, in bytes [env: DEVKIT_WS_MAX_FRAME_BYTES]
+    #[arg(long, value_name = "BYTES")]
+    pub ws_max_frame_bytes: Option<usize>,
+
+    /// Most messages accepted in one JSON-RPC batch, 0 rejects batches [env: DEVKIT_MAX_BATCH]
+    #[arg(long, value_name = "COUNT")]
+    pub max_batch: Option<usize>,
+
+    /// Default SSE keep-alive interval in seconds; clients may pass ?keepalive= [env: DEVKIT_SSE_KEEPALIVE]
+    #[arg(long, value_name = "SECS")]
+    pub sse_keepalive: Option<u64>,
+
+    /// Shortest keep-alive interval a client may request [env: DEVKIT_SSE_KEEPALIVE_MIN]
+    #[arg(long, value_name = "SECS")]
+    pub sse_keepalive_min: Option<u64>,
+
+    /// Longest keep-alive interval a client may request [env: DEVKIT_SSE_KEEPALIVE_MAX]
+    #[arg(long, value_name = "SECS")]
+    pub sse_keepalive_max: Option<u64>,
+
+    /// Seconds a session may go without requests or open streams before it is closed, 0 keeps it [env: DEVKIT_SESSION_IDLE_TIMEOUT]
+    #[arg(long, value_name = "SECS")]
+    pub session_idle_timeout: Option<u64>,
+
+    /// Seconds after which a session is closed even when busy, 0 for no limit (default) [env: DEVKIT_SESSION_MAX_LIFETIME]
+    #[arg(long, value_name = "SECS")]
+    pub session_max_lifetime: Option<u64>,
+
+    /// Most bytes queued on a session's SSE streams before it counts as not reading [env: DEVKIT_SSE_BUFFER_MAX_BYTES]
+    #[arg(long, value_name = "BYTES")]
+    pub sse_buffer_max_bytes: Option<usize>,
+
+    /// Most messages queued on a session's SSE streams before it counts as not reading [env: DEVKIT_SSE_BUFFER_MAX_MESSAGES]
+    #[arg(long, value_name = "COUNT")]
+    pub sse_buffer_max_messages: Option<usize>,
+
+    /// Seconds a session may stay over an SSE buffer limit before it is dropped [env: DEVKIT_SSE_BUFFER_GRACE]
+    #[arg(long, value_name = "SECS")]
+    pub sse_buffer_grace: Option<u64>,
+
+    /// PEM certificate (chain) to serve HTTPS with, together with --tls-key [env: DEVKIT_TLS_CERT]
+    #[arg(long, value_name = "PATH")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// PEM private key of --tls-cert [env: DEVKIT_TLS_KEY]
+    #[arg(long, value_name = "PATH")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Serve HTTPS with a self-signed certificate generated once into the state directory [env: DEVKIT_TLS_SELF_SIGNED]
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub tls_self_signed: Option<bool>,
+
+    /// Refuse to start with an unreadable config instead of falling back to defaults [env: DEVKIT_STRICT_CONFIG]
+    #[arg(long)]
+    pub strict_config: bool,
+}
+
+/// Fully resolved server options
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpServerOptions {
+    pub container: bool,
+    pub host: IpAddr,
+    /// `host` came from the flag or the environment rather than the mode default
+    pub host_explicit: bool,
+    pub port: u16,
+    /// Fall back to a free port when `port` is in use
+    pub auto_port: bool,
+    /// Reuse a running server for the same port instead of failing
+    pub join: bool,
+    pub public_url: Option<String>,
+    pub log_format: LogFormat,
+    pub log_stdout: bool,
+    pub popup: bool,
+    pub health: bool,
+    pub shutdown_grace: Duration,
+    pub resume_grace: Duration,
+    pub websocket: bool,
+    /// Compress responses for clients sending `Accept-Encoding`
+    pub compression: bool,
+    pub ws_max_frame_bytes: usize,
+    pub max_batch: usize,
+    pub sse_keepalive: KeepAliveRange,
+    /// When abandoned or long-lived sessions are closed
+    pub session_limits: SessionLimits,
+    pub sse_buffer: OutboundLimits,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub tls_self_signed: bool,
+}
+
+/// Where the HTTPS certificate comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlsSource {
+    /// PEM files given on the command line or in the environment
+    Files { cert: PathBuf, key: PathBuf },
+    /// Generated on first use and kept in the state directory
+    SelfSigned,
+}
+
+impl HttpServerOptions {
+    /// Resolve against the process environment
+    pub fn resolve(args: &HttpServerArgs) -> Self {
+        Self::resolve_with(args, |name| std::env::var(name).ok(), Path::new("/.dockerenv").exists())
+    }
+
+    /// Resolve with an injectable environment (used by tests)
+    pub fn resolve_with(
+        args: &HttpServerArgs,
+        env: impl Fn(&str) -> Option<String>,
+        dockerenv_exists: bool,
+    ) -> Self {
+        let env = |name: &str| env(name).filter(|v| !v.trim().is_empty());
+        let env_bool = |name: &str| env(name).and_then(|v| parse_bool(&v));
+
+        let container = args.container || env_bool("DEVKIT_CONTAINER").unwrap_or(dockerenv_exists);
+
+        let default_host = if container {
+            IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+        } else {
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        };
+        let explicit_host = args
+            .host
+            .or_else(|| env("MCP_HTTP_BIND").and_then(|v| parse_bind_ip(&v).ok()))
+            .or_else(|| env("MCP_HTTP_HOST").and_then(|v| parse_bind_ip(&v).ok()));
+
+        let port = args
+            .port
+            .or_else(|| env("MCP_HTTP_PORT").and_then(|v| v.trim().parse().ok()))
+            .or_else(|| {
+                if container {
+                    env("PORT").and_then(|v| v.trim().parse().ok())
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(DEFAULT_PORT);
+
+        let public_url = args
+            .public_url
+            .clone()
+            .or_else(|| env("PUBLIC_URL"))
+            .map(|url| url.trim().trim_end_matches('/').to_string());
+
+        let secs = |arg: Option<u64>, name: &str, default: Duration| {
+            arg.or_else(|| env(name).and_then(|v| v.trim().parse().ok()))
+                .map(Duration::from_secs)
+                .unwrap_or(default)
+        };
+        let keepalive_defaults = KeepAliveRange::default();
+        let sse_keepalive = KeepAliveRange {
+            default: secs(args.sse_keepalive, "DEVKIT_SSE_KEEPALIVE", keepalive_defaults.default),
+            min: secs(args.sse_keepalive_min, "DEVKIT_SSE_KEEPALIVE_MIN", keepalive_defaults.min),
+            max: secs(args.sse_keepalive_max, "DEVKIT_SSE_KEEPALIVE_MAX", keepalive_defaults.max),
+        };
+
+        // 0 disables a limit
+        let limit = |arg: Option<u64>, name: &str, default: Option<Duration>| {
+            Some(secs(arg, name, default.unwrap_or_default())).filter(|limit| !limit.is_zero())
+        };
+        let session_defaults = SessionLimits::default();
+        let session_limits = SessionLimits {
+            idle_timeout: limit(args.session_idle_timeout, "DEVKIT_SESSION_IDLE_TIMEOUT", session_defaults.idle_timeout),
+            max_lifetime: limit(args.session_max_lifetime, "DEVKIT_SESSION_MAX_LIFETIME", session_defaults.max_lifetime),
+        };
+
+        let buffer_defaults = OutboundLimits::default();
+        let sse_buffer = OutboundLimits {
+            max_bytes: args
+                .sse_buffer_max_bytes
+                .or_else(|| env("DEVKIT_SSE_BUFFER_MAX_BYTES").and_then(|v| v.trim().parse().ok()))
+                .unwrap_or(buffer_defaults.max_bytes),
+            max_messages: args
+                .sse_buffer_max_messages
+                .or_else(|| env("DEVKIT_SSE_BUFFER_MAX_MESSAGES").and_then(|v| v.trim().parse().ok()))
+                .unwrap_or(buffer_defaults.max_messages),
+            grace: secs(args.sse_buffer_grace, "DEVKIT_SSE_BUFFER_GRACE", buffer_defaults.grace),
+        };
+
+        let log_format = args
+            .log_format
+            .or_else(|| env("DEVKIT_LOG_FORMAT").and_then(|v| v.parse().ok()))
+            .unwrap_or(if container { LogFormat::Json } else { LogFormat::Text });
+
+        Self {
+            container,
+            host: explicit_host.unwrap_or(default_host),
+            host_explicit: explicit_host.is_some(),
+            port,
+            // A container's port mapping points at one port, moving elsewhere would hide the server
+            auto_port: args.auto_port.or_else(|| env_bool("DEVKIT_AUTO_PORT")).unwrap_or(!container),
+            public_url,
+            log_format,
+            log_stdout: args.log_stdout.or_else(|| env_bool("DEVKIT_LOG_STDOUT")).unwrap_or(container),
+            popup: args.popup.or_else(|| env_bool("DEVKIT_POPUP")).unwrap_or(!container),
+            health: args.health.or_else(|| env_bool("DEVKIT_HEALTH")).unwrap_or(container),
+            shutdown_grace: args
+                .shutdown_grace
+                .or_else(|| env("DEVKIT_SHUTDOWN_GRACE").and_then(|v| v.trim().parse().ok()))
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_SHUTDOWN_GRACE),
+            resume_grace: secs(args.resume_grace, "DEVKIT_RESUME_GRACE", DEFAULT_RESUME_GRACE),
+            websocket: args.websocket.or_else(|| env_bool("DEVKIT_WEBSOCKET")).unwrap_or(false),
+            compression: args.compression.or_else(|| env_bool("DEVKIT_HTTP_COMPRESSION")).unwrap_or(true),
+            join: args.join.or_else(|| env_bool("DEVKIT_JOIN")).unwrap_or(true),
+            ws_max_frame_bytes: args
+                .ws_max_frame_bytes
+                .or_else(|| env("DEVKIT_WS_MAX_FRAME_BYTES").and_then(|v| v.trim().parse().ok()))
+                .unwrap_or(DEFAULT_WS_MAX_FRAME_BYTES),
+            max_batch: args
+                .max_batch
+                .or_else(|| env("DEVKIT_MAX_BATCH").and_then(|v| v.trim().parse().ok()))
+                .unwrap_or(DEFAULT_MAX_BATCH),
+            sse_keepalive,
+            session_limits,
+            sse_buffer,
+            tls_cert: args.tls_cert.clone().or_else(|| env("DEVKIT_TLS_CERT").map(PathBuf::from)),
+            tls_key: args.tls_key.clone().or_else(|| env("DEVKIT_TLS_KEY").map(PathBuf::from)),
+            tls_self_signed: args.tls_self_signed.or_else(|| env_bool("DEVKIT_TLS_SELF_SIGNED")).unwrap_or(false),
+        }
+    }
+
+    /// Take the bind address from `mcp_config.http_bind` unless the flag or environment set one
+    pub fn apply_config_bind(&mut self, bind: Option<&str>) -> Result<(), String> {
+        let Some(bind) = bind.filter(|bind| !bind.trim().is_empty()) else {
+            return Ok(());
+        };
+        let host = parse_bind_ip(bind).map_err(|e| format!("mcp_config.http_bind: {}", e))?;
+        if !self.host_explicit {
+            self.host = host;
+        }
+        Ok(())
+    }
+
+    /// Warning for binds other machines can reach without a token, or with one sent in clear text
+    pub fn exposure_warning(&self, authenticated: bool) -> Option<String> {
+        if self.host.is_loopback() {
+            return None;
+        }
+        let reach = if self.host.is_unspecified() { "every network interface" } else { "a non-loopback address" };
+        if !authenticated {
+            return Some(format!(
+                "Listening on {} ({}) without an access token: anyone who can reach this port can call the tools. \
+                 Set DEVKIT_HTTP_TOKEN, restrict access with a firewall or reverse proxy, or bind 127.0.0.1 unless this is intended",
+                self.bind_addr(),
+                reach
+            ));
+        }
+        if self.scheme() == "http" {
+            return Some(format!(
+                "Listening on {} ({}) over plain HTTP: the access token travels unencrypted, enable TLS unless the network is trusted",
+                self.bind_addr(),
+                reach
+            ));
+        }
+        None
+    }
+
+    /// HTTPS certificate to serve with, `None` for plain HTTP
+    pub fn tls(&self) -> Result<Option<TlsSource>, String> {
+        match (&self.tls_cert, &self.tls_key, self.tls_self_signed) {
+            (None, None, false) => Ok(None),
+            (None, None, true) => Ok(Some(TlsSource::SelfSigned)),
+            (Some(_), Some(_), true) => Err("--tls-self-signed cannot be combined with --tls-cert/--tls-key".to_string()),
+            (Some(cert), Some(key), false) => Ok(Some(TlsSource::Files { cert: cert.clone(), key: key.clone() })),
+            (Some(_), None, _) => Err("--tls-cert needs --tls-key".to_string()),
+            (None, Some(_), _) => Err("--tls-key needs --tls-cert".to_string()),
+        }
+    }
+
+    fn scheme(&self) -> &'static str {
+        if self.tls_self_signed || self.tls_cert.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// Names a self-signed certificate must cover: loopback, the bound address and the public host
+    pub fn tls_names(&self) -> Vec<String> {
+        let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+        if !self.host.is_unspecified() && !self.host.is_loopback() {
+            names.push(self.host.to_string());
+        }
+        let public_host = self
+            .public_url
+            .as_deref()
+            .and_then(|url| reqwest::Url::parse(url).ok())
+            .and_then(|url| url.host_str().map(|host| host.trim_start_matches('[').trim_end_matches(']').to_string()));
+        if let Some(host) = public_host {
+            if !names.contains(&host) {
+                names.push(host);
+            }
+        }
+        names
+    }
+
+    pub fn bind_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+
+    /// URL for clients on this machine; wildcard binds are reached through loopback
+    pub fn local_url(&self, local_addr: SocketAddr) -> String {
+        let ip = match local_addr.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip,
+        };
+        format!("{}://{}", self.scheme(), SocketAddr::new(ip, local_addr.port()))
+    }
+
+    /// URL to advertise in the startup banner
+    pub fn public_url(&self, local_addr: SocketAddr) -> String {
+        self.public_url.clone().unwrap_or_else(|| self.local_url(local_addr))
+    }
+}
+
+/// IP address to bind; IPv6 may be written in brackets (`[::]`) as in URLs
+fn parse_bind_ip(value: &str) -> Result<IpAddr, String> {
+    let value = value.trim();
+    let unbracketed = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')).unwrap_or(value);
+    unbracketed
+        .parse()
+        .map_err(|_| format!("'{}' is not an IP address (e.g. 127.0.0.1, 0.0.0.0, ::1 or ::)", value))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn resolve(args: &HttpServerArgs, vars: &[(&str, &str)], dockerenv: bool) -> HttpServerOptions {
+        let vars: HashMap<String, String> =
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        HttpServerOptions::resolve_with(args, |name| vars.get(name).cloned(), dockerenv)
+    }
+
+    #[test]
+    fn test_normal_mode_defaults() {
+        let options = resolve(&HttpServerArgs::default(), &[("PORT", "9000")], false);
+        assert!(!options.container);
+        assert_eq!(options.bind_addr(), "127.0.0.1:8808".parse().unwrap());
+        assert_eq!(options.log_format, LogFormat::Text);
+        assert!(!options.log_stdout);
+        assert!(options.popup);
+        assert!(!options.health);
+        assert_eq!(options.shutdown_grace, DEFAULT_SHUTDOWN_GRACE);
+        assert_eq!(options.resume_grace, DEFAULT_RESUME_GRACE);
+        assert!(!options.websocket);
+        assert_eq!(options.max_batch, DEFAULT_MAX_BATCH);
+        assert!(options.auto_port);
+        assert!(options.compression);
+        assert!(options.join);
+    }
+
+    #[test]
+    fn test_join() {
+        assert!(!resolve(&HttpServerArgs::default(), &[("DEVKIT_JOIN", "0")], false).join);
+        let args = HttpServerArgs { join: Some(true), ..Default::default() };
+        assert!(resolve(&args, &[("DEVKIT_JOIN", "0")], false).join);
+    }
+
+    #[test]
+    fn test_compression() {
+        assert!(!resolve(&HttpServerArgs::default(), &[("DEVKIT_HTTP_COMPRESSION", "0")], false).compression);
+        let args = HttpServerArgs { compression: Some(true), ..Default::default() };
+        assert!(resolve(&args, &[("DEVKIT_HTTP_COMPRESSION", "0")], true).compression);
+    }
+
+    #[test]
+    fn test_auto_port() {
+        assert!(!resolve(&HttpServerArgs::default(), &[("DEVKIT_AUTO_PORT", "0")], false).auto_port);
+        let args = HttpServerArgs { auto_port: Some(true), ..Default::default() };
+        assert!(resolve(&args, &[("DEVKIT_AUTO_PORT", "0")], true).auto_port);
+    }
+
+    #[test]
+    fn test_max_batch() {
+        assert_eq!(resolve(&HttpServerArgs::default(), &[("DEVKIT_MAX_BATCH", "5")], false).max_batch, 5);
+        let args = HttpServerArgs { max_batch: Some(0), ..Default::default() };
+        assert_eq!(resolve(&args, &[("DEVKIT_MAX_BATCH", "5")], false).max_batch, 0);
+    }
+
+    #[test]
+    fn test_websocket() {
+        let options = resolve(&HttpServerArgs::default(), &[("DEVKIT_WEBSOCKET", "1"), ("DEVKIT_WS_MAX_FRAME_BYTES", "1024")], false);
+        assert!(options.websocket);
+        assert_eq!(options.ws_max_frame_bytes, 1024);
+
+        let args = HttpServerArgs { websocket: Some(false), ..Default::default() };
+        assert!(!resolve(&args, &[("DEVKIT_WEBSOCKET", "1")], false).websocket);
+    }
+
+    #[test]
+    fn test_session_limits() {
+        assert_eq!(resolve(&HttpServerArgs::default(), &[], false).session_limits, SessionLimits::default());
+
+        let args = HttpServerArgs { session_idle_timeout: Some(0), ..Default::default() };
+        let options = resolve(&args, &[("DEVKIT_SESSION_IDLE_TIMEOUT", "60"), ("DEVKIT_SESSION_MAX_LIFETIME", "86400")], false);
+        assert_eq!(options.session_limits.idle_timeout, None);
+        assert_eq!(options.session_limits.max_lifetime, Some(Duration::from_secs(86400)));
+    }
+
+    #[test]
+    fn test_sse_keepalive() {
+        let options = resolve(&HttpServerArgs::default(), &[], false);
+        assert_eq!(options.sse_keepalive, KeepAliveRange::default());
+
+        let args = HttpServerArgs { sse_keepalive_min: Some(1), ..Default::default() };
+        let options = resolve(&args, &[("DEVKIT_SSE_KEEPALIVE", "15"), ("DEVKIT_SSE_KEEPALIVE_MIN", "10")], false);
+        assert_eq!(options.sse_keepalive.default, Duration::from_secs(15));
+        assert_eq!(options.sse_keepalive.min, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_sse_buffer() {
+        assert_eq!(resolve(&HttpServerArgs::default(), &[], false).sse_buffer, OutboundLimits::default());
+
+        let args = HttpServerArgs { sse_buffer_max_messages: Some(10), ..Default::default() };
+        let vars = [("DEVKIT_SSE_BUFFER_MAX_BYTES", "65536"), ("DEVKIT_SSE_BUFFER_MAX_MESSAGES", "500"), ("DEVKIT_SSE_BUFFER_GRACE", "1")];
+        let options = resolve(&args, &vars, false);
+        assert_eq!(
+            options.sse_buffer,
+            OutboundLimits { max_bytes: 65536, max_messages: 10, grace: Duration::from_secs(1) }
+        );
+    }
+
+    #[test]
+    fn test_shutdown_grace() {
+        let options = resolve(&HttpServerArgs::default(), &[("DEVKIT_SHUTDOWN_GRACE", "5")], false);
+        assert_eq!(options.shutdown_grace, Duration::from_secs(5));
+
+        let args = HttpServerArgs { shutdown_grace: Some(2), ..Default::default() };
+        assert_eq!(resolve(&args, &[("DEVKIT_SHUTDOWN_GRACE", "5")], false).shutdown_grace, Duration::from_secs(2));
+
+        let options = resolve(&HttpServerArgs::default(), &[("DEVKIT_RESUME_GRACE", "0")], false);
+        assert!(options.resume_grace.is_zero());
+    }
+
+    #[test]
+    fn test_container_mode_defaults() {
+        for (args, vars, dockerenv) in [
+            (HttpServerArgs { container: true, ..Default::default() }, vec![], false),
+            (HttpServerArgs::default(), vec![("DEVKIT_CONTAINER", "1")], false),
+            (HttpServerArgs::default(), vec![], true),
+        ] {
+            let options = resolve(&args, &vars, dockerenv);
+            assert!(options.container);
+            assert_eq!(options.bind_addr(), "0.0.0.0:8808".parse().unwrap());
+            assert_eq!(options.log_format, LogFormat::Json);
+            assert!(options.log_stdout);
+            assert!(!options.popup);
+            assert!(options.health);
+            assert!(!options.auto_port);
+        }
+
+        // An explicit opt-out wins over /.dockerenv
+        assert!(!resolve(&HttpServerArgs::default(), &[("DEVKIT_CONTAINER", "0")], true).container);
+    }
+
+    #[test]
+    fn test_container_port_precedence() {
+        let args = HttpServerArgs { container: true, ..Default::default() };
+        assert_eq!(resolve(&args, &[("PORT", "9000")], false).port, 9000);
+        assert_eq!(resolve(&args, &[("PORT", "9000"), ("MCP_HTTP_PORT", "9100")], false).port, 9100);
+
+        let args = HttpServerArgs { container: true, port: Some(9200), ..Default::default() };
+        assert_eq!(resolve(&args, &[("PORT", "9000"), ("MCP_HTTP_PORT", "9100")], false).port, 9200);
+    }
+
+    #[test]
+    fn test_individual_overrides_in_container_mode() {
+        let args = HttpServerArgs {
+            container: true,
+            host: Some("10.0.0.5".parse().unwrap()),
+            popup: Some(true),
+            ..Default::default()
+        };
+        let vars = [
+            ("DEVKIT_LOG_FORMAT", "text"),
+            ("DEVKIT_LOG_STDOUT", "false"),
+            ("DEVKIT_HEALTH", "off"),
+            ("MCP_HTTP_HOST", "192.168.1.1"),
+        ];
+        let options = resolve(&args, &vars, false);
+        assert_eq!(options.host, "10.0.0.5".parse::<IpAddr>().unwrap());
+        assert_eq!(options.log_format, LogFormat::Text);
+        assert!(!options.log_stdout);
+        assert!(options.popup);
+        assert!(!options.health);
+    }
+
+    #[test]
+    fn test_public_url() {
+        let local: SocketAddr = "0.0.0.0:8808".parse().unwrap();
+
+        let options = resolve(&HttpServerArgs::default(), &[], true);
+        assert_eq!(options.public_url(local), "http://127.0.0.1:8808");
+
+        let options = resolve(&HttpServerArgs::default(), &[("PUBLIC_URL", "https://mcp.example.com/")], true);
+        assert_eq!(options.public_url(local), "https://mcp.example.com");
+        assert_eq!(options.local_url(local), "http://127.0.0.1:8808");
+
+        assert_eq!(options.local_url("[::]:8808".parse().unwrap()), "http://[::1]:8808");
+    }
+
+    #[test]
+    fn test_bind_address() {
+        let options = resolve(&HttpServerArgs::default(), &[("MCP_HTTP_BIND", "[::]"), ("MCP_HTTP_HOST", "10.0.0.5")], false);
+        assert_eq!(options.bind_addr(), "[::]:8808".parse().unwrap());
+        assert!(options.host_explicit);
+        assert_eq!(resolve(&HttpServerArgs::default(), &[("MCP_HTTP_HOST", "::1")], false).host, IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert!(parse_bind_ip("localhost").is_err());
+
+        // The config only replaces the mode default
+        let mut options = resolve(&HttpServerArgs::default(), &[], true);
+        options.apply_config_bind(Some("192.168.1.20")).unwrap();
+        assert_eq!(options.host, "192.168.1.20".parse::<IpAddr>().unwrap());
+        let mut options = resolve(&HttpServerArgs::default(), &[("MCP_HTTP_BIND", "127.0.0.1")], false);
+        options.apply_config_bind(Some("0.0.0.0")).unwrap();
+        assert_eq!(options.host, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert!(options.apply_config_bind(Some("lan")).is_err());
+    }
+
+    #[test]
+    fn test_tls() {
+        assert_eq!(resolve(&HttpServerArgs::default(), &[], false).tls(), Ok(None));
+
+        let options = resolve(&HttpServerArgs::default(), &[("DEVKIT_TLS_SELF_SIGNED", "1")], false);
+        assert_eq!(options.tls(), Ok(Some(TlsSource::SelfSigned)));
+        assert_eq!(options.local_url("0.0.0.0:8808".parse().unwrap()), "https://127.0.0.1:8808");
+
+        let args = HttpServerArgs { tls_cert: Some("cert.pem".into()), ..Default::default() };
+        let options = resolve(&args, &[("DEVKIT_TLS_KEY", "key.pem")], false);
+        assert_eq!(options.tls(), Ok(Some(TlsSource::Files { cert: "cert.pem".into(), key: "key.pem".into() })));
+        assert!(resolve(&args, &[], false).tls().unwrap_err().contains("--tls-key"));
+        assert!(resolve(&args, &[("DEVKIT_TLS_KEY", "key.pem"), ("DEVKIT_TLS_SELF_SIGNED", "1")], false).tls().is_err());
+    }
+
+    #[test]
+    fn test_tls_names() {
+        let vars = [("MCP_HTTP_BIND", "192.168.1.20"), ("PUBLIC_URL", "https://mcp.example.com:8443")];
+        assert_eq!(
+            resolve(&HttpServerArgs::default(), &vars, false).tls_names(),
+            ["localhost", "127.0.0.1", "::1", "192.168.1.20", "mcp.example.com"]
+        );
+        let vars = [("MCP_HTTP_BIND", "::"), ("PUBLIC_URL", "https://[::1]:8808")];
+        assert_eq!(resolve(&HttpServerArgs::default(), &vars, false).tls_names(), ["localhost", "127.0.0.1", "::1"]);
+    }
+
+    #[test]
+    fn test_exposure_warning() {
+        assert_eq!(resolve(&HttpServerArgs::default(), &[], false).exposure_warning(false), None);
+        assert_eq!(resolve(&HttpServerArgs::default(), &[("MCP_HTTP_BIND", "::1")], false).exposure_warning(false), None);
+
+        let warning = resolve(&HttpServerArgs::default(), &[], true).exposure_warning(false).unwrap();
+        assert!(warning.contains("0.0.0.0:8808 (every network interface) without an access token"), "{}", warning);
+        let lan = resolve(&HttpServerArgs::default(), &[("MCP_HTTP_BIND", "192.168.1.20")], false);
+        assert!(lan.exposure_warning(false).unwrap().contains("non-loopback"));
+
+        // A token sent in clear text is still worth a warning, not over TLS
+        assert!(lan.exposure_warning(true).unwrap().contains("plain HTTP"));
+        let tls = resolve(&HttpServerArgs::default(), &[("MCP_HTTP_BIND", "192.168.1.20"), ("DEVKIT_TLS_SELF_SIGNED", "1")], false);
+        assert_eq!(tls.exposure_warning(true), None);
+    }
+}
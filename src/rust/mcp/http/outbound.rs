@@ -0,0 +1,466 @@
+//! Bounded outbound buffers of SSE streams
+//!
+//! Messages of a session's SSE streams are pulled from the session as soon as
+//! they are produced and queued until the HTTP response takes them. A client
+//! that stops reading would let that queue grow without bound, so the bytes
+//! and messages queued per session are counted against [`OutboundLimits`].
+//!
+//! Going over a limit (the high watermark) starts a grace timer; the session is
+//! only dropped if its queues are still above half the limits (the low
+//! watermark) when the timer runs out, so a burst the client drains promptly
+//! never trips it. A dropped session has its queues freed and is closed.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Default limit on bytes queued for one session (16 MiB)
+pub const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// Default limit on messages queued for one session
+pub const DEFAULT_MAX_MESSAGES: usize = 1000;
+
+/// Default time a session may stay above the low watermark after going over a limit
+pub const DEFAULT_GRACE: Duration = Duration::from_secs(5);
+
+static GLOBAL: once_cell::sync::Lazy<OutboundBuffers> =
+    once_cell::sync::Lazy::new(|| OutboundBuffers::new(OutboundLimits::default()));
+
+/// Limits on what may be queued for one session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutboundLimits {
+    pub max_bytes: usize,
+    pub max_messages: usize,
+    pub grace: Duration,
+}
+
+impl Default for OutboundLimits {
+    fn default() -> Self {
+        Self { max_bytes: DEFAULT_MAX_BYTES, max_messages: DEFAULT_MAX_MESSAGES, grace: DEFAULT_GRACE }
+    }
+}
+
+/// What is queued for one session, as listed by /health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Occupancy {
+    pub bytes: usize,
+    pub messages: usize,
+    /// Open SSE streams of the session
+    pub streams: usize,
+    /// Time since the session went over a limit, while the grace timer runs
+    pub over_limit_ms: Option<u64>,
+}
+
+#[derive(Default)]
+struct SessionBuffer {
+    bytes: usize,
+    messages: usize,
+    #[cfg(feature = "http-server")]
+    queues: Vec<std::sync::Weak<stream::Queue>>,
+    over_since: Option<Instant>,
+}
+
+impl SessionBuffer {
+    fn occupancy(&self, now: Instant) -> Occupancy {
+        #[cfg(feature = "http-server")]
+        let streams = self.queues.iter().filter(|queue| queue.strong_count() > 0).count();
+        #[cfg(not(feature = "http-server"))]
+        let streams = 0;
+        Occupancy {
+            bytes: self.bytes,
+            messages: self.messages,
+            streams,
+            over_limit_ms: self.over_since.map(|since| now.duration_since(since).as_millis() as u64),
+        }
+    }
+}
+
+/// Outbound accounting of every session's SSE streams
+pub struct OutboundBuffers {
+    limits: RwLock<OutboundLimits>,
+    sessions: Mutex<HashMap<String, SessionBuffer>>,
+    dropped: AtomicU64,
+}
+
+impl OutboundBuffers {
+    pub fn new(limits: OutboundLimits) -> Self {
+        Self { limits: RwLock::new(limits), sessions: Mutex::new(HashMap::new()), dropped: AtomicU64::new(0) }
+    }
+
+    /// Buffers shared by every endpoint of the process
+    pub fn global() -> &'static OutboundBuffers {
+        &GLOBAL
+    }
+
+    pub fn limits(&self) -> OutboundLimits {
+        self.limits.read().map(|limits| *limits).unwrap_or_default()
+    }
+
+    /// Change the limits; queued messages are measured against them from the next change on
+    pub fn set_limits(&self, limits: OutboundLimits) {
+        if let Ok(mut current) = self.limits.write() {
+            *current = limits;
+        }
+    }
+
+    /// What is queued for `session`, `None` when it has no open stream
+    pub fn occupancy(&self, session: &str) -> Option<Occupancy> {
+        let sessions = self.sessions.lock().ok()?;
+        sessions.get(session).map(|buffer| buffer.occupancy(Instant::now()))
+    }
+
+    /// Sessions dropped for not reading their streams since the process started
+    pub fn dropped_sessions(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(feature = "http-server")]
+mod stream {
+    use futures::task::AtomicWaker;
+    use futures::{Stream, StreamExt};
+    use rmcp::transport::common::server_side_http::ServerSseMessage;
+    use std::collections::VecDeque;
+    use std::pin::Pin;
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Mutex, Weak};
+    use std::task::{Context, Poll};
+    use tokio::sync::Notify;
+    use tokio::time::Instant;
+
+    use super::{OutboundBuffers, OutboundLimits, SessionBuffer};
+    use crate::log_important;
+
+    /// State of a session's queues after a change
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Pressure {
+        Normal,
+        /// Over a limit; dropped unless below the low watermark by `deadline`
+        Grace { deadline: Instant },
+        Exceeded,
+    }
+
+    impl OutboundLimits {
+        fn above_high(&self, bytes: usize, messages: usize) -> bool {
+            bytes > self.max_bytes || messages > self.max_messages
+        }
+
+        fn above_low(&self, bytes: usize, messages: usize) -> bool {
+            bytes > self.max_bytes / 2 || messages > self.max_messages / 2
+        }
+    }
+
+    impl SessionBuffer {
+        fn pressure(&mut self, limits: &OutboundLimits, now: Instant) -> Pressure {
+            if limits.above_high(self.bytes, self.messages) {
+                self.over_since.get_or_insert(now);
+            } else if !limits.above_low(self.bytes, self.messages) {
+                self.over_since = None;
+            }
+            match self.over_since {
+                None => Pressure::Normal,
+                Some(since) if now.duration_since(since) >= limits.grace => Pressure::Exceeded,
+                Some(since) => Pressure::Grace { deadline: since + limits.grace },
+            }
+        }
+    }
+
+    /// Messages of one stream waiting for the HTTP response to take them
+    #[derive(Default)]
+    pub(super) struct Queue {
+        state: Mutex<QueueState>,
+        /// Response waiting for a message
+        waker: AtomicWaker,
+        /// Set when the response is gone
+        abandoned: Notify,
+    }
+
+    #[derive(Default)]
+    struct QueueState {
+        messages: VecDeque<(ServerSseMessage, usize)>,
+        finished: bool,
+    }
+
+    impl Queue {
+        fn push(&self, message: ServerSseMessage, bytes: usize) {
+            if let Ok(mut state) = self.state.lock() {
+                state.messages.push_back((message, bytes));
+            }
+            self.waker.wake();
+        }
+
+        /// End the stream after the queued messages
+        fn finish(&self) {
+            if let Ok(mut state) = self.state.lock() {
+                state.finished = true;
+            }
+            self.waker.wake();
+        }
+
+        /// End the stream now; returns the bytes and messages discarded
+        fn discard(&self) -> (usize, usize) {
+            let discarded = match self.state.lock() {
+                Ok(mut state) => {
+                    state.finished = true;
+                    std::mem::take(&mut state.messages)
+                }
+                Err(_) => VecDeque::new(),
+            };
+            self.waker.wake();
+            (discarded.iter().map(|(_, bytes)| bytes).sum(), discarded.len())
+        }
+    }
+
+    /// Stream handed to the HTTP response
+    struct Outbound {
+        queue: Arc<Queue>,
+        buffers: &'static OutboundBuffers,
+        session: String,
+    }
+
+    impl Stream for Outbound {
+        type Item = ServerSseMessage;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            self.queue.waker.register(cx.waker());
+            let next = match self.queue.state.lock() {
+                Ok(mut state) => match state.messages.pop_front() {
+                    Some(message) => Ok(message),
+                    None => Err(state.finished),
+                },
+                Err(_) => Err(true),
+            };
+            match next {
+                Ok((message, bytes)) => {
+                    self.buffers.release(&self.session, bytes, 1);
+                    Poll::Ready(Some(message))
+                }
+                Err(true) => Poll::Ready(None),
+                Err(false) => Poll::Pending,
+            }
+        }
+    }
+
+    impl Drop for Outbound {
+        fn drop(&mut self) {
+            let (bytes, messages) = self.queue.discard();
+            self.buffers.release(&self.session, bytes, messages);
+            if let Ok(mut sessions) = self.buffers.sessions.lock() {
+                if let Some(buffer) = sessions.get_mut(&self.session) {
+                    buffer.queues.retain(|queue| queue.strong_count() > 0 && !Weak::ptr_eq(queue, &Arc::downgrade(&self.queue)));
+                    if buffer.queues.is_empty() && buffer.messages == 0 {
+                        sessions.remove(&self.session);
+                    }
+                }
+            }
+            self.queue.abandoned.notify_one();
+        }
+    }
+
+    impl OutboundBuffers {
+        /// Count a message of `bytes` queued for `session`
+        fn push(&self, session: &str, bytes: usize) -> Pressure {
+            let limits = self.limits();
+            let Ok(mut sessions) = self.sessions.lock() else {
+                return Pressure::Normal;
+            };
+            let buffer = sessions.entry(session.to_string()).or_default();
+            buffer.bytes += bytes;
+            buffer.messages += 1;
+            buffer.pressure(&limits, Instant::now())
+        }
+
+        /// Count `messages` totalling `bytes` as taken from the queues of `session`
+        fn release(&self, session: &str, bytes: usize, messages: usize) {
+            let limits = self.limits();
+            if let Ok(mut sessions) = self.sessions.lock() {
+                if let Some(buffer) = sessions.get_mut(session) {
+                    buffer.bytes = buffer.bytes.saturating_sub(bytes);
+                    buffer.messages = buffer.messages.saturating_sub(messages);
+                    buffer.pressure(&limits, Instant::now());
+                }
+            }
+        }
+
+        /// Re-check `session` when its grace timer runs out
+        fn pressure(&self, session: &str) -> Pressure {
+            let limits = self.limits();
+            let Ok(mut sessions) = self.sessions.lock() else {
+                return Pressure::Normal;
+            };
+            sessions.get_mut(session).map_or(Pressure::Normal, |buffer| buffer.pressure(&limits, Instant::now()))
+        }
+
+        /// Queue `stream` of `session` under the outbound limits
+        ///
+        /// `close_session` is called once if the session is dropped for not
+        /// reading its streams.
+        pub fn guard(
+            &'static self,
+            session: &str,
+            stream: impl Stream<Item = ServerSseMessage> + Send + 'static,
+            close_session: impl FnOnce() + Send + 'static,
+        ) -> impl Stream<Item = ServerSseMessage> + Send + Sync + 'static {
+            let queue = Arc::new(Queue::default());
+            if let Ok(mut sessions) = self.sessions.lock() {
+                sessions.entry(session.to_string()).or_default().queues.push(Arc::downgrade(&queue));
+            }
+            tokio::spawn(self.pump(session.to_string(), stream, queue.clone(), close_session));
+            Outbound { queue, buffers: self, session: session.to_string() }
+        }
+
+        async fn pump(
+            &'static self,
+            session: String,
+            stream: impl Stream<Item = ServerSseMessage> + Send + 'static,
+            queue: Arc<Queue>,
+            close_session: impl FnOnce() + Send + 'static,
+        ) {
+            let mut stream = std::pin::pin!(stream);
+            let mut deadline = None;
+            loop {
+                let pressure = tokio::select! {
+                    message = stream.next() => {
+                        let Some(message) = message else {
+                            queue.finish();
+                            return;
+                        };
+                        let bytes = message_len(&message);
+                        let pressure = self.push(&session, bytes);
+                        queue.push(message, bytes);
+                        pressure
+                    }
+                    _ = queue.abandoned.notified() => return,
+                    _ = sleep_until(deadline) => self.pressure(&session),
+                };
+                deadline = match pressure {
+                    Pressure::Normal => None,
+                    Pressure::Grace { deadline } => Some(deadline),
+                    Pressure::Exceeded => break,
+                };
+            }
+
+            let occupancy = self.occupancy(&session).unwrap_or_default();
+            let limits = self.limits();
+            log_important!(
+                warn,
+                "Session {}: client is not reading its stream ({} bytes in {} messages queued, limits {} bytes / {} messages), dropping the session after {} ms",
+                session,
+                occupancy.bytes,
+                occupancy.messages,
+                limits.max_bytes,
+                limits.max_messages,
+                occupancy.over_limit_ms.unwrap_or_default()
+            );
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            let queues: Vec<_> = match self.sessions.lock() {
+                Ok(sessions) => sessions.get(&session).map(|b| b.queues.iter().filter_map(Weak::upgrade).collect()).unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+            for queue in queues {
+                let (bytes, messages) = queue.discard();
+                self.release(&session, bytes, messages);
+            }
+            close_session();
+        }
+    }
+
+    async fn sleep_until(deadline: Option<Instant>) {
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Size of a message as sent: its JSON plus the event id
+    fn message_len(message: &ServerSseMessage) -> usize {
+        let json = serde_json::to_vec(message.message.as_ref()).map(|bytes| bytes.len()).unwrap_or_default();
+        json + message.event_id.as_ref().map_or(0, String::len)
+    }
+}
+
+#[cfg(all(test, feature = "http-server"))]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use rmcp::transport::common::server_side_http::ServerSseMessage;
+    use serde_json::json;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    fn buffers(max_bytes: usize) -> &'static OutboundBuffers {
+        let limits = OutboundLimits { max_bytes, max_messages: 100, grace: Duration::from_secs(2) };
+        Box::leak(Box::new(OutboundBuffers::new(limits)))
+    }
+
+    /// `count` log notifications of about 200 bytes each, then a stream that stays open
+    fn burst(count: usize) -> impl futures::Stream<Item = ServerSseMessage> + Send + 'static {
+        let message = |i: usize| ServerSseMessage {
+            event_id: Some(i.to_string()),
+            message: Arc::new(
+                serde_json::from_value(json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/message",
+                    "params": { "level": "info", "data": "x".repeat(150) }
+                }))
+                .unwrap(),
+            ),
+        };
+        futures::stream::iter((0..count).map(message)).chain(futures::stream::pending())
+    }
+
+    fn closed_flag() -> (Arc<AtomicBool>, impl FnOnce() + Send + 'static) {
+        let closed = Arc::new(AtomicBool::new(false));
+        let flag = closed.clone();
+        (closed, move || flag.store(true, Ordering::SeqCst))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stalled_client_is_dropped_after_grace() {
+        let buffers = buffers(1000);
+        let (closed, close) = closed_flag();
+        // The client never reads its stream
+        let mut stalled = Box::pin(buffers.guard("stalled", burst(20), close));
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let occupancy = buffers.occupancy("stalled").unwrap();
+        assert_eq!(occupancy.messages, 20);
+        assert!(occupancy.bytes > 1000, "{:?}", occupancy);
+        assert_eq!(occupancy.streams, 1);
+        assert!(occupancy.over_limit_ms.is_some());
+        assert!(!closed.load(Ordering::SeqCst));
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(closed.load(Ordering::SeqCst));
+        assert_eq!(buffers.dropped_sessions(), 1);
+        let occupancy = buffers.occupancy("stalled").unwrap();
+        assert_eq!((occupancy.bytes, occupancy.messages), (0, 0));
+
+        // The queued messages were discarded and the stream ends
+        assert!(stalled.next().await.is_none());
+        drop(stalled);
+        assert_eq!(buffers.occupancy("stalled"), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_drained_within_grace_is_kept() {
+        let buffers = buffers(1000);
+        let (closed, close) = closed_flag();
+        let mut slow = Box::pin(buffers.guard("slow", burst(20), close));
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(buffers.occupancy("slow").unwrap().over_limit_ms.is_some());
+        for _ in 0..20 {
+            slow.next().await.unwrap();
+        }
+        let occupancy = buffers.occupancy("slow").unwrap();
+        assert_eq!((occupancy.bytes, occupancy.messages, occupancy.over_limit_ms), (0, 0, None));
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(!closed.load(Ordering::SeqCst));
+        assert_eq!(buffers.dropped_sessions(), 0);
+    }
+}
@@ -0,0 +1,145 @@
+//! Port selection for the HTTP server
+//!
+//! When the configured port is taken, [`bind_listener`] tries the next few
+//! ports and finally one the OS picks, instead of failing to start. The port
+//! actually bound is written to `<state_dir>/mcp_http.port` so clients and
+//! scripts can find the server without parsing logs; the file is removed on
+//! shutdown unless another instance has written its own port since.
+
+use anyhow::Result;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+
+use crate::utils::paths;
+use crate::{log_debug, log_important};
+
+/// Name of the well-known port file in the state directory
+pub const PORT_FILE: &str = "mcp_http.port";
+
+/// Ports after the configured one tried before asking the OS for any free port
+const FALLBACK_ATTEMPTS: u16 = 10;
+
+/// Bind `addr`, or with `auto_port` a free port when it is in use
+///
+/// The sequential ports come first so a restarted server tends to land on the
+/// same port and its clients can resume their sessions.
+pub async fn bind_listener(addr: SocketAddr, auto_port: bool) -> std::io::Result<TcpListener> {
+    let error = match TcpListener::bind(addr).await {
+        Ok(listener) => return Ok(listener),
+        Err(e) if auto_port && e.kind() == ErrorKind::AddrInUse && addr.port() != 0 => e,
+        Err(e) => return Err(e),
+    };
+    log_important!(warn, "Port {} is in use ({}), looking for a free one", addr.port(), error);
+
+    let next = (1..=FALLBACK_ATTEMPTS).filter_map(|offset| addr.port().checked_add(offset));
+    for port in next.chain(std::iter::once(0)) {
+        match TcpListener::bind(SocketAddr::new(addr.ip(), port)).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) if e.kind() == ErrorKind::AddrInUse => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(error)
+}
+
+/// Handle returned by [`write_port_file`]; removes the file when dropped
+pub struct PortFile {
+    path: PathBuf,
+    port: u16,
+}
+
+impl PortFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PortFile {
+    fn drop(&mut self) {
+        // A newer instance may have taken the file over
+        if read_port_file_at(&self.path) != Some(self.port) {
+            return;
+        }
+        match fs::remove_file(&self.path) {
+            Ok(()) => log_debug!("Removed port file {}", self.path.display()),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => log_important!(warn, "Failed to remove port file {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Path of the well-known port file
+pub fn port_file_path() -> Result<PathBuf> {
+    Ok(paths::state_dir()?.join(PORT_FILE))
+}
+
+/// Record `port` in the well-known port file
+pub fn write_port_file(port: u16) -> Result<PortFile> {
+    write_port_file_at(&port_file_path()?, port)
+}
+
+/// Port of the most recently started server, if its file is still there
+pub fn read_port_file() -> Option<u16> {
+    read_port_file_at(&port_file_path().ok()?)
+}
+
+fn write_port_file_at(path: &Path, port: u16) -> Result<PortFile> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write to a temp file first so readers never see a half-written number
+    let tmp_path = path.with_extension(format!("port.{}.tmp", std::process::id()));
+    fs::write(&tmp_path, format!("{}\n", port))?;
+    fs::rename(&tmp_path, path)?;
+    log_debug!("Wrote port file {}", path.display());
+    Ok(PortFile { path: path.to_path_buf(), port })
+}
+
+fn read_port_file_at(path: &Path) -> Option<u16> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn loopback(port: u16) -> SocketAddr {
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)
+    }
+
+    #[tokio::test]
+    async fn test_busy_port_falls_back_to_a_free_one() {
+        let taken = TcpListener::bind(loopback(0)).await.unwrap();
+        let busy = taken.local_addr().unwrap();
+
+        let listener = bind_listener(busy, true).await.unwrap();
+        assert_ne!(listener.local_addr().unwrap().port(), busy.port());
+
+        let error = bind_listener(busy, false).await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::AddrInUse);
+    }
+
+    #[test]
+    fn test_port_file_is_removed_only_by_its_writer() {
+        let dir = TestDir::reserve("port-test");
+        let path = dir.join(PORT_FILE);
+
+        let first = write_port_file_at(&path, 8808).unwrap();
+        assert_eq!(read_port_file_at(&path), Some(8808));
+        drop(first);
+        assert!(!path.exists());
+
+        // A second instance took the file over; the first one leaves it alone
+        let first = write_port_file_at(&path, 8808).unwrap();
+        let second = write_port_file_at(&path, 8809).unwrap();
+        drop(first);
+        assert_eq!(read_port_file_at(&path), Some(8809));
+        drop(second);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,200 @@
+//! Closing streamable HTTP sessions whose clients went away
+//!
+//! A client that disappears without a `DELETE` leaves its session, server and
+//! channels behind for good. Sessions are therefore closed once they had no
+//! request and no open SSE stream for the idle timeout (default 30 minutes),
+//! and, when a maximum lifetime is set, once they are older than that whatever
+//! they are doing. A client that comes back afterwards gets a 404 and
+//! initializes a new session, as the protocol prescribes.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Default time a session may sit without requests or streams
+pub const DEFAULT_SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Longest pause between two sweeps
+const MAX_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// When sessions are closed; `None` disables a limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionLimits {
+    pub idle_timeout: Option<Duration>,
+    pub max_lifetime: Option<Duration>,
+}
+
+impl Default for SessionLimits {
+    fn default() -> Self {
+        Self { idle_timeout: Some(DEFAULT_SESSION_IDLE_TIMEOUT), max_lifetime: None }
+    }
+}
+
+impl SessionLimits {
+    /// No limits: sessions live until their client deletes them
+    pub const NONE: Self = Self { idle_timeout: None, max_lifetime: None };
+
+    pub fn is_enabled(&self) -> bool {
+        self.idle_timeout.is_some() || self.max_lifetime.is_some()
+    }
+
+    /// Time between sweeps: a quarter of the shortest limit, between 1 and 60 seconds
+    pub fn sweep_interval(&self) -> Duration {
+        let shortest = self.idle_timeout.into_iter().chain(self.max_lifetime).min().unwrap_or(MAX_SWEEP_INTERVAL);
+        (shortest / 4).clamp(Duration::from_secs(1), MAX_SWEEP_INTERVAL)
+    }
+}
+
+/// Why a session was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReapReason {
+    Idle(Duration),
+    Lifetime(Duration),
+}
+
+impl fmt::Display for ReapReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Idle(limit) => write!(f, "idle for {} s", limit.as_secs()),
+            Self::Lifetime(limit) => write!(f, "reached its maximum lifetime of {} s", limit.as_secs()),
+        }
+    }
+}
+
+/// Last activity and open streams of the sessions of one endpoint
+#[derive(Clone, Default)]
+pub struct SessionActivity {
+    sessions: Arc<Mutex<HashMap<String, Activity>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Activity {
+    created: Instant,
+    last_seen: Instant,
+    streams: usize,
+}
+
+impl SessionActivity {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Activity>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start tracking a new session
+    pub fn opened(&self, id: &str) {
+        let now = Instant::now();
+        self.lock().insert(id.to_string(), Activity { created: now, last_seen: now, streams: 0 });
+    }
+
+    /// Record a request of the session
+    pub fn touch(&self, id: &str) {
+        if let Some(activity) = self.lock().get_mut(id) {
+            activity.last_seen = Instant::now();
+        }
+    }
+
+    /// Count a stream of the session as open until the guard is dropped
+    pub fn stream(&self, id: &str) -> StreamGuard {
+        if let Some(activity) = self.lock().get_mut(id) {
+            activity.streams += 1;
+            activity.last_seen = Instant::now();
+        }
+        StreamGuard { activity: self.clone(), id: id.to_string() }
+    }
+
+    /// Stop tracking a closed session
+    pub fn forget(&self, id: &str) {
+        self.lock().remove(id);
+    }
+
+    /// Number of tracked sessions
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sessions to close at `now` under `limits`
+    pub fn due(&self, limits: SessionLimits, now: Instant) -> Vec<(String, ReapReason)> {
+        let mut due: Vec<(String, ReapReason)> = self
+            .lock()
+            .iter()
+            .filter_map(|(id, activity)| {
+                if let Some(limit) = limits.max_lifetime.filter(|limit| now.saturating_duration_since(activity.created) >= *limit) {
+                    return Some((id.clone(), ReapReason::Lifetime(limit)));
+                }
+                let limit = limits.idle_timeout?;
+                (activity.streams == 0 && now.saturating_duration_since(activity.last_seen) >= limit)
+                    .then(|| (id.clone(), ReapReason::Idle(limit)))
+            })
+            .collect();
+        due.sort_by(|a, b| a.0.cmp(&b.0));
+        due
+    }
+}
+
+/// Keeps a session's stream counted as open; see [`SessionActivity::stream`]
+pub struct StreamGuard {
+    activity: SessionActivity,
+    id: String,
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        if let Some(activity) = self.activity.lock().get_mut(&self.id) {
+            activity.streams = activity.streams.saturating_sub(1);
+            activity.last_seen = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn limits(idle_minutes: Option<u32>, lifetime_minutes: Option<u32>) -> SessionLimits {
+        SessionLimits { idle_timeout: idle_minutes.map(|m| MINUTE * m), max_lifetime: lifetime_minutes.map(|m| MINUTE * m) }
+    }
+
+    #[test]
+    fn test_idle_sessions_without_streams_are_due() {
+        let activity = SessionActivity::default();
+        activity.opened("idle");
+        activity.opened("streaming");
+        let stream = activity.stream("streaming");
+
+        let later = Instant::now() + MINUTE * 31;
+        assert_eq!(activity.due(limits(Some(30), None), later), vec![("idle".to_string(), ReapReason::Idle(MINUTE * 30))]);
+        assert!(activity.due(limits(Some(30), None), Instant::now()).is_empty());
+        assert!(activity.due(SessionLimits::NONE, later).is_empty());
+
+        // A closed stream counts as the last activity
+        drop(stream);
+        assert_eq!(activity.due(limits(Some(30), None), later).len(), 2);
+        activity.forget("idle");
+        assert_eq!(activity.len(), 1);
+    }
+
+    #[test]
+    fn test_lifetime_applies_to_busy_sessions() {
+        let activity = SessionActivity::default();
+        activity.opened("busy");
+        let _stream = activity.stream("busy");
+
+        let due = activity.due(limits(Some(30), Some(120)), Instant::now() + MINUTE * 121);
+        assert_eq!(due, vec![("busy".to_string(), ReapReason::Lifetime(MINUTE * 120))]);
+    }
+
+    #[test]
+    fn test_sweep_interval() {
+        assert_eq!(SessionLimits::default().sweep_interval(), MAX_SWEEP_INTERVAL);
+        let tiny = SessionLimits { idle_timeout: None, max_lifetime: Some(Duration::from_secs(2)) };
+        assert_eq!(tiny.sweep_interval(), Duration::from_secs(1));
+        let short = SessionLimits { idle_timeout: Some(Duration::from_secs(20)), max_lifetime: None };
+        assert_eq!(short.sweep_interval(), Duration::from_secs(5));
+    }
+}
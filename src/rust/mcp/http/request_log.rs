@@ -0,0 +1,117 @@
+//! Access log of the HTTP server
+//!
+//! Every request gets a correlation id: the client's `X-Request-Id` when it is
+//! a sane token, a fresh one otherwise. The id is set on the request before it
+//! reaches a transport, echoed in the response and logged with the method,
+//! path, MCP session, status and duration once the response head is ready.
+//! Tool calls carried by the request run with the same id, so their log lines
+//! can be matched to it (see [`crate::utils::correlation`]). For SSE streams the
+//! duration ends when the stream opens, not when it closes.
+
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::time::Instant;
+
+use super::health::{HEALTH_PATH, READINESS_PATH};
+use super::metrics::METRICS_PATH;
+use crate::utils::correlation::{self, REQUEST_ID_HEADER};
+use crate::{log_debug, log_important};
+
+/// Session header of the streamable HTTP transport
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Query parameter naming the session on the legacy SSE transport
+const SESSION_PARAM: &str = "sessionId";
+
+/// Axum middleware: assign a correlation id and log the request
+pub async fn log_http_requests(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(correlation::accept)
+        .map(str::to_string)
+        .unwrap_or_else(correlation::new_id);
+    let header = HeaderValue::from_str(&id).expect("correlation ids are header-safe");
+    request.headers_mut().insert(REQUEST_ID_HEADER, header.clone());
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let session = session_id(request.headers(), request.uri().query());
+    let started = Instant::now();
+
+    let mut response = correlation::scope(id.clone(), next.run(request)).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+
+    // A session created by this request is only known from the response
+    let session = session.or_else(|| session_id(response.headers(), None)).unwrap_or_else(|| "-".to_string());
+    let status = response.status().as_u16();
+    let elapsed_ms = started.elapsed().as_millis();
+    correlation::sync_scope(id, || {
+        if response.status().is_server_error() {
+            log_important!(warn, "{} {} {} {} ms session={}", method, path, status, elapsed_ms, session);
+        } else if matches!(path.as_str(), HEALTH_PATH | READINESS_PATH | METRICS_PATH) {
+            // Probes and scrapes would drown the rest at info
+            log_debug!("{} {} {} {} ms", method, path, status, elapsed_ms);
+        } else {
+            log_important!(info, "{} {} {} {} ms session={}", method, path, status, elapsed_ms, session);
+        }
+    });
+    response
+}
+
+/// MCP session of a request or response: the session header, else `?sessionId=`
+fn session_id(headers: &HeaderMap, query: Option<&str>) -> Option<String> {
+    if let Some(session) = headers.get(SESSION_HEADER).and_then(|value| value.to_str().ok()) {
+        return Some(session.to_string());
+    }
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(SESSION_PARAM)?.strip_prefix('='))
+        .filter(|session| !session.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::middleware::from_fn;
+    use axum::routing::get;
+    use axum::Router;
+
+    #[tokio::test]
+    async fn test_correlation_id_is_kept_or_assigned_and_threaded() {
+        // Echo the id the handler saw, both from the header and from the task-local
+        let handler = |request: Request| async move {
+            let header = request.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+            format!("{} {}", header, correlation::current().unwrap_or_default())
+        };
+        let app = Router::new().route("/mcp", get(handler)).layer(from_fn(log_http_requests));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let client = reqwest::Client::new();
+
+        let response = client.get(&url).header(REQUEST_ID_HEADER, "agent-turn-7").send().await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "agent-turn-7");
+        assert_eq!(response.text().await.unwrap(), "agent-turn-7 agent-turn-7");
+
+        for request in [client.get(&url), client.get(&url).header(REQUEST_ID_HEADER, "bad id")] {
+            let response = request.send().await.unwrap();
+            let id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+            assert_eq!(id.len(), 12);
+            assert_eq!(response.text().await.unwrap(), format!("{0} {0}", id));
+        }
+    }
+
+    #[test]
+    fn test_session_id_sources() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(session_id(&headers, Some("sessionId=abc&x=1")).as_deref(), Some("abc"));
+        assert_eq!(session_id(&headers, Some("sessionIdx=abc")), None);
+        headers.insert(SESSION_HEADER, HeaderValue::from_static("s-1"));
+        assert_eq!(session_id(&headers, Some("sessionId=abc")).as_deref(), Some("s-1"));
+    }
+}
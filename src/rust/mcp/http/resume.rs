@@ -0,0 +1,674 @@
+//! Streamable HTTP sessions that survive a server restart
+//!
+//! On graceful shutdown the metadata of every open session (id, endpoint,
+//! client info, memory subscriptions and the last SSE event id) is written to
+//! `<state_dir>/sessions/<port>.json`. The next process on that port loads it,
+//! and a client that comes back with a previously issued `Mcp-Session-Id`
+//! within the resume window gets a freshly constructed server seeded with that
+//! metadata instead of a 401. Entries older than the window are dropped.
+//!
+//! The SSE event cache itself is not persisted: events sent after the saved
+//! last event id and before the restart are not replayed.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rmcp::model::ClientInfo;
+use rmcp::{Peer, RoleServer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::mcp::tools::memory::Subscriptions;
+use crate::utils::paths;
+use crate::{log_debug, log_important};
+
+/// Default time a client has to reconnect to its session after a restart
+pub const DEFAULT_RESUME_GRACE: Duration = Duration::from_secs(600);
+
+/// Sub-directory of the state dir holding one file per port
+const SESSIONS_DIR: &str = "sessions";
+
+/// Session metadata written at shutdown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSession {
+    pub session_id: String,
+    pub endpoint: String,
+    /// Initialize parameters the client sent, replayed against the new server
+    pub client: ClientInfo,
+    /// Subscribed memory resource URIs
+    #[serde(default)]
+    pub subscriptions: Vec<String>,
+    /// Id of the last event sent on the session's standalone SSE stream
+    #[serde(default)]
+    pub last_event_id: Option<String>,
+    pub saved_at: DateTime<Utc>,
+}
+
+impl SavedSession {
+    fn is_stale(&self, grace: Duration, now: DateTime<Utc>) -> bool {
+        let age = now.signed_duration_since(self.saved_at).to_std().unwrap_or_default();
+        age > grace
+    }
+}
+
+/// Live sessions of this process and the saved ones still waiting for their client
+#[derive(Clone)]
+pub struct SessionStore {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// `None` when resumption is disabled
+    file: Option<PathBuf>,
+    grace: Duration,
+    live: Mutex<HashMap<String, LiveSession>>,
+    saved: Mutex<HashMap<String, SavedSession>>,
+}
+
+struct LiveSession {
+    endpoint: String,
+    peer: Peer<RoleServer>,
+    subscriptions: Arc<Subscriptions>,
+    last_event_id: Option<String>,
+    /// Restored from a previous process
+    restored: bool,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl SessionStore {
+    /// Store persisting to `file`; a zero `grace` disables resumption
+    pub fn new(file: PathBuf, grace: Duration) -> Self {
+        let file = (!grace.is_zero()).then_some(file);
+        Self {
+            inner: Arc::new(Inner {
+                file,
+                grace,
+                live: Mutex::new(HashMap::new()),
+                saved: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Store that tracks sessions but never saves or restores them
+    pub fn disabled() -> Self {
+        Self::new(PathBuf::new(), Duration::ZERO)
+    }
+
+    /// Store for the server listening on `port`
+    pub fn for_port(port: u16, grace: Duration) -> Result<Self> {
+        let file = paths::state_subdir(SESSIONS_DIR)?.join(format!("{}.json", port));
+        Ok(Self::new(file, grace))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.file.is_some()
+    }
+
+    pub fn file(&self) -> Option<&Path> {
+        self.inner.file.as_deref()
+    }
+
+    /// Load the sessions saved by the previous process and delete the file
+    ///
+    /// Returns how many sessions may still be resumed; stale ones are dropped.
+    pub fn load(&self) -> Result<usize> {
+        let Some(file) = &self.inner.file else {
+            return Ok(0);
+        };
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        // Each saved session is resumed at most once
+        fs::remove_file(file)?;
+
+        let sessions: Vec<SavedSession> = serde_json::from_str(&content)?;
+        let now = Utc::now();
+        let total = sessions.len();
+        let mut saved = lock(&self.inner.saved);
+        saved.extend(
+            sessions
+                .into_iter()
+                .filter(|s| !s.is_stale(self.inner.grace, now))
+                .map(|s| (s.session_id.clone(), s)),
+        );
+        if saved.len() < total {
+            log_debug!("Dropped {} stale saved session(s)", total - saved.len());
+        }
+        Ok(saved.len())
+    }
+
+    /// Track an initialized session so it can be saved at shutdown
+    pub fn register(&self, session_id: &str, endpoint: &str, peer: Peer<RoleServer>, subscriptions: Arc<Subscriptions>) {
+        self.insert(session_id, endpoint, peer, subscriptions, None, false);
+    }
+
+    fn insert(
+        &self,
+        session_id: &str,
+        endpoint: &str,
+        peer: Peer<RoleServer>,
+        subscriptions: Arc<Subscriptions>,
+        last_event_id: Option<String>,
+        restored: bool,
+    ) {
+        let mut live = lock(&self.inner.live);
+        live.retain(|_, s| !s.peer.is_transport_closed());
+        live.insert(
+            session_id.to_string(),
+            LiveSession { endpoint: endpoint.to_string(), peer, subscriptions, last_event_id, restored },
+        );
+    }
+
+    /// Remember the id of an event sent on the session's standalone stream
+    pub fn record_event(&self, session_id: &str, event_id: &str) {
+        if let Some(session) = lock(&self.inner.live).get_mut(session_id) {
+            session.last_event_id = Some(event_id.to_string());
+        }
+    }
+
+    /// Last event id sent on the session's standalone stream
+    pub fn last_event_id(&self, session_id: &str) -> Option<String> {
+        lock(&self.inner.live).get(session_id).and_then(|s| s.last_event_id.clone())
+    }
+
+    /// Subscribed URIs of a live session, sorted
+    pub fn subscriptions(&self, session_id: &str) -> Option<Vec<String>> {
+        lock(&self.inner.live).get(session_id).map(|s| s.subscriptions.uris())
+    }
+
+    /// Whether the live session was restored from a previous process
+    pub fn is_restored(&self, session_id: &str) -> bool {
+        lock(&self.inner.live).get(session_id).is_some_and(|s| s.restored)
+    }
+
+    /// Claim the saved session of `endpoint` with this id, unless it is past the window
+    pub fn take_saved(&self, session_id: &str, endpoint: &str) -> Option<SavedSession> {
+        let mut saved = lock(&self.inner.saved);
+        let now = Utc::now();
+        saved.retain(|_, s| !s.is_stale(self.inner.grace, now));
+        if saved.get(session_id)?.endpoint != endpoint {
+            return None;
+        }
+        saved.remove(session_id)
+    }
+
+    /// Sessions that would be written by [`save`](Self::save)
+    ///
+    /// Saved sessions nobody reconnected to yet are carried over, so a second
+    /// restart within the window does not lose them.
+    pub fn snapshot(&self) -> Vec<SavedSession> {
+        let now = Utc::now();
+        let mut sessions: Vec<SavedSession> = lock(&self.inner.live)
+            .iter()
+            .filter(|(_, s)| !s.peer.is_transport_closed())
+            .filter_map(|(id, s)| {
+                Some(SavedSession {
+                    session_id: id.clone(),
+                    endpoint: s.endpoint.clone(),
+                    client: s.peer.peer_info()?.clone(),
+                    subscriptions: s.subscriptions.uris(),
+                    last_event_id: s.last_event_id.clone(),
+                    saved_at: now,
+                })
+            })
+            .collect();
+        sessions.extend(
+            lock(&self.inner.saved)
+                .values()
+                .filter(|s| !s.is_stale(self.inner.grace, now))
+                .cloned(),
+        );
+        sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+        sessions
+    }
+
+    /// Write the resumable sessions to the state file; returns how many were saved
+    pub fn save(&self) -> Result<usize> {
+        let Some(file) = &self.inner.file else {
+            return Ok(0);
+        };
+        let sessions = self.snapshot();
+        if sessions.is_empty() {
+            return Ok(0);
+        }
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write to a temp file first so the next process never reads a half-written file
+        let tmp_path = file.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(&sessions)?)?;
+        fs::rename(&tmp_path, file)?;
+        log_important!(info, "Saved {} session(s) for resumption to {}", sessions.len(), file.display());
+        Ok(sessions.len())
+    }
+}
+
+#[cfg(feature = "http-server")]
+pub use manager::ResumableSessionManager;
+
+#[cfg(feature = "http-server")]
+mod manager {
+    use futures::future::Either;
+    use futures::{Stream, StreamExt};
+    use rmcp::model::{ClientJsonRpcMessage, ServerJsonRpcMessage};
+    use rmcp::transport::common::server_side_http::{ServerSseMessage, SessionId};
+    use rmcp::transport::streamable_http_server::session::local::{
+        create_local_session, LocalSessionManager, LocalSessionManagerError, LocalSessionWorker,
+    };
+    use rmcp::transport::streamable_http_server::SessionManager;
+    use rmcp::transport::WorkerTransport;
+    use rmcp::ServiceExt;
+    use serde_json::json;
+    use std::sync::{Arc, Weak};
+    use std::time::Instant;
+
+    use super::{SavedSession, SessionStore};
+    use crate::mcp::http::reaper::{SessionActivity, SessionLimits};
+    use crate::mcp::http::OutboundBuffers;
+    use crate::mcp::DevkitServer;
+    use crate::{log_debug, log_important};
+
+    type ServerFactory = Arc<dyn Fn() -> DevkitServer + Send + Sync>;
+
+    /// [`LocalSessionManager`] that brings back sessions saved by a previous process
+    ///
+    /// An unknown session id that matches a saved session of this endpoint is
+    /// restored on first use: a new server is created, the client's initialize
+    /// request is replayed against it and its subscriptions are re-established.
+    /// Every SSE stream is queued under the process's [`OutboundBuffers`] limits.
+    pub struct ResumableSessionManager {
+        local: Arc<LocalSessionManager>,
+        store: SessionStore,
+        endpoint: String,
+        new_server: ServerFactory,
+        /// Serializes restores so concurrent requests restore a session once
+        restoring: tokio::sync::Mutex<()>,
+        /// Requests and open streams per session, for closing abandoned ones
+        activity: SessionActivity,
+    }
+
+    impl ResumableSessionManager {
+        pub fn new(
+            store: SessionStore,
+            endpoint: &str,
+            new_server: impl Fn() -> DevkitServer + Send + Sync + 'static,
+        ) -> Self {
+            Self {
+                local: Arc::new(LocalSessionManager::default()),
+                store,
+                endpoint: endpoint.to_string(),
+                new_server: Arc::new(new_server),
+                restoring: tokio::sync::Mutex::new(()),
+                activity: SessionActivity::default(),
+            }
+        }
+
+        /// Close sessions that stay idle or live longer than `limits` allow
+        ///
+        /// The sweep runs on its own task until the manager is dropped.
+        pub fn with_limits(self, limits: SessionLimits) -> Self {
+            if limits.is_enabled() {
+                let local = Arc::downgrade(&self.local);
+                tokio::spawn(reap(local, self.activity.clone(), limits, self.endpoint.clone()));
+            }
+            self
+        }
+
+        async fn restore(&self, saved: SavedSession) -> anyhow::Result<()> {
+            let id: SessionId = saved.session_id.as_str().into();
+            let (handle, worker) = create_local_session(id.clone(), self.local.session_config.clone());
+            self.local.sessions.write().await.insert(id.clone(), handle);
+            self.activity.opened(&id);
+
+            let server = (self.new_server)();
+            let subscriptions = server.subscriptions().clone();
+            let (peer_tx, peer_rx) = tokio::sync::oneshot::channel();
+            let local = self.local.clone();
+            let session_id = id.clone();
+            tokio::spawn(async move {
+                match server.serve(WorkerTransport::<LocalSessionWorker>::spawn(worker)).await {
+                    Ok(running) => {
+                        let _ = peer_tx.send(running.peer().clone());
+                        let _ = running.waiting().await;
+                    }
+                    Err(e) => log_important!(warn, "Failed to restore session {}: {}", session_id, e),
+                }
+                let _ = local.close_session(&session_id).await;
+            });
+
+            let initialize: ClientJsonRpcMessage = serde_json::from_value(json!({
+                "jsonrpc": "2.0",
+                "id": 0,
+                "method": "initialize",
+                "params": saved.client,
+            }))?;
+            if let ServerJsonRpcMessage::Error(error) = self.local.initialize_session(&id, initialize).await? {
+                anyhow::bail!("initialize rejected: {}", error.error.message);
+            }
+            let initialized: ClientJsonRpcMessage =
+                serde_json::from_value(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))?;
+            self.local.accept_message(&id, initialized).await?;
+
+            let peer = peer_rx.await?;
+            for uri in &saved.subscriptions {
+                if let Err(e) = subscriptions.subscribe(uri, peer.clone()) {
+                    log_important!(warn, "Session {}: cannot restore subscription {}: {}", id, uri, e);
+                }
+            }
+            self.store
+                .insert(&saved.session_id, &saved.endpoint, peer, subscriptions, saved.last_event_id, true);
+            Ok(())
+        }
+
+        /// Record the event ids of a standalone stream
+        fn track_events(
+            &self,
+            id: &SessionId,
+            stream: impl Stream<Item = ServerSseMessage> + Send + Sync + 'static,
+        ) -> impl Stream<Item = ServerSseMessage> + Send + Sync + 'static {
+            let store = self.store.clone();
+            let id = id.clone();
+            stream.inspect(move |message| {
+                if let Some(event_id) = &message.event_id {
+                    store.record_event(&id, event_id);
+                }
+            })
+        }
+
+        /// Queue a stream of `id` under the outbound limits; the session is closed if it goes over
+        ///
+        /// The session does not count as idle while the stream is open.
+        fn bounded(
+            &self,
+            id: &SessionId,
+            stream: impl Stream<Item = ServerSseMessage> + Send + 'static,
+        ) -> impl Stream<Item = ServerSseMessage> + Send + Sync + 'static {
+            let open = self.activity.stream(id);
+            let stream = stream.inspect(move |_| {
+                let _ = &open;
+            });
+            let local = self.local.clone();
+            let session_id = id.clone();
+            OutboundBuffers::global().guard(id, stream, move || {
+                tokio::spawn(async move {
+                    let _ = local.close_session(&session_id).await;
+                });
+            })
+        }
+    }
+
+    /// Close the sessions of one endpoint that went over `limits`, until its manager is gone
+    async fn reap(local: Weak<LocalSessionManager>, activity: SessionActivity, limits: SessionLimits, endpoint: String) {
+        loop {
+            tokio::time::sleep(limits.sweep_interval()).await;
+            let Some(local) = local.upgrade() else {
+                return;
+            };
+            for (id, reason) in activity.due(limits, Instant::now()) {
+                log_important!(info, "Closing session {} on endpoint '{}': {}", id, endpoint, reason);
+                activity.forget(&id);
+                if let Err(e) = local.close_session(&id.as_str().into()).await {
+                    log_debug!("Session {} was already gone: {}", id, e);
+                }
+            }
+        }
+    }
+
+    impl SessionManager for ResumableSessionManager {
+        type Error = LocalSessionManagerError;
+        type Transport = WorkerTransport<LocalSessionWorker>;
+
+        async fn create_session(&self) -> Result<(SessionId, Self::Transport), Self::Error> {
+            let (id, transport) = self.local.create_session().await?;
+            self.activity.opened(&id);
+            Ok((id, transport))
+        }
+
+        async fn initialize_session(
+            &self,
+            id: &SessionId,
+            message: ClientJsonRpcMessage,
+        ) -> Result<ServerJsonRpcMessage, Self::Error> {
+            self.activity.touch(id);
+            self.local.initialize_session(id, message).await
+        }
+
+        async fn has_session(&self, id: &SessionId) -> Result<bool, Self::Error> {
+            if self.local.has_session(id).await? {
+                return Ok(true);
+            }
+            if !self.store.is_enabled() {
+                return Ok(false);
+            }
+            let _restoring = self.restoring.lock().await;
+            if self.local.has_session(id).await? {
+                return Ok(true);
+            }
+            let Some(saved) = self.store.take_saved(id, &self.endpoint) else {
+                return Ok(false);
+            };
+            match self.restore(saved).await {
+                Ok(()) => {
+                    log_important!(info, "Resumed session {} on endpoint '{}'", id, self.endpoint);
+                    Ok(true)
+                }
+                Err(e) => {
+                    log_important!(warn, "Failed to resume session {}: {:#}", id, e);
+                    let _ = self.local.close_session(id).await;
+                    Ok(false)
+                }
+            }
+        }
+
+        async fn close_session(&self, id: &SessionId) -> Result<(), Self::Error> {
+            self.activity.forget(id);
+            self.local.close_session(id).await
+        }
+
+        async fn create_stream(
+            &self,
+            id: &SessionId,
+            message: ClientJsonRpcMessage,
+        ) -> Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static, Self::Error> {
+            let stream = self.local.create_stream(id, message).await?;
+            Ok(self.bounded(id, stream))
+        }
+
+        async fn accept_message(&self, id: &SessionId, message: ClientJsonRpcMessage) -> Result<(), Self::Error> {
+            self.activity.touch(id);
+            self.local.accept_message(id, message).await
+        }
+
+        async fn create_standalone_stream(
+            &self,
+            id: &SessionId,
+        ) -> Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static, Self::Error> {
+            let stream = self.local.create_standalone_stream(id).await?;
+            Ok(self.bounded(id, self.track_events(id, stream)))
+        }
+
+        async fn resume(
+            &self,
+            id: &SessionId,
+            last_event_id: String,
+        ) -> Result<impl Stream<Item = ServerSseMessage> + Send + Sync + 'static, Self::Error> {
+            let stream = match self.local.resume(id, last_event_id.clone()).await {
+                Ok(stream) => Either::Left(self.track_events(id, stream)),
+                // Event ids issued by the previous process are unknown to the restored session
+                Err(e) if self.store.is_restored(id) => {
+                    log_debug!("Session {}: cannot replay from event {} ({}), opening a new stream", id, last_event_id, e);
+                    let stream = self.local.create_standalone_stream(id).await?;
+                    Either::Right(self.track_events(id, stream))
+                }
+                Err(e) => return Err(e),
+            };
+            Ok(self.bounded(id, stream))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use rmcp::model::{ClientCapabilities, Implementation, ProtocolVersion};
+
+    /// Sessions file in a fresh directory, removed with the returned guard
+    fn temp_file() -> (TestDir, PathBuf) {
+        let dir = TestDir::new("sessions");
+        let file = dir.join("sessions.json");
+        (dir, file)
+    }
+
+    fn saved(session_id: &str, age: Duration) -> SavedSession {
+        SavedSession {
+            session_id: session_id.to_string(),
+            endpoint: "default".to_string(),
+            client: ClientInfo {
+                protocol_version: ProtocolVersion::LATEST,
+                capabilities: ClientCapabilities::default(),
+                client_info: Implementation {
+                    name: "resume-test".to_string(),
+                    title: None,
+                    version: "0.0.0".to_string(),
+                    icons: None,
+                    website_url: None,
+                },
+            },
+            subscriptions: vec!["devkit://memory/ns/a".to_string()],
+            last_event_id: Some("3".to_string()),
+            saved_at: Utc::now() - chrono::Duration::from_std(age).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_load_drops_stale_sessions() {
+        let (_dir, file) = temp_file();
+        let sessions = vec![saved("fresh", Duration::from_secs(5)), saved("stale", Duration::from_secs(120))];
+        fs::write(&file, serde_json::to_string(&sessions).unwrap()).unwrap();
+
+        let store = SessionStore::new(file.clone(), Duration::from_secs(60));
+        assert_eq!(store.load().unwrap(), 1);
+        assert!(!file.exists());
+        assert!(store.take_saved("stale", "default").is_none());
+        assert!(store.take_saved("fresh", "readonly").is_none());
+        assert_eq!(store.take_saved("fresh", "default"), Some(sessions[0].clone()));
+        // Claimed once
+        assert!(store.take_saved("fresh", "default").is_none());
+    }
+
+    #[test]
+    fn test_unclaimed_sessions_are_carried_over() {
+        let (_dir, file) = temp_file();
+        fs::write(&file, serde_json::to_string(&[saved("waiting", Duration::ZERO)]).unwrap()).unwrap();
+
+        let store = SessionStore::new(file.clone(), Duration::from_secs(60));
+        assert_eq!(store.load().unwrap(), 1);
+        assert_eq!(store.save().unwrap(), 1);
+        let again = SessionStore::new(file.clone(), Duration::from_secs(60));
+        assert_eq!(again.load().unwrap(), 1);
+        assert!(again.take_saved("waiting", "default").is_some());
+    }
+
+    #[test]
+    fn test_disabled_store() {
+        let store = SessionStore::disabled();
+        assert!(!store.is_enabled());
+        assert_eq!(store.load().unwrap(), 0);
+        assert_eq!(store.save().unwrap(), 0);
+    }
+
+    #[cfg(feature = "http-server")]
+    #[tokio::test]
+    async fn test_session_resumes_after_restart() {
+        use crate::mcp::http::keepalive_service;
+        use crate::mcp::server::DEFAULT_ENDPOINT;
+        use crate::mcp::DevkitServer;
+        use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
+        use serde_json::{json, Value};
+
+        const URI: &str = "devkit://memory/0123abcd/f00-ba4";
+
+        /// Serve /sse backed by `store`; returns the endpoint URL
+        async fn start(store: &SessionStore) -> String {
+            let server_store = store.clone();
+            let new_server = move || DevkitServer::new().with_popup(false).with_sessions(server_store.clone());
+            let manager = ResumableSessionManager::new(store.clone(), DEFAULT_ENDPOINT, new_server.clone());
+            let service = StreamableHttpService::new(
+                move || Ok::<_, std::io::Error>(new_server()),
+                Arc::new(manager),
+                StreamableHttpServerConfig::default(),
+            );
+            let app = axum::Router::new().route("/sse", keepalive_service(service, Default::default()));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/sse", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            url
+        }
+
+        async fn post(url: &str, session: Option<&str>, body: Value) -> reqwest::Response {
+            let mut request = reqwest::Client::new()
+                .post(url)
+                .header("Accept", "application/json, text/event-stream")
+                .json(&body);
+            if let Some(session) = session {
+                request = request.header("Mcp-Session-Id", session);
+            }
+            request.send().await.unwrap()
+        }
+
+        async fn rpc_body(response: reqwest::Response) -> Value {
+            let text = response.text().await.unwrap();
+            text.lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .find_map(|data| serde_json::from_str(data.trim()).ok())
+                .unwrap_or_else(|| serde_json::from_str(&text).unwrap())
+        }
+
+        let (_dir, file) = temp_file();
+        let before = SessionStore::new(file.clone(), Duration::from_secs(60));
+        let url = start(&before).await;
+        let init = post(&url, None, json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {
+                "protocolVersion": "2025-03-26",
+                "capabilities": {},
+                "clientInfo": { "name": "resume-test", "version": "0.0.0" }
+            }
+        }))
+        .await;
+        let session = init.headers()["mcp-session-id"].to_str().unwrap().to_string();
+        rpc_body(init).await;
+        post(&url, Some(&session), json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).await;
+        let subscribed = post(&url, Some(&session), json!({
+            "jsonrpc": "2.0", "id": 2, "method": "resources/subscribe", "params": { "uri": URI }
+        }))
+        .await;
+        assert!(rpc_body(subscribed).await.get("result").is_some());
+        assert_eq!(before.subscriptions(&session), Some(vec![URI.to_string()]));
+        assert_eq!(before.save().unwrap(), 1);
+
+        // A new process on the same state file
+        let after = SessionStore::new(file.clone(), Duration::from_secs(60));
+        assert_eq!(after.load().unwrap(), 1);
+        let url = start(&after).await;
+
+        let unknown = post(&url, Some("not-a-session"), json!({ "jsonrpc": "2.0", "id": 3, "method": "ping" })).await;
+        assert_eq!(unknown.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let ping = post(&url, Some(&session), json!({ "jsonrpc": "2.0", "id": 4, "method": "ping" })).await;
+        assert!(ping.status().is_success());
+        assert_eq!(rpc_body(ping).await["id"], 4);
+        assert!(after.is_restored(&session));
+        assert_eq!(after.subscriptions(&session), Some(vec![URI.to_string()]));
+        assert_eq!(after.snapshot()[0].client.client_info.name, "resume-test");
+    }
+}
@@ -0,0 +1,224 @@
+//! Human-readable status page served at `/`
+//!
+//! A single self-contained HTML document (no external assets) so a browser
+//! pointed at the server shows that it is up and how to connect to it.
+
+use axum::extract::State;
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{DrainState, HEALTH_PATH};
+use crate::mcp::DevkitServer;
+
+/// Path of the status page
+pub const STATUS_PATH: &str = "/";
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>devkit MCP server</title>
+<style>
+body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; color: #222; }
+h1 { font-size: 1.4rem; } h2 { font-size: 1.1rem; margin-top: 1.5rem; }
+table { border-collapse: collapse; } td { padding: .2rem 1rem .2rem 0; vertical-align: top; }
+code, pre { font-family: ui-monospace, monospace; background: #f3f3f3; border-radius: 4px; }
+pre { padding: .6rem; overflow-x: auto; }
+.status { color: #1a7f37; font-weight: 600; }
+</style>
+</head>
+<body>
+<h1>devkit MCP server <span class="status">{{status}}</span></h1>
+{{body}}
+</body>
+</html>
+"#;
+
+/// What the status page shows besides live session counts
+#[derive(Clone)]
+pub struct StatusPage {
+    /// Base URL clients should use, e.g. http://127.0.0.1:8808
+    pub url: String,
+    /// Transport name -> path, as published in the discovery file
+    pub transports: BTreeMap<String, String>,
+    /// Show only a stub when clients must authenticate
+    pub auth_required: bool,
+    pub started: Instant,
+}
+
+#[derive(Clone)]
+struct StatusState {
+    page: Arc<StatusPage>,
+    drain: DrainState,
+    factory: Arc<dyn Fn() -> DevkitServer + Send + Sync>,
+}
+
+/// Router serving `GET /`
+///
+/// `factory` builds a server of the default endpoint to list its enabled tools.
+pub fn status_router<F>(page: StatusPage, drain: DrainState, factory: F) -> Router
+where
+    F: Fn() -> DevkitServer + Send + Sync + 'static,
+{
+    let state = StatusState { page: Arc::new(page), drain, factory: Arc::new(factory) };
+    Router::new().route(STATUS_PATH, get(status)).with_state(state)
+}
+
+async fn status(State(state): State<StatusState>) -> Html<String> {
+    let tools: Vec<String> = (state.factory)()
+        .tool_definitions()
+        .into_iter()
+        .map(|tool| tool.name.into_owned())
+        .collect();
+    Html(render(&state.page, &state.drain, &tools))
+}
+
+fn render(page: &StatusPage, drain: &DrainState, tools: &[String]) -> String {
+    let status = if drain.is_draining() { "draining" } else { "running" };
+
+    // Nothing beyond "it is up" without credentials
+    if page.auth_required {
+        let body = "<p>The server is running. Authentication is required; \
+                    configure your MCP client with the access token.</p>";
+        return TEMPLATE
+            .replace("{{status}}", &format!("{}, auth required", status))
+            .replace("{{body}}", body);
+    }
+
+    let sessions = drain.session_counts();
+    let mut body = String::new();
+
+    body.push_str("<table>\n");
+    for (label, value) in [
+        ("Version", env!("CARGO_PKG_VERSION").to_string()),
+        ("Uptime", format_uptime(page.started.elapsed())),
+        ("URL", format!("<code>{}</code>", escape(&page.url))),
+        ("Active sessions", sessions.active.to_string()),
+        ("Tool calls in flight", drain.in_flight().to_string()),
+    ] {
+        body.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", label, value));
+    }
+    body.push_str("</table>\n");
+
+    body.push_str("<h2>Enabled tools</h2>\n");
+    if tools.is_empty() {
+        body.push_str("<p>None</p>\n");
+    } else {
+        let items: Vec<String> = tools.iter().map(|t| format!("<li><code>{}</code></li>", escape(t))).collect();
+        body.push_str(&format!("<ul>\n{}\n</ul>\n", items.join("\n")));
+    }
+
+    body.push_str("<h2>Endpoints</h2>\n");
+    for (name, path) in page.transports.iter().filter(|(name, _)| name.starts_with("streamable_http")) {
+        let endpoint = format!("{}{}", page.url.trim_end_matches('/'), path);
+        let label = name.strip_prefix("streamable_http:").unwrap_or("default");
+        let snippet = format!(
+            "{{\n  \"mcpServers\": {{\n    \"devkit\": {{\n      \"serverUrl\": \"{0}\"\n    }}\n  }}\n}}",
+            endpoint
+        );
+        body.push_str(&format!(
+            "<p><b>{}</b>: <code>{}</code></p>\n<pre>{}</pre>\n",
+            escape(label),
+            escape(&endpoint),
+            escape(&snippet)
+        ));
+    }
+    if let Some(path) = page.transports.get("mcp") {
+        let endpoint = format!("{}{}", page.url.trim_end_matches('/'), path);
+        body.push_str(&format!("<p><b>Streamable HTTP</b> (Claude Desktop, newer Windsurf): <code>{}</code></p>\n", escape(&endpoint)));
+    }
+    if let Some(path) = page.transports.get("websocket") {
+        let endpoint = format!("{}{}", page.url.trim_end_matches('/').replacen("http", "ws", 1), path);
+        body.push_str(&format!("<p><b>WebSocket</b>: <code>{}</code></p>\n", escape(&endpoint)));
+    }
+
+    if page.transports.contains_key("health") {
+        body.push_str(&format!("<p><a href=\"{0}\">{0}</a></p>\n", HEALTH_PATH));
+    }
+
+    TEMPLATE.replace("{{status}}", status).replace("{{body}}", &body)
+}
+
+fn format_uptime(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let (days, hours, minutes, seconds) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, seconds)
+    } else {
+        format!("{}m {}s", minutes, seconds)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(auth_required: bool) -> StatusPage {
+        StatusPage {
+            url: "http://127.0.0.1:8808".to_string(),
+            transports: BTreeMap::from([
+                ("streamable_http".to_string(), "/sse".to_string()),
+                ("streamable_http:readonly".to_string(), "/sse/readonly".to_string()),
+                ("mcp".to_string(), "/mcp".to_string()),
+                ("health".to_string(), HEALTH_PATH.to_string()),
+            ]),
+            auth_required,
+            started: Instant::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_page_renders_over_http() {
+        let app = status_router(page(false), DrainState::new(), || DevkitServer::new().with_popup(false));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let response = reqwest::get(format!("http://{}/", addr)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/html"));
+        let html = response.text().await.unwrap();
+        assert!(html.contains("http://127.0.0.1:8808/sse"));
+        assert!(html.contains("http://127.0.0.1:8808/sse/readonly"));
+        assert!(html.contains("<b>Streamable HTTP</b> (Claude Desktop, newer Windsurf): <code>http://127.0.0.1:8808/mcp</code>"));
+        assert!(html.contains(env!("CARGO_PKG_VERSION")));
+        assert!(html.contains("href=\"/health\""));
+    }
+
+    #[test]
+    fn test_lists_tool_names() {
+        let tools = vec!["store".to_string(), "index".to_string()];
+        let html = render(&page(false), &DrainState::new(), &tools);
+        assert!(html.contains("<code>store</code>"));
+        assert!(html.contains("<code>index</code>"));
+        assert!(html.contains("running"));
+    }
+
+    #[test]
+    fn test_auth_stub_hides_details() {
+        let html = render(&page(true), &DrainState::new(), &["store".to_string()]);
+        assert!(html.contains("auth required"));
+        assert!(!html.contains("/sse"));
+        assert!(!html.contains("store"));
+    }
+
+    #[test]
+    fn test_format_uptime() {
+        assert_eq!(format_uptime(Duration::from_secs(65)), "1m 5s");
+        assert_eq!(format_uptime(Duration::from_secs(3_725)), "1h 2m 5s");
+        assert_eq!(format_uptime(Duration::from_secs(90_061)), "1d 1h 1m");
+    }
+}
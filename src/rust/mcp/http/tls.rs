@@ -0,0 +1,195 @@
+//! HTTPS for `devkit-mcp-http`
+//!
+//! The certificate comes from PEM files (`--tls-cert`/`--tls-key`) or is generated
+//! self-signed on first use and kept in the state directory, so clients only have to
+//! trust it once. A new one is generated when the names it must cover change (another
+//! bind address or public URL). The SHA-256 fingerprint is logged at startup for pinning.
+
+use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
+use axum_server::Handle;
+use ring::digest::{digest, SHA256};
+use rustls::ServerConfig;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::options::TlsSource;
+use crate::utils::paths;
+
+/// State subdirectory of the generated certificates
+pub const SELF_SIGNED_DIR: &str = "tls";
+
+/// Loaded certificate, ready to serve
+pub struct TlsSetup {
+    pub config: Arc<ServerConfig>,
+    /// SHA-256 of the leaf certificate, colon-separated hex
+    pub fingerprint: String,
+    /// Certificate file; for a self-signed one, the file clients should trust
+    pub cert_file: PathBuf,
+    pub self_signed: bool,
+}
+
+impl TlsSetup {
+    /// Load the configured certificate; `names` are covered by a self-signed one
+    pub fn load(source: &TlsSource, names: &[String]) -> Result<Self, String> {
+        match source {
+            TlsSource::Files { cert, key } => {
+                let cert_pem = fs::read(cert).map_err(|e| format!("Cannot read TLS certificate {}: {}", cert.display(), e))?;
+                let key_pem = fs::read(key).map_err(|e| format!("Cannot read TLS key {}: {}", key.display(), e))?;
+                Self::from_pem(&cert_pem, &key_pem, cert.clone(), false)
+            }
+            TlsSource::SelfSigned => {
+                let dir = paths::state_subdir(SELF_SIGNED_DIR).map_err(|e| e.to_string())?;
+                Self::self_signed(&dir, names)
+            }
+        }
+    }
+
+    /// Self-signed certificate for `names` in `dir`, generated when missing or unreadable
+    pub fn self_signed(dir: &Path, names: &[String]) -> Result<Self, String> {
+        let id = hex::encode(&digest(&SHA256, names.join(",").as_bytes()).as_ref()[..6]);
+        let cert_file = dir.join(format!("self-signed-{}.crt", id));
+        let key_file = dir.join(format!("self-signed-{}.key", id));
+
+        if let (Ok(cert_pem), Ok(key_pem)) = (fs::read(&cert_file), fs::read(&key_file)) {
+            if let Ok(setup) = Self::from_pem(&cert_pem, &key_pem, cert_file.clone(), true) {
+                return Ok(setup);
+            }
+        }
+
+        let generated = rcgen::generate_simple_self_signed(names.to_vec())
+            .map_err(|e| format!("Cannot generate a self-signed certificate: {}", e))?;
+        let cert_pem = generated.cert.pem();
+        let key_pem = generated.key_pair.serialize_pem();
+        let saved = fs::create_dir_all(dir)
+            .and_then(|_| write_private(&key_file, key_pem.as_bytes()))
+            .and_then(|_| fs::write(&cert_file, &cert_pem));
+        if let Err(e) = saved {
+            // Still usable for this run, clients just have to trust a new one next time
+            crate::log_important!(warn, "Failed to save the self-signed certificate to {}: {}", dir.display(), e);
+        }
+        Self::from_pem(cert_pem.as_bytes(), key_pem.as_bytes(), cert_file, true)
+    }
+
+    fn from_pem(cert_pem: &[u8], key_pem: &[u8], cert_file: PathBuf, self_signed: bool) -> Result<Self, String> {
+        let certs = rustls_pemfile::certs(&mut &cert_pem[..])
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid TLS certificate {}: {}", cert_file.display(), e))?;
+        let Some(leaf) = certs.first() else {
+            return Err(format!("No certificate found in {}", cert_file.display()));
+        };
+        let fingerprint = fingerprint(leaf.as_ref());
+        let key = rustls_pemfile::private_key(&mut &key_pem[..])
+            .map_err(|e| format!("Invalid TLS key: {}", e))?
+            .ok_or_else(|| "No private key found in the TLS key file".to_string())?;
+
+        let mut config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(|e| e.to_string())?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| format!("TLS certificate and key cannot be used together: {}", e))?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(Self { config: Arc::new(config), fingerprint, cert_file, self_signed })
+    }
+}
+
+/// Serve `app` over HTTPS until `shutdown` completes and the open connections finish
+pub async fn serve_tls(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    config: Arc<ServerConfig>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> io::Result<()> {
+    let handle = Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown.await;
+        shutdown_handle.graceful_shutdown(None);
+    });
+    axum_server::from_tcp_rustls(listener.into_std()?, RustlsConfig::from_config(config))
+        .handle(handle)
+        .serve(app.into_make_service())
+        .await
+}
+
+/// Colon-separated uppercase hex, as browsers and `openssl x509 -fingerprint` show it
+fn fingerprint(der: &[u8]) -> String {
+    digest(&SHA256, der).as_ref().iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+/// Write a private key readable by the owner only
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?.write_all(contents)
+    }
+    #[cfg(not(unix))]
+    {
+        fs::write(path, contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use axum::routing::get;
+
+
+    #[tokio::test]
+    async fn test_self_signed_certificate_is_reused_and_served() {
+        let dir = TestDir::reserve("tls-self-signed");
+        let names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+        let first = TlsSetup::self_signed(&dir, &names).unwrap();
+        assert!(first.self_signed);
+        assert_eq!(first.fingerprint.len(), 32 * 3 - 1);
+        assert_eq!(TlsSetup::self_signed(&dir, &names).unwrap().fingerprint, first.fingerprint);
+        // Other names, another certificate
+        assert_ne!(TlsSetup::self_signed(&dir, &["localhost".to_string()]).unwrap().fingerprint, first.fingerprint);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let app = Router::new().route("/", get(|| async { "secure" }));
+        let server = tokio::spawn(serve_tls(listener, app, first.config, async move {
+            let _ = stopped.await;
+        }));
+
+        let client = reqwest::Client::builder().danger_accept_invalid_certs(true).build().unwrap();
+        let body = client.get(format!("https://{}/", addr)).send().await.unwrap().text().await.unwrap();
+        assert_eq!(body, "secure");
+        // Plain HTTP is not answered
+        assert!(reqwest::get(format!("http://{}/", addr)).await.is_err());
+
+        stop.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_certificate_files() {
+        let dir = TestDir::new("tls-files");
+        let generated = rcgen::generate_simple_self_signed(vec!["mcp.example.com".to_string()]).unwrap();
+        let other = rcgen::generate_simple_self_signed(vec!["mcp.example.com".to_string()]).unwrap();
+        let (cert, key, other_key) = (dir.join("cert.pem"), dir.join("key.pem"), dir.join("other.pem"));
+        fs::write(&cert, generated.cert.pem()).unwrap();
+        fs::write(&key, generated.key_pair.serialize_pem()).unwrap();
+        fs::write(&other_key, other.key_pair.serialize_pem()).unwrap();
+
+        let setup = TlsSetup::load(&TlsSource::Files { cert: cert.clone(), key: key.clone() }, &[]).unwrap();
+        assert!(!setup.self_signed);
+        assert_eq!(setup.cert_file, cert);
+
+        let missing = TlsSetup::load(&TlsSource::Files { cert: dir.join("missing.pem"), key: key.clone() }, &[]);
+        assert!(missing.err().unwrap().starts_with("Cannot read TLS certificate"));
+        let swapped = TlsSetup::load(&TlsSource::Files { cert: key.clone(), key: cert.clone() }, &[]);
+        assert!(swapped.err().unwrap().starts_with("No certificate found"));
+        let mismatched = TlsSetup::load(&TlsSource::Files { cert, key: other_key }, &[]);
+        assert!(mismatched.err().unwrap().starts_with("TLS certificate and key cannot be used together"));
+    }
+}
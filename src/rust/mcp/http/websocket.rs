@@ -0,0 +1,289 @@
+//! MCP over WebSocket
+//!
+//! Each connection to `/ws` is one MCP session: every JSON-RPC message travels
+//! as a text frame. Sessions come from the same [`DevkitServer`] factory as the
+//! streamable HTTP transport, so they are registered with the [`DrainState`]
+//! and end when the server shuts down.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures::channel::mpsc;
+use futures::{SinkExt, StreamExt};
+use rmcp::model::{ClientJsonRpcMessage, ServerJsonRpcMessage};
+use rmcp::ServiceExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::drain::SHUTTING_DOWN_MESSAGE;
+use crate::mcp::boundary::check_message;
+use super::DrainState;
+use crate::mcp::DevkitServer;
+use crate::{log_debug, log_important};
+
+/// Path of the WebSocket endpoint
+pub const WS_PATH: &str = "/ws";
+
+/// Interval between keepalive pings; a peer that misses two pongs is dropped
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+type ServerFactory = Arc<dyn Fn() -> DevkitServer + Send + Sync>;
+
+#[derive(Clone)]
+struct WsState {
+    factory: ServerFactory,
+    drain: DrainState,
+    shutdown: CancellationToken,
+    max_frame_bytes: usize,
+}
+
+/// Router serving `GET /ws`
+pub fn ws_router<F>(factory: F, drain: DrainState, shutdown: CancellationToken, max_frame_bytes: usize) -> Router
+where
+    F: Fn() -> DevkitServer + Send + Sync + 'static,
+{
+    let state = WsState {
+        factory: Arc::new(factory),
+        drain,
+        shutdown,
+        max_frame_bytes,
+    };
+    Router::new().route(WS_PATH, get(upgrade)).with_state(state)
+}
+
+async fn upgrade(State(state): State<WsState>, ws: WebSocketUpgrade) -> Response {
+    if state.drain.is_draining() {
+        return (StatusCode::SERVICE_UNAVAILABLE, SHUTTING_DOWN_MESSAGE).into_response();
+    }
+    ws.max_frame_size(state.max_frame_bytes)
+        .max_message_size(state.max_frame_bytes)
+        .on_upgrade(move |socket| serve_socket(socket, state))
+}
+
+/// Run one MCP session over an upgraded socket
+async fn serve_socket(socket: WebSocket, state: WsState) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+    let (out_tx, mut out_rx) = mpsc::unbounded::<ServerJsonRpcMessage>();
+    let (in_tx, in_rx) = mpsc::unbounded::<ClientJsonRpcMessage>();
+    // Errors for frames the service never sees
+    let (reply_tx, mut reply_rx) = mpsc::unbounded::<String>();
+    let alive = Arc::new(AtomicBool::new(true));
+
+    // Socket -> service; closing the channel ends the session
+    let reader_alive = alive.clone();
+    let reader = tokio::spawn(async move {
+        while let Some(Ok(frame)) = ws_rx.next().await {
+            match frame {
+                Message::Text(text) => match check_message(text.as_bytes()) {
+                    Ok(message) => {
+                        if in_tx.unbounded_send(message).is_err() {
+                            break;
+                        }
+                    }
+                    Err(Some(reply)) => {
+                        log_important!(warn, "Rejected invalid WebSocket message: {}", reply["error"]["message"]);
+                        let _ = reply_tx.unbounded_send(reply.to_string());
+                    }
+                    Err(None) => {}
+                },
+                Message::Pong(_) => reader_alive.store(true, Ordering::SeqCst),
+                Message::Close(_) => break,
+                // Pings are answered by the socket itself; binary frames are not part of the protocol
+                Message::Ping(_) | Message::Binary(_) => {}
+            }
+        }
+    });
+
+    // Service -> socket, plus keepalive pings
+    let writer_alive = alive.clone();
+    let writer = tokio::spawn(async move {
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        ping.tick().await;
+        loop {
+            tokio::select! {
+                message = out_rx.next() => {
+                    let Some(message) = message else { break };
+                    let Ok(text) = serde_json::to_string(&message) else { continue };
+                    if ws_tx.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                Some(reply) = reply_rx.next() => {
+                    if ws_tx.send(Message::Text(reply)).await.is_err() {
+                        return;
+                    }
+                }
+                _ = ping.tick() => {
+                    if !writer_alive.swap(false, Ordering::SeqCst) {
+                        log_debug!("WebSocket peer missed a pong, closing");
+                        break;
+                    }
+                    if ws_tx.send(Message::Ping(Vec::new())).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+        let _ = ws_tx.send(Message::Close(None)).await;
+    });
+
+    let server = (state.factory)();
+    match server.serve((out_tx, in_rx)).await {
+        Ok(service) => {
+            log_debug!("WebSocket session started");
+            let cancel = service.cancellation_token();
+            tokio::select! {
+                _ = service.waiting() => {}
+                _ = state.shutdown.cancelled() => cancel.cancel(),
+            }
+            log_debug!("WebSocket session ended");
+        }
+        Err(e) => log_important!(warn, "WebSocket session failed to initialize: {}", e),
+    }
+
+    reader.abort();
+    let _ = writer.await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::http::DEFAULT_WS_MAX_FRAME_BYTES;
+    use serde_json::{json, Value};
+    use tokio_tungstenite::tungstenite::Message as ClientMessage;
+
+    async fn start(max_frame_bytes: usize) -> (String, DrainState, CancellationToken) {
+        let drain = DrainState::new();
+        let token = CancellationToken::new();
+        let session_drain = drain.clone();
+        let app = ws_router(
+            move || DevkitServer::new().with_popup(false).with_drain(session_drain.clone()),
+            drain.clone(),
+            token.clone(),
+            max_frame_bytes,
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}{}", listener.local_addr().unwrap(), WS_PATH);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, drain, token)
+    }
+
+    async fn request<S>(socket: &mut S, message: Value) -> Value
+    where
+        S: futures::Sink<ClientMessage> + futures::Stream<Item = Result<ClientMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+        <S as futures::Sink<ClientMessage>>::Error: std::fmt::Debug,
+    {
+        socket.send(ClientMessage::Text(message.to_string())).await.unwrap();
+        loop {
+            match socket.next().await.unwrap().unwrap() {
+                ClientMessage::Text(text) => {
+                    let value: Value = serde_json::from_str(&text).unwrap();
+                    // Skip server-initiated notifications
+                    if value.get("id") == message.get("id") {
+                        return value;
+                    }
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initialize_and_tool_call_over_websocket() {
+        let (url, drain, token) = start(DEFAULT_WS_MAX_FRAME_BYTES).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+
+        let init = request(
+            &mut socket,
+            json!({
+                "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": { "name": "ws-test", "version": "0.0.0" }
+                }
+            }),
+        )
+        .await;
+        assert_eq!(init["result"]["serverInfo"]["version"], env!("CARGO_PKG_VERSION"));
+        socket
+            .send(ClientMessage::Text(json!({"jsonrpc": "2.0", "method": "notifications/initialized"}).to_string()))
+            .await
+            .unwrap();
+
+        let tools = request(&mut socket, json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"})).await;
+        assert!(tools["result"]["tools"].as_array().unwrap().iter().any(|t| t["name"] == "docs"));
+
+        // Offline and not cached: answered locally with the structured offline error
+        let call = request(
+            &mut socket,
+            json!({
+                "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                "params": { "name": "docs", "arguments": { "library": "ws-test/not-cached", "offline": true } }
+            }),
+        )
+        .await;
+        assert_eq!(call["result"]["isError"], true);
+        assert_eq!(call["result"]["structuredContent"]["error"]["kind"], "offline");
+
+        // Schema violations name the offending argument
+        let call = request(
+            &mut socket,
+            json!({
+                "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                "params": { "name": "docs", "arguments": { "library": "a/b", "page": "two" } }
+            }),
+        )
+        .await;
+        assert_eq!(call["error"]["code"], -32602);
+        assert_eq!(call["error"]["data"], json!({ "path": "page" }));
+
+        // Frames that are not JSON get a parse error and the session stays up
+        socket.send(ClientMessage::Text("{not json".to_string())).await.unwrap();
+        let reply = loop {
+            if let ClientMessage::Text(text) = socket.next().await.unwrap().unwrap() {
+                break serde_json::from_str::<Value>(&text).unwrap();
+            }
+        };
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], -32700);
+        let ping = request(&mut socket, json!({"jsonrpc": "2.0", "id": 5, "method": "ping"})).await;
+        assert!(ping.get("result").is_some());
+        assert_eq!(drain.session_counts().active, 1);
+
+        // Shutdown closes the session
+        token.cancel();
+        while let Some(Ok(frame)) = socket.next().await {
+            if matches!(frame, ClientMessage::Close(_)) {
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frame_closes_the_connection() {
+        let (url, _drain, _token) = start(1024).await;
+        let (mut socket, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+
+        let _ = socket.send(ClientMessage::Text("x".repeat(4096))).await;
+        let closed = loop {
+            match socket.next().await {
+                Some(Ok(ClientMessage::Close(_))) | Some(Err(_)) | None => break true,
+                Some(Ok(_)) => continue,
+            }
+        };
+        assert!(closed);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_rejected_while_draining() {
+        let (url, drain, _token) = start(DEFAULT_WS_MAX_FRAME_BYTES).await;
+        drain.start_draining();
+        assert!(tokio_tungstenite::connect_async(&url).await.is_err());
+    }
+}
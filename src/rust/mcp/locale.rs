@@ -0,0 +1,87 @@
+//! Output language of a session
+//!
+//! A client may declare its locale in `initialize` as
+//! `capabilities.experimental.locale.tag` (a BCP 47 tag such as `zh-CN`). It is
+//! the session default for localized output unless the config pins a
+//! `language`; a scope set around part of a call still takes precedence.
+
+use rmcp::model::ClientInfo;
+
+use crate::utils::locale::Locale;
+
+/// Key of the locale object in `capabilities.experimental`
+pub const LOCALE_KEY: &str = "locale";
+
+/// Language tag in the locale object
+pub const TAG_KEY: &str = "tag";
+
+/// Supported locale declared by the client, if any
+pub fn client_locale(client: Option<&ClientInfo>) -> Option<Locale> {
+    let tag = client?.capabilities.experimental.as_ref()?.get(LOCALE_KEY)?.get(TAG_KEY)?.as_str()?;
+    Locale::from_tag(tag)
+}
+
+/// Locale a session uses: the configured one, else the client's, else English
+pub fn session_locale(pinned: Option<Locale>, client: Option<&ClientInfo>) -> Locale {
+    pinned.or_else(|| client_locale(client)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::call::call_with_capabilities;
+    use crate::docs_client::quota::SessionUsage;
+    use crate::mcp::DevkitServer;
+    use crate::utils::locale;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use serde_json::{json, Value};
+
+    fn client(locale: Option<&str>) -> ClientInfo {
+        serde_json::from_value(json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": capabilities(locale),
+            "clientInfo": { "name": "locale-test", "version": "0.0.0" }
+        }))
+        .unwrap()
+    }
+
+    fn capabilities(locale: Option<&str>) -> Value {
+        match locale {
+            Some(tag) => json!({ "experimental": { "locale": { "tag": tag } } }),
+            None => json!({}),
+        }
+    }
+
+    #[test]
+    fn test_session_locale_precedence() {
+        assert_eq!(session_locale(None, Some(&client(Some("zh-CN")))), Locale::Zh);
+        assert_eq!(session_locale(None, Some(&client(Some("fr-FR")))), Locale::En);
+        assert_eq!(session_locale(None, None), Locale::En);
+        assert_eq!(session_locale(Some(Locale::En), Some(&client(Some("zh-CN")))), Locale::En);
+        assert_eq!(session_locale(Some(Locale::Zh), Some(&client(None))), Locale::Zh);
+    }
+
+    #[tokio::test]
+    async fn test_declared_locale_picks_language() {
+        let arguments = json!({ "operation": 42 }).as_object().cloned().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("7"));
+
+        for (declared, invalid, footer) in [
+            (Some("zh-CN"), "git_context 的参数无效", "_本会话已请求 1 次，距离限流约剩 7 次。_"),
+            (Some("en-US"), "Invalid arguments for git_context", "_This session: 1 request; ~7 remaining before rate limit._"),
+            (None, "Invalid arguments for git_context", "_This session: 1 request; ~7 remaining before rate limit._"),
+        ] {
+            let error = call_with_capabilities(DevkitServer::new(), capabilities(declared), "git_context", arguments.clone())
+                .await
+                .unwrap()
+                .unwrap_err();
+            assert!(error.message.starts_with(invalid), "{:?}: {}", declared, error.message);
+
+            let usage = SessionUsage::default();
+            usage.record(200, &headers);
+            let locale = session_locale(None, Some(&client(declared)));
+            assert_eq!(locale::scope(locale, async { usage.footer(1) }).await.as_deref(), Some(footer), "{:?}", declared);
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Machine-readable description of what this server offers
+//!
+//! Built from the tool definitions a session would get from `tools/list`, so
+//! disabled tools are left out, plus the limits the tools enforce, the
+//! configured concurrency limits, the error codes of [`super::boundary`] and an
+//! example call per tool. Served by `devkit manifest` and `GET /manifest` for
+//! people and scripts that put tool descriptions into prompts.
+
+use rmcp::ServerHandler;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use super::boundary::UPSTREAM_UNAVAILABLE;
+use super::concurrency::TOOL_BUSY;
+use super::sessions::{SESSION_CANCELLED, SESSION_RATE_LIMITED};
+use super::result_limit::{LIMITS_KEY, MAX_RESULT_SIZE_KEY};
+use super::DevkitServer;
+
+/// Version of the manifest layout; bumped when fields change meaning
+pub const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub manifest_version: u32,
+    pub server: ServerVersion,
+    pub tools: Vec<ToolManifest>,
+    pub error_codes: Vec<ErrorCodeEntry>,
+    /// Limits a client can declare in `initialize`, keyed by capability path
+    pub client_limits: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerVersion {
+    pub name: String,
+    pub version: String,
+    /// Cargo features compiled into this build
+    pub features: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolManifest {
+    pub name: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub input_schema: Value,
+    pub output_schema: Option<Value>,
+    pub read_only: Option<bool>,
+    pub destructive: Option<bool>,
+    /// Limits the tool enforces, e.g. `max_snippets: 1..=50`
+    pub limits: BTreeMap<String, Value>,
+    /// Configured concurrency limit, if any
+    pub concurrency: Option<Concurrency>,
+    pub example: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Concurrency {
+    pub max_concurrent: usize,
+    pub queue_timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorCodeEntry {
+    pub code: i32,
+    pub name: String,
+    pub meaning: String,
+}
+
+impl Manifest {
+    /// Manifest of what `server` offers its clients
+    pub fn build(server: &DevkitServer) -> Self {
+        let tools = server
+            .tool_definitions()
+            .into_iter()
+            .map(|tool| {
+                let name = tool.name.to_string();
+                let annotations = tool.annotations.as_ref();
+                ToolManifest {
+                    title: tool.title.clone().or_else(|| annotations.and_then(|a| a.title.clone())),
+                    description: tool.description.as_ref().map(|d| d.to_string()),
+                    input_schema: Value::Object((*tool.input_schema).clone()),
+                    output_schema: tool.output_schema.as_ref().map(|schema| Value::Object((**schema).clone())),
+                    read_only: annotations.and_then(|a| a.read_only_hint),
+                    destructive: annotations.and_then(|a| a.destructive_hint),
+                    limits: tool_limits(&name),
+                    concurrency: server.tool_limit(&name).map(|limit| Concurrency {
+                        max_concurrent: limit.max_concurrent,
+                        queue_timeout_ms: limit.queue_timeout.as_millis() as u64,
+                    }),
+                    example: example_call(&name),
+                    name,
+                }
+            })
+            .collect();
+
+        Self {
+            manifest_version: MANIFEST_VERSION,
+            server: ServerVersion {
+                name: server.get_info().server_info.name,
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                features: compiled_features(),
+            },
+            tools,
+            error_codes: error_codes(),
+            client_limits: BTreeMap::from([(
+                format!("capabilities.experimental.{}.{}", LIMITS_KEY, MAX_RESULT_SIZE_KEY),
+                "Largest serialized tool result in bytes; larger results are trimmed".to_string(),
+            )]),
+        }
+    }
+
+    /// Rendering for people reading it in a terminal or a wiki
+    pub fn to_markdown(&self) -> String {
+        let mut text = format!(
+            "# {} {} capabilities\n\nManifest version {}. Features: {}.\n",
+            self.server.name,
+            self.server.version,
+            self.manifest_version,
+            if self.server.features.is_empty() { "none".to_string() } else { self.server.features.join(", ") }
+        );
+
+        for tool in &self.tools {
+            text.push_str(&format!("\n## `{}`", tool.name));
+            if let Some(title) = &tool.title {
+                text.push_str(&format!(" — {}", title));
+            }
+            text.push_str("\n\n");
+            if let Some(description) = &tool.description {
+                text.push_str(&format!("{}\n\n", description));
+            }
+            let required: Vec<&str> = tool.input_schema["required"]
+                .as_array()
+                .map(|r| r.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            if let Some(properties) = tool.input_schema["properties"].as_object() {
+                text.push_str("| parameter | type | required | description |\n|---|---|---|---|\n");
+                for (name, schema) in properties {
+                    text.push_str(&format!(
+                        "| `{}` | {} | {} | {} |\n",
+                        name,
+                        schema["type"].as_str().unwrap_or("any"),
+                        if required.contains(&name.as_str()) { "yes" } else { "no" },
+                        schema["description"].as_str().unwrap_or_default().replace('|', "\\|").replace('\n', " ")
+                    ));
+                }
+                text.push('\n');
+            }
+            if !tool.limits.is_empty() || tool.concurrency.is_some() {
+                text.push_str("Limits:\n");
+                for (name, value) in &tool.limits {
+                    text.push_str(&format!("- {}: {}\n", name, value));
+                }
+                if let Some(concurrency) = tool.concurrency {
+                    text.push_str(&format!(
+                        "- concurrent calls: {} (queued up to {} ms)\n",
+                        concurrency.max_concurrent, concurrency.queue_timeout_ms
+                    ));
+                }
+                text.push('\n');
+            }
+            text.push_str(&format!("Example:\n\n```json\n{}\n```\n", serde_json::to_string_pretty(&tool.example).unwrap_or_default()));
+        }
+
+        text.push_str("\n## Error codes\n\n| code | name | meaning |\n|---|---|---|\n");
+        for entry in &self.error_codes {
+            text.push_str(&format!("| {} | {} | {} |\n", entry.code, entry.name, entry.meaning));
+        }
+        text.push_str("\n## Client limits\n\n");
+        for (key, meaning) in &self.client_limits {
+            text.push_str(&format!("- `{}`: {}\n", key, meaning));
+        }
+        text
+    }
+}
+
+fn compiled_features() -> Vec<String> {
+    [
+        ("gui", cfg!(feature = "gui")),
+        ("server", cfg!(feature = "server")),
+        ("http-server", cfg!(feature = "http-server")),
+        ("client", cfg!(feature = "client")),
+        ("docs", cfg!(feature = "docs")),
+        ("otel", cfg!(feature = "otel")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
+fn range<T: Serialize>(range: &std::ops::RangeInclusive<T>) -> Value {
+    json!({ "min": range.start(), "max": range.end() })
+}
+
+/// Limits enforced by a tool beyond what its schema says
+fn tool_limits(tool: &str) -> BTreeMap<String, Value> {
+    use super::tools::command::mcp::DEFAULT_TIMEOUT_SECS;
+    use super::tools::command::runner::{HEAD_BYTES, TAIL_BYTES};
+    use super::tools::git::mcp::LOG_LIMIT_RANGE;
+    use super::tools::git::repo::{MAX_DIFF_BYTES, MAX_STATUS_FILES};
+    use super::tools::project_docs::index::{MAX_FILES, MAX_FILE_BYTES};
+    use super::tools::project_docs::mcp::LIMIT_RANGE;
+
+    let limits: Vec<(&str, Value)> = match tool {
+        #[cfg(feature = "docs")]
+        "docs" => {
+            use super::tools::docs::mcp::{
+                DEFAULT_MAX_RESPONSE_BYTES, MAX_BATCH_LIBRARIES, MAX_BATCH_TOPICS, MAX_BYTES_RANGE, MAX_SNIPPETS_RANGE, MAX_SUGGESTED_TOPICS,
+            };
+            vec![
+                ("page", range(&(1..=10))),
+                ("libraries_max", json!(MAX_BATCH_LIBRARIES)),
+                ("topics_max", json!(MAX_BATCH_TOPICS)),
+                ("max_snippets", range(&MAX_SNIPPETS_RANGE)),
+                ("max_bytes", range(&MAX_BYTES_RANGE)),
+                ("max_bytes_default", json!(DEFAULT_MAX_RESPONSE_BYTES)),
+                ("merged_snippets_default", json!(super::tools::docs::merge::DEFAULT_MERGED_SNIPPETS)),
+                ("suggested_topics_max", json!(MAX_SUGGESTED_TOPICS)),
+            ]
+        }
+        #[cfg(feature = "docs")]
+        "docs_search" => vec![("limit", range(&super::tools::docs::search::LIMIT_RANGE))],
+        #[cfg(feature = "docs")]
+        "docs_compare" => {
+            use super::tools::docs::compare::{DEFAULT_COMPARE_SNIPPETS, LIBRARIES_RANGE};
+            vec![
+                ("libraries", range(&LIBRARIES_RANGE)),
+                ("max_snippets", range(&super::tools::docs::mcp::MAX_SNIPPETS_RANGE)),
+                ("max_snippets_default", json!(DEFAULT_COMPARE_SNIPPETS)),
+            ]
+        }
+        "project_docs" => vec![
+            ("limit", range(&LIMIT_RANGE)),
+            ("indexed_files_max", json!(MAX_FILES)),
+            ("file_bytes_max", json!(MAX_FILE_BYTES)),
+        ],
+        "git_context" => vec![
+            ("log_limit", range(&LOG_LIMIT_RANGE)),
+            ("status_files_max", json!(MAX_STATUS_FILES)),
+            ("diff_bytes_max", json!(MAX_DIFF_BYTES)),
+        ],
+        "run_command" => vec![
+            ("timeout_secs_default", json!(DEFAULT_TIMEOUT_SECS)),
+            ("output_head_bytes", json!(HEAD_BYTES)),
+            ("output_tail_bytes", json!(TAIL_BYTES)),
+        ],
+        _ => Vec::new(),
+    };
+    limits.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+}
+
+/// A `tools/call` request for `tool`
+fn example_call(tool: &str) -> Value {
+    let arguments = match tool {
+        "store" => json!({ "action": "store", "project_path": "/path/to/project", "content": "Use tabs for indentation", "category": "rule" }),
+        "index" => json!({ "project_root_path": "/path/to/project", "query": "where are HTTP routes registered" }),
+        "project_docs" => json!({ "project_root_path": "/path/to/project", "query": "deployment", "limit": 5 }),
+        "run_command" => json!({ "project_root_path": "/path/to/project", "command": "cargo test" }),
+        "git_context" => json!({ "project_root_path": "/path/to/project", "operation": "diff", "staged": true }),
+        "docs" => json!({ "library": "vercel/next.js", "topic": "routing", "max_snippets": 5 }),
+        "docs_search" => json!({ "query": "redis", "language": "python", "limit": 5 }),
+        "docs_compare" => json!({ "libraries": ["facebook/react", "vuejs/docs"], "topic": "routing" }),
+        "cache" | "cache_sync" => json!({ "message": "Apply the migration?", "choices": ["Yes", "No"] }),
+        "cache_get" => json!({ "task_id": "<task_id from cache>" }),
+        _ => json!({}),
+    };
+    json!({ "method": "tools/call", "params": { "name": tool, "arguments": arguments } })
+}
+
+/// Codes a client can receive, see [`super::boundary`]
+fn error_codes() -> Vec<ErrorCodeEntry> {
+    let entry = |code: i32, name: &str, meaning: &str| ErrorCodeEntry { code, name: name.to_string(), meaning: meaning.to_string() };
+    vec![
+        entry(-32700, "parse_error", "Body or line is not JSON; data has line and column"),
+        entry(-32600, "invalid_request", "JSON but not a JSON-RPC message, or an unknown tool"),
+        entry(-32602, "invalid_params", "Tool arguments violate the schema; data.path names the field"),
+        entry(-32603, "internal_error", "The tool failed or panicked; data may carry request_id and tool"),
+        entry(UPSTREAM_UNAVAILABLE.0, "upstream_unavailable", "Upstream timed out or was unreachable; retryable"),
+        entry(TOOL_BUSY.0, "tool_busy", "Tool, session or process at its concurrency limit; retryable, data has tool, limit and scope for session or process limits"),
+        entry(SESSION_RATE_LIMITED.0, "session_rate_limited", "Session over its calls per minute; retryable after data.retry_after_ms"),
+        entry(SESSION_CANCELLED.0, "session_cancelled", "Session cancelled by an administrator; reconnect to continue"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_lists_every_tool_and_round_trips() {
+        let server = DevkitServer::new();
+        let manifest = Manifest::build(&server);
+
+        let names: Vec<String> = server.tool_definitions().into_iter().map(|tool| tool.name.into_owned()).collect();
+        let listed: Vec<&str> = manifest.tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(listed, names);
+        for tool in &manifest.tools {
+            assert_eq!(tool.input_schema["type"], "object", "{}", tool.name);
+            assert_eq!(tool.example["params"]["name"], tool.name.as_str());
+        }
+        if let Some(docs) = manifest.tools.iter().find(|tool| tool.name == "docs") {
+            assert_eq!(docs.limits["page"], json!({ "min": 1, "max": 10 }));
+        }
+        assert!(manifest.error_codes.iter().any(|entry| entry.code == TOOL_BUSY.0));
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(serde_json::from_str::<Manifest>(&json).unwrap(), manifest);
+
+        let markdown = manifest.to_markdown();
+        for name in &names {
+            assert!(markdown.contains(&format!("## `{}`", name)), "{}", name);
+        }
+        assert!(markdown.contains("| -32002 | tool_busy |"));
+    }
+
+    #[test]
+    fn test_disabled_tools_are_left_out() {
+        let endpoint = crate::config::McpEndpointConfig {
+            name: "narrow".to_string(),
+            path: "/sse/narrow".to_string(),
+            tools: std::collections::HashMap::from([("git_context".to_string(), false)]),
+            memory_read_only: false,
+        };
+        let manifest = Manifest::build(&DevkitServer::new().with_endpoint(&endpoint));
+        assert!(!manifest.tools.iter().any(|tool| tool.name == "git_context"));
+    }
+}
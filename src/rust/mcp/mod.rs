@@ -1,12 +1,28 @@
+#[cfg(feature = "gui")]
 pub mod commands;
+pub mod bootstrap;
+pub mod boundary;
+pub mod concurrency;
+pub mod dry_run;
 pub mod pantry;
+pub mod pipe;
 pub mod history;
+pub mod http;
+pub mod locale;
+pub mod manifest;
+pub mod progress;
+pub mod result_limit;
 pub mod server;
+pub mod sessions;
+#[cfg(test)]
+mod snapshots;
+pub mod telemetry;
 pub mod tools;
 pub mod types;
 pub mod handlers;
 pub mod utils;
 
+#[cfg(feature = "gui")]
 pub use commands::*;
 pub use pantry::*;
 pub use history::*;
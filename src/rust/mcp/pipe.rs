@@ -0,0 +1,134 @@
+//! Windows named pipe transport
+//!
+//! `devkit-mcp --pipe [name]` serves MCP on `\\.\pipe\<name>` (default
+//! [`DEFAULT_PIPE_NAME`]) instead of stdio, for IDEs that cannot reliably spawn
+//! the server as a subprocess or reach localhost TCP. Every client that opens
+//! the pipe gets its own session; messages are newline-delimited JSON-RPC, as
+//! on stdio. Remote clients are rejected, only processes on this machine can
+//! connect.
+
+/// Pipe name used when `--pipe` is given without one
+pub const DEFAULT_PIPE_NAME: &str = "devkit-mcp";
+
+/// Namespace every local pipe name lives in
+const PIPE_PREFIX: &str = r"\\.\pipe\";
+
+/// Full pipe path for `name`, which may already be one
+pub fn pipe_path(name: &str) -> String {
+    if name.starts_with(PIPE_PREFIX) {
+        name.to_string()
+    } else {
+        format!("{}{}", PIPE_PREFIX, name)
+    }
+}
+
+/// Pipe name requested on the command line: `--pipe`, `--pipe <name>` or `--pipe=<name>`
+pub fn pipe_arg(args: &[String]) -> Option<String> {
+    let position = args.iter().position(|arg| arg == "--pipe" || arg.starts_with("--pipe="))?;
+    let name = match args[position].strip_prefix("--pipe=") {
+        Some(name) => Some(name.to_string()),
+        None => args.get(position + 1).filter(|next| !next.starts_with("--")).cloned(),
+    };
+    Some(name.filter(|name| !name.is_empty()).unwrap_or_else(|| DEFAULT_PIPE_NAME.to_string()))
+}
+
+/// Serve MCP on the named pipe until Ctrl+C
+#[cfg(windows)]
+pub async fn run_pipe_server(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use rmcp::ServiceExt;
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    use super::boundary::checked_transport;
+    use super::DevkitServer;
+    use crate::log_important;
+
+    let path = pipe_path(name);
+    // Fails when another process already serves this pipe
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&path)
+        .map_err(|e| format!("Cannot create named pipe {}: {}", path, e))?;
+    log_important!(info, "MCP server listening on named pipe {}", path);
+
+    loop {
+        tokio::select! {
+            connected = server.connect() => connected?,
+            _ = tokio::signal::ctrl_c() => {
+                log_important!(info, "Ctrl+C received, closing named pipe {}", path);
+                return Ok(());
+            }
+        }
+        let client = server;
+        // The next instance exists before this session starts, so a second client never finds the pipe missing
+        server = ServerOptions::new().create(&path)?;
+
+        tokio::spawn(async move {
+            let (read, write) = tokio::io::split(client);
+            match DevkitServer::new().serve(checked_transport(read, write)).await {
+                Ok(service) => {
+                    if let Err(e) = service.waiting().await {
+                        log_important!(warn, "Named pipe session ended with an error: {}", e);
+                    }
+                }
+                Err(e) => log_important!(warn, "Named pipe session failed to start: {}", e),
+            }
+        });
+    }
+}
+
+/// Named pipes only exist on Windows
+#[cfg(not(windows))]
+pub async fn run_pipe_server(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err(format!("Named pipe {} requested, but named pipes are only available on Windows; use stdio or devkit-mcp-http", pipe_path(name)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_pipe_arg() {
+        assert_eq!(pipe_arg(&args(&["devkit-mcp"])), None);
+        assert_eq!(pipe_arg(&args(&["devkit-mcp", "--pipe"])).as_deref(), Some(DEFAULT_PIPE_NAME));
+        assert_eq!(pipe_arg(&args(&["devkit-mcp", "--pipe", "--strict-config"])).as_deref(), Some(DEFAULT_PIPE_NAME));
+        assert_eq!(pipe_arg(&args(&["devkit-mcp", "--pipe", "ide"])).as_deref(), Some("ide"));
+        assert_eq!(pipe_arg(&args(&["devkit-mcp", "--pipe=ide"])).as_deref(), Some("ide"));
+        assert_eq!(pipe_arg(&args(&["devkit-mcp", "--pipe="])).as_deref(), Some(DEFAULT_PIPE_NAME));
+    }
+
+    #[test]
+    fn test_pipe_path() {
+        assert_eq!(pipe_path("devkit-mcp"), r"\\.\pipe\devkit-mcp");
+        assert_eq!(pipe_path(r"\\.\pipe\custom"), r"\\.\pipe\custom");
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn test_serves_initialize_over_pipe() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::windows::named_pipe::ClientOptions;
+
+        let name = format!("devkit-mcp-test-{}", std::process::id());
+        tokio::spawn({
+            let name = name.clone();
+            async move { run_pipe_server(&name).await.unwrap() }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let client = ClientOptions::new().open(pipe_path(&name)).unwrap();
+        let (read, mut write) = tokio::io::split(client);
+        let initialize = serde_json::json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": { "protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": { "name": "test", "version": "1" } }
+        });
+        write.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+        let line = BufReader::new(read).lines().next_line().await.unwrap().unwrap();
+        let reply: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(reply["id"], 1);
+        assert!(reply["result"]["serverInfo"].is_object());
+    }
+}
@@ -0,0 +1,114 @@
+//! Progress notifications of a tool call
+//!
+//! When a `tools/call` request carries a `progressToken` in `_meta`, the server
+//! runs the call inside [`scope`] and tools can [`report`] how far they got;
+//! each report is sent as `notifications/progress`. Tools that assemble a large
+//! answer put partial results in the message, e.g. the docs tool sends every
+//! page of a `max_pages` lookup as soon as it arrives, so clients can start
+//! rendering while later pages download. Without a token, or outside a call,
+//! reporting does nothing.
+
+use rmcp::model::{Meta, ProgressNotificationParam, ProgressToken};
+use rmcp::service::{Peer, RoleServer};
+use std::future::Future;
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+use crate::log_debug;
+
+tokio::task_local! {
+    static REPORTER: Reporter;
+}
+
+/// Where the progress of the current call goes
+#[derive(Clone)]
+pub struct Reporter {
+    token: ProgressToken,
+    sink: Sink,
+}
+
+#[derive(Clone)]
+enum Sink {
+    Peer(Peer<RoleServer>),
+    #[cfg(test)]
+    Collect(Arc<Mutex<Vec<ProgressNotificationParam>>>),
+}
+
+impl Reporter {
+    /// Reporter for a request whose `_meta` asks for progress
+    pub fn for_request(meta: &Meta, peer: &Peer<RoleServer>) -> Option<Self> {
+        let token = meta.get_progress_token()?;
+        Some(Self { token, sink: Sink::Peer(peer.clone()) })
+    }
+
+    /// Reporter that keeps the notifications in memory
+    #[cfg(test)]
+    pub fn collecting(token: &str) -> (Self, Arc<Mutex<Vec<ProgressNotificationParam>>>) {
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let token = ProgressToken(rmcp::model::NumberOrString::String(token.into()));
+        (Self { token, sink: Sink::Collect(sent.clone()) }, sent)
+    }
+}
+
+/// Run `future` reporting to `reporter`; `None` leaves reporting off
+pub async fn scope<F: Future>(reporter: Option<Reporter>, future: F) -> F::Output {
+    match reporter {
+        Some(reporter) => REPORTER.scope(reporter, future).await,
+        None => future.await,
+    }
+}
+
+/// Whether the client of the current call wants progress, so partial results
+/// are worth rendering
+pub fn is_active() -> bool {
+    REPORTER.try_with(|_| ()).is_ok()
+}
+
+/// Report `progress` of `total` (when known) with `message`
+///
+/// A notification that cannot be sent is logged and otherwise ignored: the
+/// call itself still succeeds.
+pub async fn report(progress: u32, total: Option<u32>, message: impl Into<String>) {
+    let Ok(reporter) = REPORTER.try_with(Reporter::clone) else {
+        return;
+    };
+    let notification = ProgressNotificationParam {
+        progress_token: reporter.token,
+        progress: f64::from(progress),
+        total: total.map(f64::from),
+        message: Some(message.into()),
+    };
+    match reporter.sink {
+        Sink::Peer(peer) => {
+            if let Err(e) = peer.notify_progress(notification).await {
+                log_debug!("Progress notification not sent: {}", e);
+            }
+        }
+        #[cfg(test)]
+        Sink::Collect(sent) => sent.lock().unwrap().push(notification),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reports_only_inside_a_scope() {
+        report(1, None, "nobody listens").await;
+        assert!(!is_active());
+
+        let (reporter, sent) = Reporter::collecting("t1");
+        scope(Some(reporter), async {
+            assert!(is_active());
+            report(1, Some(3), "page 1").await;
+            report(2, Some(3), "page 2").await;
+        })
+        .await;
+        assert!(!scope(None, async { is_active() }).await);
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!((sent[1].progress, sent[1].total, sent[1].message.as_deref()), (2.0, Some(3.0), Some("page 2")));
+    }
+}
@@ -0,0 +1,348 @@
+//! Keep tool results within the size a client says it can take
+//!
+//! A client may declare `capabilities.experimental.limits.maxToolResultSize`
+//! (bytes of the serialized `CallToolResult`) in `initialize`. Results above
+//! it are cut down before they are sent instead of being truncated blindly on
+//! the client side. Tools that render to a byte budget read theirs from
+//! [`reply_budget`] while the call runs, so they leave out whole snippets
+//! rather than have their reply cut. Whatever is still too large is trimmed:
+//! structured content is dropped (the text carries the same information), or
+//! shortened if the tool declares an output schema, then text is trimmed at a
+//! line boundary and a notice saying so is appended. Clients that declare
+//! nothing get every result unchanged.
+
+use rmcp::model::{CallToolResult, ClientInfo, Content, RawContent};
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Key of the limits object in `capabilities.experimental`
+pub const LIMITS_KEY: &str = "limits";
+
+/// Largest serialized tool result the client accepts, in bytes
+pub const MAX_RESULT_SIZE_KEY: &str = "maxToolResultSize";
+
+/// Results trimmed since the process started
+static TRUNCATED_RESULTS: AtomicU64 = AtomicU64::new(0);
+
+tokio::task_local! {
+    static CLIENT_LIMIT: usize;
+}
+
+/// A result that had to be trimmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncation {
+    /// Serialized size before trimming
+    pub original_bytes: usize,
+    /// Serialized size sent to the client
+    pub sent_bytes: usize,
+    pub limit: usize,
+}
+
+/// Result size limit declared by the client, if any
+pub fn client_result_limit(client: Option<&ClientInfo>) -> Option<usize> {
+    client?
+        .capabilities
+        .experimental
+        .as_ref()?
+        .get(LIMITS_KEY)?
+        .get(MAX_RESULT_SIZE_KEY)?
+        .as_u64()
+        .filter(|limit| *limit > 0)
+        .map(|limit| limit as usize)
+}
+
+/// Run `future` with the result size limit of the calling client, if it declared one
+pub async fn scope<F: Future>(limit: Option<usize>, future: F) -> F::Output {
+    match limit {
+        Some(limit) => CLIENT_LIMIT.scope(limit, future).await,
+        None => future.await,
+    }
+}
+
+/// Bytes of reply text a tool rendering to a budget should stay within for
+/// the calling client; `None` when it declared no limit
+///
+/// Two fifths of the limit: structured content repeats most of the text, and
+/// the rest is left for JSON escapes and notes.
+pub fn reply_budget() -> Option<usize> {
+    CLIENT_LIMIT.try_with(|limit| limit / 5 * 2).ok()
+}
+
+/// Number of results trimmed to fit a client limit
+pub fn truncated_results() -> u64 {
+    TRUNCATED_RESULTS.load(Ordering::Relaxed)
+}
+
+/// Trim `result` to at most `limit` serialized bytes
+///
+/// With `keep_structured` (the tool declares an output schema, so its results
+/// must carry structured content) the structured content is shortened to at
+/// most half the limit instead of dropped. The truncation notice is always
+/// kept, so a limit smaller than the notice itself yields just the notice.
+pub fn fit_to_limit(mut result: CallToolResult, limit: usize, keep_structured: bool) -> (CallToolResult, Option<Truncation>) {
+    let original_bytes = serialized_len(&result);
+    if original_bytes <= limit {
+        return (result, None);
+    }
+
+    match result.structured_content.as_mut() {
+        Some(structured) if keep_structured => trim_value(structured, limit / 2),
+        _ => result.structured_content = None,
+    }
+    if serialized_len(&result) > limit {
+        let notice = Content::text(format!(
+            "\n---\n**Result truncated** to fit this client's limit of {} bytes (full result: {} bytes). \
+             Narrow the request to see the rest.",
+            limit, original_bytes
+        ));
+        let contents = std::mem::take(&mut result.content);
+        result.content.push(notice);
+        let mut budget = limit.saturating_sub(serialized_len(&result));
+        let mut kept = Vec::new();
+        for content in contents {
+            // Each further array element also costs a comma
+            let cost = json_len(&content) + 1;
+            if cost <= budget {
+                budget -= cost;
+                kept.push(content);
+                continue;
+            }
+            if let RawContent::Text(text) = &content.raw {
+                let trimmed = trim_to_json_len(&text.text, budget.saturating_sub(json_len(&Content::text("")) + 1));
+                if !trimmed.is_empty() {
+                    kept.push(Content::text(trimmed));
+                }
+            }
+            break;
+        }
+        kept.append(&mut result.content);
+        result.content = kept;
+    }
+
+    TRUNCATED_RESULTS.fetch_add(1, Ordering::Relaxed);
+    let sent_bytes = serialized_len(&result);
+    (result, Some(Truncation { original_bytes, sent_bytes, limit }))
+}
+
+fn serialized_len(result: &CallToolResult) -> usize {
+    serde_json::to_vec(result).map(|bytes| bytes.len()).unwrap_or(usize::MAX)
+}
+
+fn json_len(content: &Content) -> usize {
+    serde_json::to_vec(content).map(|bytes| bytes.len()).unwrap_or(usize::MAX)
+}
+
+fn value_len(value: &Value) -> usize {
+    serde_json::to_vec(value).map(|bytes| bytes.len()).unwrap_or(usize::MAX)
+}
+
+/// Shorten `value` until its JSON form fits in `max` bytes, keeping its shape
+/// so it still matches the schema it was built for: trailing array elements
+/// go first, then the longest strings are cut
+fn trim_value(value: &mut Value, max: usize) {
+    loop {
+        let len = value_len(value);
+        if len <= max || !shrink(value, len - max) {
+            return;
+        }
+    }
+}
+
+/// Remove up to `excess` bytes from the largest part of `value` that can
+/// shrink; false when nothing can
+fn shrink(value: &mut Value, excess: usize) -> bool {
+    match value {
+        Value::Array(items) if items.len() > 1 => {
+            items.pop();
+            true
+        }
+        Value::Array(items) => items.first_mut().is_some_and(|item| shrink(item, excess)),
+        Value::Object(fields) => {
+            let mut values: Vec<&mut Value> = fields.values_mut().collect();
+            values.sort_by_key(|value| Reverse(value_len(value)));
+            values.into_iter().any(|value| shrink(value, excess))
+        }
+        Value::String(text) if !text.is_empty() => {
+            let keep = crate::utils::text::prefix_bytes(text, text.len().saturating_sub(excess)).len();
+            text.truncate(keep);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Longest prefix of `text` ending at a line break whose JSON string form
+/// (escapes included, quotes excluded) fits in `max` bytes
+fn trim_to_json_len(text: &str, max: usize) -> String {
+    let escaped_len = |s: &str| serde_json::to_string(s).map(|json| json.len() - 2).unwrap_or(usize::MAX);
+    let mut end = max.min(text.len());
+    loop {
+        let cut = crate::utils::text::prefix_bytes(text, end);
+        end = cut.len();
+        let cut = match cut.rfind('\n') {
+            Some(line_end) if end < text.len() && line_end > 0 => &cut[..line_end],
+            _ => cut,
+        };
+        if escaped_len(cut) <= max || cut.is_empty() {
+            return cut.to_string();
+        }
+        end = cut.len() * 3 / 4;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::call::call_with_capabilities;
+    use crate::mcp::tools::git::repo::tests::{commit, temp_repo};
+    use crate::mcp::DevkitServer;
+    use serde_json::json;
+
+    fn result(text: &str) -> CallToolResult {
+        CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(json!({ "text": text })),
+        }
+    }
+
+    fn text_of(result: &CallToolResult) -> String {
+        result.content.iter().filter_map(|c| c.as_text()).map(|t| t.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_client_result_limit() {
+        let client = |capabilities: serde_json::Value| -> ClientInfo {
+            serde_json::from_value(json!({
+                "protocolVersion": "2025-03-26",
+                "capabilities": capabilities,
+                "clientInfo": { "name": "limits-test", "version": "0.0.0" }
+            }))
+            .unwrap()
+        };
+        assert_eq!(client_result_limit(None), None);
+        assert_eq!(client_result_limit(Some(&client(json!({})))), None);
+        assert_eq!(
+            client_result_limit(Some(&client(json!({ "experimental": { "limits": { "maxToolResultSize": 4096 } } })))),
+            Some(4096)
+        );
+        assert_eq!(
+            client_result_limit(Some(&client(json!({ "experimental": { "limits": { "maxToolResultSize": 0 } } })))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fit_to_limit() {
+        let small = result("short");
+        let (unchanged, truncation) = fit_to_limit(small.clone(), 10_000, false);
+        assert_eq!(unchanged, small);
+        assert!(truncation.is_none());
+
+        let text: String = (0..400).map(|i| format!("line {} with \"quotes\"\n", i)).collect();
+        let (trimmed, truncation) = fit_to_limit(result(&text), 2048, false);
+        let truncation = truncation.unwrap();
+        assert!(truncation.sent_bytes <= 2048, "{:?}", truncation);
+        assert!(truncation.original_bytes > 2048);
+        assert!(trimmed.structured_content.is_none());
+        let shown = text_of(&trimmed);
+        assert!(shown.starts_with("line 0 with"));
+        assert!(shown.contains("Result truncated"));
+        // Cut at a line boundary
+        assert!(shown.split("\n---\n").next().unwrap().lines().all(|line| line.ends_with("\"quotes\"")));
+
+        // Dropping the structured duplicate is enough here
+        let text = "x".repeat(600);
+        let (trimmed, truncation) = fit_to_limit(result(&text), 1000, false);
+        assert!(truncation.is_some());
+        assert_eq!(text_of(&trimmed), text);
+    }
+
+    #[test]
+    fn test_structured_content_is_shortened_for_output_schemas() {
+        let snippets: Vec<_> = (0..40).map(|i| json!({ "title": format!("Snippet {}", i), "content": "x".repeat(200) })).collect();
+        let text = "y".repeat(10_000);
+        let result = CallToolResult {
+            content: vec![Content::text(&text)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(json!({ "library": "lib", "total_snippets": 40, "snippets": snippets })),
+        };
+        let (trimmed, truncation) = fit_to_limit(result, 4096, true);
+        assert!(truncation.unwrap().sent_bytes <= 4096);
+        let structured = trimmed.structured_content.clone().unwrap();
+        // Same shape, fewer snippets, leading ones kept
+        assert_eq!(structured["library"], "lib");
+        let kept = structured["snippets"].as_array().unwrap();
+        assert!(!kept.is_empty() && kept.len() < 40);
+        assert_eq!(kept[0]["title"], "Snippet 0");
+        assert!(text_of(&trimmed).contains("Result truncated"));
+
+        // A single oversized string is cut, not removed
+        let mut value = json!({ "snippets": [{ "content": "z".repeat(5000) }] });
+        trim_value(&mut value, 1000);
+        assert!(value_len(&value) <= 1000);
+        assert!(!value["snippets"][0]["content"].as_str().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_declared_limit_trims_tool_result() {
+        let dir = temp_repo();
+        commit(&dir, "big.txt", "start\n", "first");
+        let body: String = (0..2000).map(|i| format!("changed line {}\n", i)).collect();
+        std::fs::write(dir.join("big.txt"), body).unwrap();
+        let arguments = json!({ "project_root_path": dir.to_string_lossy(), "operation": "diff" })
+            .as_object()
+            .cloned()
+            .unwrap();
+
+        let limited = json!({ "experimental": { "limits": { "maxToolResultSize": 4096 } } });
+        let before = truncated_results();
+        let trimmed = call_with_capabilities(DevkitServer::new(), limited, "git_context", arguments.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(serde_json::to_vec(&trimmed).unwrap().len() <= 4096);
+        assert!(text_of(&trimmed).contains("Result truncated"));
+        assert!(truncated_results() > before);
+
+        // No declared limit, no change
+        let full = call_with_capabilities(DevkitServer::new(), json!({}), "git_context", arguments).await.unwrap().unwrap();
+        assert!(full.structured_content.is_some());
+        assert!(!text_of(&full).contains("Result truncated"));
+    }
+
+    #[cfg(feature = "docs")]
+    #[tokio::test]
+    async fn test_declared_limit_renders_docs_to_budget() {
+        use crate::docs_client::mock::{MockResponse, MockServer};
+        use crate::mcp::tools::docs::{DOCS_API_URL_ENV, DOCS_API_URL_LOCK};
+
+        let snippets: Vec<_> = (0..30)
+            .map(|i| {
+                let content: Vec<String> = (0..300).map(|j| format!("term{}x{}", i, j)).collect();
+                json!({ "title": format!("Section {}", i), "content": content.join(" ") })
+            })
+            .collect();
+        let body = json!({ "snippets": snippets }).to_string();
+        let server = MockServer::start(move |_| MockResponse::json(200, &body)).await;
+        let _guard = DOCS_API_URL_LOCK.lock().await;
+        std::env::set_var(DOCS_API_URL_ENV, server.base_url());
+
+        let limited = json!({ "experimental": { "limits": { "maxToolResultSize": 16_384 } } });
+        let arguments = json!({ "library": "result-limit/budget" }).as_object().cloned().unwrap();
+        let result = call_with_capabilities(DevkitServer::new(), limited, "docs", arguments).await.unwrap().unwrap();
+        std::env::remove_var(DOCS_API_URL_ENV);
+
+        assert!(serde_json::to_vec(&result).unwrap().len() <= 16_384);
+        // Rendered to a budget: whole snippets left out and listed, no generic trim
+        let text = text_of(&result);
+        assert!(!text.contains("Result truncated"), "{}", text);
+        let structured = result.structured_content.unwrap();
+        assert!(!structured["snippets"].as_array().unwrap().is_empty());
+        assert!(!structured["omitted_by_max_bytes"].as_array().unwrap().is_empty());
+    }
+}
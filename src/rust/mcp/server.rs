@@ -2,21 +2,71 @@ use anyhow::Result;
 use rmcp::{
     ServerHandler, ServiceExt, RoleServer,
     model::{ErrorData as McpError},
-    transport::stdio,
-    service::RequestContext,
+    service::{NotificationContext, RequestContext},
 };
 use rmcp::model::*;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::Instrument;
 
-use super::tools::{InteractionTool, MemoryTool, AcemcpTool, DocsTool};
+use super::tools::{InteractionTool, MemoryTool, AcemcpTool, ProjectDocsTool, RunCommandTool, GitContextTool, ListSessionsTool, ReloadConfigTool};
+use super::tools::command::{Approver, NoApprover, PopupApprover, RunCommandRequest};
+use super::tools::git::GitContextRequest;
+use super::tools::memory::{resources, MemoryManager, Subscriptions};
+use super::tools::sessions::ListSessionsRequest;
+#[cfg(feature = "docs")]
+use super::tools::{DocsCompareTool, DocsSearchTool, DocsStatsTool, DocsStatusTool, DocsTool};
+use super::boundary::{checked_transport, parse_arguments, run_tool_call};
+use super::concurrency::{self, CallLimiter, ToolLimit, ToolLimiter};
+use super::dry_run;
+use super::http::drain::{DrainState, SHUTTING_DOWN_MESSAGE};
+#[cfg(feature = "http-server")]
+use super::http::resume::SessionStore;
+use super::locale::session_locale;
+use super::progress::{self, Reporter};
+use super::result_limit;
+use super::sessions::{self, ClientDetails, ClientSession, SessionManager};
+use super::telemetry;
+use super::tools::project_docs::ProjectDocsRequest;
 use super::types::{CacheRequest, StoreRequest};
-use crate::mcp::tools::docs::types::DocsRequest;
-use crate::config::load_standalone_config;
+use super::utils::ToolError;
+#[cfg(feature = "docs")]
+use crate::mcp::tools::docs::types::{DocsCompareRequest, DocsSearchRequest, DocsStatsRequest, DocsToolRequest};
+use crate::config::{live, McpEndpointConfig};
+use crate::utils::correlation;
+use crate::utils::locale::{self, Locale};
+use crate::utils::{summarize_for_log, SummaryLimits};
 use crate::{log_important, log_debug};
 
+/// Label of the unnamed endpoint (/sse, /ws and stdio)
+pub const DEFAULT_ENDPOINT: &str = "default";
+
+/// Size caps for tool arguments/results in debug logs
+static LOG_SUMMARY_LIMITS: once_cell::sync::Lazy<SummaryLimits> =
+    once_cell::sync::Lazy::new(SummaryLimits::from_env);
+
 #[derive(Clone)]
 pub struct DevkitServer {
-    enabled_tools: HashMap<String, bool>,
+    /// Interactive popup tools (cache, cache_sync, cache_get) offered to clients
+    popup_enabled: bool,
+    /// Shutdown state shared with the HTTP transport
+    drain: Option<DrainState>,
+    /// Named HTTP endpoint this server belongs to, `None` for the default one
+    endpoint: Option<String>,
+    /// Per-endpoint tool switches, taking precedence over the config file
+    tool_overrides: HashMap<String, bool>,
+    /// Memory tool only answers recall on this endpoint
+    memory_read_only: bool,
+    /// Memory resources this session subscribed to
+    subscriptions: Arc<Subscriptions>,
+    /// Output language pinned by the config, overriding the client's locale
+    language: Option<Locale>,
+    /// Client, call counts and rate limit of this session
+    client: Arc<ClientSession>,
+    /// Session registry saved at shutdown so clients can resume after a restart
+    #[cfg(feature = "http-server")]
+    sessions: Option<SessionStore>,
 }
 
 impl Default for DevkitServer {
@@ -27,68 +77,445 @@ impl Default for DevkitServer {
 
 impl DevkitServer {
     pub fn new() -> Self {
-        // Load config, use defaults on failure
-        let enabled_tools = match load_standalone_config() {
-            Ok(config) => config.mcp_config.tools,
-            Err(e) => {
-                log_important!(warn, "Failed to load config, using defaults: {}", e);
-                crate::config::default_mcp_tools()
+        // Tool switches and limits are read from the live config on every call
+        let config = live::current();
+        let language = config.mcp_config.language.as_deref().and_then(|tag| {
+            let locale = Locale::from_tag(tag);
+            if locale.is_none() {
+                log_important!(warn, "Unsupported language {:?} in config, following the client", tag);
             }
-        };
+            locale
+        });
+
+        Self {
+            popup_enabled: true,
+            drain: None,
+            endpoint: None,
+            tool_overrides: HashMap::new(),
+            memory_read_only: false,
+            subscriptions: Arc::new(Subscriptions::default()),
+            language,
+            client: Arc::new(ClientSession::default()),
+            #[cfg(feature = "http-server")]
+            sessions: None,
+        }
+    }
+
+    /// Enable or disable the interactive popup tools (disabled in container mode)
+    pub fn with_popup(mut self, enabled: bool) -> Self {
+        self.popup_enabled = enabled;
+        self
+    }
+
+    /// Track tool calls and sessions for graceful shutdown
+    pub fn with_drain(mut self, drain: DrainState) -> Self {
+        self.drain = Some(drain);
+        self
+    }
+
+    /// Register streamable HTTP sessions for resumption after a restart
+    #[cfg(feature = "http-server")]
+    pub fn with_sessions(mut self, sessions: SessionStore) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Serve a named endpoint with its own tool switches
+    pub fn with_endpoint(mut self, endpoint: &McpEndpointConfig) -> Self {
+        self.endpoint = Some(endpoint.name.clone());
+        self.tool_overrides = endpoint.tools.clone();
+        self.memory_read_only = endpoint.memory_read_only;
+        self
+    }
 
-        Self { enabled_tools }
+    /// Endpoint label used in session listings and metrics
+    pub fn endpoint_label(&self) -> &str {
+        self.endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT)
     }
 
-    /// Check if tool is enabled - reads latest config
+    /// Configured concurrency limit of a tool, `None` when unlimited
+    pub fn tool_limit(&self, tool: &str) -> Option<ToolLimit> {
+        live::current().mcp_config.tool_limits.get(concurrency::limit_key(tool)).and_then(ToolLimit::from_config)
+    }
+
+    /// Memory resources this session subscribed to
+    pub fn subscriptions(&self) -> &Arc<Subscriptions> {
+        &self.subscriptions
+    }
+
+    /// Popup tools are offered unless disabled for the process or this endpoint
+    fn popup_available(&self) -> bool {
+        self.popup_enabled && self.tool_overrides.get("cache").copied().unwrap_or(true)
+    }
+
+    /// Check if tool is enabled - endpoint overrides first, then the config file
+    ///
+    /// Without a running watcher the file is read on every call, so switches
+    /// toggled in the GUI or an editor still apply before `reload_config`.
     fn is_tool_enabled(&self, tool_name: &str) -> bool {
-        // Re-read config each time to get latest state
-        match load_standalone_config() {
-            Ok(config) => {
-                let enabled = config.mcp_config.tools.get(tool_name).copied().unwrap_or(true);
-                log_debug!("Tool {} status: {}", tool_name, enabled);
-                enabled
+        if let Some(enabled) = self.tool_overrides.get(tool_name) {
+            return *enabled;
+        }
+        // Tools missing from older configs fall back to their default switch
+        let enabled = live::latest().mcp_config.tools.get(tool_name).copied()
+            .or_else(|| crate::config::default_mcp_tools().get(tool_name).copied())
+            .unwrap_or(true);
+        log_debug!("Tool {} status: {}", tool_name, enabled);
+        enabled
+    }
+
+    /// Route a tool call to its implementation
+    async fn dispatch_tool(&self, request: CallToolRequestParam) -> Result<CallToolResult, ToolError> {
+        if matches!(request.name.as_ref(), "cache" | "cache_sync" | "cache_get") && !self.popup_available() {
+            return Err(McpError::internal_error(
+                "Interaction tool is disabled".to_string(),
+                None
+            ).into());
+        }
+
+        match request.name.as_ref() {
+            "cache" => {
+                let cache_request: CacheRequest = parse_arguments(&request.name, request.arguments)?;
+
+                // Use async version that returns immediately
+                Ok(InteractionTool::prompt_start(cache_request).await?)
+            }
+            "cache_sync" => {
+                let cache_request: CacheRequest = parse_arguments(&request.name, request.arguments)?;
+
+                Ok(InteractionTool::prompt_sync(cache_request).await?)
+            }
+            "cache_get" => {
+                let arguments_value = request.arguments
+                    .map(serde_json::Value::Object)
+                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+
+                let task_id = arguments_value.get("task_id")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| McpError::invalid_params("task_id is required".to_string(), Some(serde_json::json!({ "path": "task_id" }))))?;
+
+                Ok(InteractionTool::cache_get(task_id).await?)
+            }
+            "store" => {
+                // Check if store tool is enabled
+                if !self.is_tool_enabled("store") {
+                    return Err(McpError::internal_error(
+                        "Memory tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let store_request: StoreRequest = parse_arguments(&request.name, request.arguments)?;
+
+                // A dry run changes nothing, so it is allowed on read-only endpoints
+                if self.memory_read_only && !dry_run::is_active() && matches!(store_request.action.as_str(), "store" | "记忆" | "update" | "delete" | "sync" | "archive" | "restore") {
+                    return Err(McpError::invalid_params(
+                        format!("Memory is read-only on the {} endpoint; only recall, list and review_stale are allowed", self.endpoint_label()),
+                        None,
+                    ).into());
+                }
+
+                MemoryTool::store(store_request).await
+            }
+            "index" => {
+                if !self.is_tool_enabled("index") {
+                    return Err(McpError::internal_error(
+                        "Search tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let acemcp_request: crate::mcp::tools::acemcp::types::AcemcpRequest = parse_arguments(&request.name, request.arguments)?;
+
+                Ok(AcemcpTool::search_context(acemcp_request).await?)
+            }
+            "project_docs" => {
+                if !self.is_tool_enabled("project_docs") {
+                    return Err(McpError::internal_error(
+                        "Project docs tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let project_docs_request: ProjectDocsRequest = parse_arguments(&request.name, request.arguments)?;
+
+                ProjectDocsTool::search(project_docs_request).await
+            }
+            "run_command" => {
+                if !self.is_tool_enabled("run_command") {
+                    return Err(McpError::internal_error(
+                        "Run command tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let run_request: RunCommandRequest = parse_arguments(&request.name, request.arguments)?;
+
+                // Approval needs the popup; without it gated commands are refused
+                let approver: Arc<dyn Approver> = if cfg!(feature = "gui") && self.popup_available() {
+                    Arc::new(PopupApprover)
+                } else {
+                    Arc::new(NoApprover)
+                };
+                RunCommandTool::run(run_request, approver).await
+            }
+            "git_context" => {
+                if !self.is_tool_enabled("git_context") {
+                    return Err(McpError::internal_error(
+                        "Git context tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let git_request: GitContextRequest = parse_arguments(&request.name, request.arguments)?;
+
+                GitContextTool::query(git_request).await
+            }
+            "list_sessions" => {
+                if !self.is_tool_enabled("list_sessions") {
+                    return Err(McpError::internal_error(
+                        "List sessions tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let sessions_request: ListSessionsRequest = parse_arguments(&request.name, request.arguments)?;
+
+                ListSessionsTool::run(sessions_request, &self.client, live::current().mcp_config.session_rate_limit)
+            }
+            "reload_config" => {
+                if !self.is_tool_enabled("reload_config") {
+                    return Err(McpError::internal_error(
+                        "Reload config tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                ReloadConfigTool::run()
+            }
+            #[cfg(feature = "docs")]
+            "docs" => {
+                if !self.is_tool_enabled("docs") {
+                    return Err(McpError::internal_error(
+                        "Docs tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let docs_request = DocsToolRequest::from_arguments(&request.name, request.arguments)?;
+
+                DocsTool::query_docs(docs_request).await
+            }
+            #[cfg(feature = "docs")]
+            "docs_search" => {
+                if !self.is_tool_enabled("docs_search") {
+                    return Err(McpError::internal_error(
+                        "Docs search tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let search_request: DocsSearchRequest = parse_arguments(&request.name, request.arguments)?;
+
+                DocsSearchTool::search(search_request).await
+            }
+            #[cfg(feature = "docs")]
+            "docs_compare" => {
+                if !self.is_tool_enabled("docs_compare") {
+                    return Err(McpError::internal_error(
+                        "Docs compare tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let compare_request: DocsCompareRequest = parse_arguments(&request.name, request.arguments)?;
+
+                DocsCompareTool::compare(compare_request).await
+            }
+            #[cfg(feature = "docs")]
+            "devkit_stats" => {
+                if !self.is_tool_enabled("devkit_stats") {
+                    return Err(McpError::internal_error(
+                        "Stats tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                let stats_request: DocsStatsRequest = parse_arguments(&request.name, request.arguments)?;
+
+                Ok(DocsStatsTool::stats(stats_request).await)
             }
-            Err(e) => {
-                log_important!(warn, "Config read failed, using cached: {}", e);
-                // Use cached config on read failure
-                self.enabled_tools.get(tool_name).copied().unwrap_or(true)
+            #[cfg(feature = "docs")]
+            "docs_status" => {
+                if !self.is_tool_enabled("docs_status") {
+                    return Err(McpError::internal_error(
+                        "Docs status tool is disabled".to_string(),
+                        None
+                    ).into());
+                }
+
+                Ok(DocsStatusTool::status().await)
+            }
+            _ => {
+                Err(McpError::invalid_request(
+                    format!("Unknown tool: {}", request.name),
+                    None
+                ).into())
             }
         }
     }
-}
 
-impl ServerHandler for DevkitServer {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: Implementation {
-                name: "build-cache".to_string(),
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                icons: None,
-                title: None,
-                website_url: None,
-            },
-            instructions: Some("Build cache and code indexing utilities".to_string()),
+    /// Tools exposed via tools/list, limited to what this build compiled in
+    pub fn tool_definitions(&self) -> Vec<Tool> {
+        let mut tools = Vec::new();
+
+        // Interaction tools need the popup UI
+        #[cfg(feature = "gui")]
+        if self.popup_available() {
+            tools.extend(Self::interaction_tool_definitions());
         }
-    }
 
-    async fn initialize(
-        &self,
-        _request: InitializeRequestParam,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<ServerInfo, McpError> {
-        Ok(self.get_info())
+        // Memory tool - only when enabled
+        if self.is_tool_enabled("store") {
+            let ji_schema = serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "description": "Operation type: store (add entry), recall (get project info), update (replace the content of entry `id`), delete (remove entry `id` or entries `ids`), sync (merge with the store file at `sync_path`), list (entries with their IDs, see `filter`), review_stale (entries not recalled in `stale_days` days), archive (exclude `id`/`ids` from recall but keep them), restore (undo archive)"
+                    },
+                    "project_path": {
+                        "type": "string",
+                        "description": "Project path (required)"
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "Entry content (required for store and update operations)"
+                    },
+                    "category": {
+                        "type": "string",
+                        "description": "Category: rule, preference, pattern, context"
+                    },
+                    "id": {
+                        "type": "string",
+                        "description": "Entry ID as returned by store (required for update and delete operations)"
+                    },
+                    "sync_path": {
+                        "type": "string",
+                        "description": "Sync file or folder shared between machines (required for sync operation)"
+                    },
+                    "ids": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Entry IDs for bulk delete, archive and restore"
+                    },
+                    "stale_days": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "description": "review_stale: days without recall after which an entry is listed (default 60)"
+                    },
+                    "filter": {
+                        "type": "string",
+                        "enum": ["active", "archived", "all"],
+                        "description": "list: active (default), archived or all"
+                    }
+                },
+                "required": ["action", "project_path"]
+            });
+
+            if let serde_json::Value::Object(ref schema_map) = ji_schema {
+                tools.push(Tool {
+                    name: Cow::Borrowed("store"),
+                    description: Some(Cow::Borrowed("Key-value storage for build configuration and project metadata")),
+                    input_schema: Arc::new(schema_map.clone()),
+                    annotations: Some(ToolAnnotations {
+                        title: Some("Config Store".to_string()),
+                        read_only_hint: Some(false),     // Can modify (store data)
+                        destructive_hint: Some(true),    // delete removes entries
+                        idempotent_hint: Some(true),     // Storing same data is idempotent
+                        open_world_hint: Some(false),    // Closed domain, local storage
+                    }),
+                    icons: None,
+                    meta: None,
+                    output_schema: None,
+                    title: Some("Config Store".to_string()),
+                });
+            }
+        }
+
+        // Index tool - only when enabled
+        if self.is_tool_enabled("index") {
+            tools.push(AcemcpTool::get_tool_definition());
+        }
+
+        // Project docs tool - only when enabled
+        if self.is_tool_enabled("project_docs") {
+            tools.push(ProjectDocsTool::get_tool_definition());
+        }
+
+        // Run command tool - off by default, only when enabled
+        if self.is_tool_enabled("run_command") {
+            tools.push(RunCommandTool::get_tool_definition());
+        }
+
+        // Git context tool - only when enabled
+        if self.is_tool_enabled("git_context") {
+            tools.push(GitContextTool::get_tool_definition());
+        }
+
+        // List sessions tool - off by default, only when enabled
+        if self.is_tool_enabled("list_sessions") {
+            tools.push(ListSessionsTool::get_tool_definition());
+        }
+
+        // Reload config tool - admin only, off by default
+        if self.is_tool_enabled("reload_config") {
+            tools.push(ReloadConfigTool::get_tool_definition());
+        }
+
+        // Docs tool - only when compiled in and enabled
+        #[cfg(feature = "docs")]
+        if self.is_tool_enabled("docs") {
+            tools.push(DocsTool::get_tool_definition());
+        }
+
+        // Docs search tool - only when compiled in and enabled
+        #[cfg(feature = "docs")]
+        if self.is_tool_enabled("docs_search") {
+            tools.push(DocsSearchTool::get_tool_definition());
+        }
+
+        // Docs compare tool - only when compiled in and enabled
+        #[cfg(feature = "docs")]
+        if self.is_tool_enabled("docs_compare") {
+            tools.push(DocsCompareTool::get_tool_definition());
+        }
+
+        // Stats tool - only when compiled in and enabled
+        #[cfg(feature = "docs")]
+        if self.is_tool_enabled("devkit_stats") {
+            tools.push(DocsStatsTool::get_tool_definition());
+        }
+
+        // Docs status tool - only when compiled in and enabled
+        #[cfg(feature = "docs")]
+        if self.is_tool_enabled("docs_status") {
+            tools.push(DocsStatusTool::get_tool_definition());
+        }
+
+        dry_run::advertise(&mut tools);
+        tools
     }
 
-    async fn list_tools(
-        &self,
-        _request: Option<PaginatedRequestParam>,
-        _context: RequestContext<RoleServer>,
-    ) -> Result<ListToolsResult, McpError> {
-        use std::sync::Arc;
-        use std::borrow::Cow;
+    /// Whether `tool` declares an output schema, so its results must carry structured content
+    fn declares_output_schema(&self, tool: &str) -> bool {
+        self.tool_definitions().iter().any(|definition| definition.name == tool && definition.output_schema.is_some())
+    }
 
+    /// Cache tools backed by the interactive popup
+    #[cfg(feature = "gui")]
+    fn interaction_tool_definitions() -> Vec<Tool> {
         let mut tools = Vec::new();
 
         // Cache tool - stores data for async retrieval
@@ -107,6 +534,10 @@ impl ServerHandler for DevkitServer {
                 "format": {
                     "type": "boolean",
                     "description": "Enable structured format, defaults to true"
+                },
+                "diff": {
+                    "type": "string",
+                    "description": "Optional unified diff to review, shown above the choices"
                 }
             },
             "required": ["message"]
@@ -182,60 +613,195 @@ impl ServerHandler for DevkitServer {
             });
         }
 
-        // Memory tool - only when enabled
-        if self.is_tool_enabled("store") {
-            let ji_schema = serde_json::json!({
-                "type": "object",
-                "properties": {
-                    "action": {
-                        "type": "string",
-                        "description": "Operation type: store (add entry), recall (get project info)"
-                    },
-                    "project_path": {
-                        "type": "string",
-                        "description": "Project path (required)"
-                    },
-                    "content": {
-                        "type": "string",
-                        "description": "Entry content (required for store operation)"
-                    },
-                    "category": {
-                        "type": "string",
-                        "description": "Category: rule, preference, pattern, context"
-                    }
-                },
-                "required": ["action", "project_path"]
-            });
+        tools
+    }
 
-            if let serde_json::Value::Object(ref schema_map) = ji_schema {
-                tools.push(Tool {
-                    name: Cow::Borrowed("store"),
-                    description: Some(Cow::Borrowed("Key-value storage for build configuration and project metadata")),
-                    input_schema: Arc::new(schema_map.clone()),
-                    annotations: Some(ToolAnnotations {
-                        title: Some("Config Store".to_string()),
-                        read_only_hint: Some(false),     // Can modify (store data)
-                        destructive_hint: Some(false),   // Not destructive, only additive
-                        idempotent_hint: Some(true),     // Storing same data is idempotent
-                        open_world_hint: Some(false),    // Closed domain, local storage
-                    }),
-                    icons: None,
-                    meta: None,
-                    output_schema: None,
-                    title: Some("Config Store".to_string()),
-                });
+    /// Run a `tools/call` request: guards, limits, dispatch and result shaping
+    async fn handle_tool_call(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let tool_name = request.name.clone();
+        let debug = log::log_enabled!(log::Level::Debug);
+        if debug {
+            let arguments = request.arguments.clone().map(serde_json::Value::Object).unwrap_or_default();
+            log_debug!("Tool call request: {} {}", tool_name, summarize_for_log(&arguments, &LOG_SUMMARY_LIMITS));
+        }
+
+        // Keep the call counted as in flight until it returns
+        let _guard = match &self.drain {
+            Some(drain) => match drain.track_call(self.endpoint_label()) {
+                Some(guard) => Some(guard),
+                None => return Err(McpError::internal_error(SHUTTING_DOWN_MESSAGE.to_string(), None)),
+            },
+            None => None,
+        };
+
+        // Limits come from the live config, so a reload applies to the next call
+        let config = live::current();
+
+        // Refused when the session was cancelled or is over its rate limit
+        self.client.begin_call(&tool_name, config.mcp_config.session_rate_limit)?;
+
+        // Wait for a slot of this session, then of the process, then of this tool
+        let session_call_limit = ToolLimit::from_config(&config.mcp_config.session_call_limit);
+        let _session_permit = self.client.calls().acquire(&tool_name, session_call_limit).await?;
+        let call_limit = ToolLimit::from_config(&config.mcp_config.call_limit);
+        let _process_permit = CallLimiter::global().acquire(&tool_name, call_limit).await?;
+        let limit = self.tool_limit(&tool_name);
+        let _permit = ToolLimiter::global().acquire(&tool_name, limit).await?;
+
+        let request_id = context.id.to_string();
+        let span = telemetry::tool_call_span(&tool_name, &request_id, self.endpoint_label());
+        #[cfg(all(feature = "otel", feature = "http-server"))]
+        if let Some(parts) = context.extensions.get::<axum::http::request::Parts>() {
+            telemetry::otel::continue_remote_trace(&span, &parts.headers);
+        }
+
+        // Messages of this call follow the session language
+        let locale = session_locale(self.language, context.peer.peer_info());
+        let dry_run = dry_run::requested(&tool_name, request.arguments.as_ref())?;
+        if dry_run {
+            span.record("mcp.dry_run", true);
+        }
+        // Tools rendering to a byte budget read the client's result size limit while they run
+        let client_limit = result_limit::client_result_limit(context.peer.peer_info());
+        // Progress goes to the client only when it sent a progress token
+        let reporter = Reporter::for_request(&context.meta, &context.peer);
+        let server = self.clone();
+        // Boxed before selecting on it so the in-flight call lives on the heap, not the caller's stack
+        let call = Box::pin(run_tool_call(&tool_name, &request_id, progress::scope(reporter, locale::scope(locale, result_limit::scope(client_limit, async move {
+            // Boxed to keep the wrapping futures shallow; dispatch holds every tool's state
+            let dispatch = Box::pin(server.dispatch_tool(request));
+            if dry_run {
+                dry_run::run(dispatch).await
+            } else {
+                dispatch.await
+            }
+        }))))
+        .instrument(span.clone()));
+        // Cancelling the session aborts the call
+        let result = tokio::select! {
+            result = call => result,
+            _ = self.client.cancellation().cancelled() => Err(sessions::cancelled_error()),
+        };
+        // Trim to the size the client declared it can take, if it declared one
+        let result = match (result, client_limit) {
+            (Ok(result), Some(limit)) => {
+                let (result, truncation) = result_limit::fit_to_limit(result, limit, self.declares_output_schema(&tool_name));
+                if let Some(truncation) = truncation {
+                    span.record("mcp.result.truncated", true);
+                    log_important!(
+                        info,
+                        "Tool {} result trimmed from {} to {} bytes (client limit {})",
+                        tool_name,
+                        truncation.original_bytes,
+                        truncation.sent_bytes,
+                        truncation.limit
+                    );
+                }
+                Ok(result)
+            }
+            (result, _) => result,
+        };
+        telemetry::record_tool_outcome(&span, &result);
+
+        if debug {
+            match &result {
+                Ok(result) => {
+                    let value = serde_json::to_value(result).unwrap_or_default();
+                    log_debug!("Tool call result: {} {}", tool_name, summarize_for_log(&value, &LOG_SUMMARY_LIMITS));
+                }
+                Err(e) => log_debug!("Tool call failed: {} {}", tool_name, e.message),
             }
         }
+        result
+    }
+}
 
-        // Index tool - only when enabled
-        if self.is_tool_enabled("index") {
-            tools.push(AcemcpTool::get_tool_definition());
+impl ServerHandler for DevkitServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_logging()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .build(),
+            server_info: Implementation {
+                name: "build-cache".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                icons: None,
+                title: None,
+                website_url: None,
+            },
+            instructions: Some("Build cache and code indexing utilities".to_string()),
         }
+    }
 
-        // Docs tool - only when enabled
-        if self.is_tool_enabled("docs") {
-            tools.push(DocsTool::get_tool_definition());
+    async fn initialize(
+        &self,
+        _request: InitializeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ServerInfo, McpError> {
+        Ok(self.get_info())
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        // Streamable HTTP sessions are known by the id the client echoes back
+        #[cfg(feature = "http-server")]
+        let session_id = context
+            .extensions
+            .get::<axum::http::request::Parts>()
+            .and_then(|parts| parts.headers.get("mcp-session-id"))
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_string);
+        #[cfg(not(feature = "http-server"))]
+        let session_id: Option<String> = None;
+        #[cfg(feature = "http-server")]
+        if let (Some(sessions), Some(session_id)) = (&self.sessions, &session_id) {
+            sessions.register(session_id, self.endpoint_label(), context.peer.clone(), self.subscriptions.clone());
+        }
+        let client_info = context.peer.peer_info().map(|info| &info.client_info);
+        let details = ClientDetails {
+            session_id: session_id.clone(),
+            endpoint: self.endpoint_label().to_string(),
+            client_name: client_info.map(|client| client.name.clone()),
+            client_version: client_info.map(|client| client.version.clone()),
+        };
+        SessionManager::global().register(&self.client, details);
+        if let Some(drain) = &self.drain {
+            // Keep-alive chosen by the HTTP transport for this client, if any
+            #[cfg(feature = "http-server")]
+            let keep_alive = context
+                .extensions
+                .get::<axum::http::request::Parts>()
+                .and_then(|parts| parts.extensions.get::<super::http::EffectiveKeepAlive>())
+                .map(|keep_alive| keep_alive.0);
+            #[cfg(not(feature = "http-server"))]
+            let keep_alive = None;
+            let locale = session_locale(self.language, context.peer.peer_info());
+            drain.register_session(context.peer, session_id.as_deref(), self.endpoint_label(), keep_alive, locale);
         }
+    }
+
+    /// Accepted so clients may set a level; only the shutdown notice is sent as a log message
+    async fn set_level(
+        &self,
+        _request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        Ok(())
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let tools = self.tool_definitions();
 
         log_debug!("Tools returned to client: {:?}", tools.iter().map(|t| &t.name).collect::<Vec<_>>());
 
@@ -246,118 +812,311 @@ impl ServerHandler for DevkitServer {
         })
     }
 
+    /// Memory entries of every project this process has opened a store for
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let mut items = Vec::new();
+        for (namespace, project) in resources::known_projects() {
+            let Ok(memories) = MemoryManager::new(&project.to_string_lossy()).and_then(|m| m.get_all_memories()) else {
+                continue;
+            };
+            for memory in memories {
+                let mut resource = RawResource::new(resources::memory_uri(&namespace, &memory.id), memory_title(&memory.content));
+                resource.description = Some(format!("{:?} memory of {}", memory.category, project.display()));
+                resource.mime_type = Some("text/plain".to_string());
+                items.push(resource.no_annotation());
+            }
+        }
+        Ok(ListResourcesResult::with_all_items(items))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        let template = RawResourceTemplate {
+            uri_template: resources::MEMORY_URI_TEMPLATE.to_string(),
+            name: "memory".to_string(),
+            title: Some("Project memory entry".to_string()),
+            description: Some("One entry of a project's memory store; subscribe to be notified of edits".to_string()),
+            mime_type: Some("text/plain".to_string()),
+        };
+        Ok(ListResourceTemplatesResult::with_all_items(vec![template.no_annotation()]))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let not_found = || McpError::resource_not_found(format!("No memory at {}", request.uri), None);
+        let (namespace, id) = resources::parse_memory_uri(&request.uri).ok_or_else(not_found)?;
+        let project = resources::project_for_namespace(namespace).ok_or_else(not_found)?;
+        let manager = MemoryManager::new(&project.to_string_lossy())
+            .map_err(|e| McpError::internal_error(format!("Failed to read memory: {}", e), None))?;
+        let memory = manager
+            .get_memory(id)
+            .map_err(|e| McpError::internal_error(format!("Failed to read memory: {}", e), None))?
+            .ok_or_else(not_found)?;
+        // Reading one entry counts as recalling it for the stale review
+        if let Err(e) = manager.record_recall(&[id], chrono::Utc::now()) {
+            log_debug!("Failed to record memory recall: {}", e);
+        }
+
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(memory.content, request.uri.clone())],
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if resources::parse_memory_uri(&request.uri).is_none() {
+            return Err(McpError::invalid_params(format!("Not a memory resource: {}", request.uri), None));
+        }
+        self.subscriptions
+            .subscribe(&request.uri, context.peer)
+            .map_err(|e| McpError::invalid_params(format!("Cannot subscribe to {}: {}", request.uri, e), None))?;
+        log_debug!("Subscribed to {} ({} on this session)", request.uri, self.subscriptions.len());
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.subscriptions.unsubscribe(&request.uri);
+        Ok(())
+    }
+
     async fn call_tool(
         &self,
         request: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        log_debug!("Tool call request: {}", request.name);
+        // Logs of the call carry the id of the HTTP request that brought it, or a fresh one
+        let correlation_id = request_correlation_id(&context).unwrap_or_else(correlation::new_id);
+        // Boxed: the call future is large and nesting it in the scope inline overflows the layout depth limit
+        correlation::scope(correlation_id, Box::pin(self.handle_tool_call(request, context))).await
+    }
+}
 
-        match request.name.as_ref() {
-            "cache" => {
-                let arguments_value = request.arguments
-                    .map(serde_json::Value::Object)
-                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+/// Resource name of a memory: its first line, shortened
+fn memory_title(content: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    let line = content.lines().next().unwrap_or_default();
+    crate::utils::text::truncate(line, MAX_CHARS).into_owned()
+}
 
-                let cache_request: CacheRequest = serde_json::from_value(arguments_value)
-                    .map_err(|e| McpError::invalid_params(format!("Parameter parse error: {}", e), None))?;
+/// Correlation id the HTTP transport put on the request carrying this call
+#[cfg(feature = "http-server")]
+fn request_correlation_id(context: &RequestContext<RoleServer>) -> Option<String> {
+    let parts = context.extensions.get::<axum::http::request::Parts>()?;
+    let id = parts.headers.get(correlation::REQUEST_ID_HEADER)?.to_str().ok()?;
+    correlation::accept(id).map(str::to_string)
+}
 
-                // Use async version that returns immediately
-                InteractionTool::prompt_start(cache_request).await
-            }
-            "cache_sync" => {
-                let arguments_value = request.arguments
-                    .map(serde_json::Value::Object)
-                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+#[cfg(not(feature = "http-server"))]
+fn request_correlation_id(_context: &RequestContext<RoleServer>) -> Option<String> {
+    None
+}
 
-                let cache_request: CacheRequest = serde_json::from_value(arguments_value)
-                    .map_err(|e| McpError::invalid_params(format!("Parameter parse error: {}", e), None))?;
+/// Start MCP server
+pub async fn run_server() -> Result<(), Box<dyn std::error::Error>> {
+    let service = DevkitServer::new()
+        .serve(checked_transport(tokio::io::stdin(), tokio::io::stdout()))
+        .await
+        .inspect_err(|e| {
+            log_important!(error, "Server start failed: {}", e);
+        })?;
 
-                InteractionTool::prompt_sync(cache_request).await
-            }
-            "cache_get" => {
-                let arguments_value = request.arguments
-                    .map(serde_json::Value::Object)
-                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+    service.waiting().await?;
+    Ok(())
+}
 
-                let task_id = arguments_value.get("task_id")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-                    .ok_or_else(|| McpError::invalid_params("task_id is required".to_string(), None))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
 
-                InteractionTool::cache_get(task_id).await
-            }
-            "store" => {
-                // Check if store tool is enabled
-                if !self.is_tool_enabled("store") {
-                    return Err(McpError::internal_error(
-                        "Memory tool is disabled".to_string(),
-                        None
-                    ));
-                }
+    fn tool_names() -> Vec<String> {
+        DevkitServer::new()
+            .tool_definitions()
+            .into_iter()
+            .map(|tool| tool.name.into_owned())
+            .collect()
+    }
 
-                let arguments_value = request.arguments
-                    .map(serde_json::Value::Object)
-                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+    #[test]
+    fn test_tool_list_matches_compiled_features() {
+        let names = tool_names();
+
+        for tool in ["cache", "cache_sync", "cache_get"] {
+            assert_eq!(names.iter().any(|n| n == tool), cfg!(feature = "gui"), "{}", tool);
+        }
+        if !cfg!(feature = "docs") {
+            assert!(!names.iter().any(|n| n == "docs" || n == "docs_search" || n == "docs_compare" || n == "devkit_stats" || n == "docs_status"));
+        }
+    }
 
-                let store_request: StoreRequest = serde_json::from_value(arguments_value)
-                    .map_err(|e| McpError::invalid_params(format!("Parameter parse error: {}", e), None))?;
+    #[test]
+    fn test_endpoint_overrides_tools() {
+        let endpoint = McpEndpointConfig {
+            name: "readonly".to_string(),
+            path: "/sse/readonly".to_string(),
+            tools: HashMap::from([("store".to_string(), false), ("cache".to_string(), false)]),
+            memory_read_only: true,
+        };
+        let server = DevkitServer::new().with_endpoint(&endpoint);
+        assert_eq!(server.endpoint_label(), "readonly");
+        let names: Vec<_> = server.tool_definitions().into_iter().map(|tool| tool.name.into_owned()).collect();
+        assert!(!names.iter().any(|n| n == "store" || n.starts_with("cache")));
+        assert_eq!(DevkitServer::new().endpoint_label(), DEFAULT_ENDPOINT);
+    }
 
-                MemoryTool::store(store_request).await
-            }
-            "index" => {
-                if !self.is_tool_enabled("index") {
-                    return Err(McpError::internal_error(
-                        "Search tool is disabled".to_string(),
-                        None
-                    ));
-                }
+    #[test]
+    fn test_popup_tools_can_be_disabled() {
+        let names: Vec<_> = DevkitServer::new()
+            .with_popup(false)
+            .tool_definitions()
+            .into_iter()
+            .map(|tool| tool.name.into_owned())
+            .collect();
+        assert!(!names.iter().any(|n| n.starts_with("cache")));
+    }
 
-                let arguments_value = request.arguments
-                    .map(serde_json::Value::Object)
-                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+    #[tokio::test]
+    async fn test_dry_run_is_advertised_and_refused_where_unsupported() {
+        use crate::cli::call::call_with_capabilities;
+        use serde_json::json;
 
-                let acemcp_request: crate::mcp::tools::acemcp::types::AcemcpRequest = serde_json::from_value(arguments_value)
-                    .map_err(|e| McpError::invalid_params(format!("Parameter parse error: {}", e), None))?;
+        let tools = DevkitServer::new().tool_definitions();
+        let accepts = |name: &str| {
+            tools.iter().find(|tool| tool.name == name).map(|tool| tool.input_schema["properties"].get(dry_run::DRY_RUN_KEY).is_some())
+        };
+        assert_eq!(accepts("store"), Some(true));
+        assert_eq!(accepts("git_context"), Some(false));
 
-                AcemcpTool::search_context(acemcp_request).await
-            }
-            "docs" => {
-                if !self.is_tool_enabled("docs") {
-                    return Err(McpError::internal_error(
-                        "Docs tool is disabled".to_string(),
-                        None
-                    ));
-                }
+        let project = TestDir::new("dry-run-call");
+        std::fs::create_dir_all(project.join(".git")).unwrap();
+        let arguments = json!({
+            "action": "store", "project_path": project.to_string_lossy(), "content": "Use tabs", "dry_run": true
+        });
+        let result = call_with_capabilities(DevkitServer::new(), json!({}), "store", arguments.as_object().cloned().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(result.meta.unwrap().get(dry_run::META_KEY), Some(&json!(true)));
+        assert!(!project.join(".devkit-memory").exists());
 
-                let arguments_value = request.arguments
-                    .map(serde_json::Value::Object)
-                    .unwrap_or(serde_json::Value::Object(serde_json::Map::new()));
+        let arguments = json!({ "dry_run": true });
+        let error = call_with_capabilities(DevkitServer::new(), json!({}), "git_context", arguments.as_object().cloned().unwrap())
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(error.message.contains("does not support dry_run"), "{}", error.message);
+    }
 
-                let docs_request: DocsRequest = serde_json::from_value(arguments_value)
-                    .map_err(|e| McpError::invalid_params(format!("Parameter parse error: {}", e), None))?;
+    #[tokio::test]
+    async fn test_memory_subscription_notifies_on_update() {
+        use crate::mcp::tools::memory::MemoryCategory;
+        use serde_json::{json, Value};
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 
-                DocsTool::query_docs(docs_request).await
+        let project = TestDir::new("resources");
+        std::fs::create_dir_all(project.join(".git")).unwrap();
+        let project_path = project.to_string_lossy().to_string();
+        let manager = MemoryManager::new(&project_path).unwrap();
+        let id = manager.add_memory("Use tabs for indentation", MemoryCategory::Rule).unwrap();
+        let uri = resources::memory_uri(&manager.namespace(), &id);
+
+        let (client, server_io) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(async move {
+            if let Ok(service) = DevkitServer::new().with_popup(false).serve(server_io).await {
+                let _ = service.waiting().await;
             }
-            _ => {
-                Err(McpError::invalid_request(
-                    format!("Unknown tool: {}", request.name),
-                    None
-                ))
+        });
+        let (read, mut write) = tokio::io::split(client);
+        let mut lines = BufReader::new(read).lines();
+
+        async fn send(write: &mut (impl AsyncWriteExt + Unpin), message: Value) {
+            write.write_all(format!("{}\n", message).as_bytes()).await.unwrap();
+        }
+        // Messages up to and including the response to `id`
+        async fn until_response(
+            lines: &mut tokio::io::Lines<BufReader<impl tokio::io::AsyncRead + Unpin>>,
+            id: u64,
+        ) -> Vec<Value> {
+            let mut seen = Vec::new();
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let message: Value = serde_json::from_str(&line).unwrap();
+                let done = message["id"] == id;
+                seen.push(message);
+                if done {
+                    break;
+                }
             }
+            seen
         }
-    }
-}
 
-/// Start MCP server
-pub async fn run_server() -> Result<(), Box<dyn std::error::Error>> {
-    let service = DevkitServer::new()
-        .serve(stdio())
-        .await
-        .inspect_err(|e| {
-            log_important!(error, "Server start failed: {}", e);
-        })?;
+        send(&mut write, json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": { "name": "resource-test", "version": "0.0.0" }
+            }
+        })).await;
+        let init = until_response(&mut lines, 1).await;
+        assert_eq!(init.last().unwrap()["result"]["capabilities"]["resources"]["subscribe"], true);
+        send(&mut write, json!({"jsonrpc": "2.0", "method": "notifications/initialized"})).await;
 
-    service.waiting().await?;
-    Ok(())
+        send(&mut write, json!({"jsonrpc": "2.0", "id": 2, "method": "resources/templates/list"})).await;
+        let templates = until_response(&mut lines, 2).await;
+        assert_eq!(
+            templates.last().unwrap()["result"]["resourceTemplates"][0]["uriTemplate"],
+            resources::MEMORY_URI_TEMPLATE
+        );
+
+        send(&mut write, json!({"jsonrpc": "2.0", "id": 3, "method": "resources/subscribe", "params": {"uri": uri}})).await;
+        let subscribed = until_response(&mut lines, 3).await;
+        assert!(subscribed.last().unwrap().get("error").is_none(), "{:?}", subscribed);
+
+        send(&mut write, json!({
+            "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+            "params": {
+                "name": "store",
+                "arguments": { "action": "update", "project_path": project_path, "id": id, "content": "Use spaces" }
+            }
+        })).await;
+        let mut seen = until_response(&mut lines, 4).await;
+        assert_eq!(seen.last().unwrap()["result"]["isError"], false, "{:?}", seen);
+
+        // The notification may trail the tool result
+        let notified = |seen: &[Value]| {
+            seen.iter()
+                .any(|m| m["method"] == "notifications/resources/updated" && m["params"]["uri"] == uri.as_str())
+        };
+        if !notified(&seen) {
+            let line = tokio::time::timeout(std::time::Duration::from_secs(5), lines.next_line())
+                .await
+                .expect("no resource update notification")
+                .unwrap()
+                .unwrap();
+            seen.push(serde_json::from_str(&line).unwrap());
+        }
+        assert!(notified(&seen), "{:?}", seen);
+
+        send(&mut write, json!({"jsonrpc": "2.0", "id": 5, "method": "resources/read", "params": {"uri": uri}})).await;
+        let read = until_response(&mut lines, 5).await;
+        assert_eq!(read.last().unwrap()["result"]["contents"][0]["text"], "Use spaces");
+    }
 }
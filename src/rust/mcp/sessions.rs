@@ -0,0 +1,350 @@
+//! Per-client session state
+//!
+//! Every MCP session gets its own [`DevkitServer`](super::DevkitServer) and with
+//! it a [`ClientSession`]: which client connected, when, and how many calls it
+//! made per tool. Sessions register with the process-wide [`SessionManager`]
+//! once initialized and drop out of it with their server, so `list_sessions`
+//! only sees live clients.
+//!
+//! `mcp_config.session_rate_limit` caps the tool calls of one session per
+//! minute; `list_sessions` can change the cap of a single session. A call over
+//! the cap is refused with [`SESSION_RATE_LIMITED`]. Cancelling a session aborts
+//! its running calls and refuses new ones with [`SESSION_CANCELLED`] until the
+//! client reconnects.
+
+use chrono::{DateTime, Utc};
+use rmcp::model::{ErrorCode, ErrorData as McpError};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::concurrency::{CallLimiter, CallScope};
+use crate::log_important;
+
+/// Application error: the session made more calls in the last minute than its
+/// rate limit allows. Retrying after `retry_after_ms` succeeds.
+pub const SESSION_RATE_LIMITED: ErrorCode = ErrorCode(-32003);
+
+/// Application error: the session was cancelled by an administrator and has to
+/// reconnect
+pub const SESSION_CANCELLED: ErrorCode = ErrorCode(-32004);
+
+/// Window the rate limit counts calls in
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+static GLOBAL: once_cell::sync::Lazy<SessionManager> = once_cell::sync::Lazy::new(SessionManager::default);
+
+/// Source of the ids of sessions without an `Mcp-Session-Id` (stdio, WebSocket)
+static NEXT_LOCAL_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Who is on the other end, as learned at initialize
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientDetails {
+    /// Streamable HTTP session id
+    pub session_id: Option<String>,
+    pub endpoint: String,
+    pub client_name: Option<String>,
+    pub client_version: Option<String>,
+}
+
+/// One session, as listed by `list_sessions`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub endpoint: String,
+    pub client_name: Option<String>,
+    pub client_version: Option<String>,
+    pub connected_at: Option<DateTime<Utc>>,
+    pub last_call_at: Option<DateTime<Utc>>,
+    pub calls: u64,
+    pub calls_by_tool: BTreeMap<String, u64>,
+    /// Calls refused by the rate limit
+    pub rate_limited: u64,
+    /// Calls allowed per minute, `None` when unlimited
+    pub rate_limit_per_minute: Option<u32>,
+    pub cancelled: bool,
+}
+
+/// State of one client session
+pub struct ClientSession {
+    local_id: u64,
+    state: Mutex<State>,
+    cancellation: CancellationToken,
+    calls: CallLimiter,
+}
+
+#[derive(Default)]
+struct State {
+    details: ClientDetails,
+    connected_at: Option<DateTime<Utc>>,
+    last_call_at: Option<DateTime<Utc>>,
+    calls: BTreeMap<String, u64>,
+    rate_limited: u64,
+    /// Set by `list_sessions`; `Some(0)` lifts the configured limit
+    rate_limit_override: Option<u32>,
+    /// Start of every call within the last [`RATE_WINDOW`]
+    recent: VecDeque<Instant>,
+}
+
+impl Default for ClientSession {
+    fn default() -> Self {
+        Self {
+            local_id: NEXT_LOCAL_ID.fetch_add(1, Ordering::Relaxed),
+            state: Mutex::default(),
+            cancellation: CancellationToken::new(),
+            calls: CallLimiter::new(CallScope::Session),
+        }
+    }
+}
+
+impl ClientSession {
+    /// Id shown by `list_sessions`: the `Mcp-Session-Id`, or `local-<n>` without one
+    pub fn id(&self) -> String {
+        self.state
+            .lock()
+            .ok()
+            .and_then(|state| state.details.session_id.clone())
+            .unwrap_or_else(|| format!("local-{}", self.local_id))
+    }
+
+    /// Cancelled when an administrator cancels the session
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Cap on the calls this session runs at once
+    pub fn calls(&self) -> &CallLimiter {
+        &self.calls
+    }
+
+    /// Abort the running calls of this session and refuse new ones
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+
+    /// Override the configured rate limit; `None` goes back to the config, 0 is unlimited
+    pub fn set_rate_limit(&self, per_minute: Option<u32>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.rate_limit_override = per_minute;
+        }
+    }
+
+    /// Count a call of `tool`, or refuse it when cancelled or over the rate limit
+    ///
+    /// `configured` is the per-minute limit from the config, `None` or 0 for unlimited.
+    pub fn begin_call(&self, tool: &str, configured: Option<u32>) -> Result<(), McpError> {
+        self.begin_call_at(tool, configured, Instant::now())
+    }
+
+    fn begin_call_at(&self, tool: &str, configured: Option<u32>, now: Instant) -> Result<(), McpError> {
+        if self.cancellation.is_cancelled() {
+            return Err(cancelled_error());
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return Ok(());
+        };
+        if let Some(limit) = effective_limit(state.rate_limit_override, configured) {
+            while state.recent.front().is_some_and(|start| now.duration_since(*start) >= RATE_WINDOW) {
+                state.recent.pop_front();
+            }
+            if state.recent.len() >= limit as usize {
+                state.rate_limited += 1;
+                let retry_after = state.recent.front().map_or(Duration::ZERO, |oldest| RATE_WINDOW - now.duration_since(*oldest));
+                return Err(rate_limited_error(tool, limit, retry_after));
+            }
+            state.recent.push_back(now);
+        }
+        *state.calls.entry(tool.to_string()).or_default() += 1;
+        state.last_call_at = Some(Utc::now());
+        Ok(())
+    }
+
+    /// Counters of this session, with `configured` as the default rate limit
+    pub fn info(&self, configured: Option<u32>) -> SessionInfo {
+        let id = self.id();
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        SessionInfo {
+            id,
+            endpoint: state.details.endpoint.clone(),
+            client_name: state.details.client_name.clone(),
+            client_version: state.details.client_version.clone(),
+            connected_at: state.connected_at,
+            last_call_at: state.last_call_at,
+            calls: state.calls.values().sum(),
+            calls_by_tool: state.calls.clone(),
+            rate_limited: state.rate_limited,
+            rate_limit_per_minute: effective_limit(state.rate_limit_override, configured),
+            cancelled: self.cancellation.is_cancelled(),
+        }
+    }
+}
+
+/// Limit in force: the override if set, else the config; 0 means unlimited
+fn effective_limit(override_limit: Option<u32>, configured: Option<u32>) -> Option<u32> {
+    override_limit.or(configured).filter(|limit| *limit > 0)
+}
+
+/// Registry of the live sessions of this process
+#[derive(Default)]
+pub struct SessionManager {
+    sessions: Mutex<Vec<Weak<ClientSession>>>,
+}
+
+impl SessionManager {
+    /// Registry shared by every transport of the process
+    pub fn global() -> &'static SessionManager {
+        &GLOBAL
+    }
+
+    /// Record an initialized session; it is forgotten once its server is dropped
+    pub fn register(&self, session: &Arc<ClientSession>, details: ClientDetails) {
+        if let Ok(mut state) = session.state.lock() {
+            state.details = details;
+            state.connected_at = Some(Utc::now());
+        }
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.retain(|s| s.strong_count() > 0);
+            if !sessions.iter().any(|s| s.as_ptr() == Arc::as_ptr(session)) {
+                sessions.push(Arc::downgrade(session));
+            }
+        }
+    }
+
+    /// Live sessions in the order they connected
+    pub fn sessions(&self) -> Vec<Arc<ClientSession>> {
+        let Ok(mut sessions) = self.sessions.lock() else {
+            return Vec::new();
+        };
+        sessions.retain(|s| s.strong_count() > 0);
+        sessions.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// Session with the id shown by `list_sessions`
+    pub fn find(&self, id: &str) -> Option<Arc<ClientSession>> {
+        self.sessions().into_iter().find(|session| session.id() == id)
+    }
+
+    /// Cancel the session with `id`; false when there is none
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.find(id) {
+            Some(session) => {
+                session.cancel();
+                log_important!(info, "Session {} cancelled", id);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn rate_limited_error(tool: &str, limit: u32, retry_after: Duration) -> McpError {
+    let retry_after_ms = u64::try_from(retry_after.as_millis()).unwrap_or(u64::MAX);
+    McpError::new(
+        SESSION_RATE_LIMITED,
+        format!(
+            "Rate limit of {} calls per minute reached for this session; {} can be called again in {} ms",
+            limit, tool, retry_after_ms
+        ),
+        Some(json!({ "tool": tool, "limit": limit, "retry_after_ms": retry_after_ms, "retryable": true })),
+    )
+}
+
+/// Error for calls of a cancelled session
+pub fn cancelled_error() -> McpError {
+    McpError::new(
+        SESSION_CANCELLED,
+        "This session was cancelled by an administrator; reconnect to continue".to_string(),
+        Some(json!({ "retryable": false })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(session_id: Option<&str>) -> ClientDetails {
+        ClientDetails {
+            session_id: session_id.map(str::to_string),
+            endpoint: "default".to_string(),
+            client_name: Some("test-client".to_string()),
+            client_version: Some("1.0".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_counts_calls_per_tool() {
+        let session = ClientSession::default();
+        session.begin_call("docs", None).unwrap();
+        session.begin_call("docs", None).unwrap();
+        session.begin_call("store", None).unwrap();
+
+        let info = session.info(None);
+        assert_eq!(info.calls, 3);
+        assert_eq!(info.calls_by_tool["docs"], 2);
+        assert!(info.last_call_at.is_some());
+        assert_eq!(info.rate_limit_per_minute, None);
+    }
+
+    #[test]
+    fn test_rate_limit_refuses_until_the_window_passes() {
+        let session = ClientSession::default();
+        let start = Instant::now();
+        session.begin_call_at("docs", Some(2), start).unwrap();
+        session.begin_call_at("docs", Some(2), start + Duration::from_secs(10)).unwrap();
+
+        let error = session.begin_call_at("docs", Some(2), start + Duration::from_secs(20)).unwrap_err();
+        assert_eq!(error.code, SESSION_RATE_LIMITED);
+        assert_eq!(error.data.unwrap()["retry_after_ms"], 40_000);
+
+        session.begin_call_at("docs", Some(2), start + Duration::from_secs(60)).unwrap();
+        let info = session.info(Some(2));
+        assert_eq!(info.calls, 3);
+        assert_eq!(info.rate_limited, 1);
+    }
+
+    #[test]
+    fn test_override_replaces_configured_limit() {
+        let session = ClientSession::default();
+        session.set_rate_limit(Some(1));
+        session.begin_call("docs", Some(100)).unwrap();
+        assert_eq!(session.begin_call("docs", Some(100)).unwrap_err().code, SESSION_RATE_LIMITED);
+
+        // 0 lifts the limit altogether
+        session.set_rate_limit(Some(0));
+        session.begin_call("docs", Some(1)).unwrap();
+        assert_eq!(session.info(Some(1)).rate_limit_per_minute, None);
+    }
+
+    #[test]
+    fn test_cancelled_session_refuses_calls() {
+        let manager = SessionManager::default();
+        let session = Arc::new(ClientSession::default());
+        manager.register(&session, details(Some("abc")));
+
+        assert!(manager.cancel("abc"));
+        assert!(session.cancellation().is_cancelled());
+        assert_eq!(session.begin_call("docs", None).unwrap_err().code, SESSION_CANCELLED);
+        assert!(!manager.cancel("missing"));
+    }
+
+    #[test]
+    fn test_dropped_sessions_leave_the_registry() {
+        let manager = SessionManager::default();
+        let http = Arc::new(ClientSession::default());
+        let stdio = Arc::new(ClientSession::default());
+        manager.register(&http, details(Some("abc")));
+        manager.register(&stdio, details(None));
+
+        let ids: Vec<String> = manager.sessions().iter().map(|s| s.id()).collect();
+        assert_eq!(ids, vec!["abc".to_string(), format!("local-{}", stdio.local_id)]);
+        assert_eq!(manager.find("abc").unwrap().info(None).client_name.as_deref(), Some("test-client"));
+
+        drop(http);
+        assert_eq!(manager.sessions().len(), 1);
+        assert!(manager.find("abc").is_none());
+    }
+}
@@ -0,0 +1,258 @@
+//! Inputs shared by the snapshot tests
+//!
+//! Timestamps are fixed so the golden files never change by themselves.
+
+// Not every build uses every fixture (docs ones need the `docs` feature)
+#![allow(dead_code)]
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::path::PathBuf;
+
+use crate::docs_client::cache::{CacheStats, OldestEntry};
+use crate::docs_client::keys::{KeyStatus, KeyUsage};
+use crate::docs_client::quota::{RateLimit, UsageSnapshot};
+use crate::docs_client::{DocsRequest, DocsResponse, DocumentSnippet, PaginationInfo, SearchResult};
+use crate::mcp::tools::command::runner::{Captured, CommandOutput};
+use crate::mcp::tools::git::repo::{ChangedFile, CommitSummary, DiffFileStat, RepoDiff, RepoStatus};
+use crate::mcp::tools::memory::{MemoryCategory, MemoryEntry};
+use crate::mcp::tools::project_docs::{DocSection, SearchHit};
+
+pub fn fixed_time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap()
+}
+
+fn snippet(title: Option<&str>, content: &str) -> DocumentSnippet {
+    DocumentSnippet { content: content.to_string(), title: title.map(str::to_string), score: None }
+}
+
+/// Upstream traffic captured with `devkit --record`
+pub fn recording_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/rust/docs_client/fixtures/recorded")
+}
+
+pub fn request() -> DocsRequest {
+    DocsRequest::new("vercel/next.js")
+}
+
+/// Topic, version, page and a snippet limit
+pub fn detailed_request() -> DocsRequest {
+    DocsRequest {
+        topic: Some("routing".to_string()),
+        version: Some("v15.1.8".to_string()),
+        page: Some(10),
+        max_snippets: Some(2),
+        ..request()
+    }
+}
+
+/// The parser fixture with titles, scores and pagination
+pub fn full_response() -> DocsResponse {
+    DocsResponse::from_body(include_str!("../../docs_client/fixtures/docs_full.json"))
+}
+
+/// Snippets that are all blank
+pub fn empty_snippets() -> DocsResponse {
+    DocsResponse {
+        snippets: vec![snippet(Some("Empty"), ""), snippet(None, "   \n\t\n")],
+        ..Default::default()
+    }
+}
+
+pub fn unicode_titles() -> DocsResponse {
+    DocsResponse {
+        snippets: vec![
+            snippet(Some("路由 🚀 Routing"), "使用 `app` 目录定义路由。\n\n```tsx\nexport default function Page() {\n  return <h1>你好，世界 👋</h1>\n}\n```"),
+            snippet(Some("Ünïcödé — Дата и время"), "Combining marks: e\u{301} and RTL: مرحبا"),
+            snippet(None, "Untitled snippet with trailing spaces   \n\n"),
+        ],
+        ..Default::default()
+    }
+}
+
+/// More snippets than the limit and the largest page numbers upstream could send
+pub fn max_pagination() -> DocsResponse {
+    DocsResponse {
+        snippets: (1..=3).map(|i| snippet(Some(&format!("Snippet {}", i)), &format!("content {}", i))).collect(),
+        pagination: Some(PaginationInfo { current_page: u32::MAX, total_pages: u32::MAX, has_next: true }),
+        ..Default::default()
+    }
+}
+
+/// First `count` (at most 5) search results, covering huge, exact and missing star counts
+pub fn suggestions(count: usize) -> Vec<SearchResult> {
+    let result = |id: &str, description: Option<String>, stars: Option<u64>, trust_score: Option<f64>| SearchResult {
+        id: id.to_string(),
+        title: None,
+        description,
+        stars,
+        trust_score,
+        benchmark_score: None,
+        state: None,
+        versions: Vec::new(),
+    };
+    vec![
+        result("/vercel/next.js", Some("The React Framework".to_string()), Some(131_500), Some(9.8)),
+        result("/huge/stars", Some("高性能文档框架，".repeat(20)), Some(12_345_678_901), None),
+        result("/acme/tiny", None, Some(7), Some(3.25)),
+        result("/acme/no-meta", None, None, None),
+        result("acme/exact", Some("Exactly one thousand stars".to_string()), Some(1000), Some(10.0)),
+    ]
+    .into_iter()
+    .take(count)
+    .collect()
+}
+
+pub fn empty_cache_stats() -> (CacheStats, UsageSnapshot, Vec<KeyUsage>) {
+    let stats = CacheStats {
+        memory_entries: 0,
+        disk_entries: 0,
+        total_bytes: 0,
+        hits: 0,
+        misses: 0,
+        hit_ratio: 0.0,
+        oldest: None,
+        directory: None,
+        max_bytes: None,
+    };
+    (stats, UsageSnapshot { requests: 0, rate_limit: None, since: fixed_time() }, Vec::new())
+}
+
+pub fn busy_cache_stats() -> (CacheStats, UsageSnapshot, Vec<KeyUsage>) {
+    let stats = CacheStats {
+        memory_entries: 17,
+        disk_entries: 42,
+        total_bytes: 1_234_567,
+        hits: 90,
+        misses: 10,
+        hit_ratio: 0.9,
+        oldest: Some(OldestEntry { library: "vercel/next.js".to_string(), stored_at: fixed_time() }),
+        directory: Some(PathBuf::from("/home/dev/.cache/devkit/docs")),
+        max_bytes: None,
+    };
+    let session = UsageSnapshot {
+        requests: 12,
+        rate_limit: Some(RateLimit { limit: Some(1000), remaining: Some(3), reset_secs: Some(60) }),
+        since: fixed_time(),
+    };
+    let key = |key: &str, requests, status| KeyUsage { key: key.to_string(), requests, status };
+    let keys = vec![
+        key("ctx7…a1b2", 1, KeyStatus::Active),
+        key("ctx7…c3d4", 7, KeyStatus::Benched { secs: 42 }),
+        key("ctx7…e5f6", 0, KeyStatus::Disabled),
+    ];
+    (stats, session, keys)
+}
+
+fn memory(id: &str, content: &str, category: MemoryCategory) -> MemoryEntry {
+    MemoryEntry { id: id.to_string(), content: content.to_string(), category, created_at: fixed_time(), updated_at: fixed_time() }
+}
+
+/// One or more entries per category, with multi-line, unicode and blank content
+pub fn memories() -> Vec<MemoryEntry> {
+    vec![
+        memory("r1", "Use `anyhow` for errors\n   in binaries", MemoryCategory::Rule),
+        memory("r2", "代码注释使用中文", MemoryCategory::Rule),
+        memory("p1", "Prefers small commits 🙂", MemoryCategory::Preference),
+        memory("t1", "   ", MemoryCategory::Pattern),
+        memory("t2", "Builders for structs with more than three optional fields", MemoryCategory::Pattern),
+        memory("c1", "Monorepo with pnpm workspaces", MemoryCategory::Context),
+    ]
+}
+
+pub fn blank_memories() -> Vec<MemoryEntry> {
+    vec![memory("b1", "", MemoryCategory::Rule), memory("b2", " \n\t", MemoryCategory::Context)]
+}
+
+fn changed(path: &str, orig_path: Option<&str>, index: char, worktree: char, kind: &'static str) -> ChangedFile {
+    ChangedFile { path: path.to_string(), orig_path: orig_path.map(str::to_string), index, worktree, kind }
+}
+
+/// Every group of changes, tracking an upstream, with more files than listed
+pub fn busy_status() -> RepoStatus {
+    RepoStatus {
+        branch: Some("feature/snapshots".to_string()),
+        detached: false,
+        head: Some("9f8e7d6".to_string()),
+        has_commits: true,
+        upstream: Some("origin/feature/snapshots".to_string()),
+        ahead: Some(2),
+        behind: Some(1),
+        files: vec![
+            changed("src/lib.rs", None, 'U', 'U', "conflicted"),
+            changed("README.md", None, 'M', '.', "modified"),
+            changed("docs/新しい.md", Some("docs/old name.md"), 'R', '.', "renamed"),
+            changed("Cargo.toml", None, 'M', 'M', "modified"),
+            changed("notes.txt", None, '?', '?', "untracked"),
+        ],
+        truncated: true,
+    }
+}
+
+pub fn truncated_diff() -> RepoDiff {
+    let stat = |path: &str, additions, deletions| DiffFileStat { path: path.to_string(), orig_path: None, additions, deletions };
+    RepoDiff {
+        staged: true,
+        files: vec![stat("src/main.rs", Some(3), Some(1)), stat("logo.png", None, None)],
+        text: "diff --git a/src/main.rs b/src/main.rs\n@@ -1 +1,3 @@\n-fn main() {}\n+fn main() {\n+    println!(\"hi\");\n+}".to_string(),
+        total_bytes: 48_000,
+        truncated: true,
+    }
+}
+
+pub fn commits() -> Vec<CommitSummary> {
+    [
+        ("1a2b3c4", "Add snapshot tests for formatters"),
+        ("5d6e7f8", "修复：unicode subject ✨"),
+        ("9a0b1c2", "Merge branch 'main' into feature/*snapshots*"),
+    ]
+    .into_iter()
+    .map(|(hash, subject)| CommitSummary { hash: hash.to_string(), subject: subject.to_string() })
+    .collect()
+}
+
+pub fn finished_command() -> CommandOutput {
+    CommandOutput {
+        exit_code: Some(0),
+        timed_out: false,
+        duration_ms: 1234,
+        stdout: Captured { text: "cargo 1.80.0 (376290515 2024-07-16)\n".to_string(), total_bytes: 36, truncated: false },
+        stderr: Captured::default(),
+    }
+}
+
+/// Killed by the timeout, with a truncated stdout and colored stderr
+pub fn timed_out_command() -> CommandOutput {
+    CommandOutput {
+        exit_code: None,
+        timed_out: true,
+        duration_ms: 30_000,
+        stdout: Captured {
+            text: "running 120 tests\ntest a ... ok\n[... 1048000 bytes omitted ...]\ntest z ... ok\n".to_string(),
+            total_bytes: 1_048_576,
+            truncated: true,
+        },
+        stderr: Captured {
+            text: "\u{1b}[1m\u{1b}[33mwarning\u{1b}[0m: unused variable `x`\r\n\u{1b}]8;;https://example.com\u{7}link\u{1b}]8;;\u{7}\n".to_string(),
+            total_bytes: 71,
+            truncated: false,
+        },
+    }
+}
+
+pub fn search_hits() -> Vec<SearchHit> {
+    let hit = |path: &str, title: &str, lines: (usize, usize), excerpt: &str| SearchHit {
+        section: DocSection {
+            path: path.to_string(),
+            title: title.to_string(),
+            start_line: lines.0,
+            end_line: lines.1,
+            text: excerpt.to_string(),
+        },
+        score: 10,
+        excerpt: excerpt.to_string(),
+    };
+    vec![
+        hit("README.md", "Install > Linux", (12, 30), "Run `cargo install devkit` to install."),
+        hit("docs/安装.md", "安装 > Windows", (1, 8), "下载安装包后运行 `devkit --version`。"),
+    ]
+}
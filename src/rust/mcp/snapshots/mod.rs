@@ -0,0 +1,255 @@
+//! Snapshot tests of every Markdown formatter
+//!
+//! Each formatter is fed shared fixtures and its output compared with a golden
+//! file in `golden/<name>.md`. A formatting change updates the golden files in
+//! the same commit, so the review shows exactly what users will read:
+//!
+//! ```text
+//! DEVKIT_UPDATE_SNAPSHOTS=1 cargo test snapshots
+//! git diff src/rust/mcp/snapshots/golden
+//! ```
+//!
+//! Every output is also checked to be well-formed Markdown, see
+//! [`assert_well_formed_markdown`].
+
+mod fixtures;
+
+use std::path::PathBuf;
+
+/// Set to rewrite the golden files instead of comparing against them
+const UPDATE_ENV: &str = "DEVKIT_UPDATE_SNAPSHOTS";
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/rust/mcp/snapshots/golden")
+        .join(format!("{}.md", name))
+}
+
+/// Compare `actual` with the golden file `name`, or rewrite it when [`UPDATE_ENV`] is set
+#[track_caller]
+fn assert_snapshot(name: &str, actual: &str) {
+    assert_well_formed_markdown(name, actual);
+
+    let path = golden_path(name);
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = match std::fs::read_to_string(&path) {
+        // Checkouts with autocrlf must not fail every snapshot
+        Ok(expected) => expected.replace("\r\n", "\n"),
+        Err(_) => panic!("no golden file {}; run with {}=1 to create it", path.display(), UPDATE_ENV),
+    };
+    if expected == actual {
+        return;
+    }
+
+    let line = expected.lines().zip(actual.lines()).position(|(e, a)| e != a).unwrap_or_else(|| {
+        expected.lines().count().min(actual.lines().count())
+    });
+    panic!(
+        "snapshot {} differs at line {}\n  expected: {:?}\n  actual:   {:?}\n\n--- actual ---\n{}\n--------------\n\
+         run with {}=1 to accept the new output",
+        name,
+        line + 1,
+        expected.lines().nth(line).unwrap_or("<end of file>"),
+        actual.lines().nth(line).unwrap_or("<end of file>"),
+        actual,
+        UPDATE_ENV
+    );
+}
+
+/// Structural checks every formatter output must pass
+///
+/// - code fences are balanced, so nothing after an unclosed fence renders as code
+/// - no ANSI escapes or other control characters besides newline and tab
+/// - no heading without text
+#[track_caller]
+fn assert_well_formed_markdown(name: &str, text: &str) {
+    let fences = text.lines().filter(|line| line.trim_start().starts_with("```")).count();
+    assert!(fences % 2 == 0, "{}: unbalanced code fences ({} fence lines)", name, fences);
+
+    if let Some(c) = text.chars().find(|c| c.is_control() && *c != '\n' && *c != '\t') {
+        panic!("{}: control character {:?} in output", name, c);
+    }
+
+    for (number, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level) && trimmed[level..].trim().is_empty() {
+            panic!("{}: empty heading on line {}", name, number + 1);
+        }
+    }
+}
+
+#[test]
+fn test_markdown_checks_reject_broken_output() {
+    let broken = |text: &'static str| std::panic::catch_unwind(|| assert_well_formed_markdown("broken", text)).is_err();
+    assert!(broken("```rust\nfn main() {}\n"));
+    assert!(broken("\u{1b}[31mred\u{1b}[0m"));
+    assert!(broken("# Title\n\n## \n"));
+    assert!(!broken("# Title\n\n```\ncode\n```\n#hashtag\n"));
+}
+
+#[cfg(feature = "docs")]
+mod docs {
+    use super::fixtures::*;
+    use super::assert_snapshot;
+    use crate::docs_client::mock::{MockResponse, MockServer};
+    use crate::docs_client::{Client, DocType, DocsConfig, DocsRequest, Transport};
+    use crate::utils::test_dir::TestDir;
+    use crate::mcp::tools::docs::DocsTool;
+    use crate::utils::locale::{self, Locale};
+
+    #[test]
+    fn test_docs_response() {
+        assert_snapshot("docs_response_full", &DocsTool::format_response(&full_response(), &request()));
+        assert_snapshot("docs_response_empty_snippets", &DocsTool::format_response(&empty_snippets(), &request()));
+        assert_snapshot("docs_response_unicode_titles", &DocsTool::format_response(&unicode_titles(), &request()));
+        assert_snapshot(
+            "docs_response_max_pagination",
+            &DocsTool::format_response(&max_pagination(), &detailed_request()),
+        );
+    }
+
+    /// Titles and hints follow the session language
+    #[tokio::test]
+    async fn test_docs_response_in_chinese() {
+        let (response, not_found) = locale::scope(Locale::Zh, async {
+            let mut response = max_pagination();
+            response.moved_to = Some("vercel/next".to_string());
+            (
+                DocsTool::format_response(&response, &detailed_request()),
+                DocsTool::format_not_found_with_suggestions("next", &suggestions(2)),
+            )
+        })
+        .await;
+        assert_snapshot("docs_response_zh", &response);
+        assert_snapshot("not_found_suggestions_zh", &not_found);
+    }
+
+    #[test]
+    fn test_docs_response_notes() {
+        let mut response = full_response();
+        response.doc_type = DocType::Info;
+        response.auto_fallback = true;
+        response.moved_to = Some("vercel/next".to_string());
+        assert_snapshot("docs_response_info_fallback_moved", &DocsTool::format_response(&response, &request()));
+    }
+
+    #[test]
+    fn test_not_found() {
+        assert_snapshot("not_found_no_suggestions", &DocsTool::format_not_found_no_suggestions("acme/unknown"));
+        assert_snapshot(
+            "not_found_one_suggestion",
+            &DocsTool::format_not_found_with_suggestions("nextjs", &suggestions(1)),
+        );
+        assert_snapshot(
+            "not_found_many_suggestions",
+            &DocsTool::format_not_found_with_suggestions("next", &suggestions(5)),
+        );
+    }
+
+    /// The shipped recording renders the same output on every run
+    #[tokio::test]
+    async fn test_replayed_recording() {
+        let transport = Transport::replay(recording_dir()).unwrap();
+        let client = Client::new(DocsConfig::default()).unwrap().with_transport(transport);
+        let request = DocsRequest { topic: Some("routing".to_string()), doc_type: Some(DocType::Auto), ..request() };
+        let response = client.docs(&request).await.unwrap();
+        assert_snapshot("docs_replayed_recording", &DocsTool::format_response(&response, &request));
+    }
+
+    /// A recorded session replays to byte-identical output without the upstream
+    #[tokio::test]
+    async fn test_record_then_replay_is_identical() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/docs/code/vercel/next.js" => MockResponse::json(200, include_str!("../../docs_client/fixtures/docs_full.json")),
+            _ => MockResponse::json(200, include_str!("../../docs_client/fixtures/docs_extra_fields.json")),
+        })
+        .await;
+        let dir = TestDir::reserve("replay");
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let request = detailed_request();
+
+        let recording = Client::new(config.clone()).unwrap().with_transport(Transport::record(dir.path()).unwrap());
+        let recorded = DocsTool::format_response(&recording.docs(&request).await.unwrap(), &request);
+        drop(server);
+
+        let replay = Client::new(config).unwrap().with_transport(Transport::replay(dir.path()).unwrap());
+        let replayed = DocsTool::format_response(&replay.docs(&request).await.unwrap(), &request);
+        assert_eq!(replayed.as_bytes(), recorded.as_bytes());
+    }
+
+    #[test]
+    fn test_cache_stats() {
+        let (stats, session, keys) = empty_cache_stats();
+        assert_snapshot("cache_stats_empty", &DocsTool::format_cache_stats(&stats, &session, &keys));
+        let (stats, session, keys) = busy_cache_stats();
+        assert_snapshot("cache_stats_busy", &DocsTool::format_cache_stats(&stats, &session, &keys));
+    }
+}
+
+mod memory {
+    use super::fixtures::*;
+    use super::assert_snapshot;
+    use crate::mcp::tools::memory::MemoryManager;
+
+    #[test]
+    fn test_project_info() {
+        assert_snapshot("memory_empty", &MemoryManager::format_project_info(&[]));
+        assert_snapshot("memory_blank_only", &MemoryManager::format_project_info(&blank_memories()));
+        assert_snapshot("memory_all_categories", &MemoryManager::format_project_info(&memories()));
+    }
+}
+
+mod git {
+    use super::fixtures::*;
+    use super::assert_snapshot;
+    use crate::mcp::tools::git::mcp::{format_diff, format_log, format_status};
+    use crate::mcp::tools::git::repo::RepoStatus;
+
+    #[test]
+    fn test_status() {
+        let clean = RepoStatus { branch: Some("main".to_string()), head: Some("1a2b3c4".to_string()), has_commits: true, ..Default::default() };
+        assert_snapshot("git_status_clean", &format_status(&clean));
+        assert_snapshot("git_status_unborn", &format_status(&RepoStatus { branch: Some("main".to_string()), ..Default::default() }));
+        assert_snapshot("git_status_changes", &format_status(&busy_status()));
+    }
+
+    #[test]
+    fn test_diff_and_log() {
+        assert_snapshot("git_diff_empty", &format_diff(&Default::default()));
+        assert_snapshot("git_diff_truncated", &format_diff(&truncated_diff()));
+        assert_snapshot("git_log_empty", &format_log(&[]));
+        assert_snapshot("git_log", &format_log(&commits()));
+    }
+}
+
+mod command {
+    use super::fixtures::*;
+    use super::assert_snapshot;
+    use crate::mcp::tools::command::mcp::format_output;
+
+    #[test]
+    fn test_command_output() {
+        assert_snapshot("command_success", &format_output("cargo --version", &finished_command(), 60));
+        assert_snapshot("command_timed_out", &format_output("make test", &timed_out_command(), 30));
+    }
+}
+
+mod project_docs {
+    use super::fixtures::*;
+    use super::assert_snapshot;
+    use crate::mcp::tools::project_docs::mcp::format_hits;
+    use crate::mcp::tools::project_docs::IndexStats;
+
+    #[test]
+    fn test_hits() {
+        let stats = IndexStats { files: 12, sections: 87, ..Default::default() };
+        assert_snapshot("project_docs_no_hits", &format_hits("websocket", &[], &stats));
+        let stats = IndexStats { truncated: true, ..stats };
+        assert_snapshot("project_docs_hits", &format_hits("install", &search_hits(), &stats));
+    }
+}
@@ -0,0 +1,145 @@
+//! Tracing spans for tool calls and HTTP requests
+//!
+//! The spans are always created; without a subscriber they cost next to
+//! nothing. Builds with the `otel` feature export them over OTLP/HTTP once
+//! `mcp_config.telemetry.enabled` is set and offline mode is off, see [`init`].
+//!
+//! | span                  | created for                           |
+//! |-----------------------|---------------------------------------|
+//! | `mcp.tool_call`       | every `tools/call`, on any transport  |
+//! | `http.server.request` | each request to the HTTP transport    |
+//! | `http.client.request` | each docs API request (upstream)      |
+
+#[cfg(feature = "otel")]
+pub mod otel;
+
+use rmcp::model::{CallToolResult, ErrorData as McpError};
+use tracing::field::Empty;
+use tracing::Span;
+
+use crate::config::{offline_mode, OfflineMode, TelemetryConfig};
+use crate::log_important;
+
+#[cfg(feature = "otel")]
+pub use otel::TelemetryGuard;
+
+/// Without the `otel` feature there is nothing to flush
+#[cfg(not(feature = "otel"))]
+pub struct TelemetryGuard;
+
+/// Start exporting spans when the config asks for it and offline mode is off
+///
+/// Failures are logged and the server keeps running without export. Keep the
+/// guard alive until shutdown; dropping it flushes pending spans.
+pub fn init(config: &TelemetryConfig) -> Option<TelemetryGuard> {
+    init_with(config, offline_mode())
+}
+
+fn init_with(config: &TelemetryConfig, offline: OfflineMode) -> Option<TelemetryGuard> {
+    if !config.enabled {
+        return None;
+    }
+    // Export sends spans to a collector, which offline mode rules out like any other outbound traffic
+    if let Err(e) = offline.check("OpenTelemetry export") {
+        log_important!(warn, "OpenTelemetry export disabled: {}", e);
+        return None;
+    }
+    #[cfg(feature = "otel")]
+    match otel::install(config) {
+        Ok(guard) => {
+            log_important!(info, "OpenTelemetry export enabled");
+            Some(guard)
+        }
+        Err(e) => {
+            log_important!(warn, "OpenTelemetry export disabled: {:#}", e);
+            None
+        }
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        log_important!(warn, "telemetry.enabled is set, but this build has no OpenTelemetry support (otel feature)");
+        None
+    }
+}
+
+/// Span around one tool call; the outcome is added by [`record_tool_outcome`]
+pub fn tool_call_span(tool: &str, request_id: &str, endpoint: &str) -> Span {
+    tracing::info_span!(
+        "mcp.tool_call",
+        mcp.tool.name = tool,
+        mcp.request.id = request_id,
+        mcp.endpoint = endpoint,
+        mcp.tool.is_error = Empty,
+        mcp.result.truncated = Empty,
+        mcp.dry_run = Empty,
+        error.code = Empty,
+        otel.status_code = Empty,
+        otel.status_description = Empty,
+    )
+}
+
+/// Mark the span failed when the call returned a JSON-RPC error
+///
+/// Tool results flagged `is_error` are answers, not failures, and only get an attribute.
+pub fn record_tool_outcome(span: &Span, result: &Result<CallToolResult, McpError>) {
+    match result {
+        Ok(result) => {
+            span.record("mcp.tool.is_error", result.is_error.unwrap_or(false));
+        }
+        Err(error) => {
+            span.record("error.code", error.code.0);
+            span.record("otel.status_code", "ERROR");
+            span.record("otel.status_description", error.message.as_ref());
+        }
+    }
+}
+
+#[cfg(feature = "http-server")]
+pub use middleware::trace_http_requests;
+
+#[cfg(feature = "http-server")]
+mod middleware {
+    use axum::extract::Request;
+    use axum::middleware::Next;
+    use axum::response::Response;
+    use tracing::field::Empty;
+    use tracing::Instrument;
+
+    /// Axum middleware: one `http.server.request` span per request
+    ///
+    /// With the `otel` feature a W3C `traceparent` header makes the span part
+    /// of the caller's trace.
+    pub async fn trace_http_requests(request: Request, next: Next) -> Response {
+        let span = tracing::info_span!(
+            "http.server.request",
+            otel.kind = "server",
+            http.request.method = %request.method(),
+            url.path = request.uri().path(),
+            http.response.status_code = Empty,
+            otel.status_code = Empty,
+        );
+        #[cfg(feature = "otel")]
+        super::otel::continue_remote_trace(&span, request.headers());
+
+        let response = next.run(request).instrument(span.clone()).await;
+        let status = response.status();
+        span.record("http.response.status_code", i64::from(status.as_u16()));
+        if status.is_server_error() {
+            span.record("otel.status_code", "ERROR");
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_mode_disables_export() {
+        let config = TelemetryConfig { enabled: true, endpoint: Some("http://127.0.0.1:9/v1/traces".to_string()), ..Default::default() };
+        for offline in [OfflineMode::resolve_with(None, Some("1"), None), OfflineMode::resolve_with(None, None, Some(true))] {
+            assert!(init_with(&config, offline).is_none(), "exported with offline mode {}", offline);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! OTLP/HTTP export of the devkit spans
+//!
+//! Spans are handed to a batch processor running on its own thread with a
+//! bounded queue: a slow or unreachable collector drops spans instead of
+//! delaying tool calls, and export errors never reach the caller.
+
+use anyhow::{bail, Result};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider, TracerProviderBuilder};
+use opentelemetry_sdk::Resource;
+use std::time::Duration;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
+
+use crate::config::TelemetryConfig;
+use crate::log_important;
+
+/// `service.name` resource attribute
+pub const SERVICE_NAME: &str = "devkit";
+
+/// Longest wait for pending spans on shutdown
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Timeout of one export request
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Flushes and stops the exporter when dropped, waiting at most [`SHUTDOWN_TIMEOUT`]
+pub struct TelemetryGuard {
+    provider: SdkTracerProvider,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown_with_timeout(SHUTDOWN_TIMEOUT) {
+            log_important!(warn, "OpenTelemetry shutdown: {}", e);
+        }
+    }
+}
+
+/// Build the exporter and install it as the global tracing subscriber
+pub fn install(config: &TelemetryConfig) -> Result<TelemetryGuard> {
+    let provider = build_provider(config)?;
+    tracing::subscriber::set_global_default(subscriber(&provider))?;
+    Ok(TelemetryGuard { provider })
+}
+
+/// Tracer provider exporting to the configured OTLP/HTTP endpoint
+pub fn build_provider(config: &TelemetryConfig) -> Result<SdkTracerProvider> {
+    let ratio = config.sample_ratio.unwrap_or(1.0);
+    if !(0.0..=1.0).contains(&ratio) {
+        bail!("telemetry.sample_ratio must be between 0 and 1, got {}", ratio);
+    }
+
+    let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_http().with_timeout(EXPORT_TIMEOUT);
+    if let Some(endpoint) = config.endpoint.as_deref().filter(|endpoint| !endpoint.trim().is_empty()) {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+    if !config.headers.is_empty() {
+        exporter = exporter.with_headers(config.headers.clone());
+    }
+    Ok(provider_builder(ratio).with_batch_exporter(exporter.build()?).build())
+}
+
+/// Resource and sampler shared by every provider; the exporter is added by the caller
+pub fn provider_builder(sample_ratio: f64) -> TracerProviderBuilder {
+    SdkTracerProvider::builder()
+        .with_resource(resource())
+        // A sampled caller keeps its trace complete regardless of the local ratio
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(sample_ratio))))
+}
+
+/// Service name, version and host of this process
+pub fn resource() -> Resource {
+    let mut attributes = vec![KeyValue::new("service.version", env!("CARGO_PKG_VERSION"))];
+    if let Some(host) = host_name() {
+        attributes.push(KeyValue::new("host.name", host));
+    }
+    Resource::builder().with_service_name(SERVICE_NAME).with_attributes(attributes).build()
+}
+
+/// Subscriber exporting the spans of this crate and ignoring those of dependencies
+pub fn subscriber(provider: &SdkTracerProvider) -> impl Subscriber + Send + Sync {
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(SERVICE_NAME))
+        .with_filter(Targets::new().with_target(SERVICE_NAME, Level::INFO));
+    tracing_subscriber::registry().with(layer)
+}
+
+/// Make `span` a child of the W3C trace context in `headers`, if there is one
+#[cfg(feature = "http-server")]
+pub fn continue_remote_trace(span: &tracing::Span, headers: &axum::http::HeaderMap) {
+    use opentelemetry::propagation::{Extractor, TextMapPropagator};
+    use opentelemetry::trace::TraceContextExt;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    struct HeaderExtractor<'a>(&'a axum::http::HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|name| name.as_str()).collect()
+        }
+    }
+
+    let context = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    if context.span().span_context().is_valid() {
+        let _ = span.set_parent(context);
+    }
+}
+
+fn host_name() -> Option<String> {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .chain(std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::boundary::run_tool_call;
+    use crate::mcp::telemetry::{record_tool_outcome, tool_call_span};
+    use crate::mcp::utils::ToolError;
+    use opentelemetry::trace::{SpanKind, Status};
+    use opentelemetry::Value;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SpanData};
+    use std::time::Instant;
+    use tracing::Instrument;
+
+    fn attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+        span.attributes.iter().find(|kv| kv.key.as_str() == key).map(|kv| &kv.value)
+    }
+
+    /// Run `f` on a current-thread runtime with spans recorded in memory
+    fn record<F: std::future::Future>(sample_ratio: f64, f: impl FnOnce() -> F) -> Vec<SpanData> {
+        let exporter = InMemorySpanExporter::default();
+        let provider = provider_builder(sample_ratio).with_simple_exporter(exporter.clone()).build();
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        tracing::subscriber::with_default(subscriber(&provider), || {
+            runtime.block_on(f());
+        });
+        provider.force_flush().unwrap();
+        exporter.get_finished_spans().unwrap()
+    }
+
+    #[test]
+    fn test_tool_call_span_parents_upstream_requests() {
+        let spans = record(1.0, || async {
+            let span = tool_call_span("docs", "7", "default");
+            let result = run_tool_call("docs", "7", async {
+                let request = reqwest::Request::new(reqwest::Method::GET, "https://docs.example/api/v1/search?query=x".parse().unwrap());
+                crate::docs_client::upstream_span(&request).in_scope(|| ());
+                Err(ToolError::Failed(anyhow::anyhow!("boom")))
+            })
+            .instrument(span.clone())
+            .await;
+            record_tool_outcome(&span, &result);
+        });
+
+        let tool = spans.iter().find(|span| span.name == "mcp.tool_call").expect("tool call span");
+        let upstream = spans.iter().find(|span| span.name == "http.client.request").expect("upstream span");
+        assert_eq!(upstream.parent_span_id, tool.span_context.span_id());
+        assert_eq!(upstream.span_context.trace_id(), tool.span_context.trace_id());
+        assert_eq!(upstream.span_kind, SpanKind::Client);
+        assert_eq!(attribute(upstream, "url.path"), Some(&Value::from("/api/v1/search")));
+
+        assert_eq!(attribute(tool, "mcp.tool.name"), Some(&Value::from("docs")));
+        assert_eq!(attribute(tool, "mcp.request.id"), Some(&Value::from("7")));
+        assert_eq!(attribute(tool, "mcp.endpoint"), Some(&Value::from("default")));
+        assert_eq!(attribute(tool, "error.code"), Some(&Value::I64(-32603)));
+        assert!(matches!(tool.status, Status::Error { .. }));
+    }
+
+    #[test]
+    fn test_sample_ratio_zero_exports_nothing() {
+        let spans = record(0.0, || async {
+            tool_call_span("memory", "1", "default").in_scope(|| ());
+        });
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn test_resource_names_service() {
+        let resource = resource();
+        let get = |key: &str| resource.get(&opentelemetry::Key::from(key.to_string()));
+        assert_eq!(get("service.name"), Some(Value::from(SERVICE_NAME)));
+        assert_eq!(get("service.version"), Some(Value::from(env!("CARGO_PKG_VERSION"))));
+    }
+
+    #[cfg(feature = "http-server")]
+    #[test]
+    fn test_http_span_continues_remote_trace() {
+        use axum::http::StatusCode;
+        use axum::routing::get;
+
+        let spans = record(1.0, || async {
+            let app = axum::Router::new()
+                .route("/health", get(|| async { StatusCode::SERVICE_UNAVAILABLE }))
+                .layer(axum::middleware::from_fn(crate::mcp::telemetry::trace_http_requests));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/health", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            reqwest::Client::new()
+                .get(&url)
+                .header("traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+                .send()
+                .await
+                .unwrap();
+        });
+
+        let span = spans.iter().find(|span| span.name == "http.server.request").expect("http span");
+        assert_eq!(span.span_context.trace_id().to_string(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(span.parent_span_id.to_string(), "00f067aa0ba902b7");
+        assert_eq!(span.span_kind, SpanKind::Server);
+        assert_eq!(attribute(span, "http.request.method"), Some(&Value::from("GET")));
+        assert_eq!(attribute(span, "http.response.status_code"), Some(&Value::I64(503)));
+        assert!(matches!(span.status, Status::Error { .. }));
+    }
+
+    #[test]
+    fn test_unreachable_collector_does_not_block() {
+        // Accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = TelemetryConfig {
+            enabled: true,
+            endpoint: Some(format!("http://{}/v1/traces", listener.local_addr().unwrap())),
+            ..Default::default()
+        };
+        let provider = build_provider(&config).unwrap();
+        let started = Instant::now();
+        tracing::subscriber::with_default(subscriber(&provider), || {
+            for i in 0..100 {
+                tool_call_span("memory", &i.to_string(), "default").in_scope(|| ());
+            }
+        });
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let started = Instant::now();
+        drop(TelemetryGuard { provider });
+        assert!(started.elapsed() < SHUTDOWN_TIMEOUT + Duration::from_secs(2));
+    }
+}
@@ -3,6 +3,7 @@ use tauri::{AppHandle, Emitter, State};
 use crate::config::{AppState, save_config};
 use crate::network::proxy::{ProxyDetector, ProxyInfo, ProxyType};
 use super::AcemcpTool;
+use crate::utils::text;
 use super::types::{AcemcpRequest, ProjectIndexStatus, ProjectsIndexStatus, ProjectFilesStatus, DetectedProxy, ProxySpeedTestResult, SpeedTestMetric, SpeedTestProgress, SpeedTestStageStatus};
 use reqwest;
 
@@ -264,7 +265,7 @@ pub async fn test_acemcp_connection(
                 let msg = format!("API 端点返回错误状态: {} {}", status.as_u16(), status.as_str());
                 Ok(TestConnectionResult {
                     success: false,
-                    message: format!("{} - 响应: {}", msg, if body.len() > 200 { format!("{}...", &body[..200]) } else { body }),
+                    message: format!("{} - 响应: {}", msg, text::truncate_with(&body, 200, "...")),
                 })
             }
         }
@@ -1230,11 +1231,7 @@ pub async fn test_acemcp_proxy_speed(
                   None);
 
     for q in queries {
-        let display_q = if q.len() > 30 {
-            format!("{}...", &q[..30])
-        } else {
-            q.clone()
-        };
+        let display_q = text::truncate_with(&q, 30, "...");
         
         log::debug!("🔎 [SpeedTest] 执行搜索: {}", display_q);
 
@@ -1315,11 +1312,7 @@ pub async fn test_acemcp_proxy_speed(
                        preview.total_matches, preview.response_length);
             // 输出第一个片段的预览（截断显示）
             if let Some(first_snippet) = preview.snippets.first() {
-                let snippet_preview = if first_snippet.snippet.len() > 100 {
-                    format!("{}...", &first_snippet.snippet[..100])
-                } else {
-                    first_snippet.snippet.clone()
-                };
+                let snippet_preview = text::truncate_with(&first_snippet.snippet, 100, "...");
                 // 去除换行符以便日志更整洁
                 let snippet_oneline = snippet_preview.replace('\n', " ↵ ");
                 log::debug!("📝 [SpeedTest] 首个片段: file={}, content={}", 
@@ -1565,11 +1558,8 @@ pub async fn test_acemcp_proxy_speed(
     if !all_success {
         if let Some(first_fail) = metrics.iter().find(|m| !m.success) {
             if let Some(err) = &first_fail.error {
-                let mut err_short = err.replace('\n', " / ");
-                if err_short.len() > 120 {
-                    err_short.truncate(120);
-                    err_short.push_str("...");
-                }
+                let err_oneline = err.replace('\n', " / ");
+                let err_short = text::truncate_with(&err_oneline, 120, "...");
                 recommendation = format!("{} | 失败示例: {} - {}", recommendation, first_fail.name, err_short);
             } else {
                 recommendation = format!("{} | 存在失败项", recommendation);
@@ -1979,11 +1969,7 @@ async fn search_endpoint(client: &reqwest::Client, url: &str, token: &str, paylo
     let body = response.text().await.unwrap_or_default();
     
     // 输出原始响应内容用于调试（截断显示）
-    let body_preview = if body.len() > 500 {
-        format!("{}... (total {}B)", &body[..500], body.len())
-    } else {
-        body.clone()
-    };
+    let body_preview = text::truncate_with(&body, 500, &format!("... (total {}B)", body.len()));
     log::debug!("🔍 [SpeedTest] 搜索原始响应: {}", body_preview);
     
     let preview = parse_search_result_preview(&body);
@@ -2005,7 +1991,7 @@ fn parse_search_result_preview(body: &str) -> Option<super::types::SearchResultP
         Ok(v) => v,
         Err(e) => {
             log::warn!("⚠️ [SpeedTest] 搜索响应不是有效 JSON: error={}, body={}", e, 
-                      if body.len() > 100 { &body[..100] } else { body });
+                      text::prefix(body, 100));
             // 如果不是 JSON，返回基本信息
             return Some(SearchResultPreview {
                 total_matches: 0,
@@ -2110,11 +2096,7 @@ fn parse_search_result_preview(body: &str) -> Option<super::types::SearchResultP
                 
                 snippets.push(SearchResultSnippet {
                     file_path,
-                    snippet: if snippet_content.len() > 800 {
-                        format!("{}...", &snippet_content[..800])
-                    } else {
-                        snippet_content
-                    },
+                    snippet: text::truncate_with(&snippet_content, 800, "...").into_owned(),
                     line_number: None,
                 });
             }
@@ -2163,11 +2145,7 @@ fn parse_search_result_preview(body: &str) -> Option<super::types::SearchResultP
                 if !snippet.is_empty() {
                     snippets.push(SearchResultSnippet {
                         file_path,
-                        snippet: if snippet.len() > 200 {
-                            format!("{}...", &snippet[..200])
-                        } else {
-                            snippet
-                        },
+                        snippet: text::truncate_with(&snippet, 200, "...").into_owned(),
                         line_number: None,
                     });
                 }
@@ -2191,11 +2169,7 @@ fn parse_search_result_preview(body: &str) -> Option<super::types::SearchResultP
                     
                     snippets.push(SearchResultSnippet {
                         file_path,
-                        snippet: if snippet.len() > 200 {
-                            format!("{}...", &snippet[..200])
-                        } else {
-                            snippet
-                        },
+                        snippet: text::truncate_with(&snippet, 200, "...").into_owned(),
                         line_number: None,
                     });
                 }
@@ -2228,11 +2202,7 @@ fn parse_search_result_preview(body: &str) -> Option<super::types::SearchResultP
                 if !snippet.is_empty() {
                     snippets.push(SearchResultSnippet {
                         file_path,
-                        snippet: if snippet.len() > 200 {
-                            format!("{}...", &snippet[..200])
-                        } else {
-                            snippet
-                        },
+                        snippet: text::truncate_with(&snippet, 200, "...").into_owned(),
                         line_number,
                     });
                 }
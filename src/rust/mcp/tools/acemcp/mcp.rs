@@ -5,7 +5,7 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
@@ -26,8 +26,11 @@ use super::types::{
     FileIndexStatus,
     FileIndexStatusKind,
 };
+use crate::config::offline_mode;
+use crate::docs_client::HttpTuning;
 use crate::log_debug;
 use crate::log_important;
+use crate::mcp::utils::offline_result;
 // 代理模块（在 create_acemcp_client 中使用）
 
 /// Acemcp工具实现
@@ -41,6 +44,11 @@ impl AcemcpTool {
             request.project_root_path, request.query
         );
 
+        // 离线模式下不访问远程索引服务
+        if let Err(e) = offline_mode().check("codebase search") {
+            return Ok(offline_result(&e));
+        }
+
         // 读取配置
         let mut acemcp_config = Self::get_acemcp_config()
             .await
@@ -132,6 +140,11 @@ impl AcemcpTool {
             request.project_root_path, request.query
         );
 
+        // 离线模式下不访问远程索引服务
+        if let Err(e) = offline_mode().check("codebase search") {
+            return Ok(offline_result(&e));
+        }
+
         // 读取配置
         let mut acemcp_config = Self::get_acemcp_config()
             .await
@@ -247,6 +260,7 @@ impl AcemcpTool {
         // 从配置文件中读取acemcp配置
         let config = crate::config::load_standalone_config()
             .map_err(|e| anyhow::anyhow!("读取配置文件失败: {}", e))?;
+        let http = config.mcp_config.docs_http_tuning();
 
         Ok(AcemcpConfig {
             base_url: config.mcp_config.acemcp_base_url,
@@ -264,6 +278,8 @@ impl AcemcpTool {
             proxy_type: config.mcp_config.acemcp_proxy_type,
             proxy_username: config.mcp_config.acemcp_proxy_username,
             proxy_password: config.mcp_config.acemcp_proxy_password,
+            timeout_secs: config.mcp_config.acemcp_timeout_secs,
+            http,
         })
     }
 
@@ -496,10 +512,9 @@ where
 
     let project_status = all_status.projects
         .entry(normalized_root.clone())
-        .or_insert_with(|| {
-            let mut status = ProjectIndexStatus::default();
-            status.project_root = normalized_root;
-            status
+        .or_insert_with(|| ProjectIndexStatus {
+            project_root: normalized_root,
+            ..Default::default()
         });
 
     updater(project_status);
@@ -516,10 +531,9 @@ fn get_project_status(project_root: &str) -> ProjectIndexStatus {
         .to_string_lossy()
         .replace('\\', "/");
 
-    all_status.projects.get(&normalized_root).cloned().unwrap_or_else(|| {
-        let mut status = ProjectIndexStatus::default();
-        status.project_root = normalized_root;
-        status
+    all_status.projects.get(&normalized_root).cloned().unwrap_or_else(|| ProjectIndexStatus {
+        project_root: normalized_root,
+        ..Default::default()
     })
 }
 
@@ -584,7 +598,7 @@ fn split_content(path: &str, content: &str, max_lines: usize) -> Vec<BlobItem> {
     }
 
     // 计算需要的 chunk 数量
-    let num_chunks = (total_lines + max_lines - 1) / max_lines;
+    let num_chunks = total_lines.div_ceil(max_lines);
     let mut blobs = Vec::new();
 
     // 按 chunk 索引分割（从 0 开始，但显示时从 1 开始）
@@ -656,7 +670,7 @@ fn build_gitignore(root: &Path) -> Option<Gitignore> {
     let gi_path = root.join(".gitignore");
     if gi_path.exists() {
         if builder.add(gi_path).is_some() { return None; }
-        return match builder.build() { Ok(gi) => Some(gi), Err(_) => None };
+        return builder.build().ok();
     }
     None
 }
@@ -958,7 +972,7 @@ pub(crate) async fn update_index(config: &AcemcpConfig, project_root_path: &str)
     let mut failed_batches: Vec<usize> = Vec::new();
     
     if !new_blobs.is_empty() {
-        let total_batches = (new_blobs.len() + batch_size - 1) / batch_size;
+        let total_batches = new_blobs.len().div_ceil(batch_size);
         log_important!(info,
             "=== 开始批量上传代码索引 ==="
         );
@@ -1088,7 +1102,7 @@ pub(crate) async fn update_index(config: &AcemcpConfig, project_root_path: &str)
 
     // 合并并保存 projects.json（与 Python 版本保持一致）
     // 只保留当前项目中仍然存在的 blob 的哈希值（自动删除已删除的 blob）
-    let all_blob_names: Vec<String> = existing_hashes.into_iter().chain(uploaded_names.into_iter()).collect();
+    let all_blob_names: Vec<String> = existing_hashes.into_iter().chain(uploaded_names).collect();
     projects.0.insert(normalized_root.clone(), all_blob_names.clone());
     if let Ok(s) = serde_json::to_string_pretty(&projects) { let _ = fs::write(projects_path, s); }
 
@@ -1123,7 +1137,7 @@ pub(crate) async fn update_index(config: &AcemcpConfig, project_root_path: &str)
 
     // 首次成功索引时，写入 store 记忆
     if is_first_success {
-        let _ = write_index_memory_to_store(project_root_path, config);
+        write_index_memory_to_store(project_root_path, config);
     }
 
     log_important!(info, "索引更新完成，共 {} 个 blobs", blob_names.len());
@@ -1258,58 +1272,101 @@ async fn search_only(config: &AcemcpConfig, project_root_path: &str, query: &str
     }
 }
 
-/// 创建支持代理的 HTTP 客户端
-/// 根据配置决定是否使用代理
+/// 默认请求超时
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// 进程内共用的 HTTP 客户端（索引上传与搜索共用连接池），代理或连接参数变化时重建
+static SHARED_CLIENT: Mutex<Option<(ClientKey, Client)>> = Mutex::new(None);
+
+/// 决定 HTTP 客户端的配置项
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClientKey {
+    /// (代理 URL, 用户名, 密码)，None 表示直连
+    proxy: Option<(String, String, String)>,
+    timeout_secs: u64,
+    http: HttpTuning,
+}
+
+impl ClientKey {
+    fn from_config(config: &AcemcpConfig) -> anyhow::Result<Self> {
+        let proxy = if config.proxy_enabled.unwrap_or(false) {
+            let host = config.proxy_host.clone().unwrap_or_else(|| "127.0.0.1".to_string());
+            let port = config.proxy_port.unwrap_or(7890);
+            let proxy_type = config.proxy_type.clone().unwrap_or_else(|| "http".to_string());
+
+            // 校验代理类型，避免拼接出无效 URL
+            match proxy_type.as_str() {
+                "http" | "https" | "socks5" => {}
+                other => anyhow::bail!("不支持的代理类型: {}（仅支持 http/https/socks5）", other),
+            }
+
+            let username = config.proxy_username.as_deref().unwrap_or("").trim().to_string();
+            let password = config.proxy_password.clone().unwrap_or_default();
+            Some((format!("{}://{}:{}", proxy_type, host, port), username, password))
+        } else {
+            None
+        };
+        Ok(Self {
+            proxy,
+            timeout_secs: config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS),
+            http: config.http.clone(),
+        })
+    }
+}
+
+/// 获取支持代理的 HTTP 客户端
+/// 配置不变时复用同一个客户端及其连接池
 fn create_acemcp_client(config: &AcemcpConfig) -> anyhow::Result<Client> {
-    let mut client_builder = Client::builder()
-        .timeout(Duration::from_secs(60));
-    
-    // 检查是否启用代理
-    if config.proxy_enabled.unwrap_or(false) {
-        let host = config.proxy_host.clone().unwrap_or_else(|| "127.0.0.1".to_string());
-        let port = config.proxy_port.unwrap_or(7890);
-        let proxy_type = config.proxy_type.clone().unwrap_or_else(|| "http".to_string());
-        
-        // 校验代理类型，避免拼接出无效 URL
-        match proxy_type.as_str() {
-            "http" | "https" | "socks5" => {}
-            other => anyhow::bail!("不支持的代理类型: {}（仅支持 http/https/socks5）", other),
+    let key = ClientKey::from_config(config)?;
+    let mut shared = SHARED_CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((current, client)) = shared.as_ref() {
+        if *current == key {
+            return Ok(client.clone());
         }
+    }
+
+    let client = build_acemcp_client(&key)?;
+    *shared = Some((key, client.clone()));
+    Ok(client)
+}
+
+/// 根据配置构建 HTTP 客户端
+fn build_acemcp_client(key: &ClientKey) -> anyhow::Result<Client> {
+    let mut client_builder = Client::builder()
+        .timeout(Duration::from_secs(key.timeout_secs))
+        .pool_max_idle_per_host(key.http.pool_max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(key.http.pool_idle_timeout_secs))
+        .tcp_keepalive(key.http.tcp_keepalive_secs.map(Duration::from_secs));
+    if !key.http.http2 {
+        client_builder = client_builder.http1_only();
+    }
 
+    if let Some((proxy_url, username, password)) = &key.proxy {
         // 仅用于日志提示（避免泄露密码）
-        let has_auth = config
-            .proxy_username
-            .as_deref()
-            .map(|u| !u.trim().is_empty())
-            .unwrap_or(false);
-
-        if has_auth {
-            log_important!(info, "🔧 使用代理: {}://{}:{}（带认证）", proxy_type, host, port);
+        if username.is_empty() {
+            log_important!(info, "🔧 使用代理: {}", proxy_url);
         } else {
-            log_important!(info, "🔧 使用代理: {}://{}:{}", proxy_type, host, port);
+            log_important!(info, "🔧 使用代理: {}（带认证）", proxy_url);
         }
-        
-        // 构建代理 URL
-        let proxy_url = format!("{}://{}:{}", proxy_type, host, port);
-        
+
         // 使用 Proxy::all() 让所有请求都走代理
-        let mut reqwest_proxy = reqwest::Proxy::all(&proxy_url)
+        let mut reqwest_proxy = reqwest::Proxy::all(proxy_url)
             .map_err(|e| anyhow::anyhow!("创建代理失败: {}", e))?;
 
         // 代理认证（Basic Auth）
-        if let Some(username) = config.proxy_username.as_deref() {
-            let username = username.trim();
-            if !username.is_empty() {
-                let password = config.proxy_password.as_deref().unwrap_or("");
-                reqwest_proxy = reqwest_proxy.basic_auth(username, password);
-            }
+        if !username.is_empty() {
+            reqwest_proxy = reqwest_proxy.basic_auth(username, password);
         }
 
         client_builder = client_builder.proxy(reqwest_proxy);
+    } else if let Some(proxy) = key.http.proxy()? {
+        // 未启用 acemcp 代理时使用共用的上游代理
+        client_builder = client_builder.proxy(proxy);
     } else {
         log_debug!("使用直连模式（未启用代理）");
     }
-    
+    log_debug!("acemcp HTTP 客户端: timeout={}s, {}", key.timeout_secs, key.http);
+
     client_builder.build()
         .map_err(|e| anyhow::anyhow!("构建 HTTP 客户端失败: {}", e))
 }
@@ -3,6 +3,7 @@
 
 pub mod mcp;
 pub mod types;
+#[cfg(feature = "gui")]
 pub mod commands;
 pub mod watcher;
 
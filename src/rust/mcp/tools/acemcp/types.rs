@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
+use crate::docs_client::HttpTuning;
+
 /// Acemcp搜索请求参数
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AcemcpRequest {
@@ -43,6 +45,12 @@ pub struct AcemcpConfig {
     pub proxy_username: Option<String>,
     /// 代理密码（可选）
     pub proxy_password: Option<String>,
+    /// 请求超时（秒），None 为默认 60 秒
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// 上游 HTTP 客户端参数（连接池、HTTP/2、TCP keepalive），与 Docs 共用配置
+    #[serde(default)]
+    pub http: HttpTuning,
 }
 
 
@@ -24,6 +24,12 @@ pub struct WatcherManager {
     auto_index_enabled: Arc<Mutex<bool>>,
 }
 
+impl Default for WatcherManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl WatcherManager {
     /// 创建新的监听器管理器
     pub fn new() -> Self {
@@ -124,7 +130,7 @@ impl WatcherManager {
         let project_root_clone = normalized_root.clone();
         let config_fallback = config.clone();
         tokio::spawn(async move {
-            while let Some(_) = rx.recv().await {
+            while rx.recv().await.is_some() {
                 log_important!(info, "触发自动索引更新: project_root={}", project_root_clone);
                 
                 // 每次触发时读取最新配置，避免“用户修改配置但监听仍沿用旧配置”的情况
@@ -197,7 +203,7 @@ impl WatcherManager {
 
 /// 全局监听器管理器实例
 static WATCHER_MANAGER: once_cell::sync::Lazy<WatcherManager> =
-    once_cell::sync::Lazy::new(|| WatcherManager::new());
+    once_cell::sync::Lazy::new(WatcherManager::new);
 
 /// 获取全局监听器管理器
 pub fn get_watcher_manager() -> &'static WatcherManager {
@@ -0,0 +1,203 @@
+//! Command line parsing and allowlist matching
+//!
+//! Commands run without a shell, so the requested line is split into argv with
+//! shell-like quoting and compared token by token against the configured
+//! templates: literal tokens must be equal, `{}` takes exactly one argument and
+//! a trailing `{...}` takes any remaining arguments.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::CommandRule;
+use crate::mcp::utils::decode_and_normalize_path;
+
+/// Placeholder for exactly one argument
+pub const ONE_ARG: &str = "{}";
+/// Placeholder for the remaining arguments, only valid as the last token
+pub const REST_ARGS: &str = "{...}";
+
+/// Unquoted tokens that only make sense to a shell
+const SHELL_OPERATORS: &[&str] = &["&&", "||", ";", "|", "&", ">", ">>", "<", "2>", "2>&1"];
+
+/// Split a command line into arguments, honouring single and double quotes
+pub fn split_command_line(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    // Whether the current token exists, so that `''` yields an empty argument
+    let mut in_token = false;
+    // Whether any part of the current token was quoted
+    let mut quoted = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_token = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => anyhow::bail!("Unclosed single quote in command"),
+                    }
+                }
+            }
+            '"' => {
+                in_token = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => anyhow::bail!("Unclosed double quote in command"),
+                        },
+                        Some(c) => current.push(c),
+                        None => anyhow::bail!("Unclosed double quote in command"),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_token {
+                    push_arg(&mut args, std::mem::take(&mut current), quoted)?;
+                    in_token = false;
+                    quoted = false;
+                }
+            }
+            c => {
+                in_token = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_token {
+        push_arg(&mut args, current, quoted)?;
+    }
+    if args.is_empty() {
+        anyhow::bail!("Command is empty");
+    }
+    Ok(args)
+}
+
+fn push_arg(args: &mut Vec<String>, arg: String, quoted: bool) -> Result<()> {
+    if !quoted && (SHELL_OPERATORS.contains(&arg.as_str()) || arg.contains('`') || arg.contains("$(")) {
+        anyhow::bail!(
+            "Commands run without a shell; '{}' is not supported. Pipes, redirects and chaining must be separate calls",
+            arg
+        );
+    }
+    args.push(arg);
+    Ok(())
+}
+
+/// Whether `argv` fits the template
+pub fn template_matches(template: &[String], argv: &[String]) -> bool {
+    let mut args = argv.iter();
+    for (i, token) in template.iter().enumerate() {
+        if token == REST_ARGS && i == template.len() - 1 {
+            return true;
+        }
+        match args.next() {
+            Some(_) if token == ONE_ARG => {}
+            Some(arg) if arg == token => {}
+            _ => return false,
+        }
+    }
+    args.next().is_none()
+}
+
+/// First rule allowing `argv` in `project`; project-specific rules win over global ones
+pub fn find_rule<'a>(rules: &'a [CommandRule], project: &Path, argv: &[String]) -> Option<&'a CommandRule> {
+    let matches = |rule: &&CommandRule| {
+        split_command_line(&rule.template)
+            .map(|template| template_matches(&template, argv))
+            .unwrap_or(false)
+    };
+    rules
+        .iter()
+        .filter(|rule| rule.project.as_deref().is_some_and(|p| same_project(p, project)))
+        .find(matches)
+        .or_else(|| rules.iter().filter(|rule| rule.project.is_none()).find(matches))
+}
+
+/// Templates usable in `project`, for the rejection message
+pub fn allowed_templates(rules: &[CommandRule], project: &Path) -> Vec<String> {
+    rules
+        .iter()
+        .filter(|rule| rule.project.as_deref().is_none_or(|p| same_project(p, project)))
+        .map(|rule| rule.template.clone())
+        .collect()
+}
+
+fn same_project(configured: &str, project: &Path) -> bool {
+    decode_and_normalize_path(configured)
+        .ok()
+        .and_then(|path| Path::new(&path).canonicalize().ok())
+        .is_some_and(|path| path == project)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(line: &str) -> Vec<String> {
+        split_command_line(line).unwrap()
+    }
+
+    fn rule(template: &str, project: Option<&str>) -> CommandRule {
+        CommandRule {
+            template: template.to_string(),
+            project: project.map(|p| p.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_split_command_line() {
+        assert_eq!(argv("cargo  test -p devkit"), ["cargo", "test", "-p", "devkit"]);
+        assert_eq!(argv(r#"git commit -m "fix \"quoted\" bug" ''"#), ["git", "commit", "-m", "fix \"quoted\" bug", ""]);
+        assert_eq!(argv("echo 'a && b' \"|\""), ["echo", "a && b", "|"]);
+
+        for line in ["cargo test && rm -rf /", "ls | wc", "echo $(whoami)", "cat `x`", "'open", "   "] {
+            assert!(split_command_line(line).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_template_matching() {
+        let template = argv("cargo test {...}");
+        assert!(template_matches(&template, &argv("cargo test")));
+        assert!(template_matches(&template, &argv("cargo test -p devkit -- --nocapture")));
+        assert!(!template_matches(&template, &argv("cargo build")));
+        assert!(!template_matches(&template, &argv("cargo")));
+
+        let template = argv("npm run {}");
+        assert!(template_matches(&template, &argv("npm run lint")));
+        assert!(!template_matches(&template, &argv("npm run")));
+        assert!(!template_matches(&template, &argv("npm run lint --fix")));
+
+        // {...} in the middle is a literal token
+        assert!(!template_matches(&argv("a {...} b"), &argv("a x b")));
+    }
+
+    #[test]
+    fn test_project_rules_take_precedence() {
+        let project = std::env::temp_dir().canonicalize().unwrap();
+        let other = project.join("devkit-no-such-project");
+        let rules = vec![
+            rule("make {...}", None),
+            rule("make test", Some(&project.to_string_lossy())),
+            rule("make deploy", Some(&other.to_string_lossy())),
+        ];
+
+        let found = find_rule(&rules, &project, &argv("make test")).unwrap();
+        assert_eq!(found.project.as_deref(), Some(project.to_string_lossy().as_ref()));
+        assert_eq!(find_rule(&rules, &project, &argv("make deploy")).unwrap().template, "make {...}");
+        assert!(find_rule(&rules, &project, &argv("rm -rf target")).is_none());
+        assert_eq!(allowed_templates(&rules, &project), ["make {...}", "make test"]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Confirmation step for commands flagged `requires_approval`
+
+use anyhow::Result;
+
+use crate::mcp::types::{DishResponse, PopupRequest};
+use crate::mcp::utils::generate_request_id;
+
+/// Popup option that allows the command
+pub const RUN_OPTION: &str = "Run";
+/// Popup option that refuses the command
+pub const REJECT_OPTION: &str = "Reject";
+
+/// What the user is asked to confirm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalRequest {
+    pub project: String,
+    pub command: String,
+    /// Allowlist template the command matched
+    pub template: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Approval {
+    Approved,
+    Rejected { reason: Option<String> },
+}
+
+/// Decides whether a command that requires approval may run
+///
+/// Called from a blocking thread; implementations may wait for the user.
+pub trait Approver: Send + Sync {
+    fn approve(&self, request: &ApprovalRequest) -> Result<Approval>;
+}
+
+/// Asks through the confirmation popup
+pub struct PopupApprover;
+
+impl Approver for PopupApprover {
+    fn approve(&self, request: &ApprovalRequest) -> Result<Approval> {
+        let popup = PopupRequest {
+            id: generate_request_id(),
+            message: format!(
+                "An agent wants to run a command in `{}`:\n\n```\n{}\n```\n\nAllowed by the template `{}`. Choose **{}** to execute it.",
+                request.project, request.command, request.template, RUN_OPTION
+            ),
+            menu: Some(vec![RUN_OPTION.to_string(), REJECT_OPTION.to_string()]),
+            chalkboard: true,
+            project_root_path: Some(request.project.clone()),
+            diff: None,
+        };
+        let response = crate::mcp::handlers::create_tauri_popup(&popup)?;
+        Ok(decision_from_response(&response))
+    }
+}
+
+/// Used where no popup can be shown: approval-gated commands never run
+pub struct NoApprover;
+
+impl Approver for NoApprover {
+    fn approve(&self, _request: &ApprovalRequest) -> Result<Approval> {
+        Ok(Approval::Rejected {
+            reason: Some("This command requires approval, but the confirmation popup is not available here".to_string()),
+        })
+    }
+}
+
+/// Approved only when the user picked the run option and not the reject option
+pub fn decision_from_response(response: &str) -> Approval {
+    let trimmed = response.trim();
+    if trimmed == "CANCELLED" || trimmed == "\"CANCELLED\"" {
+        return Approval::Rejected { reason: Some("Cancelled by the user".to_string()) };
+    }
+    match serde_json::from_str::<DishResponse>(trimmed) {
+        Ok(dish) => {
+            let picked = |option: &str| dish.toppings.iter().any(|t| t == option);
+            if picked(RUN_OPTION) && !picked(REJECT_OPTION) {
+                Approval::Approved
+            } else {
+                Approval::Rejected { reason: dish.note.filter(|note| !note.trim().is_empty()) }
+            }
+        }
+        Err(_) => Approval::Rejected { reason: Some(trimmed.to_string()).filter(|text| !text.is_empty()) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::types::build_serve_response;
+
+    fn response(note: Option<&str>, toppings: &[&str]) -> String {
+        build_serve_response(
+            note.map(|n| n.to_string()),
+            toppings.iter().map(|t| t.to_string()).collect(),
+            vec![],
+            Some("req".to_string()),
+            "popup",
+        )
+    }
+
+    #[test]
+    fn test_decision_from_response() {
+        assert_eq!(decision_from_response(&response(None, &[RUN_OPTION])), Approval::Approved);
+        assert_eq!(
+            decision_from_response(&response(Some("not on main"), &[REJECT_OPTION])),
+            Approval::Rejected { reason: Some("not on main".to_string()) }
+        );
+        // A note alone does not approve
+        assert_eq!(
+            decision_from_response(&response(Some("sure"), &[])),
+            Approval::Rejected { reason: Some("sure".to_string()) }
+        );
+        assert_eq!(
+            decision_from_response("CANCELLED"),
+            Approval::Rejected { reason: Some("Cancelled by the user".to_string()) }
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError, Tool, ToolAnnotations};
+use serde_json::json;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::allowlist::{allowed_templates, find_rule, split_command_line};
+use super::approval::{Approval, ApprovalRequest, Approver};
+use super::runner::{self, env_secrets, redact, sanitized_env, CommandOutput, CommandSpec};
+use super::types::RunCommandRequest;
+use crate::config::CommandRule;
+use crate::mcp::utils::{decode_and_normalize_path, project_path_error, validate_project_path, ToolError};
+use crate::log_important;
+
+/// Timeout of rules without `timeout_secs`
+pub const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// Command settings read from the config file
+#[derive(Debug, Clone, Default)]
+pub struct CommandSettings {
+    pub rules: Vec<CommandRule>,
+    /// Extra variables inherited by every command
+    pub env: Vec<String>,
+    /// Values removed from captured output
+    pub secrets: Vec<String>,
+}
+
+impl CommandSettings {
+    fn load() -> Self {
+        let mut secrets = env_secrets();
+        let Ok(config) = crate::config::load_standalone_config() else {
+            return Self { secrets, ..Default::default() };
+        };
+        let mcp = config.mcp_config;
+        secrets.extend(mcp.docs_api_key.into_iter().chain(mcp.docs_api_keys));
+        secrets.extend(mcp.acemcp_token);
+        secrets.extend(mcp.acemcp_proxy_password);
+        secrets.push(config.telegram_config.bot_token);
+        Self { rules: mcp.commands, env: mcp.command_env, secrets }
+    }
+}
+
+/// Allowlisted command execution inside the project directory
+pub struct RunCommandTool;
+
+impl RunCommandTool {
+    pub async fn run(request: RunCommandRequest, approver: Arc<dyn Approver>) -> Result<CallToolResult, ToolError> {
+        if let Err(e) = validate_project_path(&request.project_root_path) {
+            return Err(project_path_error(format!(
+                "Path validation failed: {}\nOriginal path: {}",
+                e, request.project_root_path
+            ))
+            .into());
+        }
+        Self::execute(request, &CommandSettings::load(), approver).await
+    }
+
+    /// Match, confirm if required, run and report
+    pub async fn execute(
+        request: RunCommandRequest,
+        settings: &CommandSettings,
+        approver: Arc<dyn Approver>,
+    ) -> Result<CallToolResult, ToolError> {
+        let project = PathBuf::from(decode_and_normalize_path(&request.project_root_path)?)
+            .canonicalize()
+            .map_err(|e| project_path_error(format!("Failed to resolve {}: {}", request.project_root_path, e)))?;
+        let argv = split_command_line(&request.command)
+            .map_err(|e| McpError::invalid_params(e.to_string(), Some(json!({ "path": "command" }))))?;
+
+        let Some(rule) = find_rule(&settings.rules, &project, &argv) else {
+            return Err(McpError::invalid_params(
+                format!("Command is not in the allowlist: {}", request.command),
+                Some(json!({ "path": "command", "allowed": allowed_templates(&settings.rules, &project) })),
+            )
+            .into());
+        };
+
+        let rule_timeout = rule.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        let timeout_secs = request.timeout_secs.map_or(rule_timeout, |secs| secs.clamp(1, rule_timeout));
+
+        if rule.requires_approval {
+            let approval_request = ApprovalRequest {
+                project: project.to_string_lossy().into_owned(),
+                command: request.command.clone(),
+                template: rule.template.clone(),
+            };
+            let decision = tokio::task::spawn_blocking(move || approver.approve(&approval_request))
+                .await
+                .map_err(|e| anyhow::anyhow!("Approval task failed: {}", e))??;
+            if let Approval::Rejected { reason } = decision {
+                log_important!(info, "run_command rejected by user: {}", request.command);
+                let mut text = format!("Command was not approved: {}", request.command);
+                if let Some(reason) = &reason {
+                    text.push_str(&format!("\nReason: {}", reason));
+                }
+                return Ok(CallToolResult {
+                    content: vec![Content::text(text)],
+                    is_error: Some(true),
+                    meta: None,
+                    structured_content: Some(json!({ "command": request.command, "approved": false, "reason": reason })),
+                });
+            }
+        }
+
+        let mut env_names = settings.env.clone();
+        env_names.extend(rule.env.iter().cloned());
+        let spec = CommandSpec {
+            argv: argv.clone(),
+            cwd: project.clone(),
+            env: sanitized_env(&env_names),
+            timeout: Duration::from_secs(timeout_secs),
+        };
+
+        log_important!(info, "run_command: {} (template {}, timeout {}s)", request.command, rule.template, timeout_secs);
+        let mut output = match runner::run(&spec).await {
+            Ok(output) => output,
+            Err(e) => {
+                return Ok(CallToolResult {
+                    content: vec![Content::text(format!("{:#}", e))],
+                    is_error: Some(true),
+                    meta: None,
+                    structured_content: None,
+                })
+            }
+        };
+        output.stdout.text = redact(&output.stdout.text, &settings.secrets);
+        output.stderr.text = redact(&output.stderr.text, &settings.secrets);
+
+        Ok(CallToolResult {
+            content: vec![Content::text(format_output(&request.command, &output, timeout_secs))],
+            is_error: Some(output.timed_out),
+            meta: None,
+            structured_content: Some(json!({
+                "command": request.command,
+                "argv": argv,
+                "project_root_path": project.to_string_lossy(),
+                "template": rule.template,
+                "approved": rule.requires_approval.then_some(true),
+                "exit_code": output.exit_code,
+                "timed_out": output.timed_out,
+                "duration_ms": output.duration_ms,
+                "stdout": output.stdout,
+                "stderr": output.stderr,
+            })),
+        })
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "project_root_path": {
+                    "type": "string",
+                    "description": "Absolute path to the project root directory; the command runs there"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Command line to run, e.g. `cargo test -p devkit`. Runs without a shell and must match a configured template"
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Optional timeout in seconds, capped by the template's timeout"
+                }
+            },
+            "required": ["project_root_path", "command"]
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("run_command"),
+                description: Some(Cow::Borrowed("Run an allowlisted project command (test, lint, build) in the project directory. Returns exit code and size-capped stdout/stderr. Commands not in the allowlist are rejected; some need user approval first.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Run Command".to_string()),
+                    read_only_hint: Some(false),
+                    destructive_hint: Some(true),     // Depends on the allowlisted command
+                    idempotent_hint: Some(false),
+                    open_world_hint: Some(false),
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Run Command".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+pub(crate) fn format_output(command: &str, output: &CommandOutput, timeout_secs: u64) -> String {
+    let status = match (output.timed_out, output.exit_code) {
+        (true, _) => format!("timed out after {}s, killed", timeout_secs),
+        (false, Some(code)) => format!("exit code {}", code),
+        (false, None) => "terminated by signal".to_string(),
+    };
+    let mut text = format!("$ {}\n{} ({} ms)\n", command, status, output.duration_ms);
+    for (name, captured) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        if captured.text.is_empty() {
+            continue;
+        }
+        text.push_str(&format!("\n## {}\n```\n{}\n```\n", name, strip_ansi(&captured.text).trim_end()));
+        if captured.truncated {
+            text.push_str(&format!("({} bytes total, middle omitted)\n", captured.total_bytes));
+        }
+    }
+    text
+}
+
+/// Drop ANSI escape sequences (colors, cursor movement) and other control
+/// characters that tools print even when not attached to a terminal
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                // CSI: parameters and intermediates up to a final byte in @..~
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC: up to BEL or ST (ESC \)
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\n' | '\t' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use std::sync::Mutex;
+
+    /// Records requests and answers with a fixed decision
+    struct FakeApprover {
+        decision: Approval,
+        seen: Mutex<Vec<ApprovalRequest>>,
+    }
+
+    impl Approver for FakeApprover {
+        fn approve(&self, request: &ApprovalRequest) -> anyhow::Result<Approval> {
+            self.seen.lock().unwrap().push(request.clone());
+            Ok(self.decision.clone())
+        }
+    }
+
+    fn fake(decision: Approval) -> Arc<FakeApprover> {
+        Arc::new(FakeApprover { decision, seen: Mutex::new(Vec::new()) })
+    }
+
+    fn project() -> TestDir {
+        TestDir::new("run-command")
+    }
+
+    fn settings() -> CommandSettings {
+        CommandSettings {
+            rules: vec![
+                CommandRule { template: "echo {...}".to_string(), ..Default::default() },
+                CommandRule { template: "touch {}".to_string(), requires_approval: true, ..Default::default() },
+            ],
+            env: Vec::new(),
+            secrets: vec!["supersecretvalue".to_string()],
+        }
+    }
+
+    fn request(project: &std::path::Path, command: &str) -> RunCommandRequest {
+        RunCommandRequest {
+            project_root_path: project.to_string_lossy().into_owned(),
+            command: command.to_string(),
+            timeout_secs: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_command_runs_in_project() {
+        let dir = project();
+        let approver = fake(Approval::Approved);
+        let result = RunCommandTool::execute(request(&dir, "echo hi supersecretvalue"), &settings(), approver.clone())
+            .await
+            .unwrap();
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["exit_code"], 0);
+        assert_eq!(structured["stdout"]["text"], "hi [REDACTED]\n");
+        assert!(approver.seen.lock().unwrap().is_empty());
+
+        let err = match RunCommandTool::execute(request(&dir, "rm -rf x"), &settings(), approver).await {
+            Err(ToolError::Rpc(err)) => err,
+            other => panic!("expected rejection, got {:?}", other.map(|r| r.content)),
+        };
+        assert_eq!(err.data.unwrap()["allowed"], json!(["echo {...}", "touch {}"]));
+    }
+
+    #[tokio::test]
+    async fn test_approval_gates_execution() {
+        let dir = project();
+
+        let rejecting = fake(Approval::Rejected { reason: Some("not now".to_string()) });
+        let result = RunCommandTool::execute(request(&dir, "touch marker"), &settings(), rejecting.clone()).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(result.structured_content.unwrap()["reason"], "not now");
+        assert!(!dir.join("marker").exists());
+        let seen = rejecting.seen.lock().unwrap().clone();
+        assert_eq!((seen[0].command.as_str(), seen[0].template.as_str()), ("touch marker", "touch {}"));
+
+        let approving = fake(Approval::Approved);
+        let result = RunCommandTool::execute(request(&dir, "touch marker"), &settings(), approving).await.unwrap();
+        assert_eq!(result.structured_content.unwrap()["approved"], true);
+        assert!(dir.join("marker").exists());
+    }
+}
@@ -0,0 +1,13 @@
+//! 受控命令执行工具
+//!
+//! 只执行配置中允许的命令模板，在项目目录下运行并限制超时、输出大小与继承的环境变量
+
+pub mod allowlist;
+pub mod approval;
+pub mod mcp;
+pub mod runner;
+pub mod types;
+
+pub use approval::{Approval, ApprovalRequest, Approver, NoApprover, PopupApprover};
+pub use mcp::RunCommandTool;
+pub use types::RunCommandRequest;
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// run_command 工具请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunCommandRequest {
+    /// 项目根目录的绝对路径，命令在该目录下执行
+    pub project_root_path: String,
+    /// 要执行的命令行，必须匹配配置中的某个命令模板
+    pub command: String,
+    /// 超时时间（秒），不能超过模板配置的超时
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+}
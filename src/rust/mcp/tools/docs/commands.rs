@@ -1,6 +1,8 @@
 use tauri::State;
 use crate::config::AppState;
-use super::types::{DocsRequest, DocsConfig, TestConnectionResponse, docs_api_base_url};
+use super::types::{DocsRequest, DocsConfig, TestConnectionResponse};
+use crate::docs_client::{Client, DocsError};
+use crate::utils::text;
 
 /// 测试 Docs 连接
 #[tauri::command]
@@ -15,10 +17,7 @@ pub async fn test_docs_connection(
             .lock()
             .map_err(|e| format!("获取配置失败: {}", e))?;
 
-        DocsConfig {
-            api_key: config.mcp_config.docs_api_key.clone(),
-            base_url: docs_api_base_url(),
-        }
+        config.mcp_config.docs_client_config()
     }; // config 在这里自动 drop
 
     // 使用用户指定的库，或默认使用 Spring Framework
@@ -27,10 +26,9 @@ pub async fn test_docs_connection(
 
     // 执行测试查询
     let test_request = DocsRequest {
-        library: test_library.clone(),
         topic: test_topic,
-        version: None,
         page: Some(1),
+        ..DocsRequest::new(test_library.clone())
     };
 
     // 调用内部方法执行查询
@@ -57,59 +55,27 @@ async fn execute_test_query(
     config: &DocsConfig,
     request: &DocsRequest
 ) -> Result<String, String> {
-    use reqwest::header::AUTHORIZATION;
-    use reqwest::Client;
-    use std::time::Duration;
-
-    let client = Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()
+    let client = Client::new(config.clone())
         .map_err(|e| format!("创建 HTTP 客户端失败: {}", e))?;
 
-    // 构建 URL
-    let url = format!("{}/docs/code/{}", config.base_url, request.library);
-
-    // 构建请求
-    let mut req_builder = client.get(&url);
-
-    // 添加 API Key (如果有)
-    if let Some(api_key) = &config.api_key {
-        req_builder = req_builder.header(AUTHORIZATION, format!("Bearer {}", api_key));
-    }
-
-    // 添加查询参数
-    if let Some(topic) = &request.topic {
-        req_builder = req_builder.query(&[("topic", topic)]);
-    }
-    if let Some(page) = request.page {
-        req_builder = req_builder.query(&[("page", page.to_string())]);
-    }
-
-    // 发送请求
-    let response = req_builder.send().await
-        .map_err(|e| format!("请求失败: {}", e))?;
-
-    let status = response.status();
-
-    // 处理错误状态码
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_else(|_| "无法读取错误信息".to_string());
-        return Err(format_test_error(status.as_u16(), &error_text, &request.library));
-    }
-
-    // 读取响应文本 (Docs API 返回纯文本 Markdown，不是 JSON)
-    let response_text = response.text().await
-        .map_err(|e| format!("读取响应失败: {}", e))?;
+    let response = client.docs(request).await
+        .map_err(|e| format_test_error(&e, &request.library))?;
 
     // 如果响应为空
-    if response_text.trim().is_empty() {
+    if response.is_empty() {
         return Ok("未找到文档内容".to_string());
     }
 
+    let response_text = response.snippets
+        .iter()
+        .map(|s| s.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
     // 生成预览文本 (只显示前 300 个字符)
     let preview = if response_text.len() > 300 {
         // 尝试在合适的位置截断（避免截断单词）
-        let truncated = &response_text[..300];
+        let truncated = text::prefix_bytes(&response_text, 300);
         if let Some(last_newline) = truncated.rfind('\n') {
             format!("{}...", &truncated[..last_newline])
         } else {
@@ -123,13 +89,23 @@ async fn execute_test_query(
 }
 
 /// 格式化测试错误消息
-fn format_test_error(status_code: u16, error_text: &str, library: &str) -> String {
-    match status_code {
-        401 => "API 密钥无效或已过期".to_string(),
-        404 => format!("库 \"{}\" 不存在，请检查库标识符是否正确", library),
-        429 => "速率限制已达上限，建议配置 API Key".to_string(),
-        500..=599 => format!("Docs 服务器错误: {}", error_text),
-        _ => format!("请求失败 (状态码: {}): {}", status_code, error_text),
+fn format_test_error(error: &DocsError, library: &str) -> String {
+    match error {
+        DocsError::Unauthorized => "API 密钥无效或已过期".to_string(),
+        DocsError::NotFound { .. } => format!("库 \"{}\" 不存在，请检查库标识符是否正确", library),
+        DocsError::RateLimited { .. } => "速率限制已达上限，建议配置 API Key".to_string(),
+        DocsError::Degraded { failures, retry_in } => {
+            format!("Docs 服务暂时不可用（连续失败 {} 次），请 {} 秒后再试", failures, retry_in.as_secs().max(1))
+        }
+        DocsError::Server { message, .. } => format!("Docs 服务器错误: {}", message),
+        DocsError::Status { status, message } => format!("请求失败 (状态码: {}): {}", status, message),
+        DocsError::InvalidHeader { name, reason } => format!("上游请求头 {} 配置无效: {}", name, reason),
+        DocsError::InvalidProxy { url, reason } => format!("代理 {} 配置无效: {}", url, reason),
+        DocsError::TooManyRedirects { limit } => format!("重定向次数超过 {} 次，请检查库标识符", limit),
+        DocsError::Network(e) => format!("请求失败: {}", e),
+        DocsError::Parse(e) => format!("读取响应失败: {}", e),
+        DocsError::NotRecorded { request, dir } => format!("回放目录 {} 中没有 {} 的记录", dir, request),
+        DocsError::Recording { path, reason } => format!("读写录制文件 {} 失败: {}", path, reason),
     }
 }
 
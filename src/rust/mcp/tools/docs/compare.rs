@@ -0,0 +1,336 @@
+//! `docs_compare`: the same topic in two or three libraries, side by side
+//!
+//! Each library is looked up concurrently through the same path as the `docs`
+//! tool (aliases, search fallback, cache and offline snapshots). The reply
+//! opens with a table listing the snippet titles of every library in parallel
+//! columns, followed by the snippets themselves grouped per library, so
+//! `react vs vue routing` reads as one answer instead of separate lookups.
+
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError, Tool, ToolAnnotations};
+use serde_json::json;
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use super::mcp::{select_snippets, snippet_label, MAX_SNIPPETS_RANGE};
+use super::types::{DocsCompareRequest, DocsConfig, DocsRequest};
+use super::DocsTool;
+use crate::config::{OfflineError, OfflineMode};
+use crate::docs_client::cache::DocsCache;
+use crate::docs_client::SessionUsage;
+use crate::mcp::utils::ToolError;
+use crate::log_important;
+
+/// Number of libraries one comparison takes
+pub const LIBRARIES_RANGE: RangeInclusive<usize> = 2..=3;
+
+/// Snippets per library when `max_snippets` is not given
+pub const DEFAULT_COMPARE_SNIPPETS: u32 = 3;
+
+/// Side-by-side comparison of libraries
+pub struct DocsCompareTool;
+
+/// What one library contributes to the comparison
+struct Column {
+    requested: String,
+    /// Library the snippets come from, `None` when there are none
+    library: Option<String>,
+    /// Title and content of each shown snippet
+    snippets: Vec<(Option<String>, String)>,
+    total: usize,
+    /// Why there are no snippets: not found, suggestions or an error
+    note: Option<String>,
+    failed: bool,
+}
+
+impl DocsCompareTool {
+    pub async fn compare(request: DocsCompareRequest) -> Result<CallToolResult, ToolError> {
+        let (libraries, topic) = Self::validate(&request)?;
+        let operation = format!("docs comparison of {}", libraries.join(", "));
+        let offline = OfflineMode::resolve(None).check(&operation).err();
+
+        log_important!(info, "Docs comparison: libraries={:?}, topic={}", libraries, topic);
+        let (config, settings) = DocsTool::get_config()
+            .await
+            .map_err(|e| McpError::internal_error(format!("Failed to get docs config: {}", e), None))?;
+        let footer = offline.is_none().then(|| SessionUsage::shared().footer(settings.quota_footer_every)).flatten();
+
+        let max_snippets = request.max_snippets.unwrap_or(DEFAULT_COMPARE_SNIPPETS);
+        let result = Self::compare_with(&config, &libraries, &topic, max_snippets, DocsCache::shared(), settings.auto_resolve, offline).await;
+        Ok(match footer {
+            Some(footer) => DocsTool::append_text(result, &footer),
+            None => result,
+        })
+    }
+
+    /// Trimmed, distinct libraries and the topic
+    fn validate(request: &DocsCompareRequest) -> Result<(Vec<String>, String), McpError> {
+        let topic = request.topic.trim();
+        if topic.is_empty() {
+            return Err(McpError::invalid_params("topic must not be empty", Some(json!({ "path": "topic" }))));
+        }
+        let mut libraries: Vec<String> = Vec::new();
+        for library in &request.libraries {
+            let library = library.trim();
+            if library.is_empty() {
+                return Err(McpError::invalid_params("libraries must not contain empty identifiers", Some(json!({ "path": "libraries" }))));
+            }
+            if !libraries.iter().any(|seen| seen == library) {
+                libraries.push(library.to_string());
+            }
+        }
+        if !LIBRARIES_RANGE.contains(&libraries.len()) {
+            return Err(McpError::invalid_params(
+                format!(
+                    "compare takes {} to {} different libraries, got {}",
+                    LIBRARIES_RANGE.start(),
+                    LIBRARIES_RANGE.end(),
+                    libraries.len()
+                ),
+                Some(json!({ "path": "libraries" })),
+            ));
+        }
+        if let Some(max) = request.max_snippets.filter(|max| !MAX_SNIPPETS_RANGE.contains(max)) {
+            return Err(McpError::invalid_params(
+                format!("max_snippets must be between {} and {}, got {}", MAX_SNIPPETS_RANGE.start(), MAX_SNIPPETS_RANGE.end(), max),
+                Some(json!({ "path": "max_snippets" })),
+            ));
+        }
+        Ok((libraries, topic.to_string()))
+    }
+
+    async fn compare_with(
+        config: &DocsConfig,
+        libraries: &[String],
+        topic: &str,
+        max_snippets: u32,
+        cache: &DocsCache,
+        auto_resolve: bool,
+        offline: Option<OfflineError>,
+    ) -> CallToolResult {
+        let lookups = libraries.iter().map(|library| {
+            let request = DocsRequest { topic: Some(topic.to_string()), max_snippets: Some(max_snippets), ..DocsRequest::new(library) };
+            let offline = offline.as_ref();
+            async move {
+                let output = match offline {
+                    Some(offline) => DocsTool::query_offline(&request, cache, offline).map_err(|_| format!("{} and it is not cached", offline)),
+                    None => DocsTool::fetch_docs(config, &request, cache, auto_resolve).await.map_err(|e| format!("{:#}", e)),
+                };
+                (request, output)
+            }
+        });
+        let columns: Vec<Column> = futures::future::join_all(lookups)
+            .await
+            .into_iter()
+            .map(|(request, output)| match output {
+                Ok(output) => match (output.library, output.response) {
+                    (Some(library), Some(response)) => {
+                        let selection = select_snippets(&response, &request);
+                        let snippets = selection
+                            .shown
+                            .iter()
+                            .map(|(snippet, content)| (snippet.title.clone(), content.trim_end().to_string()))
+                            .collect();
+                        Column { requested: request.library, library: Some(library), snippets, total: selection.total, note: None, failed: false }
+                    }
+                    _ => Column { requested: request.library, library: None, snippets: Vec::new(), total: 0, note: Some(output.text), failed: false },
+                },
+                Err(error) => {
+                    log_important!(warn, "Docs comparison lookup of {} failed: {}", request.library, error);
+                    Column { requested: request.library, library: None, snippets: Vec::new(), total: 0, note: Some(error), failed: true }
+                }
+            })
+            .collect();
+
+        let failed = columns.iter().filter(|column| column.failed).count();
+        let structured: Vec<_> = columns
+            .iter()
+            .map(|column| {
+                let mut entry = json!({
+                    "requested": column.requested,
+                    "library": column.library,
+                    "status": if column.failed { "error" } else if column.library.is_some() { "ok" } else { "no_docs" },
+                    "snippets": column.snippets.iter().map(|(title, content)| json!({ "title": title, "content": content })).collect::<Vec<_>>(),
+                    "total_snippets": column.total,
+                });
+                if let Some(note) = &column.note {
+                    entry[if column.failed { "error" } else { "message" }] = json!(note);
+                }
+                entry
+            })
+            .collect();
+        CallToolResult {
+            content: vec![Content::text(format_comparison(topic, &columns))],
+            is_error: Some(failed == columns.len()),
+            meta: None,
+            structured_content: Some(json!({ "topic": topic, "libraries": structured })),
+        }
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "libraries": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "minItems": LIBRARIES_RANGE.start(),
+                    "maxItems": LIBRARIES_RANGE.end(),
+                    "description": "Libraries to compare, as owner/repo or a known name, e.g. [\"facebook/react\", \"vuejs/docs\"]"
+                },
+                "topic": {
+                    "type": "string",
+                    "description": "Topic looked up in every library, e.g. routing"
+                },
+                "max_snippets": {
+                    "type": "integer",
+                    "minimum": MAX_SNIPPETS_RANGE.start(),
+                    "maximum": MAX_SNIPPETS_RANGE.end(),
+                    "description": "Snippets shown per library, defaults to 3"
+                }
+            },
+            "required": ["libraries", "topic"]
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("docs_compare"),
+                description: Some(Cow::Borrowed("Compare how two or three libraries document the same topic (e.g. react vs vue routing): fetches the topic for each library concurrently and answers with a side-by-side table of snippet titles followed by the snippets per library.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Docs Compare".to_string()),
+                    read_only_hint: Some(true),
+                    destructive_hint: Some(false),
+                    idempotent_hint: Some(true),
+                    open_world_hint: Some(true),     // Queries the docs API
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Docs Compare".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+/// Overview table, then the snippets of each library
+fn format_comparison(topic: &str, columns: &[Column]) -> String {
+    let names: Vec<&str> = columns.iter().map(|column| column.library.as_deref().unwrap_or(&column.requested)).collect();
+    let mut text = format!("# {}: {}\n\n", names.join(" vs "), topic);
+
+    text.push_str(&format!("| | {} |\n", names.iter().map(|name| format!("`{}`", cell(name))).collect::<Vec<_>>().join(" | ")));
+    text.push_str(&format!("|---|{}\n", "---|".repeat(columns.len())));
+    let counts: Vec<String> = columns
+        .iter()
+        .map(|column| match (&column.library, column.failed) {
+            (_, true) => "failed".to_string(),
+            (None, false) => "no documentation".to_string(),
+            (Some(_), false) => format!("{} of {}", column.snippets.len(), column.total),
+        })
+        .collect();
+    text.push_str(&format!("| Snippets | {} |\n", counts.join(" | ")));
+    let rows = columns.iter().map(|column| column.snippets.len()).max().unwrap_or(0);
+    for row in 0..rows {
+        let titles: Vec<String> = columns
+            .iter()
+            .map(|column| column.snippets.get(row).map_or_else(String::new, |(title, content)| cell(&snippet_label(title.as_deref(), content))))
+            .collect();
+        text.push_str(&format!("| {} | {} |\n", row + 1, titles.join(" | ")));
+    }
+
+    for (column, name) in columns.iter().zip(&names) {
+        text.push_str(&format!("\n---\n\n## {}\n\n", name));
+        if let Some(note) = &column.note {
+            let note = if column.failed { format!("Lookup failed: {}", note) } else { note.clone() };
+            text.push_str(&format!("{}\n", note.trim_end()));
+            continue;
+        }
+        for (index, (title, content)) in column.snippets.iter().enumerate() {
+            text.push_str(&format!("### {}. {}\n\n{}\n\n", index + 1, snippet_label(title.as_deref(), content), content));
+        }
+    }
+    text.trim_end().to_string()
+}
+
+/// `value` safe inside a table cell
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::cache::DEFAULT_TTL;
+    use crate::docs_client::mock::{MockResponse, MockServer};
+
+    fn request(libraries: &[&str], topic: &str) -> DocsCompareRequest {
+        DocsCompareRequest { libraries: libraries.iter().map(|l| l.to_string()).collect(), topic: topic.to_string(), max_snippets: None }
+    }
+
+    #[test]
+    fn test_validation() {
+        let message = |request: DocsCompareRequest| DocsCompareTool::validate(&request).unwrap_err().message.to_string();
+        assert!(message(request(&["a/b"], "routing")).contains("2 to 3 different libraries, got 1"));
+        assert!(message(request(&["a/b", " a/b "], "routing")).contains("got 1"));
+        assert!(message(request(&["a/b", "c/d", "e/f", "g/h"], "routing")).contains("got 4"));
+        assert!(message(request(&["a/b", ""], "routing")).contains("empty"));
+        assert!(message(request(&["a/b", "c/d"], " ")).contains("topic"));
+        let too_many = DocsCompareRequest { max_snippets: Some(0), ..request(&["a/b", "c/d"], "routing") };
+        assert!(message(too_many).contains("max_snippets"));
+
+        let (libraries, topic) = DocsCompareTool::validate(&request(&[" facebook/react", "vuejs/docs "], " routing ")).unwrap();
+        assert_eq!((libraries, topic.as_str()), (vec!["facebook/react".to_string(), "vuejs/docs".to_string()], "routing"));
+    }
+
+    #[tokio::test]
+    async fn test_libraries_are_compared_side_by_side() {
+        let snippets = |items: &[(&str, &str)]| {
+            let snippets: Vec<_> = items.iter().map(|(title, content)| json!({ "title": title, "content": content })).collect();
+            MockResponse::json(200, &json!({ "snippets": snippets }).to_string())
+        };
+        let server = MockServer::start(move |req| {
+            assert_eq!(req.query("topic").as_deref(), Some("routing"));
+            match req.path.as_str() {
+                "/docs/code/facebook/react" => snippets(&[
+                    ("Router setup", "Wrap the app in <BrowserRouter>."),
+                    ("Links | navigation", "Use <Link to=\"/\">."),
+                    ("Loaders", "Fetch before render."),
+                ]),
+                "/docs/code/vuejs/docs" => snippets(&[("createRouter", "const router = createRouter({ routes })")]),
+                _ => MockResponse::text(500, "maintenance"),
+            }
+        })
+        .await;
+        let config = DocsConfig {
+            base_url: server.base_url(),
+            retry: crate::docs_client::RetryTuning { max_retries: 0, ..Default::default() },
+            ..Default::default()
+        };
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+        let libraries = ["facebook/react".to_string(), "vuejs/docs".to_string(), "broken/lib".to_string()];
+
+        let result = DocsCompareTool::compare_with(&config, &libraries, "routing", 2, &cache, false, None).await;
+        assert_eq!(result.is_error, Some(false));
+        let text = &result.content[0].as_text().unwrap().text;
+        assert!(text.starts_with("# facebook/react vs vuejs/docs vs broken/lib: routing\n\n"), "{}", text);
+        assert!(text.contains("| Snippets | 2 of 3 | 1 of 1 | failed |"), "{}", text);
+        assert!(text.contains("| 1 | Router setup | createRouter |  |"), "{}", text);
+        assert!(text.contains("| 2 | Links \\| navigation |  |  |"), "{}", text);
+        assert!(text.contains("## vuejs/docs\n\n### 1. createRouter\n\nconst router = createRouter({ routes })"), "{}", text);
+        assert!(text.contains("## broken/lib\n\nLookup failed:"), "{}", text);
+        assert!(!text.contains("Loaders"));
+
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["libraries"][0]["snippets"][1]["title"], "Links | navigation");
+        assert_eq!(structured["libraries"][2]["status"], "error");
+    }
+
+    #[test]
+    fn test_untitled_snippets_are_labelled_by_their_first_line() {
+        assert_eq!(snippet_label(None, "\n  npm install next  \nmore"), "npm install next");
+        assert_eq!(snippet_label(Some(" "), "x"), "x");
+        assert_eq!(snippet_label(Some(&"路由".repeat(40)), ""), format!("{}…", "路由".repeat(30)));
+    }
+}
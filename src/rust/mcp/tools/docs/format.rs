@@ -0,0 +1,168 @@
+//! Reply text formats of the docs tool (`format`)
+//!
+//! Answers are rendered as Markdown; `json` replaces the text with the
+//! structured content serialized as JSON, `plain` strips the Markdown this
+//! tool adds (headings, emphasis, quotes, rules and code fences) so clients
+//! that post-process the text get it without decorations. Code inside fences
+//! is kept as is. Structured content is the same in every format.
+
+use rmcp::model::{CallToolResult, RawContent};
+use serde_json::{json, Value};
+use std::future::Future;
+
+use super::types::OutputFormat;
+
+tokio::task_local! {
+    static CURRENT: OutputFormat;
+}
+
+/// Format of the call being served, for partial results; Markdown outside [`scope`]
+pub fn current() -> OutputFormat {
+    CURRENT.try_with(|format| *format).unwrap_or_default()
+}
+
+/// Run `future` with `format` as the reply format
+pub async fn scope<F: Future>(format: OutputFormat, future: F) -> F::Output {
+    CURRENT.scope(format, future).await
+}
+
+/// Render the text of `result` in `format`
+pub fn apply(mut result: CallToolResult, format: OutputFormat) -> CallToolResult {
+    if format == OutputFormat::Markdown {
+        return result;
+    }
+    let structured = result.structured_content.clone();
+    for content in &mut result.content {
+        if let RawContent::Text(raw) = &mut content.raw {
+            raw.text = match format {
+                OutputFormat::Json => to_json(structured.as_ref(), &raw.text),
+                OutputFormat::Plain => to_plain(&raw.text),
+                OutputFormat::Markdown => unreachable!(),
+            };
+        }
+    }
+    result
+}
+
+/// The structured content as JSON; answers without snippets also carry their
+/// message, since for them the text is the answer
+fn to_json(structured: Option<&Value>, text: &str) -> String {
+    let mut object = match structured {
+        Some(Value::Object(object)) => object.clone(),
+        _ => Default::default(),
+    };
+    if !object.contains_key("snippets") {
+        object.insert("message".to_string(), json!(to_plain(text)));
+    }
+    serde_json::to_string_pretty(&Value::Object(object)).unwrap_or_default()
+}
+
+/// `text` without Markdown decorations, blank lines collapsed to one
+pub fn to_plain(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push(line.to_string());
+            continue;
+        }
+        if is_rule(trimmed) {
+            lines.push(String::new());
+            continue;
+        }
+        lines.push(strip_inline(strip_block(trimmed)));
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut blank = true;
+    for line in lines {
+        let is_blank = line.trim().is_empty();
+        if is_blank && blank {
+            continue;
+        }
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        output.push_str(line.trim_end());
+        blank = is_blank;
+    }
+    output.trim_end().to_string()
+}
+
+/// `---`, `***` or `___`
+fn is_rule(line: &str) -> bool {
+    let line = line.trim_end();
+    line.len() >= 3 && ['-', '*', '_'].into_iter().any(|c| line.chars().all(|ch| ch == c))
+}
+
+/// Heading markers and quote markers
+fn strip_block(mut line: &str) -> &str {
+    while let Some(rest) = line.strip_prefix('>') {
+        line = rest.trim_start();
+    }
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    if (1..=6).contains(&hashes) && line[hashes..].starts_with(' ') {
+        line = line[hashes..].trim_start();
+    }
+    line
+}
+
+/// Bold markers, inline code ticks and notes wrapped in `_…_`
+fn strip_inline(line: &str) -> String {
+    let line = line.replace("**", "").replace('`', "");
+    match line.strip_prefix('_').and_then(|rest| rest.strip_suffix('_')) {
+        Some(note) if !note.is_empty() => note.to_string(),
+        _ => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+
+    const MARKDOWN: &str = "# vercel/next.js Documentation\n\n**Topic**: routing\n\n---\n\n## Routing\n\nUse the `app` directory.\n\n```tsx\nexport default function Page() {\n  return <h1>**hi**</h1>\n}\n```\n\n_Page 1/3, request page 2 for more._\n\n---\nSource: Docs - vercel/next.js\n\n> Note: library id `a/b` has moved to `c/d` — update your configuration.";
+
+    fn result(text: &str, structured: Option<Value>) -> CallToolResult {
+        CallToolResult { content: vec![Content::text(text)], is_error: Some(false), meta: None, structured_content: structured }
+    }
+
+    fn text(result: &CallToolResult) -> &str {
+        &result.content[0].as_text().unwrap().text
+    }
+
+    #[test]
+    fn test_plain_drops_decorations_but_keeps_code() {
+        let plain = to_plain(MARKDOWN);
+        assert_eq!(
+            plain,
+            "vercel/next.js Documentation\n\nTopic: routing\n\nRouting\n\nUse the app directory.\n\nexport default function Page() {\n  return <h1>**hi**</h1>\n}\n\nPage 1/3, request page 2 for more.\n\nSource: Docs - vercel/next.js\n\nNote: library id a/b has moved to c/d — update your configuration."
+        );
+        // Underscores inside words are not emphasis
+        assert_eq!(to_plain("use snake_case_names"), "use snake_case_names");
+    }
+
+    #[test]
+    fn test_json_is_the_structured_content() {
+        let structured = json!({ "library": "vercel/next.js", "snippets": [{ "title": "Routing" }] });
+        let json_result = apply(result(MARKDOWN, Some(structured.clone())), OutputFormat::Json);
+        assert_eq!(serde_json::from_str::<Value>(text(&json_result)).unwrap(), structured);
+        assert_eq!(json_result.structured_content, Some(structured));
+
+        // Without snippets the message is the answer
+        let suggestions = apply(result("## Did you mean\n\n- `a/b`", Some(json!({}))), OutputFormat::Json);
+        let value: Value = serde_json::from_str(text(&suggestions)).unwrap();
+        assert_eq!(value["message"], "Did you mean\n\n- a/b");
+    }
+
+    #[test]
+    fn test_markdown_is_unchanged() {
+        let markdown = apply(result(MARKDOWN, None), OutputFormat::Markdown);
+        assert_eq!(text(&markdown), MARKDOWN);
+    }
+}
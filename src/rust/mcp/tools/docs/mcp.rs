@@ -1,43 +1,202 @@
 use anyhow::Result;
-use rmcp::model::{ErrorData as McpError, Tool, ToolAnnotations, CallToolResult, Content};
-use reqwest::header::AUTHORIZATION;
-use reqwest::Client;
+use rmcp::model::{ErrorData as McpError, Tool, ToolAnnotations, CallToolResult, Content, RawContent};
 use serde_json::json;
+use chrono::Utc;
 use std::borrow::Cow;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
 
-use super::types::{DocsRequest, DocsConfig, SearchResponse, SearchResult, docs_api_base_url, docs_website_url};
+use super::format;
+use super::merge;
+use super::rank;
+use super::save;
+use super::types::{CacheAction, DocType, DocsSource, OutputFormat, DocsRequest, DocsToolRequest, DocsConfig, DocsResponse, DocumentSnippet, SearchResult, docs_website_url};
+use crate::docs_client::cache::{CacheKey, CacheStats, DocsCache, Snapshot};
+use crate::docs_client::keys::{KeyPool, KeyUsage};
+use crate::docs_client::quota::{SessionUsage, UsageSnapshot, DEFAULT_FOOTER_EVERY};
+use crate::docs_client::resolve::{self, library_id, match_confidence, AUTO_RESOLVE_THRESHOLD};
+use crate::config::{McpConfig, OfflineError, OfflineMode};
+use crate::docs_client::{contract, key_check, Client, DocsError, AUTO_MIN_SNIPPETS};
+use crate::mcp::boundary::is_upstream_unavailable;
+use crate::mcp::dry_run;
+use crate::mcp::progress;
+use crate::mcp::result_limit;
+use crate::mcp::tools::project_docs::mcp::LIMIT_RANGE as PROJECT_LIMIT_RANGE;
+use crate::mcp::tools::project_docs::{index_project_docs, ProjectDocsTool, SearchHit};
+use crate::utils::locale::{self, Message};
+use crate::utils::text;
+use crate::mcp::utils::{decode_and_normalize_path, offline_result, project_path_error, validate_project_path, ToolError};
 use crate::log_debug;
 use crate::log_important;
 
+/// Allowed values of the `max_snippets` parameter
+pub const MAX_SNIPPETS_RANGE: RangeInclusive<u32> = 1..=50;
+
+/// Valid values of `max_tokens`
+pub const MAX_TOKENS_RANGE: RangeInclusive<u32> = 100..=100_000;
+
+/// Characters counted as one token when applying `max_tokens`; a rough
+/// estimate that is close for English prose and code
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Valid values of `max_bytes`
+pub const MAX_BYTES_RANGE: RangeInclusive<u32> = 1_000..=10_000_000;
+
+/// `max_bytes` when neither the call nor `docs_max_response_bytes` sets it
+pub const DEFAULT_MAX_RESPONSE_BYTES: u32 = 100_000;
+
+/// Titles listed in the index of snippets left out for `max_bytes`
+const OMITTED_INDEX_MAX: usize = 30;
+
+/// Characters of a snippet label without a title
+const LABEL_CHARS: usize = 60;
+
+/// Libraries one call may look up together (`library` plus `libraries`)
+pub const MAX_BATCH_LIBRARIES: usize = 5;
+
+/// Topics one call may look up together (`topic` plus `topics`)
+pub const MAX_BATCH_TOPICS: usize = 5;
+
+/// Valid values of `max_pages`; the API serves at most 10 pages
+pub const MAX_PAGES_RANGE: RangeInclusive<u32> = 1..=10;
+
+/// Topics listed when a topic has no documentation
+pub const MAX_SUGGESTED_TOPICS: usize = 15;
+
+/// Environment variable overriding the docs API base URL, e.g. to point
+/// `devkit call docs` at a local mock
+pub const DOCS_API_URL_ENV: &str = "DEVKIT_DOCS_API_URL";
+
+/// Held by tests that point [`DOCS_API_URL_ENV`] at a mock server, since the
+/// variable is shared by the whole test process
+#[cfg(test)]
+pub(crate) static DOCS_API_URL_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Tool output: Markdown text plus optional structured content
+pub(super) struct DocsOutput {
+    pub(super) text: String,
+    pub(super) structured: Option<serde_json::Value>,
+    /// Library whose documentation the text shows; `None` for messages such as not found
+    pub(super) library: Option<String>,
+    /// The rendered response, for merging with other sources
+    pub(super) response: Option<DocsResponse>,
+}
+
+impl From<String> for DocsOutput {
+    fn from(text: String) -> Self {
+        Self { text, structured: None, library: None, response: None }
+    }
+}
+
+/// What a batch lookup fans out over, one section each
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Batch {
+    Libraries,
+    Topics,
+}
+
+/// Tool behaviour read from the config file
+pub(crate) struct ToolSettings {
+    /// Default of the `auto_resolve` parameter
+    pub(super) auto_resolve: bool,
+    /// Upstream requests between two quota footers
+    pub(super) quota_footer_every: u64,
+    /// Default of the `max_bytes` parameter; `None` for no limit
+    pub(super) max_response_bytes: Option<u32>,
+}
+
 /// Docs tool implementation
 pub struct DocsTool;
 
 impl DocsTool {
-    /// Query framework documentation
-    pub async fn query_docs(request: DocsRequest) -> Result<CallToolResult, McpError> {
+    /// Query framework documentation, rendering the reply text in the requested
+    /// format and language (`language` overrides the session language)
+    pub async fn query_docs(request: DocsToolRequest) -> Result<CallToolResult, ToolError> {
+        let output_format = request.format.unwrap_or_default();
+        let query = format::scope(output_format, Self::query(request.clone()));
+        let result = match request.language {
+            Some(language) => locale::scope(language, query).await,
+            None => query.await,
+        };
+        result.map(|result| format::apply(result, output_format))
+    }
+
+    async fn query(mut tool_request: DocsToolRequest) -> Result<CallToolResult, ToolError> {
+        if let Some(action) = tool_request.cache {
+            return Ok(Self::cache_operation(action, &tool_request.query.library));
+        }
+        Self::validate_request(&tool_request)?;
+        let libraries = Self::requested_libraries(&tool_request.query);
+        if let [library] = libraries.as_slice() {
+            tool_request.query.library = library.clone();
+        }
+        if let [topic] = Self::requested_topics(&tool_request.query).as_slice() {
+            tool_request.query.topic = Some(topic.clone());
+        }
+        let sources = Self::selected_sources(&tool_request)?;
+        let save_target = Self::save_target(&tool_request)?;
+        let request = &tool_request.query;
+
         log_important!(info,
-            "Docs query: library={}, topic={:?}, version={:?}, page={:?}",
-            request.library, request.topic, request.version, request.page
+            "Docs query: library={}, topic={:?}, version={:?}, page={:?}, doc_type={:?}",
+            request.library, request.topic, request.version, request.page, request.doc_type
         );
 
-        let config = Self::get_config()
+        let (config, settings) = Self::get_config()
             .await
             .map_err(|e| McpError::internal_error(format!("Failed to get docs config: {}", e), None))?;
+        let auto_resolve = request.auto_resolve.unwrap_or(settings.auto_resolve);
+        // Within a client's result size limit, leaving out whole snippets beats having the reply cut
+        let client_budget = result_limit::reply_budget()
+            .map(|budget| u32::try_from(budget).unwrap_or(u32::MAX).clamp(*MAX_BYTES_RANGE.start(), *MAX_BYTES_RANGE.end()));
+        tool_request.query.max_bytes = [request.max_bytes.or(settings.max_response_bytes), client_budget].into_iter().flatten().min();
+        let request = &tool_request.query;
+
+        let operation = format!("docs lookup of {}", libraries.join(", "));
+        let offline = OfflineMode::resolve(request.offline).check(&operation);
+        if request.list_versions == Some(true) {
+            return Ok(match offline {
+                Ok(()) => Self::list_versions(&config, &request.library).await,
+                Err(offline) => offline_result(&offline),
+            });
+        }
+        if let Some((batch, requests)) = Self::batch_requests(request) {
+            let footer = offline.is_ok().then(|| SessionUsage::shared().footer(settings.quota_footer_every)).flatten();
+            let result = Self::query_batch(&config, batch, requests, DocsCache::shared(), auto_resolve, offline.err()).await;
+            return Ok(match footer {
+                Some(footer) => Self::append_text(result, &footer),
+                None => result,
+            });
+        }
+        if sources != [DocsSource::Library] {
+            let footer = sources.contains(&DocsSource::Library).then(|| SessionUsage::shared().footer(settings.quota_footer_every));
+            let output = Self::query_sources(&config, &tool_request, &sources, DocsCache::shared(), auto_resolve, offline.err()).await;
+            return match output {
+                Ok(output) => Self::success(output, &tool_request, save_target.as_deref(), footer.flatten()),
+                Err(result) => Ok(result),
+            };
+        }
+        if let Err(offline) = offline {
+            return match Self::query_offline(request, DocsCache::shared(), &offline) {
+                Ok(output) => Self::success(output, &tool_request, save_target.as_deref(), None),
+                Err(result) => Ok(result),
+            };
+        }
 
-        match Self::fetch_docs(&config, &request).await {
-            Ok(result) => {
+        match Self::fetch_docs(&config, request, DocsCache::shared(), auto_resolve).await {
+            Ok(output) => {
                 log_important!(info, "Docs query success");
-                Ok(CallToolResult {
-                    content: vec![Content::text(result)],
-                    is_error: Some(false),
-                    meta: None,
-                    structured_content: None,
-                })
+                let footer = SessionUsage::shared().footer(settings.quota_footer_every);
+                Self::success(output, &tool_request, save_target.as_deref(), footer)
+            }
+            // Timeouts and network failures become a retryable JSON-RPC error
+            Err(e) if is_upstream_unavailable(&e) => {
+                log_important!(warn, "Docs query failed: {:#}", e);
+                Err(e.context("Docs query failed").into())
             }
             Err(e) => {
-                let error_msg = format!("Docs query failed: {}", e);
+                let error_msg = Message::DocsQueryFailed { error: &e.to_string() }.text();
                 log_important!(warn, "{}", error_msg);
                 Ok(CallToolResult {
                     content: vec![Content::text(error_msg)],
@@ -49,6 +208,524 @@ impl DocsTool {
         }
     }
 
+    /// Reject parameters the schema marks as invalid
+    fn validate_request(tool_request: &DocsToolRequest) -> Result<(), McpError> {
+        let request = &tool_request.query;
+        let libraries = Self::requested_libraries(request);
+        if libraries.is_empty() {
+            return Err(McpError::invalid_params("library is required".to_string(), None));
+        }
+        if request.libraries.iter().flatten().any(|library| library.trim().is_empty()) {
+            return Err(McpError::invalid_params(
+                "libraries must not contain empty identifiers".to_string(),
+                Some(json!({ "path": "libraries" })),
+            ));
+        }
+        if libraries.len() > MAX_BATCH_LIBRARIES {
+            return Err(McpError::invalid_params(
+                format!("at most {} libraries can be looked up at once, got {}", MAX_BATCH_LIBRARIES, libraries.len()),
+                Some(json!({ "path": "libraries" })),
+            ));
+        }
+        let topics = Self::requested_topics(request);
+        if request.topics.iter().flatten().any(|topic| topic.trim().is_empty()) {
+            return Err(McpError::invalid_params(
+                "topics must not contain empty topics".to_string(),
+                Some(json!({ "path": "topics" })),
+            ));
+        }
+        if topics.len() > MAX_BATCH_TOPICS {
+            return Err(McpError::invalid_params(
+                format!("at most {} topics can be looked up at once, got {}", MAX_BATCH_TOPICS, topics.len()),
+                Some(json!({ "path": "topics" })),
+            ));
+        }
+        if libraries.len() > 1 && topics.len() > 1 {
+            return Err(McpError::invalid_params(
+                "topics cannot be combined with several libraries; use docs_compare for one topic across libraries".to_string(),
+                Some(json!({ "path": "topics" })),
+            ));
+        }
+        if request.list_versions == Some(true) {
+            for (set, path) in [
+                (libraries.len() > 1, "libraries"),
+                (topics.len() > 1, "topics"),
+                (tool_request.sources.is_some(), "sources"),
+                (tool_request.save_to.is_some(), "save_to"),
+            ] {
+                if set {
+                    return Err(McpError::invalid_params(
+                        format!("{} cannot be combined with list_versions", path),
+                        Some(json!({ "path": path })),
+                    ));
+                }
+            }
+        }
+        for (several, what) in [(libraries.len() > 1, "several libraries"), (topics.len() > 1, "several topics")] {
+            if !several {
+                continue;
+            }
+            for (set, path) in [(tool_request.sources.is_some(), "sources"), (tool_request.save_to.is_some(), "save_to")] {
+                if set {
+                    return Err(McpError::invalid_params(
+                        format!("{} cannot be combined with {}", path, what),
+                        Some(json!({ "path": path })),
+                    ));
+                }
+            }
+        }
+        if let Some(max_snippets) = request.max_snippets {
+            if !MAX_SNIPPETS_RANGE.contains(&max_snippets) {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "max_snippets must be between {} and {}, got {}",
+                        MAX_SNIPPETS_RANGE.start(),
+                        MAX_SNIPPETS_RANGE.end(),
+                        max_snippets
+                    ),
+                    None,
+                ));
+            }
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            if !MAX_TOKENS_RANGE.contains(&max_tokens) {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "max_tokens must be between {} and {}, got {}",
+                        MAX_TOKENS_RANGE.start(),
+                        MAX_TOKENS_RANGE.end(),
+                        max_tokens
+                    ),
+                    Some(json!({ "path": "max_tokens" })),
+                ));
+            }
+        }
+        if let Some(max_bytes) = request.max_bytes {
+            if !MAX_BYTES_RANGE.contains(&max_bytes) {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "max_bytes must be between {} and {}, got {}",
+                        MAX_BYTES_RANGE.start(),
+                        MAX_BYTES_RANGE.end(),
+                        max_bytes
+                    ),
+                    Some(json!({ "path": "max_bytes" })),
+                ));
+            }
+        }
+        if let Some(max_pages) = request.max_pages {
+            if !MAX_PAGES_RANGE.contains(&max_pages) {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "max_pages must be between {} and {}, got {}",
+                        MAX_PAGES_RANGE.start(),
+                        MAX_PAGES_RANGE.end(),
+                        max_pages
+                    ),
+                    Some(json!({ "path": "max_pages" })),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// `library` followed by `libraries`, trimmed and without duplicates
+    fn requested_libraries(request: &DocsRequest) -> Vec<String> {
+        let mut libraries: Vec<String> = Vec::new();
+        for library in std::iter::once(&request.library).chain(request.libraries.iter().flatten()) {
+            let library = library.trim();
+            if !library.is_empty() && !libraries.iter().any(|seen| seen == library) {
+                libraries.push(library.to_string());
+            }
+        }
+        libraries
+    }
+
+    /// `topic` followed by `topics`, trimmed and without duplicates
+    fn requested_topics(request: &DocsRequest) -> Vec<String> {
+        let mut topics: Vec<String> = Vec::new();
+        for topic in request.topic.iter().chain(request.topics.iter().flatten()) {
+            let topic = topic.trim();
+            if !topic.is_empty() && !topics.iter().any(|seen| seen == topic) {
+                topics.push(topic.to_string());
+            }
+        }
+        topics
+    }
+
+    /// One lookup per library, or per topic of the one library; `None` for a single lookup
+    fn batch_requests(request: &DocsRequest) -> Option<(Batch, Vec<DocsRequest>)> {
+        let libraries = Self::requested_libraries(request);
+        let topics = Self::requested_topics(request);
+        if libraries.len() > 1 {
+            let requests = libraries.into_iter().map(|library| DocsRequest { library, libraries: None, ..request.clone() });
+            Some((Batch::Libraries, requests.collect()))
+        } else if topics.len() > 1 {
+            let requests = topics.into_iter().map(|topic| DocsRequest { topic: Some(topic), topics: None, ..request.clone() });
+            Some((Batch::Topics, requests.collect()))
+        } else {
+            None
+        }
+    }
+
+    /// Look up several libraries, or several topics of one library, concurrently, one section each
+    ///
+    /// A failing lookup becomes a section with its error; the result is an
+    /// error only when every lookup failed.
+    async fn query_batch(
+        config: &DocsConfig,
+        batch: Batch,
+        requests: Vec<DocsRequest>,
+        cache: &DocsCache,
+        auto_resolve: bool,
+        offline: Option<OfflineError>,
+    ) -> CallToolResult {
+        let count = requests.len();
+        let lookups = requests.into_iter().map(|request| {
+            let offline = offline.as_ref();
+            async move {
+                let output = match offline {
+                    Some(offline) => Self::query_offline(&request, cache, offline).map_err(|_| format!("{} and it is not cached", offline)),
+                    None => Self::fetch_docs(config, &request, cache, auto_resolve).await.map_err(|e| format!("{:#}", e)),
+                };
+                (request, output)
+            }
+        });
+        let outputs = futures::future::join_all(lookups).await;
+        let library = outputs.first().map(|(request, _)| request.library.clone()).unwrap_or_default();
+        let topics: Vec<String> = outputs.iter().filter_map(|(request, _)| request.topic.clone()).collect();
+
+        let mut sections = Vec::new();
+        let mut entries = Vec::new();
+        let mut failed = 0;
+        for (request, output) in outputs {
+            match output {
+                Ok(output) => {
+                    let mut entry = output.structured.unwrap_or_else(|| json!({}));
+                    let found = match (&output.library, &output.response) {
+                        (Some(library), Some(response)) => {
+                            if let (Some(entry), serde_json::Value::Object(payload)) =
+                                (entry.as_object_mut(), lookup_payload(library, response, &request))
+                            {
+                                entry.extend(payload);
+                            }
+                            true
+                        }
+                        _ => false,
+                    };
+                    match batch {
+                        Batch::Libraries => entry["requested"] = json!(request.library),
+                        Batch::Topics => entry["topic"] = json!(request.topic),
+                    }
+                    entry["status"] = json!(if found { "ok" } else { "no_docs" });
+                    if !found {
+                        entry["message"] = json!(output.text);
+                    }
+                    sections.push(if found { output.text } else { format!("{}{}", Self::section_title(&request, batch), output.text) });
+                    entries.push(entry);
+                }
+                Err(error) => {
+                    failed += 1;
+                    log_important!(warn, "Docs query for {} ({:?}) failed: {}", request.library, request.topic, error);
+                    let title = Self::section_title(&request, batch);
+                    sections.push(format!("{}{}", title, Message::DocsQueryFailed { error: &error }.text()));
+                    let mut entry = json!({ "status": "error", "error": error });
+                    match batch {
+                        Batch::Libraries => entry["requested"] = json!(request.library),
+                        Batch::Topics => entry["topic"] = json!(request.topic),
+                    }
+                    entries.push(entry);
+                }
+            }
+        }
+
+        let (text, structured) = match batch {
+            Batch::Libraries => {
+                log_important!(info, "Docs batch query of {} libraries: {} failed", count, failed);
+                let libraries: Vec<&str> = entries.iter().filter_map(|entry| entry["requested"].as_str()).collect();
+                let text = format!("Documentation for {} libraries: {}\n\n{}", count, libraries.join(", "), sections.join("\n\n"));
+                (text, json!({ "libraries": entries }))
+            }
+            Batch::Topics => {
+                log_important!(info, "Docs batch query of {} topics of {}: {} failed", count, library, failed);
+                let text = format!("Documentation of {} for {} topics: {}\n\n{}", library, count, topics.join(", "), sections.join("\n\n"));
+                (text, json!({ "library": library, "topics": entries }))
+            }
+        };
+        CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(failed == count),
+            meta: None,
+            structured_content: Some(structured),
+        }
+    }
+
+    /// Heading of a batch section without docs: the library, and the topic when fanning out over topics
+    fn section_title(request: &DocsRequest, batch: Batch) -> String {
+        let title = Message::DocsTitle { library: &request.library }.text();
+        match (batch, &request.topic) {
+            (Batch::Topics, Some(topic)) => format!("{}\n\n{}\n\n", title, Message::DocsTopic { topic }.text()),
+            _ => format!("{}\n\n", title),
+        }
+    }
+
+    /// Add `text` as a paragraph after the result's text
+    pub(super) fn append_text(mut result: CallToolResult, text: &str) -> CallToolResult {
+        if let Some(raw) = result.content.iter_mut().find_map(|content| match &mut content.raw {
+            RawContent::Text(raw) => Some(raw),
+            _ => None,
+        }) {
+            raw.text.push_str(&format!("\n\n{}", text));
+        }
+        result
+    }
+
+    /// `sources`, deduplicated; library docs only when not given
+    fn selected_sources(request: &DocsToolRequest) -> Result<Vec<DocsSource>, ToolError> {
+        let Some(requested) = &request.sources else {
+            return Ok(vec![DocsSource::Library]);
+        };
+        let mut sources = Vec::new();
+        for source in requested {
+            if !sources.contains(source) {
+                sources.push(*source);
+            }
+        }
+        if sources.is_empty() {
+            return Err(McpError::invalid_params("sources must name at least one source".to_string(), Some(json!({ "path": "sources" }))).into());
+        }
+        if sources.contains(&DocsSource::Project) {
+            let Some(root) = &request.project_root_path else {
+                return Err(McpError::invalid_params(
+                    "project_root_path is required with the project source".to_string(),
+                    Some(json!({ "path": "project_root_path" })),
+                )
+                .into());
+            };
+            if let Err(e) = validate_project_path(root) {
+                return Err(project_path_error(format!("Path validation failed: {}\nOriginal path: {}", e, root)).into());
+            }
+        }
+        Ok(sources)
+    }
+
+    /// Look up the selected sources concurrently and merge them into one answer
+    ///
+    /// Fails with an error result only when every source failed.
+    async fn query_sources(
+        config: &DocsConfig,
+        tool_request: &DocsToolRequest,
+        sources: &[DocsSource],
+        cache: &DocsCache,
+        auto_resolve: bool,
+        offline: Option<OfflineError>,
+    ) -> Result<DocsOutput, CallToolResult> {
+        let request = &tool_request.query;
+        let budget = request.max_snippets.map_or(merge::DEFAULT_MERGED_SNIPPETS, |max| max as usize);
+        let query = request.topic.clone().unwrap_or_else(|| request.library.clone());
+
+        let project = async {
+            if !sources.contains(&DocsSource::Project) {
+                return None;
+            }
+            let root = tool_request.project_root_path.as_deref().unwrap_or_default();
+            Some(Self::search_project(root, &query, budget).await)
+        };
+        let upstream = async {
+            if !sources.contains(&DocsSource::Library) {
+                return None;
+            }
+            let output = match &offline {
+                Some(offline) => Self::query_offline(request, cache, offline).map_err(|_| format!("{} and it is not cached", offline)),
+                None => Self::fetch_docs(config, request, cache, auto_resolve).await.map_err(|e| format!("{:#}", e)),
+            };
+            Some(output.map(|output| match (output.library, output.response) {
+                (Some(library), Some(response)) => {
+                    let banner = output.structured.as_ref().and_then(|s| s["resolution"]["banner"].as_str()).map(str::to_string);
+                    merge::Upstream::Docs { library, response, banner }
+                }
+                _ => merge::Upstream::Message(output.text),
+            }))
+        };
+        let (project, upstream) = tokio::join!(project, upstream);
+
+        let merged = merge::merge(&query, merge::Sources { project, upstream }, budget);
+        log_important!(
+            info,
+            "Docs query from {:?}: {} warning(s)",
+            sources,
+            merged.structured["warnings"].as_array().map_or(0, Vec::len)
+        );
+        if merged.failed {
+            return Err(CallToolResult {
+                content: vec![Content::text(merged.text)],
+                is_error: Some(true),
+                meta: None,
+                structured_content: Some(merged.structured),
+            });
+        }
+        Ok(DocsOutput { text: merged.text, structured: Some(merged.structured), library: merged.library, response: None })
+    }
+
+    /// Sections of the project's own docs matching `query`
+    async fn search_project(root: &str, query: &str, budget: usize) -> Result<Vec<SearchHit>, String> {
+        let root = PathBuf::from(decode_and_normalize_path(root).map_err(|e| e.to_string())?);
+        let globs = ProjectDocsTool::configured_globs();
+        let query = query.to_string();
+        // Enough hits to take over budget the library docs leave unused
+        let limit = budget.clamp(1, *PROJECT_LIMIT_RANGE.end());
+        tokio::task::spawn_blocking(move || index_project_docs(&root, &globs).map(|index| index.search(&query, limit)))
+            .await
+            .map_err(|e| format!("Project docs indexing task failed: {}", e))?
+            .map_err(|e| format!("{:#}", e))
+    }
+
+    /// Where `save_to` writes, checked before any lookup
+    fn save_target(request: &DocsToolRequest) -> Result<Option<PathBuf>, ToolError> {
+        let Some(save_to) = &request.save_to else {
+            return Ok(None);
+        };
+        let Some(root) = &request.project_root_path else {
+            return Err(McpError::invalid_params(
+                "project_root_path is required with save_to".to_string(),
+                Some(json!({ "path": "project_root_path" })),
+            )
+            .into());
+        };
+        if let Err(e) = validate_project_path(root) {
+            return Err(project_path_error(format!("Path validation failed: {}\nOriginal path: {}", e, root)).into());
+        }
+        let root = PathBuf::from(decode_and_normalize_path(root)?);
+        Ok(Some(save::resolve_target(&root, save_to, request.overwrite.unwrap_or(false))?))
+    }
+
+    /// Successful lookup as a tool result, saved to `save_target` first when set
+    fn success(
+        mut output: DocsOutput,
+        request: &DocsToolRequest,
+        save_target: Option<&Path>,
+        footer: Option<String>,
+    ) -> Result<CallToolResult, ToolError> {
+        if let (Some(library), Some(response)) = (&output.library, &output.response) {
+            match (output.structured.as_mut(), lookup_payload(library, response, &request.query)) {
+                (Some(serde_json::Value::Object(structured)), serde_json::Value::Object(payload)) => structured.extend(payload),
+                (_, payload) => output.structured = Some(payload),
+            }
+        }
+        if let (Some(target), Some(save_to)) = (save_target, &request.save_to) {
+            let saved = match &output.library {
+                Some(library) => {
+                    let document = save::front_matter(library, &request.query, Utc::now()) + &output.text + "\n";
+                    let saved = save::write(target, save_to, &document, request.overwrite.unwrap_or(false))?;
+                    if dry_run::is_active() {
+                        dry_run::record(json!({ "action": "save", "path": saved.path, "bytes": saved.bytes, "overwrite": saved.overwritten }));
+                        output.text.push_str(&format!("\n\n_Would save to `{}` ({} bytes)._", saved.path.display(), saved.bytes));
+                    } else {
+                        log_important!(info, "Docs saved to {} ({} bytes)", saved.path.display(), saved.bytes);
+                        output.text.push_str(&format!("\n\n_Saved to `{}`._", saved.path.display()));
+                    }
+                    Some(saved)
+                }
+                None => {
+                    output.text.push_str("\n\n_Nothing saved: no documentation was returned._");
+                    None
+                }
+            };
+            let structured = output.structured.get_or_insert_with(|| json!({}));
+            structured["saved"] = json!(saved);
+        }
+        if let Some(footer) = footer {
+            output.text.push_str(&format!("\n\n{}", footer));
+        }
+        Ok(CallToolResult {
+            content: vec![Content::text(output.text)],
+            is_error: Some(false),
+            meta: None,
+            // Results without docs (not found, suggestions) are still objects, as the output schema says
+            structured_content: Some(output.structured.unwrap_or_else(|| json!({}))),
+        })
+    }
+
+    /// Output schema: every field is optional because the shape depends on the call
+    ///
+    /// A lookup fills the library, snippet and pagination fields; `sources`
+    /// answers add per-source status, a not-found lookup `available_topics`
+    /// and cache operations their statistics.
+    fn output_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "library": { "type": "string", "description": "Library the snippets come from (the resolved one after auto_resolve)" },
+                "topic": { "type": ["string", "null"] },
+                "version": { "type": ["string", "null"] },
+                "doc_type": { "type": "string", "enum": ["code", "info", "auto"], "description": "Type of the returned docs; info after an auto fallback" },
+                "source": { "type": "string", "description": "Where the snippets come from, e.g. library" },
+                "snippets": {
+                    "type": "array",
+                    "description": "Snippets shown, most relevant first",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "source": { "type": "string" },
+                            "library": { "type": "string" },
+                            "title": { "type": ["string", "null"] },
+                            "content": { "type": "string" },
+                            "score": { "type": ["number", "null"] },
+                            "path": { "type": "string", "description": "Project file of a project snippet" }
+                        },
+                        "required": ["content"]
+                    }
+                },
+                "total_snippets": { "type": "integer", "description": "Snippets found before max_snippets" },
+                "pagination": {
+                    "type": ["object", "null"],
+                    "properties": {
+                        "current_page": { "type": "integer" },
+                        "total_pages": { "type": "integer" },
+                        "has_next": { "type": "boolean" }
+                    }
+                },
+                "duplicates_removed": { "type": "integer", "description": "Near-duplicate snippets left out" },
+                "omitted_by_max_tokens": { "type": "integer", "description": "Snippets left out to stay within max_tokens" },
+                "omitted_by_max_bytes": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Titles of the snippets left out to stay within max_bytes; pass them as `titles` to fetch them"
+                },
+                "truncated": { "type": "boolean", "description": "The snippet shown was cut to fit max_tokens or max_bytes" },
+                "merged_pages": { "type": "integer", "description": "Pages merged into this answer (max_pages)" },
+                "moved_to": { "type": ["string", "null"], "description": "New identifier when the library was renamed" },
+                "auto_fallback": { "type": "boolean" },
+                "snapshot": {
+                    "type": "object",
+                    "description": "Set when offline_ok served a stored snapshot because the docs service was unavailable",
+                    "properties": {
+                        "stored_at": { "type": "string", "format": "date-time" },
+                        "age_secs": { "type": "integer" },
+                        "expired": { "type": "boolean" },
+                        "reason": { "type": "string" }
+                    }
+                },
+                "resolution": { "type": "object", "description": "Set when another library than the one asked for was fetched: `via` is alias for a bare name from the bundled table, search for auto_resolve, learned for a name auto_resolve resolved before" },
+                "libraries": {
+                    "type": "array",
+                    "description": "One entry per library of a several-library lookup, with the lookup fields above plus `requested`, `status` (ok, no_docs or error) and `message` or `error`",
+                    "items": { "type": "object" }
+                },
+                "topics": {
+                    "type": "array",
+                    "description": "One entry per topic of a several-topic lookup, like the `libraries` entries but with `topic` instead of `requested`",
+                    "items": { "type": "object" }
+                },
+                "available_topics": { "type": "array", "items": { "type": "string" } },
+                "versions": { "type": "array", "items": { "type": "string" }, "description": "Documented versions, from list_versions" },
+                "sources": { "type": "object", "description": "Status per source of a sources query" },
+                "warnings": { "type": "array" },
+                "saved": { "type": ["object", "null"], "description": "File written for save_to" }
+            }
+        })
+    }
+
     /// Get tool definition
     pub fn get_tool_definition() -> Tool {
         let schema = json!({
@@ -56,12 +733,24 @@ impl DocsTool {
             "properties": {
                 "library": {
                     "type": "string",
-                    "description": "Library identifier in format: owner/repo (e.g., vercel/next.js, facebook/react)"
+                    "description": "Library identifier in format: owner/repo (e.g., vercel/next.js, facebook/react). Well-known bare names such as nextjs or spring boot are mapped to their identifier, other bare names are searched for. Required unless `cache` is set; with `cache: clear` it limits the wipe to that library"
+                },
+                "libraries": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "maxItems": MAX_BATCH_LIBRARIES,
+                    "description": "More library identifiers to look up in the same call (optional), e.g. to compare APIs across frameworks. They are fetched concurrently together with `library` (which may then be omitted) and answered in one section per library; cannot be combined with sources or save_to"
                 },
                 "topic": {
                     "type": "string",
                     "description": "Query topic (optional, e.g., routing, authentication, core)"
                 },
+                "topics": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "maxItems": MAX_BATCH_TOPICS,
+                    "description": "More topics of the same library to look up in the same call (optional), e.g. [\"routing\", \"auth\", \"config\"]. They are fetched concurrently together with `topic` and answered in one section per topic; cannot be combined with several libraries, sources or save_to"
+                },
                 "version": {
                     "type": "string",
                     "description": "Version number (optional, e.g., v15.1.8)"
@@ -71,9 +760,99 @@ impl DocsTool {
                     "description": "Page number (optional, default 1, max 10)",
                     "minimum": 1,
                     "maximum": 10
+                },
+                "max_pages": {
+                    "type": "integer",
+                    "description": "Fetch up to this many pages starting at `page` and merge their snippets, stopping early when there is no next page or the character budget (docs_page_budget_chars, default 60000) is used up (optional, default 1). With a progressToken in _meta, each page is also sent as a progress notification as soon as it arrives",
+                    "minimum": MAX_PAGES_RANGE.start(),
+                    "maximum": MAX_PAGES_RANGE.end()
+                },
+                "max_snippets": {
+                    "type": "integer",
+                    "description": "Return at most this many snippets, most relevant first (optional)",
+                    "minimum": MAX_SNIPPETS_RANGE.start(),
+                    "maximum": MAX_SNIPPETS_RANGE.end()
+                },
+                "max_tokens": {
+                    "type": "integer",
+                    "description": "Keep the answer within about this many tokens (4 characters each): the most relevant snippets that fit are kept, a first snippet that alone is too long is cut, and a note says what was left out (optional; applies to library docs, not to sources answers)",
+                    "minimum": MAX_TOKENS_RANGE.start(),
+                    "maximum": MAX_TOKENS_RANGE.end()
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "description": "Keep the answer within this many bytes (optional, default from config, 100000): the most relevant snippets that fit are kept and the titles of the others are listed, to be fetched with `titles`",
+                    "minimum": MAX_BYTES_RANGE.start(),
+                    "maximum": MAX_BYTES_RANGE.end()
+                },
+                "titles": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only return the snippets with these titles, as listed when an answer was cut down for max_bytes; use with the same library, topic and page (optional)"
+                },
+                "rerank": {
+                    "type": "boolean",
+                    "description": "Order snippets by how well their title and content match `topic` instead of the upstream order (optional, default false)"
+                },
+                "auto_resolve": {
+                    "type": "boolean",
+                    "description": "If the library is not found, fetch the best matching search result instead of listing suggestions (optional, default from config)"
+                },
+                "offline": {
+                    "type": "boolean",
+                    "description": "Serve from the local cache only and never hit the network (optional, overrides DEVKIT_OFFLINE and the offline config setting)"
+                },
+                "offline_ok": {
+                    "type": "boolean",
+                    "description": "When the docs service is unreachable, failing or degraded, answer from the locally stored snapshot of this lookup even if it has expired, with a banner giving its age (optional, default false)"
+                },
+                "refresh": {
+                    "type": "boolean",
+                    "description": "Skip the cache, fetch from upstream and cache the result again (optional, default false; ignored when offline)"
+                },
+                "list_versions": {
+                    "type": "boolean",
+                    "description": "List the versions of the library that have their own docs instead of fetching docs; pass one of them as `version` (optional)"
+                },
+                "cache": {
+                    "type": "string",
+                    "enum": ["stats", "clear", "reset"],
+                    "description": "Cache management instead of a query (optional): stats reports entries, size, hit ratio and this session's request count; clear wipes the cache or only `library`; reset zeroes the counters"
+                },
+                "doc_type": {
+                    "type": "string",
+                    "enum": ["code", "info", "auto"],
+                    "description": "Documentation type (optional): code snippets (default), info pages (prose), or auto (info pages when few code snippets exist)"
+                },
+                "sources": {
+                    "type": "array",
+                    "items": { "type": "string", "enum": ["library", "project"] },
+                    "description": "Sources to answer from (optional, default [\"library\"]): library the library docs, project the project's own README, docs/ and doc comments (needs project_root_path). Several sources are queried concurrently and merged, project sections first, splitting max_snippets (default 10) between them"
+                },
+                "save_to": {
+                    "type": "string",
+                    "description": "Also write the result as Markdown with a front-matter header (library, topic, version, fetch time, source) to this path relative to project_root_path (optional, e.g. docs/nextjs-routing.md)"
+                },
+                "project_root_path": {
+                    "type": "string",
+                    "description": "Absolute path of the project root; required with save_to and the project source"
+                },
+                "overwrite": {
+                    "type": "boolean",
+                    "description": "Replace an existing save_to file (optional, default false)"
+                },
+                "language": {
+                    "type": "string",
+                    "enum": ["en", "zh"],
+                    "description": "Language of titles, hints and error messages in the reply (optional, default the session language from the language setting or the client)"
+                },
+                "format": {
+                    "type": "string",
+                    "enum": ["markdown", "json", "plain"],
+                    "description": "Format of the reply text (optional): markdown (default), json (the structured result as JSON text, for clients that parse the text) or plain (no headings, emphasis, quotes or rules). Structured content and save_to files are not affected"
                 }
             },
-            "required": ["library"]
+            "required": []
         });
 
         if let serde_json::Value::Object(schema_map) = schema {
@@ -83,14 +862,14 @@ impl DocsTool {
                 input_schema: Arc::new(schema_map),
                 annotations: Some(ToolAnnotations {
                     title: Some("Docs Lookup".to_string()),
-                    read_only_hint: Some(true),       // Only reads external docs
-                    destructive_hint: Some(false),    // Not destructive
+                    read_only_hint: Some(false),      // save_to writes a file into the project
+                    destructive_hint: Some(false),    // Never replaces a file without overwrite: true
                     idempotent_hint: Some(true),      // Same query = same result
                     open_world_hint: Some(true),      // Interacts with external API
                 }),
                 icons: None,
                 meta: None,
-                output_schema: None,
+                output_schema: Self::output_schema().as_object().cloned().map(Arc::new),
                 title: Some("Docs Lookup".to_string()),
             }
         } else {
@@ -98,189 +877,707 @@ impl DocsTool {
         }
     }
 
-    /// Get config
-    async fn get_config() -> Result<DocsConfig> {
-        let config = crate::config::load_standalone_config()
-            .map_err(|e| anyhow::anyhow!("Failed to read config: {}", e))?;
-
-        Ok(DocsConfig {
-            api_key: config.mcp_config.docs_api_key,
-            base_url: docs_api_base_url(),
-        })
-    }
-
-    /// Fetch docs via HTTP
-    async fn fetch_docs(config: &DocsConfig, request: &DocsRequest) -> Result<String> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()?;
-
-        let url = format!("{}/docs/code/{}", config.base_url, request.library);
-        log_debug!("Docs request URL: {}", url);
-
-        let mut req_builder = client.get(&url);
-
-        if let Some(api_key) = &config.api_key {
-            req_builder = req_builder.header(AUTHORIZATION, format!("Bearer {}", api_key));
-            log_debug!("Using API Key for auth");
-        } else {
-            log_debug!("Free mode, no API Key");
+    /// Start the docs API contract check in the background when
+    /// `docs_contract_preflight` is set; never delays or fails startup
+    pub async fn start_contract_preflight() {
+        let enabled = crate::config::load_standalone_config()
+            .map(|config| config.mcp_config.docs_contract_preflight)
+            .unwrap_or(false);
+        if !enabled {
+            return;
+        }
+        if let Err(e) = crate::config::offline_mode().check("docs API contract check") {
+            log_debug!("{}", e);
+            return;
         }
+        match Self::get_config().await.and_then(|(config, _)| Ok(Client::new(config)?)) {
+            Ok(client) => {
+                contract::spawn_preflight(client);
+            }
+            Err(e) => log_important!(warn, "Docs API contract check skipped: {}", e),
+        }
+    }
 
-        if let Some(topic) = &request.topic {
-            req_builder = req_builder.query(&[("topic", topic)]);
+    /// Check the configured API keys in the background unless
+    /// `docs_key_check` is off; never delays or fails startup
+    pub async fn start_key_check() {
+        let enabled = crate::config::load_standalone_config()
+            .map(|config| config.mcp_config.docs_key_check.unwrap_or(true))
+            .unwrap_or(false);
+        if !enabled {
+            return;
         }
-        if let Some(version) = &request.version {
-            req_builder = req_builder.query(&[("version", version)]);
+        let client = match Self::get_config().await.and_then(|(config, _)| Ok(Client::new(config)?)) {
+            Ok(client) => client,
+            Err(e) => {
+                log_important!(warn, "Docs API key check skipped: {}", e);
+                return;
+            }
+        };
+        if client.keys().is_empty() {
+            return;
         }
-        if let Some(page) = request.page {
-            req_builder = req_builder.query(&[("page", page.to_string())]);
+        if let Err(e) = crate::config::offline_mode().check("docs API key check") {
+            log_debug!("{}", e);
+            return;
         }
+        key_check::spawn_key_check(client);
+    }
 
-        let response = req_builder.send().await?;
-        let status = response.status();
-
-        log_debug!("Docs response status: {}", status);
-
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unable to read error".to_string());
+    /// Get the client config and the tool settings from the live config,
+    /// so changed API keys apply to the next call
+    pub(crate) async fn get_config() -> Result<(DocsConfig, ToolSettings)> {
+        let config = crate::config::live::current();
 
-            if status.as_u16() == 404 {
-                log_important!(info, "Library '{}' not found, triggering search", request.library);
-                return Self::handle_not_found_with_search(config, request).await;
-            }
+        let docs_config = Self::client_config(&config.mcp_config, std::env::var(DOCS_API_URL_ENV).ok())?;
+        let settings = ToolSettings {
+            auto_resolve: config.mcp_config.docs_auto_resolve,
+            quota_footer_every: config.mcp_config.docs_quota_footer_every.unwrap_or(DEFAULT_FOOTER_EVERY),
+            max_response_bytes: match config.mcp_config.docs_max_response_bytes {
+                Some(0) => None,
+                Some(bytes) => Some(u32::try_from(bytes).unwrap_or(u32::MAX)),
+                None => Some(DEFAULT_MAX_RESPONSE_BYTES),
+            },
+        };
+        Ok((docs_config, settings))
+    }
 
-            return Err(anyhow::anyhow!(
-                "API request failed (status: {}): {}",
-                status,
-                Self::format_error_message(status.as_u16(), &error_text)
-            ));
+    /// Client config from the settings; `env_url` ([`DOCS_API_URL_ENV`]) takes
+    /// precedence over `docs_base_url`
+    fn client_config(mcp_config: &McpConfig, env_url: Option<String>) -> Result<DocsConfig> {
+        let mut docs_config = mcp_config.docs_client_config();
+        if let Some(url) = env_url.filter(|url| !url.trim().is_empty()) {
+            docs_config.base_url = url.trim().trim_end_matches('/').to_string();
         }
+        reqwest::Url::parse(&docs_config.base_url)
+            .map_err(|e| anyhow::anyhow!("Invalid docs API URL {}: {}", docs_config.base_url, e))?;
+        Ok(docs_config)
+    }
 
-        let response_text = response.text().await?;
+    /// Cache stats, clear or counter reset, as text plus the same numbers as structured content
+    fn cache_operation(action: CacheAction, library: &str) -> CallToolResult {
+        let cache = DocsCache::shared();
+        let usage = SessionUsage::shared();
+        let keys = KeyPool::current();
+        let (text, structured) = match action {
+            CacheAction::Stats => {
+                let stats = cache.stats();
+                let session = usage.snapshot();
+                let key_usage = keys.as_ref().map(|pool| pool.usage()).unwrap_or_default();
+                let text = Self::format_cache_stats(&stats, &session, &key_usage);
+                let structured = serde_json::to_value(&stats).map(|mut value| {
+                    value["session"] = json!(session);
+                    value["keys"] = json!(key_usage);
+                    value
+                });
+                (text, structured)
+            }
+            CacheAction::Clear if dry_run::is_active() => {
+                let library = Some(library).filter(|l| !l.trim().is_empty());
+                let (result, files) = cache.clear_preview(library);
+                let mut change = json!(result);
+                change["action"] = json!("clear");
+                change["files"] = json!(files);
+                dry_run::record(change);
+                (result.to_string().replacen("Cleared", "Would clear", 1), serde_json::to_value(&result))
+            }
+            CacheAction::Clear => {
+                let library = Some(library).filter(|l| !l.trim().is_empty());
+                let result = cache.clear(library);
+                log_important!(info, "{}", result);
+                (result.to_string(), serde_json::to_value(&result))
+            }
+            CacheAction::Reset if dry_run::is_active() => {
+                let session = usage.snapshot();
+                dry_run::record(json!({ "action": "reset", "cache": cache.stats(), "session": session }));
+                ("Would reset cache hit/miss and session request counters".to_string(), Ok(json!({ "reset": false })))
+            }
+            CacheAction::Reset => {
+                cache.reset_counters();
+                usage.reset();
+                if let Some(pool) = &keys {
+                    pool.reset_counters();
+                }
+                log_important!(info, "Docs cache and session counters reset");
+                ("Cache hit/miss and session request counters reset".to_string(), Ok(json!({ "reset": true })))
+            }
+        };
 
-        if response_text.trim().is_empty() {
-            return Ok("No documentation found. Try adjusting query parameters.".to_string());
+        CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: structured.ok(),
         }
+    }
 
-        Ok(Self::format_text_response(&response_text, request))
+    /// Markdown of the `cache: stats` action
+    pub(crate) fn format_cache_stats(stats: &CacheStats, session: &UsageSnapshot, keys: &[KeyUsage]) -> String {
+        format!(
+            "# Docs Cache\n\n{}\n## Session\n\nRequests since {}: {}\n",
+            stats,
+            session.since.to_rfc3339(),
+            session.requests
+        ) + &Self::format_rate_limit(session)
+            + &Self::format_key_usage(keys)
     }
 
-    /// Format error message
-    fn format_error_message(status_code: u16, error_text: &str) -> String {
-        match status_code {
-            401 => "Invalid or expired API key".to_string(),
-            404 => format!("Library not found: {}", error_text),
-            429 => "Rate limit reached, consider configuring an API Key".to_string(),
-            500..=599 => format!("Docs server error: {}", error_text),
-            _ => error_text.to_string(),
+    /// Rate-limit line of the stats output
+    pub(super) fn format_rate_limit(session: &UsageSnapshot) -> String {
+        match session.rate_limit {
+            Some(rate_limit) => format!(
+                "Rate limit: {} remaining of {}\n",
+                rate_limit.remaining.map_or("?".to_string(), |n| n.to_string()),
+                rate_limit.limit.map_or("?".to_string(), |n| n.to_string())
+            ),
+            None => "Rate limit: not reported yet\n".to_string(),
         }
     }
 
-    /// Format text response to Markdown
-    fn format_text_response(content: &str, request: &DocsRequest) -> String {
-        let mut output = String::new();
-
-        output.push_str(&format!("# {} Documentation\n\n", request.library));
-
-        if let Some(topic) = &request.topic {
-            output.push_str(&format!("**Topic**: {}\n", topic));
-        }
-        if let Some(version) = &request.version {
-            output.push_str(&format!("**Version**: {}\n", version));
-        }
-        if let Some(page) = request.page {
-            output.push_str(&format!("**Page**: {}\n", page));
+    /// Per-key section of the stats output; omitted with fewer than two keys
+    fn format_key_usage(keys: &[KeyUsage]) -> String {
+        if keys.len() < 2 {
+            return String::new();
         }
-        output.push_str("\n---\n\n");
-
-        output.push_str(content);
-
-        output.push_str(&format!("\n\n---\nSource: Docs - {}\n", request.library));
-
-        output
+        let lines: Vec<String> = keys
+            .iter()
+            .map(|k| format!("- {}: {} request{} ({})", k.key, k.requests, if k.requests == 1 { "" } else { "s" }, k.status))
+            .collect();
+        format!("\n## API Keys\n\n{}\n", lines.join("\n"))
     }
 
-    /// Handle 404 error: search for candidate libraries
-    async fn handle_not_found_with_search(
+    /// Fetch docs through the library client and render them as Markdown
+    pub(super) async fn fetch_docs(
         config: &DocsConfig,
         request: &DocsRequest,
-    ) -> Result<String> {
-        let search_query = if request.library.contains('/') {
-            request.library.split('/').last().unwrap_or(&request.library)
-        } else {
-            &request.library
-        };
+        cache: &DocsCache,
+        auto_resolve: bool,
+    ) -> Result<DocsOutput> {
+        let client = Client::new(config.clone())?;
 
-        log_debug!("Search query: {}", search_query);
+        // A shorthand the search resolved before goes straight to its library
+        if let Some(output) = Self::fetch_learned(&client, request, cache).await? {
+            return Ok(output);
+        }
 
-        match Self::search_libraries(config, search_query).await {
-            Ok(results) => {
-                if results.is_empty() {
-                    Ok(Self::format_not_found_no_suggestions(&request.library))
-                } else {
-                    Ok(Self::format_not_found_with_suggestions(&request.library, &results))
+        // A bare name cannot be fetched as is: use its alias or search for it
+        if !resolve::is_library_id(&request.library) {
+            if let Some(id) = resolve::alias(&request.library) {
+                let aliased = DocsRequest { library: id.to_string(), ..request.clone() };
+                match Self::fetch_cached(&client, &aliased, cache).await {
+                    Ok(response) if !response.is_empty() => {
+                        log_important!(info, "Resolved '{}' to '{}' from the alias table", request.library, id);
+                        return Ok(Self::resolved_output(request, &aliased, response, 1.0, "alias"));
+                    }
+                    Ok(_) | Err(DocsError::NotFound { .. }) => log_debug!("Alias {} of '{}' has no docs, searching", id, request.library),
+                    Err(e) => return Err(e.into()),
                 }
             }
-            Err(e) => {
-                log_debug!("Search failed: {}", e);
-                Ok(Self::format_not_found_no_suggestions(&request.library))
+            log_debug!("'{}' is not an owner/repo id, resolving it by search", request.library);
+            return Ok(Self::handle_not_found_with_search(&client, request, cache, auto_resolve).await);
+        }
+
+        match Self::fetch_cached(&client, request, cache).await {
+            Ok(response) => {
+                if response.is_empty() {
+                    return Ok(Self::no_documentation(&client, request, cache).await);
+                }
+                Ok(DocsOutput {
+                    text: Self::format_response(&response, request),
+                    structured: None,
+                    library: Some(request.library.clone()),
+                    response: Some(response),
+                })
+            }
+            Err(DocsError::NotFound { .. }) => {
+                log_important!(info, "Library '{}' not found, triggering search", request.library);
+                Ok(Self::handle_not_found_with_search(&client, request, cache, auto_resolve).await)
             }
+            Err(e) if request.offline_ok == Some(true) && e.is_upstream_unavailable() => {
+                match cache.snapshot(&CacheKey::from_request(request)) {
+                    Some(snapshot) => {
+                        log_important!(warn, "Docs upstream unavailable, serving the stored snapshot of {}: {}", request.library, e);
+                        Ok(Self::from_snapshot(snapshot, request, &e))
+                    }
+                    None => Err(e.into()),
+                }
+            }
+            Err(e) => Err(e.into()),
         }
     }
 
-    /// Search libraries
-    async fn search_libraries(config: &DocsConfig, query: &str) -> Result<Vec<SearchResult>> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(15))
-            .build()?;
-
-        let url = format!("{}/search", config.base_url);
-        log_debug!("Docs search URL: {}", url);
-
-        let mut req_builder = client.get(&url).query(&[("query", query)]);
+    /// Docs of the library an earlier search resolved `request.library` to
+    ///
+    /// `None` when nothing was learned for it, or when the learned library no
+    /// longer exists; that alias is forgotten and the lookup starts over.
+    async fn fetch_learned(client: &Client, request: &DocsRequest, cache: &DocsCache) -> Result<Option<DocsOutput>, DocsError> {
+        let Some(id) = cache.aliases().get(&request.library) else {
+            return Ok(None);
+        };
+        let learned = DocsRequest { library: id, ..request.clone() };
+        match Self::fetch_cached(client, &learned, cache).await {
+            Ok(response) if response.is_empty() => Ok(Some(Self::no_documentation(client, &learned, cache).await)),
+            Ok(response) => {
+                log_important!(info, "Resolved '{}' to '{}' from the learned aliases", request.library, learned.library);
+                Ok(Some(Self::resolved_output(request, &learned, response, 1.0, "learned")))
+            }
+            Err(DocsError::NotFound { .. }) => {
+                log_important!(info, "Learned alias {} of '{}' no longer exists, forgetting it", learned.library, request.library);
+                cache.aliases().forget(&request.library);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// `offline_ok`: a stored snapshot with a banner saying how old it is and why it is used
+    fn from_snapshot(snapshot: Snapshot, request: &DocsRequest, error: &DocsError) -> DocsOutput {
+        let age = Utc::now().signed_duration_since(snapshot.stored_at).max(chrono::Duration::zero());
+        let banner = format!(
+            "> **Offline snapshot** from {} ({} old{}): the docs service is unavailable ({}).\n\n",
+            snapshot.stored_at.format("%Y-%m-%d %H:%M UTC"),
+            format_age(age),
+            if snapshot.expired { ", past the cache lifetime and possibly outdated" } else { "" },
+            error
+        );
+        DocsOutput {
+            text: banner + &Self::format_response(&snapshot.response, request),
+            structured: Some(json!({
+                "snapshot": {
+                    "stored_at": snapshot.stored_at.to_rfc3339(),
+                    "age_secs": age.num_seconds(),
+                    "expired": snapshot.expired,
+                    "reason": error.to_string(),
+                }
+            })),
+            library: Some(request.library.clone()),
+            response: Some(snapshot.response),
+        }
+    }
+
+    /// Reply to an empty result; for a topic or a version, list the topics the library does have
+    ///
+    /// The topics come from the snippet titles of the same lookup without the
+    /// topic, or for a version without a topic, of the latest docs. Without
+    /// any, or when that lookup fails, the plain reply is used, plus a pointer
+    /// to `list_versions` for a version.
+    async fn no_documentation(client: &Client, request: &DocsRequest, cache: &DocsCache) -> DocsOutput {
+        let (missing, broad) = match (&request.topic, &request.version) {
+            (Some(topic), _) => (format!("topic \"{}\"", topic), DocsRequest { topic: None, page: None, ..request.clone() }),
+            (None, Some(version)) => (format!("version \"{}\"", version), DocsRequest { version: None, page: None, ..request.clone() }),
+            (None, None) => return Message::DocsNoDocumentation.text().into(),
+        };
+        let versions_hint = if request.topic.is_none() { "\n\nUse `list_versions: true` to see the versions that have docs." } else { "" };
+        let topics = match Self::fetch_cached(client, &broad, cache).await {
+            Ok(response) => available_topics(&response),
+            Err(e) => {
+                log_debug!("Topic listing for {} failed: {}", request.library, e);
+                Vec::new()
+            }
+        };
+        if topics.is_empty() {
+            return format!("{}{}", Message::DocsNoDocumentation.text(), versions_hint).into();
+        }
+
+        let scope = if request.topic.is_some() { "this library" } else { "the latest docs" };
+        DocsOutput {
+            text: format!(
+                "No documentation found for {}.\n\nTopics available for {}: {}{}",
+                missing,
+                scope,
+                topics.join(", "),
+                versions_hint
+            ),
+            structured: Some(json!({ "available_topics": topics })),
+            library: None,
+            response: None,
+        }
+    }
+
+    /// Offline mode: serve from the cache only, anything else is an offline error result
+    pub(super) fn query_offline(request: &DocsRequest, cache: &DocsCache, offline: &OfflineError) -> Result<DocsOutput, CallToolResult> {
+        let Some(response) = cache.get(&CacheKey::from_request(request)) else {
+            log_important!(info, "{} and it is not cached", offline);
+            return Err(offline_result(offline));
+        };
+
+        log_debug!("Docs served from cache in offline mode: {}", request.library);
+        Ok(DocsOutput {
+            text: Self::format_response(&response, request),
+            structured: None,
+            library: Some(request.library.clone()),
+            response: Some(response),
+        })
+    }
+
+    /// Docs from the cache or the API; with `max_pages`, the following pages merged in
+    ///
+    /// Pages are fetched one after another until there is no next page, the
+    /// page count is reached or the merged content exceeds the character
+    /// budget. A failing later page ends the merge with the pages so far.
+    /// When the client asked for progress, each page is also sent as a progress
+    /// notification as soon as it arrives.
+    async fn fetch_cached(client: &Client, request: &DocsRequest, cache: &DocsCache) -> Result<DocsResponse, DocsError> {
+        let mut response = Self::fetch_page(client, request, cache).await?;
+        let max_pages = request.max_pages.unwrap_or(1);
+        if max_pages <= 1 || response.is_empty() {
+            return Ok(response);
+        }
+        Self::report_page(&response, request, 1, max_pages).await;
+
+        let budget = client.config().page_budget();
+        let mut chars: usize = response.snippets.iter().map(|snippet| snippet.content.len()).sum();
+        let mut pages = 1;
+        while pages < max_pages && chars < budget {
+            let Some(next) = response.pagination.as_ref().filter(|p| p.has_next).map(|p| p.current_page.saturating_add(1)) else {
+                break;
+            };
+            let page_request = DocsRequest { page: Some(next), ..request.clone() };
+            let page = match Self::fetch_page(client, &page_request, cache).await {
+                Ok(page) if !page.is_empty() => page,
+                Ok(_) => break,
+                Err(e) => {
+                    log_important!(warn, "Docs page {} of {} failed, keeping {} pages: {}", next, request.library, pages, e);
+                    break;
+                }
+            };
+            Self::report_page(&page, &page_request, pages + 1, max_pages).await;
+            chars += page.snippets.iter().map(|snippet| snippet.content.len()).sum::<usize>();
+            response.snippets.extend(page.snippets);
+            response.pagination = page.pagination;
+            pages += 1;
+        }
+        log_debug!("Docs merged {} pages of {} ({} chars)", pages, request.library, chars);
+        response.merged_pages = pages;
+        Ok(response)
+    }
+
+    /// Send page `fetched` of at most `max_pages` as a partial result, in the requested format
+    async fn report_page(page: &DocsResponse, request: &DocsRequest, fetched: u32, max_pages: u32) {
+        if !progress::is_active() {
+            return;
+        }
+        let markdown = Self::format_response(page, request);
+        let partial = match format::current() {
+            OutputFormat::Markdown => markdown,
+            OutputFormat::Plain => format::to_plain(&markdown),
+            OutputFormat::Json => lookup_payload(&request.library, page, request).to_string(),
+        };
+        progress::report(fetched, Some(max_pages), partial).await;
+    }
+
+    /// One page from the cache, or from the API (non-empty responses are cached)
+    ///
+    /// A stale or bypassed entry with an ETag is revalidated: when the upstream
+    /// answers 304 it is served and stored again without downloading the docs.
+    /// Recording and replaying skip the cache, so every lookup reaches the transport.
+    async fn fetch_page(client: &Client, request: &DocsRequest, cache: &DocsCache) -> Result<DocsResponse, DocsError> {
+        if !client.transport().is_network() {
+            return client.docs(request).await;
+        }
+        let key = CacheKey::from_request(request);
+        if request.refresh == Some(true) {
+            log_debug!("Docs cache bypassed for refresh: {}", request.library);
+        } else if let Some(response) = cache.get(&key) {
+            log_debug!("Docs cache hit: {}", request.library);
+            return Ok(response);
+        }
+
+        let stale = cache.snapshot(&key).filter(|snapshot| snapshot.response.etag.is_some());
+        let response = match stale {
+            Some(stale) => match client.revalidate(request, &stale.response).await? {
+                Some(response) => response,
+                None => {
+                    log_debug!("Docs not modified upstream, serving the cached copy: {}", request.library);
+                    cache.put(&key, &stale.response);
+                    return Ok(stale.response);
+                }
+            },
+            None => client.docs(request).await?,
+        };
+        if !response.is_empty() {
+            cache.put(&key, &response);
+        }
+        Ok(response)
+    }
+
+    /// Render snippets to Markdown
+    pub fn format_response(response: &DocsResponse, request: &DocsRequest) -> String {
+        let Selection { shown, total, duplicates, omitted_by_budget, cut, omitted_by_size, cut_to_size } = select_snippets(response, request);
+        // Info pages often have no titles, so keep a visible break between them
+        let separator = if response.doc_type == DocType::Info { "\n\n---\n\n" } else { "\n\n" };
+
+        // Written into one buffer sized up front: merged multi-page responses
+        // run into megabytes and were copied several times while growing
+        let body_len: usize = shown
+            .iter()
+            .map(|(snippet, content)| content.len() + snippet.title.as_ref().map_or(0, |title| title.len() + 5) + separator.len())
+            .sum();
+        let mut output = String::with_capacity(body_len + request.library.len() * 2 + 512);
 
-        if let Some(api_key) = &config.api_key {
-            req_builder = req_builder.header(AUTHORIZATION, format!("Bearer {}", api_key));
+        Self::push_header(&mut output, request);
+        for (i, (snippet, content)) in shown.iter().enumerate() {
+            if i > 0 {
+                output.push_str(separator);
+            }
+            if let Some(title) = &snippet.title {
+                output.push_str("## ");
+                output.push_str(title);
+                output.push_str("\n\n");
+            }
+            output.push_str(content.trim_end());
+        }
+        let mut notes = Vec::new();
+        if cut {
+            notes.push(Message::DocsSnippetCut);
+        }
+        if cut_to_size {
+            notes.push(Message::DocsSnippetCutToSize { max_bytes: request.max_bytes.unwrap_or_default() });
+        }
+        if let Some(pagination) = &response.pagination {
+            if pagination.has_next {
+                notes.push(Message::DocsNextPage { current: pagination.current_page, total: pagination.total_pages });
+            }
+        }
+        if response.merged_pages > 1 {
+            let first = request.page.unwrap_or(1);
+            notes.push(Message::DocsMergedPages { first, last: first.saturating_add(response.merged_pages - 1) });
+        }
+        if omitted_by_budget > 0 {
+            notes.push(Message::DocsOmittedByBudget {
+                shown: shown.len(),
+                total,
+                max_tokens: request.max_tokens.unwrap_or_default(),
+                omitted: omitted_by_budget,
+            });
+        } else if shown.len() < total && omitted_by_size.is_empty() {
+            notes.push(Message::DocsShowing { shown: shown.len(), total });
+        }
+        if duplicates > 0 {
+            notes.push(Message::DocsDuplicatesRemoved { count: duplicates });
+        }
+        for note in notes {
+            output.push_str("\n\n");
+            output.push_str(&note.text());
+        }
+        if !omitted_by_size.is_empty() {
+            Self::push_omitted_index(&mut output, request, shown.len(), &omitted_by_size);
+        }
+        Self::push_footer(&mut output, request);
+
+        if response.auto_fallback {
+            output.push_str(&format!("\n{}\n", Message::DocsAutoFallback { min_snippets: AUTO_MIN_SNIPPETS }.text()));
+        }
+        if let Some(moved_to) = &response.moved_to {
+            output.push_str(&format!("\n{}\n", Message::DocsMoved { from: &request.library, to: moved_to }.text()));
         }
+        output
+    }
 
-        let response = req_builder.send().await?;
-        let status = response.status();
+    /// Title and request parameters above the snippets
+    fn push_header(output: &mut String, request: &DocsRequest) {
+        output.push_str(&format!("{}\n\n", Message::DocsTitle { library: &request.library }.text()));
 
-        if !status.is_success() {
-            return Err(anyhow::anyhow!("Search request failed: {}", status));
+        if let Some(topic) = &request.topic {
+            output.push_str(&format!("{}\n", Message::DocsTopic { topic }.text()));
+        }
+        if let Some(version) = &request.version {
+            output.push_str(&format!("{}\n", Message::DocsVersion { version }.text()));
+        }
+        if let Some(page) = request.page {
+            output.push_str(&format!("{}\n", Message::DocsPage { page }.text()));
         }
+        output.push_str("\n---\n\n");
+    }
 
-        let response_text = response.text().await?;
-        let search_response: SearchResponse = serde_json::from_str(&response_text)
-            .map_err(|e| anyhow::anyhow!("Failed to parse search response: {}", e))?;
+    /// Titles of the snippets left out for `max_bytes`, to ask for with `titles`
+    fn push_omitted_index(output: &mut String, request: &DocsRequest, shown: usize, omitted: &[&DocumentSnippet]) {
+        let max_bytes = request.max_bytes.unwrap_or_default();
+        output.push_str(&format!("\n\n{}\n", Message::DocsOmittedBySize { shown, max_bytes, omitted: omitted.len() }.text()));
+        for snippet in omitted.iter().take(OMITTED_INDEX_MAX) {
+            output.push_str(&format!("\n- {}", snippet_label(snippet.title.as_deref(), &snippet.content)));
+        }
+        if omitted.len() > OMITTED_INDEX_MAX {
+            output.push_str(&format!("\n- … ({})", Message::DocsMoreOmitted { count: omitted.len() - OMITTED_INDEX_MAX }.text()));
+        }
+    }
 
-        Ok(search_response.results.into_iter().take(5).collect())
+    /// Source line below the snippets
+    fn push_footer(output: &mut String, request: &DocsRequest) {
+        output.push_str(&format!("\n\n---\n{}\n", Message::DocsSource { library: &request.library }.text()));
+    }
+
+    /// Handle 404 error: search for candidate libraries, or fetch the top hit when auto-resolving
+    async fn handle_not_found_with_search(
+        client: &Client,
+        request: &DocsRequest,
+        cache: &DocsCache,
+        auto_resolve: bool,
+    ) -> DocsOutput {
+        let search_query = if request.library.contains('/') {
+            request.library.split('/').next_back().unwrap_or(&request.library)
+        } else {
+            &request.library
+        };
+
+        log_debug!("Search query: {}", search_query);
+
+        match client.search(search_query).await {
+            Ok(results) if !results.is_empty() => {
+                if auto_resolve {
+                    if let Some(output) = Self::auto_resolve(client, request, cache, &results[0]).await {
+                        return output;
+                    }
+                }
+                let results: Vec<SearchResult> = results.into_iter().take(5).collect();
+                Self::format_not_found_with_suggestions(&request.library, &results).into()
+            }
+            Ok(_) => Self::format_not_found_no_suggestions(&request.library).into(),
+            Err(e) => {
+                log_debug!("Search failed: {}", e);
+                Self::format_not_found_no_suggestions(&request.library).into()
+            }
+        }
+    }
+
+    /// Fetch the top search hit if it is confident enough; `None` falls back to suggestions
+    async fn auto_resolve(
+        client: &Client,
+        request: &DocsRequest,
+        cache: &DocsCache,
+        top: &SearchResult,
+    ) -> Option<DocsOutput> {
+        let confidence = match_confidence(&request.library, top);
+        let resolved = library_id(top);
+        if confidence < AUTO_RESOLVE_THRESHOLD || resolved == request.library.trim_matches('/') {
+            log_debug!("Auto-resolve skipped: {} (confidence {:.2})", resolved, confidence);
+            return None;
+        }
+
+        let resolved_request = DocsRequest { library: resolved.clone(), ..request.clone() };
+        let response = match Self::fetch_cached(client, &resolved_request, cache).await {
+            Ok(response) if !response.is_empty() => response,
+            Ok(_) => return None,
+            Err(e) => {
+                log_debug!("Auto-resolve fetch of {} failed: {}", resolved, e);
+                return None;
+            }
+        };
+        log_important!(info, "Auto-resolved '{}' to '{}' (confidence {:.2})", request.library, resolved, confidence);
+        cache.aliases().learn(&request.library, &resolved);
+        Some(Self::resolved_output(request, &resolved_request, response, confidence, "search"))
+    }
+
+    /// Docs of `resolved` shown for `request`, with a banner naming both
+    fn resolved_output(request: &DocsRequest, resolved: &DocsRequest, response: DocsResponse, confidence: f64, via: &str) -> DocsOutput {
+        let banner = format!("Showing results for {} (you asked for {})", resolved.library, request.library);
+        let text = format!("> **{}**\n\n{}", banner, Self::format_response(&response, resolved));
+        DocsOutput {
+            library: Some(resolved.library.clone()),
+            response: Some(response),
+            text,
+            structured: Some(json!({
+                "resolution": {
+                    "requested_library": request.library,
+                    "resolved_library": resolved.library,
+                    "confidence": confidence,
+                    "via": via,
+                    "banner": banner,
+                }
+            })),
+        }
+    }
+
+    /// `list_versions`: the documented versions of `library`, from its search entry
+    async fn list_versions(config: &DocsConfig, library: &str) -> CallToolResult {
+        let library = resolve::alias(library).unwrap_or(library.trim().trim_matches('/'));
+        let query = library.rsplit('/').next().unwrap_or(library);
+        let results = match Client::new(config.clone()) {
+            Ok(client) => client.search(query).await,
+            Err(e) => Err(e),
+        };
+        let (text, structured) = match results {
+            Ok(results) => match resolve::find_library(&results, library) {
+                Some(found) => {
+                    let id = library_id(found);
+                    log_important!(info, "Docs versions of {}: {}", id, found.versions.len());
+                    (Self::format_versions(&id, &found.versions), Some(json!({ "library": id, "versions": found.versions })))
+                }
+                None if results.is_empty() => (Self::format_not_found_no_suggestions(library), None),
+                None => (Self::format_not_found_with_suggestions(library, &results[..results.len().min(5)]), None),
+            },
+            Err(e) => {
+                log_important!(warn, "Docs version listing of {} failed: {}", library, e);
+                return CallToolResult {
+                    content: vec![Content::text(format!("Docs version listing failed: {}", e))],
+                    is_error: Some(true),
+                    meta: None,
+                    structured_content: None,
+                };
+            }
+        };
+        CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(structured.unwrap_or_else(|| json!({}))),
+        }
+    }
+
+    fn format_versions(library: &str, versions: &[String]) -> String {
+        if versions.is_empty() {
+            return format!(
+                "**{}** has no separately documented versions: only the latest docs are available, leave `version` unset.",
+                library
+            );
+        }
+        let list: Vec<String> = versions.iter().map(|version| format!("- `{}`", version)).collect();
+        format!(
+            "**Documented versions of {}**\n\n{}\n\nPass one as `version`, e.g. {{ \"library\": \"{}\", \"version\": \"{}\" }}; without it the latest docs are returned.",
+            library,
+            list.join("\n"),
+            library,
+            versions[0]
+        )
     }
 
     /// Format 404 error message (no suggestions)
-    fn format_not_found_no_suggestions(library: &str) -> String {
+    pub(crate) fn format_not_found_no_suggestions(library: &str) -> String {
         let website = docs_website_url();
         format!(
-            "**Library \"{}\" not found**\n\n\
-            Please check the library identifier. Format: `owner/repo`, e.g.:\n\
+            "{}\n\n{}\n\
             - `vercel/next.js`\n\
             - `facebook/react`\n\
-            - `spring-projects/spring-framework`\n\n\
-            Tip: Search for libraries at [Docs]({})",
-            library,
-            website
+            - `spring-projects/spring-framework`\n\n{}",
+            Message::DocsNotFound { library }.text(),
+            Message::DocsCheckIdentifier.text(),
+            Message::DocsSearchTip { website: &website }.text()
         )
     }
 
     /// Format 404 error message (with suggestions)
-    fn format_not_found_with_suggestions(library: &str, results: &[SearchResult]) -> String {
-        let mut output = format!(
-            "**Library \"{}\" not found**\n\n\
-            **Suggestions**: Related libraries found, use full identifier to query:\n\n",
-            library
-        );
+    pub(crate) fn format_not_found_with_suggestions(library: &str, results: &[SearchResult]) -> String {
+        let mut output = format!("{}\n\n{}\n\n", Message::DocsNotFound { library }.text(), Message::DocsSuggestions.text());
+        output.push_str(&Self::format_search_results(results));
 
+        output.push_str("---\n\n");
+        output.push_str(&format!("{}\n", Message::DocsUseFullIdentifier.text()));
+        output.push_str("```json\n");
+        if let Some(first) = results.first() {
+            let lib_id = first.id.trim_start_matches('/');
+            output.push_str(&format!(
+                "{{ \"library\": \"{}\", \"topic\": \"core\" }}\n",
+                lib_id
+            ));
+        }
+        output.push_str("```\n");
+
+        output
+    }
+
+    /// Numbered search results with stars, score and a shortened description
+    pub(crate) fn format_search_results(results: &[SearchResult]) -> String {
+        let mut output = String::new();
         for (idx, result) in results.iter().enumerate() {
             let lib_id = result.id.trim_start_matches('/');
 
@@ -306,37 +1603,1065 @@ impl DocsTool {
             ));
 
             if let Some(desc) = &result.description {
-                let short_desc = if desc.len() > 100 {
-                    format!("{}...", &desc[..100])
-                } else {
-                    desc.clone()
-                };
+                let short_desc = text::truncate_with(desc, 100, "...");
                 output.push_str(&format!("   {}\n", short_desc));
             }
             output.push('\n');
         }
-
-        output.push_str("---\n\n");
-        output.push_str("Use full library identifier, e.g.:\n");
-        output.push_str("```json\n");
-        if let Some(first) = results.first() {
-            let lib_id = first.id.trim_start_matches('/');
-            output.push_str(&format!(
-                "{{ \"library\": \"{}\", \"topic\": \"core\" }}\n",
-                lib_id
-            ));
-        }
-        output.push_str("```\n");
-
         output
     }
 
     /// Format stars count
-    fn format_stars(stars: u64) -> String {
-        if stars >= 1000 {
+    pub(crate) fn format_stars(stars: u64) -> String {
+        if stars >= 1_000_000 {
+            format!("{:.1}M", stars as f64 / 1_000_000.0)
+        } else if stars >= 1000 {
             format!("{:.1}K", stars as f64 / 1000.0)
         } else {
             stars.to_string()
         }
     }
 }
+
+/// Rough age for the snapshot banner: minutes, hours or days
+fn format_age(age: chrono::Duration) -> String {
+    let (value, unit) = match age.num_minutes() {
+        minutes if minutes < 60 => (minutes, "minute"),
+        minutes if minutes < 48 * 60 => (age.num_hours(), "hour"),
+        _ => (age.num_days(), "day"),
+    };
+    format!("{} {}{}", value, unit, if value == 1 { "" } else { "s" })
+}
+
+/// Snippets a lookup shows
+pub(super) struct Selection<'a> {
+    /// Snippets with the content to show, which is cut for a snippet over the budget
+    pub(super) shown: Vec<(&'a DocumentSnippet, &'a str)>,
+    /// Distinct non-empty snippets in the response
+    pub(super) total: usize,
+    /// Near-duplicates of other snippets, left out
+    duplicates: usize,
+    /// Snippets within `max_snippets` left out for `max_tokens`
+    omitted_by_budget: usize,
+    /// The only shown snippet was cut to fit `max_tokens`
+    cut: bool,
+    /// Snippets left out for `max_bytes`, in order
+    omitted_by_size: Vec<&'a DocumentSnippet>,
+    /// The only shown snippet was cut to fit `max_bytes`
+    cut_to_size: bool,
+}
+
+/// Distinct non-empty snippets in relevance order (by the topic with
+/// `rerank`), only those named in `titles` if given, limited by
+/// `max_snippets`, then by `max_tokens` and then by `max_bytes`
+///
+/// Snippets that fit the token budget are kept, in order, until the first one
+/// that does not; when even the first one is too long it is cut at a line
+/// break, so there is always something to read. The byte limit works the
+/// same way on what is left.
+pub(super) fn select_snippets<'a>(response: &'a DocsResponse, request: &DocsRequest) -> Selection<'a> {
+    let snippets: Vec<_> = response.snippets.iter().filter(|snippet| !snippet.content.trim().is_empty()).collect();
+    let (mut snippets, duplicates) = rank::dedup(snippets);
+    if let Some(titles) = &request.titles {
+        let wanted: Vec<String> = titles.iter().map(|title| title.trim().to_lowercase()).collect();
+        snippets.retain(|snippet| wanted.contains(&snippet_label(snippet.title.as_deref(), &snippet.content).to_lowercase()));
+    }
+    if let (Some(true), Some(topic)) = (request.rerank, &request.topic) {
+        rank::rerank(&mut snippets, topic);
+    }
+    let total = snippets.len();
+    // Snippets arrive in relevance order, so the limit keeps the best ones
+    let candidates = &snippets[..request.max_snippets.map_or(total, |max| total.min(max as usize))];
+    let (mut shown, omitted_by_budget, cut) = match request.max_tokens {
+        Some(max_tokens) => within_tokens(candidates, max_tokens),
+        None => (candidates.iter().map(|snippet| (*snippet, snippet.content.as_str())).collect(), 0, false),
+    };
+
+    let mut omitted_by_size = Vec::new();
+    let mut cut_to_size = false;
+    if let Some(max_bytes) = request.max_bytes.map(|max| max as usize) {
+        let mut used = 0;
+        let fits = shown
+            .iter()
+            .take_while(|(snippet, content)| {
+                used += content.trim_end().len() + snippet.title.as_ref().map_or(0, String::len);
+                used <= max_bytes
+            })
+            .count();
+        if fits < shown.len() {
+            omitted_by_size = shown.split_off(fits.max(1)).into_iter().map(|(snippet, _)| snippet).collect();
+            if fits == 0 {
+                let (snippet, content) = shown[0];
+                let available = max_bytes.saturating_sub(snippet.title.as_ref().map_or(0, String::len));
+                shown[0].1 = end_at_line(text::prefix_bytes(content, available));
+                cut_to_size = true;
+            }
+        }
+    }
+    Selection { shown, total, duplicates, omitted_by_budget, cut, omitted_by_size, cut_to_size }
+}
+
+/// Title of a snippet, or the start of its first line when it has none
+///
+/// The index of snippets left out for `max_bytes` lists these, and `titles`
+/// matches them.
+pub(super) fn snippet_label(title: Option<&str>, content: &str) -> String {
+    let label = title.map(str::trim).filter(|title| !title.is_empty()).unwrap_or_else(|| content.lines().find(|line| !line.trim().is_empty()).unwrap_or_default().trim());
+    text::truncate(label, LABEL_CHARS).into_owned()
+}
+
+/// Snippets of `candidates` within the `max_tokens` budget, how many were
+/// left out and whether the only one shown was cut
+fn within_tokens<'a>(candidates: &[&'a DocumentSnippet], max_tokens: u32) -> (Vec<(&'a DocumentSnippet, &'a str)>, usize, bool) {
+
+    let budget = max_tokens as usize * CHARS_PER_TOKEN;
+    let mut used = 0;
+    let mut shown = Vec::new();
+    let mut cut = false;
+    for snippet in candidates {
+        let chars = snippet.content.trim_end().chars().count() + snippet.title.as_ref().map_or(0, |title| title.chars().count());
+        if used + chars <= budget {
+            used += chars;
+            shown.push((*snippet, snippet.content.as_str()));
+            continue;
+        }
+        if shown.is_empty() {
+            let available = budget.saturating_sub(snippet.title.as_ref().map_or(0, |title| title.chars().count()));
+            shown.push((*snippet, cut_at_line(&snippet.content, available)));
+            cut = true;
+        }
+        break;
+    }
+    let omitted_by_budget = candidates.len() - shown.len();
+    (shown, omitted_by_budget, cut)
+}
+
+/// Longest prefix of `text` with at most `max_chars` characters, ending at a
+/// line break when there is one in its second half
+fn cut_at_line(text: &str, max_chars: usize) -> &str {
+    end_at_line(text::prefix(text, max_chars))
+}
+
+/// `prefix` up to its last line break when that is in its second half
+fn end_at_line(prefix: &str) -> &str {
+    let end = prefix.len();
+    match prefix.rfind('\n') {
+        Some(line) if line >= end / 2 => &prefix[..line],
+        _ => prefix,
+    }
+}
+
+/// Structured content of a lookup: the snippets shown, pagination and where they came from
+fn lookup_payload(library: &str, response: &DocsResponse, request: &DocsRequest) -> serde_json::Value {
+    let selection = select_snippets(response, request);
+    let source = DocsSource::Library.name();
+    json!({
+        "library": library,
+        "topic": request.topic,
+        "version": request.version,
+        "doc_type": response.doc_type,
+        "source": source,
+        "snippets": selection.shown.iter().map(|(snippet, content)| json!({
+            "source": source,
+            "library": library,
+            "title": snippet.title,
+            "content": content.trim_end(),
+            "score": snippet.score,
+        })).collect::<Vec<_>>(),
+        "total_snippets": selection.total,
+        "duplicates_removed": selection.duplicates,
+        "omitted_by_max_tokens": selection.omitted_by_budget,
+        "omitted_by_max_bytes": selection.omitted_by_size.iter().map(|snippet| snippet_label(snippet.title.as_deref(), &snippet.content)).collect::<Vec<_>>(),
+        "truncated": selection.cut || selection.cut_to_size,
+        "pagination": response.pagination,
+        "merged_pages": response.merged_pages.max(1),
+        "moved_to": response.moved_to,
+        "auto_fallback": response.auto_fallback,
+    })
+}
+
+/// Distinct section names in the snippet titles, in order of appearance
+///
+/// A title like `Routing > Dynamic routes` or `Routing: Dynamic routes`
+/// counts as its first part; case is ignored when deduplicating.
+pub(crate) fn available_topics(response: &DocsResponse) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    response
+        .snippets
+        .iter()
+        .filter_map(|snippet| snippet.title.as_deref())
+        .filter_map(|title| title.split([':', '>']).next().map(str::trim).filter(|topic| !topic.is_empty()))
+        .filter(|topic| seen.insert(topic.to_lowercase()))
+        .take(MAX_SUGGESTED_TOPICS)
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use crate::docs_client::cache::DEFAULT_TTL;
+    use crate::docs_client::mock::{MockResponse, MockServer};
+    use crate::docs_client::types::docs_api_base_url;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn response_with(count: usize) -> DocsResponse {
+        DocsResponse {
+            snippets: (1..=count)
+                .map(|i| DocumentSnippet {
+                    content: format!("content {}", i),
+                    title: Some(format!("Snippet {}", i)),
+                    score: None,
+                })
+                .collect(),
+            ..DocsResponse::default()
+        }
+    }
+
+    fn request_with(max_snippets: Option<u32>) -> DocsRequest {
+        DocsRequest { max_snippets, ..DocsRequest::new("vercel/next.js") }
+    }
+
+    #[test]
+    fn test_base_url_from_settings_and_env() {
+        let mut mcp_config = crate::config::default_mcp_config();
+        assert_eq!(DocsTool::client_config(&mcp_config, None).unwrap().base_url, docs_api_base_url());
+
+        mcp_config.docs_base_url = Some(" https://docs.mirror.example/api/v2/ ".to_string());
+        mcp_config.docs_timeout_secs = Some(5);
+        let config = DocsTool::client_config(&mcp_config, None).unwrap();
+        assert_eq!((config.base_url.as_str(), config.timeout_secs), ("https://docs.mirror.example/api/v2", Some(5)));
+
+        let env = Some("http://127.0.0.1:9/".to_string());
+        assert_eq!(DocsTool::client_config(&mcp_config, env).unwrap().base_url, "http://127.0.0.1:9");
+        assert_eq!(DocsTool::client_config(&mcp_config, Some(" ".to_string())).unwrap().base_url, "https://docs.mirror.example/api/v2");
+
+        mcp_config.docs_base_url = Some("docs.mirror.example".to_string());
+        assert!(DocsTool::client_config(&mcp_config, None).unwrap_err().to_string().contains("Invalid docs API URL"));
+    }
+
+    #[test]
+    fn test_max_snippets_keeps_the_first_ones() {
+        let output = DocsTool::format_response(&response_with(27), &request_with(Some(3)));
+        assert!(output.contains("## Snippet 3"));
+        assert!(!output.contains("## Snippet 4"));
+        assert!(output.contains("_Showing 3 of 27 snippets._"));
+    }
+
+    #[test]
+    fn test_max_snippets_above_total_has_no_footer() {
+        let output = DocsTool::format_response(&response_with(2), &request_with(Some(10)));
+        assert!(output.contains("## Snippet 2"));
+        assert!(!output.contains("_Showing"));
+
+        let output = DocsTool::format_response(&response_with(2), &request_with(None));
+        assert!(!output.contains("_Showing"));
+    }
+
+    #[test]
+    fn test_max_snippets_ignores_empty_snippets() {
+        let mut response = response_with(3);
+        response.snippets.insert(0, DocumentSnippet { content: "  ".to_string(), title: None, score: None });
+
+        let output = DocsTool::format_response(&response, &request_with(Some(2)));
+        assert!(output.contains("## Snippet 2"));
+        assert!(output.contains("_Showing 2 of 3 snippets._"));
+    }
+
+    #[test]
+    fn test_duplicates_are_removed_and_rerank_follows_the_topic() {
+        let mut response = response_with(4);
+        response.snippets[2].content = "content  1".to_string();
+        response.snippets[3].content = "Middleware runs before routes".to_string();
+
+        let output = DocsTool::format_response(&response, &request_with(None));
+        assert!(!output.contains("## Snippet 3"));
+        assert!(output.contains("_1 near-duplicate snippet removed._"), "{}", output);
+        assert_eq!(lookup_payload("vercel/next.js", &response, &request_with(None))["duplicates_removed"], json!(1));
+
+        let request = DocsRequest { topic: Some("middleware".to_string()), rerank: Some(true), ..request_with(Some(1)) };
+        let output = DocsTool::format_response(&response, &request);
+        assert!(output.contains("## Snippet 4"), "{}", output);
+        assert!(output.contains("_Showing 1 of 3 snippets._"));
+        // Without rerank the upstream order stays
+        let request = DocsRequest { rerank: None, ..request };
+        assert!(DocsTool::format_response(&response, &request).contains("## Snippet 1"));
+    }
+
+    #[test]
+    fn test_max_tokens_keeps_the_snippets_that_fit() {
+        let mut response = response_with(4);
+        response.snippets[1].content = "x".repeat(400);
+        // Snippet 1 (~25 chars) fits, snippet 2 (410 chars) does not and stops the selection
+        let request = DocsRequest { max_tokens: Some(100), ..request_with(None) };
+        let output = DocsTool::format_response(&response, &request);
+        assert!(output.contains("## Snippet 1"));
+        assert!(!output.contains("## Snippet 2"));
+        assert!(!output.contains("## Snippet 3"));
+        assert!(output.contains("_Showing 1 of 4 snippets to stay within max_tokens (100); 3 omitted"), "{}", output);
+
+        let payload = lookup_payload("vercel/next.js", &response, &request);
+        assert_eq!(payload["snippets"].as_array().unwrap().len(), 1);
+        assert_eq!((payload["omitted_by_max_tokens"].clone(), payload["truncated"].clone()), (json!(3), json!(false)));
+
+        // A large enough budget changes nothing
+        let request = DocsRequest { max_tokens: Some(1000), ..request_with(Some(3)) };
+        let output = DocsTool::format_response(&response, &request);
+        assert!(output.contains("## Snippet 3"));
+        assert!(output.contains("_Showing 3 of 4 snippets._"));
+    }
+
+    #[test]
+    fn test_max_tokens_cuts_an_oversized_first_snippet() {
+        let mut response = response_with(2);
+        response.snippets[0].content = (0..200).map(|i| format!("line {}\n", i)).collect();
+        let request = DocsRequest { max_tokens: Some(100), ..request_with(None) };
+        let output = DocsTool::format_response(&response, &request);
+        assert!(output.contains("line 0\n"));
+        assert!(!output.contains("line 199"));
+        assert!(output.contains("_[snippet cut to fit max_tokens]_"));
+        assert!(output.contains("1 omitted"), "{}", output);
+
+        let payload = lookup_payload("vercel/next.js", &response, &request);
+        let content = payload["snippets"][0]["content"].as_str().unwrap();
+        assert!(content.len() <= 400 && content.ends_with(|c: char| c.is_ascii_digit()), "{:?}", content);
+        assert_eq!(payload["truncated"], json!(true));
+        assert_eq!(cut_at_line("ééé", 2), "éé");
+    }
+
+    #[test]
+    fn test_max_tokens_bounds() {
+        for (max_tokens, valid) in [(100, true), (100_000, true), (99, false), (100_001, false)] {
+            let request = DocsRequest { max_tokens: Some(max_tokens), ..request_with(None) };
+            assert_eq!(DocsTool::validate_request(&request.into()).is_ok(), valid, "{}", max_tokens);
+        }
+    }
+
+    #[test]
+    fn test_max_bytes_lists_the_omitted_snippets() {
+        let mut response = response_with(5);
+        response.snippets[2].content = "x".repeat(2000);
+        response.snippets[4].title = None;
+        response.snippets[4].content = "npm install next\nmore".to_string();
+        // Snippets 1 and 2 fit, snippet 3 does not and everything from there is listed
+        let request = DocsRequest { max_bytes: Some(1000), ..request_with(None) };
+        let output = DocsTool::format_response(&response, &request);
+        assert!(output.contains("## Snippet 2"));
+        assert!(!output.contains("## Snippet 3") && !output.contains("xxx"));
+        assert!(output.contains("_Showing 2 snippets to stay within 1000 bytes; 3 omitted."), "{}", output);
+        assert!(output.contains("\n- Snippet 3\n- Snippet 4\n- npm install next"), "{}", output);
+        assert!(!output.contains("_Showing 2 of 5 snippets._"));
+
+        let payload = lookup_payload("vercel/next.js", &response, &request);
+        assert_eq!(payload["omitted_by_max_bytes"], json!(["Snippet 3", "Snippet 4", "npm install next"]));
+
+        // The follow-up asks for the listed titles from the same response
+        let follow_up = DocsRequest { titles: Some(vec!["snippet 4".to_string(), " npm install next ".to_string()]), ..request };
+        let output = DocsTool::format_response(&response, &follow_up);
+        assert!(output.contains("## Snippet 4") && output.contains("npm install next\nmore"), "{}", output);
+        assert!(!output.contains("## Snippet 1") && !output.contains("omitted"), "{}", output);
+    }
+
+    #[test]
+    fn test_max_bytes_cuts_an_oversized_first_snippet() {
+        let mut response = response_with(2);
+        response.snippets[0].content = (0..500).map(|i| format!("line {}\n", i)).collect();
+        let request = DocsRequest { max_bytes: Some(1000), ..request_with(None) };
+        let output = DocsTool::format_response(&response, &request);
+        assert!(output.contains("line 0\n") && !output.contains("line 499"));
+        assert!(output.contains("_[snippet cut to stay within 1000 bytes]_"));
+        assert!(output.contains("\n- Snippet 2"), "{}", output);
+
+        let payload = lookup_payload("vercel/next.js", &response, &request);
+        assert!(payload["snippets"][0]["content"].as_str().unwrap().len() <= 1000);
+        assert_eq!(payload["truncated"], json!(true));
+
+        for (max_bytes, valid) in [(1_000, true), (10_000_000, true), (999, false)] {
+            let request = DocsRequest { max_bytes: Some(max_bytes), ..request_with(None) };
+            assert_eq!(DocsTool::validate_request(&request.into()).is_ok(), valid, "{}", max_bytes);
+        }
+    }
+
+    /// `react/react` 404s; search returns `top` first; facebook/react has docs
+    async fn resolve_server(top: &'static str) -> MockServer {
+        MockServer::start(move |req| match req.path.as_str() {
+            "/docs/code/facebook/react" => MockResponse::text(200, "React docs"),
+            "/search" => MockResponse::json(
+                200,
+                &json!({ "results": [
+                    serde_json::from_str::<serde_json::Value>(top).unwrap(),
+                    { "id": "/preactjs/preact", "title": "Preact", "trustScore": 8.0 },
+                ]})
+                .to_string(),
+            ),
+            _ => MockResponse::text(404, "not found"),
+        })
+        .await
+    }
+
+    async fn query(server: &MockServer, auto_resolve: bool) -> DocsOutput {
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+        DocsTool::fetch_docs(&config, &DocsRequest::new("react/react"), &cache, auto_resolve)
+            .await
+            .unwrap()
+    }
+
+    const CONFIDENT_HIT: &str = r#"{"id":"/facebook/react","title":"React","trustScore":9.5}"#;
+
+    #[tokio::test]
+    async fn test_auto_resolve_above_threshold_fetches_top_hit() {
+        let server = resolve_server(CONFIDENT_HIT).await;
+        let output = query(&server, true).await;
+
+        assert!(output.text.starts_with("> **Showing results for facebook/react (you asked for react/react)**"));
+        assert!(output.text.contains("React docs"));
+        let resolution = &output.structured.unwrap()["resolution"];
+        assert_eq!(resolution["requested_library"], "react/react");
+        assert_eq!(resolution["resolved_library"], "facebook/react");
+        assert!(resolution["confidence"].as_f64().unwrap() >= AUTO_RESOLVE_THRESHOLD);
+        assert_eq!(resolution["banner"], "Showing results for facebook/react (you asked for react/react)");
+        assert_eq!(resolution["via"], "search");
+    }
+
+    #[tokio::test]
+    async fn test_auto_resolve_below_threshold_lists_suggestions() {
+        let server = resolve_server(r#"{"id":"/someone/react-fork","title":"Fork","trustScore":1.0}"#).await;
+        let output = query(&server, true).await;
+
+        assert!(output.text.contains("not found"));
+        assert!(output.text.contains("someone/react-fork"));
+        assert!(output.structured.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bare_names_are_resolved_before_fetching() {
+        let server = resolve_server(CONFIDENT_HIT).await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+
+        // Alias: fetched directly, without a search
+        let output = DocsTool::fetch_docs(&config, &DocsRequest::new("React"), &cache, false).await.unwrap();
+        assert!(output.text.starts_with("> **Showing results for facebook/react (you asked for React)**"), "{}", output.text);
+        assert_eq!(output.library.as_deref(), Some("facebook/react"));
+        assert_eq!(output.structured.unwrap()["resolution"]["via"], "alias");
+        assert_eq!(server.request_count(), 1);
+
+        // Not in the table: searched for instead of a request that would 404
+        let output = DocsTool::fetch_docs(&config, &DocsRequest::new("react dom"), &cache, true).await.unwrap();
+        assert!(output.text.contains("not found"), "{}", output.text);
+        assert!(output.text.contains("facebook/react"));
+        assert_eq!(server.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_list_versions_from_the_search_entry() {
+        let server = MockServer::start(|req| {
+            assert_eq!(req.path.split('?').next(), Some("/search"));
+            MockResponse::json(200, include_str!("../../../docs_client/fixtures/search_mixed.json"))
+        })
+        .await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+
+        let result_text = |result: &CallToolResult| result.content[0].as_text().unwrap().text.clone();
+        let result = DocsTool::list_versions(&config, "react").await;
+        let text = result_text(&result);
+        assert!(text.contains("**Documented versions of facebook/react**\n\n- `v18.3.1`\n- `v19.0.0`"), "{}", text);
+        assert_eq!(result.structured_content.unwrap()["versions"], json!(["v18.3.1", "v19.0.0"]));
+
+        let result = DocsTool::list_versions(&config, "/preactjs/preact").await;
+        assert!(result_text(&result).contains("no separately documented versions"));
+        assert_eq!(result.structured_content.unwrap()["versions"], json!([]));
+
+        let result = DocsTool::list_versions(&config, "someone/else").await;
+        assert_eq!(result.is_error, Some(false));
+        assert!(result_text(&result).contains("**Library \"someone/else\" not found**"));
+
+        let request = DocsRequest { list_versions: Some(true), libraries: Some(vec!["vuejs/core".to_string()]), ..DocsRequest::new("facebook/react") };
+        assert!(DocsTool::validate_request(&request.into()).unwrap_err().message.contains("cannot be combined with list_versions"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_resolve_is_opt_in() {
+        let server = resolve_server(CONFIDENT_HIT).await;
+        let output = query(&server, false).await;
+
+        assert!(output.text.contains("not found"));
+        assert!(!output.text.contains("React docs"));
+        assert!(output.structured.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_resolutions_are_learned() {
+        let server = resolve_server(CONFIDENT_HIT).await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+
+        // 404, search, fetch of the hit
+        DocsTool::fetch_docs(&config, &DocsRequest::new("react/react"), &cache, true).await.unwrap();
+        assert_eq!(server.request_count(), 3);
+        assert_eq!(cache.aliases().get("react/react").as_deref(), Some("facebook/react"));
+
+        // Learned: straight to the library, even for an uncached topic
+        let request = DocsRequest { topic: Some("hooks".to_string()), ..DocsRequest::new("/React/react") };
+        let output = DocsTool::fetch_docs(&config, &request, &cache, true).await.unwrap();
+        assert_eq!(server.request_count(), 4);
+        assert_eq!(output.library.as_deref(), Some("facebook/react"));
+        assert_eq!(output.structured.unwrap()["resolution"]["via"], "learned");
+
+        // A learned library that no longer exists is forgotten and resolved again
+        cache.aliases().learn("react/react", "gone/react");
+        let output = DocsTool::fetch_docs(&config, &DocsRequest::new("react/react"), &cache, true).await.unwrap();
+        assert_eq!(output.structured.unwrap()["resolution"]["via"], "search");
+        assert_eq!(cache.aliases().get("react/react").as_deref(), Some("facebook/react"));
+
+        assert_eq!(cache.clear(Some("facebook/react")).forgotten_aliases, 1);
+        assert!(cache.aliases().is_empty());
+    }
+
+    #[test]
+    fn test_offline_serves_cached_docs_only() {
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+        let offline = OfflineMode::resolve_with(Some(true), None, None).check("docs lookup of a/b").unwrap_err();
+
+        let miss = DocsTool::query_offline(&DocsRequest::new("a/b"), &cache, &offline).err().unwrap();
+        assert_eq!(miss.is_error, Some(true));
+        let error = &miss.structured_content.unwrap()["error"];
+        assert_eq!(error["kind"], "offline");
+        assert_eq!(error["source"], "parameter");
+
+        cache.put(&CacheKey::from_request(&DocsRequest::new("a/b")), &response_with(2));
+        let hit = DocsTool::query_offline(&DocsRequest::new("a/b"), &cache, &offline).ok().unwrap();
+        assert_eq!(hit.library.as_deref(), Some("a/b"));
+        assert!(hit.structured.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_offline_ok_serves_an_expired_snapshot_when_the_upstream_fails() {
+        let server = MockServer::start(|_| MockResponse::text(503, "maintenance")).await;
+        let config = DocsConfig {
+            base_url: server.base_url(),
+            retry: crate::docs_client::RetryTuning { max_retries: 0, ..Default::default() },
+            ..Default::default()
+        };
+        let cache = DocsCache::new(None, std::time::Duration::ZERO);
+        cache.put(&CacheKey::from_request(&DocsRequest::new("a/b")), &response_with(2));
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        // Not asked for: the failure is reported
+        let err = DocsTool::fetch_docs(&config, &DocsRequest::new("a/b"), &cache, false).await.err().unwrap();
+        assert!(format!("{:#}", err).contains("503"), "{:#}", err);
+
+        let request = DocsRequest { offline_ok: Some(true), ..DocsRequest::new("a/b") };
+        let output = DocsTool::fetch_docs(&config, &request, &cache, false).await.unwrap();
+        assert!(output.text.starts_with("> **Offline snapshot** from "), "{}", output.text);
+        assert!(output.text.contains("0 minutes old, past the cache lifetime"), "{}", output.text);
+        assert!(output.text.contains("## Snippet 2"));
+        let snapshot = &output.structured.as_ref().unwrap()["snapshot"];
+        assert_eq!(snapshot["expired"], json!(true));
+        assert!(snapshot["reason"].as_str().unwrap().contains("maintenance"));
+
+        // Nothing stored for this lookup: still an error
+        let request = DocsRequest { offline_ok: Some(true), ..DocsRequest::new("c/d") };
+        assert!(DocsTool::fetch_docs(&config, &request, &cache, false).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_expired_entry_with_an_etag_is_revalidated() {
+        let revalidated = Arc::new(AtomicUsize::new(0));
+        let seen = revalidated.clone();
+        let server = MockServer::start(move |req| {
+            if req.header("if-none-match").as_deref() == Some("\"abc\"") {
+                seen.fetch_add(1, Ordering::SeqCst);
+                return MockResponse::text(304, "");
+            }
+            MockResponse::text(200, "## One\n\nfirst\n\n## Two\n\nsecond\n\n## Three\n\nthird").with_header("ETag", "\"abc\"")
+        })
+        .await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let cache = DocsCache::new(None, std::time::Duration::ZERO);
+        let request = DocsRequest::new("a/b");
+
+        let first = DocsTool::fetch_docs(&config, &request, &cache, false).await.unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let second = DocsTool::fetch_docs(&config, &request, &cache, false).await.unwrap();
+        assert_eq!(second.text, first.text);
+        assert_eq!(revalidated.load(Ordering::SeqCst), 1);
+        assert_eq!(server.request_count(), 2);
+
+        // Refreshing asks too instead of downloading unchanged docs
+        let request = DocsRequest { refresh: Some(true), ..request };
+        DocsTool::fetch_docs(&config, &request, &cache, false).await.unwrap();
+        assert_eq!(revalidated.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_snapshot_age_wording() {
+        assert_eq!(format_age(chrono::Duration::minutes(1)), "1 minute");
+        assert_eq!(format_age(chrono::Duration::hours(5)), "5 hours");
+        assert_eq!(format_age(chrono::Duration::days(3)), "3 days");
+    }
+
+    #[test]
+    fn test_save_to_writes_front_matter_and_reports_the_path() {
+        let project = TestDir::new("docs-save-to");
+        let request = DocsToolRequest {
+            project_root_path: Some(project.to_string_lossy().into_owned()),
+            save_to: Some("docs/nextjs-routing.md".to_string()),
+            ..DocsRequest { topic: Some("routing".to_string()), ..request_with(Some(2)) }.into()
+        };
+
+        let target = DocsTool::save_target(&request).unwrap().unwrap();
+        let text = DocsTool::format_response(&response_with(3), &request.query);
+        let output = DocsOutput { text: text.clone(), structured: None, library: Some(request.query.library.clone()), response: None };
+        let result = DocsTool::success(output, &request, Some(&target), Some("_quota_".to_string())).unwrap();
+
+        let saved = std::fs::read_to_string(&target).unwrap();
+        assert!(saved.starts_with("---\nlibrary: \"vercel/next.js\"\ntopic: \"routing\"\nfetched_at: "), "{}", saved);
+        assert!(saved.ends_with(&format!("---\n\n{}\n", text)), "{}", saved);
+        assert!(!saved.contains("_quota_"));
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["saved"]["path"], target.to_string_lossy().as_ref());
+        assert_eq!(structured["saved"]["bytes"], saved.len());
+
+        // A second save needs overwrite, and is refused before any lookup
+        let err = match DocsTool::save_target(&request) {
+            Err(ToolError::Rpc(err)) => err,
+            _ => panic!("expected an invalid params error"),
+        };
+        assert_eq!(err.data.unwrap()["exists"], true);
+        assert!(DocsTool::save_target(&DocsToolRequest { overwrite: Some(true), ..request.clone() }).is_ok());
+        assert!(DocsTool::save_target(&DocsToolRequest { project_root_path: None, ..request }).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_structured_content() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(
+                200,
+                &json!({
+                    "snippets": [
+                        { "codeTitle": "Routing", "content": "app/ directory\n", "score": 0.9 },
+                        { "content": "  " },
+                        { "codeTitle": "Layouts", "content": "layout.tsx" },
+                    ],
+                    "pagination": { "currentPage": 1, "totalPages": 3, "hasNext": true }
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let request = DocsRequest { topic: Some("routing".to_string()), max_snippets: Some(1), ..DocsRequest::new("vercel/next.js") };
+        let output = DocsTool::fetch_docs(&config, &request, &DocsCache::new(None, DEFAULT_TTL), false).await.unwrap();
+        let request = DocsToolRequest::from(request);
+        let result = DocsTool::success(output, &request, None, None).unwrap();
+
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["library"], "vercel/next.js");
+        assert_eq!(structured["topic"], "routing");
+        assert_eq!(structured["source"], "library");
+        assert_eq!(structured["doc_type"], "code");
+        assert_eq!(structured["total_snippets"], 2);
+        assert_eq!(
+            structured["snippets"],
+            json!([{ "source": "library", "library": "vercel/next.js", "title": "Routing", "content": "app/ directory", "score": 0.9 }])
+        );
+        assert_eq!(structured["pagination"], json!({ "current_page": 1, "total_pages": 3, "has_next": true }));
+
+        // Results without docs are still objects
+        let not_found = DocsTool::success(Message::DocsNoDocumentation.text().into(), &request, None, None).unwrap();
+        assert_eq!(not_found.structured_content, Some(json!({})));
+        let schema = DocsTool::get_tool_definition().output_schema.unwrap();
+        assert_eq!(schema["type"], "object");
+        assert!(schema.get("required").is_none());
+    }
+
+    /// Four pages of 20-character snippets
+    async fn paged_server() -> MockServer {
+        MockServer::start(|req| {
+            let page: u32 = req.query("page").and_then(|p| p.parse().ok()).unwrap_or(1);
+            MockResponse::json(
+                200,
+                &json!({
+                    "snippets": [{ "codeTitle": format!("Page {}", page), "content": format!("content of page {:>4}", page) }],
+                    "pagination": { "currentPage": page, "totalPages": 4, "hasNext": page < 4 }
+                })
+                .to_string(),
+            )
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_max_pages_merges_following_pages() {
+        let server = paged_server().await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+
+        let request = DocsRequest { page: Some(2), max_pages: Some(5), ..DocsRequest::new("vercel/next.js") };
+        let output = DocsTool::fetch_docs(&config, &request, &cache, false).await.unwrap();
+        assert_eq!(server.request_count(), 3);
+        assert!(output.text.contains("## Page 2") && output.text.contains("## Page 4"), "{}", output.text);
+        assert!(output.text.contains("_Merged pages 2-4._"), "{}", output.text);
+        assert!(!output.text.contains("request page"), "{}", output.text);
+        let structured = DocsTool::success(output, &request.clone().into(), None, None).unwrap().structured_content.unwrap();
+        assert_eq!(structured["merged_pages"], 3);
+        assert_eq!(structured["total_snippets"], 3);
+
+        // Pages are cached one by one
+        DocsTool::fetch_docs(&config, &DocsRequest { page: Some(3), ..DocsRequest::new("vercel/next.js") }, &cache, false).await.unwrap();
+        assert_eq!(server.request_count(), 3);
+
+        // The character budget stops the merge after the page that reaches it
+        let config = DocsConfig { page_budget_chars: Some(30), ..config };
+        let request = DocsRequest { max_pages: Some(10), ..DocsRequest::new("vercel/next.js") };
+        let output = DocsTool::fetch_docs(&config, &request, &DocsCache::new(None, DEFAULT_TTL), false).await.unwrap();
+        assert!(output.text.contains("_Merged pages 1-2._"), "{}", output.text);
+        assert!(output.text.contains("_Page 2/4, request page 3 for more._"), "{}", output.text);
+    }
+
+    #[tokio::test]
+    async fn test_max_pages_sends_each_page_as_progress() {
+        let server = paged_server().await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let request = DocsRequest { max_pages: Some(3), ..DocsRequest::new("vercel/next.js") };
+
+        let (reporter, sent) = progress::Reporter::collecting("docs-1");
+        let output = progress::scope(Some(reporter), DocsTool::fetch_docs(&config, &request, &DocsCache::new(None, DEFAULT_TTL), false))
+            .await
+            .unwrap();
+        assert!(output.text.contains("_Merged pages 1-3._"));
+
+        let sent = sent.lock().unwrap();
+        let pages: Vec<_> = sent.iter().map(|n| (n.progress, n.total)).collect();
+        assert_eq!(pages, [(1.0, Some(3.0)), (2.0, Some(3.0)), (3.0, Some(3.0))]);
+        let second = sent[1].message.as_deref().unwrap();
+        assert!(second.contains("**Page**: 2") && second.contains("## Page 2") && !second.contains("## Page 1"), "{}", second);
+    }
+
+    #[tokio::test]
+    async fn test_several_libraries_are_answered_in_sections() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/docs/code/vercel/next.js" => MockResponse::text(200, "## Routing\n\nUse the app directory."),
+            "/docs/code/remix-run/remix" => MockResponse::text(200, "## Routes\n\nUse file-based routes."),
+            _ => MockResponse::text(500, "maintenance"),
+        })
+        .await;
+        let config = DocsConfig {
+            base_url: server.base_url(),
+            retry: crate::docs_client::RetryTuning { max_retries: 0, ..Default::default() },
+            ..Default::default()
+        };
+        let request = DocsRequest {
+            libraries: Some(vec!["remix-run/remix".to_string(), " vercel/next.js ".to_string(), "broken/lib".to_string()]),
+            ..DocsRequest::new("vercel/next.js")
+        };
+        let libraries = DocsTool::requested_libraries(&request);
+        assert_eq!(libraries, ["vercel/next.js", "remix-run/remix", "broken/lib"]);
+
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+        let (batch, requests) = DocsTool::batch_requests(&request).unwrap();
+        let result = DocsTool::query_batch(&config, batch, requests, &cache, false, None).await;
+        assert_eq!(result.is_error, Some(false));
+        let text = result.content[0].as_text().unwrap().text.clone();
+        let next = text.find("# vercel/next.js Documentation").unwrap();
+        let remix = text.find("# remix-run/remix Documentation").unwrap();
+        let broken = text.find("# broken/lib Documentation\n\nDocs query failed").unwrap();
+        assert!(next < remix && remix < broken, "{}", text);
+
+        let entries = result.structured_content.unwrap()["libraries"].as_array().cloned().unwrap();
+        let status: Vec<_> = entries.iter().map(|e| (e["requested"].as_str().unwrap(), e["status"].as_str().unwrap())).collect();
+        assert_eq!(status, [("vercel/next.js", "ok"), ("remix-run/remix", "ok"), ("broken/lib", "error")]);
+        assert!(entries[1]["snippets"][0]["content"].as_str().unwrap().contains("file-based routes"));
+
+        // Every library failing makes the result an error
+        let request = DocsRequest { libraries: Some(vec!["broken/two".to_string()]), ..DocsRequest::new("broken/lib") };
+        let (batch, requests) = DocsTool::batch_requests(&request).unwrap();
+        let result = DocsTool::query_batch(&config, batch, requests, &cache, false, None).await;
+        assert_eq!(result.is_error, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_several_topics_are_answered_in_sections() {
+        let server = MockServer::start(|req| {
+            assert_eq!(req.path, "/docs/code/vercel/next.js");
+            match req.query("topic").as_deref() {
+                Some("routing") => MockResponse::text(200, "## Routing\n\nUse the app directory."),
+                Some("auth") => MockResponse::text(200, "## Auth\n\nUse middleware."),
+                _ => MockResponse::text(500, "maintenance"),
+            }
+        })
+        .await;
+        let config = DocsConfig {
+            base_url: server.base_url(),
+            retry: crate::docs_client::RetryTuning { max_retries: 0, ..Default::default() },
+            ..Default::default()
+        };
+        let request = DocsRequest {
+            topic: Some("routing".to_string()),
+            topics: Some(vec![" auth ".to_string(), "routing".to_string(), "config".to_string()]),
+            ..DocsRequest::new("vercel/next.js")
+        };
+        let (batch, requests) = DocsTool::batch_requests(&request).unwrap();
+        assert_eq!(batch, Batch::Topics);
+        let topics: Vec<_> = requests.iter().map(|request| request.topic.as_deref().unwrap()).collect();
+        assert_eq!(topics, ["routing", "auth", "config"]);
+
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+        let result = DocsTool::query_batch(&config, batch, requests, &cache, false, None).await;
+        assert_eq!(result.is_error, Some(false));
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert!(text.starts_with("Documentation of vercel/next.js for 3 topics: routing, auth, config"), "{}", text);
+        let routing = text.find("Use the app directory.").unwrap();
+        let auth = text.find("Use middleware.").unwrap();
+        let config = text.find("**Topic**: config\n\nDocs query failed").unwrap();
+        assert!(routing < auth && auth < config, "{}", text);
+
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["library"], "vercel/next.js");
+        let status: Vec<_> = structured["topics"].as_array().unwrap().iter().map(|e| (e["topic"].as_str().unwrap(), e["status"].as_str().unwrap())).collect();
+        assert_eq!(status, [("routing", "ok"), ("auth", "ok"), ("config", "error")]);
+
+        // One topic in either field is a single lookup
+        assert!(DocsTool::batch_requests(&DocsRequest { topics: Some(vec!["auth".to_string()]), ..DocsRequest::new("a/b") }).is_none());
+    }
+
+    #[test]
+    fn test_several_libraries_validation() {
+        let message = |request: DocsToolRequest| DocsTool::validate_request(&request).unwrap_err().message.to_string();
+        let many = |count: usize| Some((0..count).map(|i| format!("owner/lib{}", i)).collect::<Vec<_>>());
+        let save_to = |request: DocsRequest| DocsToolRequest { save_to: Some("docs.md".to_string()), ..request.into() };
+
+        assert!(DocsTool::validate_request(&DocsRequest { libraries: many(5), ..DocsRequest::new("") }.into()).is_ok());
+        assert!(message(DocsRequest { libraries: many(5), ..DocsRequest::new("owner/extra") }.into()).contains("at most 5 libraries"));
+        assert!(message(DocsRequest { libraries: Some(vec![" ".to_string()]), ..DocsRequest::new("a/b") }.into()).contains("empty"));
+        assert!(message(DocsRequest { libraries: Some(Vec::new()), ..DocsRequest::new("") }.into()).contains("library is required"));
+        let save = save_to(DocsRequest { libraries: many(1), ..DocsRequest::new("a/b") });
+        assert!(message(save).contains("save_to cannot be combined"));
+        // The same library twice is a single lookup
+        let same = save_to(DocsRequest { libraries: Some(vec!["a/b".to_string()]), ..DocsRequest::new("a/b") });
+        assert!(DocsTool::validate_request(&same).is_ok());
+
+        let topics = |topics: &[&str]| Some(topics.iter().map(|t| t.to_string()).collect::<Vec<_>>());
+        assert!(DocsTool::validate_request(&DocsRequest { topics: topics(&["a", "b", "c", "d", "e"]), ..DocsRequest::new("a/b") }.into()).is_ok());
+        let six = DocsRequest { topic: Some("f".to_string()), topics: topics(&["a", "b", "c", "d", "e"]), ..DocsRequest::new("a/b") };
+        assert!(message(six.into()).contains("at most 5 topics"));
+        assert!(message(DocsRequest { topics: topics(&["a", ""]), ..DocsRequest::new("a/b") }.into()).contains("empty topics"));
+        assert!(message(DocsRequest { topics: topics(&["a", "b"]), libraries: many(1), ..DocsRequest::new("a/b") }.into()).contains("docs_compare"));
+        let save = save_to(DocsRequest { topics: topics(&["a", "b"]), ..DocsRequest::new("a/b") });
+        assert!(message(save).contains("save_to cannot be combined with several topics"));
+    }
+
+    #[test]
+    fn test_max_pages_bounds() {
+        for max_pages in [0, 11] {
+            let request = DocsRequest { max_pages: Some(max_pages), ..DocsRequest::new("vercel/next.js") };
+            assert!(DocsTool::validate_request(&request.into()).unwrap_err().message.contains("max_pages"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_to_dry_run_writes_nothing() {
+        let project = TestDir::new("docs-dry-run");
+        let request = DocsToolRequest {
+            project_root_path: Some(project.to_string_lossy().into_owned()),
+            save_to: Some("docs/next.md".to_string()),
+            ..request_with(Some(2)).into()
+        };
+        let target = DocsTool::save_target(&request).unwrap().unwrap();
+        let text = DocsTool::format_response(&response_with(3), &request.query);
+        let output = || DocsOutput { text: text.clone(), structured: None, library: Some(request.query.library.clone()), response: None };
+
+        let result = dry_run::run(async { DocsTool::success(output(), &request, Some(&target), None) }).await.ok().unwrap();
+        assert!(!project.join("docs").exists());
+        let structured = result.structured_content.as_ref().unwrap();
+        assert_eq!(structured["dry_run"]["changes"][0]["action"], "save");
+        assert!(format!("{:?}", result.content).contains("Would save to"));
+
+        // The planned size is what a real save writes
+        let planned = dry_run::planned_files(&result)[target.to_string_lossy().as_ref()];
+        DocsTool::success(output(), &request, Some(&target), None).unwrap();
+        assert_eq!(std::fs::metadata(&target).unwrap().len() as usize, planned);
+    }
+
+    /// Empty for any topic; without a topic, snippets titled `titles` (401 if there are none)
+    async fn topics_server(titles: Vec<String>) -> MockServer {
+        MockServer::start(move |req| match req.query("topic") {
+            Some(_) => MockResponse::json(200, r#"{"snippets": []}"#),
+            None if titles.is_empty() => MockResponse::text(401, "invalid key"),
+            None => {
+                let snippets: Vec<_> = titles.iter().map(|title| json!({ "title": title, "content": "text" })).collect();
+                MockResponse::json(200, &json!({ "snippets": snippets }).to_string())
+            }
+        })
+        .await
+    }
+
+    async fn lookup(server: &MockServer, topic: Option<&str>) -> DocsOutput {
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let request = DocsRequest { topic: topic.map(str::to_string), ..DocsRequest::new("vercel/next.js") };
+        DocsTool::fetch_docs(&config, &request, &DocsCache::new(None, DEFAULT_TTL), false).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_empty_topic_lists_available_topics() {
+        let mut titles = vec!["Routing > Dynamic routes".to_string(), "routing: layouts".to_string(), "Middleware".to_string()];
+        titles.extend((1..=20).map(|i| format!("Topic {}", i)));
+        let server = topics_server(titles).await;
+
+        let output = lookup(&server, Some("deploy")).await;
+        assert!(output.text.starts_with("No documentation found for topic \"deploy\"."), "{}", output.text);
+        assert!(output.text.contains("Topics available for this library: Routing, Middleware, Topic 1, "), "{}", output.text);
+        assert!(output.text.ends_with("Topic 13"), "{}", output.text);
+        let topics = output.structured.unwrap()["available_topics"].as_array().unwrap().len();
+        assert_eq!(topics, MAX_SUGGESTED_TOPICS);
+        assert_eq!(server.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_empty_version_lists_topics_of_the_latest_docs() {
+        let server = MockServer::start(|req| match req.query("version") {
+            Some(_) => MockResponse::json(200, r#"{"snippets": []}"#),
+            None => MockResponse::json(200, r#"{"snippets": [{"title": "Routing: basics", "content": "text"}]}"#),
+        })
+        .await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let request = DocsRequest { version: Some("v1.0.0".to_string()), ..DocsRequest::new("vercel/next.js") };
+        let output = DocsTool::fetch_docs(&config, &request, &DocsCache::new(None, DEFAULT_TTL), false).await.unwrap();
+
+        assert!(output.text.starts_with("No documentation found for version \"v1.0.0\"."), "{}", output.text);
+        assert!(output.text.contains("Topics available for the latest docs: Routing"), "{}", output.text);
+        assert!(output.text.ends_with("Use `list_versions: true` to see the versions that have docs."));
+        assert_eq!(output.structured.unwrap()["available_topics"], json!(["Routing"]));
+        assert_eq!(server.request_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_skips_the_cache_and_stores_the_result() {
+        let server = MockServer::start(|_| MockResponse::text(200, "React docs")).await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+        let cached = DocsRequest::new("facebook/react");
+        let refresh = DocsRequest { refresh: Some(true), ..cached.clone() };
+
+        DocsTool::fetch_docs(&config, &cached, &cache, false).await.unwrap();
+        DocsTool::fetch_docs(&config, &cached, &cache, false).await.unwrap();
+        assert_eq!(server.request_count(), 1);
+        DocsTool::fetch_docs(&config, &refresh, &cache, false).await.unwrap();
+        assert_eq!(server.request_count(), 2);
+        // The refreshed copy is served afterwards
+        assert!(DocsTool::fetch_docs(&config, &cached, &cache, false).await.unwrap().text.contains("React docs"));
+        assert_eq!(server.request_count(), 2);
+        assert_eq!(cache.stats().memory_entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_empty_without_topic_keeps_plain_reply() {
+        let server = MockServer::start(|_| MockResponse::json(200, r#"{"snippets": []}"#)).await;
+        let output = lookup(&server, None).await;
+        assert_eq!(output.text, Message::DocsNoDocumentation.text());
+        assert_eq!(server.request_count(), 1);
+
+        // The lookup without the topic is empty as well
+        let output = lookup(&server, Some("deploy")).await;
+        assert_eq!(output.text, Message::DocsNoDocumentation.text());
+        assert!(output.structured.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_topic_listing_keeps_plain_reply() {
+        let server = topics_server(Vec::new()).await;
+        let output = lookup(&server, Some("deploy")).await;
+        assert_eq!(output.text, Message::DocsNoDocumentation.text());
+        assert!(output.structured.is_none());
+        assert_eq!(server.request_count(), 2);
+    }
+
+    /// A project whose docs/auth.md covers authentication
+    fn auth_project() -> TestDir {
+        let root = TestDir::new("docs-sources");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(root.join("docs/auth.md"), "# Authentication\n\nOur apps sign in through the company SSO proxy.\n").unwrap();
+        root
+    }
+
+    fn sources_request(root: &Path) -> DocsToolRequest {
+        DocsToolRequest {
+            project_root_path: Some(root.to_string_lossy().into_owned()),
+            sources: Some(vec![DocsSource::Library, DocsSource::Project, DocsSource::Project]),
+            ..DocsRequest { topic: Some("authentication".to_string()), ..DocsRequest::new("vercel/next.js") }.into()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sources_merge_upstream_and_project_docs() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/docs/code/vercel/next.js" => MockResponse::text(200, "Use middleware to protect routes."),
+            _ => MockResponse::text(404, "not found"),
+        })
+        .await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let root = auth_project();
+        let request = sources_request(&root);
+
+        let sources = DocsTool::selected_sources(&request).unwrap();
+        assert_eq!(sources, vec![DocsSource::Library, DocsSource::Project]);
+        let output = DocsTool::query_sources(&config, &request, &sources, &DocsCache::new(None, DEFAULT_TTL), false, None).await.ok().unwrap();
+
+        let project = output.text.find("SSO proxy").unwrap();
+        let library = output.text.find("Use middleware").unwrap();
+        assert!(project < library, "{}", output.text);
+        let structured = output.structured.unwrap();
+        let attributed: Vec<(&str, &str)> = structured["snippets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| (s["source"].as_str().unwrap(), s["content"].as_str().unwrap()))
+            .collect();
+        assert_eq!(attributed, vec![
+            ("project", "Our apps sign in through the company SSO proxy."),
+            ("library", "Use middleware to protect routes."),
+        ]);
+        assert_eq!(output.library.as_deref(), Some("vercel/next.js"));
+    }
+
+    #[tokio::test]
+    async fn test_sources_keep_project_docs_when_upstream_fails() {
+        let server = MockServer::start(|_| MockResponse::text(401, "invalid key")).await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let root = auth_project();
+        let request = sources_request(&root);
+        let sources = DocsTool::selected_sources(&request).unwrap();
+
+        let output = DocsTool::query_sources(&config, &request, &sources, &DocsCache::new(None, DEFAULT_TTL), false, None).await.ok().unwrap();
+        assert!(output.text.contains("## Warnings\n\n- Library docs unavailable:"), "{}", output.text);
+        assert!(output.text.contains("SSO proxy"));
+        let structured = output.structured.unwrap();
+        assert_eq!(structured["sources"]["library"]["status"], "failed");
+        assert_eq!(structured["sources"]["project"]["shown"], 1);
+
+        // Offline and not cached: a warning too, without touching the network
+        let offline = OfflineMode::resolve_with(Some(true), None, None).check("docs lookup").unwrap_err();
+        let requests = server.request_count();
+        let output = DocsTool::query_sources(&config, &request, &sources, &DocsCache::new(None, DEFAULT_TTL), false, Some(offline)).await.ok().unwrap();
+        assert!(output.text.contains("not cached"), "{}", output.text);
+        assert_eq!(server.request_count(), requests);
+
+        // Without a project root the project source is refused up front
+        let err = DocsTool::selected_sources(&DocsToolRequest { project_root_path: None, ..request }).err().unwrap();
+        assert!(matches!(err, ToolError::Rpc(e) if e.message.contains("project_root_path")));
+    }
+
+    #[test]
+    fn test_max_snippets_bounds() {
+        for valid in [1, 3, 50] {
+            assert!(DocsTool::validate_request(&request_with(Some(valid)).into()).is_ok());
+        }
+        for invalid in [0, 51] {
+            let err = DocsTool::validate_request(&request_with(Some(invalid)).into()).unwrap_err();
+            assert!(err.message.contains("between 1 and 50"));
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! One answer from several documentation sources (`sources`)
+//!
+//! Library docs and the project's own docs are looked up concurrently and
+//! rendered as labeled sections, project sections first since they are more
+//! specific to the question. The snippet budget (`max_snippets`) is split
+//! between the sources, and what one source cannot use goes to the other. A
+//! failing source becomes a warning; the answer keeps whatever the other found.
+
+use serde_json::{json, Value};
+
+use super::types::{DocsResponse, DocsSource};
+use crate::mcp::tools::project_docs::SearchHit;
+use crate::utils::locale::Message;
+
+/// Snippets in a merged answer when `max_snippets` is not given
+pub const DEFAULT_MERGED_SNIPPETS: usize = 10;
+
+/// What the library docs lookup produced
+pub enum Upstream {
+    /// Documentation of `library`, with the auto-resolve banner if it was resolved
+    Docs { library: String, response: DocsResponse, banner: Option<String> },
+    /// No documentation, e.g. a not-found message with suggestions
+    Message(String),
+}
+
+/// Results of the queried sources; `None` for a source that was not selected
+pub struct Sources {
+    pub project: Option<Result<Vec<SearchHit>, String>>,
+    pub upstream: Option<Result<Upstream, String>>,
+}
+
+/// Merged answer
+pub struct Merged {
+    pub text: String,
+    pub structured: Value,
+    /// Library shown, when library docs are part of the answer
+    pub library: Option<String>,
+    /// Every selected source failed
+    pub failed: bool,
+}
+
+/// Split `total` snippets into (project, upstream)
+///
+/// The project gets the larger half; a source with fewer results than its
+/// share leaves the rest to the other.
+pub fn split_budget(total: usize, project_found: usize, upstream_found: usize) -> (usize, usize) {
+    let project = project_found.min(total.div_ceil(2).max(total.saturating_sub(upstream_found)));
+    let upstream = upstream_found.min(total - project);
+    (project, upstream)
+}
+
+/// Render the sources into one answer about `query`, in the current language
+pub fn merge(query: &str, sources: Sources, budget: usize) -> Merged {
+    let project_hits = match &sources.project {
+        Some(Ok(hits)) => hits.as_slice(),
+        _ => &[],
+    };
+    let upstream_snippets: Vec<(&Option<String>, &str)> = match &sources.upstream {
+        Some(Ok(Upstream::Docs { response, .. })) => response
+            .snippets
+            .iter()
+            .filter(|snippet| !snippet.content.trim().is_empty())
+            .map(|snippet| (&snippet.title, snippet.content.trim_end()))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let (project_budget, upstream_budget) = split_budget(budget, project_hits.len(), upstream_snippets.len());
+
+    let mut text = format!("{}\n\n", Message::DocsMergedTitle { query }.text());
+    let mut snippets = Vec::new();
+    let mut warnings = Vec::new();
+    let mut status = serde_json::Map::new();
+
+    if let Some(Err(error)) = &sources.project {
+        warnings.push(Message::DocsProjectUnavailable { error }.text());
+    }
+    if let Some(Err(error)) = &sources.upstream {
+        warnings.push(Message::DocsLibraryUnavailable { error }.text());
+    }
+    if !warnings.is_empty() {
+        text.push_str(&format!("{}\n\n", Message::DocsWarnings.text()));
+        for warning in &warnings {
+            text.push_str(&format!("- {}\n", warning));
+        }
+        text.push('\n');
+    }
+
+    if let Some(result) = &sources.project {
+        if result.is_ok() {
+            text.push_str(&format!("{}\n\n", Message::DocsProjectSection.text()));
+            if project_hits.is_empty() {
+                text.push_str(&format!("{}\n\n", Message::DocsNoProjectMatches.text()));
+            }
+            for hit in &project_hits[..project_budget] {
+                let section = &hit.section;
+                text.push_str(&format!(
+                    "### {}\n`{}:{}-{}`\n\n{}\n\n",
+                    section.title,
+                    section.path,
+                    section.start_line,
+                    section.end_line,
+                    section.text.trim()
+                ));
+                snippets.push(json!({
+                    "source": DocsSource::Project.name(),
+                    "title": section.title,
+                    "path": section.path,
+                    "start_line": section.start_line,
+                    "end_line": section.end_line,
+                    "content": section.text.trim(),
+                }));
+            }
+        }
+        status.insert(DocsSource::Project.name().to_string(), source_status(result, project_hits.len(), project_budget));
+    }
+
+    let mut library = None;
+    if let Some(result) = &sources.upstream {
+        match result {
+            Ok(Upstream::Docs { library: shown, banner, .. }) => {
+                text.push_str(&format!("{}\n\n", Message::DocsLibrarySection { library: Some(shown) }.text()));
+                if let Some(banner) = banner {
+                    text.push_str(&format!("> **{}**\n\n", banner));
+                }
+                for (title, content) in &upstream_snippets[..upstream_budget] {
+                    if let Some(title) = title {
+                        text.push_str(&format!("### {}\n\n", title));
+                    }
+                    text.push_str(content);
+                    text.push_str("\n\n");
+                    snippets.push(json!({
+                        "source": DocsSource::Library.name(),
+                        "library": shown,
+                        "title": title,
+                        "content": content,
+                    }));
+                }
+                library = Some(shown.clone());
+            }
+            Ok(Upstream::Message(message)) => {
+                text.push_str(&format!("{}\n\n", Message::DocsLibrarySection { library: None }.text()));
+                text.push_str(message.trim_end());
+                text.push_str("\n\n");
+            }
+            Err(_) => {}
+        }
+        status.insert(DocsSource::Library.name().to_string(), source_status(result, upstream_snippets.len(), upstream_budget));
+    }
+
+    let shown = snippets.len();
+    let found = project_hits.len() + upstream_snippets.len();
+    if shown < found {
+        text.push_str(&format!("{}\n", Message::DocsShowing { shown, total: found }.text()));
+    }
+
+    let failed = [sources.project.as_ref().map(Result::is_err), sources.upstream.as_ref().map(Result::is_err)]
+        .into_iter()
+        .flatten()
+        .all(|failed| failed);
+    Merged {
+        text: text.trim_end().to_string(),
+        structured: json!({ "sources": status, "snippets": snippets, "warnings": warnings }),
+        library,
+        failed,
+    }
+}
+
+fn source_status<T>(result: &Result<T, String>, found: usize, shown: usize) -> Value {
+    match result {
+        Ok(_) => json!({ "status": "ok", "found": found, "shown": shown }),
+        Err(error) => json!({ "status": "failed", "error": error }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::types::DocumentSnippet;
+    use crate::mcp::tools::project_docs::DocSection;
+
+    fn hits(count: usize) -> Vec<SearchHit> {
+        (1..=count)
+            .map(|i| SearchHit {
+                section: DocSection {
+                    path: "docs/auth.md".to_string(),
+                    title: format!("Auth > Step {}", i),
+                    start_line: i * 10,
+                    end_line: i * 10 + 5,
+                    text: format!("project step {}", i),
+                },
+                score: 10,
+                excerpt: String::new(),
+            })
+            .collect()
+    }
+
+    fn docs(count: usize) -> Upstream {
+        let snippet = |i| DocumentSnippet { content: format!("library snippet {}", i), title: Some(format!("Snippet {}", i)), score: None };
+        Upstream::Docs {
+            library: "vercel/next.js".to_string(),
+            response: DocsResponse { snippets: (1..=count).map(snippet).collect(), ..Default::default() },
+            banner: None,
+        }
+    }
+
+    #[test]
+    fn test_budget_split_gives_leftovers_to_the_other_source() {
+        assert_eq!(split_budget(10, 20, 20), (5, 5));
+        assert_eq!(split_budget(9, 20, 20), (5, 4));
+        assert_eq!(split_budget(10, 2, 20), (2, 8));
+        assert_eq!(split_budget(10, 20, 3), (7, 3));
+        assert_eq!(split_budget(10, 1, 1), (1, 1));
+        assert_eq!(split_budget(1, 4, 4), (1, 0));
+        assert_eq!(split_budget(0, 4, 4), (0, 0));
+    }
+
+    #[test]
+    fn test_project_sections_come_first_and_snippets_are_attributed() {
+        let merged = merge("authentication", Sources { project: Some(Ok(hits(4))), upstream: Some(Ok(docs(6))) }, 6);
+
+        let project = merged.text.find("## Project docs").unwrap();
+        let library = merged.text.find("## Library docs: vercel/next.js").unwrap();
+        assert!(project < library);
+        assert!(merged.text.contains("`docs/auth.md:30-35`"));
+        assert!(!merged.text.contains("Step 4"));
+        assert!(merged.text.contains("### Snippet 3") && !merged.text.contains("Snippet 4"));
+        assert!(merged.text.ends_with("_Showing 6 of 10 snippets._"));
+        assert!(!merged.failed);
+        assert_eq!(merged.library.as_deref(), Some("vercel/next.js"));
+
+        let sources: Vec<&str> = merged.structured["snippets"].as_array().unwrap().iter().map(|s| s["source"].as_str().unwrap()).collect();
+        assert_eq!(sources, vec!["project", "project", "project", "library", "library", "library"]);
+        assert_eq!(merged.structured["snippets"][0]["path"], "docs/auth.md");
+        assert_eq!(merged.structured["sources"]["project"], json!({ "status": "ok", "found": 4, "shown": 3 }));
+        assert_eq!(merged.structured["warnings"], json!([]));
+    }
+
+    #[test]
+    fn test_failed_source_becomes_a_warning() {
+        let merged = merge(
+            "auth",
+            Sources { project: Some(Ok(hits(2))), upstream: Some(Err("request timed out".to_string())) },
+            DEFAULT_MERGED_SNIPPETS,
+        );
+        assert!(merged.text.contains("## Warnings\n\n- Library docs unavailable: request timed out"));
+        assert!(merged.text.contains("### Auth > Step 2"));
+        assert!(!merged.text.contains("## Library docs"));
+        assert!(!merged.failed);
+        assert!(merged.library.is_none());
+        assert_eq!(merged.structured["sources"]["library"]["status"], "failed");
+
+        let merged = merge(
+            "auth",
+            Sources { project: Some(Err("not a directory".to_string())), upstream: Some(Err("offline".to_string())) },
+            DEFAULT_MERGED_SNIPPETS,
+        );
+        assert!(merged.failed);
+        assert_eq!(merged.structured["warnings"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_headings_follow_the_language() {
+        let sources = Sources { project: Some(Ok(hits(1))), upstream: Some(Err("request timed out".to_string())) };
+        let merged = crate::utils::locale::scope(crate::utils::locale::Locale::Zh, async { merge("认证", sources, 10) }).await;
+        assert!(merged.text.starts_with("# 文档：认证\n\n## 警告\n\n- 库文档不可用：request timed out"), "{}", merged.text);
+        assert!(merged.text.contains("## 项目文档"));
+    }
+}
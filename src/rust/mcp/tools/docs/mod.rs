@@ -1,7 +1,23 @@
 pub mod types;
 pub mod mcp;
+pub mod compare;
+pub mod format;
+pub mod merge;
+pub mod rank;
+pub mod save;
+pub mod search;
+pub mod stats;
+pub mod status;
+#[cfg(feature = "gui")]
 pub mod commands;
 
-pub use mcp::DocsTool;
-pub use types::{DocsRequest, DocsConfig};
+pub use mcp::{DocsTool, DOCS_API_URL_ENV};
+#[cfg(test)]
+pub(crate) use mcp::DOCS_API_URL_LOCK;
+pub use compare::DocsCompareTool;
+pub use search::DocsSearchTool;
+pub use stats::DocsStatsTool;
+pub use status::DocsStatusTool;
+pub use types::{DocsRequest, DocsConfig, DocsCompareRequest, DocsSearchRequest};
+#[cfg(feature = "gui")]
 pub use commands::{test_docs_connection, get_docs_config, save_docs_config};
@@ -0,0 +1,195 @@
+//! Deduplication and topic re-ranking of docs snippets
+//!
+//! The upstream sometimes returns the same example several times with small
+//! differences (a renamed variable, another comment). Each snippet is reduced
+//! to the hashes of its three-word shingles; a snippet sharing at least
+//! [`NEAR_DUPLICATE_SIMILARITY`] of them (Jaccard) with an earlier kept one is
+//! dropped, so the more relevant copy stays. Snippets too short for shingles to
+//! mean much are only dropped when their normalized text is identical.
+//!
+//! To stay linear in the number of snippets, each shingle set is summarized by
+//! a MinHash signature split into [`BANDS`] bands (locality-sensitive hashing):
+//! only snippets sharing a band bucket are compared, and a pair at the
+//! threshold shares one with a probability above 99.9%.
+//!
+//! Re-ranking (`rerank`) orders snippets by how many words of the topic appear
+//! in their title and content, keeping the upstream order between equals.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use super::types::DocumentSnippet;
+
+/// Shingle overlap from which two snippets are near-duplicates
+pub const NEAR_DUPLICATE_SIMILARITY: f64 = 0.8;
+
+/// Words per shingle
+const SHINGLE_WORDS: usize = 3;
+
+/// Snippets with fewer words are compared by their exact text
+const MIN_FINGERPRINT_WORDS: usize = 8;
+
+/// LSH bands of a MinHash signature
+const BANDS: usize = 16;
+
+/// MinHash values per band
+const ROWS: usize = 4;
+
+/// `snippets` without near-duplicates of earlier ones, and how many were dropped
+pub fn dedup(snippets: Vec<&DocumentSnippet>) -> (Vec<&DocumentSnippet>, usize) {
+    // Shingle sets of the kept snippets, and the kept snippets in each band bucket
+    let mut fingerprints: Vec<HashSet<u64>> = Vec::new();
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    let mut texts: HashSet<String> = HashSet::new();
+    let mut kept = Vec::with_capacity(snippets.len());
+    let mut dropped = 0;
+    for snippet in snippets {
+        let words = words(&snippet.content);
+        let duplicate = if words.len() < MIN_FINGERPRINT_WORDS {
+            !texts.insert(words.join(" "))
+        } else {
+            let fingerprint = shingles(&words);
+            let bands = bands(&fingerprint);
+            let mut candidates: Vec<usize> = bands.iter().filter_map(|band| buckets.get(band)).flatten().copied().collect();
+            candidates.sort_unstable();
+            candidates.dedup();
+            let near = candidates.iter().any(|&seen| similarity(&fingerprints[seen], &fingerprint) >= NEAR_DUPLICATE_SIMILARITY);
+            if !near {
+                for band in bands {
+                    buckets.entry(band).or_default().push(fingerprints.len());
+                }
+                fingerprints.push(fingerprint);
+            }
+            near
+        };
+        if duplicate {
+            dropped += 1;
+        } else {
+            kept.push(snippet);
+        }
+    }
+    (kept, dropped)
+}
+
+/// Stable sort by the number of topic words in the title (counting double) and content
+pub fn rerank(snippets: &mut [&DocumentSnippet], topic: &str) {
+    let terms: HashSet<String> = words(topic).into_iter().filter(|term| term.chars().count() > 1).collect();
+    if terms.is_empty() {
+        return;
+    }
+    let score = |snippet: &DocumentSnippet| {
+        let title: HashSet<String> = snippet.title.as_deref().map(words).unwrap_or_default().into_iter().collect();
+        let content: HashSet<String> = words(&snippet.content).into_iter().collect();
+        terms.iter().map(|term| 2 * usize::from(title.contains(term)) + usize::from(content.contains(term))).sum::<usize>()
+    };
+    snippets.sort_by_cached_key(|snippet| std::cmp::Reverse(score(snippet)));
+}
+
+/// Lower-cased alphanumeric words
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase).collect()
+}
+
+/// Hashes of the word shingles of `words`
+fn shingles(words: &[String]) -> HashSet<u64> {
+    words
+        .windows(SHINGLE_WORDS)
+        .map(|shingle| {
+            let mut hasher = DefaultHasher::new();
+            shingle.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// Bucket keys of the MinHash signature of a shingle set, one per band
+fn bands(shingles: &HashSet<u64>) -> Vec<(usize, u64)> {
+    let signature: Vec<u64> = (0..(BANDS * ROWS) as u64)
+        .map(|seed| shingles.iter().map(|&shingle| mix(shingle ^ seed.wrapping_mul(SEED_STEP))).min().unwrap_or(0))
+        .collect();
+    signature
+        .chunks(ROWS)
+        .enumerate()
+        .map(|(band, rows)| {
+            let mut hasher = DefaultHasher::new();
+            rows.hash(&mut hasher);
+            (band, hasher.finish())
+        })
+        .collect()
+}
+
+/// Spreads the seeds of the MinHash functions over all bits
+const SEED_STEP: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// SplitMix64 finalizer, one independent hash function per seed
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Jaccard similarity of two shingle sets
+fn similarity(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared).max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(title: &str, content: &str) -> DocumentSnippet {
+        DocumentSnippet { content: content.to_string(), title: Some(title.to_string()), score: None }
+    }
+
+    const EXAMPLE: &str = "Use the app directory to define routes. Each folder becomes a route segment and a page file makes the segment public. Layouts wrap the pages below them and keep their state across navigation.";
+
+    #[test]
+    fn test_near_duplicates_are_dropped() {
+        let snippets = [
+            snippet("Routing", EXAMPLE),
+            snippet("Routing again", &EXAMPLE.replace("makes the segment public", "makes the segment visible")),
+            snippet("Data", "Fetch data in server components with async functions and cache the result per request."),
+            snippet("Short", "npm install next"),
+            snippet("Short copy", "npm  install\nnext"),
+            snippet("Other", "npm install react"),
+        ];
+        let (kept, dropped) = dedup(snippets.iter().collect());
+        let titles: Vec<_> = kept.iter().map(|s| s.title.as_deref().unwrap()).collect();
+        assert_eq!(titles, ["Routing", "Data", "Short", "Other"]);
+        assert_eq!(dropped, 2);
+    }
+
+    #[test]
+    fn test_only_kept_snippets_are_compared() {
+        // Each copy drifts a little further; compared with the first, the third is no longer near
+        let first = "one two three four five six seven eight nine ten eleven twelve";
+        let second = "one two three four five six seven eight nine ten eleven dozen";
+        let third = "one two three four five six seven eight nine ten twice dozen";
+        let snippets = [snippet("First", first), snippet("Second", second), snippet("Third", third)];
+        let (kept, dropped) = dedup(snippets.iter().collect());
+        let titles: Vec<_> = kept.iter().map(|s| s.title.as_deref().unwrap()).collect();
+        assert_eq!(titles, ["First", "Third"]);
+        assert_eq!(dropped, 1);
+    }
+
+    #[test]
+    fn test_rerank_prefers_topic_words_and_keeps_order_between_equals() {
+        let snippets = [
+            snippet("Install", "npm install next"),
+            snippet("Middleware", "Run code before a request completes"),
+            snippet("Config", "next.config.js options, including middleware matchers"),
+            snippet("Deploy", "Deploy to any Node.js host"),
+        ];
+        let mut ranked: Vec<_> = snippets.iter().collect();
+        rerank(&mut ranked, "Middleware");
+        let titles: Vec<_> = ranked.iter().map(|s| s.title.as_deref().unwrap()).collect();
+        assert_eq!(titles, ["Middleware", "Config", "Install", "Deploy"]);
+
+        // Nothing to rank by
+        rerank(&mut ranked, " / ");
+        assert_eq!(ranked[0].title.as_deref(), Some("Middleware"));
+    }
+}
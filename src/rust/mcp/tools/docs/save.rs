@@ -0,0 +1,211 @@
+//! Saving a docs lookup into the project (`save_to`)
+//!
+//! The rendered Markdown is written below a front-matter header naming the
+//! library, topic, version, fetch time and source, so a saved file can be
+//! refreshed or traced back later. The target must be a project-relative path
+//! that stays inside the project, also through symlinked directories, and an
+//! existing file is only replaced with `overwrite: true`.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rmcp::model::ErrorData as McpError;
+use serde::Serialize;
+use serde_json::json;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+
+use super::types::{docs_website_url, DocsRequest};
+use crate::mcp::dry_run;
+
+/// A file written for `save_to`, reported as structured content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SavedDocs {
+    pub path: PathBuf,
+    pub bytes: usize,
+    pub overwritten: bool,
+}
+
+/// Absolute path `save_to` points at inside `project_root`
+///
+/// Checked before the lookup so a bad path or an existing file fails without
+/// spending a request.
+pub fn resolve_target(project_root: &Path, save_to: &str, overwrite: bool) -> Result<PathBuf, McpError> {
+    let relative = Path::new(save_to.trim());
+    let is_relative_file = relative.file_name().is_some()
+        && relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !is_relative_file {
+        return Err(invalid(format!(
+            "save_to must be a file path relative to the project that stays inside it: {}",
+            save_to
+        )));
+    }
+
+    let root = project_root
+        .canonicalize()
+        .map_err(|e| invalid(format!("Cannot resolve project root {}: {}", project_root.display(), e)))?;
+    let target = root.join(relative);
+
+    // The closest existing part of the path decides where a write would land
+    if let Some(existing) = target.ancestors().find(|p| fs::symlink_metadata(p).is_ok()) {
+        let resolved = existing
+            .canonicalize()
+            .map_err(|e| invalid(format!("Cannot resolve {}: {}", existing.display(), e)))?;
+        if !resolved.starts_with(&root) {
+            return Err(invalid(format!("save_to leaves the project through a symlink: {}", save_to)));
+        }
+        if existing == target {
+            if resolved.is_dir() {
+                return Err(invalid(format!("save_to is a directory: {}", save_to)));
+            }
+            if !overwrite {
+                return Err(exists(save_to));
+            }
+        }
+    }
+    Ok(target)
+}
+
+/// Write `document` to a target from [`resolve_target`], creating parent directories
+///
+/// During a dry run the write is only staged.
+pub fn write(target: &Path, save_to: &str, document: &str, overwrite: bool) -> Result<SavedDocs, McpError> {
+    let failed = |e: std::io::Error| McpError::internal_error(format!("Failed to save {}: {}", target.display(), e), None);
+
+    if dry_run::is_active() {
+        let previous = fs::metadata(target).ok().map(|metadata| metadata.len() as usize);
+        if previous.is_some() && !overwrite {
+            return Err(exists(save_to));
+        }
+        dry_run::stage(target, document.to_string(), previous);
+        return Ok(SavedDocs { path: target.to_path_buf(), bytes: document.len(), overwritten: previous.is_some() });
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(failed)?;
+    }
+    let overwritten = overwrite && target.exists();
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        // Also refuses a file created since the target was checked
+        options.create_new(true);
+    }
+    let mut file = match options.open(target) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => return Err(exists(save_to)),
+        Err(e) => return Err(failed(e)),
+    };
+    file.write_all(document.as_bytes()).map_err(failed)?;
+
+    Ok(SavedDocs { path: target.to_path_buf(), bytes: document.len(), overwritten })
+}
+
+/// Front-matter header of a saved lookup of `library`
+///
+/// Values are written as JSON strings, which YAML reads unchanged whatever
+/// characters they contain.
+pub fn front_matter(library: &str, request: &DocsRequest, fetched_at: DateTime<Utc>) -> String {
+    let field = |name: &str, value: &str| format!("{}: {}\n", name, json!(value));
+
+    let mut header = String::from("---\n");
+    header.push_str(&field("library", library));
+    if let Some(topic) = &request.topic {
+        header.push_str(&field("topic", topic));
+    }
+    if let Some(version) = &request.version {
+        header.push_str(&field("version", version));
+    }
+    if let Some(page) = request.page {
+        header.push_str(&format!("page: {}\n", page));
+    }
+    header.push_str(&field("fetched_at", &fetched_at.to_rfc3339_opts(SecondsFormat::Secs, true)));
+    header.push_str(&field("source", &format!("{}/{}", docs_website_url(), library.trim_matches('/'))));
+    header.push_str("---\n\n");
+    header
+}
+
+fn invalid(message: String) -> McpError {
+    McpError::invalid_params(message, Some(json!({ "path": "save_to" })))
+}
+
+fn exists(save_to: &str) -> McpError {
+    McpError::invalid_params(
+        format!("{} already exists; pass overwrite: true to replace it", save_to),
+        Some(json!({ "path": "save_to", "exists": true })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use chrono::TimeZone;
+
+    fn temp_project() -> TestDir {
+        TestDir::new("docs-save")
+    }
+
+    #[test]
+    fn test_save_to_must_stay_inside_the_project() {
+        let project = temp_project();
+        for bad in ["", "  ", "/etc/passwd", "../outside.md", "docs/../../outside.md", "docs/.."] {
+            let err = resolve_target(&project, bad, false).unwrap_err();
+            assert_eq!(err.data.unwrap()["path"], "save_to", "{:?}", bad);
+        }
+        fs::create_dir_all(project.join("docs")).unwrap();
+        assert!(resolve_target(&project, "docs", true).unwrap_err().message.contains("is a directory"));
+
+        let target = resolve_target(&project, "./docs/new/nextjs.md", false).unwrap();
+        assert_eq!(target, project.join("docs/new/nextjs.md"));
+
+        #[cfg(unix)]
+        {
+            let outside = temp_project();
+            std::os::unix::fs::symlink(&outside, project.join("linked")).unwrap();
+            let err = resolve_target(&project, "linked/escape.md", false).unwrap_err();
+            assert!(err.message.contains("symlink"), "{}", err.message);
+        }
+    }
+
+    #[test]
+    fn test_existing_file_needs_overwrite() {
+        let project = temp_project();
+        let target = resolve_target(&project, "docs/routing.md", false).unwrap();
+        let saved = write(&target, "docs/routing.md", "first", false).unwrap();
+        assert_eq!(saved, SavedDocs { path: project.join("docs/routing.md"), bytes: 5, overwritten: false });
+
+        let err = resolve_target(&project, "docs/routing.md", false).unwrap_err();
+        assert_eq!(err.data.unwrap()["exists"], true);
+        // A file that appeared after the check is not replaced either
+        assert!(write(&target, "docs/routing.md", "second", false).is_err());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "first");
+
+        let target = resolve_target(&project, "docs/routing.md", true).unwrap();
+        let saved = write(&target, "docs/routing.md", "second", true).unwrap();
+        assert!(saved.overwritten);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "second");
+    }
+
+    #[test]
+    fn test_front_matter_fields() {
+        let fetched_at = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
+        let request = DocsRequest {
+            topic: Some("routing: \"app\" dir".to_string()),
+            version: Some("v15.1.8".to_string()),
+            page: Some(2),
+            ..DocsRequest::new("vercel/next.js")
+        };
+        let header = front_matter("/vercel/next.js", &request, fetched_at);
+        let expected = format!(
+            "---\nlibrary: \"/vercel/next.js\"\ntopic: \"routing: \\\"app\\\" dir\"\nversion: \"v15.1.8\"\npage: 2\n\
+             fetched_at: \"2026-01-02T03:04:05Z\"\nsource: \"{}/vercel/next.js\"\n---\n\n",
+            docs_website_url()
+        );
+        assert_eq!(header, expected);
+
+        let minimal = front_matter("facebook/react", &DocsRequest::new("react"), fetched_at);
+        assert!(minimal.starts_with("---\nlibrary: \"facebook/react\"\nfetched_at:"), "{}", minimal);
+        assert!(!minimal.contains("topic") && !minimal.contains("version") && !minimal.contains("page"));
+    }
+}
@@ -0,0 +1,254 @@
+//! `docs_search`: find library identifiers before asking `docs` for them
+//!
+//! The same upstream search the `docs` tool falls back to when a library is not
+//! found, offered on its own so agents can look up `owner/repo` up front. The
+//! upstream has no language facet, so `language` is matched against the words
+//! of each result's identifier, title and description.
+
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError, Tool, ToolAnnotations};
+use serde_json::json;
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use super::types::{DocsSearchRequest, SearchResult};
+use super::DocsTool;
+use crate::config::OfflineMode;
+use crate::docs_client::resolve::library_id;
+use crate::docs_client::Client;
+use crate::mcp::boundary::is_upstream_unavailable;
+use crate::mcp::utils::{offline_result, ToolError};
+use crate::{log_debug, log_important};
+
+/// Allowed values of the `limit` parameter
+pub const LIMIT_RANGE: RangeInclusive<usize> = 1..=20;
+
+/// Results returned when `limit` is not given
+const DEFAULT_LIMIT: usize = 10;
+
+/// Library search against the docs API
+pub struct DocsSearchTool;
+
+impl DocsSearchTool {
+    pub async fn search(request: DocsSearchRequest) -> Result<CallToolResult, ToolError> {
+        let query = request.query.trim();
+        if query.is_empty() {
+            return Err(McpError::invalid_params("query must not be empty", Some(json!({ "path": "query" }))).into());
+        }
+        let limit = request.limit.unwrap_or(DEFAULT_LIMIT);
+        if !LIMIT_RANGE.contains(&limit) {
+            return Err(McpError::invalid_params(
+                format!("limit must be between {} and {}", LIMIT_RANGE.start(), LIMIT_RANGE.end()),
+                Some(json!({ "path": "limit" })),
+            )
+            .into());
+        }
+        let language = request.language.as_deref().map(str::trim).filter(|language| !language.is_empty());
+
+        if let Err(offline) = OfflineMode::resolve(None).check(&format!("docs search for {:?}", query)) {
+            log_important!(info, "{}", offline);
+            return Ok(offline_result(&offline));
+        }
+        let (config, _) = DocsTool::get_config()
+            .await
+            .map_err(|e| McpError::internal_error(format!("Failed to get docs config: {}", e), None))?;
+        let client = Client::new(config).map_err(anyhow::Error::from)?;
+        Self::search_with(&client, query, limit, language).await
+    }
+
+    async fn search_with(client: &Client, query: &str, limit: usize, language: Option<&str>) -> Result<CallToolResult, ToolError> {
+        let results = match client.search(query).await {
+            Ok(results) => results,
+            Err(e) => {
+                let e = anyhow::Error::from(e);
+                if is_upstream_unavailable(&e) {
+                    log_important!(warn, "Docs search failed: {:#}", e);
+                    return Err(e.context("Docs search failed").into());
+                }
+                let error_msg = format!("Docs search failed: {}", e);
+                log_important!(warn, "{}", error_msg);
+                return Ok(CallToolResult {
+                    content: vec![Content::text(error_msg)],
+                    is_error: Some(true),
+                    meta: None,
+                    structured_content: None,
+                });
+            }
+        };
+        let found = results.len();
+        let results: Vec<SearchResult> = results
+            .into_iter()
+            .filter(|result| language.is_none_or(|language| mentions_language(result, language)))
+            .take(limit)
+            .collect();
+        log_debug!("Docs search: query={}, language={:?}, found={}, returned={}", query, language, found, results.len());
+
+        let structured: Vec<_> = results
+            .iter()
+            .map(|result| {
+                json!({
+                    "library": library_id(result),
+                    "title": result.title,
+                    "description": result.description,
+                    "stars": result.stars,
+                    "trust_score": result.trust_score,
+                    "benchmark_score": result.benchmark_score,
+                    "state": result.state,
+                })
+            })
+            .collect();
+        Ok(CallToolResult {
+            content: vec![Content::text(format_results(query, language, &results))],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(json!({
+                "query": query,
+                "language": language,
+                "results": structured,
+            })),
+        })
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Library name or keywords, e.g. next.js or orm"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": LIMIT_RANGE.start(),
+                    "maximum": LIMIT_RANGE.end(),
+                    "description": "Maximum number of libraries to return, defaults to 10"
+                },
+                "language": {
+                    "type": "string",
+                    "description": "Only libraries whose identifier, title or description mention this language, e.g. python (optional, case-insensitive)"
+                }
+            },
+            "required": ["query"]
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("docs_search"),
+                description: Some(Cow::Borrowed("Search the documentation index for libraries. Returns owner/repo identifiers with stars and trust scores to pass to the docs tool as `library`.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Docs Search".to_string()),
+                    read_only_hint: Some(true),
+                    destructive_hint: Some(false),
+                    idempotent_hint: Some(true),
+                    open_world_hint: Some(true),     // Queries the docs API
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Docs Search".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+/// Whether `language` is one of the words of the result's identifier, title or description
+fn mentions_language(result: &SearchResult, language: &str) -> bool {
+    let language = language.to_lowercase();
+    [Some(result.id.as_str()), result.title.as_deref(), result.description.as_deref()]
+        .into_iter()
+        .flatten()
+        .flat_map(|text| text.split(|c: char| !(c.is_alphanumeric() || matches!(c, '+' | '#'))))
+        .any(|word| word.to_lowercase() == language)
+}
+
+fn format_results(query: &str, language: Option<&str>, results: &[SearchResult]) -> String {
+    let mut text = match language {
+        Some(language) => format!("# Libraries matching \"{}\" ({})\n\n", query, language),
+        None => format!("# Libraries matching \"{}\"\n\n", query),
+    };
+    if results.is_empty() {
+        text.push_str("No libraries found. Try a shorter name or drop the language filter.\n");
+        return text;
+    }
+    text.push_str(&DocsTool::format_search_results(results));
+    text.push_str("---\n\nPass an identifier to the docs tool as `library`.\n");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::mock::{MockResponse, MockServer};
+    use crate::docs_client::DocsConfig;
+
+    fn result(id: &str, title: &str, description: &str) -> SearchResult {
+        serde_json::from_value(json!({ "id": id, "title": title, "description": description })).unwrap()
+    }
+
+    #[test]
+    fn test_language_matches_whole_words() {
+        let client = result("/redis/redis-py", "redis-py", "Redis Python client");
+        let server = result("/redis/redis", "Redis", "In-memory data store written in C");
+        let cpp = result("/nlohmann/json", "JSON for Modern C++", "");
+
+        assert!(mentions_language(&client, "python"));
+        assert!(mentions_language(&client, "PY"));
+        assert!(!mentions_language(&server, "python"));
+        assert!(mentions_language(&server, "c"));
+        assert!(mentions_language(&cpp, "c++"));
+        assert!(!mentions_language(&cpp, "c"));
+    }
+
+    #[tokio::test]
+    async fn test_search_filters_and_limits() {
+        let server = MockServer::start(|req| {
+            assert_eq!(req.query("query").as_deref(), Some("redis"));
+            MockResponse::json(
+                200,
+                &json!({ "results": [
+                    { "id": "/redis/redis", "title": "Redis", "description": "In-memory data store written in C", "stars": 65000, "trustScore": 9.1 },
+                    { "id": "/redis/redis-py", "title": "redis-py", "description": "Redis Python client" },
+                    { "id": "/redis/redis-om-python", "title": "Redis OM Python", "description": "Object mapping for Redis" },
+                ]})
+                .to_string(),
+            )
+        })
+        .await;
+        let client = Client::new(DocsConfig { base_url: server.base_url(), ..Default::default() }).unwrap();
+
+        let all = DocsSearchTool::search_with(&client, "redis", 2, None).await.ok().unwrap();
+        let structured = all.structured_content.unwrap();
+        let libraries: Vec<_> = structured["results"].as_array().unwrap().iter().map(|r| r["library"].clone()).collect();
+        assert_eq!(libraries, vec![json!("redis/redis"), json!("redis/redis-py")]);
+        assert_eq!(structured["results"][0]["trust_score"], 9.1);
+
+        let python = DocsSearchTool::search_with(&client, "redis", 10, Some("Python")).await.ok().unwrap();
+        let structured = python.structured_content.unwrap();
+        let libraries: Vec<_> = structured["results"].as_array().unwrap().iter().map(|r| r["library"].clone()).collect();
+        assert_eq!(libraries, vec![json!("redis/redis-py"), json!("redis/redis-om-python")]);
+        assert_eq!(structured["language"], "Python");
+    }
+
+    #[tokio::test]
+    async fn test_empty_query_and_bad_limit_are_invalid_params() {
+        for request in [
+            DocsSearchRequest { query: " ".to_string(), limit: None, language: None },
+            DocsSearchRequest { query: "react".to_string(), limit: Some(0), language: None },
+        ] {
+            match DocsSearchTool::search(request).await {
+                Err(ToolError::Rpc(error)) => assert!(error.data.unwrap()["path"].is_string()),
+                _ => panic!("expected an invalid params error"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_results() {
+        let text = format_results("redis", Some("python"), &[result("/redis/redis-py", "redis-py", "Redis Python client")]);
+        assert!(text.starts_with("# Libraries matching \"redis\" (python)\n\n1. **redis/redis-py**\n"), "{}", text);
+        assert!(format_results("nothing", None, &[]).contains("No libraries found"));
+    }
+}
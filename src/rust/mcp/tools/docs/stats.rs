@@ -0,0 +1,197 @@
+//! `devkit_stats`: upstream request metrics of this process
+//!
+//! Reports the [`Metrics`] registry of the docs client (requests, status codes,
+//! latency and bytes received per operation) with the hit rate of the docs
+//! cache, as Markdown plus the same numbers as structured content. The HTTP
+//! server serves the same registry in Prometheus format at `/metrics`.
+//!
+//! With `check_keys` the configured API keys are validated against the API
+//! first; otherwise the outcome of the startup key check is shown, if any.
+
+use rmcp::model::{CallToolResult, Content, Tool, ToolAnnotations};
+use serde_json::json;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::types::DocsStatsRequest;
+use super::DocsTool;
+use crate::config::OfflineMode;
+use crate::docs_client::cache::{CacheStats, DocsCache};
+use crate::docs_client::key_check::{self, KeyCheckReport};
+use crate::docs_client::metrics::{MetricsSnapshot, OperationMetrics};
+use crate::docs_client::{Client, Metrics};
+
+/// Upstream request metrics and cache hit rate
+pub struct DocsStatsTool;
+
+impl DocsStatsTool {
+    pub async fn stats(request: DocsStatsRequest) -> CallToolResult {
+        let keys = if request.check_keys == Some(true) { Self::check_keys().await } else { Ok(key_check::last_report()) };
+        let snapshot = Metrics::shared().snapshot();
+        let cache = DocsCache::shared().stats();
+        let mut text = format_stats(&snapshot, &cache);
+        match &keys {
+            Ok(Some(report)) => text.push_str(&format_keys(report)),
+            Ok(None) => {}
+            Err(reason) => text.push_str(&format!("\n## API Keys\n\nNot checked: {}\n", reason)),
+        }
+        CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(json!({
+                "since": snapshot.since,
+                "operations": snapshot.operations,
+                "cache": { "hits": cache.hits, "misses": cache.misses, "hit_ratio": cache.hit_ratio },
+                "keys": keys.ok().flatten(),
+            })),
+        }
+    }
+
+    /// Validate the configured keys now; the reason when that is not possible
+    async fn check_keys() -> Result<Option<KeyCheckReport>, String> {
+        OfflineMode::resolve(None).check("docs API key check").map_err(|e| e.to_string())?;
+        let (config, _) = DocsTool::get_config().await.map_err(|e| e.to_string())?;
+        let client = Client::new(config).map_err(|e| e.to_string())?;
+        if client.keys().is_empty() {
+            return Err("no API key is configured, requests use the free rate limit".to_string());
+        }
+        Ok(Some(client.check_keys().await))
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "check_keys": {
+                    "type": "boolean",
+                    "description": "Validate each configured docs API key against the API and report its remaining quota"
+                }
+            }
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("devkit_stats"),
+                description: Some(Cow::Borrowed("Metrics of the docs API requests made by this server: requests and status codes, latency, bytes received and the docs cache hit rate. With check_keys, also whether each configured API key is valid, expired or out of quota.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Devkit Stats".to_string()),
+                    read_only_hint: Some(true),
+                    destructive_hint: Some(false),
+                    idempotent_hint: Some(false),    // Counters change between calls
+                    open_world_hint: Some(true),     // check_keys queries the docs API
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Devkit Stats".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+fn format_stats(snapshot: &MetricsSnapshot, cache: &CacheStats) -> String {
+    let mut text = format!("# Devkit Stats\n\nSince {}\n\n## Docs API\n\n", snapshot.since.to_rfc3339());
+    if snapshot.operations.is_empty() {
+        text.push_str("No upstream requests yet.\n");
+    } else {
+        text.push_str("| Operation | Requests | Status codes | Failures | Mean | p95 | Max | Received |\n");
+        text.push_str("|---|---|---|---|---|---|---|---|\n");
+        for (operation, metrics) in &snapshot.operations {
+            text.push_str(&format_operation(operation.as_str(), metrics));
+        }
+    }
+    text.push_str(&format!(
+        "\n## Cache\n\nHits: {}, misses: {} (hit ratio {:.1}%)\n",
+        cache.hits,
+        cache.misses,
+        cache.hit_ratio * 100.0
+    ));
+    text
+}
+
+fn format_operation(name: &str, metrics: &OperationMetrics) -> String {
+    let statuses: Vec<String> = metrics.statuses.iter().map(|(status, count)| format!("{}×{}", status, count)).collect();
+    let ms = |value: Option<u64>| value.map_or_else(|| "-".to_string(), |ms| format!("{} ms", ms));
+    format!(
+        "| {} | {} | {} | {} | {} | {} | {} | {} bytes |\n",
+        name,
+        metrics.requests,
+        if statuses.is_empty() { "-".to_string() } else { statuses.join(", ") },
+        metrics.failures,
+        ms(metrics.latency.mean_ms()),
+        ms(metrics.latency.quantile_ms(0.95)),
+        ms(metrics.latency.max_ms.into()),
+        metrics.bytes_received
+    )
+}
+
+/// Result of each key, with the time of the check
+fn format_keys(report: &KeyCheckReport) -> String {
+    if report.keys.is_empty() {
+        return String::new();
+    }
+    let lines: Vec<String> = report.keys.iter().map(|check| format!("- {}: {}", check.key, check.health)).collect();
+    format!("\n## API Keys\n\nChecked {}\n\n{}\n", report.checked_at.to_rfc3339(), lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::metrics::Operation;
+    use std::time::Duration;
+
+    fn cache_stats(hits: u64, misses: u64) -> CacheStats {
+        let lookups = hits + misses;
+        CacheStats {
+            memory_entries: 0,
+            disk_entries: 0,
+            total_bytes: 0,
+            hits,
+            misses,
+            hit_ratio: if lookups == 0 { 0.0 } else { hits as f64 / lookups as f64 },
+            oldest: None,
+            directory: None,
+            max_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_format_lists_operations_and_cache_hit_rate() {
+        let metrics = Metrics::default();
+        metrics.record_response(Operation::Docs, 200, Duration::from_millis(120));
+        metrics.record_response(Operation::Docs, 429, Duration::from_millis(80));
+        metrics.record_bytes(Operation::Docs, 2048);
+        metrics.record_failure(Operation::Search, Duration::from_secs(15));
+
+        let text = format_stats(&metrics.snapshot(), &cache_stats(3, 1));
+        assert!(text.contains("| docs | 2 | 200×1, 429×1 | 0 | 100 ms | 120 ms | 120 ms | 2048 bytes |"), "{}", text);
+        assert!(text.contains("| search | 1 | - | 1 | 15000 ms | 15000 ms | 15000 ms | 0 bytes |"), "{}", text);
+        assert!(text.contains("Hits: 3, misses: 1 (hit ratio 75.0%)"), "{}", text);
+
+        let empty = format_stats(&Metrics::default().snapshot(), &cache_stats(0, 0));
+        assert!(empty.contains("No upstream requests yet."));
+    }
+
+    #[test]
+    fn test_format_keys_lists_each_key() {
+        use crate::docs_client::key_check::{KeyCheck, KeyHealth};
+        use crate::docs_client::quota::RateLimit;
+
+        let check = |key: &str, health| KeyCheck { key: key.to_string(), health };
+        let report = KeyCheckReport {
+            keys: vec![
+                check("ctx7…9f2a", KeyHealth::Valid { rate_limit: Some(RateLimit { limit: Some(60), remaining: Some(12), reset_secs: None }) }),
+                check("ctx7…0b1c", KeyHealth::Expired { message: "key expired".to_string() }),
+            ],
+            checked_at: chrono::Utc::now(),
+        };
+        let text = format_keys(&report);
+        assert!(text.contains("- ctx7…9f2a: valid, 12 of 60 requests left\n"), "{}", text);
+        assert!(text.contains("- ctx7…0b1c: expired (key expired)"), "{}", text);
+        assert!(format_keys(&KeyCheckReport { keys: Vec::new(), checked_at: report.checked_at }).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! `docs_status`: why docs lookups are failing
+//!
+//! Everything that decides whether a lookup can succeed, in one reply: whether
+//! the API answers right now (one search, skipped in offline mode), the
+//! configured keys in masked form with their state in the rotation and the
+//! outcome of the last key check, the rate limit left according to the last
+//! response, the circuit breaker, the cache and the last upstream error seen
+//! by this process before the check.
+
+use rmcp::model::{CallToolResult, Content, Tool, ToolAnnotations};
+use serde::Serialize;
+use serde_json::json;
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::DocsTool;
+use crate::config::{OfflineError, OfflineMode};
+use crate::docs_client::cache::{CacheStats, DocsCache};
+use crate::docs_client::key_check::{self, KeyCheckReport, KEY_CHECK_QUERY};
+use crate::docs_client::keys::KeyUsage;
+use crate::docs_client::metrics::UpstreamError;
+use crate::docs_client::{Client, RetryPolicy};
+
+/// Timeout of the reachability probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Health of the docs API
+pub struct DocsStatusTool;
+
+/// Outcome of the reachability probe
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum Reachability {
+    /// The probe search succeeded
+    Ok { latency_ms: u64 },
+    /// The API answered, but with an error such as a rejected key
+    Error { latency_ms: u64, error: String },
+    /// No answer: network, timeout or an open circuit breaker
+    Unreachable { error: String },
+    /// Not probed
+    Skipped { reason: String },
+}
+
+impl DocsStatusTool {
+    pub async fn status() -> CallToolResult {
+        let offline = OfflineMode::resolve(None).check("docs status probe").err();
+        let client = match DocsTool::get_config().await.map_err(|e| e.to_string()).and_then(|(config, _)| Client::new(config).map_err(|e| e.to_string())) {
+            Ok(client) => client,
+            Err(e) => {
+                return CallToolResult {
+                    content: vec![Content::text(format!("# Docs Status\n\nThe docs client cannot be set up: {}\n", e))],
+                    is_error: Some(true),
+                    meta: None,
+                    structured_content: Some(json!({ "configured": false, "error": e })),
+                }
+            }
+        };
+        Self::status_with(&client, DocsCache::shared(), offline).await
+    }
+
+    async fn status_with(client: &Client, cache: &DocsCache, offline: Option<OfflineError>) -> CallToolResult {
+        // Taken first, as the probe may replace it
+        let last_error = client.metrics().snapshot().last_error;
+        let reachability = match &offline {
+            Some(offline) => Reachability::Skipped { reason: offline.to_string() },
+            None => Self::probe(client).await,
+        };
+        let keys = client.keys().usage();
+        let key_check = key_check::last_report();
+        let session = client.usage().snapshot();
+        let breaker_open = client.breaker().is_open();
+        let cache_stats = cache.stats();
+
+        let mut text = format!("# Docs Status\n\nAPI: {}\n\n", client.config().base_url);
+        text.push_str(&format!("## Reachability\n\n{}\n", format_reachability(&reachability)));
+        if breaker_open {
+            text.push_str("Circuit breaker: open, requests are refused until it cools down\n");
+        }
+        text.push_str(&format!("\n## API Keys\n\n{}", format_keys(&keys, key_check.as_ref())));
+        text.push_str(&format!("\n## Rate Limit\n\n{}", DocsTool::format_rate_limit(&session)));
+        text.push_str(&format!("\n## Cache\n\n{}", format_cache(&cache_stats)));
+        text.push_str(&format!("\n## Last Error\n\n{}\n", format_last_error(last_error.as_ref())));
+
+        CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(json!({
+                "configured": true,
+                "base_url": client.config().base_url,
+                "reachability": reachability,
+                "circuit_breaker_open": breaker_open,
+                "keys": keys,
+                "key_check": key_check,
+                "rate_limit": session.rate_limit,
+                "session_requests": session.requests,
+                "cache": cache_stats,
+                "last_error": last_error,
+            })),
+        }
+    }
+
+    /// One search with a single attempt
+    async fn probe(client: &Client) -> Reachability {
+        let client = client.clone().with_retry(RetryPolicy::none()).with_timeouts(PROBE_TIMEOUT, PROBE_TIMEOUT);
+        let started = Instant::now();
+        let result = client.search(KEY_CHECK_QUERY).await;
+        let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        match result {
+            Ok(_) => Reachability::Ok { latency_ms },
+            Err(e) if e.is_upstream_unavailable() => Reachability::Unreachable { error: e.to_string() },
+            Err(e) => Reachability::Error { latency_ms, error: e.to_string() },
+        }
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {}
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("docs_status"),
+                description: Some(Cow::Borrowed("Diagnose why docs queries fail: whether the docs API is reachable now, the configured API keys (masked) and their state, the remaining rate limit, the circuit breaker, cache statistics and the last upstream error.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Docs Status".to_string()),
+                    read_only_hint: Some(true),
+                    destructive_hint: Some(false),
+                    idempotent_hint: Some(false),    // Reflects the current state
+                    open_world_hint: Some(true),     // Probes the docs API
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Docs Status".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+fn format_reachability(reachability: &Reachability) -> String {
+    match reachability {
+        Reachability::Ok { latency_ms } => format!("Reachable, answered in {} ms", latency_ms),
+        Reachability::Error { latency_ms, error } => format!("Reachable ({} ms), but the probe failed: {}", latency_ms, error),
+        Reachability::Unreachable { error } => format!("Unreachable: {}", error),
+        Reachability::Skipped { reason } => format!("Not probed: {}", reason),
+    }
+}
+
+/// Masked keys with their rotation state and, when checked, what the API said about them
+fn format_keys(keys: &[KeyUsage], check: Option<&KeyCheckReport>) -> String {
+    if keys.is_empty() {
+        return "None configured, requests use the free rate limit\n".to_string();
+    }
+    let lines: Vec<String> = keys
+        .iter()
+        .map(|usage| {
+            let checked = check
+                .and_then(|report| report.keys.iter().find(|checked| checked.key == usage.key))
+                .map_or_else(String::new, |checked| format!("; last check: {}", checked.health));
+            let plural = if usage.requests == 1 { "" } else { "s" };
+            format!("- {}: {} ({} request{}{})", usage.key, usage.status, usage.requests, plural, checked)
+        })
+        .collect();
+    format!("{}\n", lines.join("\n"))
+}
+
+fn format_cache(stats: &CacheStats) -> String {
+    format!(
+        "{} entries on disk, {} in memory; hit ratio {:.1}% ({} hits, {} misses)\n",
+        stats.disk_entries,
+        stats.memory_entries,
+        stats.hit_ratio * 100.0,
+        stats.hits,
+        stats.misses
+    )
+}
+
+fn format_last_error(error: Option<&UpstreamError>) -> String {
+    match error {
+        Some(error) => format!("{} ({} request, {})", error.message, error.operation.as_str(), error.at.to_rfc3339()),
+        None => "None since the server started".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::docs_client::cache::DEFAULT_TTL;
+    use crate::docs_client::mock::{MockResponse, MockServer};
+    use crate::docs_client::{DocsConfig, KeyPool, Metrics, SessionUsage};
+
+    #[tokio::test]
+    async fn test_status_reports_probe_keys_and_last_error() {
+        let server = MockServer::start(|req| match req.header("authorization").unwrap_or_default().as_str() {
+            "Bearer fine-key-000001" => MockResponse::json(200, r#"{"results": []}"#).with_header("X-RateLimit-Remaining", "42"),
+            _ => MockResponse::text(401, "Invalid API key"),
+        })
+        .await;
+        let config = DocsConfig { base_url: server.base_url(), ..Default::default() };
+        let client = Client::new(config)
+            .unwrap()
+            .with_keys(Arc::new(KeyPool::new(&["fine-key-000001".to_string()])))
+            .with_metrics(Arc::default())
+            .with_usage(Arc::new(SessionUsage::default()));
+        client.metrics().record_error(crate::docs_client::metrics::Operation::Docs, None, "operation timed out");
+        let cache = DocsCache::new(None, DEFAULT_TTL);
+
+        let result = DocsStatusTool::status_with(&client, &cache, None).await;
+        let text = &result.content[0].as_text().unwrap().text;
+        assert!(text.contains("Reachable, answered in"), "{}", text);
+        assert!(text.contains("- fine…0001: active (1 request)"), "{}", text);
+        assert!(!text.contains("fine-key-000001"), "keys must be masked: {}", text);
+        assert!(text.contains("Rate limit: 42 remaining of ?"), "{}", text);
+        assert!(text.contains("## Last Error\n\noperation timed out (docs request"), "{}", text);
+
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["reachability"]["status"], "ok");
+        assert_eq!(structured["rate_limit"]["remaining"], 42);
+        assert_eq!(structured["cache"]["disk_entries"], 0);
+
+        // A rejected key: the API answers, the probe fails
+        let client = client.with_keys(Arc::new(KeyPool::new(&["typo-key-000002".to_string()]))).with_metrics(Arc::new(Metrics::default()));
+        let result = DocsStatusTool::status_with(&client, &cache, None).await;
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["reachability"]["status"], "error");
+        assert_eq!(structured["last_error"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_status_skips_the_probe_offline() {
+        let client = Client::new(DocsConfig { base_url: "http://127.0.0.1:9".to_string(), ..Default::default() }).unwrap();
+        let offline = OfflineMode::resolve_with(Some(true), None, None).check("docs status probe").unwrap_err();
+        let result = DocsStatusTool::status_with(&client, &DocsCache::new(None, DEFAULT_TTL), Some(offline)).await;
+        let text = &result.content[0].as_text().unwrap().text;
+        assert!(text.contains("Not probed:"), "{}", text);
+        assert!(text.contains("None configured, requests use the free rate limit"), "{}", text);
+    }
+}
@@ -1,80 +1,110 @@
+use rmcp::model::{ErrorData as McpError, JsonObject};
 use serde::{Deserialize, Serialize};
 
-/// Docs 查询请求参数
-#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
-pub struct DocsRequest {
-    /// 库标识符，格式: owner/repo (例如: "vercel/next.js", "facebook/react")
-    #[schemars(description = "库标识符，格式: owner/repo (例如: vercel/next.js, facebook/react)")]
-    pub library: String,
-    /// 查询主题 (可选，例如: "routing", "authentication")
-    #[schemars(description = "查询主题 (可选，例如: routing, authentication)")]
+use crate::mcp::boundary::parse_arguments;
+use crate::utils::locale::Locale;
+
+// 上游 API 类型定义在 docs_client 中，此处保留原有导入路径
+pub use crate::docs_client::types::*;
+
+/// docs 工具请求参数：上游查询参数之外，还有只在 MCP 侧使用的缓存管理、文档来源、保存与回复选项
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocsToolRequest {
+    /// 发往上游的查询参数，与其余参数位于同一层，由 [`DocsToolRequest::from_arguments`] 单独解析
+    #[serde(skip, default = "empty_query")]
+    pub query: DocsRequest,
+    /// 缓存管理操作 (可选)，设置后不查询文档
     #[serde(default)]
-    pub topic: Option<String>,
-    /// 版本号 (可选，例如: "v15.1.8")
-    #[schemars(description = "版本号 (可选，例如: v15.1.8)")]
+    pub cache: Option<CacheAction>,
+    /// 项目根目录 (可选)，save_to 相对于它解析
     #[serde(default)]
-    pub version: Option<String>,
-    /// 分页页码 (可选，默认1，最大10)
-    #[schemars(description = "分页页码 (可选，默认1，最大10)")]
+    pub project_root_path: Option<String>,
+    /// 保存路径 (可选)，相对项目根目录；把格式化后的 Markdown 连同 front matter 写入该文件
     #[serde(default)]
-    pub page: Option<u32>,
-}
-
-/// Docs 配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DocsConfig {
-    /// API 密钥 (可选，免费使用时可为空)
-    pub api_key: Option<String>,
-    /// API 基础 URL
-    pub base_url: String,
+    pub save_to: Option<String>,
+    /// save_to 已存在时是否覆盖 (可选，默认 false)
+    #[serde(default)]
+    pub overwrite: Option<bool>,
+    /// 查询的文档来源 (可选，默认只查 library)；包含 project 时需要 project_root_path
+    #[serde(default)]
+    pub sources: Option<Vec<DocsSource>>,
+    /// 回复文本的格式 (可选，默认 markdown)；结构化内容不受影响
+    #[serde(default)]
+    pub format: Option<OutputFormat>,
+    /// 回复中标题、提示与错误信息的语言 (可选)，优先于配置 language 与客户端声明的语言
+    #[serde(default)]
+    pub language: Option<Locale>,
 }
 
-pub fn docs_website_url() -> String {
-    format!("https://{}{}", "context", "7.com")
+fn empty_query() -> DocsRequest {
+    DocsRequest::new("")
 }
 
-pub fn docs_api_base_url() -> String {
-    format!("{}/api/v2", docs_website_url())
+impl DocsToolRequest {
+    /// 解析工具参数；查询参数与其余参数分两次解析，出错时仍能指出是哪个参数
+    pub fn from_arguments(tool: &str, arguments: Option<JsonObject>) -> Result<Self, McpError> {
+        let query = parse_arguments(tool, arguments.clone())?;
+        Ok(Self { query, ..parse_arguments(tool, arguments)? })
+    }
 }
 
-impl Default for DocsConfig {
-    fn default() -> Self {
+impl From<DocsRequest> for DocsToolRequest {
+    fn from(query: DocsRequest) -> Self {
         Self {
-            api_key: None,
-            base_url: docs_api_base_url(),
+            query,
+            cache: None,
+            project_root_path: None,
+            save_to: None,
+            overwrite: None,
+            sources: None,
+            format: None,
+            language: None,
         }
     }
 }
 
-/// Docs API 响应结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DocsResponse {
-    /// 文档片段列表
-    pub snippets: Vec<DocumentSnippet>,
-    /// 分页信息
-    pub pagination: Option<PaginationInfo>,
+/// 回复文本的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// 带标题、分隔线与提示的 Markdown
+    #[default]
+    Markdown,
+    /// 结构化内容序列化成的 JSON
+    Json,
+    /// 去掉标题、强调、引用与分隔线标记的纯文本
+    Plain,
 }
 
-/// 文档片段
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DocumentSnippet {
-    /// 片段内容 (Markdown 格式)
-    pub content: String,
-    /// 片段标题
-    pub title: Option<String>,
-    /// 相关性分数
-    pub score: Option<f64>,
+/// 文档来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocsSource {
+    /// 上游文档 API 收录的库文档
+    Library,
+    /// 项目自身的 README、docs/ 与文档注释
+    Project,
 }
 
-/// 分页信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PaginationInfo {
-    /// 当前页码
-    pub current_page: u32,
-    /// 总页数
-    pub total_pages: u32,
-    /// 是否有下一页
-    pub has_next: bool,
+impl DocsSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            DocsSource::Library => "library",
+            DocsSource::Project => "project",
+        }
+    }
+}
+
+/// 文档缓存管理操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheAction {
+    /// 条目数、占用空间、命中率、最早条目
+    Stats,
+    /// 清空全部或指定库的缓存
+    Clear,
+    /// 计数清零（缓存命中统计与本次会话的请求计数）
+    Reset,
 }
 
 /// 测试连接响应
@@ -88,29 +118,80 @@ pub struct TestConnectionResponse {
     pub preview: Option<String>,
 }
 
-/// 库搜索响应
+/// docs_search 工具请求参数
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchResponse {
-    /// 搜索结果列表
-    pub results: Vec<SearchResult>,
+pub struct DocsSearchRequest {
+    /// 库名称或关键词
+    pub query: String,
+    /// 返回的最大结果数，默认 10
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// 只保留库名或描述中提到该语言的结果（不区分大小写）
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+/// devkit_stats 工具请求参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocsStatsRequest {
+    /// 为 true 时逐个验证配置的 API 密钥（有效性、过期与剩余额度）
+    #[serde(default)]
+    pub check_keys: Option<bool>,
 }
 
-/// 搜索结果项
+/// docs_compare 工具请求参数
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchResult {
-    /// 库标识符 (格式: /owner/repo)
-    pub id: String,
-    /// 库名称
-    pub title: Option<String>,
-    /// 库描述
-    pub description: Option<String>,
-    /// GitHub stars 数量
-    pub stars: Option<u64>,
-    /// 信任分数 (0-10)
-    #[serde(rename = "trustScore")]
-    pub trust_score: Option<f64>,
-    /// 基准测试分数
-    #[serde(rename = "benchmarkScore")]
-    pub benchmark_score: Option<f64>,
+pub struct DocsCompareRequest {
+    /// 要对比的库（2 到 3 个），如 facebook/react 与 vuejs/docs
+    pub libraries: Vec<String>,
+    /// 对比的主题，如 routing
+    pub topic: String,
+    /// 每个库显示的片段数，默认 3
+    #[serde(default)]
+    pub max_snippets: Option<u32>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TOOL_OPTIONS: [&str; 7] = ["cache", "project_root_path", "save_to", "overwrite", "sources", "format", "language"];
+
+    #[test]
+    fn test_tool_options_stay_off_the_upstream_request() {
+        let request = serde_json::to_value(DocsRequest::new("vercel/next.js")).unwrap();
+        let schema = serde_json::to_value(schemars::schema_for!(DocsRequest)).unwrap();
+        for option in TOOL_OPTIONS {
+            assert!(request.get(option).is_none(), "DocsRequest serializes {}", option);
+            assert!(schema["properties"].get(option).is_none(), "DocsRequest schema has {}", option);
+        }
+    }
+
+    #[test]
+    fn test_from_arguments_splits_query_and_options() {
+        let arguments = json!({
+            "library": "vercel/next.js",
+            "topic": "routing",
+            "save_to": "docs/next.md",
+            "overwrite": true,
+            "sources": ["library", "project"],
+            "format": "plain",
+            "language": "zh"
+        });
+        let request = DocsToolRequest::from_arguments("docs", arguments.as_object().cloned()).unwrap();
+        assert_eq!(request.query.library, "vercel/next.js");
+        assert_eq!(request.query.topic.as_deref(), Some("routing"));
+        assert_eq!(request.save_to.as_deref(), Some("docs/next.md"));
+        assert_eq!(request.overwrite, Some(true));
+        assert_eq!(request.sources, Some(vec![DocsSource::Library, DocsSource::Project]));
+        assert_eq!(request.format, Some(OutputFormat::Plain));
+        assert_eq!(request.language, Some(Locale::Zh));
+
+        // An invalid option or query parameter is still reported by name
+        let error = DocsToolRequest::from_arguments("docs", json!({ "library": "x", "format": "html" }).as_object().cloned()).unwrap_err();
+        assert_eq!(error.data, Some(json!({ "path": "format" })));
+        let error = DocsToolRequest::from_arguments("docs", json!({ "library": "x", "page": "two" }).as_object().cloned()).unwrap_err();
+        assert_eq!(error.data, Some(json!({ "path": "page" })));
+    }
+}
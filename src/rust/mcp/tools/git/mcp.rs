@@ -0,0 +1,265 @@
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError, Tool, ToolAnnotations};
+use serde_json::json;
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use super::repo::{ChangedFile, CommitSummary, GitError, GitRepo, RepoDiff, RepoStatus, MAX_DIFF_BYTES};
+use super::types::{GitContextRequest, GitOperation};
+use crate::mcp::utils::{decode_and_normalize_path, project_path_error, validate_project_path, ToolError};
+
+/// Allowed values of the `limit` parameter
+pub const LOG_LIMIT_RANGE: RangeInclusive<usize> = 1..=100;
+
+/// Commits returned when `limit` is not given
+const DEFAULT_LOG_LIMIT: usize = 10;
+
+/// Status, diff and recent log of the project's git work tree
+pub struct GitContextTool;
+
+impl GitContextTool {
+    pub async fn query(request: GitContextRequest) -> Result<CallToolResult, ToolError> {
+        if let Err(e) = validate_project_path(&request.project_root_path) {
+            return Err(project_path_error(format!(
+                "Path validation failed: {}\nOriginal path: {}",
+                e, request.project_root_path
+            ))
+            .into());
+        }
+        let limit = request.limit.unwrap_or(DEFAULT_LOG_LIMIT);
+        if !LOG_LIMIT_RANGE.contains(&limit) {
+            return Err(McpError::invalid_params(
+                format!("limit must be between {} and {}", LOG_LIMIT_RANGE.start(), LOG_LIMIT_RANGE.end()),
+                Some(json!({ "path": "limit" })),
+            )
+            .into());
+        }
+        if let Some((i, path)) = request.paths.iter().enumerate().find(|(_, path)| !is_project_relative(path)) {
+            return Err(McpError::invalid_params(
+                format!("paths must be relative to the project and stay inside it: {}", path),
+                Some(json!({ "path": format!("paths[{}]", i) })),
+            )
+            .into());
+        }
+
+        let dir = PathBuf::from(decode_and_normalize_path(&request.project_root_path)?);
+        tokio::task::spawn_blocking(move || Self::run(&dir, &request, limit))
+            .await
+            .map_err(|e| anyhow::anyhow!("git task failed: {}", e))?
+    }
+
+    fn run(dir: &Path, request: &GitContextRequest, limit: usize) -> Result<CallToolResult, ToolError> {
+        let repo = GitRepo::open(dir).map_err(|e| match e {
+            GitError::NotWorkTree(_) => ToolError::Rpc(McpError::invalid_params(
+                e.to_string(),
+                Some(json!({ "path": "project_root_path" })),
+            )),
+            other => ToolError::Failed(other.into()),
+        })?;
+
+        let (text, structured) = match request.operation {
+            GitOperation::Status => {
+                let status = repo.status().map_err(anyhow::Error::from)?;
+                (format_status(&status), json!({ "operation": "status", "status": status }))
+            }
+            GitOperation::Diff => {
+                let diff = repo.diff(request.staged, &request.paths, MAX_DIFF_BYTES).map_err(anyhow::Error::from)?;
+                (format_diff(&diff), json!({ "operation": "diff", "paths": request.paths, "diff": diff }))
+            }
+            GitOperation::Log => {
+                let commits = repo.log(limit).map_err(anyhow::Error::from)?;
+                (format_log(&commits), json!({ "operation": "log", "commits": commits }))
+            }
+        };
+        Ok(CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(structured),
+        })
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "project_root_path": {
+                    "type": "string",
+                    "description": "Absolute path to the project root directory; must be inside a git work tree"
+                },
+                "operation": {
+                    "type": "string",
+                    "enum": ["status", "diff", "log"],
+                    "description": "status: branch, upstream and changed files; diff: unified diff of changes; log: recent commit subjects"
+                },
+                "staged": {
+                    "type": "boolean",
+                    "description": "diff only: show staged changes instead of unstaged ones"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "diff only: limit to these paths, relative to the project root"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": LOG_LIMIT_RANGE.start(),
+                    "maximum": LOG_LIMIT_RANGE.end(),
+                    "description": "log only: number of commits, defaults to 10"
+                }
+            },
+            "required": ["project_root_path", "operation"]
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("git_context"),
+                description: Some(Cow::Borrowed("Read the project's git state: status (branch, ahead/behind, changed files), diff (size-capped) or recent commit subjects. Use instead of asking the user to paste git output.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Git Context".to_string()),
+                    read_only_hint: Some(true),
+                    destructive_hint: Some(false),
+                    idempotent_hint: Some(true),
+                    open_world_hint: Some(false),
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Git Context".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+/// Relative, no `..`, and no pathspec magic such as `:(top)`
+fn is_project_relative(path: &str) -> bool {
+    !path.is_empty()
+        && !path.starts_with(':')
+        && Path::new(path).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+pub(crate) fn format_status(status: &RepoStatus) -> String {
+    let mut text = String::from("# Git status\n\n");
+    let branch = status.branch.as_deref().unwrap_or("");
+    match (status.detached, status.has_commits) {
+        (true, _) => text.push_str(&format!("HEAD detached at `{}`", status.head.as_deref().unwrap_or("?"))),
+        (false, false) => text.push_str(&format!("Branch `{}` (no commits yet)", branch)),
+        (false, true) => text.push_str(&format!("Branch `{}` at `{}`", branch, status.head.as_deref().unwrap_or("?"))),
+    }
+    if let Some(upstream) = &status.upstream {
+        text.push_str(&format!(
+            ", tracking `{}` (ahead {}, behind {})",
+            upstream,
+            status.ahead.unwrap_or(0),
+            status.behind.unwrap_or(0)
+        ));
+    }
+    text.push('\n');
+
+    if status.files.is_empty() {
+        text.push_str("\nWorking tree clean.\n");
+        return text;
+    }
+    let describe = |f: &ChangedFile| match &f.orig_path {
+        Some(orig) => format!("- {} `{}` → `{}`\n", f.kind, orig, f.path),
+        None => format!("- {} `{}`\n", f.kind, f.path),
+    };
+    type Group = (&'static str, fn(&ChangedFile) -> bool);
+    let groups: [Group; 4] = [
+        ("Conflicts", |f| f.kind == "conflicted"),
+        ("Staged", ChangedFile::staged),
+        ("Unstaged", ChangedFile::unstaged),
+        ("Untracked", |f| f.kind == "untracked"),
+    ];
+    for (title, belongs) in groups {
+        let lines: String = status.files.iter().filter(|f| belongs(f)).map(describe).collect();
+        if !lines.is_empty() {
+            text.push_str(&format!("\n## {}\n{}", title, lines));
+        }
+    }
+    if status.truncated {
+        text.push_str("\n_More changed files not listed._\n");
+    }
+    text
+}
+
+pub(crate) fn format_diff(diff: &RepoDiff) -> String {
+    let kind = if diff.staged { "staged" } else { "unstaged" };
+    if diff.files.is_empty() {
+        return format!("# Git diff ({})\n\nNo changes.\n", kind);
+    }
+    let added: u32 = diff.files.iter().filter_map(|f| f.additions).sum();
+    let deleted: u32 = diff.files.iter().filter_map(|f| f.deletions).sum();
+    let mut text = format!("# Git diff ({})\n\n{} files, +{} -{}\n\n```diff\n{}", kind, diff.files.len(), added, deleted, diff.text);
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str("```\n");
+    if diff.truncated {
+        text.push_str(&format!(
+            "\n_Diff truncated: showing {} of {} bytes. Pass `paths` to narrow it down._\n",
+            diff.text.len(),
+            diff.total_bytes
+        ));
+    }
+    text
+}
+
+pub(crate) fn format_log(commits: &[CommitSummary]) -> String {
+    if commits.is_empty() {
+        return "# Recent commits\n\nNo commits yet.\n".to_string();
+    }
+    let mut text = String::from("# Recent commits\n\n");
+    for commit in commits {
+        text.push_str(&format!("- `{}` {}\n", commit.hash, commit.subject));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use crate::mcp::tools::git::repo::tests::{commit, temp_repo};
+
+    fn request(dir: &Path, operation: GitOperation) -> GitContextRequest {
+        GitContextRequest {
+            project_root_path: dir.to_string_lossy().into_owned(),
+            operation,
+            staged: false,
+            paths: Vec::new(),
+            limit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_outputs() {
+        let dir = temp_repo();
+        let result = GitContextTool::query(request(&dir, GitOperation::Log)).await.unwrap();
+        assert_eq!(result.structured_content.unwrap()["commits"], json!([]));
+
+        commit(&dir, "a.txt", "one\n", "first");
+        std::fs::write(dir.join("a.txt"), "two\n").unwrap();
+        let result = GitContextTool::query(request(&dir, GitOperation::Status)).await.unwrap();
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["status"]["files"][0]["path"], "a.txt");
+        assert_eq!(structured["status"]["branch"], "main");
+
+        let mut bad = request(&dir, GitOperation::Diff);
+        bad.paths = vec!["../outside".to_string()];
+        match GitContextTool::query(bad).await {
+            Err(ToolError::Rpc(err)) => assert_eq!(err.data, Some(json!({ "path": "paths[0]" }))),
+            other => panic!("expected invalid params, got {:?}", other.map(|r| r.content)),
+        }
+
+        let outside = TestDir::new("not-git");
+        match GitContextTool::query(request(&outside, GitOperation::Status)).await {
+            Err(ToolError::Rpc(err)) => assert!(err.message.contains("not inside a git work tree")),
+            other => panic!("expected invalid params, got {:?}", other.map(|r| r.content)),
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! git 上下文工具
+//!
+//! 直接调用 git 可执行文件，为项目路径提供 status、diff 与最近提交记录
+
+pub mod mcp;
+pub mod repo;
+pub mod types;
+
+pub use mcp::GitContextTool;
+pub use repo::{GitError, GitRepo};
+pub use types::{GitContextRequest, GitOperation};
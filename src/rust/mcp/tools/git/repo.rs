@@ -0,0 +1,364 @@
+//! Read-only queries against a git work tree through the `git` executable
+//!
+//! Porcelain formats with `-z` are parsed so that paths with spaces, quotes or
+//! newlines come through unchanged. Unborn branches (no commits yet) and
+//! detached HEAD are reported rather than treated as errors.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+/// Files listed by a status query
+pub const MAX_STATUS_FILES: usize = 500;
+/// Default size cap of diff text
+pub const MAX_DIFF_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Error)]
+pub enum GitError {
+    #[error("Failed to run git: {0}")]
+    Unavailable(std::io::Error),
+    #[error("{0} is not inside a git work tree")]
+    NotWorkTree(String),
+    #[error("git {command} failed: {stderr}")]
+    Failed { command: String, stderr: String },
+}
+
+/// One changed path, with porcelain v2 index/work tree status letters (`.` = unchanged)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangedFile {
+    pub path: String,
+    /// Source path of a rename or copy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_path: Option<String>,
+    pub index: char,
+    pub worktree: char,
+    /// modified, added, deleted, renamed, copied, type_changed, untracked or conflicted
+    pub kind: &'static str,
+}
+
+impl ChangedFile {
+    pub fn staged(&self) -> bool {
+        self.kind != "untracked" && self.kind != "conflicted" && self.index != '.'
+    }
+
+    pub fn unstaged(&self) -> bool {
+        self.kind != "untracked" && self.kind != "conflicted" && self.worktree != '.'
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RepoStatus {
+    /// Branch name, `None` when HEAD is detached
+    pub branch: Option<String>,
+    pub detached: bool,
+    /// Abbreviated HEAD commit, `None` before the first commit
+    pub head: Option<String>,
+    pub has_commits: bool,
+    pub upstream: Option<String>,
+    pub ahead: Option<u32>,
+    pub behind: Option<u32>,
+    pub files: Vec<ChangedFile>,
+    /// Whether more than MAX_STATUS_FILES paths changed
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffFileStat {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_path: Option<String>,
+    /// `None` for binary files
+    pub additions: Option<u32>,
+    pub deletions: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RepoDiff {
+    pub staged: bool,
+    pub files: Vec<DiffFileStat>,
+    pub text: String,
+    pub total_bytes: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitSummary {
+    pub hash: String,
+    pub subject: String,
+}
+
+/// A directory inside a git work tree
+#[derive(Debug, Clone)]
+pub struct GitRepo {
+    dir: PathBuf,
+}
+
+impl GitRepo {
+    /// Fails with NotWorkTree outside a work tree (including inside `.git` and bare repositories)
+    pub fn open(dir: &Path) -> Result<Self, GitError> {
+        let repo = Self { dir: dir.to_path_buf() };
+        match repo.run(&["rev-parse", "--is-inside-work-tree"]) {
+            Ok(out) if out.trim() == "true" => Ok(repo),
+            Ok(_) | Err(GitError::Failed { .. }) => Err(GitError::NotWorkTree(dir.display().to_string())),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn status(&self) -> Result<RepoStatus, GitError> {
+        let out = self.run(&["status", "--porcelain=v2", "--branch", "-z", "--untracked-files=normal"])?;
+        Ok(parse_status(&out))
+    }
+
+    /// Work tree or staged changes, optionally limited to `paths` (relative to the project)
+    pub fn diff(&self, staged: bool, paths: &[String], max_bytes: usize) -> Result<RepoDiff, GitError> {
+        let mut base = vec!["diff", "--no-color", "--no-ext-diff", "-M"];
+        if staged {
+            base.push("--cached");
+        }
+        let with_paths = |extra: &[&'static str]| {
+            let mut args: Vec<&str> = base.clone();
+            args.extend_from_slice(extra);
+            args.push("--");
+            args.extend(paths.iter().map(|p| p.as_str()));
+            args
+        };
+
+        let numstat = self.run(&with_paths(&["--numstat", "-z"]))?;
+        let mut text = self.run(&with_paths(&[]))?;
+        let total_bytes = text.len();
+        let truncated = total_bytes > max_bytes;
+        if truncated {
+            let cut = crate::utils::text::prefix_bytes(&text, max_bytes).len();
+            // End on a whole line when there is one
+            let cut = text[..cut].rfind('\n').map_or(cut, |i| i + 1);
+            text.truncate(cut);
+        }
+        Ok(RepoDiff { staged, files: parse_numstat(&numstat), text, total_bytes, truncated })
+    }
+
+    /// Subjects of the last `limit` commits, newest first; empty before the first commit
+    pub fn log(&self, limit: usize) -> Result<Vec<CommitSummary>, GitError> {
+        if self.run(&["rev-parse", "--verify", "-q", "HEAD"]).is_err() {
+            return Ok(Vec::new());
+        }
+        let count = format!("-n{}", limit);
+        let out = self.run(&["log", &count, "--format=%h%x1f%s"])?;
+        Ok(out
+            .lines()
+            .filter_map(|line| line.split_once('\x1f'))
+            .map(|(hash, subject)| CommitSummary { hash: hash.to_string(), subject: subject.to_string() })
+            .collect())
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, GitError> {
+        let output = Command::new("git")
+            .args(["-c", "core.quotepath=false", "--no-pager"])
+            .args(args)
+            .current_dir(&self.dir)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("GIT_OPTIONAL_LOCKS", "0")
+            .env("LC_ALL", "C")
+            .output()
+            .map_err(GitError::Unavailable)?;
+        if !output.status.success() {
+            return Err(GitError::Failed {
+                command: args.first().copied().unwrap_or_default().to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Parse `git status --porcelain=v2 --branch -z`
+pub fn parse_status(out: &str) -> RepoStatus {
+    let mut status = RepoStatus::default();
+    let mut records = out.split('\0').filter(|r| !r.is_empty());
+
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => {
+                    status.has_commits = true;
+                    status.head = Some(value.chars().take(10).collect());
+                }
+                "branch.head" if value == "(detached)" => status.detached = true,
+                "branch.head" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    let mut counts = value.split(' ').map(|n| n.trim_start_matches(['+', '-']).parse().ok());
+                    status.ahead = counts.next().flatten();
+                    status.behind = counts.next().flatten();
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let file = match record.as_bytes().first() {
+            Some(b'1') => fields(record, 9).and_then(|f| changed(f[1], f[8], None)),
+            Some(b'2') => {
+                let orig = records.next().map(|s| s.to_string());
+                fields(record, 10).and_then(|f| changed(f[1], f[9], orig))
+            }
+            Some(b'u') => record.splitn(11, ' ').nth(10).map(|path| ChangedFile {
+                path: path.to_string(),
+                orig_path: None,
+                index: record.chars().nth(2).unwrap_or('U'),
+                worktree: record.chars().nth(3).unwrap_or('U'),
+                kind: "conflicted",
+            }),
+            Some(b'?') => record.get(2..).map(|path| ChangedFile {
+                path: path.to_string(),
+                orig_path: None,
+                index: '?',
+                worktree: '?',
+                kind: "untracked",
+            }),
+            _ => None,
+        };
+        if let Some(file) = file {
+            if status.files.len() < MAX_STATUS_FILES {
+                status.files.push(file);
+            } else {
+                status.truncated = true;
+            }
+        }
+    }
+    status
+}
+
+/// Exactly `n` space-separated fields, the last one being the (possibly spaced) path
+fn fields(record: &str, n: usize) -> Option<Vec<&str>> {
+    Some(record.splitn(n, ' ').collect::<Vec<_>>()).filter(|f| f.len() == n)
+}
+
+/// Ordinary or rename entry from its `XY` field and path
+fn changed(xy: &str, path: &str, orig_path: Option<String>) -> Option<ChangedFile> {
+    let mut letters = xy.chars();
+    let (index, worktree) = (letters.next()?, letters.next()?);
+    let kind = match (index, worktree) {
+        ('R', _) => "renamed",
+        ('C', _) => "copied",
+        ('A', _) => "added",
+        ('D', _) | (_, 'D') => "deleted",
+        ('T', _) | (_, 'T') => "type_changed",
+        _ => "modified",
+    };
+    Some(ChangedFile { path: path.to_string(), orig_path, index, worktree, kind })
+}
+
+/// Parse `git diff --numstat -z`; renames carry an empty path followed by old and new paths
+pub fn parse_numstat(out: &str) -> Vec<DiffFileStat> {
+    let mut files = Vec::new();
+    let mut records = out.split('\0');
+    while let Some(record) = records.next() {
+        let mut fields = record.splitn(3, '\t');
+        let (Some(added), Some(deleted), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let (path, orig_path) = if path.is_empty() {
+            let orig = records.next().unwrap_or_default().to_string();
+            (records.next().unwrap_or_default().to_string(), Some(orig))
+        } else {
+            (path.to_string(), None)
+        };
+        files.push(DiffFileStat { path, orig_path, additions: added.parse().ok(), deletions: deleted.parse().ok() });
+    }
+    files
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::fs;
+    use crate::utils::test_dir::TestDir;
+
+    /// Fresh repository on `main` with a local identity, independent of the user's git config
+    pub(crate) fn temp_repo() -> TestDir {
+        let dir = TestDir::new("git");
+        git(&dir, &["init", "-q", "-b", "main"]);
+        git(&dir, &["config", "user.name", "Devkit Test"]);
+        git(&dir, &["config", "user.email", "test@example.com"]);
+        git(&dir, &["config", "commit.gpgsign", "false"]);
+        dir
+    }
+
+    pub(crate) fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git").args(args).current_dir(dir).status().unwrap();
+        assert!(status.success(), "git {:?}", args);
+    }
+
+    pub(crate) fn commit(dir: &Path, file: &str, content: &str, message: &str) {
+        fs::write(dir.join(file), content).unwrap();
+        git(dir, &["add", file]);
+        git(dir, &["commit", "-q", "-m", message]);
+    }
+
+    #[test]
+    fn test_refuses_outside_work_tree() {
+        let dir = TestDir::new("not-git");
+        assert!(matches!(GitRepo::open(&dir), Err(GitError::NotWorkTree(_))));
+
+        let repo = temp_repo();
+        assert!(matches!(GitRepo::open(&repo.join(".git")), Err(GitError::NotWorkTree(_))));
+    }
+
+    #[test]
+    fn test_repository_without_commits() {
+        let dir = temp_repo();
+        fs::write(dir.join("new file.txt"), "hello\n").unwrap();
+        let repo = GitRepo::open(&dir).unwrap();
+
+        let status = repo.status().unwrap();
+        assert_eq!((status.branch.as_deref(), status.has_commits, status.head.as_deref()), (Some("main"), false, None));
+        assert_eq!(status.files[0].path, "new file.txt");
+        assert_eq!(status.files[0].kind, "untracked");
+        assert!(repo.log(5).unwrap().is_empty());
+
+        git(&dir, &["add", "."]);
+        let diff = repo.diff(true, &[], MAX_DIFF_BYTES).unwrap();
+        assert_eq!(diff.files, [DiffFileStat { path: "new file.txt".into(), orig_path: None, additions: Some(1), deletions: Some(0) }]);
+        assert!(diff.text.contains("+hello"));
+    }
+
+    #[test]
+    fn test_status_diff_and_log() {
+        let dir = temp_repo();
+        commit(&dir, "a.txt", "one\n", "first");
+        commit(&dir, "b.txt", "two\n", "second");
+        git(&dir, &["branch", "base", "HEAD~1"]);
+        git(&dir, &["branch", "-u", "base"]);
+
+        fs::write(dir.join("a.txt"), "one\nmore\n").unwrap();
+        git(&dir, &["mv", "b.txt", "c.txt"]);
+        let repo = GitRepo::open(&dir).unwrap();
+
+        let status = repo.status().unwrap();
+        assert_eq!((status.upstream.as_deref(), status.ahead, status.behind), (Some("base"), Some(1), Some(0)));
+        let renamed = status.files.iter().find(|f| f.kind == "renamed").unwrap();
+        assert_eq!((renamed.path.as_str(), renamed.orig_path.as_deref(), renamed.staged()), ("c.txt", Some("b.txt"), true));
+        let modified = status.files.iter().find(|f| f.path == "a.txt").unwrap();
+        assert!(modified.unstaged() && !modified.staged());
+
+        let unstaged = repo.diff(false, &[], MAX_DIFF_BYTES).unwrap();
+        assert_eq!(unstaged.files.len(), 1);
+        assert!(unstaged.text.contains("+more"));
+        let staged = repo.diff(true, &[], MAX_DIFF_BYTES).unwrap();
+        assert_eq!((staged.files[0].path.as_str(), staged.files[0].orig_path.as_deref()), ("c.txt", Some("b.txt")));
+        assert!(repo.diff(false, &["c.txt".to_string()], MAX_DIFF_BYTES).unwrap().files.is_empty());
+
+        let capped = repo.diff(false, &[], 20).unwrap();
+        assert!(capped.truncated && capped.text.len() <= 20 && capped.total_bytes > 20);
+
+        let log = repo.log(1).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].subject, "second");
+
+        git(&dir, &["checkout", "-q", "-f", "--detach", "HEAD~1"]);
+        let status = repo.status().unwrap();
+        assert!(status.detached && status.branch.is_none() && status.has_commits);
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// git_context 支持的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitOperation {
+    Status,
+    Diff,
+    Log,
+}
+
+/// git_context 工具请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitContextRequest {
+    /// 项目根目录的绝对路径，必须位于 git 工作区内
+    pub project_root_path: String,
+    /// 操作类型
+    pub operation: GitOperation,
+    /// diff：只看已暂存的改动
+    #[serde(default)]
+    pub staged: bool,
+    /// diff：只看这些路径（相对项目根目录）
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// log：返回的提交数，默认 10
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
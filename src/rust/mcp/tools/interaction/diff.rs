@@ -0,0 +1,379 @@
+//! 弹窗中展示的统一 diff 模型
+//!
+//! 在 MCP 端把 unified diff 文本解析为文件、hunk 和行，前端只负责着色和折叠。
+//! 超过行数上限时只保留前面的行，统计数字仍覆盖完整 diff；无法解析时保留原文按等宽文本显示。
+
+use serde::{Deserialize, Serialize};
+
+/// 默认最多展示的 diff 行数（可通过 mcp_config.popup_diff_max_lines 调整）
+pub const DEFAULT_DIFF_MAX_LINES: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+    /// `\ No newline at end of file`
+    NoNewline,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    /// 去掉 `+`/`-`/空格前缀后的内容
+    pub text: String,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    /// 完整的 `@@ -a,b +c,d @@ ...` 行
+    pub header: String,
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffFile {
+    /// `/dev/null`（新建文件）时为空
+    pub old_path: Option<String>,
+    /// `/dev/null`（删除文件）时为空
+    pub new_path: Option<String>,
+    pub hunks: Vec<DiffHunk>,
+    pub additions: usize,
+    pub deletions: usize,
+    /// 超出行数上限，部分或全部 hunk 未展示
+    pub truncated: bool,
+}
+
+/// 解析结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffView {
+    pub files: Vec<DiffFile>,
+    pub additions: usize,
+    pub deletions: usize,
+    /// diff 中的内容行总数（上下文、新增、删除）
+    pub total_lines: usize,
+    /// 实际展示的内容行数，小于 total_lines 表示被截断
+    pub shown_lines: usize,
+    /// 无法解析时的原文（同样受行数上限约束），此时 files 为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plain: Option<String>,
+}
+
+impl DiffView {
+    pub fn is_truncated(&self) -> bool {
+        self.shown_lines < self.total_lines
+    }
+
+    /// 弹窗请求中的 diff：空文本视为没有，行数上限取自配置
+    pub fn for_popup(text: Option<&str>) -> Option<Self> {
+        let text = text.filter(|t| !t.trim().is_empty())?;
+        let max_lines = crate::config::load_standalone_config()
+            .ok()
+            .and_then(|config| config.mcp_config.popup_diff_max_lines)
+            .unwrap_or(DEFAULT_DIFF_MAX_LINES);
+        Some(Self::parse(text, max_lines))
+    }
+
+    /// 解析 unified diff，最多保留 `max_lines` 行内容
+    pub fn parse(text: &str, max_lines: usize) -> Self {
+        match parse_files(text) {
+            Some(files) => Self::from_files(files, max_lines),
+            None => Self::plain(text, max_lines),
+        }
+    }
+
+    fn from_files(mut files: Vec<DiffFile>, max_lines: usize) -> Self {
+        let mut view = DiffView::default();
+        let mut budget = max_lines;
+
+        for file in files.iter_mut() {
+            view.additions += file.additions;
+            view.deletions += file.deletions;
+            let mut kept = Vec::new();
+            for mut hunk in std::mem::take(&mut file.hunks) {
+                let content_lines = hunk.lines.iter().filter(|l| l.kind != DiffLineKind::NoNewline).count();
+                view.total_lines += content_lines;
+                if budget == 0 {
+                    file.truncated = true;
+                    continue;
+                }
+                if content_lines > budget {
+                    // 截到第 budget 行内容为止，附带紧随其后的无换行标记
+                    let mut seen = 0;
+                    hunk.lines.retain(|l| {
+                        if l.kind != DiffLineKind::NoNewline {
+                            seen += 1;
+                        }
+                        seen <= budget
+                    });
+                    file.truncated = true;
+                    view.shown_lines += budget;
+                    budget = 0;
+                } else {
+                    view.shown_lines += content_lines;
+                    budget -= content_lines;
+                }
+                kept.push(hunk);
+            }
+            file.hunks = kept;
+        }
+
+        view.files = files;
+        view
+    }
+
+    fn plain(text: &str, max_lines: usize) -> Self {
+        let lines: Vec<&str> = text.lines().collect();
+        let shown = lines.len().min(max_lines);
+        DiffView {
+            total_lines: lines.len(),
+            shown_lines: shown,
+            plain: Some(lines[..shown].join("\n")),
+            ..Default::default()
+        }
+    }
+}
+
+/// `a/src/x.rs` -> `src/x.rs`，`/dev/null` -> None；去掉 git 附加的时间戳
+fn parse_path(raw: &str) -> Option<String> {
+    let raw = raw.split('\t').next().unwrap_or(raw).trim();
+    if raw == "/dev/null" {
+        return None;
+    }
+    let path = raw.strip_prefix("a/").or_else(|| raw.strip_prefix("b/")).unwrap_or(raw);
+    Some(path.to_string())
+}
+
+/// `@@ -12,5 +12,7 @@ fn main()` 中的行号范围
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, _) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |r: &str| -> Option<(usize, usize)> {
+        match r.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((r.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(old)?;
+    let (new_start, new_count) = range(new)?;
+    Some((old_start, old_count, new_start, new_count))
+}
+
+/// 解析出所有文件；不是合法 unified diff 时返回 None
+fn parse_files(text: &str) -> Option<Vec<DiffFile>> {
+    let mut files: Vec<DiffFile> = Vec::new();
+    // 当前 hunk 还剩多少旧/新行未读
+    let mut remaining = (0usize, 0usize);
+    let mut line_no = (0usize, 0usize);
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let in_hunk = remaining != (0, 0);
+
+        if in_hunk {
+            let file = files.last_mut()?;
+            let hunk = file.hunks.last_mut()?;
+            let (kind, body) = match line.chars().next() {
+                Some('+') => (DiffLineKind::Added, &line[1..]),
+                Some('-') => (DiffLineKind::Removed, &line[1..]),
+                Some(' ') => (DiffLineKind::Context, &line[1..]),
+                // 部分工具会去掉空上下文行末尾的空格
+                None => (DiffLineKind::Context, ""),
+                Some('\\') => {
+                    hunk.lines.push(DiffLine { kind: DiffLineKind::NoNewline, text: line.to_string(), old_line: None, new_line: None });
+                    continue;
+                }
+                Some(_) => return None,
+            };
+            let (old_line, new_line) = match kind {
+                DiffLineKind::Added => {
+                    remaining.1 = remaining.1.checked_sub(1)?;
+                    file.additions += 1;
+                    line_no.1 += 1;
+                    (None, Some(line_no.1 - 1))
+                }
+                DiffLineKind::Removed => {
+                    remaining.0 = remaining.0.checked_sub(1)?;
+                    file.deletions += 1;
+                    line_no.0 += 1;
+                    (Some(line_no.0 - 1), None)
+                }
+                _ => {
+                    remaining = (remaining.0.checked_sub(1)?, remaining.1.checked_sub(1)?);
+                    line_no = (line_no.0 + 1, line_no.1 + 1);
+                    (Some(line_no.0 - 1), Some(line_no.1 - 1))
+                }
+            };
+            hunk.lines.push(DiffLine { kind, text: body.to_string(), old_line, new_line });
+            continue;
+        }
+
+        if line.starts_with('\\') {
+            // 紧跟在 hunk 最后一行之后的无换行标记
+            if let Some(hunk) = files.last_mut().and_then(|f| f.hunks.last_mut()) {
+                hunk.lines.push(DiffLine { kind: DiffLineKind::NoNewline, text: line.to_string(), old_line: None, new_line: None });
+            }
+        } else if line.starts_with("@@") {
+            let (old_start, old_count, new_start, new_count) = parse_hunk_header(line)?;
+            if files.is_empty() {
+                // 没有文件头的裸 hunk
+                files.push(DiffFile::default());
+            }
+            files.last_mut()?.hunks.push(DiffHunk {
+                header: line.to_string(),
+                old_start,
+                old_count,
+                new_start,
+                new_count,
+                lines: Vec::new(),
+            });
+            remaining = (old_count, new_count);
+            line_no = (old_start, new_start);
+        } else if let Some(rest) = line.strip_prefix("diff --git ") {
+            let mut paths = rest.split_whitespace();
+            files.push(DiffFile {
+                old_path: paths.next().and_then(parse_path),
+                new_path: paths.next().and_then(parse_path),
+                ..Default::default()
+            });
+        } else if let Some(old) = line.strip_prefix("--- ") {
+            let new = lines.next_if(|next| next.starts_with("+++ "))?;
+            // `diff --git` 已经建好文件且还没有 hunk 时沿用，否则是新文件
+            let reuse = files.last().is_some_and(|f| f.hunks.is_empty());
+            if !reuse {
+                files.push(DiffFile::default());
+            }
+            let file = files.last_mut()?;
+            file.old_path = parse_path(old);
+            file.new_path = parse_path(&new[4..]);
+        } else if line.starts_with('+') || line.starts_with('-') {
+            // hunk 之外出现的改动行：行数与 hunk 头不符
+            return None;
+        }
+        // 其余行（index、mode、Binary files ...、提交说明）作为文件头忽略
+    }
+
+    if remaining != (0, 0) || !files.iter().any(|f| !f.hunks.is_empty()) {
+        return None;
+    }
+    Some(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a9c2f10 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,5 @@
+ use std::fs;
+-use std::io;
++use std::io::{self, Read};
++use std::path::Path;
+
+ fn main() {
+@@ -20,2 +21,2 @@ fn helper() {
+     let a = 1;
+-    let b = 2;
++    let b = 3;
+\\ No newline at end of file
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1,2 @@
++hello
++world
+";
+
+    #[test]
+    fn test_parses_files_hunks_and_counts() {
+        let view = DiffView::parse(SAMPLE, DEFAULT_DIFF_MAX_LINES);
+        assert!(view.plain.is_none());
+        assert_eq!(view.files.len(), 2);
+        assert_eq!((view.additions, view.deletions), (5, 2));
+        assert_eq!(view.total_lines, 11);
+        assert!(!view.is_truncated());
+
+        let lib = &view.files[0];
+        assert_eq!(lib.old_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(lib.hunks.len(), 2);
+        assert_eq!((lib.additions, lib.deletions), (3, 2));
+        let first = &lib.hunks[0];
+        assert_eq!((first.old_start, first.old_count, first.new_start, first.new_count), (1, 4, 1, 5));
+        assert_eq!(first.lines[1].kind, DiffLineKind::Removed);
+        assert_eq!(first.lines[1].text, "use std::io;");
+        assert_eq!((first.lines[1].old_line, first.lines[1].new_line), (Some(2), None));
+        assert_eq!((first.lines[3].old_line, first.lines[3].new_line), (None, Some(3)));
+        // 空上下文行
+        assert_eq!(first.lines[4].kind, DiffLineKind::Context);
+        assert_eq!((first.lines[4].old_line, first.lines[4].new_line), (Some(3), Some(4)));
+        assert_eq!(lib.hunks[1].header, "@@ -20,2 +21,2 @@ fn helper() {");
+        assert_eq!(lib.hunks[1].lines.last().unwrap().kind, DiffLineKind::NoNewline);
+
+        let new = &view.files[1];
+        assert_eq!(new.old_path, None);
+        assert_eq!(new.new_path.as_deref(), Some("new.txt"));
+        assert_eq!(new.hunks[0].lines[1].new_line, Some(2));
+    }
+
+    #[test]
+    fn test_plain_diff_without_git_header() {
+        let text = "--- a.txt\t2024-01-01\n+++ b.txt\t2024-01-02\n@@ -1 +1 @@\n-old\n+new\n";
+        let view = DiffView::parse(text, 10);
+        assert_eq!(view.files.len(), 1);
+        assert_eq!(view.files[0].old_path.as_deref(), Some("a.txt"));
+        assert_eq!((view.additions, view.deletions), (1, 1));
+    }
+
+    #[test]
+    fn test_truncation_keeps_counts() {
+        let view = DiffView::parse(SAMPLE, 7);
+        assert!(view.is_truncated());
+        assert_eq!((view.shown_lines, view.total_lines), (7, 11));
+        // 统计仍覆盖完整 diff
+        assert_eq!((view.additions, view.deletions), (5, 2));
+
+        let lib = &view.files[0];
+        assert!(lib.truncated);
+        assert_eq!(lib.hunks.len(), 2);
+        assert_eq!(lib.hunks[1].lines.len(), 1);
+        assert!(view.files[1].truncated);
+        assert!(view.files[1].hunks.is_empty());
+        assert_eq!(view.files[1].additions, 2);
+
+        let exact = DiffView::parse(SAMPLE, 11);
+        assert!(!exact.is_truncated());
+        assert!(exact.files.iter().all(|f| !f.truncated));
+    }
+
+    #[test]
+    fn test_malformed_falls_back_to_plain() {
+        for text in [
+            "just some text\nwithout hunks",
+            "@@ -1,2 +1,2 @@\n-a\n+b\n",
+            "@@ -x +1 @@\n+a\n",
+            "--- a\n+++ b\n@@ -1 +1 @@\n-a\n+b\n+extra\n",
+        ] {
+            let view = DiffView::parse(text, 100);
+            assert!(view.files.is_empty(), "{:?}", text);
+            assert_eq!(view.plain.as_deref(), Some(text.trim_end()));
+        }
+
+        let long: String = (0..50).map(|i| format!("line {}\n", i)).collect();
+        let view = DiffView::parse(&long, 10);
+        assert_eq!((view.shown_lines, view.total_lines), (10, 50));
+        assert_eq!(view.plain.unwrap().lines().count(), 10);
+    }
+}
@@ -0,0 +1,43 @@
+use rmcp::model::{ErrorData as McpError, CallToolResult, Content};
+
+use crate::log_important;
+use crate::mcp::CacheRequest;
+
+/// Message returned by every interaction entry point in headless builds
+const HEADLESS_MESSAGE: &str =
+    "Interactive UI is not available: this build was compiled without the `gui` feature.";
+
+/// Headless fallback for the interaction tool
+///
+/// Same API as the GUI implementation, but there is no popup to launch, so every
+/// call returns an error result the client can show to the user.
+#[derive(Clone)]
+pub struct InteractionTool;
+
+impl InteractionTool {
+    pub async fn prompt_start(request: CacheRequest) -> Result<CallToolResult, McpError> {
+        Ok(Self::unavailable(&request.message))
+    }
+
+    pub async fn prompt_sync(request: CacheRequest) -> Result<CallToolResult, McpError> {
+        Ok(Self::unavailable(&request.message))
+    }
+
+    pub async fn cache_get(_task_id: String) -> Result<CallToolResult, McpError> {
+        Ok(Self::unavailable(""))
+    }
+
+    pub async fn prompt_blocking(request: CacheRequest) -> Result<CallToolResult, McpError> {
+        Ok(Self::unavailable(&request.message))
+    }
+
+    fn unavailable(message: &str) -> CallToolResult {
+        log_important!(warn, "Interaction requested in headless build, message length {}", message.len());
+        CallToolResult {
+            content: vec![Content::text(HEADLESS_MESSAGE)],
+            is_error: Some(true),
+            meta: None,
+            structured_content: None,
+        }
+    }
+}
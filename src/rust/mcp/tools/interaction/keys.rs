@@ -0,0 +1,265 @@
+//! 弹窗键盘操作映射
+//!
+//! 把按键事件映射为弹窗动作，不涉及任何界面状态的修改。
+//! 前端 `usePopupKeys.ts` 按同一张规则表实现；终端降级（`devkit prompt`）
+//! 按行读取输入，使用下面的 [`map_line`]。
+
+use std::collections::HashMap;
+
+use crate::config::{ShortcutBinding, ShortcutKey};
+
+/// 弹窗动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopupAction {
+    Submit,
+    Enhance,
+    Continue,
+    /// 切换第 n 个预定义选项（从 0 开始）
+    ToggleOption(usize),
+    FocusNext,
+    FocusPrevious,
+    /// 请求取消，需再次确认
+    RequestCancel,
+    ConfirmCancel,
+    DismissCancel,
+    ToggleHelp,
+}
+
+/// 映射按键时需要的弹窗状态
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyContext {
+    pub option_count: usize,
+    /// 焦点在文本框内，此时数字和 `?` 作为普通输入
+    pub editing_text: bool,
+    pub confirming_cancel: bool,
+    pub help_open: bool,
+    pub is_mac: bool,
+}
+
+/// 把按键事件映射为弹窗动作，不处理的按键返回 None
+pub fn map_key(
+    event: &ShortcutKey,
+    ctx: &KeyContext,
+    shortcuts: &HashMap<String, ShortcutBinding>,
+) -> Option<PopupAction> {
+    let plain = !event.ctrl && !event.alt && !event.meta;
+
+    // 确认取消时只接受确认或返回
+    if ctx.confirming_cancel {
+        return match event.key.as_str() {
+            "Enter" | "y" | "Y" if plain => Some(PopupAction::ConfirmCancel),
+            "Escape" | "n" | "N" if plain => Some(PopupAction::DismissCancel),
+            _ => None,
+        };
+    }
+
+    if ctx.help_open {
+        return match event.key.as_str() {
+            "Escape" | "?" if plain => Some(PopupAction::ToggleHelp),
+            _ => None,
+        };
+    }
+
+    let configured = shortcuts
+        .values()
+        .filter(|binding| binding.enabled && binding.scope == "popup")
+        .find(|binding| matches_binding(&binding.key_combination, event, ctx.is_mac))
+        .and_then(|binding| match binding.action.as_str() {
+            "submit" => Some(PopupAction::Submit),
+            "enhance" => Some(PopupAction::Enhance),
+            "continue" => Some(PopupAction::Continue),
+            _ => None,
+        });
+    if configured.is_some() {
+        return configured;
+    }
+
+    if !plain {
+        return None;
+    }
+    match event.key.as_str() {
+        "Escape" => Some(PopupAction::RequestCancel),
+        "Tab" if event.shift => Some(PopupAction::FocusPrevious),
+        "Tab" => Some(PopupAction::FocusNext),
+        // `?` 通常带 Shift，不检查 Shift
+        "?" if !ctx.editing_text => Some(PopupAction::ToggleHelp),
+        key if !ctx.editing_text && !event.shift => option_index(key, ctx.option_count).map(PopupAction::ToggleOption),
+        _ => None,
+    }
+}
+
+/// 按键是否匹配绑定；macOS 上 Ctrl 绑定同时接受 Cmd
+pub fn matches_binding(binding: &ShortcutKey, event: &ShortcutKey, is_mac: bool) -> bool {
+    if !binding.key.eq_ignore_ascii_case(&event.key) || binding.alt != event.alt || binding.shift != event.shift {
+        return false;
+    }
+    let exact = binding.ctrl == event.ctrl && binding.meta == event.meta;
+    let cmd_for_ctrl = is_mac && binding.ctrl && !binding.meta && event.meta && !event.ctrl;
+    exact || cmd_for_ctrl
+}
+
+/// 数字键 1-9 对应的选项下标
+fn option_index(key: &str, option_count: usize) -> Option<usize> {
+    let digit = key.parse::<usize>().ok().filter(|_| key.len() == 1)?;
+    (1..=option_count.min(9)).contains(&digit).then(|| digit - 1)
+}
+
+/// 终端降级中一行输入对应的动作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineAction {
+    Submit,
+    Continue,
+    RequestCancel,
+    ShowHelp,
+    /// 切换若干选项（从 0 开始）
+    ToggleOptions(Vec<usize>),
+    /// 追加到回复正文
+    Text(String),
+}
+
+/// 把终端中的一行输入映射为动作
+///
+/// 单独一行 `.` 发送，`?` 显示帮助，Esc 或 `:q` 请求取消，`:continue` 继续；
+/// 尚未输入正文时，只含选项编号的一行（如 `1 3` 或 `1,3`）切换对应选项。
+/// 其余内容原样作为正文。Ctrl+D 与 Ctrl+C 由调用方分别映射为发送和取消。
+pub fn map_line(line: &str, option_count: usize, has_text: bool) -> LineAction {
+    let trimmed = line.trim();
+    match trimmed {
+        "." => return LineAction::Submit,
+        "?" | ":help" => return LineAction::ShowHelp,
+        "\u{1b}" | ":q" | ":cancel" => return LineAction::RequestCancel,
+        ":continue" => return LineAction::Continue,
+        _ => {}
+    }
+
+    if !has_text && !trimmed.is_empty() {
+        let picks: Option<Vec<usize>> = trimmed
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let n = part.parse::<usize>().ok()?;
+                (1..=option_count).contains(&n).then(|| n - 1)
+            })
+            .collect();
+        if let Some(picks) = picks {
+            return LineAction::ToggleOptions(picks);
+        }
+    }
+    LineAction::Text(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::default_shortcuts;
+
+    fn key(key: &str) -> ShortcutKey {
+        ShortcutKey { key: key.to_string(), ctrl: false, alt: false, shift: false, meta: false }
+    }
+
+    fn with(mut event: ShortcutKey, f: impl FnOnce(&mut ShortcutKey)) -> ShortcutKey {
+        f(&mut event);
+        event
+    }
+
+    fn ctx(option_count: usize) -> KeyContext {
+        KeyContext { option_count, ..Default::default() }
+    }
+
+    #[test]
+    fn test_submit_accepts_cmd_on_mac_only() {
+        let shortcuts = default_shortcuts();
+        let ctrl_enter = with(key("Enter"), |k| k.ctrl = true);
+        let cmd_enter = with(key("Enter"), |k| k.meta = true);
+
+        assert_eq!(map_key(&ctrl_enter, &ctx(0), &shortcuts), Some(PopupAction::Submit));
+        assert_eq!(map_key(&cmd_enter, &ctx(0), &shortcuts), None);
+        let mac = KeyContext { is_mac: true, ..ctx(0) };
+        assert_eq!(map_key(&cmd_enter, &mac, &shortcuts), Some(PopupAction::Submit));
+        assert_eq!(map_key(&ctrl_enter, &mac, &shortcuts), Some(PopupAction::Submit));
+
+        let shift = with(ctrl_enter.clone(), |k| k.shift = true);
+        assert_eq!(map_key(&shift, &ctx(0), &shortcuts), Some(PopupAction::Enhance));
+        let alt = with(key("Enter"), |k| k.alt = true);
+        assert_eq!(map_key(&alt, &ctx(0), &shortcuts), Some(PopupAction::Continue));
+        // 文本框中单独的 Enter 用于换行
+        assert_eq!(map_key(&key("Enter"), &ctx(0), &shortcuts), None);
+    }
+
+    #[test]
+    fn test_custom_and_disabled_bindings() {
+        let mut shortcuts = default_shortcuts();
+        let submit = shortcuts.get_mut("quick_submit").unwrap();
+        submit.key_combination = with(key("s"), |k| k.ctrl = true);
+        shortcuts.get_mut("continue").unwrap().enabled = false;
+
+        let ctrl_s = with(key("S"), |k| k.ctrl = true);
+        assert_eq!(map_key(&ctrl_s, &ctx(0), &shortcuts), Some(PopupAction::Submit));
+        assert_eq!(map_key(&with(key("Enter"), |k| k.ctrl = true), &ctx(0), &shortcuts), None);
+        assert_eq!(map_key(&with(key("Enter"), |k| k.alt = true), &ctx(0), &shortcuts), None);
+    }
+
+    #[test]
+    fn test_number_keys_pick_options() {
+        let shortcuts = default_shortcuts();
+        assert_eq!(map_key(&key("1"), &ctx(3), &shortcuts), Some(PopupAction::ToggleOption(0)));
+        assert_eq!(map_key(&key("3"), &ctx(3), &shortcuts), Some(PopupAction::ToggleOption(2)));
+        assert_eq!(map_key(&key("4"), &ctx(3), &shortcuts), None);
+        assert_eq!(map_key(&key("0"), &ctx(3), &shortcuts), None);
+        // 输入文字时数字就是数字
+        let editing = KeyContext { editing_text: true, ..ctx(3) };
+        assert_eq!(map_key(&key("1"), &editing, &shortcuts), None);
+        assert_eq!(map_key(&with(key("1"), |k| k.ctrl = true), &ctx(3), &shortcuts), None);
+    }
+
+    #[test]
+    fn test_escape_needs_confirmation() {
+        let shortcuts = default_shortcuts();
+        assert_eq!(map_key(&key("Escape"), &ctx(0), &shortcuts), Some(PopupAction::RequestCancel));
+        // 文本框中同样需要确认，不会直接丢弃输入
+        let editing = KeyContext { editing_text: true, ..ctx(0) };
+        assert_eq!(map_key(&key("Escape"), &editing, &shortcuts), Some(PopupAction::RequestCancel));
+
+        let confirming = KeyContext { confirming_cancel: true, ..ctx(2) };
+        assert_eq!(map_key(&key("Enter"), &confirming, &shortcuts), Some(PopupAction::ConfirmCancel));
+        assert_eq!(map_key(&key("y"), &confirming, &shortcuts), Some(PopupAction::ConfirmCancel));
+        assert_eq!(map_key(&key("Escape"), &confirming, &shortcuts), Some(PopupAction::DismissCancel));
+        assert_eq!(map_key(&key("n"), &confirming, &shortcuts), Some(PopupAction::DismissCancel));
+        // 确认期间其余按键不生效
+        assert_eq!(map_key(&with(key("Enter"), |k| k.ctrl = true), &confirming, &shortcuts), None);
+        assert_eq!(map_key(&key("1"), &confirming, &shortcuts), None);
+    }
+
+    #[test]
+    fn test_focus_and_help() {
+        let shortcuts = default_shortcuts();
+        assert_eq!(map_key(&key("Tab"), &ctx(0), &shortcuts), Some(PopupAction::FocusNext));
+        assert_eq!(map_key(&with(key("Tab"), |k| k.shift = true), &ctx(0), &shortcuts), Some(PopupAction::FocusPrevious));
+
+        let question = with(key("?"), |k| k.shift = true);
+        assert_eq!(map_key(&question, &ctx(0), &shortcuts), Some(PopupAction::ToggleHelp));
+        let editing = KeyContext { editing_text: true, ..ctx(0) };
+        assert_eq!(map_key(&question, &editing, &shortcuts), None);
+
+        let help = KeyContext { help_open: true, ..ctx(2) };
+        assert_eq!(map_key(&question, &help, &shortcuts), Some(PopupAction::ToggleHelp));
+        assert_eq!(map_key(&key("Escape"), &help, &shortcuts), Some(PopupAction::ToggleHelp));
+        assert_eq!(map_key(&key("1"), &help, &shortcuts), None);
+    }
+
+    #[test]
+    fn test_terminal_lines() {
+        assert_eq!(map_line(".", 0, true), LineAction::Submit);
+        assert_eq!(map_line("?", 2, false), LineAction::ShowHelp);
+        assert_eq!(map_line("\u{1b}", 2, true), LineAction::RequestCancel);
+        assert_eq!(map_line(":q", 2, false), LineAction::RequestCancel);
+        assert_eq!(map_line(":continue", 0, false), LineAction::Continue);
+        assert_eq!(map_line("1, 3", 3, false), LineAction::ToggleOptions(vec![0, 2]));
+        assert_eq!(map_line("2 1", 3, false), LineAction::ToggleOptions(vec![1, 0]));
+        // 超出范围或已有正文时按正文处理
+        assert_eq!(map_line("4", 3, false), LineAction::Text("4".to_string()));
+        assert_eq!(map_line("1", 3, true), LineAction::Text("1".to_string()));
+        assert_eq!(map_line("  indented\n", 0, false), LineAction::Text("  indented".to_string()));
+        assert_eq!(map_line("", 3, false), LineAction::Text(String::new()));
+    }
+}
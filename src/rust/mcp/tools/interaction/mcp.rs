@@ -9,6 +9,7 @@ use tokio::time::{sleep, Duration, Instant};
 
 use crate::config::load_standalone_config;
 use crate::mcp::{CacheRequest, PopupRequest};
+use super::DiffView;
 use crate::mcp::save_history_entry;
 use crate::mcp::handlers::{find_ui_command, parse_mcp_response};
 use crate::mcp::utils::{generate_request_id, popup_error};
@@ -93,44 +94,7 @@ fn clear_persisted_task_if_matches(task_id: &str) {
 }
 
 fn is_ui_process_running(pid: u32) -> bool {
-    #[cfg(target_os = "linux")]
-    {
-        std::path::Path::new(&format!("/proc/{}", pid)).exists()
-    }
-
-    #[cfg(windows)]
-    {
-        let filter = format!("PID eq {}", pid);
-        std::process::Command::new("tasklist")
-            .args(["/FI", &filter, "/FO", "CSV", "/NH"])
-            .output()
-            .ok()
-            .and_then(|o| {
-                if !o.status.success() {
-                    return None;
-                }
-                let out = String::from_utf8_lossy(&o.stdout);
-                let lower = out.to_ascii_lowercase();
-                let looks_like_no_tasks = lower.contains("no tasks")
-                    || lower.contains("info")
-                    || out.contains("信息")
-                    || out.contains("没有")
-                    || out.contains("无")
-                    || out.contains("未")
-                    || out.contains("找不到");
-                Some(!looks_like_no_tasks && out.contains(&pid.to_string()))
-            })
-            .unwrap_or(false)
-    }
-
-    #[cfg(all(not(target_os = "linux"), not(windows)))]
-    {
-        std::process::Command::new("kill")
-            .args(["-0", &pid.to_string()])
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false)
-    }
+    crate::utils::is_process_running(pid)
 }
 
 fn cleanup_task_files(task_id: &str, task: &PendingTask) {
@@ -232,6 +196,7 @@ impl InteractionTool {
             },
             chalkboard: request.format,
             project_root_path: request.project_root_path,
+            diff: DiffView::for_popup(request.diff.as_deref()),
         };
 
         // Create temp files
@@ -385,6 +350,7 @@ impl InteractionTool {
             },
             chalkboard: request.format,
             project_root_path: request.project_root_path,
+            diff: DiffView::for_popup(request.diff.as_deref()),
         };
 
         let temp_dir = std::env::temp_dir();
@@ -629,6 +595,7 @@ impl InteractionTool {
             },
             chalkboard: request.format,
             project_root_path: request.project_root_path,
+            diff: DiffView::for_popup(request.diff.as_deref()),
         };
 
         match crate::mcp::handlers::create_tauri_popup(&popup_request) {
@@ -2,7 +2,19 @@
 //!
 //! 提供智能代码审查交互功能，支持预定义选项、自由文本输入和食材
 
+#[cfg(feature = "gui")]
 pub mod mcp;
+pub mod diff;
+pub mod keys;
+
+// 未启用 gui 特性时没有弹窗界面可启动，使用无界面降级实现
+#[cfg(not(feature = "gui"))]
+pub mod headless;
 
 // 重新导出主要类型和功能
+pub use diff::DiffView;
+pub use keys::{map_key, map_line, KeyContext, LineAction, PopupAction};
+#[cfg(feature = "gui")]
 pub use mcp::InteractionTool;
+#[cfg(not(feature = "gui"))]
+pub use headless::InteractionTool;
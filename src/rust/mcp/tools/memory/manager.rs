@@ -1,42 +1,134 @@
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, SecondsFormat, Utc};
+use ring::digest::{digest, SHA256};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
 
-use super::types::{MemoryEntry, MemoryCategory, MemoryMetadata};
+use super::resources;
+use crate::mcp::dry_run;
+use super::types::{MemoryEntry, MemoryCategory, MemoryFilter, MemoryMetadata, MemorySnapshot, StaleMemory};
+
+/// 条目标记，写在每行末尾：`- 内容 <!-- id:xxxx updated:2024-01-01T00:00:00.000Z -->`
+const ID_MARKER_PREFIX: &str = "<!-- id:";
+const ID_MARKER_SUFFIX: &str = "-->";
+
+/// 已删除条目的墓碑文件
+const TOMBSTONES_FILE: &str = "tombstones.json";
+
+/// 每个条目最近一次被召回的时间（本机记录，不参与同步）
+const RECALLS_FILE: &str = "recalls.json";
+
+/// 已归档条目及归档时间；归档条目保留在分类文件中，但不参与召回（本机记录，不参与同步）
+const ARCHIVE_FILE: &str = "archived.json";
+
+/// 全部分类及其文件名
+const CATEGORY_FILES: [(MemoryCategory, &str); 4] = [
+    (MemoryCategory::Rule, "rules.md"),
+    (MemoryCategory::Preference, "preferences.md"),
+    (MemoryCategory::Pattern, "patterns.md"),
+    (MemoryCategory::Context, "context.md"),
+];
+
+/// 内存记忆库：记忆文件路径 -> 内容，进程内共享，进程退出后丢失
+static IN_MEMORY_FILES: once_cell::sync::Lazy<Mutex<HashMap<PathBuf, String>>> = once_cell::sync::Lazy::new(Default::default);
 
 /// 记忆管理器
 pub struct MemoryManager {
     memory_dir: PathBuf,
     project_path: String,
+    /// 启动预检发现配置目录不可写时只在内存中保存记忆
+    in_memory: bool,
 }
 
 impl MemoryManager {
-    /// 创建新的记忆管理器
+    /// 创建新的记忆管理器，存储方式由启动目录预检决定
     pub fn new(project_path: &str) -> Result<Self> {
+        Self::open(project_path, crate::utils::paths::preflight().memory_in_memory())
+    }
+
+    /// 只在内存中保存记忆的管理器，不创建 `.devkit-memory`
+    pub fn in_memory(project_path: &str) -> Result<Self> {
+        Self::open(project_path, true)
+    }
+
+    fn open(project_path: &str, in_memory: bool) -> Result<Self> {
         // 规范化项目路径
         let normalized_path = Self::normalize_project_path(project_path)?;
         let memory_dir = normalized_path.join(".devkit-memory");
 
-        // 创建记忆目录，如果失败则说明项目不适合使用记忆功能
-        fs::create_dir_all(&memory_dir)
-            .map_err(|e| anyhow::anyhow!(
-                "无法在git项目中创建记忆目录: {}\n错误: {}\n这可能是因为项目目录没有写入权限。",
-                memory_dir.display(),
-                e
-            ))?;
+        // 创建记忆目录，如果失败则说明项目不适合使用记忆功能（试运行时不创建）
+        if !in_memory {
+            dry_run::create_dir_all(&memory_dir)
+                .map_err(|e| anyhow::anyhow!(
+                    "无法在git项目中创建记忆目录: {}\n错误: {}\n这可能是因为项目目录没有写入权限。",
+                    memory_dir.display(),
+                    e
+                ))?;
+        }
 
         let manager = Self {
             memory_dir,
             project_path: normalized_path.to_string_lossy().to_string(),
+            in_memory,
         };
 
         // 初始化记忆文件结构
         manager.initialize_memory_structure()?;
 
+        // 记录命名空间，供 MCP 资源按 URI 找回项目
+        resources::remember_project(&manager.namespace(), &normalized_path);
+
         Ok(manager)
     }
 
+    /// 记忆目录（`<项目>/.devkit-memory`）
+    pub fn memory_dir(&self) -> &Path {
+        &self.memory_dir
+    }
+
+    /// 记忆只保存在内存中（本次进程结束后丢失）
+    pub fn is_in_memory(&self) -> bool {
+        self.in_memory
+    }
+
+    /// 读取记忆目录下的文件，不存在时为 None；试运行中读到的是暂存的内容
+    fn read_file(&self, name: &str) -> Result<Option<String>> {
+        let path = self.memory_dir.join(name);
+        if let Some(staged) = dry_run::staged(&path) {
+            return Ok(Some(staged));
+        }
+        if self.in_memory {
+            return Ok(IN_MEMORY_FILES.lock().unwrap_or_else(PoisonError::into_inner).get(&path).cloned());
+        }
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?))
+    }
+
+    /// 写入记忆目录下的文件；试运行时只暂存，不落盘
+    fn write_file(&self, name: &str, content: String) -> Result<()> {
+        let path = self.memory_dir.join(name);
+        if dry_run::is_active() {
+            let previous = self.read_file(name)?.map(|content| content.len());
+            dry_run::stage(&path, content, previous);
+            return Ok(());
+        }
+        if self.in_memory {
+            IN_MEMORY_FILES.lock().unwrap_or_else(PoisonError::into_inner).insert(path, content);
+            return Ok(());
+        }
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 项目命名空间：规范化项目路径的 sha256 前 16 位，用于资源 URI
+    pub fn namespace(&self) -> String {
+        hex::encode(digest(&SHA256, self.project_path.as_bytes()).as_ref())[..16].to_string()
+    }
+
     /// 规范化项目路径
     fn normalize_project_path(project_path: &str) -> Result<PathBuf> {
         // 使用增强的路径解码和规范化功能
@@ -155,10 +247,8 @@ impl MemoryManager {
                 MemoryCategory::Context => "context.md",
             };
 
-            let file_path = self.memory_dir.join(filename);
-            if !file_path.exists() {
-                let header_content = self.get_category_header(category);
-                fs::write(&file_path, header_content)?;
+            if self.read_file(filename)?.is_none() {
+                self.write_file(filename, self.get_category_header(category))?;
             }
         }
 
@@ -187,31 +277,273 @@ impl MemoryManager {
         // 更新元数据
         self.update_metadata()?;
 
+        resources::publish_update(&self.namespace(), &id);
         Ok(id)
     }
 
-    /// 获取所有记忆
-    pub fn get_all_memories(&self) -> Result<Vec<MemoryEntry>> {
-        let mut memories = Vec::new();
+    /// 按 ID 查找记忆
+    pub fn get_memory(&self, id: &str) -> Result<Option<MemoryEntry>> {
+        Ok(self.get_all_memories()?.into_iter().find(|m| m.id == id))
+    }
 
-        let categories = [
-            (MemoryCategory::Rule, "rules.md"),
-            (MemoryCategory::Preference, "preferences.md"),
-            (MemoryCategory::Pattern, "patterns.md"),
-            (MemoryCategory::Context, "context.md"),
-        ];
+    /// 修改记忆内容，ID 与分类保持不变；不存在时返回 None
+    pub fn update_memory(&self, id: &str, content: &str) -> Result<Option<MemoryEntry>> {
+        let content = content.split_whitespace().collect::<Vec<_>>().join(" ");
+        let updated = self.rewrite_entry(id, |entry| {
+            entry.content = content.clone();
+            entry.updated_at = Utc::now();
+            true
+        })?;
+        if updated.is_some() {
+            self.update_metadata()?;
+            resources::publish_update(&self.namespace(), id);
+        }
+        Ok(updated)
+    }
+
+    /// 删除记忆并留下墓碑；不存在时返回 false
+    pub fn delete_memory(&self, id: &str) -> Result<bool> {
+        let deleted = self.rewrite_entry(id, |_| false)?.is_some();
+        if deleted {
+            let mut tombstones = self.load_tombstones()?;
+            tombstones.insert(id.to_string(), Utc::now());
+            self.save_tombstones(&tombstones)?;
+            for file in [RECALLS_FILE, ARCHIVE_FILE] {
+                let mut times = self.load_times(file)?;
+                if times.remove(id).is_some() {
+                    self.save_times(file, &times)?;
+                }
+            }
+            self.update_metadata()?;
+            resources::publish_update(&self.namespace(), id);
+        }
+        Ok(deleted)
+    }
+
+    /// 找到 `id` 所在的行并交给 `edit`：返回 true 时写回修改后的条目，false 时删除该行
+    fn rewrite_entry(&self, id: &str, mut edit: impl FnMut(&mut MemoryEntry) -> bool) -> Result<Option<MemoryEntry>> {
+        for (category, filename) in CATEGORY_FILES.iter() {
+            let Some(content) = self.read_file(filename)? else {
+                continue;
+            };
+            let mut found = None;
+            let mut lines = Vec::new();
+            for line in content.lines() {
+                match Self::parse_line(line, *category) {
+                    Some(mut entry) if found.is_none() && entry.id == id => {
+                        if edit(&mut entry) {
+                            lines.push(Self::format_line(&entry));
+                        }
+                        found = Some(entry);
+                    }
+                    _ => lines.push(line.to_string()),
+                }
+            }
+            if found.is_some() {
+                let mut rewritten = lines.join("\n");
+                rewritten.push('\n');
+                self.write_file(filename, rewritten)?;
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+
+    /// 当前条目和墓碑，条目按文件中的顺序排列
+    pub fn snapshot(&self) -> Result<MemorySnapshot> {
+        Ok(MemorySnapshot {
+            entries: self.read_entries()?,
+            tombstones: self.load_tombstones()?,
+        })
+    }
+
+    /// 用快照替换记忆库内容
+    ///
+    /// 已有条目原地改写，新条目追加到对应分类文件末尾，标题和其他文本保持不变
+    pub fn apply_snapshot(&self, snapshot: &MemorySnapshot) -> Result<()> {
+        let before: HashMap<String, MemoryEntry> = self
+            .read_entries()?
+            .into_iter()
+            .map(|entry| (entry.id.clone(), entry))
+            .collect();
+        let wanted: HashMap<&str, &MemoryEntry> = snapshot
+            .entries
+            .iter()
+            .filter(|entry| !snapshot.tombstones.contains_key(&entry.id))
+            .map(|entry| (entry.id.as_str(), entry))
+            .collect();
+
+        let mut placed = HashSet::new();
+        for (category, filename) in CATEGORY_FILES.iter() {
+            let original = match self.read_file(filename)? {
+                Some(content) => content,
+                None => self.get_category_header(category),
+            };
+
+            let mut lines = Vec::new();
+            for line in original.lines() {
+                match Self::parse_line(line, *category) {
+                    Some(entry) => {
+                        if let Some(wanted) = wanted.get(entry.id.as_str()) {
+                            if wanted.category == *category && placed.insert(entry.id.clone()) {
+                                lines.push(Self::format_line(wanted));
+                            }
+                        }
+                    }
+                    None => lines.push(line.to_string()),
+                }
+            }
+            for entry in snapshot.entries.iter().filter(|e| e.category == *category) {
+                if wanted.contains_key(entry.id.as_str()) && placed.insert(entry.id.clone()) {
+                    lines.push(Self::format_line(entry));
+                }
+            }
+
+            let mut rewritten = lines.join("\n");
+            rewritten.push('\n');
+            if rewritten != original {
+                self.write_file(filename, rewritten)?;
+            }
+        }
+
+        self.save_tombstones(&snapshot.tombstones)?;
+        self.update_metadata()?;
+
+        // 通知订阅了变化条目的会话
+        let namespace = self.namespace();
+        for (id, entry) in before.iter() {
+            if wanted.get(id.as_str()).is_none_or(|wanted| *wanted != entry) {
+                resources::publish_update(&namespace, id);
+            }
+        }
+        for id in wanted.keys().filter(|id| !before.contains_key(**id)) {
+            resources::publish_update(&namespace, id);
+        }
+        Ok(())
+    }
+
+    fn load_tombstones(&self) -> Result<BTreeMap<String, DateTime<Utc>>> {
+        self.load_times(TOMBSTONES_FILE)
+    }
+
+    fn save_tombstones(&self, tombstones: &BTreeMap<String, DateTime<Utc>>) -> Result<()> {
+        self.save_times(TOMBSTONES_FILE, tombstones)
+    }
+
+    /// 读取记忆目录下 `ID -> 时间` 的 JSON 文件，不存在时为空
+    fn load_times(&self, file: &str) -> Result<BTreeMap<String, DateTime<Utc>>> {
+        match self.read_file(file)? {
+            Some(content) => Ok(serde_json::from_str(&content)?),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    fn save_times(&self, file: &str, times: &BTreeMap<String, DateTime<Utc>>) -> Result<()> {
+        if times.is_empty() && self.read_file(file)?.is_none() {
+            return Ok(());
+        }
+        self.write_file(file, serde_json::to_string_pretty(times)?)
+    }
 
-        for (category, filename) in categories.iter() {
-            let file_path = self.memory_dir.join(filename);
-            if file_path.exists() {
-                let content = fs::read_to_string(&file_path)?;
+    /// 召回：返回未归档记忆的汇总，并把这些条目的最近召回时间记为 `now`
+    pub fn recall(&self, now: DateTime<Utc>) -> Result<String> {
+        let entries = self.list_memories(MemoryFilter::Active)?;
+        let ids: Vec<&str> = entries.iter().map(|entry| entry.id.as_str()).collect();
+        self.record_recall(&ids, now)?;
+        Ok(Self::format_project_info(&entries))
+    }
+
+    /// 记录条目在 `now` 被召回（例如通过资源读取了单条记忆）
+    pub fn record_recall(&self, ids: &[&str], now: DateTime<Utc>) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut recalls = self.load_times(RECALLS_FILE)?;
+        for id in ids {
+            recalls.insert(id.to_string(), now);
+        }
+        self.save_times(RECALLS_FILE, &recalls)
+    }
+
+    /// 按归档状态列出记忆，顺序同分类文件
+    pub fn list_memories(&self, filter: MemoryFilter) -> Result<Vec<MemoryEntry>> {
+        let archived = self.load_times(ARCHIVE_FILE)?;
+        Ok(self
+            .read_entries()?
+            .into_iter()
+            .filter(|entry| match filter {
+                MemoryFilter::Active => !archived.contains_key(&entry.id),
+                MemoryFilter::Archived => archived.contains_key(&entry.id),
+                MemoryFilter::All => true,
+            })
+            .collect())
+    }
+
+    /// 超过 `max_idle` 未被召回的未归档记忆，最久未用的在前
+    ///
+    /// 从未召回过的条目从最后修改时间算起；修改也算一次使用
+    pub fn stale_memories(&self, max_idle: chrono::Duration, now: DateTime<Utc>) -> Result<Vec<StaleMemory>> {
+        let recalls = self.load_times(RECALLS_FILE)?;
+        let mut stale: Vec<StaleMemory> = self
+            .list_memories(MemoryFilter::Active)?
+            .into_iter()
+            .filter_map(|entry| {
+                let last_recalled_at = recalls.get(&entry.id).copied();
+                let last_used = last_recalled_at.map_or(entry.updated_at, |recalled| recalled.max(entry.updated_at));
+                let idle = now.signed_duration_since(last_used);
+                (idle > max_idle).then(|| StaleMemory { entry, last_recalled_at, idle_days: idle.num_days() })
+            })
+            .collect();
+        stale.sort_by_key(|memory| std::cmp::Reverse(memory.idle_days));
+        Ok(stale)
+    }
+
+    /// 归档记忆，返回实际归档的 ID（不存在或已归档的跳过）
+    pub fn archive_memories(&self, ids: &[&str], now: DateTime<Utc>) -> Result<Vec<String>> {
+        let existing: HashSet<String> = self.read_entries()?.into_iter().map(|entry| entry.id).collect();
+        let mut archived = self.load_times(ARCHIVE_FILE)?;
+        let mut changed = Vec::new();
+        for id in ids {
+            if existing.contains(*id) && !archived.contains_key(*id) {
+                archived.insert(id.to_string(), now);
+                changed.push(id.to_string());
+            }
+        }
+        if !changed.is_empty() {
+            self.save_times(ARCHIVE_FILE, &archived)?;
+        }
+        Ok(changed)
+    }
+
+    /// 取消归档，返回实际恢复的 ID
+    pub fn restore_memories(&self, ids: &[&str]) -> Result<Vec<String>> {
+        let mut archived = self.load_times(ARCHIVE_FILE)?;
+        let restored: Vec<String> = ids.iter().filter(|id| archived.remove(**id).is_some()).map(|id| id.to_string()).collect();
+        if !restored.is_empty() {
+            self.save_times(ARCHIVE_FILE, &archived)?;
+        }
+        Ok(restored)
+    }
+
+    /// 按文件顺序读取全部条目
+    fn read_entries(&self) -> Result<Vec<MemoryEntry>> {
+        let mut memories = Vec::new();
+
+        for (category, filename) in CATEGORY_FILES.iter() {
+            if let Some(content) = self.read_file(filename)? {
                 let entries = self.parse_memory_file(&content, *category)?;
                 memories.extend(entries);
             }
         }
+        Ok(memories)
+    }
+
+    /// 获取所有记忆
+    pub fn get_all_memories(&self) -> Result<Vec<MemoryEntry>> {
+        let mut memories = self.read_entries()?;
 
         // 按更新时间排序
-        memories.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        memories.sort_by_key(|m| std::cmp::Reverse(m.updated_at));
 
         Ok(memories)
     }
@@ -225,13 +557,10 @@ impl MemoryManager {
             MemoryCategory::Context => "context.md",
         };
 
-        let file_path = self.memory_dir.join(filename);
-        if !file_path.exists() {
-            return Ok(Vec::new());
+        match self.read_file(filename)? {
+            Some(content) => self.parse_memory_file(&content, category),
+            None => Ok(Vec::new()),
         }
-
-        let content = fs::read_to_string(&file_path)?;
-        self.parse_memory_file(&content, category)
     }
 
     /// 将记忆条目添加到对应分类文件
@@ -243,44 +572,72 @@ impl MemoryManager {
             MemoryCategory::Context => "context.md",
         };
 
-        let file_path = self.memory_dir.join(filename);
-        let mut content = if file_path.exists() {
-            fs::read_to_string(&file_path)?
-        } else {
-            format!("# {}\n\n", self.get_category_title(&entry.category))
+        let mut content = match self.read_file(filename)? {
+            Some(content) => content,
+            None => format!("# {}\n\n", self.get_category_title(&entry.category)),
         };
 
-        // 简化格式：一行一个记忆
-        content.push_str(&format!("- {}\n", entry.content));
+        // 简化格式：一行一个记忆，行尾带 ID 标记
+        content.push_str(&Self::format_line(entry));
+        content.push('\n');
 
-        fs::write(&file_path, content)?;
-        Ok(())
+        self.write_file(filename, content)
     }
 
     /// 解析记忆文件内容 - 简化版本
     fn parse_memory_file(&self, content: &str, category: MemoryCategory) -> Result<Vec<MemoryEntry>> {
-        let mut memories = Vec::new();
-
         // 按列表项解析，每个 "- " 开头的行是一个记忆条目
-        for line in content.lines() {
-            let line = line.trim();
-            if line.starts_with("- ") && line.len() > 2 {
-                let content = line[2..].trim(); // 去掉 "- " 前缀
-                if !content.is_empty() {
-                    let entry = MemoryEntry {
-                        id: uuid::Uuid::new_v4().to_string(),
-                        content: content.to_string(),
-                        category,
-                        created_at: Utc::now(),
-                        updated_at: Utc::now(),
-                    };
-
-                    memories.push(entry);
-                }
+        Ok(content.lines().filter_map(|line| Self::parse_line(line, category)).collect())
+    }
+
+    /// 解析一行记忆
+    ///
+    /// 没有标记的旧条目使用由分类和内容推导的稳定 ID；缺少更新时间时视为最早，
+    /// 同步时任何有时间的版本都比它新
+    fn parse_line(line: &str, category: MemoryCategory) -> Option<MemoryEntry> {
+        let line = line.trim();
+        let rest = line.strip_prefix("- ")?.trim();
+
+        let (content, id, updated_at) = match rest.strip_suffix(ID_MARKER_SUFFIX).and_then(|r| r.rsplit_once(ID_MARKER_PREFIX)) {
+            Some((content, marker)) => {
+                let mut fields = marker.split_whitespace();
+                let id = fields.next()?.to_string();
+                let updated_at = fields
+                    .find_map(|field| field.strip_prefix("updated:"))
+                    .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+                    .map(|time| time.with_timezone(&Utc))
+                    .unwrap_or(DateTime::UNIX_EPOCH);
+                (content.trim(), id, updated_at)
             }
+            None => {
+                let seed = format!("{:?}:{}", category, rest);
+                (rest, hex::encode(digest(&SHA256, seed.as_bytes()).as_ref())[..16].to_string(), DateTime::UNIX_EPOCH)
+            }
+        };
+        if content.is_empty() {
+            return None;
         }
 
-        Ok(memories)
+        Some(MemoryEntry {
+            id,
+            content: content.to_string(),
+            category,
+            created_at: updated_at,
+            updated_at,
+        })
+    }
+
+    /// 一行一个记忆，内容中的换行会被压成空格
+    fn format_line(entry: &MemoryEntry) -> String {
+        let content = entry.content.split_whitespace().collect::<Vec<_>>().join(" ");
+        format!(
+            "- {} {}{} updated:{} {}",
+            content,
+            ID_MARKER_PREFIX,
+            entry.id,
+            entry.updated_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+            ID_MARKER_SUFFIX
+        )
     }
 
     /// 获取分类标题
@@ -307,19 +664,18 @@ impl MemoryManager {
             version: "1.0.0".to_string(),
         };
 
-        let metadata_path = self.memory_dir.join("metadata.json");
-        let metadata_json = serde_json::to_string_pretty(&metadata)?;
-        fs::write(metadata_path, metadata_json)?;
-
-        Ok(())
+        self.write_file("metadata.json", serde_json::to_string_pretty(&metadata)?)
     }
 
-    /// 获取项目信息供MCP调用方分析 - 压缩简化版本
+    /// 获取项目信息供MCP调用方分析 - 压缩简化版本（不含归档条目，不记录召回）
     pub fn get_project_info(&self) -> Result<String> {
-        // 汇总所有记忆规则并压缩
-        let all_memories = self.get_all_memories()?;
-        if all_memories.is_empty() {
-            return Ok("📭 暂无项目记忆".to_string());
+        Ok(Self::format_project_info(&self.list_memories(MemoryFilter::Active)?))
+    }
+
+    /// 按分类压缩汇总记忆，分类内保持给定顺序
+    pub(crate) fn format_project_info(memories: &[MemoryEntry]) -> String {
+        if memories.is_empty() {
+            return "📭 暂无项目记忆".to_string();
         }
 
         let mut compressed_info = Vec::new();
@@ -333,30 +689,146 @@ impl MemoryManager {
         ];
 
         for (category, title) in categories.iter() {
-            let memories = self.get_memories_by_category(*category)?;
-            if !memories.is_empty() {
-                let mut items = Vec::new();
-                for memory in memories {
-                    let content = memory.content.trim();
-                    if !content.is_empty() {
-                        // 去除多余空格和换行，压缩内容
-                        let compressed_content = content
-                            .split_whitespace()
-                            .collect::<Vec<&str>>()
-                            .join(" ");
-                        items.push(compressed_content);
-                    }
-                }
-                if !items.is_empty() {
-                    compressed_info.push(format!("**{}**: {}", title, items.join("; ")));
-                }
+            let items: Vec<String> = memories
+                .iter()
+                .filter(|memory| memory.category == *category)
+                // 去除多余空格和换行，压缩内容
+                .map(|memory| memory.content.split_whitespace().collect::<Vec<&str>>().join(" "))
+                .filter(|content| !content.is_empty())
+                .collect();
+            if !items.is_empty() {
+                compressed_info.push(format!("**{}**: {}", title, items.join("; ")));
             }
         }
 
         if compressed_info.is_empty() {
-            Ok("📭 暂无有效项目记忆".to_string())
+            "📭 暂无有效项目记忆".to_string()
         } else {
-            Ok(format!("📚 项目记忆总览: {}", compressed_info.join(" | ")))
+            format!("📚 项目记忆总览: {}", compressed_info.join(" | "))
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn temp_project() -> TestDir {
+        let dir = TestDir::new("memory");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_update_and_delete_keep_ids_stable() {
+        let project = temp_project();
+        let manager = MemoryManager::new(&project.to_string_lossy()).unwrap();
+        let kept = manager.add_memory("保留这一条", MemoryCategory::Rule).unwrap();
+        let edited = manager.add_memory("旧内容\n换行", MemoryCategory::Pattern).unwrap();
+
+        let entry = manager.update_memory(&edited, "新内容").unwrap().unwrap();
+        assert_eq!(entry.id, edited);
+        assert_eq!(manager.get_memory(&edited).unwrap().unwrap().content, "新内容");
+        assert_eq!(manager.get_memory(&kept).unwrap().unwrap().content, "保留这一条");
+
+        assert!(manager.delete_memory(&edited).unwrap());
+        assert!(!manager.delete_memory(&edited).unwrap());
+        assert!(manager.update_memory(&edited, "x").unwrap().is_none());
+        assert_eq!(manager.get_all_memories().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_in_memory_store_works_without_memory_dir() {
+        let project = temp_project();
+        let manager = MemoryManager::in_memory(&project.to_string_lossy()).unwrap();
+        assert!(manager.is_in_memory());
+        let id = manager.add_memory("只保存在内存中", MemoryCategory::Rule).unwrap();
+        manager.update_memory(&id, "内存中的新内容").unwrap().unwrap();
+
+        // 同一进程内再次打开仍能读到，但磁盘上没有任何文件
+        let reopened = MemoryManager::in_memory(&project.to_string_lossy()).unwrap();
+        assert_eq!(reopened.get_memory(&id).unwrap().unwrap().content, "内存中的新内容");
+        assert!(!project.join(".devkit-memory").exists());
+
+        assert!(reopened.delete_memory(&id).unwrap());
+        assert!(manager.get_all_memories().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_legacy_lines_get_deterministic_ids() {
+        let project = temp_project();
+        let manager = MemoryManager::new(&project.to_string_lossy()).unwrap();
+        fs::write(project.join(".devkit-memory").join("context.md"), "# 项目上下文信息\n\n- 旧格式条目\n").unwrap();
+
+        let first = manager.get_all_memories().unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].content, "旧格式条目");
+        assert_eq!(manager.get_all_memories().unwrap()[0].id, first[0].id);
+
+        // 修改后写回 ID 标记，ID 不变
+        manager.update_memory(&first[0].id, "新格式条目").unwrap().unwrap();
+        assert_eq!(manager.get_memory(&first[0].id).unwrap().unwrap().content, "新格式条目");
+    }
+
+    #[test]
+    fn test_stale_memories_with_fake_clock() {
+        let project = temp_project();
+        let manager = MemoryManager::new(&project.to_string_lossy()).unwrap();
+        let recalled = manager.add_memory("经常用到的规则", MemoryCategory::Rule).unwrap();
+        let forgotten = manager.add_memory("早已弃用的工具", MemoryCategory::Context).unwrap();
+        let days = chrono::Duration::days;
+        let start = Utc::now();
+
+        assert!(manager.stale_memories(days(60), start + days(30)).unwrap().is_empty());
+
+        // 第 50 天召回一次，第 90 天只有另一条过期
+        manager.record_recall(&[recalled.as_str()], start + days(50)).unwrap();
+        let stale = manager.stale_memories(days(60), start + days(90)).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].entry.id, forgotten);
+        assert_eq!(stale[0].last_recalled_at, None);
+        assert_eq!(stale[0].idle_days, 90);
+
+        // 整体召回刷新全部条目
+        manager.recall(start + days(100)).unwrap();
+        assert!(manager.stale_memories(days(60), start + days(150)).unwrap().is_empty());
+        let stale = manager.stale_memories(days(60), start + days(200)).unwrap();
+        assert_eq!(stale.len(), 2);
+        assert_eq!(stale[0].last_recalled_at, Some(start + days(100)));
+    }
+
+    #[test]
+    fn test_archived_memories_leave_recall_until_restored() {
+        let project = temp_project();
+        let manager = MemoryManager::new(&project.to_string_lossy()).unwrap();
+        let kept = manager.add_memory("保留的偏好", MemoryCategory::Preference).unwrap();
+        let old = manager.add_memory("旧的构建命令", MemoryCategory::Pattern).unwrap();
+        let now = Utc::now();
+
+        let archived = manager.archive_memories(&[old.as_str(), "missing"], now).unwrap();
+        assert_eq!(archived, vec![old.clone()]);
+        assert!(manager.archive_memories(&[old.as_str()], now).unwrap().is_empty());
+
+        let recall = manager.recall(now).unwrap();
+        assert!(recall.contains("保留的偏好"));
+        assert!(!recall.contains("旧的构建命令"));
+        let ids = |filter| manager.list_memories(filter).unwrap().into_iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(MemoryFilter::Active), vec![kept.clone()]);
+        assert_eq!(ids(MemoryFilter::Archived), vec![old.clone()]);
+        assert_eq!(ids(MemoryFilter::All).len(), 2);
+        // 归档条目不出现在过期列表中
+        let later = now + chrono::Duration::days(365);
+        assert_eq!(manager.stale_memories(chrono::Duration::days(60), later).unwrap().len(), 1);
+
+        assert_eq!(manager.restore_memories(&[old.as_str()]).unwrap(), vec![old.clone()]);
+        assert!(manager.recall(now).unwrap().contains("旧的构建命令"));
+        assert!(ids(MemoryFilter::Archived).is_empty());
+
+        // 删除时一并清理归档与召回记录
+        manager.archive_memories(&[old.as_str()], now).unwrap();
+        assert!(manager.delete_memory(&old).unwrap());
+        assert!(ids(MemoryFilter::Archived).is_empty());
+        assert!(!manager.load_times(RECALLS_FILE).unwrap().contains_key(&old));
+    }
+}
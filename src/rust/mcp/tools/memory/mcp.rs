@@ -1,10 +1,21 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::Utc;
 use rmcp::model::{ErrorData as McpError, CallToolResult, Content};
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::Path;
 
-use super::{MemoryManager, MemoryCategory};
-use crate::mcp::{StoreRequest, utils::{validate_project_path, project_path_error}};
+use super::{sync_store, MemoryEntry, MemoryFilter, MemoryManager, MemoryCategory, StaleMemory};
+use crate::mcp::{dry_run, StoreRequest, utils::{validate_project_path, project_path_error, ToolError}};
+use crate::utils::text;
 use crate::log_debug;
 
+/// Days without recall after which `review_stale` lists an entry
+pub const DEFAULT_STALE_DAYS: u32 = 60;
+
+/// Characters of content shown per entry by `list` and `review_stale`
+const PREVIEW_CHARS: usize = 80;
+
 /// Project memory management tool
 #[derive(Clone)]
 pub struct MemoryTool;
@@ -12,7 +23,7 @@ pub struct MemoryTool;
 impl MemoryTool {
     pub async fn store(
         request: StoreRequest,
-    ) -> Result<CallToolResult, McpError> {
+    ) -> Result<CallToolResult, ToolError> {
         if let Err(e) = validate_project_path(&request.project_path) {
             return Err(project_path_error(format!(
                 "Path validation failed: {}\nOriginal path: {}",
@@ -22,10 +33,10 @@ impl MemoryTool {
         }
 
         let manager = MemoryManager::new(&request.project_path)
-            .map_err(|e| McpError::internal_error(format!("Failed to create memory manager: {}", e), None))?;
+            .context("Failed to create memory manager")?;
 
         let mut index_hint = String::new();
-        if is_index_enabled() {
+        if is_index_enabled() && !dry_run::is_active() {
             if let Err(e) = try_trigger_background_index(&request.project_path).await {
                 log_debug!("Background index trigger failed (not affecting memory): {}", e);
             } else {
@@ -36,7 +47,7 @@ impl MemoryTool {
         let result = match request.action.as_str() {
             "store" | "记忆" => {
                 if request.content.trim().is_empty() {
-                    return Err(McpError::invalid_params("Missing content".to_string(), None));
+                    return Err(McpError::invalid_params("Missing content".to_string(), None).into());
                 }
 
                 let category = match request.category.as_str() {
@@ -48,20 +59,117 @@ impl MemoryTool {
                 };
 
                 let id = manager.add_memory(&request.content, category)
-                    .map_err(|e| McpError::internal_error(format!("Failed to add memory: {}", e), None))?;
+                    .context("Failed to add memory")?;
+                dry_run::record(json!({ "action": "store", "id": id, "category": category, "content": request.content }));
+
+                format!("{}, ID: {}\nContent: {}\nCategory: {:?}{}", verb("Memory added", "Would add memory"), id, request.content, category, index_hint)
+            }
+            "update" => {
+                let id = required_id(&request)?;
+                if request.content.trim().is_empty() {
+                    return Err(McpError::invalid_params("Missing content".to_string(), None).into());
+                }
+                let before = manager.get_memory(id).context("Failed to update memory")?;
+                let entry = manager.update_memory(id, &request.content)
+                    .context("Failed to update memory")?
+                    .ok_or_else(|| McpError::invalid_params(format!("No memory with ID {}", id), None))?;
+                dry_run::record(json!({
+                    "action": "update",
+                    "id": entry.id,
+                    "category": entry.category,
+                    "before": before.map(|before| before.content),
+                    "content": entry.content,
+                }));
+
+                format!("{}, ID: {}\nContent: {}\nCategory: {:?}{}", verb("Memory updated", "Would update memory"), entry.id, entry.content, entry.category, index_hint)
+            }
+            "delete" => {
+                let ids = required_ids(&request)?;
+                record_entries(&manager, "delete", &ids)?;
+                let mut deleted = Vec::new();
+                for id in &ids {
+                    if manager.delete_memory(id).context("Failed to delete memory")? {
+                        deleted.push(id.to_string());
+                    }
+                }
+                if deleted.is_empty() {
+                    return Err(McpError::invalid_params(format!("No memory with ID {}", ids.join(", ")), None).into());
+                }
 
-                format!("Memory added, ID: {}\nContent: {}\nCategory: {:?}{}", id, request.content, category, index_hint)
+                match ids.as_slice() {
+                    [id] => format!("{}, ID: {}{}", verb("Memory deleted", "Would delete memory"), id, index_hint),
+                    _ => format!("{}{}", bulk_report(verb("Deleted", "Would delete"), &ids, &deleted), index_hint),
+                }
+            }
+            "archive" => {
+                let ids = required_ids(&request)?;
+                let archived = manager.archive_memories(&ids, Utc::now())
+                    .context("Failed to archive memory")?;
+                record_entries(&manager, "archive", &archived)?;
+                format!("{}\nArchived entries are left out of recall; bring them back with action `restore`.", bulk_report(verb("Archived", "Would archive"), &ids, &archived))
+            }
+            "restore" => {
+                let ids = required_ids(&request)?;
+                let restored = manager.restore_memories(&ids)
+                    .context("Failed to restore memory")?;
+                record_entries(&manager, "restore", &restored)?;
+                bulk_report(verb("Restored", "Would restore"), &ids, &restored)
+            }
+            "list" => {
+                let filter = match request.filter.as_deref().map(str::trim).unwrap_or("active") {
+                    "" | "active" => MemoryFilter::Active,
+                    "archived" => MemoryFilter::Archived,
+                    "all" => MemoryFilter::All,
+                    other => {
+                        return Err(McpError::invalid_params(
+                            format!("Unknown filter: {} (expected active, archived or all)", other),
+                            None,
+                        ).into());
+                    }
+                };
+                let entries = manager.list_memories(filter).context("Failed to list memories")?;
+                let archived: HashSet<String> = match filter {
+                    MemoryFilter::All => manager.list_memories(MemoryFilter::Archived)
+                        .context("Failed to list memories")?
+                        .into_iter()
+                        .map(|entry| entry.id)
+                        .collect(),
+                    _ => HashSet::new(),
+                };
+                format_list(&entries, filter, &archived)
+            }
+            "review_stale" => {
+                let days = request.stale_days.unwrap_or(DEFAULT_STALE_DAYS);
+                let stale = manager.stale_memories(chrono::Duration::days(days.into()), Utc::now())
+                    .context("Failed to review memories")?;
+                format_stale(&stale, days)
+            }
+            "sync" => {
+                let path = request.sync_path.as_deref()
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .ok_or_else(|| McpError::invalid_params("Missing sync_path for sync".to_string(), None))?;
+                let report = sync_store(&manager, Path::new(path))
+                    .context("Failed to sync memory")?;
+                if dry_run::is_active() {
+                    let mut change = json!(report);
+                    change["action"] = json!("sync");
+                    dry_run::record(change);
+                    report.planned()
+                } else {
+                    format!("{}{}", report, index_hint)
+                }
             }
             "recall" | "回忆" => {
-                let info = manager.get_project_info()
-                    .map_err(|e| McpError::internal_error(format!("Failed to get project info: {}", e), None))?;
+                let info = manager.recall(Utc::now())
+                    .context("Failed to get project info")?;
                 format!("{}{}", info, index_hint)
             }
             _ => {
                 return Err(McpError::invalid_params(
                     format!("Unknown action: {}", request.action),
                     None
-                ));
+                ).into());
             }
         };
 
@@ -69,6 +177,110 @@ impl MemoryTool {
     }
 }
 
+/// `past` for a real change, `planned` during a dry run
+fn verb(past: &'static str, planned: &'static str) -> &'static str {
+    if dry_run::is_active() {
+        planned
+    } else {
+        past
+    }
+}
+
+/// Record the entries `ids` as changes of a dry run; does nothing otherwise
+fn record_entries<S: AsRef<str>>(manager: &MemoryManager, action: &str, ids: &[S]) -> Result<()> {
+    if !dry_run::is_active() {
+        return Ok(());
+    }
+    for id in ids {
+        if let Some(entry) = manager.get_memory(id.as_ref())? {
+            dry_run::record(json!({ "action": action, "id": entry.id, "category": entry.category, "content": entry.content }));
+        }
+    }
+    Ok(())
+}
+
+fn required_id(request: &StoreRequest) -> Result<&str, McpError> {
+    request.id.as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| McpError::invalid_params(format!("Missing id for {}", request.action), None))
+}
+
+/// `id` and `ids` together, without blanks and duplicates
+fn required_ids(request: &StoreRequest) -> Result<Vec<&str>, McpError> {
+    let mut ids: Vec<&str> = Vec::new();
+    for id in request.id.iter().chain(request.ids.iter()).map(|id| id.trim()) {
+        if !id.is_empty() && !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    if ids.is_empty() {
+        return Err(McpError::invalid_params(format!("Missing id or ids for {}", request.action), None));
+    }
+    Ok(ids)
+}
+
+/// `Archived 2 memories: a, b` plus the IDs that were skipped
+fn bulk_report(verb: &str, requested: &[&str], done: &[String]) -> String {
+    let mut text = format!("{} {} {}: {}", verb, done.len(), if done.len() == 1 { "memory" } else { "memories" }, done.join(", "));
+    let skipped: Vec<&str> = requested.iter().copied().filter(|id| !done.iter().any(|d| d == id)).collect();
+    if !skipped.is_empty() {
+        text.push_str(&format!("\nSkipped (not found or unchanged): {}", skipped.join(", ")));
+    }
+    text
+}
+
+/// First line of an entry, shortened for listings
+fn preview(content: &str) -> String {
+    let line = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    text::truncate(&line, PREVIEW_CHARS).into_owned()
+}
+
+fn format_list(entries: &[MemoryEntry], filter: MemoryFilter, archived: &HashSet<String>) -> String {
+    let label = match filter {
+        MemoryFilter::Active => "active",
+        MemoryFilter::Archived => "archived",
+        MemoryFilter::All => "stored",
+    };
+    if entries.is_empty() {
+        return format!("No {} memories.", label);
+    }
+    let mut text = format!("{} {} {}:\n", entries.len(), label, if entries.len() == 1 { "memory" } else { "memories" });
+    for entry in entries {
+        let flag = if archived.contains(&entry.id) { " (archived)" } else { "" };
+        text.push_str(&format!("\n- `{}` {:?}{}: {}", entry.id, entry.category, flag, preview(&entry.content)));
+    }
+    text
+}
+
+fn format_stale(stale: &[StaleMemory], days: u32) -> String {
+    if stale.is_empty() {
+        return format!("No memories unrecalled for more than {} days.", days);
+    }
+    let mut text = format!(
+        "{} {} not recalled in {} days:\n",
+        stale.len(),
+        if stale.len() == 1 { "memory" } else { "memories" },
+        days
+    );
+    for memory in stale {
+        let recalled = match memory.last_recalled_at {
+            Some(at) => format!("last recalled {}", at.format("%Y-%m-%d")),
+            None => "never recalled".to_string(),
+        };
+        text.push_str(&format!(
+            "\n- `{}` {:?}, idle {} days, {}: {}",
+            memory.entry.id,
+            memory.entry.category,
+            memory.idle_days,
+            recalled,
+            preview(&memory.entry.content)
+        ));
+    }
+    text.push_str("\n\nArchive them with action `archive` and `ids` (undo with `restore`), or remove them with `delete` and `ids`.");
+    text
+}
+
 /// Check if index tool is enabled
 fn is_index_enabled() -> bool {
     match crate::config::load_standalone_config() {
@@ -91,3 +303,92 @@ async fn try_trigger_background_index(project_root: &str) -> Result<()> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_project() -> TestDir {
+        let dir = TestDir::new("memory-dry-run");
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        dir
+    }
+
+    fn request(project: &Path, action: &str, extra: serde_json::Value) -> StoreRequest {
+        let mut request = json!({ "action": action, "project_path": project.to_string_lossy() });
+        request.as_object_mut().unwrap().extend(extra.as_object().cloned().unwrap_or_default());
+        serde_json::from_value(request).unwrap()
+    }
+
+    /// Every file below `dir` with its content
+    fn snapshot(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        let mut files = BTreeMap::new();
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(snapshot(&path));
+            } else {
+                files.insert(path.clone(), fs::read(&path).unwrap());
+            }
+        }
+        files
+    }
+
+    async fn dry_run(request: StoreRequest) -> CallToolResult {
+        dry_run::run(MemoryTool::store(request)).await.ok().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_changes_nothing_and_plans_the_writes() {
+        let project = temp_project();
+        let manager = MemoryManager::new(&project.to_string_lossy()).unwrap();
+        let first = manager.add_memory("Use tabs", MemoryCategory::Rule).unwrap();
+        let second = manager.add_memory("Run clippy before pushing", MemoryCategory::Rule).unwrap();
+        let rules = manager.memory_dir().join("rules.md").to_string_lossy().into_owned();
+        let sync = project.join("shared.json");
+        let before = snapshot(&project);
+
+        let stored = dry_run(request(&project, "store", json!({ "content": "Prefer small commits", "category": "rule" }))).await;
+        assert_eq!(stored.structured_content.as_ref().unwrap()["dry_run"]["changes"][0]["content"], "Prefer small commits");
+        assert!(dry_run::planned_files(&stored).contains_key(&rules));
+
+        let updated = dry_run(request(&project, "update", json!({ "id": first, "content": "Use spaces" }))).await;
+        let change = &updated.structured_content.as_ref().unwrap()["dry_run"]["changes"][0];
+        assert_eq!((change["before"].as_str(), change["content"].as_str()), (Some("Use tabs"), Some("Use spaces")));
+
+        let archived = dry_run(request(&project, "archive", json!({ "ids": [first, "missing"] }))).await;
+        assert_eq!(archived.structured_content.as_ref().unwrap()["dry_run"]["changes"][0]["id"], first.as_str());
+
+        let synced = dry_run(request(&project, "sync", json!({ "sync_path": sync.to_string_lossy() }))).await;
+        assert!(dry_run::planned_files(&synced).contains_key(sync.to_string_lossy().as_ref()));
+        assert!(format!("{:?}", synced.content).contains("Would sync with"));
+
+        let deleted = dry_run(request(&project, "delete", json!({ "ids": [first, second] }))).await;
+        let changes = deleted.structured_content.as_ref().unwrap()["dry_run"]["changes"].clone();
+        let contents: Vec<_> = changes.as_array().unwrap().iter().map(|change| change["content"].clone()).collect();
+        assert_eq!(contents, vec![json!("Use tabs"), json!("Run clippy before pushing")]);
+        assert_eq!(deleted.meta.as_ref().unwrap().get(dry_run::META_KEY), Some(&json!(true)));
+
+        // Nothing was written, and the plan matches what the real delete writes
+        assert_eq!(snapshot(&project), before);
+        assert!(!sync.exists());
+        assert_eq!(manager.get_all_memories().unwrap().len(), 2);
+        let planned = dry_run::planned_files(&deleted)[&rules];
+        MemoryTool::store(request(&project, "delete", json!({ "ids": [first, second] }))).await.ok().unwrap();
+        assert_eq!(fs::metadata(&rules).unwrap().len() as usize, planned);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_create_the_store() {
+        let project = temp_project();
+        let result = dry_run(request(&project, "store", json!({ "content": "First rule", "category": "rule" }))).await;
+
+        assert!(!project.join(".devkit-memory").exists());
+        let text = format!("{:?}", result.content);
+        assert!(text.contains("Would add memory") && text.contains("new file"), "{}", text);
+    }
+}
@@ -5,8 +5,12 @@
 pub mod manager;
 pub mod types;
 pub mod mcp;
+pub mod resources;
+pub mod sync;
 
 // 重新导出主要类型和功能
 pub use manager::MemoryManager;
-pub use types::{MemoryEntry, MemoryCategory, MemoryMetadata};
+pub use types::{MemoryEntry, MemoryCategory, MemoryFilter, MemoryMetadata, MemorySnapshot, StaleMemory};
 pub use mcp::MemoryTool;
+pub use resources::Subscriptions;
+pub use sync::{sync_store, SyncReport};
@@ -0,0 +1,242 @@
+//! Memories as MCP resources
+//!
+//! Every entry is addressable as `devkit://memory/{namespace}/{id}`, where the
+//! namespace identifies the project. Clients may subscribe to those URIs; when
+//! the store changes an entry, every session subscribed to it receives
+//! `notifications/resources/updated`.
+
+use once_cell::sync::Lazy;
+use rmcp::model::ResourceUpdatedNotificationParam;
+use rmcp::{Peer, RoleServer};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use crate::log_debug;
+
+/// URI prefix of memory resources
+pub const MEMORY_URI_PREFIX: &str = "devkit://memory/";
+/// Resource template advertised to clients
+pub const MEMORY_URI_TEMPLATE: &str = "devkit://memory/{namespace}/{id}";
+/// Subscriptions one session may hold
+pub const MAX_SUBSCRIPTIONS_PER_SESSION: usize = 64;
+
+/// URIs of changed entries, fanned out to every session
+static UPDATES: Lazy<broadcast::Sender<String>> = Lazy::new(|| broadcast::channel(256).0);
+
+/// Namespace -> project root of every store opened by this process
+static PROJECTS: Lazy<Mutex<HashMap<String, PathBuf>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn memory_uri(namespace: &str, id: &str) -> String {
+    format!("{}{}/{}", MEMORY_URI_PREFIX, namespace, id)
+}
+
+/// Split a memory URI into namespace and id
+pub fn parse_memory_uri(uri: &str) -> Option<(&str, &str)> {
+    let (namespace, id) = uri.strip_prefix(MEMORY_URI_PREFIX)?.split_once('/')?;
+    if namespace.is_empty() || id.is_empty() || id.contains('/') {
+        return None;
+    }
+    Some((namespace, id))
+}
+
+/// Announce that an entry was changed or removed
+pub fn publish_update(namespace: &str, id: &str) {
+    // A dry run changed nothing
+    if crate::mcp::dry_run::is_active() {
+        return;
+    }
+    // No receivers just means nobody is subscribed
+    let _ = UPDATES.send(memory_uri(namespace, id));
+}
+
+pub(crate) fn remember_project(namespace: &str, project_root: &Path) {
+    if let Ok(mut projects) = PROJECTS.lock() {
+        projects.insert(namespace.to_string(), project_root.to_path_buf());
+    }
+}
+
+/// Project root of a namespace seen by this process
+pub fn project_for_namespace(namespace: &str) -> Option<PathBuf> {
+    PROJECTS.lock().ok()?.get(namespace).cloned()
+}
+
+/// Namespaces seen by this process, with their project roots
+pub fn known_projects() -> Vec<(String, PathBuf)> {
+    let mut projects: Vec<_> = PROJECTS
+        .lock()
+        .map(|p| p.iter().map(|(ns, path)| (ns.clone(), path.clone())).collect())
+        .unwrap_or_default();
+    projects.sort();
+    projects
+}
+
+/// Subscribing would exceed [`MAX_SUBSCRIPTIONS_PER_SESSION`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionLimit(pub usize);
+
+impl std::fmt::Display for SubscriptionLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at most {} resource subscriptions per session", self.0)
+    }
+}
+
+/// Resource subscriptions of one session
+///
+/// The first subscription starts a task forwarding matching updates to the
+/// session's peer. It stops when the session's transport closes or when the
+/// subscriptions are dropped together with the session's server.
+#[derive(Debug)]
+pub struct Subscriptions {
+    uris: Mutex<HashSet<String>>,
+    limit: usize,
+    forwarding: AtomicBool,
+    closed: CancellationToken,
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Self::new(MAX_SUBSCRIPTIONS_PER_SESSION)
+    }
+}
+
+impl Drop for Subscriptions {
+    fn drop(&mut self) {
+        self.closed.cancel();
+    }
+}
+
+impl Subscriptions {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            uris: Mutex::new(HashSet::new()),
+            limit,
+            forwarding: AtomicBool::new(false),
+            closed: CancellationToken::new(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.uris.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record a subscription; repeating one is not an error
+    pub fn insert(&self, uri: &str) -> Result<(), SubscriptionLimit> {
+        let mut uris = self.lock();
+        if !uris.contains(uri) && uris.len() >= self.limit {
+            return Err(SubscriptionLimit(self.limit));
+        }
+        uris.insert(uri.to_string());
+        Ok(())
+    }
+
+    /// Record a subscription and make sure updates reach `peer`
+    pub fn subscribe(self: &Arc<Self>, uri: &str, peer: Peer<RoleServer>) -> Result<(), SubscriptionLimit> {
+        self.insert(uri)?;
+        if !self.forwarding.swap(true, Ordering::SeqCst) {
+            self.spawn_forwarder(peer);
+        }
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, uri: &str) -> bool {
+        self.lock().remove(uri)
+    }
+
+    pub fn contains(&self, uri: &str) -> bool {
+        self.lock().contains(uri)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Subscribed URIs, sorted
+    pub fn uris(&self) -> Vec<String> {
+        let mut uris: Vec<String> = self.lock().iter().cloned().collect();
+        uris.sort();
+        uris
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn spawn_forwarder(self: &Arc<Self>, peer: Peer<RoleServer>) {
+        let subscriptions = Arc::downgrade(self);
+        let closed = self.closed.clone();
+        let mut updates = UPDATES.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let uri = tokio::select! {
+                    _ = closed.cancelled() => break,
+                    update = updates.recv() => match update {
+                        Ok(uri) => uri,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            log_debug!("Memory resource forwarder skipped {} updates", skipped);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+                let Some(current) = subscriptions.upgrade() else { break };
+                if !current.contains(&uri) {
+                    continue;
+                }
+
+                let sent = !peer.is_transport_closed()
+                    && peer
+                        .notify_resource_updated(ResourceUpdatedNotificationParam { uri: uri.clone() })
+                        .await
+                        .is_ok();
+                if !sent {
+                    // Session is gone; its subscriptions go with it
+                    current.lock().clear();
+                    break;
+                }
+                log_debug!("Sent resource update for {}", uri);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_uri_round_trip() {
+        let uri = memory_uri("0123abcd", "f00-ba4");
+        assert_eq!(uri, "devkit://memory/0123abcd/f00-ba4");
+        assert_eq!(parse_memory_uri(&uri), Some(("0123abcd", "f00-ba4")));
+        assert_eq!(parse_memory_uri("devkit://memory/ns"), None);
+        assert_eq!(parse_memory_uri("devkit://memory/ns/a/b"), None);
+        assert_eq!(parse_memory_uri("file:///tmp/x"), None);
+    }
+
+    #[test]
+    fn test_subscription_cap() {
+        let subscriptions = Subscriptions::new(2);
+        assert!(subscriptions.insert("devkit://memory/ns/a").is_ok());
+        assert!(subscriptions.insert("devkit://memory/ns/b").is_ok());
+        // Repeating an existing subscription does not count against the cap
+        assert!(subscriptions.insert("devkit://memory/ns/a").is_ok());
+        assert_eq!(subscriptions.insert("devkit://memory/ns/c"), Err(SubscriptionLimit(2)));
+
+        assert!(subscriptions.unsubscribe("devkit://memory/ns/a"));
+        assert!(subscriptions.insert("devkit://memory/ns/c").is_ok());
+        assert_eq!(subscriptions.len(), 2);
+    }
+
+    #[test]
+    fn test_drop_stops_forwarding() {
+        let subscriptions = Subscriptions::default();
+        let closed = subscriptions.closed.clone();
+        drop(subscriptions);
+        assert!(closed.is_cancelled());
+    }
+}
@@ -0,0 +1,438 @@
+//! 记忆库跨机器同步
+//!
+//! 两台机器指向同一个同步文件（例如 Dropbox 或 git 仓库中的 JSON 快照）。
+//! 每次同步先把文件合并进本地记忆库，再把合并结果写回文件，因此双向反复同步会收敛：
+//! - 条目按 ID 对应，两边都有时保留 updated_at 较新的版本
+//! - 墓碑永久保留且优先于条目，已删除的条目不会被旧副本带回来
+//! - 同一条目自上次同步后在两边都被修改时记为冲突，被丢弃的版本写入 conflicts.md
+//!
+//! 试运行（`dry_run`）时所有写入都只暂存，结果中列出将要写入的文件
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::types::{MemoryEntry, MemorySnapshot};
+use super::MemoryManager;
+use crate::mcp::dry_run;
+
+/// 同步路径是目录时使用的文件名
+pub const SYNC_FILE_NAME: &str = "devkit-memory.json";
+/// 冲突记录文件（位于记忆目录）
+pub const CONFLICTS_FILE: &str = "conflicts.md";
+/// 每个同步文件上次同步的时间（位于记忆目录）
+const SYNC_STATE_FILE: &str = "sync-state.json";
+
+/// 同一条目在两边被改成不同内容
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyncConflict {
+    pub kept: MemoryEntry,
+    pub discarded: MemoryEntry,
+}
+
+/// 合并结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeOutcome {
+    pub snapshot: MemorySnapshot,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// 一次同步的统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SyncReport {
+    pub file: PathBuf,
+    /// 从同步文件新增到本地的条目
+    pub added: usize,
+    /// 被同步文件中较新版本覆盖的本地条目
+    pub updated: usize,
+    /// 因对方删除而从本地移除的条目
+    pub removed: usize,
+    /// 写入同步文件的本地新条目或新版本
+    pub exported: usize,
+    pub conflicts: usize,
+}
+
+impl SyncReport {
+    /// 试运行时的说明：将会同步的内容
+    pub fn planned(&self) -> String {
+        self.to_string().replacen("Synced with", "Would sync with", 1)
+    }
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Synced with {}: {} added, {} updated, {} removed locally, {} exported",
+            self.file.display(),
+            self.added,
+            self.updated,
+            self.removed,
+            self.exported
+        )?;
+        if self.conflicts > 0 {
+            write!(f, "; {} conflicts, discarded versions logged to {}", self.conflicts, CONFLICTS_FILE)?;
+        }
+        Ok(())
+    }
+}
+
+/// 较新的版本；时间相同时按内容和分类决定，保证两边选出同一个
+fn newer<'a>(a: &'a MemoryEntry, b: &'a MemoryEntry) -> (&'a MemoryEntry, &'a MemoryEntry) {
+    let key = |e: &'a MemoryEntry| (e.updated_at, e.content.as_str(), format!("{:?}", e.category));
+    if key(a) >= key(b) {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// 合并两份快照
+///
+/// `since` 为上次与这份远端同步的时间，用来区分“对方修改了”和“两边都修改了”；
+/// 从未同步过时内容不同即视为冲突。结果中本地条目保持原顺序，远端独有的条目追加在后面。
+pub fn merge(local: &MemorySnapshot, remote: &MemorySnapshot, since: Option<DateTime<Utc>>) -> MergeOutcome {
+    let mut tombstones = local.tombstones.clone();
+    for (id, deleted_at) in remote.tombstones.iter() {
+        let at = tombstones.entry(id.clone()).or_insert(*deleted_at);
+        *at = (*at).max(*deleted_at);
+    }
+
+    let index = |entries: &[MemoryEntry]| {
+        let mut map: HashMap<String, MemoryEntry> = HashMap::new();
+        for entry in entries {
+            map.entry(entry.id.clone()).or_insert_with(|| entry.clone());
+        }
+        map
+    };
+    let (local_by_id, remote_by_id) = (index(&local.entries), index(&remote.entries));
+
+    let mut entries = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut seen = HashSet::new();
+    for entry in local.entries.iter().chain(remote.entries.iter()) {
+        if tombstones.contains_key(&entry.id) || !seen.insert(entry.id.clone()) {
+            continue;
+        }
+        let winner = match (local_by_id.get(&entry.id), remote_by_id.get(&entry.id)) {
+            (Some(mine), Some(theirs)) => {
+                let (kept, discarded) = newer(mine, theirs);
+                let differs = mine.content != theirs.content || mine.category != theirs.category;
+                let both_changed = since.is_none_or(|t| mine.updated_at >= t && theirs.updated_at >= t);
+                if differs && both_changed {
+                    conflicts.push(SyncConflict { kept: kept.clone(), discarded: discarded.clone() });
+                }
+                kept
+            }
+            (Some(only), None) | (None, Some(only)) => only,
+            (None, None) => continue,
+        };
+        entries.push(winner.clone());
+    }
+
+    MergeOutcome { snapshot: MemorySnapshot { entries, tombstones }, conflicts }
+}
+
+/// 同步路径对应的文件：目录时使用其中的 devkit-memory.json
+///
+/// 所在目录会被规范化，保证相对路径和绝对路径记录的是同一份同步状态
+pub fn sync_file(path: &Path) -> PathBuf {
+    let file = if path.is_dir() { path.join(SYNC_FILE_NAME) } else { path.to_path_buf() };
+    match (file.parent().and_then(|p| p.canonicalize().ok()), file.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => file,
+    }
+}
+
+/// 与同步文件双向同步
+pub fn sync_store(manager: &MemoryManager, path: &Path) -> Result<SyncReport> {
+    if manager.is_in_memory() {
+        anyhow::bail!("记忆库只保存在内存中（配置目录不可写），无法同步");
+    }
+    let file = sync_file(path);
+    let remote: MemorySnapshot = if file.exists() {
+        let text = fs::read_to_string(&file).with_context(|| format!("无法读取同步文件 {}", file.display()))?;
+        // 保留出错字段的路径，便于定位损坏的条目
+        serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(&text))
+            .with_context(|| format!("同步文件格式无效: {}", file.display()))?
+    } else {
+        MemorySnapshot::default()
+    };
+
+    // 与记忆文件中的时间精度一致（毫秒）
+    let started = Utc::now().trunc_subsecs(3);
+    let state_key = file.to_string_lossy().to_string();
+    let mut state = load_sync_state(manager)?;
+    let local = manager.snapshot()?;
+    let outcome = merge(&local, &remote, state.get(&state_key).copied());
+
+    let report = SyncReport {
+        file: file.clone(),
+        added: count_missing(&outcome.snapshot, &local),
+        updated: count_changed(&outcome.snapshot, &local),
+        removed: count_missing(&local, &outcome.snapshot),
+        exported: count_missing(&outcome.snapshot, &remote) + count_changed(&outcome.snapshot, &remote),
+        conflicts: outcome.conflicts.len(),
+    };
+
+    manager.apply_snapshot(&outcome.snapshot)?;
+    log_conflicts(manager, &file, &outcome.conflicts)?;
+
+    if let Some(parent) = file.parent().filter(|p| !p.as_os_str().is_empty()) {
+        dry_run::create_dir_all(parent)?;
+    }
+    let merged = serde_json::to_string_pretty(&outcome.snapshot)?;
+    if dry_run::is_active() {
+        dry_run::write(&file, merged)?;
+    } else {
+        // 先写临时文件再改名，避免同步盘读到写了一半的文件
+        let temp = file.with_extension("json.tmp");
+        fs::write(&temp, merged)?;
+        fs::rename(&temp, &file)?;
+    }
+
+    state.insert(state_key, started);
+    dry_run::write(&manager.memory_dir().join(SYNC_STATE_FILE), serde_json::to_string_pretty(&state)?)?;
+
+    Ok(report)
+}
+
+/// `from` 中有而 `to` 中没有的条目数
+fn count_missing(from: &MemorySnapshot, to: &MemorySnapshot) -> usize {
+    let ids: HashSet<&str> = to.entries.iter().map(|e| e.id.as_str()).collect();
+    from.entries.iter().filter(|e| !ids.contains(e.id.as_str())).count()
+}
+
+/// 两边都有但内容不同的条目数
+fn count_changed(merged: &MemorySnapshot, other: &MemorySnapshot) -> usize {
+    let other: HashMap<&str, &MemoryEntry> = other.entries.iter().map(|e| (e.id.as_str(), e)).collect();
+    merged
+        .entries
+        .iter()
+        .filter(|e| other.get(e.id.as_str()).is_some_and(|o| *o != *e))
+        .count()
+}
+
+fn load_sync_state(manager: &MemoryManager) -> Result<BTreeMap<String, DateTime<Utc>>> {
+    let path = manager.memory_dir().join(SYNC_STATE_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// 把被丢弃的版本追加到 conflicts.md
+fn log_conflicts(manager: &MemoryManager, file: &Path, conflicts: &[SyncConflict]) -> Result<()> {
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let time = |t: DateTime<Utc>| t.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut text = String::new();
+    for conflict in conflicts {
+        text.push_str(&format!(
+            "- {} 与 {} 同步，条目 {}：保留 {} 的版本「{}」，丢弃 {} 的版本「{}」\n",
+            time(Utc::now()),
+            file.display(),
+            conflict.kept.id,
+            time(conflict.kept.updated_at),
+            conflict.kept.content,
+            time(conflict.discarded.updated_at),
+            conflict.discarded.content
+        ));
+    }
+    dry_run::append(&manager.memory_dir().join(CONFLICTS_FILE), "# 同步冲突记录\n\n", &text)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use crate::mcp::tools::memory::MemoryCategory;
+    use chrono::Duration;
+
+    /// 简单的 xorshift，测试可重复
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    fn at(tick: i64) -> DateTime<Utc> {
+        DateTime::UNIX_EPOCH + Duration::seconds(1_700_000_000 + tick)
+    }
+
+    fn entry(id: &str, content: &str, tick: i64) -> MemoryEntry {
+        MemoryEntry {
+            id: id.to_string(),
+            content: content.to_string(),
+            category: MemoryCategory::Context,
+            created_at: at(tick),
+            updated_at: at(tick),
+        }
+    }
+
+    /// 按 ID 排序后的条目，顺序不影响等价性
+    fn normalized(snapshot: &MemorySnapshot) -> (Vec<MemoryEntry>, BTreeMap<String, DateTime<Utc>>) {
+        let mut entries = snapshot.entries.clone();
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        (entries, snapshot.tombstones.clone())
+    }
+
+    struct Replica {
+        store: MemorySnapshot,
+        last_sync: Option<DateTime<Utc>>,
+    }
+
+    /// 与 sync_store 相同的流程，只是不落盘
+    fn sync(replica: &mut Replica, remote: &mut MemorySnapshot, tick: i64) -> MergeOutcome {
+        let outcome = merge(&replica.store, remote, replica.last_sync);
+        replica.store = outcome.snapshot.clone();
+        *remote = outcome.snapshot.clone();
+        replica.last_sync = Some(at(tick));
+        outcome
+    }
+
+    #[test]
+    fn test_random_histories_converge_without_resurrection() {
+        for seed in 1..=200u64 {
+            let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1);
+            let mut replicas: Vec<Replica> = (0..3).map(|_| Replica { store: MemorySnapshot::default(), last_sync: None }).collect();
+            let mut remote = MemorySnapshot::default();
+            let mut deleted = HashSet::new();
+            let mut next_id = 0;
+
+            for tick in 0..60 {
+                let r = rng.below(replicas.len());
+                let replica = &mut replicas[r];
+                match rng.below(5) {
+                    0 => {
+                        next_id += 1;
+                        replica.store.entries.push(entry(&format!("m{}", next_id), &format!("v{}", tick), tick));
+                    }
+                    1 | 2 if !replica.store.entries.is_empty() => {
+                        let i = rng.below(replica.store.entries.len());
+                        let edited = &mut replica.store.entries[i];
+                        edited.content = format!("{} edited on {} at {}", edited.id, r, tick);
+                        edited.updated_at = at(tick);
+                    }
+                    3 if !replica.store.entries.is_empty() => {
+                        let i = rng.below(replica.store.entries.len());
+                        let gone = replica.store.entries.remove(i);
+                        replica.store.tombstones.insert(gone.id.clone(), at(tick));
+                        deleted.insert(gone.id);
+                    }
+                    _ => {
+                        sync(replica, &mut remote, tick);
+                    }
+                }
+            }
+
+            // 每台机器再同步两轮后全部一致
+            for round in 0..2 {
+                for replica in replicas.iter_mut() {
+                    sync(replica, &mut remote, 100 + round);
+                }
+            }
+            let expected = normalized(&remote);
+            for replica in replicas.iter() {
+                assert_eq!(normalized(&replica.store), expected, "seed {}", seed);
+            }
+            assert!(expected.0.iter().all(|e| !deleted.contains(&e.id)), "seed {} resurrected an entry", seed);
+            assert!(deleted.iter().all(|id| expected.1.contains_key(id)));
+
+            // 已收敛后再同步不产生变化，也不再报告冲突
+            let again = sync(&mut replicas[0], &mut remote, 200);
+            assert_eq!(normalized(&again.snapshot), expected);
+            assert!(again.conflicts.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_merge_is_commutative_and_idempotent() {
+        let a = MemorySnapshot {
+            entries: vec![entry("1", "a", 5), entry("2", "shared", 1), entry("3", "a-only", 2)],
+            tombstones: BTreeMap::from([("4".to_string(), at(3))]),
+        };
+        let b = MemorySnapshot {
+            entries: vec![entry("1", "b", 7), entry("2", "shared", 1), entry("4", "stale", 1)],
+            tombstones: BTreeMap::from([("3".to_string(), at(9))]),
+        };
+        let ab = merge(&a, &b, None);
+        let ba = merge(&b, &a, None);
+        assert_eq!(normalized(&ab.snapshot), normalized(&ba.snapshot));
+        assert_eq!(normalized(&merge(&ab.snapshot, &ab.snapshot, None).snapshot), normalized(&ab.snapshot));
+
+        let (entries, tombstones) = normalized(&ab.snapshot);
+        assert_eq!(entries, vec![entry("1", "b", 7), entry("2", "shared", 1)]);
+        assert_eq!(tombstones.keys().collect::<Vec<_>>(), vec!["3", "4"]);
+        assert_eq!(ab.conflicts.len(), 1);
+        assert_eq!(ab.conflicts[0].discarded.content, "a");
+    }
+
+    #[test]
+    fn test_one_sided_edit_is_not_a_conflict() {
+        let mine = MemorySnapshot { entries: vec![entry("1", "old", 1)], ..Default::default() };
+        let theirs = MemorySnapshot { entries: vec![entry("1", "new", 5)], ..Default::default() };
+        let outcome = merge(&mine, &theirs, Some(at(2)));
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.snapshot.entries, vec![entry("1", "new", 5)]);
+    }
+
+    #[test]
+    fn test_sync_between_two_stores_on_disk() {
+        let project = || {
+            let dir = TestDir::new("sync");
+            fs::create_dir_all(dir.join(".git")).unwrap();
+            dir
+        };
+        let (desktop_dir, laptop_dir, shared) = (project(), project(), project());
+        let desktop = MemoryManager::new(&desktop_dir.to_string_lossy()).unwrap();
+        let laptop = MemoryManager::new(&laptop_dir.to_string_lossy()).unwrap();
+
+        let kept = desktop.add_memory("两边都保留", MemoryCategory::Rule).unwrap();
+        let doomed = desktop.add_memory("稍后删除", MemoryCategory::Context).unwrap();
+        let report = sync_store(&desktop, &shared).unwrap();
+        assert_eq!(report.exported, 2);
+        let report = sync_store(&laptop, &shared).unwrap();
+        assert_eq!(report.added, 2);
+        assert_eq!(laptop.get_memory(&kept).unwrap().unwrap().category, MemoryCategory::Rule);
+
+        // 桌面删除，笔记本修改同一条目之外的内容
+        assert!(desktop.delete_memory(&doomed).unwrap());
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        laptop.update_memory(&kept, "笔记本的版本").unwrap().unwrap();
+        sync_store(&desktop, &shared).unwrap();
+        let report = sync_store(&laptop, &shared).unwrap();
+        assert_eq!(report.removed, 1);
+        sync_store(&desktop, &shared).unwrap();
+
+        assert!(laptop.get_memory(&doomed).unwrap().is_none());
+        assert!(desktop.get_memory(&doomed).unwrap().is_none());
+        assert_eq!(desktop.get_memory(&kept).unwrap().unwrap().content, "笔记本的版本");
+
+        // 两边都修改同一条目：保留较新的，另一个写入冲突记录
+        desktop.update_memory(&kept, "桌面的修改").unwrap().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        laptop.update_memory(&kept, "笔记本的修改").unwrap().unwrap();
+        sync_store(&laptop, &shared).unwrap();
+        let report = sync_store(&desktop, &shared).unwrap();
+        assert_eq!(report.conflicts, 1);
+        assert_eq!(desktop.get_memory(&kept).unwrap().unwrap().content, "笔记本的修改");
+        let log = fs::read_to_string(desktop.memory_dir().join(CONFLICTS_FILE)).unwrap();
+        assert!(log.contains("桌面的修改"));
+    }
+}
@@ -1,8 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// 记忆条目结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct MemoryEntry {
     pub id: String,
     pub content: String,
@@ -28,3 +29,33 @@ pub struct MemoryMetadata {
     pub total_entries: usize,
     pub version: String,
 }
+
+/// 记忆库的完整快照，用于在多台机器之间同步
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MemorySnapshot {
+    #[serde(default)]
+    pub entries: Vec<MemoryEntry>,
+    /// 已删除条目的 ID 及删除时间；墓碑永久保留，防止旧副本把条目带回来
+    #[serde(default)]
+    pub tombstones: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// 按归档状态筛选记忆
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemoryFilter {
+    /// 未归档的条目（参与召回）
+    #[default]
+    Active,
+    Archived,
+    All,
+}
+
+/// 长时间未被召回的记忆
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleMemory {
+    pub entry: MemoryEntry,
+    /// 最近一次召回时间，从未召回过时为 None
+    pub last_recalled_at: Option<DateTime<Utc>>,
+    /// 距最近一次召回或修改的天数
+    pub idle_days: i64,
+}
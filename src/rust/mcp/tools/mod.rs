@@ -4,10 +4,22 @@
 pub mod memory;
 pub mod interaction;
 pub mod acemcp;
+pub mod project_docs;
+pub mod command;
+pub mod git;
+pub mod sessions;
+pub mod reload;
+#[cfg(feature = "docs")]
 pub mod docs;
 
 // 重新导出工具以便访问
 pub use memory::MemoryTool;
 pub use interaction::InteractionTool;
 pub use acemcp::AcemcpTool;
-pub use docs::DocsTool;
+pub use project_docs::ProjectDocsTool;
+pub use command::RunCommandTool;
+pub use git::GitContextTool;
+pub use sessions::ListSessionsTool;
+pub use reload::ReloadConfigTool;
+#[cfg(feature = "docs")]
+pub use docs::{DocsCompareTool, DocsSearchTool, DocsStatsTool, DocsStatusTool, DocsTool};
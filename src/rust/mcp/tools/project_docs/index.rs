@@ -0,0 +1,549 @@
+//! Section index over a project's own documentation
+//!
+//! Files matched by the configured globs are split into sections: markdown by
+//! headings, Rust sources by doc comment blocks. Indexes are built on first use
+//! per project and kept in memory; each later lookup re-stats the matched files
+//! and re-parses only the ones whose mtime or size changed.
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::utils::text;
+
+/// Globs used when `mcp_config.project_docs_globs` is not set
+pub const DEFAULT_GLOBS: &[&str] = &[
+    "README*",
+    "*.md",
+    "docs/**/*.md",
+    "docs/**/*.markdown",
+    "docs/**/*.txt",
+    "src/**/*.rs",
+    "crates/*/src/**/*.rs",
+];
+
+/// Files larger than this are skipped
+pub const MAX_FILE_BYTES: u64 = 512 * 1024;
+/// Files indexed per project
+pub const MAX_FILES: usize = 2_000;
+/// Sections indexed per project
+pub const MAX_SECTIONS: usize = 20_000;
+/// Section text kept per project
+pub const MAX_INDEX_BYTES: usize = 16 * 1024 * 1024;
+
+/// Bytes inspected for NUL when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// One searchable piece of documentation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DocSection {
+    /// Path relative to the project root, with forward slashes
+    pub path: String,
+    /// Heading chain (`Install > Linux`) or documented item
+    pub title: String,
+    /// First line of the section, 1-based
+    pub start_line: usize,
+    /// Last line of the section, inclusive
+    pub end_line: usize,
+    /// Section body without the heading line or comment markers
+    pub text: String,
+}
+
+/// A section matching a query
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub section: DocSection,
+    pub score: u32,
+    /// Lines around the first match
+    pub excerpt: String,
+}
+
+/// Counters describing an index
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct IndexStats {
+    pub files: usize,
+    pub sections: usize,
+    pub bytes: usize,
+    /// Files skipped as binary, oversized or not UTF-8
+    pub skipped_files: usize,
+    /// Whether a size limit stopped indexing early
+    pub truncated: bool,
+    /// Files parsed by the last refresh (0 when everything was reused)
+    pub reparsed_files: usize,
+}
+
+#[derive(Debug, Clone)]
+struct FileEntry {
+    modified: Option<SystemTime>,
+    len: u64,
+    /// `None` for files that were skipped
+    sections: Option<Arc<Vec<DocSection>>>,
+}
+
+/// Indexed documentation of one project
+#[derive(Debug, Clone, Default)]
+pub struct ProjectIndex {
+    globs: Vec<String>,
+    files: HashMap<PathBuf, FileEntry>,
+    sections: Vec<DocSection>,
+    stats: IndexStats,
+}
+
+impl ProjectIndex {
+    pub fn sections(&self) -> &[DocSection] {
+        &self.sections
+    }
+
+    pub fn stats(&self) -> IndexStats {
+        self.stats
+    }
+
+    /// Rank sections by keyword matches; sections matching more distinct terms come first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let terms = query_terms(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let phrase = query.trim().to_lowercase();
+
+        let mut hits: Vec<(usize, SearchHit)> = self
+            .sections
+            .iter()
+            .filter_map(|section| {
+                let (matched, score) = score_section(section, &terms, &phrase);
+                (matched > 0).then(|| {
+                    let hit = SearchHit {
+                        section: section.clone(),
+                        score,
+                        excerpt: excerpt(&section.text, &terms),
+                    };
+                    (matched, hit)
+                })
+            })
+            .collect();
+
+        hits.sort_by(|(a_matched, a), (b_matched, b)| {
+            b_matched
+                .cmp(a_matched)
+                .then(b.score.cmp(&a.score))
+                .then_with(|| a.section.path.cmp(&b.section.path))
+                .then(a.section.start_line.cmp(&b.section.start_line))
+        });
+        hits.into_iter().take(limit).map(|(_, hit)| hit).collect()
+    }
+}
+
+static INDEXES: Lazy<Mutex<HashMap<PathBuf, Arc<ProjectIndex>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Index (or refresh the cached index of) the documentation under `root`
+pub fn index_project_docs(root: &Path, globs: &[String]) -> Result<Arc<ProjectIndex>> {
+    let root = root
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve project path {}", root.display()))?;
+
+    let mut indexes = INDEXES.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = indexes.remove(&root).filter(|index| index.globs == globs);
+    let index = Arc::new(build_index(&root, globs, previous.as_deref())?);
+    indexes.insert(root, index.clone());
+    Ok(index)
+}
+
+fn build_index(root: &Path, globs: &[String], previous: Option<&ProjectIndex>) -> Result<ProjectIndex> {
+    let matcher = build_globset(globs)?;
+    let mut previous = previous.map(|index| index.files.clone()).unwrap_or_default();
+
+    let mut candidates: Vec<(PathBuf, String)> = ignore::WalkBuilder::new(root)
+        .hidden(true)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?.to_string_lossy().replace('\\', "/");
+            matcher.is_match(&relative).then(|| (entry.into_path(), relative))
+        })
+        .collect();
+    // Stable order so truncation keeps the same files across refreshes
+    candidates.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let mut index = ProjectIndex { globs: globs.to_vec(), ..Default::default() };
+    for (path, relative) in candidates {
+        if index.stats.files >= MAX_FILES {
+            index.stats.truncated = true;
+            break;
+        }
+        let Ok(metadata) = std::fs::metadata(&path) else { continue };
+        let modified = metadata.modified().ok();
+        let len = metadata.len();
+
+        let entry = match previous.remove(&path) {
+            Some(entry) if entry.modified == modified && entry.len == len && modified.is_some() => entry,
+            _ => {
+                index.stats.reparsed_files += 1;
+                let sections = read_doc_file(&path, len)?.map(|content| Arc::new(split_file(&relative, &content)));
+                FileEntry { modified, len, sections }
+            }
+        };
+
+        let Some(sections) = &entry.sections else {
+            index.stats.skipped_files += 1;
+            index.files.insert(path, entry);
+            continue;
+        };
+        let bytes: usize = sections.iter().map(|s| s.text.len()).sum();
+        if index.sections.len() + sections.len() > MAX_SECTIONS || index.stats.bytes + bytes > MAX_INDEX_BYTES {
+            // Smaller files later on may still fit
+            index.stats.truncated = true;
+            index.files.insert(path, entry);
+            continue;
+        }
+        index.stats.files += 1;
+        index.stats.bytes += bytes;
+        index.sections.extend(sections.iter().cloned());
+        index.files.insert(path, entry);
+    }
+    index.stats.sections = index.sections.len();
+    Ok(index)
+}
+
+fn build_globset(globs: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        // `*` stays within one directory so `*.md` means top-level files only
+        let compiled = GlobBuilder::new(glob).literal_separator(true).build();
+        builder.add(compiled.with_context(|| format!("Invalid project docs glob '{}'", glob))?);
+    }
+    Ok(builder.build()?)
+}
+
+/// File content, or `None` for oversized, binary and non UTF-8 files
+fn read_doc_file(path: &Path, len: u64) -> Result<Option<String>> {
+    if len > MAX_FILE_BYTES {
+        return Ok(None);
+    }
+    let mut bytes = Vec::with_capacity(len as usize);
+    match std::fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)) {
+        Ok(_) => {}
+        // Deleted or unreadable between the walk and the read
+        Err(_) => return Ok(None),
+    }
+    if bytes.iter().take(BINARY_SNIFF_BYTES).any(|b| *b == 0) {
+        return Ok(None);
+    }
+    Ok(String::from_utf8(bytes).ok())
+}
+
+fn split_file(relative: &str, content: &str) -> Vec<DocSection> {
+    if relative.ends_with(".rs") {
+        split_rust_docs(relative, content)
+    } else {
+        split_markdown(relative, content)
+    }
+}
+
+/// Split markdown at ATX headings outside fenced code blocks
+pub fn split_markdown(path: &str, content: &str) -> Vec<DocSection> {
+    let mut sections = Vec::new();
+    // Open headings as (level, text)
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut title = path.to_string();
+    let mut start = 1;
+    let mut body: Vec<&str> = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    // Text before the first heading only counts when it is not blank
+    let mut flush = |title: &str, start: usize, end: usize, body: &mut Vec<&str>, preamble: bool| {
+        let text = body.join("\n").trim().to_string();
+        body.clear();
+        if !(preamble && text.is_empty()) {
+            sections.push(DocSection { path: path.to_string(), title: title.to_string(), start_line: start, end_line: end.max(start), text });
+        }
+    };
+
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            body.push(line);
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            body.push(line);
+            continue;
+        }
+        match heading(line) {
+            Some((level, text)) => {
+                flush(&title, start, i, &mut body, headings.is_empty());
+                headings.retain(|(open, _)| *open < level);
+                headings.push((level, text.to_string()));
+                title = headings.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>().join(" > ");
+                start = i + 1;
+            }
+            None => body.push(line),
+        }
+    }
+    flush(&title, start, content.lines().count(), &mut body, headings.is_empty());
+    sections
+}
+
+/// `## Title` → (2, "Title"); at most three leading spaces as in CommonMark
+fn heading(line: &str) -> Option<(usize, &str)> {
+    if line.len() - line.trim_start_matches(' ').len() > 3 {
+        return None;
+    }
+    let line = line.trim_start_matches(' ');
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim();
+    (!text.is_empty()).then_some((level, text))
+}
+
+/// One section per block of consecutive `//!` or `///` lines
+pub fn split_rust_docs(path: &str, content: &str) -> Vec<DocSection> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut sections = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some((inner, _)) = doc_line(lines[i]) else {
+            i += 1;
+            continue;
+        };
+        let start = i;
+        let mut body = Vec::new();
+        while let Some((_, text)) = lines.get(i).and_then(|line| doc_line(line)).filter(|(k, _)| *k == inner) {
+            body.push(text);
+            i += 1;
+        }
+        let text = body.join("\n").trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+        let title = if inner {
+            format!("{} (module docs)", path)
+        } else {
+            documented_item(&lines[i..]).unwrap_or_else(|| path.to_string())
+        };
+        sections.push(DocSection { path: path.to_string(), title, start_line: start + 1, end_line: i, text });
+    }
+    sections
+}
+
+/// `//! text` → (true, "text"), `/// text` → (false, "text"); `////` is a plain comment
+fn doc_line(line: &str) -> Option<(bool, &str)> {
+    let trimmed = line.trim_start();
+    let (inner, rest) = if let Some(rest) = trimmed.strip_prefix("//!") {
+        (true, rest)
+    } else if let Some(rest) = trimmed.strip_prefix("///") {
+        if rest.starts_with('/') {
+            return None;
+        }
+        (false, rest)
+    } else {
+        return None;
+    };
+    Some((inner, rest.strip_prefix(' ').unwrap_or(rest)))
+}
+
+/// First line of the item after an outer doc comment, skipping attributes
+fn documented_item(rest: &[&str]) -> Option<String> {
+    rest.iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with("#[") && !line.starts_with("//"))
+        .map(|line| line.trim_end_matches('{').trim_end().to_string())
+}
+
+/// Lowercased words of a query; single characters are dropped unless CJK
+fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.chars().count() > 1 || !word.is_ascii())
+        .map(|word| word.to_lowercase())
+        .collect();
+    terms.dedup();
+    terms
+}
+
+/// (distinct terms matched, score): title and path hits weigh more than body hits
+fn score_section(section: &DocSection, terms: &[String], phrase: &str) -> (usize, u32) {
+    let title = section.title.to_lowercase();
+    let path = section.path.to_lowercase();
+    let text = section.text.to_lowercase();
+
+    let mut matched = 0;
+    let mut score = 0;
+    for term in terms {
+        let in_title = title.contains(term.as_str());
+        let in_path = path.contains(term.as_str());
+        let in_body = text.matches(term.as_str()).count().min(5) as u32;
+        if in_title || in_path || in_body > 0 {
+            matched += 1;
+        }
+        score += if in_title { 10 } else { 0 } + if in_path { 3 } else { 0 } + in_body * 2;
+    }
+    if terms.len() > 1 && (title.contains(phrase) || text.contains(phrase)) {
+        score += 15;
+    }
+    (matched, score)
+}
+
+/// Up to ten lines starting just before the first line that mentions a term
+fn excerpt(text: &str, terms: &[String]) -> String {
+    const BEFORE: usize = 2;
+    const LINES: usize = 10;
+    const MAX_CHARS: usize = 800;
+
+    let lines: Vec<&str> = text.lines().collect();
+    let first = lines
+        .iter()
+        .position(|line| {
+            let line = line.to_lowercase();
+            terms.iter().any(|term| line.contains(term.as_str()))
+        })
+        .unwrap_or(0);
+    let start = first.saturating_sub(BEFORE);
+    let end = (start + LINES).min(lines.len());
+
+    let mut excerpt = lines[start..end].join("\n");
+    if let Cow::Owned(cut) = text::truncate(&excerpt, MAX_CHARS) {
+        excerpt = cut;
+    } else if end < lines.len() {
+        excerpt.push_str("\n…");
+    }
+    excerpt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use std::fs;
+
+    fn fixture() -> TestDir {
+        let root = TestDir::new("project-docs");
+        fs::create_dir_all(root.join("docs/guide")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("README.md"),
+            "# Devkit\n\nA toolkit.\n\n## Install\n\nRun the installer.\n\n```sh\n# not a heading\ncargo install devkit\n```\n\n## Usage\n\nStart the server.\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("docs/guide/sync.md"),
+            "# Memory sync\n\nSync merges memory stores between machines.\n\n## Conflicts\n\nConflicting memory edits keep both versions.\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "//! Crate root.\n//! Mentions memory once.\n\nuse std::fs;\n\n/// Merge two memory stores.\n#[inline]\npub fn merge() {}\n\n// plain comment\nfn private() {}\n",
+        )
+        .unwrap();
+        fs::write(root.join("docs/logo.md"), b"\x89PNG\r\n\x1a\n\0\0\0binary").unwrap();
+        fs::write(root.join("notes.txt"), "not matched by any glob").unwrap();
+        root
+    }
+
+    fn default_globs() -> Vec<String> {
+        DEFAULT_GLOBS.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_indexes_markdown_and_doc_comments() {
+        let root = fixture();
+        let index = index_project_docs(&root, &default_globs()).unwrap();
+
+        let find = |title: &str| index.sections().iter().find(|s| s.title == title).cloned().unwrap();
+        let install = find("Devkit > Install");
+        assert_eq!((install.path.as_str(), install.start_line, install.end_line), ("README.md", 5, 13));
+        assert!(install.text.contains("# not a heading"));
+        assert_eq!(find("Devkit").text, "A toolkit.");
+        assert_eq!(find("Memory sync > Conflicts").path, "docs/guide/sync.md");
+
+        let module = find("src/lib.rs (module docs)");
+        assert_eq!((module.start_line, module.end_line), (1, 2));
+        let item = find("pub fn merge() {}");
+        assert_eq!((item.start_line, item.end_line, item.text.as_str()), (6, 6, "Merge two memory stores."));
+
+        let stats = index.stats();
+        assert_eq!(stats.files, 3);
+        assert_eq!(stats.skipped_files, 1);
+        assert!(!index.sections().iter().any(|s| s.path.ends_with(".txt") || s.path.ends_with("logo.md")));
+    }
+
+    #[test]
+    fn test_refresh_reparses_only_edited_files() {
+        let root = fixture();
+        let globs = default_globs();
+        assert_eq!(index_project_docs(&root, &globs).unwrap().stats().reparsed_files, 4);
+        assert_eq!(index_project_docs(&root, &globs).unwrap().stats().reparsed_files, 0);
+
+        let sync = root.join("docs/guide/sync.md");
+        fs::write(&sync, "# Memory sync\n\n## Retention\n\nOld entries expire after a year.\n").unwrap();
+        // Same-second writes can keep the mtime, so move it explicitly
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        fs::File::options().write(true).open(&sync).unwrap().set_modified(later).unwrap();
+
+        let index = index_project_docs(&root, &globs).unwrap();
+        assert_eq!(index.stats().reparsed_files, 1);
+        assert!(index.sections().iter().any(|s| s.title == "Memory sync > Retention"));
+        assert!(!index.sections().iter().any(|s| s.title == "Memory sync > Conflicts"));
+
+        fs::remove_file(&sync).unwrap();
+        let index = index_project_docs(&root, &globs).unwrap();
+        assert!(!index.sections().iter().any(|s| s.path == "docs/guide/sync.md"));
+
+        // Changing the globs rebuilds from scratch
+        let index = index_project_docs(&root, &["*.md".to_string()]).unwrap();
+        assert_eq!((index.stats().files, index.stats().reparsed_files), (1, 1));
+    }
+
+    #[test]
+    fn test_search_ranks_by_terms_then_weight() {
+        let root = fixture();
+        let index = index_project_docs(&root, &default_globs()).unwrap();
+
+        let hits = index.search("memory conflicts", 10);
+        let titles: Vec<&str> = hits.iter().map(|h| h.section.title.as_str()).collect();
+        // Both terms, one in the title, ranks above body-only single-term hits
+        assert_eq!(titles[..2], ["Memory sync > Conflicts", "Memory sync"]);
+        assert!(hits[1..].windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(titles.contains(&"pub fn merge() {}"));
+        assert!(hits[0].excerpt.contains("keep both versions"));
+
+        let hits = index.search("install", 10);
+        assert_eq!(hits[0].section.title, "Devkit > Install");
+        assert_eq!(index.search("memory", 1).len(), 1);
+        assert!(index.search("a", 10).is_empty());
+        assert!(index.search("kubernetes", 10).is_empty());
+    }
+
+    #[test]
+    fn test_index_size_is_bounded() {
+        let root = TestDir::new("project-docs");
+        fs::write(root.join("big.md"), "x".repeat(MAX_FILE_BYTES as usize + 1)).unwrap();
+        fs::write(root.join("many.md"), "# h\n".repeat(MAX_SECTIONS + 1)).unwrap();
+        fs::write(root.join("small.md"), "# Small\n\nfits\n").unwrap();
+
+        let index = index_project_docs(&root, &["*.md".to_string()]).unwrap();
+        let stats = index.stats();
+        assert!(stats.truncated);
+        assert_eq!(stats.skipped_files, 1);
+        assert!(stats.sections <= MAX_SECTIONS);
+        assert!(index.sections().iter().any(|s| s.title == "Small"));
+    }
+}
@@ -0,0 +1,145 @@
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError, Tool, ToolAnnotations};
+use serde_json::json;
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::index::{index_project_docs, IndexStats, SearchHit, DEFAULT_GLOBS};
+use super::types::ProjectDocsRequest;
+use crate::mcp::utils::{decode_and_normalize_path, project_path_error, validate_project_path, ToolError};
+use crate::log_debug;
+
+/// Allowed values of the `limit` parameter
+pub const LIMIT_RANGE: RangeInclusive<usize> = 1..=20;
+
+/// Sections returned when `limit` is not given
+const DEFAULT_LIMIT: usize = 5;
+
+/// Search over the project's own README, docs/ and doc comments
+pub struct ProjectDocsTool;
+
+impl ProjectDocsTool {
+    pub async fn search(request: ProjectDocsRequest) -> Result<CallToolResult, ToolError> {
+        if let Err(e) = validate_project_path(&request.project_root_path) {
+            return Err(project_path_error(format!(
+                "Path validation failed: {}\nOriginal path: {}",
+                e, request.project_root_path
+            ))
+            .into());
+        }
+        let limit = request.limit.unwrap_or(DEFAULT_LIMIT);
+        if !LIMIT_RANGE.contains(&limit) {
+            return Err(McpError::invalid_params(
+                format!("limit must be between {} and {}", LIMIT_RANGE.start(), LIMIT_RANGE.end()),
+                Some(json!({ "path": "limit" })),
+            )
+            .into());
+        }
+        if request.query.trim().is_empty() {
+            return Err(McpError::invalid_params("query must not be empty", Some(json!({ "path": "query" }))).into());
+        }
+
+        let root = PathBuf::from(decode_and_normalize_path(&request.project_root_path)?);
+        let globs = Self::configured_globs();
+        let query = request.query.clone();
+        let (hits, stats) = tokio::task::spawn_blocking(move || {
+            let index = index_project_docs(&root, &globs)?;
+            anyhow::Ok((index.search(&query, limit), index.stats()))
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("Project docs indexing task failed: {}", e))??;
+
+        log_debug!(
+            "Project docs search: query={}, hits={}, files={}, sections={}, reparsed={}",
+            request.query, hits.len(), stats.files, stats.sections, stats.reparsed_files
+        );
+
+        Ok(CallToolResult {
+            content: vec![Content::text(format_hits(&request.query, &hits, &stats))],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(json!({
+                "project_root_path": request.project_root_path,
+                "query": request.query,
+                "results": hits,
+                "index": stats,
+            })),
+        })
+    }
+
+    /// `mcp_config.project_docs_globs`, falling back to the defaults
+    pub(crate) fn configured_globs() -> Vec<String> {
+        crate::config::load_standalone_config()
+            .ok()
+            .and_then(|config| config.mcp_config.project_docs_globs)
+            .filter(|globs| !globs.is_empty())
+            .unwrap_or_else(|| DEFAULT_GLOBS.iter().map(|glob| glob.to_string()).collect())
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "project_root_path": {
+                    "type": "string",
+                    "description": "Absolute path to the project root directory using forward slashes"
+                },
+                "query": {
+                    "type": "string",
+                    "description": "Keywords to look for in the project's README, docs/ folder and doc comments"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": LIMIT_RANGE.start(),
+                    "maximum": LIMIT_RANGE.end(),
+                    "description": "Maximum number of sections to return, defaults to 5"
+                }
+            },
+            "required": ["project_root_path", "query"]
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("project_docs"),
+                description: Some(Cow::Borrowed("Search the current project's own documentation (README, docs/, doc comments) without network access. Returns matching sections with file paths and line ranges to cite.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Project Docs".to_string()),
+                    read_only_hint: Some(true),
+                    destructive_hint: Some(false),
+                    idempotent_hint: Some(true),
+                    open_world_hint: Some(false),    // Local files only
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Project Docs".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+/// Markdown listing of the hits, each as `path:start-end` plus an excerpt
+pub(crate) fn format_hits(query: &str, hits: &[SearchHit], stats: &IndexStats) -> String {
+    let mut text = format!("# Project docs: {}\n\n", query);
+    if hits.is_empty() {
+        text.push_str(&format!(
+            "No sections matched. Indexed {} files ({} sections).\n",
+            stats.files, stats.sections
+        ));
+    }
+    for hit in hits {
+        let section = &hit.section;
+        text.push_str(&format!(
+            "## {}\n`{}:{}-{}`\n\n{}\n\n",
+            section.title, section.path, section.start_line, section.end_line, hit.excerpt
+        ));
+    }
+    if stats.truncated {
+        text.push_str("_Index size limit reached; some files were not indexed._\n");
+    }
+    text
+}
@@ -0,0 +1,7 @@
+pub mod index;
+pub mod mcp;
+pub mod types;
+
+pub use index::{index_project_docs, DocSection, IndexStats, ProjectIndex, SearchHit};
+pub use mcp::ProjectDocsTool;
+pub use types::ProjectDocsRequest;
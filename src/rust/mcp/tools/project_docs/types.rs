@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// project_docs 工具请求参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectDocsRequest {
+    /// 项目根目录的绝对路径
+    pub project_root_path: String,
+    /// 搜索关键词
+    pub query: String,
+    /// 返回的最大章节数，默认 5
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
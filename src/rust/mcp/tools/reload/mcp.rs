@@ -0,0 +1,89 @@
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError, Tool, ToolAnnotations};
+use serde_json::json;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::config::{live, ConfigReload};
+use crate::mcp::utils::ToolError;
+
+/// Re-reads the config file and applies it to the running server
+pub struct ReloadConfigTool;
+
+impl ReloadConfigTool {
+    pub fn run() -> Result<CallToolResult, ToolError> {
+        let reload = live::reload_and_log("reload_config called").map_err(|e| {
+            McpError::internal_error(format!("Config not reloaded, the current config stays in effect: {}", e), None)
+        })?;
+
+        Ok(CallToolResult {
+            content: vec![Content::text(format_reload(&reload))],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(json!(reload)),
+        })
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {}
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("reload_config"),
+                description: Some(Cow::Borrowed("Reload the devkit config file now instead of waiting for the file watcher. Tool switches, API keys, rate and concurrency limits and the log level apply to the next call; lists the settings that changed and those that need a restart.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Reload Config".to_string()),
+                    read_only_hint: Some(false),     // Replaces the running config
+                    destructive_hint: Some(false),
+                    idempotent_hint: Some(true),     // Reloading an unchanged file changes nothing
+                    open_world_hint: Some(false),    // Only this process
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Reload Config".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+fn format_reload(reload: &ConfigReload) -> String {
+    let mut text = "# Config reloaded\n\n".to_string();
+    if reload.changed.is_empty() {
+        text.push_str("- Changed: nothing\n");
+    } else {
+        text.push_str(&format!("- Changed: {}\n", reload.changed.join(", ")));
+    }
+    if !reload.restart_required.is_empty() {
+        text.push_str(&format!("- Needs a restart to apply: {}\n", reload.restart_required.join(", ")));
+    }
+    text.push_str(&format!("- Log level: {}\n", reload.log_level));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_reload() {
+        let reload = ConfigReload {
+            changed: vec!["http_bind".to_string(), "tools".to_string()],
+            restart_required: vec!["http_bind".to_string()],
+            log_level: "debug".to_string(),
+        };
+        let text = format_reload(&reload);
+        assert!(text.contains("- Changed: http_bind, tools\n"));
+        assert!(text.contains("- Needs a restart to apply: http_bind\n"));
+        assert!(text.contains("- Log level: debug\n"));
+
+        let unchanged = format_reload(&ConfigReload { log_level: "info".to_string(), ..Default::default() });
+        assert!(unchanged.contains("- Changed: nothing\n"));
+        assert!(!unchanged.contains("restart"));
+    }
+}
@@ -0,0 +1,8 @@
+//! reload_config 管理工具
+//!
+//! 立即重新读取配置文件并应用到运行中的服务（平时由文件监听自动完成），
+//! 返回有变化的配置项以及其中需要重启才生效的项
+
+pub mod mcp;
+
+pub use mcp::ReloadConfigTool;
@@ -0,0 +1,223 @@
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError, Tool, ToolAnnotations};
+use serde_json::json;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::types::{ListSessionsRequest, SessionAction};
+use crate::mcp::sessions::{ClientSession, SessionInfo, SessionManager};
+use crate::mcp::utils::ToolError;
+
+/// Connected clients of this process, with cancellation and per-session rate limits
+pub struct ListSessionsTool;
+
+impl ListSessionsTool {
+    /// `caller` is the session making the call, `configured` the rate limit from the config
+    pub fn run(request: ListSessionsRequest, caller: &ClientSession, configured: Option<u32>) -> Result<CallToolResult, ToolError> {
+        Self::run_with(SessionManager::global(), request, caller, configured)
+    }
+
+    fn run_with(
+        manager: &SessionManager,
+        request: ListSessionsRequest,
+        caller: &ClientSession,
+        configured: Option<u32>,
+    ) -> Result<CallToolResult, ToolError> {
+        let target = match request.action {
+            SessionAction::List => None,
+            SessionAction::Cancel | SessionAction::RateLimit => {
+                let id = request.session.as_deref().ok_or_else(|| {
+                    McpError::invalid_params("session is required for cancel and rate_limit".to_string(), Some(json!({ "path": "session" })))
+                })?;
+                let session = manager.find(id).ok_or_else(|| {
+                    McpError::invalid_params(format!("No connected session with id {}", id), Some(json!({ "path": "session" })))
+                })?;
+                Some(session)
+            }
+        };
+
+        let mut text = match (request.action, &target) {
+            (SessionAction::Cancel, Some(session)) => {
+                if std::ptr::eq(Arc::as_ptr(session), caller) {
+                    return Err(McpError::invalid_params(
+                        "A session cannot cancel itself".to_string(),
+                        Some(json!({ "path": "session" })),
+                    )
+                    .into());
+                }
+                manager.cancel(&session.id());
+                format!("Session {} cancelled: running calls were aborted and new ones are refused.\n", session.id())
+            }
+            (SessionAction::RateLimit, Some(session)) => {
+                session.set_rate_limit(request.per_minute);
+                let limit = session.info(configured).rate_limit_per_minute;
+                format!("Session {} rate limit: {}\n", session.id(), format_limit(limit))
+            }
+            _ => String::new(),
+        };
+
+        let sessions: Vec<SessionInfo> = manager.sessions().iter().map(|session| session.info(configured)).collect();
+        let caller_id = caller.id();
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&format_sessions(&sessions, &caller_id));
+
+        Ok(CallToolResult {
+            content: vec![Content::text(text)],
+            is_error: Some(false),
+            meta: None,
+            structured_content: Some(json!({
+                "action": request.action,
+                "caller": caller_id,
+                "sessions": sessions,
+            })),
+        })
+    }
+
+    pub fn get_tool_definition() -> Tool {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": ["list", "cancel", "rate_limit"],
+                    "description": "list (default): connected sessions with client, connect time and call counts; cancel: abort the running calls of `session` and refuse new ones; rate_limit: set the calls per minute of `session`"
+                },
+                "session": {
+                    "type": "string",
+                    "description": "Session id as returned by list (required for cancel and rate_limit)"
+                },
+                "per_minute": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "rate_limit: calls per minute, 0 for unlimited; omit to restore the configured limit"
+                }
+            }
+        });
+
+        if let serde_json::Value::Object(schema_map) = schema {
+            Tool {
+                name: Cow::Borrowed("list_sessions"),
+                description: Some(Cow::Borrowed("List the MCP clients connected to this server (session id, client name and version, connect time, calls per tool, rate limit). Administrators can cancel a session or change its rate limit.")),
+                input_schema: Arc::new(schema_map),
+                annotations: Some(ToolAnnotations {
+                    title: Some("Sessions".to_string()),
+                    read_only_hint: Some(false),     // cancel and rate_limit change sessions
+                    destructive_hint: Some(true),    // cancel aborts running calls
+                    idempotent_hint: Some(false),
+                    open_world_hint: Some(false),    // Only this process
+                }),
+                icons: None,
+                meta: None,
+                output_schema: None,
+                title: Some("Sessions".to_string()),
+            }
+        } else {
+            panic!("Schema creation failed");
+        }
+    }
+}
+
+fn format_limit(limit: Option<u32>) -> String {
+    match limit {
+        Some(limit) => format!("{} calls per minute", limit),
+        None => "unlimited".to_string(),
+    }
+}
+
+fn format_sessions(sessions: &[SessionInfo], caller: &str) -> String {
+    if sessions.is_empty() {
+        return "# Sessions\n\nNo connected sessions\n".to_string();
+    }
+    let mut text = format!("# Sessions\n\n{} connected\n\n", sessions.len());
+    for session in sessions {
+        let client = match (&session.client_name, &session.client_version) {
+            (Some(name), Some(version)) => format!("{} {}", name, version),
+            (Some(name), None) => name.clone(),
+            _ => "unknown client".to_string(),
+        };
+        let marker = if session.id == caller { " (this session)" } else { "" };
+        let cancelled = if session.cancelled { ", cancelled" } else { "" };
+        text.push_str(&format!("## {}{}\n\n", session.id, marker));
+        text.push_str(&format!("- Client: {} on endpoint {}{}\n", client, session.endpoint, cancelled));
+        if let Some(connected_at) = session.connected_at {
+            text.push_str(&format!("- Connected: {}\n", connected_at.to_rfc3339()));
+        }
+        let by_tool: Vec<String> = session.calls_by_tool.iter().map(|(tool, calls)| format!("{} {}", tool, calls)).collect();
+        if by_tool.is_empty() {
+            text.push_str("- Calls: none\n");
+        } else {
+            text.push_str(&format!("- Calls: {} ({})\n", session.calls, by_tool.join(", ")));
+        }
+        text.push_str(&format!("- Rate limit: {}", format_limit(session.rate_limit_per_minute)));
+        if session.rate_limited > 0 {
+            text.push_str(&format!(", {} call(s) refused", session.rate_limited));
+        }
+        text.push_str("\n\n");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::sessions::{ClientDetails, SESSION_CANCELLED};
+
+    fn register(manager: &SessionManager, session_id: &str) -> Arc<ClientSession> {
+        let session = Arc::new(ClientSession::default());
+        let details = ClientDetails {
+            session_id: Some(session_id.to_string()),
+            endpoint: "default".to_string(),
+            client_name: Some("windsurf".to_string()),
+            client_version: Some("1.2".to_string()),
+        };
+        manager.register(&session, details);
+        session
+    }
+
+    fn request(action: SessionAction, session: Option<&str>, per_minute: Option<u32>) -> ListSessionsRequest {
+        ListSessionsRequest { action, session: session.map(str::to_string), per_minute }
+    }
+
+    #[test]
+    fn test_lists_sessions_with_counts() {
+        let manager = SessionManager::default();
+        let caller = register(&manager, "one");
+        let other = register(&manager, "two");
+        other.begin_call("docs", None).unwrap();
+
+        let result = ListSessionsTool::run_with(&manager, ListSessionsRequest::default(), &caller, Some(30)).unwrap();
+        let structured = result.structured_content.unwrap();
+        assert_eq!(structured["caller"], "one");
+        assert_eq!(structured["sessions"][1]["calls_by_tool"]["docs"], 1);
+        assert_eq!(structured["sessions"][1]["rate_limit_per_minute"], 30);
+        let text = result.content[0].as_text().unwrap().text.clone();
+        assert!(text.contains("## one (this session)"));
+        assert!(text.contains("windsurf 1.2"));
+    }
+
+    #[test]
+    fn test_cancel_and_rate_limit_another_session() {
+        let manager = SessionManager::default();
+        let caller = register(&manager, "one");
+        let other = register(&manager, "two");
+
+        ListSessionsTool::run_with(&manager, request(SessionAction::RateLimit, Some("two"), Some(5)), &caller, None).unwrap();
+        assert_eq!(other.info(None).rate_limit_per_minute, Some(5));
+
+        ListSessionsTool::run_with(&manager, request(SessionAction::Cancel, Some("two"), None), &caller, None).unwrap();
+        assert_eq!(other.begin_call("docs", None).unwrap_err().code, SESSION_CANCELLED);
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_own_session() {
+        let manager = SessionManager::default();
+        let caller = register(&manager, "one");
+
+        let missing = ListSessionsTool::run_with(&manager, request(SessionAction::Cancel, Some("nope"), None), &caller, None);
+        assert!(matches!(missing, Err(ToolError::Rpc(e)) if e.data.as_ref().unwrap()["path"] == "session"));
+        let own = ListSessionsTool::run_with(&manager, request(SessionAction::Cancel, Some("one"), None), &caller, None);
+        assert!(own.is_err());
+        assert!(!caller.cancellation().is_cancelled());
+    }
+}
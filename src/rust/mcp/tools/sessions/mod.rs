@@ -0,0 +1,10 @@
+//! list_sessions 管理工具
+//!
+//! 列出当前进程中已连接的 MCP 会话（客户端信息、连接时间、调用次数），
+//! 并可取消某个会话或调整其速率限制
+
+pub mod mcp;
+pub mod types;
+
+pub use mcp::ListSessionsTool;
+pub use types::{ListSessionsRequest, SessionAction};
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// list_sessions 支持的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionAction {
+    /// 列出所有会话（默认）
+    #[default]
+    List,
+    /// 中止会话正在运行的调用，并拒绝其后续调用
+    Cancel,
+    /// 设置会话每分钟允许的调用次数
+    RateLimit,
+}
+
+/// list_sessions 工具请求参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListSessionsRequest {
+    /// 操作类型
+    #[serde(default)]
+    pub action: SessionAction,
+    /// cancel 与 rate_limit：目标会话 ID（list 返回的 id）
+    #[serde(default)]
+    pub session: Option<String>,
+    /// rate_limit：每分钟调用次数，0 表示不限制，省略则恢复配置文件中的值
+    #[serde(default)]
+    pub per_minute: Option<u32>,
+}
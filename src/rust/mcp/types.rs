@@ -1,6 +1,8 @@
 use chrono;
 use serde::{Deserialize, Serialize};
 
+use crate::mcp::tools::interaction::DiffView;
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct CacheRequest {
     #[schemars(description = "The content to display")]
@@ -17,6 +19,10 @@ pub struct CacheRequest {
     #[schemars(description = "Project root path for context")]
     #[serde(default)]
     pub project_root_path: Option<String>,
+
+    #[schemars(description = "Optional unified diff to review, shown above the choices")]
+    #[serde(default)]
+    pub diff: Option<String>,
 }
 
 fn default_chalkboard() -> bool {
@@ -25,11 +31,11 @@ fn default_chalkboard() -> bool {
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct StoreRequest {
-    #[schemars(description = "Operation type: store (add entry), recall (get project info)")]
+    #[schemars(description = "Operation type: store (add entry), recall (get project info), update (replace the content of entry `id`), delete (remove entry `id` or entries `ids`), sync (merge with the store file at `sync_path`), list (entries with their IDs, see `filter`), review_stale (entries not recalled in `stale_days` days), archive (exclude `id`/`ids` from recall but keep them), restore (undo archive)")]
     pub action: String,
     #[schemars(description = "Project path (required)")]
     pub project_path: String,
-    #[schemars(description = "Entry content (required for store operation)")]
+    #[schemars(description = "Entry content (required for store and update operations)")]
     #[serde(default)]
     pub content: String,
     #[schemars(
@@ -37,6 +43,21 @@ pub struct StoreRequest {
     )]
     #[serde(default = "default_category")]
     pub category: String,
+    #[schemars(description = "Entry ID as returned by store (required for update and delete operations)")]
+    #[serde(default)]
+    pub id: Option<String>,
+    #[schemars(description = "Entry IDs for bulk delete, archive and restore")]
+    #[serde(default)]
+    pub ids: Vec<String>,
+    #[schemars(description = "review_stale: days without recall after which an entry is listed (default 60)")]
+    #[serde(default)]
+    pub stale_days: Option<u32>,
+    #[schemars(description = "list: active (default), archived or all")]
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[schemars(description = "Sync file or folder shared between machines (required for sync operation)")]
+    #[serde(default)]
+    pub sync_path: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -58,6 +79,8 @@ pub struct PopupRequest {
     pub menu: Option<Vec<String>>,
     pub chalkboard: bool,
     pub project_root_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<DiffView>,
 }
 
 /// Structured response data format
@@ -1,6 +1,8 @@
-/// MCP error handling utilities
+//! MCP error handling utilities
 
-use rmcp::model::ErrorData as McpError;
+use rmcp::model::{CallToolResult, Content, ErrorData as McpError};
+
+use crate::config::OfflineError;
 
 /// MCP tool error types
 #[derive(Debug, thiserror::Error)]
@@ -51,6 +53,39 @@ impl From<McpToolError> for McpError {
     }
 }
 
+/// Failure of a tool call, mapped to a JSON-RPC error by the server
+///
+/// Tools return [`ToolError::Rpc`] for errors they have already classified and
+/// [`ToolError::Failed`] for everything else; see `mcp::boundary`.
+#[derive(Debug)]
+pub enum ToolError {
+    Rpc(McpError),
+    Failed(anyhow::Error),
+}
+
+impl From<McpError> for ToolError {
+    fn from(error: McpError) -> Self {
+        ToolError::Rpc(error)
+    }
+}
+
+impl From<anyhow::Error> for ToolError {
+    fn from(error: anyhow::Error) -> Self {
+        ToolError::Failed(error)
+    }
+}
+
+impl From<McpToolError> for ToolError {
+    fn from(error: McpToolError) -> Self {
+        match error {
+            McpToolError::Io(e) => ToolError::Failed(e.into()),
+            McpToolError::Json(e) => ToolError::Failed(e.into()),
+            McpToolError::Generic(e) => ToolError::Failed(e),
+            other => ToolError::Rpc(other.into()),
+        }
+    }
+}
+
 pub fn project_path_error(msg: impl Into<String>) -> McpToolError {
     McpToolError::ProjectPath(msg.into())
 }
@@ -66,3 +101,13 @@ pub fn response_error(msg: impl Into<String>) -> McpToolError {
 pub fn memory_error(msg: impl Into<String>) -> McpToolError {
     McpToolError::Memory(msg.into())
 }
+
+/// Tool result for an operation refused in offline mode
+pub fn offline_result(error: &OfflineError) -> CallToolResult {
+    CallToolResult {
+        content: vec![Content::text(error.to_string())],
+        is_error: Some(true),
+        meta: None,
+        structured_content: Some(error.to_json()),
+    }
+}
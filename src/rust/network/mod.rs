@@ -4,6 +4,7 @@
 pub mod geo;
 pub mod proxy;
 pub mod client;
+#[cfg(feature = "gui")]
 pub mod commands;
 
 pub use geo::detect_geo_location;
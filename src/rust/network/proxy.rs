@@ -2,19 +2,14 @@
 use serde::{Deserialize, Serialize};
 
 /// 代理类型
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum ProxyType {
+    #[default]
     Http,
     Socks5,
 }
 
-impl Default for ProxyType {
-    fn default() -> Self {
-        ProxyType::Http
-    }
-}
-
 impl std::fmt::Display for ProxyType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -64,6 +64,12 @@ pub struct UpdateProgress {
 pub async fn check_for_updates(app: AppHandle, state: State<'_, AppState>) -> Result<UpdateInfo, String> {
     log::info!("🔍 开始检查更新");
 
+    // 离线模式下跳过更新检查
+    if let Err(e) = crate::config::offline_mode().check("update check") {
+        log::info!("⏭️ {}", e);
+        return Err(e.to_string());
+    }
+
     // 第一步：检测地理位置（用于网络状态展示）
     let geo_info = detect_geo_location_full().await;
     log::info!("🌍 地理位置检测完成: country={}, city={:?}",
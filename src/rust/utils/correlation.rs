@@ -0,0 +1,92 @@
+//! 请求关联 ID
+//!
+//! HTTP 服务为每个请求分配一个 ID（客户端带了合法的 `X-Request-Id` 时沿用它），
+//! 工具调用在 [`scope`] 中以该 ID 运行，日志行自动带上当前 ID，一次代理交互可以从
+//! HTTP 请求一直追到工具内部的日志。作用域之外（CLI、启动日志）没有 ID。
+
+use std::future::Future;
+
+tokio::task_local! {
+    static CURRENT: String;
+}
+
+/// 携带关联 ID 的 HTTP 头
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 沿用的客户端 ID 的最大长度
+const MAX_ID_LEN: usize = 128;
+
+/// 新的关联 ID：12 位十六进制，足够在日志中区分请求
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..12].to_string()
+}
+
+/// 客户端提供的 ID 是否可以沿用：非空、不超过 128 个字符、只含字母数字与 `-_.:`
+pub fn accept(id: &str) -> Option<&str> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    valid.then_some(id)
+}
+
+/// 当前作用域的关联 ID
+pub fn current() -> Option<String> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+/// 以 `id` 运行 `future`；嵌套的作用域优先
+pub async fn scope<F: Future>(id: String, future: F) -> F::Output {
+    CURRENT.scope(id, future).await
+}
+
+/// 以 `id` 运行同步的 `f`，例如在请求结束后记录它的日志
+pub fn sync_scope<R>(id: String, f: impl FnOnce() -> R) -> R {
+    CURRENT.sync_scope(id, f)
+}
+
+/// 让 `future` 继承当前的关联 ID，用于 `tokio::spawn` 出去的任务
+///
+/// ID 在调用时读取，而不是在任务第一次被轮询时（那时已不在原来的作用域内）。
+pub fn inherit<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let id = current();
+    async move {
+        match id {
+            Some(id) => scope(id, future).await,
+            None => future.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_client_ids() {
+        assert_eq!(accept("req-42_a.b:c"), Some("req-42_a.b:c"));
+        assert_eq!(accept(""), None);
+        assert_eq!(accept("has space"), None);
+        assert_eq!(accept("line\nbreak"), None);
+        assert_eq!(accept(&"a".repeat(129)), None);
+        assert_eq!(new_id().len(), 12);
+    }
+
+    #[tokio::test]
+    async fn test_spawned_task_inherits_id() {
+        assert_eq!(current(), None);
+        let seen = scope("abc".to_string(), async { tokio::spawn(inherit(async { current() })).await.unwrap() }).await;
+        assert_eq!(seen.as_deref(), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn test_id_is_read_when_inherit_is_called() {
+        // Created inside the scope, first polled after it ended
+        let task = sync_scope("created".to_string(), || inherit(async { current() }));
+        assert_eq!(current(), None);
+        assert_eq!(task.await.as_deref(), Some("created"));
+
+        // Created outside any scope, polled inside one: the scope where it runs still applies
+        let task = inherit(async { current() });
+        assert_eq!(scope("polled".to_string(), task).await.as_deref(), Some("polled"));
+    }
+}
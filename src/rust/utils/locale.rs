@@ -0,0 +1,277 @@
+//! 输出语言与消息表
+//!
+//! 面向客户端的文字（错误信息、用量提示等）按当前语言从 [`Message`] 表中取出。
+//! 语言由调用所在的作用域决定：MCP 服务在处理每次工具调用时用 [`scope`] 设置会话语言，
+//! 作用域之外（CLI、测试）使用默认的英文。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+
+tokio::task_local! {
+    static CURRENT: Locale;
+}
+
+/// 支持的输出语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Locale {
+    /// 解析语言标签（如 `zh-CN`、`en_US`、`zh-Hans`），不支持的语言返回 `None`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" => Some(Self::En),
+            "zh" => Some(Self::Zh),
+            _ => None,
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Zh => "zh",
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+/// 当前作用域的语言，作用域外为英文
+pub fn current() -> Locale {
+    CURRENT.try_with(|locale| *locale).unwrap_or_default()
+}
+
+/// 在 `locale` 下运行 `future`；嵌套的作用域优先
+pub async fn scope<F: Future>(locale: Locale, future: F) -> F::Output {
+    CURRENT.scope(locale, future).await
+}
+
+/// 消息表中的条目
+#[derive(Debug, Clone, Copy)]
+pub enum Message<'a> {
+    /// 工具参数不符合 schema
+    InvalidArguments { tool: &'a str, error: &'a str, path: &'a str },
+    /// Docs 上游用量提示
+    QuotaFooter { requests: u64, remaining: Option<u64> },
+    /// Docs 回答的标题
+    DocsTitle { library: &'a str },
+    /// Docs 回答标题下的查询主题
+    DocsTopic { topic: &'a str },
+    /// Docs 回答标题下的版本
+    DocsVersion { version: &'a str },
+    /// Docs 回答标题下的页码
+    DocsPage { page: u32 },
+    /// 还有下一页时的提示
+    DocsNextPage { current: u32, total: u32 },
+    /// max_pages 合并了多页
+    DocsMergedPages { first: u32, last: u32 },
+    /// max_snippets 限制了片段数
+    DocsShowing { shown: usize, total: usize },
+    /// max_tokens 省略了片段
+    DocsOmittedByBudget { shown: usize, total: usize, max_tokens: u32, omitted: usize },
+    /// max_tokens 截断了片段
+    DocsSnippetCut,
+    /// max_bytes 截断了片段
+    DocsSnippetCutToSize { max_bytes: u32 },
+    /// max_bytes 省略了片段，其后列出省略片段的标题
+    DocsOmittedBySize { shown: usize, max_bytes: u32, omitted: usize },
+    /// 省略片段索引中未列出的数量
+    DocsMoreOmitted { count: usize },
+    /// 去掉了重复的片段
+    DocsDuplicatesRemoved { count: usize },
+    /// Docs 回答末尾的来源
+    DocsSource { library: &'a str },
+    /// auto 模式改用了说明文档
+    DocsAutoFallback { min_snippets: usize },
+    /// 库标识符已迁移
+    DocsMoved { from: &'a str, to: &'a str },
+    /// 没有查到文档
+    DocsNoDocumentation,
+    /// 库不存在
+    DocsNotFound { library: &'a str },
+    /// 库不存在且没有候选时的格式说明
+    DocsCheckIdentifier,
+    /// 库不存在且没有候选时的搜索提示
+    DocsSearchTip { website: &'a str },
+    /// 库不存在时的候选列表标题
+    DocsSuggestions,
+    /// 候选列表后的调用示例标题
+    DocsUseFullIdentifier,
+    /// Docs 查询失败
+    DocsQueryFailed { error: &'a str },
+    /// 多个来源合并的回答的标题
+    DocsMergedTitle { query: &'a str },
+    /// 合并回答中查询失败的来源列表的标题
+    DocsWarnings,
+    /// 项目文档查询失败
+    DocsProjectUnavailable { error: &'a str },
+    /// 库文档查询失败
+    DocsLibraryUnavailable { error: &'a str },
+    /// 合并回答中项目文档一节的标题
+    DocsProjectSection,
+    /// 项目文档没有匹配的章节
+    DocsNoProjectMatches,
+    /// 合并回答中库文档一节的标题；没有查到文档时不带库名
+    DocsLibrarySection { library: Option<&'a str> },
+}
+
+impl Message<'_> {
+    /// 当前语言下的文字
+    pub fn text(&self) -> String {
+        self.text_in(current())
+    }
+
+    pub fn text_in(&self, locale: Locale) -> String {
+        match (self, locale) {
+            (Self::InvalidArguments { tool, error, path }, Locale::En) => {
+                format!("Invalid arguments for {}: {} at {}", tool, error, path)
+            }
+            (Self::InvalidArguments { tool, error, path }, Locale::Zh) => {
+                format!("{} 的参数无效：{}（位置 {}）", tool, error, path)
+            }
+            (Self::QuotaFooter { requests, remaining }, Locale::En) => {
+                let mut text = format!("_This session: {} request{}", requests, if *requests == 1 { "" } else { "s" });
+                if let Some(remaining) = remaining {
+                    text.push_str(&format!("; ~{} remaining before rate limit", remaining));
+                }
+                text + "._"
+            }
+            (Self::QuotaFooter { requests, remaining }, Locale::Zh) => {
+                let mut text = format!("_本会话已请求 {} 次", requests);
+                if let Some(remaining) = remaining {
+                    text.push_str(&format!("，距离限流约剩 {} 次", remaining));
+                }
+                text + "。_"
+            }
+            (Self::DocsTitle { library }, Locale::En) => format!("# {} Documentation", library),
+            (Self::DocsTitle { library }, Locale::Zh) => format!("# {} 文档", library),
+            (Self::DocsTopic { topic }, Locale::En) => format!("**Topic**: {}", topic),
+            (Self::DocsTopic { topic }, Locale::Zh) => format!("**主题**：{}", topic),
+            (Self::DocsVersion { version }, Locale::En) => format!("**Version**: {}", version),
+            (Self::DocsVersion { version }, Locale::Zh) => format!("**版本**：{}", version),
+            (Self::DocsPage { page }, Locale::En) => format!("**Page**: {}", page),
+            (Self::DocsPage { page }, Locale::Zh) => format!("**页码**：{}", page),
+            (Self::DocsNextPage { current, total }, Locale::En) => {
+                format!("_Page {}/{}, request page {} for more._", current, total, current.saturating_add(1))
+            }
+            (Self::DocsNextPage { current, total }, Locale::Zh) => {
+                format!("_第 {}/{} 页，请求第 {} 页查看更多。_", current, total, current.saturating_add(1))
+            }
+            (Self::DocsMergedPages { first, last }, Locale::En) => format!("_Merged pages {}-{}._", first, last),
+            (Self::DocsMergedPages { first, last }, Locale::Zh) => format!("_已合并第 {}-{} 页。_", first, last),
+            (Self::DocsShowing { shown, total }, Locale::En) => format!("_Showing {} of {} snippets._", shown, total),
+            (Self::DocsShowing { shown, total }, Locale::Zh) => format!("_显示 {} 个片段，共 {} 个。_", shown, total),
+            (Self::DocsOmittedByBudget { shown, total, max_tokens, omitted }, Locale::En) => format!(
+                "_Showing {} of {} snippets to stay within max_tokens ({}); {} omitted, raise max_tokens to see more._",
+                shown, total, max_tokens, omitted
+            ),
+            (Self::DocsOmittedByBudget { shown, total, max_tokens, omitted }, Locale::Zh) => format!(
+                "_为不超过 max_tokens（{}），显示 {} 个片段，共 {} 个；省略了 {} 个，调高 max_tokens 可查看更多。_",
+                max_tokens, shown, total, omitted
+            ),
+            (Self::DocsSnippetCut, Locale::En) => "_[snippet cut to fit max_tokens]_".to_string(),
+            (Self::DocsSnippetCut, Locale::Zh) => "_[片段已截断以符合 max_tokens]_".to_string(),
+            (Self::DocsSnippetCutToSize { max_bytes }, Locale::En) => format!("_[snippet cut to stay within {} bytes]_", max_bytes),
+            (Self::DocsSnippetCutToSize { max_bytes }, Locale::Zh) => format!("_[片段已截断以不超过 {} 字节]_", max_bytes),
+            (Self::DocsOmittedBySize { shown, max_bytes, omitted }, Locale::En) => format!(
+                "_Showing {} snippet{} to stay within {} bytes; {} omitted. To read them, call docs again with the same library and topic and `titles` set to any of:_",
+                shown,
+                if *shown == 1 { "" } else { "s" },
+                max_bytes,
+                omitted
+            ),
+            (Self::DocsOmittedBySize { shown, max_bytes, omitted }, Locale::Zh) => format!(
+                "_为不超过 {} 字节，显示 {} 个片段，省略了 {} 个。如需查看，请用相同的 library 与 topic 再次调用 docs，并把 titles 设为以下标题：_",
+                max_bytes, shown, omitted
+            ),
+            (Self::DocsMoreOmitted { count }, Locale::En) => format!("{} more", count),
+            (Self::DocsMoreOmitted { count }, Locale::Zh) => format!("还有 {} 个", count),
+            (Self::DocsDuplicatesRemoved { count }, Locale::En) => format!(
+                "_{} near-duplicate snippet{} removed._",
+                count,
+                if *count == 1 { "" } else { "s" }
+            ),
+            (Self::DocsDuplicatesRemoved { count }, Locale::Zh) => format!("_已去掉 {} 个重复的片段。_", count),
+            (Self::DocsSource { library }, Locale::En) => format!("Source: Docs - {}", library),
+            (Self::DocsSource { library }, Locale::Zh) => format!("来源：Docs - {}", library),
+            (Self::DocsAutoFallback { min_snippets }, Locale::En) => format!(
+                "> Note: fewer than {} code snippets were found, so info pages are shown instead.",
+                min_snippets
+            ),
+            (Self::DocsAutoFallback { min_snippets }, Locale::Zh) => {
+                format!("> 注意：代码片段少于 {} 个，改为显示说明文档。", min_snippets)
+            }
+            (Self::DocsMoved { from, to }, Locale::En) => {
+                format!("> Note: library id `{}` has moved to `{}` — update your configuration.", from, to)
+            }
+            (Self::DocsMoved { from, to }, Locale::Zh) => format!("> 注意：库标识符 `{}` 已迁移到 `{}`，请更新配置。", from, to),
+            (Self::DocsNoDocumentation, Locale::En) => "No documentation found. Try adjusting query parameters.".to_string(),
+            (Self::DocsNoDocumentation, Locale::Zh) => "未找到文档，请尝试调整查询参数。".to_string(),
+            (Self::DocsNotFound { library }, Locale::En) => format!("**Library \"{}\" not found**", library),
+            (Self::DocsNotFound { library }, Locale::Zh) => format!("**未找到库 \"{}\"**", library),
+            (Self::DocsCheckIdentifier, Locale::En) => {
+                "Please check the library identifier. Format: `owner/repo`, e.g.:".to_string()
+            }
+            (Self::DocsCheckIdentifier, Locale::Zh) => "请检查库标识符，格式为 `owner/repo`，例如：".to_string(),
+            (Self::DocsSearchTip { website }, Locale::En) => format!("Tip: Search for libraries at [Docs]({})", website),
+            (Self::DocsSearchTip { website }, Locale::Zh) => format!("提示：可以在 [Docs]({}) 搜索库", website),
+            (Self::DocsSuggestions, Locale::En) => {
+                "**Suggestions**: Related libraries found, use full identifier to query:".to_string()
+            }
+            (Self::DocsSuggestions, Locale::Zh) => "**建议**：找到了相关的库，请用完整标识符查询：".to_string(),
+            (Self::DocsUseFullIdentifier, Locale::En) => "Use full library identifier, e.g.:".to_string(),
+            (Self::DocsUseFullIdentifier, Locale::Zh) => "请使用完整的库标识符，例如：".to_string(),
+            (Self::DocsQueryFailed { error }, Locale::En) => format!("Docs query failed: {}", error),
+            (Self::DocsQueryFailed { error }, Locale::Zh) => format!("文档查询失败：{}", error),
+            (Self::DocsMergedTitle { query }, Locale::En) => format!("# Docs: {}", query),
+            (Self::DocsMergedTitle { query }, Locale::Zh) => format!("# 文档：{}", query),
+            (Self::DocsWarnings, Locale::En) => "## Warnings".to_string(),
+            (Self::DocsWarnings, Locale::Zh) => "## 警告".to_string(),
+            (Self::DocsProjectUnavailable { error }, Locale::En) => format!("Project docs unavailable: {}", error),
+            (Self::DocsProjectUnavailable { error }, Locale::Zh) => format!("项目文档不可用：{}", error),
+            (Self::DocsLibraryUnavailable { error }, Locale::En) => format!("Library docs unavailable: {}", error),
+            (Self::DocsLibraryUnavailable { error }, Locale::Zh) => format!("库文档不可用：{}", error),
+            (Self::DocsProjectSection, Locale::En) => "## Project docs".to_string(),
+            (Self::DocsProjectSection, Locale::Zh) => "## 项目文档".to_string(),
+            (Self::DocsNoProjectMatches, Locale::En) => "No project sections matched.".to_string(),
+            (Self::DocsNoProjectMatches, Locale::Zh) => "没有匹配的项目文档章节。".to_string(),
+            (Self::DocsLibrarySection { library: Some(library) }, Locale::En) => format!("## Library docs: {}", library),
+            (Self::DocsLibrarySection { library: Some(library) }, Locale::Zh) => format!("## 库文档：{}", library),
+            (Self::DocsLibrarySection { library: None }, Locale::En) => "## Library docs".to_string(),
+            (Self::DocsLibrarySection { library: None }, Locale::Zh) => "## 库文档".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_tag() {
+        assert_eq!(Locale::from_tag("zh-CN"), Some(Locale::Zh));
+        assert_eq!(Locale::from_tag("zh_Hans"), Some(Locale::Zh));
+        assert_eq!(Locale::from_tag("EN-us"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("fr-FR"), None);
+        assert_eq!(Locale::from_tag(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_scope_sets_current_locale() {
+        assert_eq!(current(), Locale::En);
+        let footer = Message::QuotaFooter { requests: 1, remaining: Some(5) };
+        let (outer, inner) = scope(Locale::Zh, async { (footer.text(), scope(Locale::En, async { footer.text() }).await) }).await;
+        assert_eq!(outer, "_本会话已请求 1 次，距离限流约剩 5 次。_");
+        assert_eq!(inner, "_This session: 1 request; ~5 remaining before rate limit._");
+    }
+}
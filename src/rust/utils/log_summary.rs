@@ -0,0 +1,232 @@
+// 日志用的 JSON 摘要
+// 工具参数/结果可能携带整篇文档，直接写入日志会产生数 MB 的单行日志。
+// 这里按单字段与总大小上限序列化：过长字符串替换为 `<string, N bytes, sha256:…>`，
+// 过深或过长的数组/对象替换为计数。
+
+use ring::digest::{digest, SHA256};
+use serde_json::Value;
+
+/// 摘要上限配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryLimits {
+    /// 单个字符串最大字节数，超过则替换为摘要
+    pub max_string_bytes: usize,
+    /// 数组保留的最大元素数，其余以计数代替
+    pub max_array_items: usize,
+    /// 最大嵌套深度，更深的数组/对象以计数代替
+    pub max_depth: usize,
+    /// 序列化结果总字节上限
+    pub max_total_bytes: usize,
+    /// 是否为被替换的字符串计算 sha256
+    pub hash: bool,
+}
+
+impl Default for SummaryLimits {
+    fn default() -> Self {
+        Self {
+            max_string_bytes: 1024,
+            max_array_items: 20,
+            max_depth: 6,
+            max_total_bytes: 16 * 1024,
+            hash: true,
+        }
+    }
+}
+
+impl SummaryLimits {
+    /// 从环境变量读取上限，未设置或无效时使用默认值
+    ///
+    /// DEVKIT_LOG_MAX_STRING_BYTES、DEVKIT_LOG_MAX_ARRAY_ITEMS、DEVKIT_LOG_MAX_DEPTH、
+    /// DEVKIT_LOG_MAX_TOTAL_BYTES、DEVKIT_LOG_HASH（0/false 关闭哈希）
+    pub fn from_env() -> Self {
+        Self::from_env_with(|name| std::env::var(name).ok())
+    }
+
+    fn from_env_with(env: impl Fn(&str) -> Option<String>) -> Self {
+        let defaults = Self::default();
+        let number = |name: &str, default: usize| {
+            env(name).and_then(|v| v.trim().parse().ok()).unwrap_or(default)
+        };
+        let hash = env("DEVKIT_LOG_HASH")
+            .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "no" | "off"))
+            .unwrap_or(defaults.hash);
+
+        Self {
+            max_string_bytes: number("DEVKIT_LOG_MAX_STRING_BYTES", defaults.max_string_bytes),
+            max_array_items: number("DEVKIT_LOG_MAX_ARRAY_ITEMS", defaults.max_array_items),
+            max_depth: number("DEVKIT_LOG_MAX_DEPTH", defaults.max_depth),
+            max_total_bytes: number("DEVKIT_LOG_MAX_TOTAL_BYTES", defaults.max_total_bytes),
+            hash,
+        }
+    }
+}
+
+/// 按上限生成适合写入日志的单行 JSON
+///
+/// 先按单字段上限摘要；结果仍超过总上限时逐步收紧字段上限，
+/// 实在放不下则整体替换为 `<json, N bytes, sha256:…>`。
+pub fn summarize_for_log(value: &Value, limits: &SummaryLimits) -> String {
+    let mut current = limits.clone();
+    loop {
+        let text = summarize_value(value, &current).to_string();
+        if text.len() <= limits.max_total_bytes {
+            return text;
+        }
+        // 先收紧字符串和数组长度，仍放不下再减少深度
+        if current.max_string_bytes > 16 || current.max_array_items > 1 {
+            current.max_string_bytes = (current.max_string_bytes / 2).max(16);
+            current.max_array_items = (current.max_array_items / 2).max(1);
+        } else if current.max_depth > 1 {
+            current.max_depth /= 2;
+        } else {
+            break;
+        }
+    }
+
+    let full = value.to_string();
+    Value::String(placeholder("json", full.as_bytes(), limits.hash)).to_string()
+}
+
+/// 按单字段上限摘要 JSON 值（不检查总大小）
+pub fn summarize_value(value: &Value, limits: &SummaryLimits) -> Value {
+    summarize_at(value, limits, 0)
+}
+
+fn summarize_at(value: &Value, limits: &SummaryLimits, depth: usize) -> Value {
+    match value {
+        Value::String(s) if s.len() > limits.max_string_bytes => {
+            Value::String(placeholder("string", s.as_bytes(), limits.hash))
+        }
+        Value::Array(items) if depth >= limits.max_depth && !items.is_empty() => {
+            Value::String(format!("<array, {}>", count(items.len(), "item")))
+        }
+        Value::Object(map) if depth >= limits.max_depth && !map.is_empty() => {
+            Value::String(format!("<object, {}>", count(map.len(), "key")))
+        }
+        Value::Array(items) => {
+            let mut summarized: Vec<Value> = items
+                .iter()
+                .take(limits.max_array_items)
+                .map(|item| summarize_at(item, limits, depth + 1))
+                .collect();
+            if items.len() > limits.max_array_items {
+                let rest = items.len() - limits.max_array_items;
+                summarized.push(Value::String(format!("<{} more {}>", rest, plural(rest, "item"))));
+            }
+            Value::Array(summarized)
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), summarize_at(item, limits, depth + 1)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}", n, plural(n, noun))
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        noun.to_string()
+    } else {
+        format!("{}s", noun)
+    }
+}
+
+fn placeholder(kind: &str, bytes: &[u8], hash: bool) -> String {
+    if hash {
+        let hex = hex::encode(digest(&SHA256, bytes).as_ref());
+        format!("<{}, {} bytes, sha256:{}…>", kind, bytes.len(), &hex[..12])
+    } else {
+        format!("<{}, {} bytes>", kind, bytes.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SMALL: &str = include_str!("fixtures/tool_args_small.json");
+    const NESTED: &str = include_str!("fixtures/tool_args_nested.json");
+
+    fn fixture(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    fn tight_limits() -> SummaryLimits {
+        SummaryLimits {
+            max_string_bytes: 32,
+            max_array_items: 5,
+            max_depth: 3,
+            max_total_bytes: 4096,
+            hash: true,
+        }
+    }
+
+    #[test]
+    fn test_small_payload_passes_through() {
+        let value = fixture(SMALL);
+        assert_eq!(summarize_value(&value, &SummaryLimits::default()), value);
+        assert_eq!(summarize_for_log(&value, &SummaryLimits::default()), value.to_string());
+    }
+
+    #[test]
+    fn test_nested_payload_shape() {
+        let value = fixture(NESTED);
+        let summarized = summarize_value(&value, &tight_limits());
+
+        let document = value["document"].as_str().unwrap();
+        let expected_hash = hex::encode(digest(&SHA256, document.as_bytes()).as_ref());
+        assert_eq!(
+            summarized["document"],
+            json!(format!("<string, {} bytes, sha256:{}…>", document.len(), &expected_hash[..12]))
+        );
+
+        assert_eq!(summarized["project_root_path"], value["project_root_path"]);
+        assert_eq!(summarized["blobs"], json!([1, 2, 3, 4, 5, "<7 more items>"]));
+        assert_eq!(summarized["meta"]["tags"], json!(["a", "b"]));
+        assert_eq!(summarized["meta"]["level1"]["level2"], json!("<object, 1 key>"));
+    }
+
+    #[test]
+    fn test_hash_can_be_disabled() {
+        let limits = SummaryLimits { hash: false, ..tight_limits() };
+        let summarized = summarize_value(&fixture(NESTED), &limits);
+        assert_eq!(summarized["document"], json!("<string, 123 bytes>"));
+    }
+
+    #[test]
+    fn test_total_cap_is_respected() {
+        let value = json!({
+            "chunks": (0..200).map(|i| format!("chunk {} {}", i, "x".repeat(100))).collect::<Vec<_>>(),
+        });
+        let limits = SummaryLimits { max_total_bytes: 512, ..SummaryLimits::default() };
+
+        let text = summarize_for_log(&value, &limits);
+        assert!(text.len() <= 512, "{} bytes", text.len());
+        assert!(text.contains("more items"));
+
+        let limits = SummaryLimits { max_total_bytes: 40, ..SummaryLimits::default() };
+        assert_eq!(summarize_for_log(&value, &limits), r#"{"chunks":"<array, 200 items>"}"#);
+
+        let limits = SummaryLimits { max_total_bytes: 20, hash: false, ..SummaryLimits::default() };
+        assert_eq!(summarize_for_log(&value, &limits), format!("\"<json, {} bytes>\"", value.to_string().len()));
+    }
+
+    #[test]
+    fn test_limits_from_env() {
+        let limits = SummaryLimits::from_env_with(|name| match name {
+            "DEVKIT_LOG_MAX_STRING_BYTES" => Some("64".to_string()),
+            "DEVKIT_LOG_HASH" => Some("off".to_string()),
+            "DEVKIT_LOG_MAX_DEPTH" => Some("bogus".to_string()),
+            _ => None,
+        });
+        assert_eq!(limits.max_string_bytes, 64);
+        assert!(!limits.hash);
+        assert_eq!(limits.max_depth, SummaryLimits::default().max_depth);
+    }
+}
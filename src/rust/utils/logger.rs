@@ -1,14 +1,17 @@
 use std::env;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
-use std::path::PathBuf;
-use std::sync::Once;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
 use std::time::{SystemTime, UNIX_EPOCH};
 use log::LevelFilter;
 use env_logger::{Builder, Target};
 
 static INIT: Once = Once::new();
 
+/// 初始化时确定的日志级别；输出被禁用或尚未初始化时为 None
+static BASE_LEVEL: Mutex<Option<LevelFilter>> = Mutex::new(None);
+
 /// 日志轮转配置
 #[derive(Debug, Clone)]
 pub struct LogRotationConfig {
@@ -30,6 +33,28 @@ impl Default for LogRotationConfig {
     }
 }
 
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// 纯文本：`时间 [级别] [模块] 消息`
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象，便于容器日志采集
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("未知日志格式: {} (可选 text, json)", other)),
+        }
+    }
+}
+
 /// 日志配置
 #[derive(Debug, Clone)]
 pub struct LogConfig {
@@ -41,6 +66,10 @@ pub struct LogConfig {
     pub is_mcp_mode: bool,
     /// 日志轮转配置
     pub rotation: LogRotationConfig,
+    /// 输出格式
+    pub format: LogFormat,
+    /// 只输出到 stdout（容器模式），忽略文件路径
+    pub stdout: bool,
 }
 
 impl Default for LogConfig {
@@ -50,20 +79,26 @@ impl Default for LogConfig {
             file_path: None,
             is_mcp_mode: false,
             rotation: LogRotationConfig::default(),
+            format: LogFormat::Text,
+            stdout: false,
         }
     }
 }
 
 /// Get GUI mode log file path
 /// Uses dirs::config_dir() for cross-platform compatibility
+/// None when the startup preflight found the config directory unwritable (logs go to stderr)
 fn get_gui_log_path() -> Option<PathBuf> {
+    if super::paths::preflight().log_to_stderr() {
+        return None;
+    }
     dirs::config_dir().map(|config_dir| {
         config_dir.join("devkit").join("log").join("acemcp.log")
     })
 }
 
 /// 确保日志目录存在
-fn ensure_log_directory(log_path: &PathBuf) -> std::io::Result<()> {
+fn ensure_log_directory(log_path: &Path) -> std::io::Result<()> {
     if let Some(parent) = log_path.parent() {
         if !parent.exists() {
             fs::create_dir_all(parent)?;
@@ -119,7 +154,7 @@ fn perform_log_rotation(log_path: &PathBuf, max_backup_count: u32) {
 }
 
 /// 清理过期的日志备份文件
-fn cleanup_old_logs(log_path: &PathBuf, rotation_config: &LogRotationConfig) {
+fn cleanup_old_logs(log_path: &Path, rotation_config: &LogRotationConfig) {
     let log_dir = match log_path.parent() {
         Some(dir) => dir,
         None => return,
@@ -161,18 +196,37 @@ pub fn init_logger(config: LogConfig) -> Result<(), Box<dyn std::error::Error>>
     INIT.call_once(|| {
         let mut builder = Builder::new();
         
-        // 设置日志级别
-        builder.filter_level(config.level);
+        // 按级别过滤交给 log::set_max_level，运行中可以调整
+        builder.filter_level(LevelFilter::Trace);
+        let mut output_enabled = true;
         
         // 设置日志格式
-        builder.format(|buf, record| {
-            let log_line = format!(
-                "{} [{}] [{}] {}",
-                chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
-                record.level(),
-                record.module_path().unwrap_or("unknown"),
-                record.args()
-            );
+        let format = config.format;
+        builder.format(move |buf, record| {
+            // 请求或工具调用中的日志带上关联 ID
+            let request_id = super::correlation::current();
+            let log_line = match format {
+                LogFormat::Text => format!(
+                    "{} [{}] [{}] {}{}",
+                    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                    record.level(),
+                    record.module_path().unwrap_or("unknown"),
+                    request_id.map(|id| format!("[req={}] ", id)).unwrap_or_default(),
+                    record.args()
+                ),
+                LogFormat::Json => {
+                    let mut line = serde_json::json!({
+                        "ts": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                        "level": record.level().as_str(),
+                        "target": record.module_path().unwrap_or("unknown"),
+                        "message": record.args().to_string(),
+                    });
+                    if let Some(id) = request_id {
+                        line["request_id"] = serde_json::Value::String(id);
+                    }
+                    line.to_string()
+                }
+            };
             
             // 写入到原始目标（stderr 或文件）
             writeln!(buf, "{}", log_line)?;
@@ -181,7 +235,10 @@ pub fn init_logger(config: LogConfig) -> Result<(), Box<dyn std::error::Error>>
         });
         
         // 根据模式设置输出目标
-        if config.is_mcp_mode {
+        if config.stdout {
+            // 容器模式：只输出到 stdout，由容器运行时收集
+            builder.target(Target::Stdout);
+        } else if config.is_mcp_mode {
             // MCP 模式：只输出到文件，不输出到 stderr
             if let Some(file_path) = &config.file_path {
                 let log_path = PathBuf::from(file_path);
@@ -200,11 +257,11 @@ pub fn init_logger(config: LogConfig) -> Result<(), Box<dyn std::error::Error>>
                     builder.target(Target::Pipe(Box::new(log_file)));
                 } else {
                     // 如果文件打开失败，禁用日志输出
-                    builder.filter_level(LevelFilter::Off);
+                    output_enabled = false;
                 }
             } else {
                 // MCP 模式下没有指定文件路径，禁用日志输出
-                builder.filter_level(LevelFilter::Off);
+                output_enabled = false;
             }
         } else {
             // 非 MCP 模式：同时输出到文件和 stderr
@@ -251,6 +308,19 @@ pub fn init_logger(config: LogConfig) -> Result<(), Box<dyn std::error::Error>>
         }
         
         builder.init();
+        if output_enabled {
+            log::set_max_level(config.level);
+            if let Ok(mut base) = BASE_LEVEL.lock() {
+                *base = Some(config.level);
+            }
+        } else {
+            log::set_max_level(LevelFilter::Off);
+        }
+
+        // 目录降级只汇总提示一次
+        if let Some(warning) = super::paths::preflight().warning() {
+            log::warn!("{}", warning);
+        }
     });
     
     Ok(())
@@ -267,6 +337,9 @@ pub fn auto_init_logger() -> Result<(), Box<dyn std::error::Error>> {
         .ok()
         .or_else(|| get_gui_log_path().map(|p| p.to_string_lossy().to_string()));
     
+    // 配置目录不可写时没有日志文件，MCP 模式也改为输出到 stderr
+    let is_mcp_mode = is_mcp_mode && log_file_path.is_some();
+
     let config = if is_mcp_mode {
         // MCP 模式：只输出到文件，不输出到 stderr
         LogConfig {
@@ -277,6 +350,8 @@ pub fn auto_init_logger() -> Result<(), Box<dyn std::error::Error>> {
             file_path: log_file_path,
             is_mcp_mode: true,
             rotation: LogRotationConfig::default(),
+            format: LogFormat::Text,
+            stdout: false,
         }
     } else {
         // GUI 模式：同时输出到文件和 stderr
@@ -288,12 +363,60 @@ pub fn auto_init_logger() -> Result<(), Box<dyn std::error::Error>> {
             file_path: log_file_path,
             is_mcp_mode: false,
             rotation: LogRotationConfig::default(),
+            format: LogFormat::Text,
+            stdout: false,
         }
     };
 
     init_logger(config)
 }
 
+/// HTTP 服务端写入的日志文件：MCP_LOG_FILE，否则与 GUI 相同的日志文件
+pub fn server_log_path() -> Option<PathBuf> {
+    env::var("MCP_LOG_FILE").ok().map(PathBuf::from).or_else(get_gui_log_path)
+}
+
+/// HTTP 服务端日志初始化
+/// stdout 为 true 时（容器模式）只输出到 stdout，否则与 auto_init_logger 相同输出到文件和 stderr
+pub fn init_server_logger(format: LogFormat, stdout: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let file_path = if stdout {
+        None
+    } else {
+        server_log_path().map(|p| p.to_string_lossy().to_string())
+    };
+
+    init_logger(LogConfig {
+        level: env::var("RUST_LOG")
+            .unwrap_or_else(|_| "info".to_string())
+            .parse::<LevelFilter>()
+            .unwrap_or(LevelFilter::Info),
+        file_path,
+        is_mcp_mode: false,
+        rotation: LogRotationConfig::default(),
+        format,
+        stdout,
+    })
+}
+
+/// 运行中调整日志级别，None 恢复初始化时的级别；日志输出被禁用时不生效
+///
+/// 返回调整后的级别。
+pub fn set_log_level(level: Option<LevelFilter>) -> LevelFilter {
+    let Some(base) = BASE_LEVEL.lock().ok().and_then(|base| *base) else {
+        return log::max_level();
+    };
+    let level = level.unwrap_or(base);
+    log::set_max_level(level);
+    level
+}
+
+/// 刷新日志输出，进程退出前调用，避免最后几条日志丢失
+pub fn flush_logs() {
+    log::logger().flush();
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+}
+
 /// MCP 专用：强制使用 MCP 模式初始化日志系统
 /// 主要用于 MCP 服务器进程，避免日志输出到 stderr 干扰 MCP 通讯。
 pub fn init_mcp_logger() -> Result<(), Box<dyn std::error::Error>> {
@@ -315,10 +438,14 @@ pub fn init_mcp_logger() -> Result<(), Box<dyn std::error::Error>> {
             candidate_paths.push(PathBuf::from(p));
         }
     }
-    if let Some(p) = get_gui_log_path() {
-        candidate_paths.push(p);
+    // 配置目录不可写时输出到 stderr（不会干扰 stdout 上的 MCP 通讯），不再退到临时目录
+    let to_stderr = super::paths::preflight().log_to_stderr();
+    if !to_stderr {
+        if let Some(p) = get_gui_log_path() {
+            candidate_paths.push(p);
+        }
+        candidate_paths.push(std::env::temp_dir().join("devkit_mcp.log"));
     }
-    candidate_paths.push(std::env::temp_dir().join("devkit_mcp.log"));
 
     let log_file_path = candidate_paths
         .iter()
@@ -329,9 +456,11 @@ pub fn init_mcp_logger() -> Result<(), Box<dyn std::error::Error>> {
             .unwrap_or_else(|_| "warn".to_string())
             .parse::<LevelFilter>()
             .unwrap_or(LevelFilter::Warn),
+        is_mcp_mode: !(to_stderr && log_file_path.is_none()),
         file_path: log_file_path,
-        is_mcp_mode: true,
         rotation: LogRotationConfig::default(),
+        format: LogFormat::Text,
+        stdout: false,
     };
 
     init_logger(config)
@@ -1,3 +1,13 @@
+pub mod correlation;
+pub mod locale;
 pub mod logger;
+pub mod log_summary;
+pub mod paths;
+pub mod process;
+pub mod text;
+#[cfg(test)]
+pub mod test_dir;
 
-pub use logger::{LogConfig, init_logger, auto_init_logger, init_mcp_logger};
+pub use logger::{LogConfig, LogFormat, init_logger, auto_init_logger, init_mcp_logger, init_server_logger, server_log_path, set_log_level, flush_logs};
+pub use log_summary::{SummaryLimits, summarize_for_log, summarize_value};
+pub use process::is_process_running;
@@ -0,0 +1,357 @@
+// 应用目录定位模块
+// 统一管理配置目录、运行时状态目录等路径，避免各模块各自拼接
+//
+// 启动预检（preflight）检查配置、状态、缓存目录能否写入并统一决定降级方式：
+// 配置目录不可写时记忆库只保存在内存中、日志输出到 stderr，并只给出一条汇总警告
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// 应用目录名
+const APP_DIR_NAME: &str = "devkit";
+
+/// 状态目录覆盖环境变量（容器或测试环境下使用）
+pub const STATE_DIR_ENV: &str = "DEVKIT_STATE_DIR";
+
+/// 获取配置目录（~/.config/devkit 等平台对应位置）
+pub fn config_dir() -> Result<PathBuf> {
+    let dir = dirs::config_dir()
+        .ok_or_else(|| anyhow::anyhow!("无法获取配置目录"))?
+        .join(APP_DIR_NAME);
+    Ok(dir)
+}
+
+/// 获取运行时状态目录
+///
+/// 优先级：DEVKIT_STATE_DIR -> 平台状态目录 -> 本地数据目录 -> 配置目录
+pub fn state_dir() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var(STATE_DIR_ENV) {
+        if !dir.trim().is_empty() {
+            return Ok(PathBuf::from(dir));
+        }
+    }
+
+    let root = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .or_else(dirs::config_dir)
+        .ok_or_else(|| anyhow::anyhow!("无法获取状态目录"))?;
+    Ok(root.join(APP_DIR_NAME))
+}
+
+/// 缓存目录覆盖环境变量
+pub const CACHE_DIR_ENV: &str = "DEVKIT_CACHE_DIR";
+
+/// 获取缓存目录
+///
+/// 优先级：DEVKIT_CACHE_DIR -> 平台缓存目录 -> 状态目录下的 cache
+pub fn cache_dir() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var(CACHE_DIR_ENV) {
+        if !dir.trim().is_empty() {
+            return Ok(PathBuf::from(dir));
+        }
+    }
+
+    match dirs::cache_dir() {
+        Some(root) => Ok(root.join(APP_DIR_NAME)),
+        None => Ok(state_dir()?.join("cache")),
+    }
+}
+
+/// 获取缓存目录下的子目录，不存在时自动创建
+pub fn cache_subdir(name: &str) -> Result<PathBuf> {
+    let dir = cache_dir()?.join(name);
+    fs::create_dir_all(&dir)
+        .map_err(|e| anyhow::anyhow!("无法创建缓存目录 {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// 获取状态目录下的子目录，不存在时自动创建
+pub fn state_subdir(name: &str) -> Result<PathBuf> {
+    let dir = state_dir()?.join(name);
+    fs::create_dir_all(&dir)
+        .map_err(|e| anyhow::anyhow!("无法创建状态目录 {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// 进程启动时的目录预检结果，首次访问时执行
+static PREFLIGHT: Lazy<Preflight> = Lazy::new(Preflight::run_default);
+
+/// 需要写入的应用目录
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppDir {
+    Config,
+    State,
+    Cache,
+}
+
+impl AppDir {
+    pub const ALL: [AppDir; 3] = [AppDir::Config, AppDir::State, AppDir::Cache];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AppDir::Config => "config",
+            AppDir::State => "state",
+            AppDir::Cache => "cache",
+        }
+    }
+
+    /// 目录不可写时受影响的功能
+    fn degraded_features(self) -> &'static str {
+        match self {
+            AppDir::Config => "memories are kept in memory for this session, logs go to stderr and settings cannot be saved",
+            AppDir::State => "session resumption and instance discovery are disabled",
+            AppDir::Cache => "the docs cache is kept in memory",
+        }
+    }
+
+    fn path(self) -> Result<PathBuf> {
+        match self {
+            AppDir::Config => config_dir(),
+            AppDir::State => state_dir(),
+            AppDir::Cache => cache_dir(),
+        }
+    }
+}
+
+/// 单个目录的检查结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirStatus {
+    /// 已存在且可写
+    Ok,
+    /// 不存在，已创建
+    Created,
+    /// 目录（或不存在时最近的上级目录）没有写权限位，或位于只读文件系统
+    ReadOnly,
+    /// 无法创建或写入
+    PermissionDenied,
+}
+
+impl DirStatus {
+    pub fn is_writable(self) -> bool {
+        matches!(self, DirStatus::Ok | DirStatus::Created)
+    }
+}
+
+impl std::fmt::Display for DirStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DirStatus::Ok => "ok",
+            DirStatus::Created => "created",
+            DirStatus::ReadOnly => "read-only",
+            DirStatus::PermissionDenied => "permission denied",
+        })
+    }
+}
+
+/// 预检中一个目录的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DirCheck {
+    pub dir: AppDir,
+    /// 无法确定目录位置时为空（例如没有 HOME）
+    pub path: Option<PathBuf>,
+    pub status: DirStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 全部应用目录的检查结果，降级决策都从这里读取
+#[derive(Debug, Clone, Serialize)]
+pub struct Preflight {
+    pub directories: Vec<DirCheck>,
+}
+
+impl Preflight {
+    /// 检查平台对应的应用目录
+    fn run_default() -> Self {
+        Self::run(AppDir::ALL.iter().map(|dir| (*dir, dir.path())).collect())
+    }
+
+    /// 检查给定目录，不存在时尝试创建
+    pub fn run(dirs: Vec<(AppDir, Result<PathBuf>)>) -> Self {
+        let directories = dirs
+            .into_iter()
+            .map(|(dir, path)| match path {
+                Ok(path) => {
+                    let (status, error) = check_dir(&path);
+                    DirCheck { dir, path: Some(path), status, error }
+                }
+                Err(e) => DirCheck { dir, path: None, status: DirStatus::PermissionDenied, error: Some(e.to_string()) },
+            })
+            .collect();
+        Self { directories }
+    }
+
+    /// 目录的检查结果，未检查的目录视为可写
+    pub fn status(&self, dir: AppDir) -> DirStatus {
+        self.directories.iter().find(|check| check.dir == dir).map_or(DirStatus::Ok, |check| check.status)
+    }
+
+    pub fn is_writable(&self, dir: AppDir) -> bool {
+        self.status(dir).is_writable()
+    }
+
+    /// 记忆库只保存在内存中
+    pub fn memory_in_memory(&self) -> bool {
+        !self.is_writable(AppDir::Config)
+    }
+
+    /// 日志只输出到 stderr（显式设置的 MCP_LOG_FILE 仍然优先）
+    pub fn log_to_stderr(&self) -> bool {
+        !self.is_writable(AppDir::Config)
+    }
+
+    /// 会话恢复文件与实例发现文件可以写入
+    pub fn persist_state(&self) -> bool {
+        self.is_writable(AppDir::State)
+    }
+
+    /// docs 缓存可以写入磁盘
+    pub fn disk_cache(&self) -> bool {
+        self.is_writable(AppDir::Cache)
+    }
+
+    /// 汇总的降级说明，全部目录可写时为 None
+    pub fn warning(&self) -> Option<String> {
+        let degraded: Vec<String> = self
+            .directories
+            .iter()
+            .filter(|check| !check.status.is_writable())
+            .map(|check| {
+                let path = check.path.as_ref().map_or_else(|| "(unknown)".to_string(), |p| p.display().to_string());
+                let reason = check.error.as_ref().map_or_else(String::new, |e| format!(": {}", e));
+                format!("{} directory {}: {}{} ({})", check.dir.name(), path, check.status, reason, check.dir.degraded_features())
+            })
+            .collect();
+        (!degraded.is_empty()).then(|| format!("running degraded: {}", degraded.join("; ")))
+    }
+}
+
+/// 进程的目录预检结果（首次调用时执行检查）
+pub fn preflight() -> &'static Preflight {
+    &PREFLIGHT
+}
+
+/// 检查目录能否写入，不存在时尝试创建
+///
+/// 没有写权限位的目录直接判为只读，不依赖写入探测（root 会绕过权限位）
+pub fn check_dir(path: &Path) -> (DirStatus, Option<String>) {
+    if path.is_dir() {
+        if is_read_only(path) {
+            return (DirStatus::ReadOnly, None);
+        }
+        let probe = path.join(format!(".devkit-write-probe-{}", std::process::id()));
+        return match fs::write(&probe, b"") {
+            Ok(()) => {
+                let _ = fs::remove_file(&probe);
+                (DirStatus::Ok, None)
+            }
+            Err(e) => (classify_error(&e), Some(e.to_string())),
+        };
+    }
+
+    // 不存在时看最近的已存在上级目录，只读则不尝试创建
+    if let Some(ancestor) = path.ancestors().skip(1).find(|p| p.is_dir()) {
+        if is_read_only(ancestor) {
+            return (DirStatus::ReadOnly, Some(format!("{} is read-only", ancestor.display())));
+        }
+    }
+    match fs::create_dir_all(path) {
+        Ok(()) => (DirStatus::Created, None),
+        Err(e) => (classify_error(&e), Some(e.to_string())),
+    }
+}
+
+fn is_read_only(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|metadata| metadata.permissions().readonly())
+}
+
+fn classify_error(error: &std::io::Error) -> DirStatus {
+    match error.kind() {
+        ErrorKind::ReadOnlyFilesystem => DirStatus::ReadOnly,
+        _ => DirStatus::PermissionDenied,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn temp_root(name: &str) -> TestDir {
+        TestDir::new(&format!("paths-{}", name))
+    }
+
+    #[cfg(unix)]
+    fn set_mode(path: &Path, mode: u32) {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn test_missing_dir_is_created_then_ok() {
+        let root = temp_root("missing");
+        let config = root.join("config/devkit");
+
+        let preflight = Preflight::run(vec![(AppDir::Config, Ok(config.clone()))]);
+        assert_eq!(preflight.status(AppDir::Config), DirStatus::Created);
+        assert!(config.is_dir());
+        assert!(preflight.warning().is_none());
+        assert!(!preflight.memory_in_memory());
+
+        assert_eq!(check_dir(&config), (DirStatus::Ok, None));
+        assert_eq!(fs::read_dir(&config).unwrap().count(), 0, "write probe left behind");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_only_config_dir_degrades_with_one_warning() {
+        let root = temp_root("read-only");
+        let config = root.join("config");
+        fs::create_dir_all(&config).unwrap();
+        set_mode(&config, 0o555);
+        let locked = root.join("locked");
+        fs::create_dir_all(&locked).unwrap();
+        set_mode(&locked, 0o555);
+
+        let preflight = Preflight::run(vec![
+            (AppDir::Config, Ok(config.clone())),
+            (AppDir::State, Ok(locked.join("devkit"))),
+            (AppDir::Cache, Ok(root.join("cache"))),
+        ]);
+        assert_eq!(preflight.status(AppDir::Config), DirStatus::ReadOnly);
+        assert_eq!(preflight.status(AppDir::State), DirStatus::ReadOnly);
+        assert_eq!(preflight.status(AppDir::Cache), DirStatus::Created);
+        assert!(!locked.join("devkit").exists());
+
+        assert!(preflight.memory_in_memory());
+        assert!(preflight.log_to_stderr());
+        assert!(!preflight.persist_state());
+        assert!(preflight.disk_cache());
+
+        let warning = preflight.warning().unwrap();
+        assert!(warning.starts_with("running degraded: config directory"), "{}", warning);
+        assert!(warning.contains("memories are kept in memory"), "{}", warning);
+        assert!(warning.contains("state directory"), "{}", warning);
+        assert!(!warning.contains("cache directory"), "{}", warning);
+
+        set_mode(&config, 0o755);
+        set_mode(&locked, 0o755);
+    }
+
+    #[test]
+    fn test_unknown_location_is_reported() {
+        let preflight = Preflight::run(vec![(AppDir::Config, Err(anyhow::anyhow!("无法获取配置目录")))]);
+        assert_eq!(preflight.status(AppDir::Config), DirStatus::PermissionDenied);
+        assert!(preflight.warning().unwrap().contains("config directory (unknown): permission denied: 无法获取配置目录"));
+
+        let json = serde_json::to_value(&preflight).unwrap();
+        assert_eq!(json["directories"][0]["dir"], "config");
+        assert_eq!(json["directories"][0]["status"], "permission_denied");
+    }
+}
@@ -0,0 +1,43 @@
+// 进程相关工具函数
+
+/// 检查指定 PID 的进程是否仍在运行
+pub fn is_process_running(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        std::path::Path::new(&format!("/proc/{}", pid)).exists()
+    }
+
+    #[cfg(windows)]
+    {
+        let filter = format!("PID eq {}", pid);
+        std::process::Command::new("tasklist")
+            .args(["/FI", &filter, "/FO", "CSV", "/NH"])
+            .output()
+            .ok()
+            .and_then(|o| {
+                if !o.status.success() {
+                    return None;
+                }
+                let out = String::from_utf8_lossy(&o.stdout);
+                let lower = out.to_ascii_lowercase();
+                let looks_like_no_tasks = lower.contains("no tasks")
+                    || lower.contains("info")
+                    || out.contains("信息")
+                    || out.contains("没有")
+                    || out.contains("无")
+                    || out.contains("未")
+                    || out.contains("找不到");
+                Some(!looks_like_no_tasks && out.contains(&pid.to_string()))
+            })
+            .unwrap_or(false)
+    }
+
+    #[cfg(all(not(target_os = "linux"), not(windows)))]
+    {
+        std::process::Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    }
+}
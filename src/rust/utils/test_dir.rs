@@ -0,0 +1,75 @@
+// 测试用临时目录
+// 每个测试在系统临时目录下得到一个独立的新目录，守卫丢弃时删除它：
+// 测试断言失败（panic）时同样会清理，不会在临时目录里留下文件。
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// 丢弃时连同内容一起删除的临时目录
+#[derive(Debug)]
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// 新建并创建 `devkit-<name>-<uuid>` 目录，路径已解析符号链接（macOS 的 /tmp 即为链接）
+    pub fn new(name: &str) -> Self {
+        let mut dir = Self::reserve(name);
+        std::fs::create_dir_all(&dir.path).expect("failed to create test directory");
+        dir.path = dir.path.canonicalize().expect("failed to resolve test directory");
+        dir
+    }
+
+    /// 只分配路径、不创建目录，用于测试「目录不存在」的情况；存在时同样在丢弃时删除
+    pub fn reserve(name: &str) -> Self {
+        Self { path: std::env::temp_dir().join(format!("devkit-{}-{}", name, uuid::Uuid::new_v4())) }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removed_on_drop_and_on_panic() {
+        let dir = TestDir::new("test-dir");
+        std::fs::write(dir.join("file.txt"), "content").unwrap();
+        let path = dir.to_path_buf();
+        drop(dir);
+        assert!(!path.exists());
+
+        let path = std::panic::catch_unwind(|| {
+            let dir = TestDir::new("test-dir-panic");
+            std::fs::write(dir.join("file.txt"), "content").unwrap();
+            std::panic::panic_any(dir.to_path_buf());
+        })
+        .unwrap_err()
+        .downcast::<PathBuf>()
+        .unwrap();
+        assert!(!path.exists());
+    }
+}
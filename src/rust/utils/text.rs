@@ -0,0 +1,79 @@
+// 面向用户的文本截断
+// 按字素簇（grapheme cluster）截断：不会切开多字节字符，也不会把组合符号、
+// emoji 序列或国旗拆成两半。工具输出、预览和错误信息里的截断都用这里的函数，
+// 不要直接写 `&text[..n]`，遇到非 ASCII 内容会 panic。
+
+use std::borrow::Cow;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// 省略号
+pub const ELLIPSIS: &str = "…";
+
+/// 最多 `max` 个字素的最长前缀
+pub fn prefix(text: &str, max: usize) -> &str {
+    match text.grapheme_indices(true).nth(max) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// 不超过 `max_bytes` 字节、结束在字素边界上的最长前缀
+pub fn prefix_bytes(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let end = text
+        .grapheme_indices(true)
+        .map(|(start, grapheme)| start + grapheme.len())
+        .take_while(|&end| end <= max_bytes)
+        .last()
+        .unwrap_or(0);
+    &text[..end]
+}
+
+/// 超过 `max` 个字素时截断并追加 [`ELLIPSIS`]
+pub fn truncate(text: &str, max: usize) -> Cow<'_, str> {
+    truncate_with(text, max, ELLIPSIS)
+}
+
+/// 超过 `max` 个字素时截断并追加 `marker`
+pub fn truncate_with<'a>(text: &'a str, max: usize, marker: &str) -> Cow<'a, str> {
+    let cut = prefix(text, max);
+    if cut.len() == text.len() {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(format!("{}{}", cut, marker))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_counts_graphemes() {
+        assert_eq!(prefix("hello", 3), "hel");
+        assert_eq!(prefix("hi", 3), "hi");
+        assert_eq!(prefix("高性能文档", 2), "高性");
+        // e + 组合重音、国旗与家庭 emoji 各算一个字素
+        assert_eq!(prefix("e\u{301}x", 1), "e\u{301}");
+        assert_eq!(prefix("🇨🇳🇯🇵", 1), "🇨🇳");
+        assert_eq!(prefix("👨‍👩‍👧 family", 1), "👨‍👩‍👧");
+    }
+
+    #[test]
+    fn test_prefix_bytes_ends_on_a_grapheme_boundary() {
+        assert_eq!(prefix_bytes("abc", 10), "abc");
+        assert_eq!(prefix_bytes("文档", 4), "文");
+        assert_eq!(prefix_bytes("文档", 2), "");
+        assert_eq!(prefix_bytes("ae\u{301}", 2), "a");
+    }
+
+    #[test]
+    fn test_truncate_marks_only_cut_text() {
+        assert_eq!(truncate("short", 10), "short");
+        assert!(matches!(truncate("short", 5), Cow::Borrowed(_)));
+        assert_eq!(truncate("路由 🚀 Routing", 4), "路由 🚀…");
+        assert_eq!(truncate_with("Ünïcödé", 3, "..."), "Ünï...");
+    }
+}
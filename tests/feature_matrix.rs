@@ -0,0 +1,40 @@
+//! Compile check for every supported feature combination
+//!
+//! Slow (one `cargo check` per combination), so it is ignored by default:
+//!
+//!     cargo test --test feature_matrix -- --ignored
+
+use std::process::Command;
+
+/// Feature sets built with `--no-default-features`
+const COMBINATIONS: &[&str] = &[
+    "client",
+    "server",
+    "server,docs",
+    "http-server",
+    "http-server,docs",
+    "server,otel",
+    "http-server,otel",
+    "gui",
+    "gui,http-server",
+];
+
+#[test]
+#[ignore = "runs cargo check for each feature combination"]
+fn test_feature_combinations_build() {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let mut failed = Vec::new();
+
+    for features in COMBINATIONS {
+        let status = Command::new(&cargo)
+            .args(["check", "--lib", "--bins", "--examples", "--no-default-features", "--features", features])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .expect("failed to run cargo");
+        if !status.success() {
+            failed.push(*features);
+        }
+    }
+
+    assert!(failed.is_empty(), "feature combinations failed to build: {:?}", failed);
+}